@@ -46,8 +46,8 @@ export function parseLearningId(s: string): LearningId {
 /** Priority levels: p0=highest, p1=default, p2=lowest */
 export type Priority = 0 | 1 | 2;
 
-/** Task depth (0=milestone, 1=task, 2=subtask) */
-export type Depth = 0 | 1 | 2;
+/** Task depth: 0 for roots (milestones), +1 per level; limit set per database */
+export type Depth = number;
 
 /** Task context chain (inherited from hierarchy) */
 export interface TaskContext {
@@ -67,6 +67,31 @@ export interface InheritedLearnings {
 }
 
 /** Learning attached to a task */
+export type LinkKind = "file" | "fileRange" | "url" | "commit" | "issue";
+
+/** Typed link from a task to a file, code range, URL, commit or issue */
+export interface TaskLink {
+  id: string;
+  taskId: TaskId;
+  kind: LinkKind;
+  /** Path relative to the repository root, URL, full commit id or issue reference */
+  target: string;
+  /** Inclusive line range, only for fileRange links */
+  lineStart: number | null;
+  lineEnd: number | null;
+  note: string | null;
+  createdAt: string;
+}
+
+/** Comment on a task's discussion thread */
+export interface Comment {
+  id: string;
+  taskId: TaskId;
+  author: string | null;
+  body: string;
+  createdAt: string;
+}
+
 export interface Learning {
   id: LearningId;
   taskId: TaskId;
@@ -96,6 +121,16 @@ export interface Task {
   blocks?: TaskId[];
   bookmark?: string;
   startCommit?: string;
+  /** Branch checked out when the task started; complete integrates into it */
+  startBranch?: string;
+  /** Start branch tip after complete integrated the task branch */
+  integrationCommit?: string;
+  /** Parent branch tip the task branch was created from (stacked) */
+  baseRev?: string;
+  /** Task branch tip at completion; baseRev..headRev is this level's diff */
+  headRev?: string;
+  /** Linked worktree the task branch is checked out in (start --worktree) */
+  worktree?: string;
   /** Computed: true if task or any ancestor has incomplete blockers */
   effectivelyBlocked: boolean;
   /** Task was cancelled (abandoned without completion) */
@@ -106,6 +141,32 @@ export interface Task {
   archived: boolean;
   /** Timestamp when task was archived */
   archivedAt: string | null;
+  /** Bumped on every write to the task; pass back as expectedRevision */
+  revision: number;
+  /** Manual sibling order within a priority, lower first (tasks.move) */
+  rank: number;
+  /** Due date; open tasks past it are overdue */
+  dueAt: string | null;
+  /** Hidden from next-ready (treated as blocked) until this time */
+  deferUntil: string | null;
+  /** Arbitrary JSON object attached via os task meta (omitted if none) */
+  metadata?: Record<string, unknown>;
+  /** Lowercase labels, sorted (omitted if none) */
+  labels?: string[];
+  /** Live claim on this task itself (omitted if none) */
+  claim?: TaskClaim;
+  /** Registered repository the task works in (omitted for the working directory's) */
+  repoId?: string;
+}
+
+/**
+ * Lease on a task and its subtree, held by one agent until expiresAt
+ */
+export interface TaskClaim {
+  taskId: TaskId;
+  agent: string;
+  claimedAt: string;
+  expiresAt: string;
 }
 
 /**
@@ -114,6 +175,10 @@ export interface Task {
 export interface TaskWithContext extends Task {
   context: TaskContext;
   learnings: InheritedLearnings;
+  /** Files, code ranges, URLs, commits and issues the task points at (omitted when none) */
+  links?: TaskLink[];
+  /** Recent comments, oldest first (only with --comments) */
+  comments?: Comment[];
 }
 
 /** Recursive task tree node (from os task tree) */
@@ -163,6 +228,15 @@ export type ChangeType = "added" | "modified" | "deleted" | "renamed";
 export interface DiffEntry {
   path: string;
   changeType: ChangeType;
+  /** Path before a rename */
+  oldPath?: string;
+  /** Lines added; 0 for binary files */
+  additions: number;
+  /** Lines removed; 0 for binary files */
+  deletions: number;
+  binary: boolean;
+  /** Unified diff of the file (only with --patch) */
+  patch?: string;
 }
 
 export interface CommitResult {
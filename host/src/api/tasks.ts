@@ -10,8 +10,42 @@ import {
   decodeTaskTree,
   decodeTaskTrees,
   decodeTaskProgress,
+  decodeTaskClaim,
+  decodeTaskSession,
+  decodeTimeReport,
+  decodeTemplateSummary,
+  decodeTemplateSummaries,
+  decodeAppliedTemplate,
+  decodeComment,
+  decodeComments,
+  decodeTaskLink,
+  decodeTaskLinks,
+  decodeTrashEntries,
+  decodeRestoredTrash,
+  decodePurgedTrash,
+  decodeRepo,
+  decodeRepos,
 } from "../decoder.js";
-import type { Depth, Priority, Task, TaskWithContext, TaskTree, TaskProgress } from "../types.js";
+import type {
+  Depth,
+  IntegrationStrategy,
+  Priority,
+  Task,
+  TaskClaim,
+  TaskSession,
+  TimeReport,
+  TemplateSummary,
+  AppliedTemplate,
+  Comment,
+  TaskLink,
+  TrashEntry,
+  RestoredTrash,
+  PurgedTrash,
+  Repo,
+  TaskWithContext,
+  TaskTree,
+  TaskProgress,
+} from "../types.js";
 
 /**
  * Task type aliases for depth (ergonomic sugar)
@@ -29,8 +63,9 @@ export interface TaskFilter {
   ready?: boolean;
   completed?: boolean;
   /**
-   * Filter by depth: 0=milestones, 1=tasks, 2=subtasks.
-   * Maps to CLI: --milestones | --tasks | --subtasks
+   * Filter by depth: 0=milestones (roots), 1=tasks, 2=subtasks, and so on
+   * down to the database's configured max depth.
+   * Maps to CLI: --depth N
    * Mutually exclusive with parentId and type.
    */
   depth?: Depth;
@@ -48,14 +83,41 @@ export interface TaskFilter {
    * - "all": include all (archived and non-archived)
    */
   archived?: boolean | "all";
+  /** Only tasks carrying every label */
+  labels?: string[];
+  /** Only open tasks past their due date */
+  overdue?: boolean;
+  /** Only tasks due before this date (YYYY-MM-DD, RFC 3339, or e.g. "3d") */
+  dueBefore?: string;
+  /** Only tasks linked to this file, or to anything under this directory (repo-relative) */
+  linked?: string;
+  /** Only tasks in this registered repository (name or id) */
+  repo?: string;
 }
 
+/**
+ * Target of a new link. Files and commits are validated against the repository
+ * the CLI runs in; lines narrow a file link to an inclusive range.
+ */
+export type LinkTarget =
+  | { file: string; lines?: [number, number] }
+  | { url: string }
+  | { commit: string }
+  | { issue: string };
+
 export interface CreateTaskInput {
   description: string;
   context?: string;
   parentId?: string;
   priority?: Priority;
   blockedBy?: string[];
+  labels?: string[];
+  /** Due date: YYYY-MM-DD (end of day, UTC), RFC 3339, or from now (e.g. "3d") */
+  dueAt?: string;
+  /** Hide from nextReady until: YYYY-MM-DD (start of day, UTC), RFC 3339, or from now */
+  deferUntil?: string;
+  /** Registered repository (name or id); defaults to the parent's */
+  repo?: string;
 }
 
 export interface UpdateTaskInput {
@@ -63,6 +125,50 @@ export interface UpdateTaskInput {
   context?: string;
   priority?: Priority;
   parentId?: string;
+  /** Labels to add (existing labels are kept) */
+  labels?: string[];
+  /** New due date; null clears it */
+  dueAt?: string | null;
+  /** New deferral; null clears it */
+  deferUntil?: string | null;
+  /** Move to another registered repository; null detaches. Refused once started */
+  repo?: string | null;
+  /** Fail unless the task is still at this revision */
+  expectedRevision?: number;
+}
+
+export interface RevisionOptions {
+  /** Fail unless the task is still at this revision */
+  expectedRevision?: number;
+}
+
+/** Where tasks.move puts a task among its siblings */
+export type MovePosition =
+  | { before: string }
+  | { after: string }
+  | { first: true }
+  | { last: true };
+
+export interface NextReadyOptions {
+  /** Skip tasks under a live claim not held by agent */
+  unclaimed?: boolean;
+  agent?: string;
+  /** Include the latest N comments */
+  comments?: number;
+  /** Only tasks in this registered repository (name or id) */
+  repo?: string;
+}
+
+function pushLabels(args: string[], labels?: string[]): void {
+  for (const label of labels ?? []) args.push("--label", label);
+}
+
+function pushVars(args: string[], vars?: Record<string, string>): void {
+  for (const [name, value] of Object.entries(vars ?? {})) args.push("--var", `${name}=${value}`);
+}
+
+function pushRevision(args: string[], expectedRevision?: number): void {
+  if (expectedRevision !== undefined) args.push("--if-revision", String(expectedRevision));
 }
 
 /**
@@ -95,12 +201,7 @@ export const tasks = {
     if (filter?.ready) args.push("--ready");
     if (filter?.completed) args.push("--completed");
     if (effectiveDepth !== undefined) {
-      const depthFlags: Record<Depth, string> = {
-        0: "--milestones",
-        1: "--tasks",
-        2: "--subtasks",
-      };
-      args.push(depthFlags[effectiveDepth]);
+      args.push("--depth", String(effectiveDepth));
     }
     // Archived filter
     if (filter?.archived === true) {
@@ -109,14 +210,22 @@ export const tasks = {
       args.push("--all");
     }
     // Default (undefined or false) = hide archived (CLI default)
+    pushLabels(args, filter?.labels);
+    if (filter?.overdue) args.push("--overdue");
+    if (filter?.dueBefore) args.push("--due-before", filter.dueBefore);
+    if (filter?.linked) args.push("--linked", filter.linked);
+    if (filter?.repo) args.push("--repo", filter.repo);
     return decodeTasks(await callCli(args)).unwrap("tasks.list");
   },
 
   /**
    * Get single task with full context chain and inherited learnings.
+   * With options.comments, the latest N comments are included.
    */
-  async get(id: string): Promise<TaskWithContext> {
-    return decodeTaskWithContext(await callCli(["task", "get", id])).unwrap("tasks.get");
+  async get(id: string, options?: { comments?: number }): Promise<TaskWithContext> {
+    const args = ["task", "get", id];
+    if (options?.comments !== undefined) args.push("--comments", String(options.comments));
+    return decodeTaskWithContext(await callCli(args)).unwrap("tasks.get");
   },
 
   /**
@@ -131,6 +240,10 @@ export const tasks = {
     if (input.blockedBy && input.blockedBy.length > 0) {
       args.push("--blocked-by", input.blockedBy.join(","));
     }
+    pushLabels(args, input.labels);
+    if (input.dueAt) args.push("--due", input.dueAt);
+    if (input.deferUntil) args.push("--defer-until", input.deferUntil);
+    if (input.repo) args.push("--repo", input.repo);
     return decodeTask(await callCli(args)).unwrap("tasks.create");
   },
 
@@ -144,35 +257,82 @@ export const tasks = {
     if (input.context) args.push("--context", input.context);
     if (input.priority !== undefined) args.push("--priority", String(input.priority));
     if (input.parentId) args.push("--parent", input.parentId);
+    pushLabels(args, input.labels);
+    if (input.dueAt === null) args.push("--clear-due");
+    else if (input.dueAt) args.push("--due", input.dueAt);
+    if (input.deferUntil === null) args.push("--clear-defer");
+    else if (input.deferUntil) args.push("--defer-until", input.deferUntil);
+    if (input.repo === null) args.push("--clear-repo");
+    else if (input.repo) args.push("--repo", input.repo);
+    pushRevision(args, input.expectedRevision);
     return decodeTask(await callCli(args)).unwrap("tasks.update");
   },
 
+  /**
+   * Add labels to a task (lowercased; existing labels are kept).
+   */
+  async addLabels(id: string, labels: string[]): Promise<Task> {
+    return decodeTask(await callCli(["task", "label", "add", id, ...labels])).unwrap(
+      "tasks.addLabels"
+    );
+  },
+
+  /**
+   * Remove labels from a task.
+   */
+  async removeLabels(id: string, labels: string[]): Promise<Task> {
+    return decodeTask(await callCli(["task", "label", "rm", id, ...labels])).unwrap(
+      "tasks.removeLabels"
+    );
+  },
+
   /**
    * Mark task as started.
    * Follows blockers to find startable work, cascades to deepest leaf.
    * Creates VCS bookmark for started task and records start commit.
    * Returns the task that was actually started.
    *
+   * Fails if another agent holds a live claim on the task. With an agent,
+   * the started task is claimed for 30 minutes unless already covered.
+   * With expectedRevision, fails if the requested task changed since it was read.
+   * With worktree, the task branch is checked out in its own git worktree.
+   *
    * **Requires VCS**: Must be in a git repository.
    */
-  async start(id: string): Promise<Task> {
-    return decodeTask(await callCli(["task", "start", id])).unwrap("tasks.start");
+  async start(
+    id: string,
+    options?: { agent?: string; worktree?: boolean } & RevisionOptions
+  ): Promise<Task> {
+    const args = ["task", "start", id];
+    if (options?.agent) args.push("--agent", options.agent);
+    if (options?.worktree) args.push("--worktree");
+    pushRevision(args, options?.expectedRevision);
+    return decodeTask(await callCli(args)).unwrap("tasks.start");
   },
 
   /**
    * Complete task with optional result and learnings.
    * Learnings are attached to the task and bubbled to immediate parent.
    * Auto-bubbles up if all siblings done and parent unblocked.
-   * Commits changes and captures commit SHA.
+   * Commits changes and captures commit SHA, then integrates the task branch
+   * into the branch the task was started from (default strategy: db setting).
    *
    * **Requires VCS**: Must be in a git repository.
    */
   async complete(
     id: string,
-    options?: { result?: string; learnings?: string[] }
+    options?: {
+      result?: string;
+      learnings?: string[];
+      agent?: string;
+      integration?: IntegrationStrategy;
+    } & RevisionOptions
   ): Promise<Task> {
     const args = ["task", "complete", id];
     if (options?.result) args.push("--result", options.result);
+    if (options?.agent) args.push("--agent", options.agent);
+    if (options?.integration) args.push("--integration", options.integration);
+    pushRevision(args, options?.expectedRevision);
     if (options?.learnings) {
       for (const learning of options.learnings) {
         args.push("--learning", learning);
@@ -193,8 +353,10 @@ export const tasks = {
    * Cannot cancel completed or archived tasks.
    * Cannot cancel tasks with pending children.
    */
-  async cancel(id: string): Promise<Task> {
-    return decodeTask(await callCli(["task", "cancel", id])).unwrap("tasks.cancel");
+  async cancel(id: string, options?: RevisionOptions): Promise<Task> {
+    const args = ["task", "cancel", id];
+    pushRevision(args, options?.expectedRevision);
+    return decodeTask(await callCli(args)).unwrap("tasks.cancel");
   },
 
   /**
@@ -202,17 +364,44 @@ export const tasks = {
    * Archived tasks are hidden from default list views.
    * Cannot archive active (pending/in-progress) tasks.
    */
-  async archive(id: string): Promise<Task> {
-    return decodeTask(await callCli(["task", "archive", id])).unwrap("tasks.archive");
+  async archive(id: string, options?: RevisionOptions): Promise<Task> {
+    const args = ["task", "archive", id];
+    pushRevision(args, options?.expectedRevision);
+    return decodeTask(await callCli(args)).unwrap("tasks.archive");
   },
 
   /**
-   * Delete task (cascades to children and learnings).
+   * Move task and its subtree to the trash (undo with restore).
    */
   async delete(id: string): Promise<void> {
     await callCli(["task", "delete", id]);
   },
 
+  /**
+   * Bring a deleted subtree back under its original parent.
+   * Blocker edges whose other task is gone or no longer valid are skipped.
+   */
+  async restore(id: string): Promise<RestoredTrash> {
+    return decodeRestoredTrash(await callCli(["task", "restore", id])).unwrap("tasks.restore");
+  },
+
+  /**
+   * Deleted subtrees, most recent first.
+   */
+  async trash(): Promise<TrashEntry[]> {
+    return decodeTrashEntries(await callCli(["task", "trash", "list"])).unwrap("tasks.trash");
+  },
+
+  /**
+   * Permanently delete subtrees trashed at least olderThan ago ("30d", "12h"),
+   * with their task branches.
+   */
+  async purgeTrash(olderThan: string): Promise<PurgedTrash> {
+    return decodePurgedTrash(
+      await callCli(["task", "trash", "purge", "--older-than", olderThan])
+    ).unwrap("tasks.purgeTrash");
+  },
+
   /**
    * Add blocker relationship.
    * Validates: no self-blocks, no ancestor/descendant blocks, no cycles.
@@ -228,25 +417,233 @@ export const tasks = {
     await callCli(["task", "unblock", taskId, "--by", blockerId]);
   },
 
+  /**
+   * Reorder a task among its siblings. Priority still orders siblings first:
+   * before/after need a sibling of the same priority, first/last act within it.
+   */
+  async move(id: string, position: MovePosition): Promise<Task> {
+    const args = ["task", "move", id];
+    if ("before" in position) args.push("--before", position.before);
+    else if ("after" in position) args.push("--after", position.after);
+    else if ("first" in position) args.push("--first");
+    else args.push("--last");
+    return decodeTask(await callCli(args)).unwrap("tasks.move");
+  },
+
   /**
    * Get next ready task (DFS to find deepest unblocked incomplete leaf).
    * Returns task with full context chain and inherited learnings, or null if no ready tasks.
+   * With labels, only a task carrying every label is returned.
+   * With options.unclaimed, tasks claimed by anyone but options.agent are skipped.
    */
-  async nextReady(milestoneId?: string): Promise<TaskWithContext | null> {
+  async nextReady(
+    milestoneId?: string,
+    labels?: string[],
+    options?: NextReadyOptions
+  ): Promise<TaskWithContext | null> {
     const args = ["task", "next-ready"];
     if (milestoneId) args.push("--milestone", milestoneId);
+    pushLabels(args, labels);
+    if (options?.repo) args.push("--repo", options.repo);
+    if (options?.unclaimed) args.push("--unclaimed");
+    if (options?.agent) args.push("--agent", options.agent);
+    if (options?.comments !== undefined) args.push("--comments", String(options.comments));
     return decodeTaskWithContextOrNull(await callCli(args)).unwrap("tasks.nextReady");
   },
 
+  /**
+   * Claim a task and its subtree for an agent (ttl like "30m", default 30m).
+   * Claiming a task the agent already holds renews it.
+   */
+  async claim(id: string, agent: string, ttl?: string): Promise<TaskClaim> {
+    const args = ["task", "claim", id, "--agent", agent];
+    if (ttl) args.push("--ttl", ttl);
+    return decodeTaskClaim(await callCli(args)).unwrap("tasks.claim");
+  },
+
+  /**
+   * Extend the agent's live claim. Fails once the claim has expired.
+   */
+  async heartbeat(id: string, agent: string, ttl?: string): Promise<TaskClaim> {
+    const args = ["task", "heartbeat", id, "--agent", agent];
+    if (ttl) args.push("--ttl", ttl);
+    return decodeTaskClaim(await callCli(args)).unwrap("tasks.heartbeat");
+  },
+
+  /**
+   * Release a claim. Only its holder may release it unless force is set.
+   */
+  async release(id: string, options: { agent?: string; force?: boolean }): Promise<void> {
+    const args = ["task", "release", id];
+    if (options.agent) args.push("--agent", options.agent);
+    if (options.force) args.push("--force");
+    await callCli(args);
+  },
+
+  /**
+   * Append a comment to a task's discussion thread.
+   */
+  async addComment(id: string, body: string, options?: { author?: string }): Promise<Comment> {
+    const args = ["task", "comment", "add", id, body];
+    if (options?.author) args.push("--author", options.author);
+    return decodeComment(await callCli(args)).unwrap("tasks.addComment");
+  },
+
+  /**
+   * A task's comments, oldest first. With options.latest, only the last N.
+   */
+  async comments(id: string, options?: { latest?: number }): Promise<Comment[]> {
+    const args = ["task", "comment", "list", id];
+    if (options?.latest !== undefined) args.push("--latest", String(options.latest));
+    return decodeComments(await callCli(args)).unwrap("tasks.comments");
+  },
+
+  /**
+   * Delete a single comment.
+   */
+  async deleteComment(commentId: string): Promise<void> {
+    await callCli(["task", "comment", "delete", commentId]);
+  },
+
+  /**
+   * Link a task to a file, code range, URL, commit or issue.
+   * Linking the same target twice returns the existing link.
+   */
+  async addLink(id: string, target: LinkTarget, options?: { note?: string }): Promise<TaskLink> {
+    const args = ["task", "link", "add", id];
+    if ("file" in target) {
+      args.push("--file", target.file);
+      if (target.lines) args.push("--lines", `${target.lines[0]}-${target.lines[1]}`);
+    } else if ("url" in target) args.push("--url", target.url);
+    else if ("commit" in target) args.push("--commit", target.commit);
+    else args.push("--issue", target.issue);
+    if (options?.note) args.push("--note", options.note);
+    return decodeTaskLink(await callCli(args)).unwrap("tasks.addLink");
+  },
+
+  /**
+   * A task's links, oldest first.
+   */
+  async links(id: string): Promise<TaskLink[]> {
+    return decodeTaskLinks(await callCli(["task", "link", "list", id])).unwrap("tasks.links");
+  },
+
+  /**
+   * Remove a single link.
+   */
+  async removeLink(linkId: string): Promise<void> {
+    await callCli(["task", "link", "rm", linkId]);
+  },
+
+  /**
+   * Stop the clock on a started task without completing it.
+   * Returns the session that ended.
+   */
+  async pause(id: string, agent?: string): Promise<TaskSession> {
+    const args = ["task", "pause", id];
+    if (agent) args.push("--agent", agent);
+    return decodeTaskSession(await callCli(args)).unwrap("tasks.pause");
+  },
+
+  /**
+   * Restart the clock on a paused task (no VCS changes).
+   * Returns the new running session.
+   */
+  async resume(id: string, agent?: string): Promise<TaskSession> {
+    const args = ["task", "resume", id];
+    if (agent) args.push("--agent", agent);
+    return decodeTaskSession(await callCli(args)).unwrap("tasks.resume");
+  },
+
+  /**
+   * Time spent per task, milestone and UTC day, from work sessions.
+   * since/until: YYYY-MM-DD, RFC 3339, or ago (e.g. "7d"). until defaults to now.
+   */
+  async timeReport(options?: {
+    milestoneId?: string;
+    since?: string;
+    until?: string;
+  }): Promise<TimeReport> {
+    const args = ["report", "time"];
+    if (options?.milestoneId) args.push("--milestone", options.milestoneId);
+    if (options?.since) args.push("--since", options.since);
+    if (options?.until) args.push("--until", options.until);
+    return decodeTimeReport(await callCli(args)).unwrap("tasks.timeReport");
+  },
+
+  /**
+   * Save a task and its descendants as a template in .overseer/templates.
+   * vars: { name: value } turns each literal value into a {{name}} placeholder.
+   */
+  async saveTemplate(
+    rootId: string,
+    name: string,
+    options?: { vars?: Record<string, string>; force?: boolean }
+  ): Promise<TemplateSummary> {
+    const args = ["template", "save", rootId, name];
+    pushVars(args, options?.vars);
+    if (options?.force) args.push("--force");
+    return decodeTemplateSummary(await callCli(args)).unwrap("tasks.saveTemplate");
+  },
+
+  /**
+   * Create tasks from a template in one transaction. Every {{placeholder}}
+   * needs a value in vars. Without parentId the root becomes a milestone.
+   */
+  async applyTemplate(
+    name: string,
+    options?: { parentId?: string; vars?: Record<string, string> }
+  ): Promise<AppliedTemplate> {
+    const args = ["template", "apply", name];
+    if (options?.parentId) args.push("--parent", options.parentId);
+    pushVars(args, options?.vars);
+    return decodeAppliedTemplate(await callCli(args)).unwrap("tasks.applyTemplate");
+  },
+
+  /**
+   * Saved templates, by name.
+   */
+  async templates(): Promise<TemplateSummary[]> {
+    return decodeTemplateSummaries(await callCli(["template", "list"])).unwrap("tasks.templates");
+  },
+
+  /**
+   * Register the repository containing path (default: the CLI's working directory).
+   * Tasks created with repo: name start and complete in it.
+   */
+  async addRepo(path?: string, name?: string): Promise<Repo> {
+    const args = ["repo", "add"];
+    if (path) args.push(path);
+    if (name) args.push("--name", name);
+    return decodeRepo(await callCli(args)).unwrap("tasks.addRepo");
+  },
+
+  /**
+   * Registered repositories, by name.
+   */
+  async repos(): Promise<Repo[]> {
+    return decodeRepos(await callCli(["repo", "list"])).unwrap("tasks.repos");
+  },
+
+  /**
+   * Unregister a repository (name or id). Refused while it has open tasks.
+   */
+  async removeRepo(repo: string): Promise<Repo> {
+    return decodeRepo(await callCli(["repo", "remove", repo])).unwrap("tasks.removeRepo");
+  },
+
   /**
    * Get task tree structure.
    * If rootId provided, returns single tree rooted at that task.
    * If no rootId, returns array of all milestone trees.
    *
+   * With labels, only branches leading to a task carrying every label are kept.
+   *
    * **Warning:** Large trees may hit 50k output limit. Prefer scoping to specific milestone.
    */
-  async tree(rootId?: string): Promise<TaskTree | TaskTree[]> {
+  async tree(rootId?: string, labels?: string[]): Promise<TaskTree | TaskTree[]> {
     const args = ["task", "tree"];
+    pushLabels(args, labels);
     if (rootId) {
       args.push(rootId);
       return decodeTaskTree(await callCli(args)).unwrap("tasks.tree");
@@ -255,8 +652,8 @@ export const tasks = {
   },
 
   /**
-   * Search tasks by description.
-   * Returns tasks matching the query (case-insensitive substring match).
+   * Full-text search over description, context and result.
+   * Returns non-archived tasks matching every word (prefix match), best match first.
    */
   async search(query: string): Promise<Task[]> {
     return decodeTasks(await callCli(["task", "search", query])).unwrap("tasks.search");
@@ -266,9 +663,10 @@ export const tasks = {
    * Get progress summary for a milestone or all tasks.
    * Returns aggregate counts: { total, completed, ready, blocked }
    */
-  async progress(rootId?: string): Promise<TaskProgress> {
+  async progress(rootId?: string, labels?: string[]): Promise<TaskProgress> {
     const args = ["task", "progress"];
     if (rootId) args.push(rootId);
+    pushLabels(args, labels);
     return decodeTaskProgress(await callCli(args)).unwrap("tasks.progress");
   },
 };
@@ -14,6 +14,8 @@ export interface CliConfig {
   cliPath: string;
   /** Working directory for CLI commands */
   cwd: string;
+  /** Recorded as the `source` of events written by spawned commands */
+  source?: "mcp" | "ui";
 }
 
 // Global config, set by main entry point
@@ -43,6 +45,9 @@ export async function callCli(args: string[]): Promise<unknown> {
   return new Promise((resolve, reject) => {
     const proc = spawn(config.cliPath, [...args, "--json"], {
       cwd: config.cwd,
+      env: config.source
+        ? { ...process.env, OVERSEER_EVENT_SOURCE: config.source }
+        : process.env,
       stdio: ["ignore", "pipe", "pipe"],
     });
 
@@ -7,8 +7,21 @@ import {
   isTaskId,
   isLearningId,
   type Task,
+  type TaskClaim,
+  type TaskSession,
+  type TimeReport,
+  type TemplateSummary,
+  type AppliedTemplate,
+  type Repo,
+  type TrashEntry,
+  type BlockerEdge,
+  type RestoredTrash,
+  type PurgedTrash,
   type TaskWithContext,
   type Learning,
+  type Comment,
+  type TaskLink,
+  type LinkKind,
   type TaskId,
   type LearningId,
   type Priority,
@@ -49,7 +62,7 @@ function isNumber(v: unknown): v is number {
 
 // Helper to check valid priority (0=p0 highest, 1=p1 default, 2=p2 lowest)
 function isPriority(v: unknown): v is Priority {
-  return v === 0 || v === 1 || v === 2;
+  return typeof v === "number" && Number.isInteger(v) && v >= 0;
 }
 
 // Helper to check valid depth
@@ -114,6 +127,466 @@ export function decodeLearnings(v: unknown): Result<Learning[], DecodeError> {
   return Result.ok(learnings);
 }
 
+/**
+ * Decode a Comment from unknown JSON
+ */
+export function decodeComment(v: unknown): Result<Comment, DecodeError> {
+  if (!isObject(v)) {
+    return Result.err(new DecodeError({ message: "Comment must be object" }));
+  }
+
+  const { id, taskId, author, body, createdAt } = v;
+
+  if (!isString(id) || !id.startsWith("cmt_")) {
+    return Result.err(new DecodeError({ message: `Invalid comment id: ${id}` }));
+  }
+  if (!isString(taskId) || !isTaskId(taskId)) {
+    return Result.err(new DecodeError({ message: `Invalid comment taskId: ${taskId}` }));
+  }
+  if (author != null && !isString(author)) {
+    return Result.err(new DecodeError({ message: "Comment author must be string or null" }));
+  }
+  if (!isString(body)) {
+    return Result.err(new DecodeError({ message: "Comment body must be string" }));
+  }
+  if (!isString(createdAt)) {
+    return Result.err(new DecodeError({ message: "Comment createdAt must be string" }));
+  }
+
+  return Result.ok({
+    id,
+    taskId: taskId as TaskId,
+    author: author ?? null,
+    body,
+    createdAt,
+  });
+}
+
+/**
+ * Decode a Comment array
+ */
+export function decodeComments(v: unknown): Result<Comment[], DecodeError> {
+  if (!Array.isArray(v)) {
+    return Result.err(new DecodeError({ message: "Comments must be array" }));
+  }
+
+  const comments: Comment[] = [];
+  for (let i = 0; i < v.length; i++) {
+    const result = decodeComment(v[i]);
+    if (result.isErr()) {
+      return Result.err(new DecodeError({
+        message: result.error.message,
+        path: `comments[${i}]`
+      }));
+    }
+    comments.push(result.value);
+  }
+  return Result.ok(comments);
+}
+
+const LINK_KINDS: readonly LinkKind[] = ["file", "fileRange", "url", "commit", "issue"];
+
+function isLineNumber(v: unknown): v is number {
+  return typeof v === "number" && Number.isInteger(v) && v >= 1;
+}
+
+/**
+ * Decode a TaskLink from unknown JSON
+ */
+export function decodeTaskLink(v: unknown): Result<TaskLink, DecodeError> {
+  if (!isObject(v)) {
+    return Result.err(new DecodeError({ message: "TaskLink must be object" }));
+  }
+
+  const { id, taskId, kind, target, lineStart, lineEnd, note, createdAt } = v;
+
+  if (!isString(id) || !id.startsWith("lnk_")) {
+    return Result.err(new DecodeError({ message: `Invalid link id: ${id}` }));
+  }
+  if (!isString(taskId) || !isTaskId(taskId)) {
+    return Result.err(new DecodeError({ message: `Invalid link taskId: ${taskId}` }));
+  }
+  if (!isString(kind) || !LINK_KINDS.includes(kind as LinkKind)) {
+    return Result.err(new DecodeError({ message: `Invalid link kind: ${kind}` }));
+  }
+  if (!isString(target)) {
+    return Result.err(new DecodeError({ message: "TaskLink target must be string" }));
+  }
+  if (lineStart != null && !isLineNumber(lineStart)) {
+    return Result.err(new DecodeError({ message: `Invalid link lineStart: ${lineStart}` }));
+  }
+  if (lineEnd != null && !isLineNumber(lineEnd)) {
+    return Result.err(new DecodeError({ message: `Invalid link lineEnd: ${lineEnd}` }));
+  }
+  if (note != null && !isString(note)) {
+    return Result.err(new DecodeError({ message: "TaskLink note must be string or null" }));
+  }
+  if (!isString(createdAt)) {
+    return Result.err(new DecodeError({ message: "TaskLink createdAt must be string" }));
+  }
+
+  return Result.ok({
+    id,
+    taskId: taskId as TaskId,
+    kind: kind as LinkKind,
+    target,
+    lineStart: lineStart ?? null,
+    lineEnd: lineEnd ?? null,
+    note: note ?? null,
+    createdAt,
+  });
+}
+
+/**
+ * Decode a TaskLink array
+ */
+export function decodeTaskLinks(v: unknown): Result<TaskLink[], DecodeError> {
+  if (!Array.isArray(v)) {
+    return Result.err(new DecodeError({ message: "TaskLinks must be array" }));
+  }
+
+  const links: TaskLink[] = [];
+  for (let i = 0; i < v.length; i++) {
+    const result = decodeTaskLink(v[i]);
+    if (result.isErr()) {
+      return Result.err(new DecodeError({
+        message: result.error.message,
+        path: `links[${i}]`
+      }));
+    }
+    links.push(result.value);
+  }
+  return Result.ok(links);
+}
+
+/**
+ * Decode a TaskClaim from unknown JSON
+ */
+export function decodeTaskClaim(v: unknown): Result<TaskClaim, DecodeError> {
+  if (!isObject(v)) {
+    return Result.err(new DecodeError({ message: "TaskClaim must be object" }));
+  }
+
+  const { taskId, agent, claimedAt, expiresAt } = v;
+
+  if (!isString(taskId) || !isTaskId(taskId)) {
+    return Result.err(new DecodeError({ message: `Invalid claim taskId: ${taskId}` }));
+  }
+  if (!isString(agent)) {
+    return Result.err(new DecodeError({ message: "Claim agent must be string" }));
+  }
+  if (!isString(claimedAt) || !isString(expiresAt)) {
+    return Result.err(new DecodeError({ message: "Claim claimedAt/expiresAt must be strings" }));
+  }
+
+  return Result.ok({ taskId: taskId as TaskId, agent, claimedAt, expiresAt });
+}
+
+export function decodeTaskSession(v: unknown): Result<TaskSession, DecodeError> {
+  if (!isObject(v)) {
+    return Result.err(new DecodeError({ message: "TaskSession must be object" }));
+  }
+
+  const { id, taskId, agent, startedAt, endedAt } = v;
+
+  if (!isNumber(id) || !Number.isInteger(id)) {
+    return Result.err(new DecodeError({ message: `Invalid session id: ${id}` }));
+  }
+  if (!isString(taskId) || !isTaskId(taskId)) {
+    return Result.err(new DecodeError({ message: `Invalid session taskId: ${taskId}` }));
+  }
+  if (agent !== null && !isString(agent)) {
+    return Result.err(new DecodeError({ message: "Session agent must be string or null" }));
+  }
+  if (!isString(startedAt)) {
+    return Result.err(new DecodeError({ message: "Session startedAt must be string" }));
+  }
+  if (endedAt !== null && !isString(endedAt)) {
+    return Result.err(new DecodeError({ message: "Session endedAt must be string or null" }));
+  }
+
+  return Result.ok({
+    id,
+    taskId: taskId as TaskId,
+    agent: agent as string | null,
+    startedAt,
+    endedAt: endedAt as string | null,
+  });
+}
+
+function isSeconds(v: unknown): v is number {
+  return isNumber(v) && Number.isInteger(v) && v >= 0;
+}
+
+export function decodeTimeReport(v: unknown): Result<TimeReport, DecodeError> {
+  if (!isObject(v)) {
+    return Result.err(new DecodeError({ message: "TimeReport must be object" }));
+  }
+
+  const { since, until, totalSeconds, tasks, milestones, days } = v;
+
+  if (since !== null && !isString(since)) {
+    return Result.err(new DecodeError({ message: "TimeReport.since must be string or null" }));
+  }
+  if (!isString(until) || !isSeconds(totalSeconds)) {
+    return Result.err(new DecodeError({ message: "TimeReport until/totalSeconds invalid" }));
+  }
+  if (!Array.isArray(tasks) || !Array.isArray(milestones) || !Array.isArray(days)) {
+    return Result.err(new DecodeError({ message: "TimeReport tasks/milestones/days must be arrays" }));
+  }
+  for (const t of tasks) {
+    if (
+      !isObject(t) ||
+      !isString(t.taskId) ||
+      !isTaskId(t.taskId) ||
+      !isString(t.description) ||
+      !isString(t.milestoneId) ||
+      !isTaskId(t.milestoneId) ||
+      !isSeconds(t.seconds) ||
+      !isSeconds(t.sessions) ||
+      !isBoolean(t.running)
+    ) {
+      return Result.err(new DecodeError({ message: "Invalid TimeReport task entry" }));
+    }
+  }
+  for (const m of milestones) {
+    if (
+      !isObject(m) ||
+      !isString(m.milestoneId) ||
+      !isTaskId(m.milestoneId) ||
+      !isString(m.description) ||
+      !isSeconds(m.seconds)
+    ) {
+      return Result.err(new DecodeError({ message: "Invalid TimeReport milestone entry" }));
+    }
+  }
+  for (const d of days) {
+    if (!isObject(d) || !isString(d.date) || !isSeconds(d.seconds)) {
+      return Result.err(new DecodeError({ message: "Invalid TimeReport day entry" }));
+    }
+  }
+
+  return Result.ok(v as unknown as TimeReport);
+}
+
+export function decodeTemplateSummary(v: unknown): Result<TemplateSummary, DecodeError> {
+  if (!isObject(v)) {
+    return Result.err(new DecodeError({ message: "TemplateSummary must be object" }));
+  }
+
+  const { name, path, tasks, variables } = v;
+
+  if (!isString(name) || !isString(path)) {
+    return Result.err(new DecodeError({ message: "TemplateSummary name/path must be strings" }));
+  }
+  if (!isSeconds(tasks)) {
+    return Result.err(new DecodeError({ message: `Invalid template task count: ${tasks}` }));
+  }
+  if (!Array.isArray(variables) || !variables.every(isString)) {
+    return Result.err(new DecodeError({ message: "TemplateSummary variables must be strings" }));
+  }
+
+  return Result.ok({ name, path, tasks, variables });
+}
+
+export function decodeTemplateSummaries(v: unknown): Result<TemplateSummary[], DecodeError> {
+  if (!Array.isArray(v)) {
+    return Result.err(new DecodeError({ message: "Templates must be array" }));
+  }
+
+  const summaries: TemplateSummary[] = [];
+  for (let i = 0; i < v.length; i++) {
+    const result = decodeTemplateSummary(v[i]);
+    if (result.isErr()) {
+      return Result.err(new DecodeError({ message: result.error.message, path: `templates[${i}]` }));
+    }
+    summaries.push(result.value);
+  }
+  return Result.ok(summaries);
+}
+
+export function decodeAppliedTemplate(v: unknown): Result<AppliedTemplate, DecodeError> {
+  if (!isObject(v)) {
+    return Result.err(new DecodeError({ message: "AppliedTemplate must be object" }));
+  }
+
+  const { name, rootId, tasks, blockers } = v;
+
+  if (!isString(name)) {
+    return Result.err(new DecodeError({ message: "AppliedTemplate name must be string" }));
+  }
+  if (!isString(rootId) || !isTaskId(rootId)) {
+    return Result.err(new DecodeError({ message: `Invalid template rootId: ${rootId}` }));
+  }
+  if (!isSeconds(blockers)) {
+    return Result.err(new DecodeError({ message: `Invalid template blocker count: ${blockers}` }));
+  }
+  const decoded = decodeTasks(tasks);
+  if (decoded.isErr()) {
+    return Result.err(decoded.error);
+  }
+
+  return Result.ok({ name, rootId: rootId as TaskId, tasks: decoded.value, blockers });
+}
+
+/**
+ * Decode a Repo from unknown JSON
+ */
+export function decodeRepo(v: unknown): Result<Repo, DecodeError> {
+  if (!isObject(v)) {
+    return Result.err(new DecodeError({ message: "Repo must be object" }));
+  }
+
+  const { id, path, name, vcsType, createdAt } = v;
+
+  if (!isString(id) || !id.startsWith("repo_")) {
+    return Result.err(new DecodeError({ message: `Invalid repo id: ${id}` }));
+  }
+  if (!isString(path) || !isString(name)) {
+    return Result.err(new DecodeError({ message: "Repo path and name must be strings" }));
+  }
+  if (vcsType !== "git" && vcsType !== "none") {
+    return Result.err(new DecodeError({ message: `Invalid repo vcsType: ${vcsType}` }));
+  }
+  if (!isString(createdAt)) {
+    return Result.err(new DecodeError({ message: "Repo createdAt must be string" }));
+  }
+
+  return Result.ok({ id, path, name, vcsType, createdAt });
+}
+
+/**
+ * Decode a Repo array
+ */
+export function decodeRepos(v: unknown): Result<Repo[], DecodeError> {
+  if (!Array.isArray(v)) {
+    return Result.err(new DecodeError({ message: "Repos must be array" }));
+  }
+
+  const repos: Repo[] = [];
+  for (let i = 0; i < v.length; i++) {
+    const result = decodeRepo(v[i]);
+    if (result.isErr()) {
+      return Result.err(new DecodeError({
+        message: result.error.message,
+        path: `repos[${i}]`
+      }));
+    }
+    repos.push(result.value);
+  }
+  return Result.ok(repos);
+}
+
+/**
+ * Decode a TrashEntry from unknown JSON
+ */
+export function decodeTrashEntry(v: unknown): Result<TrashEntry, DecodeError> {
+  if (!isObject(v)) {
+    return Result.err(new DecodeError({ message: "TrashEntry must be object" }));
+  }
+
+  const { id, parentId, description, tasks, deletedAt } = v;
+
+  if (!isString(id) || !isTaskId(id)) {
+    return Result.err(new DecodeError({ message: `Invalid trash entry id: ${id}` }));
+  }
+  if (parentId != null && (!isString(parentId) || !isTaskId(parentId))) {
+    return Result.err(new DecodeError({ message: `Invalid trash entry parentId: ${parentId}` }));
+  }
+  if (!isString(description)) {
+    return Result.err(new DecodeError({ message: "TrashEntry description must be string" }));
+  }
+  if (!isSeconds(tasks)) {
+    return Result.err(new DecodeError({ message: `Invalid trash entry task count: ${tasks}` }));
+  }
+  if (!isString(deletedAt)) {
+    return Result.err(new DecodeError({ message: "TrashEntry deletedAt must be string" }));
+  }
+
+  return Result.ok({
+    id: id as TaskId,
+    parentId: (parentId ?? null) as TaskId | null,
+    description,
+    tasks,
+    deletedAt,
+  });
+}
+
+/**
+ * Decode a TrashEntry array
+ */
+export function decodeTrashEntries(v: unknown): Result<TrashEntry[], DecodeError> {
+  if (!Array.isArray(v)) {
+    return Result.err(new DecodeError({ message: "TrashEntries must be array" }));
+  }
+
+  const entries: TrashEntry[] = [];
+  for (let i = 0; i < v.length; i++) {
+    const result = decodeTrashEntry(v[i]);
+    if (result.isErr()) {
+      return Result.err(new DecodeError({
+        message: result.error.message,
+        path: `trash[${i}]`
+      }));
+    }
+    entries.push(result.value);
+  }
+  return Result.ok(entries);
+}
+
+/**
+ * Decode the result of os task restore
+ */
+export function decodeRestoredTrash(v: unknown): Result<RestoredTrash, DecodeError> {
+  if (!isObject(v)) {
+    return Result.err(new DecodeError({ message: "RestoredTrash must be object" }));
+  }
+
+  const { task, tasks, blockers, skippedBlockers } = v;
+
+  if (!isSeconds(tasks) || !isSeconds(blockers)) {
+    return Result.err(new DecodeError({ message: `Invalid restore counts: ${tasks}, ${blockers}` }));
+  }
+  if (!Array.isArray(skippedBlockers)) {
+    return Result.err(new DecodeError({ message: "RestoredTrash skippedBlockers must be array" }));
+  }
+  const skipped: BlockerEdge[] = [];
+  for (const edge of skippedBlockers) {
+    if (!isObject(edge) || !isString(edge.taskId) || !isTaskId(edge.taskId)
+      || !isString(edge.blockerId) || !isTaskId(edge.blockerId)) {
+      return Result.err(new DecodeError({ message: "Invalid skipped blocker edge" }));
+    }
+    skipped.push({ taskId: edge.taskId as TaskId, blockerId: edge.blockerId as TaskId });
+  }
+  const decoded = decodeTask(task);
+  if (decoded.isErr()) {
+    return Result.err(decoded.error);
+  }
+
+  return Result.ok({ task: decoded.value, tasks, blockers, skippedBlockers: skipped });
+}
+
+/**
+ * Decode the result of os task trash purge
+ */
+export function decodePurgedTrash(v: unknown): Result<PurgedTrash, DecodeError> {
+  if (!isObject(v)) {
+    return Result.err(new DecodeError({ message: "PurgedTrash must be object" }));
+  }
+
+  const { purged, deletedBranches } = v;
+
+  if (!Array.isArray(deletedBranches) || !deletedBranches.every(isString)) {
+    return Result.err(new DecodeError({ message: "PurgedTrash deletedBranches must be string array" }));
+  }
+  const entries = decodeTrashEntries(purged);
+  if (entries.isErr()) {
+    return Result.err(entries.error);
+  }
+
+  return Result.ok({ purged: entries.value, deletedBranches });
+}
+
 /**
  * Decode a Task from unknown JSON
  */
@@ -139,11 +612,24 @@ export function decodeTask(v: unknown): Result<Task, DecodeError> {
     blocks,
     bookmark,
     startCommit,
+    startBranch,
+    integrationCommit,
+    baseRev,
+    headRev,
+    worktree,
     effectivelyBlocked,
     cancelled,
     cancelledAt,
     archived,
     archivedAt,
+    revision,
+    rank,
+    dueAt,
+    deferUntil,
+    metadata,
+    labels,
+    claim,
+    repoId,
   } = v;
 
   // Required fields
@@ -198,6 +684,18 @@ export function decodeTask(v: unknown): Result<Task, DecodeError> {
   if (archivedAt !== null && !isString(archivedAt)) {
     return Result.err(new DecodeError({ message: "Task archivedAt must be string or null" }));
   }
+  if (!isNumber(revision) || !Number.isInteger(revision) || revision < 0) {
+    return Result.err(new DecodeError({ message: `Invalid task revision: ${revision}` }));
+  }
+  if (!isNumber(rank) || !Number.isInteger(rank)) {
+    return Result.err(new DecodeError({ message: `Invalid task rank: ${rank}` }));
+  }
+  if (dueAt !== null && !isString(dueAt)) {
+    return Result.err(new DecodeError({ message: "Task dueAt must be string or null" }));
+  }
+  if (deferUntil !== null && !isString(deferUntil)) {
+    return Result.err(new DecodeError({ message: "Task deferUntil must be string or null" }));
+  }
 
   // Optional array fields
   let decodedBlockedBy: TaskId[] | undefined;
@@ -228,6 +726,27 @@ export function decodeTask(v: unknown): Result<Task, DecodeError> {
     }
   }
 
+  let decodedLabels: string[] | undefined;
+  if (labels !== undefined) {
+    if (!Array.isArray(labels) || !labels.every(isString)) {
+      return Result.err(new DecodeError({ message: "Task labels must be string array" }));
+    }
+    decodedLabels = labels;
+  }
+
+  let decodedClaim: TaskClaim | undefined;
+  if (claim !== undefined) {
+    const claimResult = decodeTaskClaim(claim);
+    if (claimResult.isErr()) {
+      return Result.err(claimResult.error);
+    }
+    decodedClaim = claimResult.value;
+  }
+
+  if (metadata !== undefined && !isObject(metadata)) {
+    return Result.err(new DecodeError({ message: "Task metadata must be object" }));
+  }
+
   // Optional string fields
   if (bookmark !== undefined && !isString(bookmark)) {
     return Result.err(new DecodeError({ message: "Task bookmark must be string" }));
@@ -235,6 +754,24 @@ export function decodeTask(v: unknown): Result<Task, DecodeError> {
   if (startCommit !== undefined && !isString(startCommit)) {
     return Result.err(new DecodeError({ message: "Task startCommit must be string" }));
   }
+  if (startBranch !== undefined && !isString(startBranch)) {
+    return Result.err(new DecodeError({ message: "Task startBranch must be string" }));
+  }
+  if (integrationCommit !== undefined && !isString(integrationCommit)) {
+    return Result.err(new DecodeError({ message: "Task integrationCommit must be string" }));
+  }
+  if (baseRev !== undefined && !isString(baseRev)) {
+    return Result.err(new DecodeError({ message: "Task baseRev must be string" }));
+  }
+  if (headRev !== undefined && !isString(headRev)) {
+    return Result.err(new DecodeError({ message: "Task headRev must be string" }));
+  }
+  if (worktree !== undefined && !isString(worktree)) {
+    return Result.err(new DecodeError({ message: "Task worktree must be string" }));
+  }
+  if (repoId !== undefined && (!isString(repoId) || !repoId.startsWith("repo_"))) {
+    return Result.err(new DecodeError({ message: `Invalid task repoId: ${repoId}` }));
+  }
 
   const task: Task = {
     id: id as TaskId,
@@ -254,12 +791,25 @@ export function decodeTask(v: unknown): Result<Task, DecodeError> {
     cancelledAt: cancelledAt as string | null,
     archived,
     archivedAt: archivedAt as string | null,
+    revision,
+    rank,
+    dueAt: dueAt as string | null,
+    deferUntil: deferUntil as string | null,
   };
 
   if (decodedBlockedBy) task.blockedBy = decodedBlockedBy;
   if (decodedBlocks) task.blocks = decodedBlocks;
+  if (metadata !== undefined) task.metadata = metadata as Record<string, unknown>;
+  if (decodedLabels) task.labels = decodedLabels;
+  if (decodedClaim) task.claim = decodedClaim;
   if (bookmark !== undefined) task.bookmark = bookmark as string;
   if (startCommit !== undefined) task.startCommit = startCommit as string;
+  if (startBranch !== undefined) task.startBranch = startBranch as string;
+  if (integrationCommit !== undefined) task.integrationCommit = integrationCommit as string;
+  if (baseRev !== undefined) task.baseRev = baseRev as string;
+  if (headRev !== undefined) task.headRev = headRev as string;
+  if (worktree !== undefined) task.worktree = worktree as string;
+  if (repoId !== undefined) task.repoId = repoId as string;
 
   return Result.ok(task);
 }
@@ -358,7 +908,7 @@ export function decodeTaskWithContext(v: unknown): Result<TaskWithContext, Decod
   if (taskResult.isErr()) return taskResult;
 
   // Then decode context and learnings
-  const { context, learnings } = v;
+  const { context, learnings, links, comments } = v;
 
   const ctxResult = decodeTaskContext(context);
   if (ctxResult.isErr()) {
@@ -370,10 +920,32 @@ export function decodeTaskWithContext(v: unknown): Result<TaskWithContext, Decod
     return Result.err(new DecodeError({ message: lrnResult.error.message, path: "learnings" }));
   }
 
+  // Omitted when the task has no links
+  let decodedLinks: TaskLink[] | undefined;
+  if (links !== undefined) {
+    const lnkResult = decodeTaskLinks(links);
+    if (lnkResult.isErr()) {
+      return Result.err(new DecodeError({ message: lnkResult.error.message, path: "links" }));
+    }
+    decodedLinks = lnkResult.value;
+  }
+
+  // Only present when requested with --comments
+  let decodedComments: Comment[] | undefined;
+  if (comments !== undefined) {
+    const cmtResult = decodeComments(comments);
+    if (cmtResult.isErr()) {
+      return Result.err(new DecodeError({ message: cmtResult.error.message, path: "comments" }));
+    }
+    decodedComments = cmtResult.value;
+  }
+
   return Result.ok({
     ...taskResult.value,
     context: ctxResult.value,
     learnings: lrnResult.value,
+    ...(decodedLinks !== undefined && { links: decodedLinks }),
+    ...(decodedComments !== undefined && { comments: decodedComments }),
   });
 }
 
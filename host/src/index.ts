@@ -139,6 +139,7 @@ async function main(): Promise<void> {
   configureCli({
     cliPath: args.cliPath,
     cwd: args.cwd,
+    source: args.mode,
   });
 
   if (args.mode === "mcp") {
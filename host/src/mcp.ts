@@ -28,21 +28,116 @@ interface Task {
   updatedAt: string;
   result: string | null;        // Completion notes
   commitSha: string | null;     // Auto-populated on complete
-  depth: 0 | 1 | 2;             // 0=milestone, 1=task, 2=subtask
+  depth: number;                // 0=milestone (root), 1=task, 2=subtask, ...
   blockedBy?: string[];          // Omitted if empty
   blocks?: string[];             // Omitted if empty
   bookmark?: string;            // VCS bookmark name (if started)
   startCommit?: string;         // Commit SHA at start
+  startBranch?: string;         // Branch checked out at start; complete integrates into it
+  integrationCommit?: string;   // Start branch tip after complete integrated the work
+  baseRev?: string;             // Parent branch tip this task's branch was created from
+  headRev?: string;             // Branch tip at completion; baseRev..headRev is this level's diff
+  worktree?: string;            // Linked worktree path (started with worktree: true, until integrated)
   effectivelyBlocked: boolean;  // True if task OR ancestor has incomplete blockers
   cancelled: boolean;           // Task was cancelled (does NOT satisfy blockers)
   cancelledAt: string | null;
   archived: boolean;            // Task is archived (hidden from default list)
   archivedAt: string | null;
+  revision: number;             // Bumped on every write; pass back as expectedRevision
+  rank: number;                 // Manual sibling order within a priority (tasks.move)
+  dueAt: string | null;         // Open tasks past it are overdue
+  deferUntil: string | null;    // Treated as blocked (skipped by nextReady) until then
+  metadata?: Record<string, unknown>;  // JSON object from os task meta; omitted if none
+  labels?: string[];             // Lowercase, sorted; omitted if none
+  claim?: TaskClaim;             // Live claim on this task; omitted if none
+  repoId?: string;               // Registered repo it works in; omitted = working directory's
+}
+
+interface TaskClaim {
+  taskId: string;
+  agent: string;
+  claimedAt: string;
+  expiresAt: string;             // Claim covers the subtree until then
+}
+
+interface TaskSession {
+  id: number;
+  taskId: string;
+  agent: string | null;
+  startedAt: string;
+  endedAt: string | null;        // null while running
+}
+
+interface TimeReport {
+  since: string | null;
+  until: string;
+  totalSeconds: number;
+  tasks: { taskId: string; description: string; milestoneId: string; seconds: number; sessions: number; running: boolean }[];
+  milestones: { milestoneId: string; description: string; seconds: number }[];
+  days: { date: string; seconds: number }[];  // UTC days, oldest first
+}
+
+interface TemplateSummary {
+  name: string;
+  path: string;
+  tasks: number;
+  variables: string[];  // {{placeholder}} names
+}
+
+interface AppliedTemplate {
+  name: string;
+  rootId: string;
+  tasks: Task[];  // Parents before children
+  blockers: number;
+}
+
+interface Repo {
+  id: string;    // repo_...
+  path: string;  // Repository root
+  name: string;  // Accepted wherever a repo id is
+  vcsType: "git" | "none";
+  createdAt: string;
+}
+
+interface TrashEntry {
+  id: string;  // Root of the deleted subtree; pass to tasks.restore
+  parentId: string | null;
+  description: string;
+  tasks: number;  // Tasks in the subtree, root included
+  deletedAt: string;
+}
+
+interface RestoredTrash {
+  task: Task;
+  tasks: number;
+  blockers: number;  // Blocker edges rebuilt
+  skippedBlockers: { taskId: string; blockerId: string }[];  // Other task gone or edge no longer valid
 }
 
 interface TaskWithContext extends Task {
   context: { own: string; parent?: string; milestone?: string };
   learnings: { own: Learning[]; parent: Learning[]; milestone: Learning[] };
+  links?: TaskLink[];  // Omitted when the task has no links
+  comments?: Comment[];  // Latest N, oldest first (only when requested)
+}
+
+interface TaskLink {
+  id: string;  // lnk_...
+  taskId: string;
+  kind: "file" | "fileRange" | "url" | "commit" | "issue";
+  target: string;  // Repo-relative path, URL, full commit id or issue reference
+  lineStart: number | null;  // fileRange only
+  lineEnd: number | null;
+  note: string | null;
+  createdAt: string;
+}
+
+interface Comment {
+  id: string;  // cmt_...
+  taskId: string;
+  author: string | null;
+  body: string;
+  createdAt: string;
 }
 
 interface Learning {
@@ -70,33 +165,66 @@ type TaskType = "milestone" | "task" | "subtask";
 // Tasks API
 // Note: VCS (git) is REQUIRED for start/complete. CRUD ops work without VCS.
 declare const tasks: {
-  list(filter?: { parentId?: string; ready?: boolean; completed?: boolean; depth?: 0 | 1 | 2; type?: TaskType; archived?: boolean | "all" }): Promise<Task[]>;
-  get(id: string): Promise<TaskWithContext>;
+  list(filter?: { parentId?: string; ready?: boolean; completed?: boolean; depth?: number; type?: TaskType; archived?: boolean | "all"; labels?: string[]; overdue?: boolean; dueBefore?: string; linked?: string; repo?: string }): Promise<Task[]>;  // linked: tasks linked to a file or anything under a directory; repo: name or id
+  get(id: string, options?: { comments?: number }): Promise<TaskWithContext>;  // comments: include the latest N
   create(input: {
     description: string;
     context?: string;
     parentId?: string;
     priority?: 0 | 1 | 2;
     blockedBy?: string[];
+    labels?: string[];
+    dueAt?: string;       // YYYY-MM-DD, RFC 3339, or from now (e.g. "3d")
+    deferUntil?: string;  // Same formats; hidden from nextReady until then
+    repo?: string;        // Registered repo (name or id); default: the parent's
   }): Promise<Task>;
   update(id: string, input: {
     description?: string;
     context?: string;
     priority?: 0 | 1 | 2;
     parentId?: string;
+    labels?: string[];  // Added to existing labels
+    dueAt?: string | null;       // null clears
+    deferUntil?: string | null;  // null clears
+    repo?: string | null;        // null detaches; refused once started
+    expectedRevision?: number;  // Fail unless the task is still at this revision
   }): Promise<Task>;
-  start(id: string): Promise<Task>;  // VCS required: creates bookmark, records start commit
-  complete(id: string, options?: { result?: string; learnings?: string[] }): Promise<Task>;  // VCS required: commits changes (NothingToCommit = success)
+  addLabels(id: string, labels: string[]): Promise<Task>;
+  removeLabels(id: string, labels: string[]): Promise<Task>;
+  start(id: string, options?: { agent?: string; expectedRevision?: number; worktree?: boolean }): Promise<Task>;  // VCS required: creates bookmark (in its own git worktree with worktree: true), records start commit; refused if another agent holds a claim
+  complete(id: string, options?: { result?: string; learnings?: string[]; agent?: string; expectedRevision?: number; integration?: "merge" | "squash" | "fast-forward" | "rebase" }): Promise<Task>;  // VCS required: commits changes (NothingToCommit = success), then integrates the task branch into its start branch
   reopen(id: string): Promise<Task>;
-  cancel(id: string): Promise<Task>;  // Cancel task (does NOT satisfy blockers)
-  archive(id: string): Promise<Task>;  // Archive completed/cancelled task (hides from default list)
-  delete(id: string): Promise<void>;  // Best-effort VCS bookmark cleanup
+  cancel(id: string, options?: { expectedRevision?: number }): Promise<Task>;  // Cancel task (does NOT satisfy blockers)
+  archive(id: string, options?: { expectedRevision?: number }): Promise<Task>;  // Archive completed/cancelled task (hides from default list)
+  delete(id: string): Promise<void>;  // Moves the subtree to the trash; branches are kept
+  restore(id: string): Promise<RestoredTrash>;  // Undo delete: back under the original parent, blocker edges rebuilt
+  trash(): Promise<TrashEntry[]>;  // Most recently deleted first
+  purgeTrash(olderThan: string): Promise<{ purged: TrashEntry[]; deletedBranches: string[] }>;  // olderThan like "30d"; permanent
   block(taskId: string, blockerId: string): Promise<void>;
   unblock(taskId: string, blockerId: string): Promise<void>;
-  nextReady(milestoneId?: string): Promise<TaskWithContext | null>;
-  tree(rootId?: string): Promise<TaskTree | TaskTree[]>;  // Returns single tree if rootId, array of all milestone trees if not
-  search(query: string): Promise<Task[]>;  // Search by description/context/result (case-insensitive)
-  progress(rootId?: string): Promise<TaskProgress>;  // Aggregate counts for milestone or all tasks
+  move(id: string, position: { before: string } | { after: string } | { first: true } | { last: true }): Promise<Task>;  // Reorder among siblings of the same priority
+  nextReady(milestoneId?: string, labels?: string[], options?: { unclaimed?: boolean; agent?: string; comments?: number; repo?: string }): Promise<TaskWithContext | null>;  // labels: only pick tasks carrying all of them; unclaimed: skip tasks claimed by others
+  claim(id: string, agent: string, ttl?: string): Promise<TaskClaim>;  // ttl like "30m" (default); covers the subtree
+  heartbeat(id: string, agent: string, ttl?: string): Promise<TaskClaim>;  // Renew a live claim
+  release(id: string, options: { agent?: string; force?: boolean }): Promise<void>;
+  addComment(id: string, body: string, options?: { author?: string }): Promise<Comment>;  // Append to the task's discussion thread
+  comments(id: string, options?: { latest?: number }): Promise<Comment[]>;  // Oldest first; latest: only the last N
+  deleteComment(commentId: string): Promise<void>;
+  addLink(id: string, target: { file: string; lines?: [number, number] } | { url: string } | { commit: string } | { issue: string }, options?: { note?: string }): Promise<TaskLink>;  // Files/commits must exist in the repo
+  links(id: string): Promise<TaskLink[]>;
+  removeLink(linkId: string): Promise<void>;
+  pause(id: string, agent?: string): Promise<TaskSession>;   // Stop the clock without completing
+  resume(id: string, agent?: string): Promise<TaskSession>;  // Restart it (start/complete/cancel also open/close sessions)
+  timeReport(options?: { milestoneId?: string; since?: string; until?: string }): Promise<TimeReport>;  // since/until: "2026-03-01", RFC 3339 or "7d" ago
+  saveTemplate(rootId: string, name: string, options?: { vars?: Record<string, string>; force?: boolean }): Promise<TemplateSummary>;  // vars turn literal values into {{name}} placeholders
+  applyTemplate(name: string, options?: { parentId?: string; vars?: Record<string, string> }): Promise<AppliedTemplate>;  // All-or-nothing; no parentId = new milestone
+  templates(): Promise<TemplateSummary[]>;
+  addRepo(path?: string, name?: string): Promise<Repo>;  // Register a repo; start/complete of its tasks run there
+  repos(): Promise<Repo[]>;
+  removeRepo(repo: string): Promise<Repo>;  // Refused while it has open tasks
+  tree(rootId?: string, labels?: string[]): Promise<TaskTree | TaskTree[]>;  // Returns single tree if rootId, array of all milestone trees if not
+  search(query: string): Promise<Task[]>;  // Full-text search over description/context/result, best match first
+  progress(rootId?: string, labels?: string[]): Promise<TaskProgress>;  // Aggregate counts for milestone or all tasks
 };
 
 // Learnings API (learnings are added via tasks.complete)
@@ -59,9 +59,13 @@ export interface InheritedLearnings {
 export type Priority = 0 | 1 | 2;
 
 /**
- * Task depth (0=milestone, 1=task, 2=subtask)
+ * Task depth: 0 for roots (milestones), +1 per level.
+ * The deepest allowed depth is set per database (`os db config --max-depth`).
  */
-export type Depth = 0 | 1 | 2;
+export type Depth = number;
+
+/** How tasks.complete brings a task branch into its start branch */
+export type IntegrationStrategy = "merge" | "squash" | "fast-forward" | "rebase";
 
 /**
  * Task returned from list/create/update/start/complete/reopen
@@ -84,6 +88,16 @@ export interface Task {
   blocks?: TaskId[];
   bookmark?: string;
   startCommit?: string;
+  /** Branch checked out when the task started; complete integrates into it */
+  startBranch?: string;
+  /** Start branch tip after complete integrated the task branch */
+  integrationCommit?: string;
+  /** Parent branch tip the task branch was created from (stacked) */
+  baseRev?: string;
+  /** Task branch tip at completion; baseRev..headRev is this level's diff */
+  headRev?: string;
+  /** Linked worktree the task branch is checked out in (start --worktree) */
+  worktree?: string;
   /** Computed: true if task or any ancestor has incomplete blockers */
   effectivelyBlocked: boolean;
   /** Task was cancelled (abandoned without completion) */
@@ -94,6 +108,140 @@ export interface Task {
   archived: boolean;
   /** Timestamp when task was archived */
   archivedAt: string | null;
+  /** Bumped on every write to the task; pass back as expectedRevision */
+  revision: number;
+  /** Manual sibling order within a priority, lower first (tasks.move) */
+  rank: number;
+  /** Due date; open tasks past it are overdue */
+  dueAt: string | null;
+  /** Hidden from next-ready (treated as blocked) until this time */
+  deferUntil: string | null;
+  /** Arbitrary JSON object attached via os task meta (omitted if none) */
+  metadata?: Record<string, unknown>;
+  /** Lowercase labels, sorted (omitted if none) */
+  labels?: string[];
+  /** Live claim on this task itself (omitted if none) */
+  claim?: TaskClaim;
+  /** Registered repository the task works in (omitted for the working directory's) */
+  repoId?: string;
+}
+
+/**
+ * Lease on a task and its subtree, held by one agent until expiresAt
+ */
+export interface TaskClaim {
+  taskId: TaskId;
+  agent: string;
+  claimedAt: string;
+  expiresAt: string;
+}
+
+/**
+ * One stretch of work on a task; endedAt is null while it is running
+ */
+export interface TaskSession {
+  id: number;
+  taskId: TaskId;
+  agent: string | null;
+  startedAt: string;
+  endedAt: string | null;
+}
+
+/**
+ * Time from work sessions within a window (os report time). Days are UTC.
+ */
+export interface TimeReport {
+  since: string | null;
+  until: string;
+  totalSeconds: number;
+  /** Most time first */
+  tasks: Array<{
+    taskId: TaskId;
+    description: string;
+    milestoneId: TaskId;
+    seconds: number;
+    sessions: number;
+    running: boolean;
+  }>;
+  /** Most time first */
+  milestones: Array<{ milestoneId: TaskId; description: string; seconds: number }>;
+  /** Oldest first, "YYYY-MM-DD" */
+  days: Array<{ date: string; seconds: number }>;
+}
+
+/**
+ * Saved template file under .overseer/templates (os template save/list)
+ */
+export interface TemplateSummary {
+  name: string;
+  path: string;
+  tasks: number;
+  /** {{placeholder}} names, sorted */
+  variables: string[];
+}
+
+/**
+ * Tasks created by os template apply, parents before children
+ */
+export interface AppliedTemplate {
+  name: string;
+  rootId: TaskId;
+  tasks: Task[];
+  blockers: number;
+}
+
+/**
+ * Repository registered with os repo add
+ */
+export interface Repo {
+  /** repo_...; the name works wherever an id does */
+  id: string;
+  /** Absolute path of the repository root */
+  path: string;
+  name: string;
+  vcsType: "git" | "none";
+  createdAt: string;
+}
+
+/**
+ * Deleted subtree waiting in the trash (os task trash list)
+ */
+export interface TrashEntry {
+  /** Root of the subtree; pass to tasks.restore */
+  id: TaskId;
+  parentId: TaskId | null;
+  description: string;
+  /** Tasks in the subtree, root included */
+  tasks: number;
+  deletedAt: string;
+}
+
+/**
+ * taskId is blocked by blockerId
+ */
+export interface BlockerEdge {
+  taskId: TaskId;
+  blockerId: TaskId;
+}
+
+/**
+ * Result of os task restore
+ */
+export interface RestoredTrash {
+  task: Task;
+  tasks: number;
+  /** Blocker edges rebuilt */
+  blockers: number;
+  /** Edges whose other task is gone or that are no longer valid */
+  skippedBlockers: BlockerEdge[];
+}
+
+/**
+ * Result of os task trash purge
+ */
+export interface PurgedTrash {
+  purged: TrashEntry[];
+  deletedBranches: string[];
 }
 
 /**
@@ -102,6 +250,35 @@ export interface Task {
 export interface TaskWithContext extends Task {
   context: TaskContext;
   learnings: InheritedLearnings;
+  /** Files, code ranges, URLs, commits and issues the task points at (omitted when none) */
+  links?: TaskLink[];
+  /** Recent comments, oldest first (only with --comments) */
+  comments?: Comment[];
+}
+
+export type LinkKind = "file" | "fileRange" | "url" | "commit" | "issue";
+
+/** Typed link from a task to a file, code range, URL, commit or issue */
+export interface TaskLink {
+  id: string;
+  taskId: TaskId;
+  kind: LinkKind;
+  /** Path relative to the repository root, URL, full commit id or issue reference */
+  target: string;
+  /** Inclusive line range, only for fileRange links */
+  lineStart: number | null;
+  lineEnd: number | null;
+  note: string | null;
+  createdAt: string;
+}
+
+/** Comment on a task's discussion thread */
+export interface Comment {
+  id: string;
+  taskId: TaskId;
+  author: string | null;
+  body: string;
+  createdAt: string;
 }
 
 export interface Learning {
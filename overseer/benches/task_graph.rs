@@ -0,0 +1,144 @@
+//! TaskGraph benchmarks over synthetic databases.
+//!
+//! Run with `cargo bench --bench task_graph`. Each database has 10 milestones,
+//! tasks under them and subtasks under every task, with about one in ten tasks
+//! blocked by an earlier milestone and a third of them completed.
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use rusqlite::{params, Connection};
+
+use overseer::core::{TaskGraph, TaskService};
+use overseer::db::schema;
+use overseer::id::TaskId;
+use overseer::types::ListTasksFilter;
+
+const SIZES: &[usize] = &[10_000, 100_000];
+const MILESTONES: usize = 10;
+const SUBTASKS_PER_TASK: usize = 4;
+
+fn seed(total: usize) -> Connection {
+    let conn = Connection::open_in_memory().unwrap();
+    schema::init_schema(&conn).unwrap();
+
+    let tx = conn.unchecked_transaction().unwrap();
+    {
+        let mut insert = tx
+            .prepare(
+                r#"
+                INSERT INTO tasks (id, parent_id, description, priority, completed, completed_at,
+                                   created_at, updated_at)
+                VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?7)
+                "#,
+            )
+            .unwrap();
+        let mut block = tx
+            .prepare("INSERT INTO task_blockers (task_id, blocker_id) VALUES (?1, ?2)")
+            .unwrap();
+
+        let created_at = "2025-01-01T00:00:00+00:00";
+        let milestones: Vec<TaskId> = (0..MILESTONES).map(|_| TaskId::new()).collect();
+        for (i, id) in milestones.iter().enumerate() {
+            insert
+                .execute(params![
+                    id,
+                    None::<TaskId>,
+                    format!("Milestone {i}"),
+                    (i % 3) as i32,
+                    0,
+                    None::<String>,
+                    created_at
+                ])
+                .unwrap();
+        }
+
+        let mut n = MILESTONES;
+        let mut task_index = 0;
+        while n < total {
+            let milestone = &milestones[task_index % MILESTONES];
+            let task = TaskId::new();
+            let completed = task_index % 3 == 0;
+            insert
+                .execute(params![
+                    &task,
+                    milestone,
+                    format!("Task {task_index}"),
+                    (task_index % 3) as i32,
+                    completed as i32,
+                    completed.then_some(created_at),
+                    created_at
+                ])
+                .unwrap();
+            n += 1;
+            if task_index % 10 == 5 && task_index % MILESTONES > 0 {
+                block
+                    .execute(params![&task, &milestones[task_index % MILESTONES - 1]])
+                    .unwrap();
+            }
+
+            for s in 0..SUBTASKS_PER_TASK.min(total - n) {
+                insert
+                    .execute(params![
+                        TaskId::new(),
+                        &task,
+                        format!("Subtask {task_index}.{s}"),
+                        (s % 3) as i32,
+                        completed as i32,
+                        completed.then_some(created_at),
+                        created_at
+                    ])
+                    .unwrap();
+                n += 1;
+            }
+            task_index += 1;
+        }
+    }
+    tx.commit().unwrap();
+    conn
+}
+
+fn bench_task_graph(c: &mut Criterion) {
+    let mut group = c.benchmark_group("task_graph");
+    group.sample_size(10);
+
+    for &size in SIZES {
+        let conn = seed(size);
+        let svc = TaskService::new(&conn);
+        let graph = TaskGraph::load(&conn).unwrap();
+        let milestone = graph.roots().next().unwrap().id.clone();
+
+        group.bench_with_input(BenchmarkId::new("load", size), &size, |b, _| {
+            b.iter(|| TaskGraph::load(&conn).unwrap())
+        });
+        group.bench_with_input(BenchmarkId::new("next_ready", size), &size, |b, _| {
+            b.iter(|| graph.next_ready(None).unwrap())
+        });
+        group.bench_with_input(
+            BenchmarkId::new("resolve_start_target", size),
+            &size,
+            |b, _| b.iter(|| graph.resolve_start_target(&milestone).unwrap()),
+        );
+        group.bench_with_input(BenchmarkId::new("list_ready", size), &size, |b, _| {
+            let filter = ListTasksFilter {
+                ready: true,
+                ..Default::default()
+            };
+            b.iter(|| graph.list(&filter, None).len())
+        });
+        // End to end, as `os task list --ready` runs it (load + filter + clone)
+        group.bench_with_input(
+            BenchmarkId::new("service_list_ready", size),
+            &size,
+            |b, _| {
+                let filter = ListTasksFilter {
+                    ready: true,
+                    ..Default::default()
+                };
+                b.iter(|| svc.list(&filter).unwrap().len())
+            },
+        );
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_task_graph);
+criterion_main!(benches);
@@ -1,12 +1,20 @@
-use clap::Subcommand;
+use clap::{Subcommand, ValueEnum};
 use rusqlite::Connection;
 use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
 use std::fs;
 use std::path::PathBuf;
 
-use crate::db::{learning_repo, task_repo, Learning};
-use crate::error::Result;
-use crate::id::TaskId;
+use crate::db::{
+    comment_repo, label_repo, learning_repo, link_repo, metadata_repo, settings_repo, task_repo,
+    Comment, Learning, TaskLink,
+};
+use crate::error::{OsError, Result};
+use crate::id::{CommentId, LearningId, LinkId, TaskId};
+use crate::types::Task;
+
+/// Current export format version. Imports accept any 1.x file.
+const EXPORT_VERSION: &str = "1.5.0";
 
 #[derive(Subcommand, Clone)]
 pub enum DataCommand {
@@ -16,6 +24,45 @@ pub enum DataCommand {
         #[arg(short, long)]
         output: Option<PathBuf>,
     },
+    /// Import tasks and learnings from a JSON file produced by `os data export`
+    #[command(
+        about = "Import tasks and learnings from an export file",
+        long_about = r#"
+Import tasks, learnings, and blocker relationships from an export file.
+
+Modes:
+  empty  Import into an empty database, preserving ids (default)
+  merge  Preserve ids, skipping tasks and learnings that already exist
+  remap  Assign fresh ids to every imported task and learning
+
+All references, depth limits and lifecycle invariants are validated before
+anything is written. The import runs in a single transaction.
+
+Examples:
+  os data import overseer-export.json
+  os data import backup.json --mode merge
+  os data import template.json --mode remap
+"#
+    )]
+    Import {
+        /// Export file to read
+        input: PathBuf,
+
+        /// How to treat ids that may collide with existing data
+        #[arg(long, value_enum, default_value_t = ImportMode::Empty)]
+        mode: ImportMode,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ImportMode {
+    /// Database must be empty; ids are preserved
+    Empty,
+    /// Ids are preserved; existing tasks and learnings are skipped
+    Merge,
+    /// Every task and learning gets a fresh id
+    Remap,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -27,6 +74,13 @@ pub struct ExportTask {
     pub context: String,
     pub result: Option<String>,
     pub priority: i32,
+    /// Sibling order within a priority (absent before 1.2: creation order)
+    #[serde(default)]
+    pub rank: i64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub due_at: Option<chrono::DateTime<chrono::Utc>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub defer_until: Option<chrono::DateTime<chrono::Utc>>,
     pub completed: bool,
     pub completed_at: Option<chrono::DateTime<chrono::Utc>>,
     pub cancelled: bool,
@@ -37,6 +91,10 @@ pub struct ExportTask {
     pub updated_at: chrono::DateTime<chrono::Utc>,
     pub started_at: Option<chrono::DateTime<chrono::Utc>>,
     pub commit_sha: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub metadata: Option<serde_json::Value>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub labels: Vec<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -47,6 +105,12 @@ pub struct ExportData {
     pub tasks: Vec<ExportTask>,
     pub learnings: Vec<Learning>,
     pub blockers: Vec<BlockerRelation>,
+    /// Absent before 1.4
+    #[serde(default)]
+    pub comments: Vec<Comment>,
+    /// Absent before 1.5
+    #[serde(default)]
+    pub links: Vec<TaskLink>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -62,11 +126,31 @@ pub enum DataResult {
         tasks: usize,
         learnings: usize,
     },
+    Imported(ImportSummary),
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportSummary {
+    pub path: String,
+    pub mode: ImportMode,
+    pub tasks: usize,
+    pub learnings: usize,
+    pub blockers: usize,
+    #[serde(default)]
+    pub comments: usize,
+    #[serde(default)]
+    pub links: usize,
+    /// Tasks already present in the database (merge mode only)
+    pub skipped_tasks: usize,
+    /// Learnings already present in the database (merge mode only)
+    pub skipped_learnings: usize,
 }
 
 pub fn handle(conn: &Connection, cmd: DataCommand) -> Result<DataResult> {
     match cmd {
         DataCommand::Export { output } => export_data(conn, output),
+        DataCommand::Import { input, mode } => import_data(conn, input, mode),
     }
 }
 
@@ -94,6 +178,9 @@ pub(crate) fn export_data(conn: &Connection, output: Option<PathBuf>) -> Result<
                     context: full_task.context,
                     result: full_task.result,
                     priority: full_task.priority,
+                    rank: full_task.rank,
+                    due_at: full_task.due_at,
+                    defer_until: full_task.defer_until,
                     completed: full_task.completed,
                     completed_at: full_task.completed_at,
                     cancelled: full_task.cancelled,
@@ -104,6 +191,8 @@ pub(crate) fn export_data(conn: &Connection, output: Option<PathBuf>) -> Result<
                     updated_at: full_task.updated_at,
                     started_at: full_task.started_at,
                     commit_sha: full_task.commit_sha,
+                    metadata: full_task.metadata,
+                    labels: full_task.labels,
                 })
         })
         .collect();
@@ -129,11 +218,13 @@ pub(crate) fn export_data(conn: &Connection, output: Option<PathBuf>) -> Result<
     }
 
     let export = ExportData {
-        version: "1.1.0".to_string(),
+        version: EXPORT_VERSION.to_string(),
         exported_at: chrono::Utc::now().to_rfc3339(),
         tasks: export_tasks.clone(),
         learnings: all_learnings.clone(),
         blockers,
+        comments: comment_repo::list_all_comments(conn)?,
+        links: link_repo::list_all_links(conn)?,
     };
 
     let json = serde_json::to_string_pretty(&export)?;
@@ -146,10 +237,432 @@ pub(crate) fn export_data(conn: &Connection, output: Option<PathBuf>) -> Result<
     })
 }
 
+pub(crate) fn import_data(
+    conn: &Connection,
+    input: PathBuf,
+    mode: ImportMode,
+) -> Result<DataResult> {
+    let content = fs::read_to_string(&input)?;
+    let data: ExportData = serde_json::from_str(&content)?;
+
+    if !data.version.starts_with("1.") {
+        return Err(OsError::InvalidImport(format!(
+            "unsupported export version {} (expected 1.x)",
+            data.version
+        )));
+    }
+
+    if mode == ImportMode::Empty {
+        let count: i64 = conn.query_row("SELECT COUNT(*) FROM tasks", [], |row| row.get(0))?;
+        if count > 0 {
+            return Err(OsError::InvalidImport(
+                "database is not empty - use --mode merge or --mode remap".to_string(),
+            ));
+        }
+    }
+
+    let plan = plan_import(conn, &data, mode)?;
+
+    // Single transaction: either everything lands or nothing does
+    let tx = conn.unchecked_transaction()?;
+    for task in &plan.tasks {
+        task_repo::insert_task_row(&tx, task)?;
+        if let Some(metadata) = &task.metadata {
+            metadata_repo::replace_metadata(&tx, &task.id, metadata)?;
+        }
+        label_repo::add_labels(&tx, &task.id, &task.labels)?;
+    }
+    let mut learnings = 0;
+    for learning in &plan.learnings {
+        if learning_repo::insert_learning_row(&tx, learning)? {
+            learnings += 1;
+        }
+    }
+    let mut blockers = 0;
+    for (task_id, blocker_id) in &plan.blockers {
+        if task_repo::add_blocker(&tx, task_id, blocker_id)? {
+            blockers += 1;
+        }
+    }
+    let mut comments = 0;
+    for comment in &plan.comments {
+        if comment_repo::insert_comment_row(&tx, comment)? {
+            comments += 1;
+        }
+    }
+    let mut links = 0;
+    for link in &plan.links {
+        if link_repo::insert_link_row(&tx, link)? {
+            links += 1;
+        }
+    }
+    tx.commit()?;
+
+    Ok(DataResult::Imported(ImportSummary {
+        path: input.display().to_string(),
+        mode,
+        tasks: plan.tasks.len(),
+        learnings,
+        blockers,
+        comments,
+        links,
+        skipped_tasks: plan.skipped_tasks,
+        skipped_learnings: plan.skipped_learnings,
+    }))
+}
+
+/// Validated set of rows to write, with ids already remapped and tasks
+/// ordered parents-first.
+struct ImportPlan {
+    tasks: Vec<Task>,
+    learnings: Vec<Learning>,
+    blockers: Vec<(TaskId, TaskId)>,
+    comments: Vec<Comment>,
+    links: Vec<TaskLink>,
+    skipped_tasks: usize,
+    skipped_learnings: usize,
+}
+
+fn plan_import(conn: &Connection, data: &ExportData, mode: ImportMode) -> Result<ImportPlan> {
+    let invalid = |msg: String| OsError::InvalidImport(msg);
+
+    // Existing hierarchy and blocker edges, loaded once for validation
+    let mut parents: HashMap<TaskId, Option<TaskId>> = HashMap::new();
+    {
+        let mut stmt = conn.prepare("SELECT id, parent_id FROM tasks")?;
+        let rows = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?;
+        for row in rows {
+            let (id, parent): (TaskId, Option<TaskId>) = row?;
+            parents.insert(id, parent);
+        }
+    }
+    let existing: HashSet<TaskId> = parents.keys().cloned().collect();
+
+    let mut file_ids = HashSet::new();
+    for task in &data.tasks {
+        if !file_ids.insert(task.id.clone()) {
+            return Err(invalid(format!("duplicate task id {}", task.id)));
+        }
+    }
+
+    // Decide the id each file task will have in the database
+    let id_map: HashMap<TaskId, TaskId> = data
+        .tasks
+        .iter()
+        .map(|t| {
+            let new_id = match mode {
+                ImportMode::Remap => TaskId::new(),
+                ImportMode::Empty | ImportMode::Merge => t.id.clone(),
+            };
+            (t.id.clone(), new_id)
+        })
+        .collect();
+    let resolve = |id: &TaskId| -> Option<TaskId> {
+        id_map
+            .get(id)
+            .cloned()
+            .or_else(|| existing.contains(id).then(|| id.clone()))
+    };
+
+    // Imported siblings keep their relative order but go after existing children
+    let mut rank_offsets: HashMap<Option<TaskId>, i64> = HashMap::new();
+
+    let mut tasks = Vec::new();
+    let mut skipped_tasks = 0;
+    for export_task in &data.tasks {
+        if mode == ImportMode::Merge && existing.contains(&export_task.id) {
+            skipped_tasks += 1;
+            continue;
+        }
+
+        let parent_id = match &export_task.parent_id {
+            Some(pid) => Some(resolve(pid).ok_or_else(|| {
+                invalid(format!("task {}: parent {} not found", export_task.id, pid))
+            })?),
+            None => None,
+        };
+
+        if !(0..=2).contains(&export_task.priority) {
+            return Err(invalid(format!(
+                "task {}: invalid priority {}",
+                export_task.id, export_task.priority
+            )));
+        }
+        if export_task
+            .metadata
+            .as_ref()
+            .is_some_and(|m| !m.is_object())
+        {
+            return Err(invalid(format!(
+                "task {}: metadata must be a JSON object",
+                export_task.id
+            )));
+        }
+
+        let labels = label_repo::normalize_labels(&export_task.labels)
+            .map_err(|e| invalid(format!("task {}: {}", export_task.id, e)))?;
+
+        let offset = match rank_offsets.get(&parent_id) {
+            Some(&offset) => offset,
+            None => {
+                let offset = task_repo::next_rank(conn, parent_id.as_ref())?;
+                rank_offsets.insert(parent_id.clone(), offset);
+                offset
+            }
+        };
+
+        let mut task = export_task_to_task(export_task, id_map[&export_task.id].clone(), parent_id);
+        task.rank += offset;
+        task.labels = labels;
+        task.validate_lifecycle_invariants()
+            .map_err(|e| invalid(format!("task {}: {}", export_task.id, e)))?;
+
+        parents.insert(task.id.clone(), task.parent_id.clone());
+        tasks.push(task);
+    }
+
+    // Depth (and parent cycles) over the combined hierarchy
+    let settings = settings_repo::load(conn)?;
+    let mut depths: HashMap<TaskId, i32> = HashMap::new();
+    for task in &tasks {
+        let mut depth = 0;
+        let mut seen = HashSet::from([task.id.clone()]);
+        let mut current = task.parent_id.clone();
+        while let Some(pid) = current {
+            if !seen.insert(pid.clone()) {
+                return Err(invalid(format!("task {}: cycle in parent chain", task.id)));
+            }
+            depth += 1;
+            current = parents.get(&pid).cloned().flatten();
+        }
+        if depth > settings.deepest() {
+            return Err(invalid(format!(
+                "task {}: depth {} exceeds maximum of {} ({} levels)",
+                task.id,
+                depth,
+                settings.deepest(),
+                settings.max_depth
+            )));
+        }
+        depths.insert(task.id.clone(), depth);
+    }
+    // Parents must be inserted before children (FK on parent_id)
+    tasks.sort_by_key(|t| depths[&t.id]);
+
+    let is_ancestor = |ancestor: &TaskId, id: &TaskId| -> bool {
+        let mut current = parents.get(id).cloned().flatten();
+        while let Some(pid) = current {
+            if &pid == ancestor {
+                return true;
+            }
+            current = parents.get(&pid).cloned().flatten();
+        }
+        false
+    };
+
+    let mut blocker_edges: HashMap<TaskId, Vec<TaskId>> = HashMap::new();
+    {
+        let mut stmt = conn.prepare("SELECT task_id, blocker_id FROM task_blockers")?;
+        let rows = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?;
+        for row in rows {
+            let (task_id, blocker_id): (TaskId, TaskId) = row?;
+            blocker_edges.entry(task_id).or_default().push(blocker_id);
+        }
+    }
+
+    let mut blockers = Vec::new();
+    for relation in &data.blockers {
+        let task_id = resolve(&relation.task_id).ok_or_else(|| {
+            invalid(format!(
+                "blocker relation: task {} not found",
+                relation.task_id
+            ))
+        })?;
+        let blocker_id = resolve(&relation.blocker_id).ok_or_else(|| {
+            invalid(format!(
+                "task {}: blocker {} not found",
+                relation.task_id, relation.blocker_id
+            ))
+        })?;
+        if task_id == blocker_id {
+            return Err(invalid(format!(
+                "task {}: blocked by itself",
+                relation.task_id
+            )));
+        }
+        if is_ancestor(&blocker_id, &task_id) || is_ancestor(&task_id, &blocker_id) {
+            return Err(invalid(format!(
+                "task {}: blocker {} is an ancestor or descendant",
+                relation.task_id, relation.blocker_id
+            )));
+        }
+        blocker_edges
+            .entry(task_id.clone())
+            .or_default()
+            .push(blocker_id.clone());
+        blockers.push((task_id, blocker_id));
+    }
+
+    if let Some(task_id) = find_blocker_cycle(&blocker_edges) {
+        return Err(invalid(format!("task {}: cycle in blocker chain", task_id)));
+    }
+
+    let mut learnings = Vec::new();
+    let mut skipped_learnings = 0;
+    for learning in &data.learnings {
+        if mode == ImportMode::Merge && learning_repo::get_learning(conn, &learning.id)?.is_some() {
+            skipped_learnings += 1;
+            continue;
+        }
+        let task_id = resolve(&learning.task_id).ok_or_else(|| {
+            invalid(format!(
+                "learning {}: task {} not found",
+                learning.id, learning.task_id
+            ))
+        })?;
+        let source_task_id = learning
+            .source_task_id
+            .as_ref()
+            .map(|src| id_map.get(src).cloned().unwrap_or_else(|| src.clone()));
+        learnings.push(Learning {
+            id: match mode {
+                ImportMode::Remap => LearningId::new(),
+                ImportMode::Empty | ImportMode::Merge => learning.id.clone(),
+            },
+            task_id,
+            content: learning.content.clone(),
+            source_task_id,
+            created_at: learning.created_at,
+        });
+    }
+
+    // Merge mode skips comments that exist already (insert_comment_row ignores them)
+    let mut comments = Vec::new();
+    for comment in &data.comments {
+        let task_id = resolve(&comment.task_id).ok_or_else(|| {
+            invalid(format!(
+                "comment {}: task {} not found",
+                comment.id, comment.task_id
+            ))
+        })?;
+        comments.push(Comment {
+            id: match mode {
+                ImportMode::Remap => CommentId::new(),
+                ImportMode::Empty | ImportMode::Merge => comment.id.clone(),
+            },
+            task_id,
+            ..comment.clone()
+        });
+    }
+
+    // Links are imported as recorded; they are not re-validated against this repository
+    let mut links = Vec::new();
+    for link in &data.links {
+        let task_id = resolve(&link.task_id)
+            .ok_or_else(|| invalid(format!("link {}: task {} not found", link.id, link.task_id)))?;
+        links.push(TaskLink {
+            id: match mode {
+                ImportMode::Remap => LinkId::new(),
+                ImportMode::Empty | ImportMode::Merge => link.id.clone(),
+            },
+            task_id,
+            ..link.clone()
+        });
+    }
+
+    Ok(ImportPlan {
+        tasks,
+        learnings,
+        blockers,
+        comments,
+        links,
+        skipped_tasks,
+        skipped_learnings,
+    })
+}
+
+/// Returns a task on a blocker cycle, if any.
+fn find_blocker_cycle(edges: &HashMap<TaskId, Vec<TaskId>>) -> Option<TaskId> {
+    // 0 = unvisited, 1 = on stack, 2 = done
+    let mut state: HashMap<&TaskId, u8> = HashMap::new();
+
+    for start in edges.keys() {
+        if state.get(start).copied().unwrap_or(0) != 0 {
+            continue;
+        }
+        let mut stack = vec![(start, 0usize)];
+        state.insert(start, 1);
+        while let Some((node, idx)) = stack.pop() {
+            let next = edges.get(node).and_then(|n| n.get(idx));
+            match next {
+                Some(next) => {
+                    stack.push((node, idx + 1));
+                    match state.get(next).copied().unwrap_or(0) {
+                        0 => {
+                            state.insert(next, 1);
+                            stack.push((next, 0));
+                        }
+                        1 => return Some(next.clone()),
+                        _ => {}
+                    }
+                }
+                None => {
+                    state.insert(node, 2);
+                }
+            }
+        }
+    }
+
+    None
+}
+
+fn export_task_to_task(t: &ExportTask, id: TaskId, parent_id: Option<TaskId>) -> Task {
+    Task {
+        id,
+        parent_id,
+        description: t.description.clone(),
+        context: t.context.clone(),
+        context_chain: None,
+        learnings: None,
+        result: t.result.clone(),
+        priority: t.priority,
+        completed: t.completed,
+        completed_at: t.completed_at,
+        created_at: t.created_at,
+        updated_at: t.updated_at,
+        started_at: t.started_at,
+        commit_sha: t.commit_sha.clone(),
+        bookmark: None,
+        start_commit: None,
+        repo_id: None,
+        start_branch: None,
+        integration_commit: None,
+        base_rev: None,
+        head_rev: None,
+        worktree: None,
+        depth: None,
+        blocked_by: Vec::new(),
+        blocks: Vec::new(),
+        effectively_blocked: false,
+        cancelled: t.cancelled,
+        cancelled_at: t.cancelled_at,
+        archived: t.archived,
+        archived_at: t.archived_at,
+        revision: 0,
+        rank: t.rank,
+        due_at: t.due_at,
+        defer_until: t.defer_until,
+        metadata: t.metadata.clone(),
+        labels: Vec::new(), // Normalized by plan_import
+        claim: None,
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::core::task_service::TaskService;
+    use crate::db::link_repo::LinkKind;
     use crate::db::{self, learning_repo};
     use tempfile::TempDir;
 
@@ -175,6 +688,7 @@ mod tests {
                 assert_eq!(tasks, 0);
                 assert_eq!(learnings, 0);
             }
+            DataResult::Imported(_) => panic!("Expected Exported result"),
         }
 
         // Verify file exists
@@ -183,10 +697,12 @@ mod tests {
         // Verify content
         let content = fs::read_to_string(&output_path).unwrap();
         let export: ExportData = serde_json::from_str(&content).unwrap();
-        assert_eq!(export.version, "1.1.0");
+        assert_eq!(export.version, "1.5.0");
         assert_eq!(export.tasks.len(), 0);
         assert_eq!(export.learnings.len(), 0);
         assert_eq!(export.blockers.len(), 0);
+        assert_eq!(export.comments.len(), 0);
+        assert_eq!(export.links.len(), 0);
     }
 
     #[test]
@@ -202,6 +718,10 @@ mod tests {
                 parent_id: None,
                 priority: Some(0),
                 blocked_by: vec![],
+                labels: vec![],
+                due_at: None,
+                defer_until: None,
+                repo_id: None,
             })
             .unwrap();
 
@@ -212,6 +732,10 @@ mod tests {
                 parent_id: Some(task1.id.clone()),
                 priority: Some(1),
                 blocked_by: vec![],
+                labels: vec![],
+                due_at: None,
+                defer_until: None,
+                repo_id: None,
             })
             .unwrap();
 
@@ -228,6 +752,7 @@ mod tests {
                 assert_eq!(tasks, 2);
                 assert_eq!(learnings, 1);
             }
+            DataResult::Imported(_) => panic!("Expected Exported result"),
         }
     }
 
@@ -243,6 +768,10 @@ mod tests {
                 parent_id: None,
                 priority: None,
                 blocked_by: vec![],
+                labels: vec![],
+                due_at: None,
+                defer_until: None,
+                repo_id: None,
             })
             .unwrap();
 
@@ -253,6 +782,10 @@ mod tests {
                 parent_id: None,
                 priority: None,
                 blocked_by: vec![task1.id.clone()],
+                labels: vec![],
+                due_at: None,
+                defer_until: None,
+                repo_id: None,
             })
             .unwrap();
 
@@ -267,4 +800,260 @@ mod tests {
         assert_eq!(export.blockers[0].task_id, task2.id);
         assert_eq!(export.blockers[0].blocker_id, task1.id);
     }
+
+    fn import_summary(result: DataResult) -> ImportSummary {
+        match result {
+            DataResult::Imported(summary) => summary,
+            DataResult::Exported { .. } => panic!("Expected Imported result"),
+        }
+    }
+
+    /// Milestone -> task (with learning), plus a second milestone blocked by the first
+    fn seed(conn: &rusqlite::Connection) -> (TaskId, TaskId, TaskId) {
+        let task_service = TaskService::new(conn);
+        let milestone = task_service
+            .create(&crate::types::CreateTaskInput {
+                description: "Milestone".to_string(),
+                ..Default::default()
+            })
+            .unwrap();
+        let child = task_service
+            .create(&crate::types::CreateTaskInput {
+                description: "Child".to_string(),
+                parent_id: Some(milestone.id.clone()),
+                labels: vec!["backend".to_string()],
+                ..Default::default()
+            })
+            .unwrap();
+        let blocked = task_service
+            .create(&crate::types::CreateTaskInput {
+                description: "Blocked".to_string(),
+                blocked_by: vec![milestone.id.clone()],
+                ..Default::default()
+            })
+            .unwrap();
+        task_service
+            .set_metadata(&child.id, "area", &serde_json::json!("auth"))
+            .unwrap();
+        task_service.complete(&child.id, Some("done")).unwrap();
+        learning_repo::add_learning(conn, &child.id, "Learning", None).unwrap();
+        comment_repo::add_comment(conn, &child.id, Some("alice"), "Tried X").unwrap();
+        link_repo::add_link(
+            conn,
+            &child.id,
+            LinkKind::FileRange,
+            "src/auth.rs",
+            Some((10, 40)),
+            None,
+        )
+        .unwrap();
+        (milestone.id, child.id, blocked.id)
+    }
+
+    #[test]
+    fn test_import_round_trip_into_empty_database() {
+        let (conn, tmp_dir) = setup_test_db();
+        let (milestone, child, blocked) = seed(&conn);
+        let export_path = tmp_dir.path().join("export.json");
+        export_data(&conn, Some(export_path.clone())).unwrap();
+
+        let target_path = tmp_dir.path().join("target.db");
+        let target = db::open_db(&target_path).unwrap();
+        let summary = import_summary(import_data(&target, export_path, ImportMode::Empty).unwrap());
+        assert_eq!(summary.tasks, 3);
+        assert_eq!(summary.learnings, 1);
+        assert_eq!(summary.blockers, 1);
+        assert_eq!(summary.comments, 1);
+        assert_eq!(summary.links, 1);
+
+        let imported_child = task_repo::get_task(&target, &child).unwrap().unwrap();
+        assert_eq!(imported_child.parent_id, Some(milestone.clone()));
+        assert!(imported_child.completed);
+        assert_eq!(imported_child.result, Some("done".to_string()));
+        assert_eq!(
+            imported_child.metadata,
+            Some(serde_json::json!({"area": "auth"}))
+        );
+        assert_eq!(imported_child.labels, vec!["backend"]);
+        let imported_blocked = task_repo::get_task(&target, &blocked).unwrap().unwrap();
+        assert_eq!(imported_blocked.blocked_by, vec![milestone]);
+        assert_eq!(
+            learning_repo::list_learnings(&target, &child)
+                .unwrap()
+                .len(),
+            1
+        );
+        let comments = comment_repo::list_comments(&target, &child, None).unwrap();
+        assert_eq!(comments[0].author.as_deref(), Some("alice"));
+        let links = link_repo::list_links(&target, &child).unwrap();
+        assert_eq!(links[0].line_start, Some(10));
+    }
+
+    #[test]
+    fn test_import_keeps_sibling_order_after_existing_tasks() {
+        let (conn, tmp_dir) = setup_test_db();
+        let (milestone, _, blocked) = seed(&conn);
+        TaskService::new(&conn)
+            .move_task(&blocked, &crate::types::MovePosition::First)
+            .unwrap();
+        let export_path = tmp_dir.path().join("export.json");
+        export_data(&conn, Some(export_path.clone())).unwrap();
+
+        import_data(&conn, export_path, ImportMode::Remap).unwrap();
+
+        let roots = task_repo::get_sibling_ids_by_rank(&conn, None).unwrap();
+        let descriptions: Vec<String> = roots
+            .iter()
+            .map(|id| task_repo::get_task(&conn, id).unwrap().unwrap().description)
+            .collect();
+        assert_eq!(
+            descriptions,
+            vec!["Blocked", "Milestone", "Blocked", "Milestone"]
+        );
+        assert_eq!(&roots[..2], &[blocked, milestone]);
+    }
+
+    #[test]
+    fn test_import_empty_mode_rejects_non_empty_database() {
+        let (conn, tmp_dir) = setup_test_db();
+        seed(&conn);
+        let export_path = tmp_dir.path().join("export.json");
+        export_data(&conn, Some(export_path.clone())).unwrap();
+
+        let result = import_data(&conn, export_path, ImportMode::Empty);
+        assert!(matches!(result, Err(OsError::InvalidImport(_))));
+    }
+
+    #[test]
+    fn test_import_merge_skips_existing_ids() {
+        let (conn, tmp_dir) = setup_test_db();
+        seed(&conn);
+        let export_path = tmp_dir.path().join("export.json");
+        export_data(&conn, Some(export_path.clone())).unwrap();
+
+        let summary = import_summary(import_data(&conn, export_path, ImportMode::Merge).unwrap());
+        assert_eq!(summary.tasks, 0);
+        assert_eq!(summary.skipped_tasks, 3);
+        assert_eq!(summary.skipped_learnings, 1);
+        assert_eq!(summary.blockers, 0);
+        assert_eq!(summary.comments, 0);
+        assert_eq!(summary.links, 0);
+
+        let count: i64 = conn
+            .query_row("SELECT COUNT(*) FROM tasks", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(count, 3);
+    }
+
+    #[test]
+    fn test_import_remap_assigns_fresh_ids() {
+        let (conn, tmp_dir) = setup_test_db();
+        let (milestone, _, _) = seed(&conn);
+        let export_path = tmp_dir.path().join("export.json");
+        export_data(&conn, Some(export_path.clone())).unwrap();
+
+        let summary = import_summary(import_data(&conn, export_path, ImportMode::Remap).unwrap());
+        assert_eq!(summary.tasks, 3);
+        assert_eq!(summary.learnings, 1);
+        assert_eq!(summary.comments, 1);
+        assert_eq!(summary.links, 1);
+
+        let count: i64 = conn
+            .query_row("SELECT COUNT(*) FROM tasks", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(count, 6);
+
+        // The remapped child hangs off the remapped milestone, not the original
+        let children: i64 = conn
+            .query_row(
+                "SELECT COUNT(*) FROM tasks WHERE parent_id = ?1",
+                [&milestone],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(children, 1);
+    }
+
+    #[test]
+    fn test_import_rejects_dangling_blocker() {
+        let (conn, tmp_dir) = setup_test_db();
+        seed(&conn);
+        let export_path = tmp_dir.path().join("export.json");
+        export_data(&conn, Some(export_path.clone())).unwrap();
+
+        // Point the blocker relation at a task that does not exist
+        let mut export: ExportData =
+            serde_json::from_str(&fs::read_to_string(&export_path).unwrap()).unwrap();
+        export.blockers[0].blocker_id = TaskId::new();
+        fs::write(&export_path, serde_json::to_string(&export).unwrap()).unwrap();
+
+        let target = db::open_db(&tmp_dir.path().join("target.db")).unwrap();
+        let result = import_data(&target, export_path, ImportMode::Empty);
+        assert!(matches!(result, Err(OsError::InvalidImport(_))));
+    }
+
+    #[test]
+    fn test_import_failure_midway_writes_nothing() {
+        let (conn, tmp_dir) = setup_test_db();
+        seed(&conn);
+        let export_path = tmp_dir.path().join("export.json");
+        export_data(&conn, Some(export_path.clone())).unwrap();
+
+        // Links are written last: make that insert fail once everything else is in
+        let target = db::open_db(&tmp_dir.path().join("target.db")).unwrap();
+        target
+            .execute_batch(
+                "CREATE TEMP TRIGGER fail_links BEFORE INSERT ON main.task_links
+                 BEGIN SELECT RAISE(ABORT, 'link insert failed'); END;",
+            )
+            .unwrap();
+        let result = import_data(&target, export_path, ImportMode::Empty);
+        assert!(matches!(result, Err(OsError::Database(_))));
+
+        for table in [
+            "tasks",
+            "task_metadata",
+            "learnings",
+            "task_blockers",
+            "task_comments",
+            "task_links",
+        ] {
+            let count: i64 = target
+                .query_row(&format!("SELECT COUNT(*) FROM {}", table), [], |row| {
+                    row.get(0)
+                })
+                .unwrap();
+            assert_eq!(count, 0, "{} rows left behind", table);
+        }
+    }
+
+    #[test]
+    fn test_import_rejects_depth_over_limit() {
+        let (conn, tmp_dir) = setup_test_db();
+        seed(&conn);
+        let export_path = tmp_dir.path().join("export.json");
+        export_data(&conn, Some(export_path.clone())).unwrap();
+
+        // Chain the tasks four levels deep by appending descendants under the child
+        let mut export: ExportData =
+            serde_json::from_str(&fs::read_to_string(&export_path).unwrap()).unwrap();
+        let mut parent = export
+            .tasks
+            .iter()
+            .find(|t| t.parent_id.is_some())
+            .unwrap()
+            .clone();
+        for _ in 0..2 {
+            let mut deeper = parent.clone();
+            deeper.id = TaskId::new();
+            deeper.parent_id = Some(parent.id.clone());
+            export.tasks.push(deeper.clone());
+            parent = deeper;
+        }
+        fs::write(&export_path, serde_json::to_string(&export).unwrap()).unwrap();
+
+        let target = db::open_db(&tmp_dir.path().join("target.db")).unwrap();
+        let result = import_data(&target, export_path, ImportMode::Empty);
+        assert!(matches!(result, Err(OsError::InvalidImport(msg)) if msg.contains("depth")));
+    }
 }
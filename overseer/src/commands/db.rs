@@ -0,0 +1,442 @@
+use std::path::{Path, PathBuf};
+
+use clap::{Args, Subcommand};
+use rusqlite::Connection;
+use serde::{Deserialize, Serialize};
+
+use crate::core::integrity::{self, Issue};
+use crate::db::{
+    self, retry,
+    schema::{self, MigrationInfo, SCHEMA_VERSION},
+    settings_repo::{self, Settings},
+    task_repo,
+};
+use crate::error::{OsError, Result};
+use crate::vcs::{self, IntegrationStrategy, VcsBackend};
+
+#[derive(Subcommand, Clone)]
+pub enum DbCommand {
+    /// Show schema version and pending migrations (does not migrate)
+    Status,
+    /// Apply pending schema migrations
+    #[command(
+        about = "Apply pending schema migrations",
+        long_about = r#"
+Bring the database schema up to the version this binary expects.
+
+Every command already migrates on open; this makes it explicit. Existing
+databases are copied to <db>.v<N>-<timestamp>.bak before upgrading.
+
+With --dry-run, pending migrations are applied inside a transaction that is
+rolled back, so failures surface without touching the database.
+
+Examples:
+  os db migrate --dry-run
+  os db migrate
+"#
+    )]
+    Migrate(MigrateArgs),
+    /// Check lifecycle, graph and VCS invariants
+    #[command(
+        about = "Check lifecycle, graph and VCS invariants",
+        long_about = r#"
+Check the database (and the repository, when run inside one) for problems that
+the normal write paths prevent but older versions, imports or manual edits can
+leave behind: lifecycle flags without timestamps, blocker or parent cycles,
+tasks deeper than `os db config --max-depth` allows, archived tasks with active children, task branches
+that no longer exist, and SQLite corruption.
+
+Each problem has a stable code (e.g. graph.blockerCycle). With --fix, safe
+repairs are applied in one transaction:
+  - backfill missing completed_at/cancelled_at/archived_at from updated_at
+  - remove blocker rows that reference deleted tasks
+  - clear a task's branch when it no longer exists in the repository
+
+Examples:
+  os db check
+  os db check --fix --json
+"#
+    )]
+    Check(CheckArgs),
+    /// Show or change per-database settings
+    #[command(
+        about = "Show or change per-database settings",
+        long_about = r#"
+Show the settings stored in this database, or change them.
+
+--max-depth sets how many levels the task hierarchy may have (default 3:
+milestone, task, subtask). Roots are always milestones; deeper levels are
+plain tasks. The limit cannot be lowered below the depth existing tasks use.
+
+--integration sets how `os task complete` brings a task's branch into the
+branch it was started from (default merge):
+  merge         merge commit, even when a fast-forward would do
+  squash        the branch's changes as a single commit
+  fast-forward  only if the base branch has not moved on
+  rebase        replay the task's commits on the base branch
+
+--worktree-dir sets where `os task start --worktree` creates worktrees
+(default: a `<repo>.worktrees` directory next to the repository). Relative
+paths are taken from the repository root.
+
+Examples:
+  os db config
+  os db config --max-depth 5
+  os db config --integration squash
+  os db config --worktree-dir ../worktrees
+"#
+    )]
+    Config(ConfigArgs),
+}
+
+#[derive(Args, Clone)]
+pub struct MigrateArgs {
+    /// Show (and test-apply) pending migrations without changing the database
+    #[arg(long)]
+    pub dry_run: bool,
+}
+
+#[derive(Args, Clone)]
+pub struct CheckArgs {
+    /// Apply safe repairs for fixable problems
+    #[arg(long)]
+    pub fix: bool,
+}
+
+#[derive(Args, Clone)]
+pub struct ConfigArgs {
+    /// Number of hierarchy levels allowed (1-16)
+    #[arg(long)]
+    pub max_depth: Option<i32>,
+
+    /// Default way completed task branches are integrated
+    #[arg(long, value_enum)]
+    pub integration: Option<IntegrationStrategy>,
+
+    /// Directory for `os task start --worktree` (relative to the repository root)
+    #[arg(long, conflicts_with = "clear_worktree_dir")]
+    pub worktree_dir: Option<String>,
+
+    /// Go back to the default worktree directory (`<repo>.worktrees` beside the repository)
+    #[arg(long)]
+    pub clear_worktree_dir: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum SchemaState {
+    /// No database file yet
+    Missing,
+    UpToDate,
+    /// Older schema; migrations pending
+    Outdated,
+    /// Created by a newer binary; this one refuses to open it
+    TooNew,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DbStatus {
+    pub path: PathBuf,
+    pub state: SchemaState,
+    pub current_version: i32,
+    pub latest_version: i32,
+    pub pending: Vec<MigrationInfo>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MigrateResult {
+    pub path: PathBuf,
+    pub dry_run: bool,
+    pub from_version: i32,
+    pub to_version: i32,
+    /// Applied migrations, or with --dry-run the ones that would be applied
+    pub migrations: Vec<MigrationInfo>,
+    pub backup: Option<PathBuf>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CheckResult {
+    /// True when no unfixed problems remain
+    pub ok: bool,
+    /// False when not run inside a repository (branch checks skipped)
+    pub vcs_checked: bool,
+    pub issues: Vec<Issue>,
+    pub fixed: usize,
+}
+
+pub enum DbResult {
+    Status(DbStatus),
+    Migrate(MigrateResult),
+    Check(CheckResult),
+    Config(Settings),
+}
+
+pub fn handle(db_path: &Path, cmd: DbCommand) -> Result<DbResult> {
+    match cmd {
+        DbCommand::Status => Ok(DbResult::Status(status(db_path)?)),
+        DbCommand::Migrate(args) if args.dry_run => {
+            // Never create the file for a preview; test against an empty in-memory DB instead
+            let conn = if db_path.exists() {
+                schema::open_db_unmigrated(db_path)?
+            } else {
+                Connection::open_in_memory()?
+            };
+            let from_version = schema::current_version(&conn)?;
+            let migrations = schema::dry_run(&conn)?;
+            Ok(DbResult::Migrate(MigrateResult {
+                path: db_path.to_path_buf(),
+                dry_run: true,
+                from_version,
+                to_version: migrations.last().map_or(from_version, |m| m.version),
+                migrations,
+                backup: None,
+            }))
+        }
+        DbCommand::Migrate(_) => {
+            let (_, report) = schema::open_and_migrate(db_path)?;
+            Ok(DbResult::Migrate(MigrateResult {
+                path: db_path.to_path_buf(),
+                dry_run: false,
+                from_version: report.from_version,
+                to_version: report.to_version,
+                migrations: report.applied,
+                backup: report.backup,
+            }))
+        }
+        DbCommand::Check(args) => {
+            let conn = db::open_db(db_path)?;
+            let vcs = vcs::get_main_backend(&std::env::current_dir()?).ok();
+            Ok(DbResult::Check(check(&conn, vcs.as_deref(), args.fix)?))
+        }
+        DbCommand::Config(args) => {
+            let conn = db::open_db(db_path)?;
+            if let Some(max_depth) = args.max_depth {
+                set_max_depth(&conn, max_depth)?;
+            }
+            if let Some(strategy) = args.integration {
+                settings_repo::set_integration(&conn, strategy)?;
+            }
+            if let Some(ref dir) = args.worktree_dir {
+                settings_repo::set_worktree_dir(&conn, Some(dir))?;
+            } else if args.clear_worktree_dir {
+                settings_repo::set_worktree_dir(&conn, None)?;
+            }
+            Ok(DbResult::Config(settings_repo::load(&conn)?))
+        }
+    }
+}
+
+pub fn check(conn: &Connection, vcs: Option<&dyn VcsBackend>, fix: bool) -> Result<CheckResult> {
+    let mut issues = integrity::check(conn, vcs)?;
+    let fixed = if fix {
+        integrity::repair(conn, &mut issues)?
+    } else {
+        0
+    };
+    Ok(CheckResult {
+        ok: issues.iter().all(|i| i.fixed),
+        vcs_checked: vcs.is_some(),
+        issues,
+        fixed,
+    })
+}
+
+/// Change the level limit, refusing values that existing tasks already exceed.
+pub fn set_max_depth(conn: &Connection, max_depth: i32) -> Result<()> {
+    settings_repo::validate_max_depth(max_depth)?;
+    // IMMEDIATE so no deeper task can be created between the check and the write
+    let tx = retry::begin_immediate(conn)?;
+    let existing = task_repo::deepest_depth(&tx)?.map_or(0, |d| d + 1);
+    if max_depth < existing {
+        return Err(OsError::MaxDepthBelowExisting {
+            requested: max_depth,
+            existing,
+        });
+    }
+    settings_repo::set_max_depth(&tx, max_depth)?;
+    tx.commit()?;
+    Ok(())
+}
+
+fn status(db_path: &Path) -> Result<DbStatus> {
+    let current_version = if db_path.exists() {
+        schema::current_version(&schema::open_db_unmigrated(db_path)?)?
+    } else {
+        0
+    };
+
+    let state = if !db_path.exists() {
+        SchemaState::Missing
+    } else if current_version > SCHEMA_VERSION {
+        SchemaState::TooNew
+    } else if current_version == SCHEMA_VERSION {
+        SchemaState::UpToDate
+    } else {
+        SchemaState::Outdated
+    };
+    let pending = if state == SchemaState::TooNew {
+        Vec::new()
+    } else {
+        schema::pending_migrations(current_version)
+            .into_iter()
+            .map(MigrationInfo::from)
+            .collect()
+    };
+
+    Ok(DbStatus {
+        path: db_path.to_path_buf(),
+        state,
+        current_version,
+        latest_version: SCHEMA_VERSION,
+        pending,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A database file left at v6 by an older release.
+    fn database_at_v6(path: &Path) {
+        let conn = Connection::open(path).unwrap();
+        schema::create_at_version(&conn, 6);
+    }
+
+    #[test]
+    fn test_status_reports_pending_without_migrating() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("tasks.db");
+
+        let DbResult::Status(missing) = handle(&path, DbCommand::Status).unwrap() else {
+            panic!("expected status");
+        };
+        assert_eq!(missing.state, SchemaState::Missing);
+        assert!(!path.exists());
+
+        database_at_v6(&path);
+
+        let DbResult::Status(status) = handle(&path, DbCommand::Status).unwrap() else {
+            panic!("expected status");
+        };
+        assert_eq!(status.state, SchemaState::Outdated);
+        assert_eq!(status.current_version, 6);
+        assert_eq!(status.pending[0].id, "0007_event_log");
+        assert_eq!(
+            schema::current_version(&schema::open_db_unmigrated(&path).unwrap()).unwrap(),
+            6
+        );
+    }
+
+    #[test]
+    fn test_dry_run_then_migrate() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("tasks.db");
+        database_at_v6(&path);
+
+        let DbResult::Migrate(preview) =
+            handle(&path, DbCommand::Migrate(MigrateArgs { dry_run: true })).unwrap()
+        else {
+            panic!("expected migrate");
+        };
+        assert!(preview.dry_run);
+        assert_eq!(preview.to_version, SCHEMA_VERSION);
+        assert!(preview.backup.is_none());
+        let conn = schema::open_db_unmigrated(&path).unwrap();
+        assert_eq!(schema::current_version(&conn).unwrap(), 6);
+        drop(conn);
+
+        let DbResult::Migrate(result) =
+            handle(&path, DbCommand::Migrate(MigrateArgs { dry_run: false })).unwrap()
+        else {
+            panic!("expected migrate");
+        };
+        assert_eq!(result.from_version, 6);
+        assert_eq!(result.to_version, SCHEMA_VERSION);
+        assert_eq!(result.migrations.len(), preview.migrations.len());
+        assert!(result.backup.unwrap().exists());
+    }
+
+    #[test]
+    fn test_check_reports_then_fixes() {
+        let conn = Connection::open_in_memory().unwrap();
+        schema::init_schema(&conn).unwrap();
+        conn.execute_batch(
+            r#"
+            INSERT INTO tasks (id, description, created_at, updated_at, completed)
+            VALUES ('task_01JQAZ0000000000000000000A', 'Done', '2024-01-01T00:00:00Z',
+                    '2024-01-02T00:00:00Z', 1);
+            "#,
+        )
+        .unwrap();
+
+        let report = check(&conn, None, false).unwrap();
+        assert!(!report.ok);
+        assert!(!report.vcs_checked);
+        assert_eq!(report.fixed, 0);
+        let json = serde_json::to_value(&report).unwrap();
+        assert_eq!(json["issues"][0]["code"], "lifecycle.missingCompletedAt");
+        assert_eq!(json["issues"][0]["fixable"], true);
+
+        let fixed = check(&conn, None, true).unwrap();
+        assert!(fixed.ok);
+        assert_eq!(fixed.fixed, 1);
+        let completed_at: String = conn
+            .query_row("SELECT completed_at FROM tasks", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(completed_at, "2024-01-02T00:00:00Z");
+        assert!(check(&conn, None, false).unwrap().issues.is_empty());
+    }
+
+    #[test]
+    fn test_config_max_depth_cannot_drop_below_existing_tasks() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("tasks.db");
+        let config = |max_depth| {
+            handle(
+                &path,
+                DbCommand::Config(ConfigArgs {
+                    max_depth,
+                    integration: None,
+                    worktree_dir: None,
+                    clear_worktree_dir: false,
+                }),
+            )
+        };
+
+        let Ok(DbResult::Config(settings)) = config(None) else {
+            panic!("expected config");
+        };
+        assert_eq!(settings.max_depth, 3);
+
+        let conn = crate::db::open_db(&path).unwrap();
+        let svc = crate::core::TaskService::new(&conn);
+        let root = svc
+            .create(&crate::types::CreateTaskInput {
+                description: "Root".to_string(),
+                ..Default::default()
+            })
+            .unwrap();
+        svc.create(&crate::types::CreateTaskInput {
+            description: "Child".to_string(),
+            parent_id: Some(root.id),
+            ..Default::default()
+        })
+        .unwrap();
+
+        assert!(matches!(
+            config(Some(1)),
+            Err(OsError::MaxDepthBelowExisting {
+                requested: 1,
+                existing: 2
+            })
+        ));
+        assert!(matches!(config(Some(0)), Err(OsError::InvalidMaxDepth(0))));
+        let Ok(DbResult::Config(settings)) = config(Some(2)) else {
+            panic!("expected config");
+        };
+        assert_eq!(settings.max_depth, 2);
+    }
+}
@@ -0,0 +1,227 @@
+use std::io::{self, Write};
+use std::time::Duration;
+
+use clap::{Args, Subcommand};
+use rusqlite::Connection;
+
+use crate::db::event_repo::{self, Event, EventQuery};
+use crate::error::Result;
+use crate::id::TaskId;
+
+/// Parse TaskId from CLI string (requires prefix)
+fn parse_task_id(s: &str) -> std::result::Result<TaskId, String> {
+    s.parse().map_err(|e| format!("{e}"))
+}
+
+#[derive(Subcommand, Clone)]
+pub enum EventsCommand {
+    /// List events in seq order
+    List(ListArgs),
+    /// Print events as JSON lines, optionally following new ones
+    #[command(
+        about = "Print events as JSON lines",
+        long_about = r#"
+Print events as newline-delimited JSON, one event per line.
+
+Without --after-seq, starts with the last N events (-n). With --follow,
+keeps polling the database and prints new events as they are appended.
+Output is always JSON lines, regardless of --json.
+
+Examples:
+  os events tail
+  os events tail --after-seq 120 --follow
+  os events tail --task task_01JQAZ... -f
+"#
+    )]
+    Tail(TailArgs),
+}
+
+#[derive(Args, Clone)]
+pub struct ListArgs {
+    /// Only events with seq greater than N
+    #[arg(long = "after-seq", value_name = "N")]
+    pub after_seq: Option<i64>,
+
+    /// Only events for this task
+    #[arg(long, value_parser = parse_task_id)]
+    pub task: Option<TaskId>,
+
+    /// Maximum number of events
+    #[arg(long, default_value_t = 100)]
+    pub limit: usize,
+}
+
+#[derive(Args, Clone)]
+pub struct TailArgs {
+    /// Only events with seq greater than N
+    #[arg(long = "after-seq", value_name = "N")]
+    pub after_seq: Option<i64>,
+
+    /// Only events for this task
+    #[arg(long, value_parser = parse_task_id)]
+    pub task: Option<TaskId>,
+
+    /// Number of recent events to print first (ignored with --after-seq)
+    #[arg(short = 'n', long, default_value_t = 10)]
+    pub lines: usize,
+
+    /// Keep running and print new events as they arrive
+    #[arg(short, long)]
+    pub follow: bool,
+
+    /// Poll interval while following, in milliseconds
+    #[arg(long, default_value_t = 500)]
+    pub interval_ms: u64,
+}
+
+pub enum EventsResult {
+    Many(Vec<Event>),
+}
+
+pub fn handle(conn: &Connection, cmd: EventsCommand) -> Result<EventsResult> {
+    match cmd {
+        EventsCommand::List(args) => Ok(EventsResult::Many(event_repo::list_events(
+            conn,
+            &EventQuery {
+                after_seq: args.after_seq,
+                task_id: args.task,
+                limit: Some(args.limit),
+            },
+        )?)),
+        // PRECONDITION: tail streams output, handled in main() via tail()
+        EventsCommand::Tail(_) => unreachable!("tail handled before run()"),
+    }
+}
+
+/// Write events as JSON lines. Returns when caught up unless `follow` is set,
+/// or when the reader goes away (broken pipe).
+pub fn tail(conn: &Connection, args: &TailArgs, out: &mut impl Write) -> Result<()> {
+    let (mut events, mut last_seq) = match args.after_seq {
+        Some(after) => (fetch_after(conn, args, after)?, after),
+        None => {
+            // Read the high-water mark first so nothing appended meanwhile is skipped
+            let max_seq = event_repo::max_seq(conn)?;
+            let latest = event_repo::latest_events(conn, args.task.as_ref(), args.lines)?;
+            (latest, max_seq)
+        }
+    };
+
+    loop {
+        for event in &events {
+            last_seq = last_seq.max(event.seq);
+            match writeln!(out, "{}", serde_json::to_string(event)?) {
+                Err(e) if e.kind() == io::ErrorKind::BrokenPipe => return Ok(()),
+                result => result?,
+            }
+        }
+        match out.flush() {
+            Err(e) if e.kind() == io::ErrorKind::BrokenPipe => return Ok(()),
+            result => result?,
+        }
+
+        if !args.follow {
+            return Ok(());
+        }
+        std::thread::sleep(Duration::from_millis(args.interval_ms));
+        events = fetch_after(conn, args, last_seq)?;
+    }
+}
+
+fn fetch_after(conn: &Connection, args: &TailArgs, after_seq: i64) -> Result<Vec<Event>> {
+    event_repo::list_events(
+        conn,
+        &EventQuery {
+            after_seq: Some(after_seq),
+            task_id: args.task.clone(),
+            limit: None,
+        },
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::TaskService;
+    use crate::db::schema;
+    use crate::types::CreateTaskInput;
+
+    fn setup_db() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch("PRAGMA foreign_keys = ON;").unwrap();
+        schema::init_schema(&conn).unwrap();
+        conn
+    }
+
+    fn tail_args(after_seq: Option<i64>, lines: usize) -> TailArgs {
+        TailArgs {
+            after_seq,
+            task: None,
+            lines,
+            follow: false,
+            interval_ms: 0,
+        }
+    }
+
+    #[test]
+    fn test_tail_prints_json_lines() {
+        let conn = setup_db();
+        let svc = TaskService::new(&conn);
+        let task = svc
+            .create(&CreateTaskInput {
+                description: "Task".to_string(),
+                ..Default::default()
+            })
+            .unwrap();
+        svc.cancel(&task.id).unwrap();
+
+        let mut out = Vec::new();
+        tail(&conn, &tail_args(None, 1), &mut out).unwrap();
+        let lines: Vec<serde_json::Value> = String::from_utf8(out)
+            .unwrap()
+            .lines()
+            .map(|l| serde_json::from_str(l).unwrap())
+            .collect();
+        assert_eq!(lines.len(), 1);
+        assert_eq!(lines[0]["type"], "task.cancelled");
+
+        let mut out = Vec::new();
+        tail(&conn, &tail_args(Some(0), 10), &mut out).unwrap();
+        assert_eq!(String::from_utf8(out).unwrap().lines().count(), 2);
+    }
+
+    #[test]
+    fn test_list_after_seq() {
+        let conn = setup_db();
+        let svc = TaskService::new(&conn);
+        for description in ["A", "B", "C"] {
+            svc.create(&CreateTaskInput {
+                description: description.to_string(),
+                ..Default::default()
+            })
+            .unwrap();
+        }
+
+        let EventsResult::Many(all) = handle(
+            &conn,
+            EventsCommand::List(ListArgs {
+                after_seq: None,
+                task: None,
+                limit: 100,
+            }),
+        )
+        .unwrap();
+        assert_eq!(all.len(), 3);
+
+        let EventsResult::Many(rest) = handle(
+            &conn,
+            EventsCommand::List(ListArgs {
+                after_seq: Some(all[0].seq),
+                task: None,
+                limit: 100,
+            }),
+        )
+        .unwrap();
+        assert_eq!(rest.len(), 2);
+        assert_eq!(rest[0].seq, all[1].seq);
+    }
+}
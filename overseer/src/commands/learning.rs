@@ -1,6 +1,7 @@
 use clap::{Args, Subcommand};
 use rusqlite::Connection;
 
+use crate::db::search_repo::{self, SearchHit};
 use crate::db::{learning_repo, task_repo, Learning};
 use crate::error::{OsError, Result};
 use crate::id::{LearningId, TaskId};
@@ -26,6 +27,8 @@ pub enum LearningCommand {
         #[arg(value_parser = parse_learning_id)]
         id: LearningId,
     },
+    /// Full-text search over learning content, best match first
+    Search(SearchArgs),
 }
 
 #[derive(Args)]
@@ -38,10 +41,24 @@ pub struct AddArgs {
     pub source: Option<TaskId>,
 }
 
+#[derive(Args)]
+pub struct SearchArgs {
+    pub query: String,
+
+    /// Include learnings attached to archived tasks
+    #[arg(short = 'a', long)]
+    pub all: bool,
+
+    /// Maximum number of results
+    #[arg(long, default_value_t = 50)]
+    pub limit: usize,
+}
+
 pub enum LearningResult {
     One(Learning),
     Many(Vec<Learning>),
     Deleted,
+    Search(Vec<SearchHit<Learning>>),
 }
 
 pub fn handle(conn: &Connection, cmd: LearningCommand) -> Result<LearningResult> {
@@ -76,5 +93,12 @@ pub fn handle(conn: &Connection, cmd: LearningCommand) -> Result<LearningResult>
             learning_repo::delete_learning(conn, &id)?;
             Ok(LearningResult::Deleted)
         }
+
+        LearningCommand::Search(args) => Ok(LearningResult::Search(search_repo::search_learnings(
+            conn,
+            &args.query,
+            args.all,
+            args.limit,
+        )?)),
     }
 }
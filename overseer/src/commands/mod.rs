@@ -1,9 +1,19 @@
 pub mod data;
+pub mod db;
+pub mod events;
 pub mod learning;
+pub mod repo;
+pub mod report;
 pub mod task;
+pub mod template;
 pub mod vcs;
 
 pub use data::{DataCommand, DataResult};
+pub use db::{DbCommand, DbResult};
+pub use events::{EventsCommand, EventsResult};
 pub use learning::{LearningCommand, LearningResult};
+pub use repo::{RepoCommand, RepoResult};
+pub use report::{ReportCommand, ReportResult};
 pub use task::{TaskCommand, TaskResult};
+pub use template::{TemplateCommand, TemplateResult};
 pub use vcs::VcsCommand;
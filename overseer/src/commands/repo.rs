@@ -0,0 +1,78 @@
+use std::path::PathBuf;
+
+use clap::Subcommand;
+use rusqlite::Connection;
+
+use crate::core::repos;
+use crate::db::repo_repo::Repo;
+use crate::error::Result;
+
+#[derive(Subcommand, Clone)]
+pub enum RepoCommand {
+    /// Register a repository with this database
+    #[command(
+        about = "Register a repository",
+        long_about = r#"
+Register the repository containing PATH (default: the current directory), so
+tasks in this database can work in it.
+
+Create tasks with `--repo NAME` (children inherit their parent's repo); start
+and complete then branch and commit in that repository, wherever os is run
+from. Tasks without a repo use the repository of the working directory.
+Blockers may cross repos.
+
+To share one database between repositories, point them all at it with
+OVERSEER_DB_PATH or --db.
+
+Examples:
+  os repo add
+  os repo add ../frontend --name web
+"#
+    )]
+    Add {
+        /// Any path inside the repository (its root is registered)
+        #[arg(default_value = ".")]
+        path: PathBuf,
+
+        /// Name for --repo options (default: the root directory's name)
+        #[arg(long)]
+        name: Option<String>,
+    },
+
+    /// List registered repositories
+    List,
+
+    /// Unregister a repository
+    #[command(
+        about = "Unregister a repository",
+        long_about = r#"
+Unregister a repository (by name or id). Refused while it has tasks that are
+neither completed nor cancelled; its finished tasks are kept without a repo.
+Nothing in the repository itself is touched.
+
+Examples:
+  os repo remove web
+"#
+    )]
+    Remove {
+        /// Repo name or id
+        repo: String,
+    },
+}
+
+pub enum RepoResult {
+    One(Repo),
+    Many(Vec<Repo>),
+}
+
+pub fn handle(conn: &Connection, cmd: RepoCommand) -> Result<RepoResult> {
+    match cmd {
+        RepoCommand::Add { path, name } => Ok(RepoResult::One(repos::register(
+            conn,
+            &path,
+            name.as_deref(),
+        )?)),
+        RepoCommand::List => Ok(RepoResult::Many(repos::list(conn)?)),
+        RepoCommand::Remove { repo } => Ok(RepoResult::One(repos::remove(conn, &repo)?)),
+    }
+}
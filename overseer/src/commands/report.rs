@@ -0,0 +1,80 @@
+use chrono::{DateTime, Utc};
+use clap::{Args, Subcommand};
+use rusqlite::Connection;
+
+use crate::commands::task::{parse_ttl, parse_when};
+use crate::core::time_report::{self, TimeReport};
+use crate::error::Result;
+use crate::id::TaskId;
+
+/// Parse TaskId from CLI string (requires prefix)
+fn parse_task_id(s: &str) -> std::result::Result<TaskId, String> {
+    s.parse().map_err(|e| format!("{e}"))
+}
+
+/// Report bounds look back: `7d` is seven days ago, a date or timestamp is taken as is
+fn parse_past(s: &str, end_of_day: bool) -> std::result::Result<DateTime<Utc>, String> {
+    match parse_ttl(s) {
+        Ok(ago) => Ok(Utc::now() - ago),
+        Err(_) => parse_when(s, end_of_day),
+    }
+}
+
+fn parse_since(s: &str) -> std::result::Result<DateTime<Utc>, String> {
+    parse_past(s, false)
+}
+
+fn parse_until(s: &str) -> std::result::Result<DateTime<Utc>, String> {
+    parse_past(s, true)
+}
+
+#[derive(Subcommand, Clone)]
+pub enum ReportCommand {
+    /// Time spent per task, milestone and day, from work sessions
+    #[command(
+        about = "Time spent per task, milestone and day",
+        long_about = r#"
+Aggregate elapsed time from work sessions.
+
+Sessions open on `os task start` / `os task resume` and close on
+`os task pause`, `complete` or `cancel`. Running sessions count up to now.
+Days are UTC.
+
+Examples:
+  os report time
+  os report time --milestone task_01JQAZ... --since 2026-03-01
+  os report time --since 7d --json
+"#
+    )]
+    Time(TimeArgs),
+}
+
+#[derive(Args, Clone)]
+pub struct TimeArgs {
+    /// Only time on this milestone (or any task) and its descendants
+    #[arg(long, value_parser = parse_task_id)]
+    pub milestone: Option<TaskId>,
+
+    /// Start of the window: YYYY-MM-DD (UTC), RFC 3339, or ago (e.g. 7d)
+    #[arg(long, value_parser = parse_since)]
+    pub since: Option<DateTime<Utc>>,
+
+    /// End of the window (default: now); a plain date includes that whole day
+    #[arg(long, value_parser = parse_until)]
+    pub until: Option<DateTime<Utc>>,
+}
+
+pub enum ReportResult {
+    Time(TimeReport),
+}
+
+pub fn handle(conn: &Connection, cmd: ReportCommand) -> Result<ReportResult> {
+    match cmd {
+        ReportCommand::Time(args) => Ok(ReportResult::Time(time_report::time_report(
+            conn,
+            args.milestone.as_ref(),
+            args.since,
+            args.until,
+        )?)),
+    }
+}
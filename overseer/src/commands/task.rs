@@ -1,30 +1,181 @@
+use chrono::{DateTime, NaiveDate, NaiveTime, Utc};
 use clap::{Args, Subcommand};
 use rusqlite::Connection;
 
-use crate::core::{get_task_with_context, TaskService, TaskWithContext, TaskWorkflowService};
-use crate::db::task_repo;
-use crate::error::Result;
-use crate::id::TaskId;
-use crate::types::{CreateTaskInput, ListTasksFilter, Task, UpdateTaskInput};
+use crate::core::links::{self, LinkTarget};
+use crate::core::repos;
+use crate::core::task_graph::has_labels;
+use crate::core::trash::{self, PurgedTrash, RestoredTrash};
+use crate::core::{
+    get_task_with_context, TaskGraph, TaskService, TaskWithContext, TaskWorkflowService,
+};
+use crate::db::search_repo::SearchHit;
+use crate::db::trash_repo::TrashEntry;
+use crate::db::{label_repo, Comment, TaskLink};
+use crate::error::{OsError, Result};
+use crate::id::{CommentId, LinkId, RepoId, TaskId};
+use crate::types::{
+    CreateTaskInput, ListTasksFilter, MetadataFilter, MovePosition, NextReadyFilter, Task,
+    TaskClaim, TaskSession, UpdateTaskInput,
+};
 use crate::vcs::backend::VcsBackend;
+use crate::vcs::IntegrationStrategy;
 
 /// Parse TaskId from CLI string (requires prefix)
 fn parse_task_id(s: &str) -> std::result::Result<TaskId, String> {
     s.parse().map_err(|e| format!("{e}"))
 }
 
+/// Parse a metadata value: valid JSON is kept as-is, anything else becomes a string
+fn parse_meta_value(s: &str) -> serde_json::Value {
+    serde_json::from_str(s).unwrap_or_else(|_| serde_json::Value::String(s.to_string()))
+}
+
+/// Validate and normalize a label (lowercased)
+fn parse_label(s: &str) -> std::result::Result<String, String> {
+    label_repo::normalize_label(s).map_err(|e| e.to_string())
+}
+
+/// Parse CommentId from CLI string (requires prefix)
+fn parse_comment_id(s: &str) -> std::result::Result<CommentId, String> {
+    s.parse().map_err(|e| format!("{e}"))
+}
+
+/// Comment bodies are free-form but must not be blank
+fn parse_comment_body(s: &str) -> std::result::Result<String, String> {
+    if s.trim().is_empty() {
+        return Err("comment must not be empty".to_string());
+    }
+    Ok(s.to_string())
+}
+
+/// Parse LinkId from CLI string (requires prefix)
+fn parse_link_id(s: &str) -> std::result::Result<LinkId, String> {
+    s.parse().map_err(|e| format!("{e}"))
+}
+
+/// Parse a line range: `10-40`, or `42` for a single line
+fn parse_lines(s: &str) -> std::result::Result<(u32, u32), String> {
+    let invalid = || format!("invalid line range '{s}' (expected START-END or a line number)");
+    let (start, end) = s.split_once('-').unwrap_or((s, s));
+    let start: u32 = start.trim().parse().map_err(|_| invalid())?;
+    let end: u32 = end.trim().parse().map_err(|_| invalid())?;
+    if start == 0 || end < start {
+        return Err(invalid());
+    }
+    Ok((start, end))
+}
+
+/// Agent names are free-form but must not be blank
+fn parse_agent(s: &str) -> std::result::Result<String, String> {
+    let agent = s.trim();
+    if agent.is_empty() {
+        return Err("agent name must not be empty".to_string());
+    }
+    Ok(agent.to_string())
+}
+
+/// Parse a claim TTL such as `90s`, `30m`, `2h`, `1d` or `1h30m`
+pub(crate) fn parse_ttl(s: &str) -> std::result::Result<chrono::Duration, String> {
+    let invalid = || format!("invalid duration '{s}' (expected e.g. 90s, 30m, 2h, 1h30m)");
+    let mut total = chrono::Duration::zero();
+    let mut digits = String::new();
+    for c in s.trim().chars() {
+        if c.is_ascii_digit() {
+            digits.push(c);
+            continue;
+        }
+        let n: i64 = digits.parse().map_err(|_| invalid())?;
+        digits.clear();
+        total += match c {
+            's' => chrono::Duration::seconds(n),
+            'm' => chrono::Duration::minutes(n),
+            'h' => chrono::Duration::hours(n),
+            'd' => chrono::Duration::days(n),
+            _ => return Err(invalid()),
+        };
+    }
+    if !digits.is_empty() || total <= chrono::Duration::zero() {
+        return Err(invalid());
+    }
+    Ok(total)
+}
+
+/// Parse a point in time: RFC 3339, a `YYYY-MM-DD` date (UTC, at the start of
+/// the day or just before it ends), or a duration from now such as `3d`
+pub(crate) fn parse_when(s: &str, end_of_day: bool) -> std::result::Result<DateTime<Utc>, String> {
+    let s = s.trim();
+    if let Ok(dt) = DateTime::parse_from_rfc3339(s) {
+        return Ok(dt.with_timezone(&Utc));
+    }
+    if let Ok(date) = NaiveDate::parse_from_str(s, "%Y-%m-%d") {
+        let time = if end_of_day {
+            NaiveTime::from_hms_opt(23, 59, 59)
+        } else {
+            NaiveTime::from_hms_opt(0, 0, 0)
+        };
+        return Ok(date.and_time(time.unwrap_or_default()).and_utc());
+    }
+    parse_ttl(s)
+        .map(|d| Utc::now() + d)
+        .map_err(|_| format!("invalid date '{s}' (expected YYYY-MM-DD, RFC 3339 or e.g. 3d, 12h)"))
+}
+
+/// Due dates given as a plain date fall due at the end of that day
+fn parse_due(s: &str) -> std::result::Result<DateTime<Utc>, String> {
+    parse_when(s, true)
+}
+
+/// Deferrals given as a plain date end when that day starts
+fn parse_defer(s: &str) -> std::result::Result<DateTime<Utc>, String> {
+    parse_when(s, false)
+}
+
+/// Parse `path=value` (e.g. `area=auth`, `pr.number=12`) into a metadata filter
+fn parse_meta_filter(s: &str) -> std::result::Result<MetadataFilter, String> {
+    let (path, value) = s
+        .split_once('=')
+        .ok_or_else(|| format!("expected PATH=VALUE, got '{s}'"))?;
+    let path = path.trim();
+    if path.is_empty() {
+        return Err(format!("expected PATH=VALUE, got '{s}'"));
+    }
+    Ok(MetadataFilter {
+        path: path.to_string(),
+        value: parse_meta_value(value.trim()),
+    })
+}
+
 #[derive(Subcommand)]
 pub enum TaskCommand {
     Create(CreateArgs),
     Get {
         #[arg(value_parser = parse_task_id)]
         id: TaskId,
+
+        /// Include the latest N comments
+        #[arg(long, value_name = "N")]
+        comments: Option<usize>,
     },
     List(ListArgs),
     Update(UpdateArgs),
     Start {
         #[arg(value_parser = parse_task_id)]
         id: TaskId,
+
+        /// Act as this agent: another agent's claim refuses the start, and the
+        /// task is claimed for 30m unless the agent already holds a claim on it
+        #[arg(long, env = "OVERSEER_AGENT", value_parser = parse_agent)]
+        agent: Option<String>,
+
+        /// Fail unless the requested task is still at this revision
+        #[arg(long)]
+        if_revision: Option<i64>,
+
+        /// Check the new task branch out in its own git worktree instead of
+        /// the current working copy (see `os db config --worktree-dir`)
+        #[arg(long)]
+        worktree: bool,
     },
     Complete(CompleteArgs),
     Reopen {
@@ -34,21 +185,238 @@ pub enum TaskCommand {
     Cancel {
         #[arg(value_parser = parse_task_id)]
         id: TaskId,
+
+        /// Fail unless the task is still at this revision
+        #[arg(long)]
+        if_revision: Option<i64>,
     },
     Archive {
         #[arg(value_parser = parse_task_id)]
         id: TaskId,
+
+        /// Fail unless the task is still at this revision
+        #[arg(long)]
+        if_revision: Option<i64>,
     },
+    /// Move a task and its subtree to the trash
+    #[command(long_about = r#"
+Move a task and its whole subtree to the trash. Learnings, comments, links,
+work sessions and every blocker edge touching the subtree are kept with it,
+and task branches are left in place.
+
+Undo with `os task restore ID`. Only `os task trash purge` deletes for good.
+"#)]
     Delete {
         #[arg(value_parser = parse_task_id)]
         id: TaskId,
     },
+    /// Bring a deleted subtree back from the trash
+    #[command(long_about = r#"
+Restore a subtree from the trash under its original parent, with its
+learnings, comments, links and sessions. Blocker edges are rebuilt where the
+task on the other end still exists; the rest are reported as skipped.
+
+Pass the id of the task that was deleted (see `os task trash list`). If the
+parent is in the trash too, restore it first.
+"#)]
+    Restore {
+        #[arg(value_parser = parse_task_id)]
+        id: TaskId,
+    },
+    /// List or purge deleted subtrees
+    #[command(subcommand)]
+    Trash(TrashCommand),
     Block(BlockArgs),
     Unblock(UnblockArgs),
+    /// Reorder a task among its siblings (within its priority)
+    Move(MoveArgs),
     NextReady(NextReadyArgs),
     Tree(TreeArgs),
     Search(SearchArgs),
     Progress(ProgressArgs),
+    /// Read and write per-task JSON metadata
+    #[command(subcommand)]
+    Meta(MetaCommand),
+    /// Add or remove task labels
+    #[command(subcommand)]
+    Label(LabelCommand),
+    /// Timestamped notes on a task, kept apart from context and result
+    #[command(subcommand)]
+    Comment(CommentCommand),
+    /// Typed links to files, code ranges, URLs, commits and issues
+    #[command(subcommand)]
+    Link(LinkCommand),
+    /// Claim a task and its subtree for an agent until the TTL runs out
+    Claim(ClaimArgs),
+    /// Renew a live claim held by the agent
+    Heartbeat(ClaimArgs),
+    /// Release a claim
+    Release(ReleaseArgs),
+    /// Stop the clock on a started task without completing it
+    Pause(SessionArgs),
+    /// Restart the clock on a paused task (no VCS changes)
+    Resume(SessionArgs),
+}
+
+#[derive(Subcommand, Clone)]
+pub enum TrashCommand {
+    /// List deleted subtrees, most recent first
+    List,
+    /// Delete old subtrees for good, with their task branches
+    Purge {
+        /// Purge what was deleted at least this long ago, e.g. 30d or 12h
+        #[arg(long, value_name = "DURATION", value_parser = parse_ttl)]
+        older_than: chrono::Duration,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum LabelCommand {
+    /// Add labels (existing labels are kept)
+    Add {
+        #[arg(value_parser = parse_task_id)]
+        id: TaskId,
+        #[arg(required = true, value_parser = parse_label)]
+        labels: Vec<String>,
+    },
+    /// Remove labels
+    Rm {
+        #[arg(value_parser = parse_task_id)]
+        id: TaskId,
+        #[arg(required = true, value_parser = parse_label)]
+        labels: Vec<String>,
+    },
+}
+
+#[derive(Subcommand, Clone)]
+pub enum CommentCommand {
+    /// Add a comment
+    Add {
+        #[arg(value_parser = parse_task_id)]
+        id: TaskId,
+        #[arg(value_parser = parse_comment_body)]
+        body: String,
+
+        /// Who is commenting (defaults to $OVERSEER_AGENT)
+        #[arg(long, env = "OVERSEER_AGENT", value_parser = parse_agent)]
+        author: Option<String>,
+    },
+    /// List comments, oldest first
+    List {
+        #[arg(value_parser = parse_task_id)]
+        id: TaskId,
+
+        /// Only the latest N comments
+        #[arg(long, value_name = "N")]
+        latest: Option<usize>,
+    },
+    /// Delete a comment
+    Delete {
+        #[arg(value_parser = parse_comment_id)]
+        id: CommentId,
+    },
+}
+
+#[derive(Subcommand, Clone)]
+pub enum LinkCommand {
+    /// Link a task to a file, code range, URL, commit or issue
+    #[command(long_about = r#"
+Link a task to exactly one target:
+
+  --file PATH [--lines START-END]  File or directory, relative to the repository root
+  --url URL                        http(s) URL
+  --commit REV                     Commit, branch or any revision
+  --issue REF                      External issue, e.g. PROJ-123 or owner/repo#42
+
+File paths must exist in the working copy or at HEAD, and commits must
+resolve; both need a git repository. Commits are stored as full ids.
+Adding a link that already exists returns it unchanged.
+
+Examples:
+  os task link add task_01JQAZ... --file src/auth.rs --lines 40-85 --note "login handler"
+  os task link add task_01JQAZ... --commit HEAD~2
+  os task link add task_01JQAZ... --issue owner/repo#42
+"#)]
+    Add(LinkAddArgs),
+    /// Remove a link
+    Rm {
+        #[arg(value_parser = parse_link_id)]
+        id: LinkId,
+    },
+    /// List a task's links, oldest first
+    List {
+        #[arg(value_parser = parse_task_id)]
+        id: TaskId,
+    },
+}
+
+#[derive(Args, Clone)]
+#[command(group = clap::ArgGroup::new("target").required(true).multiple(false))]
+pub struct LinkAddArgs {
+    #[arg(value_parser = parse_task_id)]
+    pub id: TaskId,
+
+    /// File or directory, relative to the repository root
+    #[arg(long, group = "target")]
+    pub file: Option<String>,
+
+    /// Narrow a file link to lines START-END (or a single line)
+    #[arg(long, value_name = "START-END", requires = "file", value_parser = parse_lines)]
+    pub lines: Option<(u32, u32)>,
+
+    /// http(s) URL
+    #[arg(long, group = "target")]
+    pub url: Option<String>,
+
+    /// Commit, branch or any revision (stored as the full commit id)
+    #[arg(long, group = "target")]
+    pub commit: Option<String>,
+
+    /// External issue reference, e.g. PROJ-123 or owner/repo#42
+    #[arg(long, group = "target")]
+    pub issue: Option<String>,
+
+    /// Why the link matters
+    #[arg(long)]
+    pub note: Option<String>,
+}
+
+impl LinkAddArgs {
+    fn target(&self) -> LinkTarget {
+        match (&self.file, &self.url, &self.commit, &self.issue) {
+            (Some(path), ..) => LinkTarget::File {
+                path: path.clone(),
+                lines: self.lines,
+            },
+            (_, Some(url), ..) => LinkTarget::Url(url.clone()),
+            (_, _, Some(rev), _) => LinkTarget::Commit(rev.clone()),
+            (.., issue) => LinkTarget::Issue(issue.clone().unwrap_or_default()),
+        }
+    }
+}
+
+#[derive(Subcommand)]
+pub enum MetaCommand {
+    /// Set a metadata value (parsed as JSON, falls back to a plain string)
+    Set {
+        #[arg(value_parser = parse_task_id)]
+        id: TaskId,
+        /// Dotted key path, e.g. `area` or `pr.url`
+        path: String,
+        value: String,
+    },
+    /// Print the whole metadata object, or the value at PATH
+    Get {
+        #[arg(value_parser = parse_task_id)]
+        id: TaskId,
+        path: Option<String>,
+    },
+    /// Remove the value at PATH
+    Unset {
+        #[arg(value_parser = parse_task_id)]
+        id: TaskId,
+        path: String,
+    },
 }
 
 #[derive(Args)]
@@ -67,13 +435,29 @@ pub struct CreateArgs {
 
     #[arg(long = "blocked-by", value_delimiter = ',', value_parser = parse_task_id)]
     pub blocked_by: Vec<TaskId>,
+
+    /// Label the task (repeatable or comma-separated)
+    #[arg(long = "label", value_delimiter = ',', value_parser = parse_label)]
+    pub labels: Vec<String>,
+
+    /// Due date: YYYY-MM-DD (end of day, UTC), RFC 3339, or from now (e.g. 3d)
+    #[arg(long, value_parser = parse_due)]
+    pub due: Option<DateTime<Utc>>,
+
+    /// Hide from next-ready until: YYYY-MM-DD (start of day, UTC), RFC 3339, or from now
+    #[arg(long, value_parser = parse_defer)]
+    pub defer_until: Option<DateTime<Utc>>,
+
+    /// Registered repo (name or id) the task works in (default: the parent's)
+    #[arg(long)]
+    pub repo: Option<String>,
 }
 
 #[derive(Args)]
 #[command(group = clap::ArgGroup::new("depth_filter").multiple(false))]
 #[command(group = clap::ArgGroup::new("archive_filter").multiple(false))]
 pub struct ListArgs {
-    #[arg(long, value_parser = parse_task_id, conflicts_with_all = ["milestones", "tasks", "subtasks"])]
+    #[arg(long, value_parser = parse_task_id, conflicts_with = "depth_filter")]
     pub parent: Option<TaskId>,
 
     #[arg(long)]
@@ -82,15 +466,19 @@ pub struct ListArgs {
     #[arg(long)]
     pub completed: bool,
 
-    /// Show only milestones (depth 0)
+    /// Show only tasks at this depth (0 = milestones)
+    #[arg(long, group = "depth_filter", value_parser = clap::value_parser!(i32).range(0..))]
+    pub depth: Option<i32>,
+
+    /// Show only milestones (same as --depth 0)
     #[arg(short = 'm', long, group = "depth_filter")]
     pub milestones: bool,
 
-    /// Show only tasks (depth 1)
+    /// Show only tasks (same as --depth 1)
     #[arg(short = 't', long, group = "depth_filter")]
     pub tasks: bool,
 
-    /// Show only subtasks (depth 2)
+    /// Show only subtasks (same as --depth 2)
     #[arg(short = 's', long, group = "depth_filter")]
     pub subtasks: bool,
 
@@ -105,6 +493,30 @@ pub struct ListArgs {
     /// Show flat list instead of tree view (default). Human output only; JSON always returns flat array.
     #[arg(long)]
     pub flat: bool,
+
+    /// Filter by metadata equality, e.g. `--meta area=auth` (repeatable, all must match)
+    #[arg(long = "meta", value_name = "PATH=VALUE", value_parser = parse_meta_filter, action = clap::ArgAction::Append)]
+    pub meta: Vec<MetadataFilter>,
+
+    /// Only tasks carrying this label (repeatable or comma-separated, all must match)
+    #[arg(long = "label", value_delimiter = ',', value_parser = parse_label)]
+    pub labels: Vec<String>,
+
+    /// Only active tasks past their due date
+    #[arg(long)]
+    pub overdue: bool,
+
+    /// Only tasks due before this date (YYYY-MM-DD, RFC 3339, or from now)
+    #[arg(long, value_parser = parse_defer)]
+    pub due_before: Option<DateTime<Utc>>,
+
+    /// Only tasks linked to this file, or to anything under this directory
+    #[arg(long, value_name = "PATH")]
+    pub linked: Option<String>,
+
+    /// Only tasks belonging to this registered repo (name or id)
+    #[arg(long)]
+    pub repo: Option<String>,
 }
 
 #[derive(Args)]
@@ -123,6 +535,38 @@ pub struct UpdateArgs {
 
     #[arg(long, value_parser = parse_task_id)]
     pub parent: Option<TaskId>,
+
+    /// Add a label (repeatable or comma-separated; remove with `os task label rm`)
+    #[arg(long = "label", value_delimiter = ',', value_parser = parse_label)]
+    pub labels: Vec<String>,
+
+    /// Due date: YYYY-MM-DD (end of day, UTC), RFC 3339, or from now (e.g. 3d)
+    #[arg(long, value_parser = parse_due, conflicts_with = "clear_due")]
+    pub due: Option<DateTime<Utc>>,
+
+    /// Remove the due date
+    #[arg(long)]
+    pub clear_due: bool,
+
+    /// Hide from next-ready until: YYYY-MM-DD (start of day, UTC), RFC 3339, or from now
+    #[arg(long, value_parser = parse_defer, conflicts_with = "clear_defer")]
+    pub defer_until: Option<DateTime<Utc>>,
+
+    /// Remove the deferral
+    #[arg(long)]
+    pub clear_defer: bool,
+
+    /// Move the task to this registered repo (name or id; not once it has a branch)
+    #[arg(long, conflicts_with = "clear_repo")]
+    pub repo: Option<String>,
+
+    /// Detach the task from its repo (it then uses the working directory's)
+    #[arg(long)]
+    pub clear_repo: bool,
+
+    /// Fail unless the task is still at this revision
+    #[arg(long)]
+    pub if_revision: Option<i64>,
 }
 
 #[derive(Args)]
@@ -136,6 +580,55 @@ pub struct CompleteArgs {
     /// Add learnings discovered during this task (repeatable)
     #[arg(long = "learning", action = clap::ArgAction::Append)]
     pub learnings: Vec<String>,
+
+    /// Act as this agent: another agent's live claim refuses the completion
+    #[arg(long, env = "OVERSEER_AGENT", value_parser = parse_agent)]
+    pub agent: Option<String>,
+
+    /// Fail unless the task is still at this revision
+    #[arg(long)]
+    pub if_revision: Option<i64>,
+
+    /// How to bring the task branch into the branch it was started from
+    /// (default: `os db config --integration`, initially merge)
+    #[arg(long, value_enum)]
+    pub integration: Option<IntegrationStrategy>,
+}
+
+#[derive(Args)]
+pub struct ClaimArgs {
+    #[arg(value_parser = parse_task_id)]
+    pub id: TaskId,
+
+    #[arg(long, env = "OVERSEER_AGENT", value_parser = parse_agent)]
+    pub agent: String,
+
+    /// Lease length from now, e.g. 90s, 30m, 2h, 1h30m
+    #[arg(long, default_value = "30m", value_parser = parse_ttl)]
+    pub ttl: chrono::Duration,
+}
+
+#[derive(Args)]
+pub struct SessionArgs {
+    #[arg(value_parser = parse_task_id)]
+    pub id: TaskId,
+
+    /// Act as this agent (recorded on the session; another agent's claim refuses it)
+    #[arg(long, env = "OVERSEER_AGENT", value_parser = parse_agent)]
+    pub agent: Option<String>,
+}
+
+#[derive(Args)]
+pub struct ReleaseArgs {
+    #[arg(value_parser = parse_task_id)]
+    pub id: TaskId,
+
+    #[arg(long, env = "OVERSEER_AGENT", value_parser = parse_agent, required_unless_present = "force")]
+    pub agent: Option<String>,
+
+    /// Release whoever holds the claim
+    #[arg(long)]
+    pub force: bool,
 }
 
 #[derive(Args)]
@@ -156,21 +649,86 @@ pub struct UnblockArgs {
     pub by: TaskId,
 }
 
+#[derive(Args)]
+#[command(group = clap::ArgGroup::new("position").required(true).multiple(false))]
+pub struct MoveArgs {
+    #[arg(value_parser = parse_task_id)]
+    pub id: TaskId,
+
+    /// Place directly before this sibling
+    #[arg(long, group = "position", value_parser = parse_task_id)]
+    pub before: Option<TaskId>,
+
+    /// Place directly after this sibling
+    #[arg(long, group = "position", value_parser = parse_task_id)]
+    pub after: Option<TaskId>,
+
+    /// Place before every sibling of the same priority
+    #[arg(long, group = "position")]
+    pub first: bool,
+
+    /// Place after every sibling of the same priority
+    #[arg(long, group = "position")]
+    pub last: bool,
+}
+
+impl MoveArgs {
+    fn position(&self) -> MovePosition {
+        match (&self.before, &self.after) {
+            (Some(id), _) => MovePosition::Before(id.clone()),
+            (_, Some(id)) => MovePosition::After(id.clone()),
+            _ if self.first => MovePosition::First,
+            _ => MovePosition::Last,
+        }
+    }
+}
+
 #[derive(Args)]
 pub struct NextReadyArgs {
     #[arg(long, value_parser = parse_task_id)]
     pub milestone: Option<TaskId>,
+
+    /// Only pick a task carrying this label (repeatable or comma-separated, all must match)
+    #[arg(long = "label", value_delimiter = ',', value_parser = parse_label)]
+    pub labels: Vec<String>,
+
+    /// Skip tasks under a live claim (claims held by --agent don't count)
+    #[arg(long)]
+    pub unclaimed: bool,
+
+    #[arg(long, env = "OVERSEER_AGENT", value_parser = parse_agent)]
+    pub agent: Option<String>,
+
+    /// Only pick a task belonging to this registered repo (name or id)
+    #[arg(long)]
+    pub repo: Option<String>,
+
+    /// Include the latest N comments
+    #[arg(long, value_name = "N")]
+    pub comments: Option<usize>,
 }
 
 #[derive(Args)]
 pub struct TreeArgs {
     #[arg(value_parser = parse_task_id)]
     pub id: Option<TaskId>,
+
+    /// Keep only branches leading to tasks with this label (repeatable or comma-separated)
+    #[arg(long = "label", value_delimiter = ',', value_parser = parse_label)]
+    pub labels: Vec<String>,
 }
 
 #[derive(Args)]
 pub struct SearchArgs {
     pub query: String,
+
+    /// Include archived tasks
+    #[arg(short = 'a', long)]
+    pub all: bool,
+
+    /// Maximum number of results
+    #[arg(long, default_value_t = 50)]
+    pub limit: usize,
 }
 
 #[derive(Args)]
@@ -178,6 +736,10 @@ pub struct ProgressArgs {
     /// Root task ID (milestone) to calculate progress for. If omitted, calculates for all tasks.
     #[arg(value_parser = parse_task_id)]
     pub id: Option<TaskId>,
+
+    /// Count only tasks carrying this label (repeatable or comma-separated)
+    #[arg(long = "label", value_delimiter = ',', value_parser = parse_label)]
+    pub labels: Vec<String>,
 }
 
 pub enum TaskResult {
@@ -189,6 +751,18 @@ pub enum TaskResult {
     Tree(TaskTree),
     Trees(Vec<TaskTree>),
     Progress(TaskProgressResult),
+    Metadata(Option<serde_json::Value>),
+    Search(Vec<SearchHit<Task>>),
+    Claim(TaskClaim),
+    Released,
+    Session(TaskSession),
+    Comment(Comment),
+    Comments(Vec<Comment>),
+    Link(TaskLink),
+    Links(Vec<TaskLink>),
+    Restored(RestoredTrash),
+    Trash(Vec<TrashEntry>),
+    Purged(PurgedTrash),
 }
 
 #[derive(serde::Serialize, serde::Deserialize)]
@@ -205,6 +779,11 @@ pub struct TaskTree {
     pub children: Vec<TaskTree>,
 }
 
+/// Id of the repo named by a `--repo` option
+fn resolve_repo(conn: &Connection, key: Option<&str>) -> Result<Option<RepoId>> {
+    key.map(|key| Ok(repos::resolve(conn, key)?.id)).transpose()
+}
+
 /// Handle task command (CRUD operations - no VCS required)
 pub fn handle(conn: &Connection, cmd: TaskCommand) -> Result<TaskResult> {
     let svc = TaskService::new(conn);
@@ -217,19 +796,26 @@ pub fn handle(conn: &Connection, cmd: TaskCommand) -> Result<TaskResult> {
                 parent_id: args.parent,
                 priority: args.priority,
                 blocked_by: args.blocked_by,
+                labels: args.labels,
+                due_at: args.due,
+                defer_until: args.defer_until,
+                repo_id: resolve_repo(conn, args.repo.as_deref())?,
             };
             Ok(TaskResult::One(svc.create(&input)?))
         }
 
-        TaskCommand::Get { id } => {
+        TaskCommand::Get { id, comments } => {
             let task = svc.get(&id)?;
-            let with_ctx = get_task_with_context(conn, task)?;
+            let with_ctx =
+                get_task_with_context(conn, task)?.with_recent_comments(conn, comments)?;
             Ok(TaskResult::OneWithContext(with_ctx))
         }
 
         TaskCommand::List(args) => {
-            // Determine depth from type filter flags (pure booleans)
-            let depth = if args.milestones {
+            // Shorthand flags name the first three levels
+            let depth = if args.depth.is_some() {
+                args.depth
+            } else if args.milestones {
                 Some(0)
             } else if args.tasks {
                 Some(1)
@@ -252,6 +838,12 @@ pub fn handle(conn: &Connection, cmd: TaskCommand) -> Result<TaskResult> {
                 completed: if args.completed { Some(true) } else { None },
                 depth,
                 archived,
+                metadata: args.meta,
+                labels: args.labels,
+                overdue: args.overdue,
+                due_before: args.due_before,
+                linked: args.linked,
+                repo_id: resolve_repo(conn, args.repo.as_deref())?,
             };
             Ok(TaskResult::Many(svc.list(&filter)?))
         }
@@ -262,31 +854,71 @@ pub fn handle(conn: &Connection, cmd: TaskCommand) -> Result<TaskResult> {
                 context: args.context,
                 priority: args.priority,
                 parent_id: args.parent,
+                labels: args.labels,
+                due_at: if args.clear_due {
+                    Some(None)
+                } else {
+                    args.due.map(Some)
+                },
+                defer_until: if args.clear_defer {
+                    Some(None)
+                } else {
+                    args.defer_until.map(Some)
+                },
+                repo_id: if args.clear_repo {
+                    Some(None)
+                } else {
+                    resolve_repo(conn, args.repo.as_deref())?.map(Some)
+                },
             };
-            Ok(TaskResult::One(svc.update(&args.id, &input)?))
+            let task = svc.at_revision(&args.id, args.if_revision, |svc| {
+                svc.update(&args.id, &input)
+            })?;
+            Ok(TaskResult::One(task))
         }
 
         TaskCommand::Reopen { id } => Ok(TaskResult::One(svc.reopen(&id)?)),
 
-        TaskCommand::Cancel { id } => Ok(TaskResult::One(svc.cancel(&id)?)),
+        TaskCommand::Cancel { id, if_revision } => Ok(TaskResult::One(svc.at_revision(
+            &id,
+            if_revision,
+            |svc| svc.cancel(&id),
+        )?)),
 
-        TaskCommand::Archive { id } => Ok(TaskResult::One(svc.archive(&id)?)),
+        TaskCommand::Archive { id, if_revision } => Ok(TaskResult::One(svc.at_revision(
+            &id,
+            if_revision,
+            |svc| svc.archive(&id),
+        )?)),
 
         TaskCommand::Delete { id } => {
             svc.delete(&id)?;
             Ok(TaskResult::Deleted)
         }
 
+        TaskCommand::Restore { id } => Ok(TaskResult::Restored(trash::restore(conn, &id)?)),
+
+        TaskCommand::Trash(cmd) => handle_trash(conn, cmd, None),
+
         TaskCommand::Block(args) => Ok(TaskResult::One(svc.add_blocker(&args.id, &args.by)?)),
 
         TaskCommand::Unblock(args) => Ok(TaskResult::One(svc.remove_blocker(&args.id, &args.by)?)),
 
+        TaskCommand::Move(args) => Ok(TaskResult::One(svc.move_task(&args.id, &args.position())?)),
+
         TaskCommand::NextReady(args) => {
-            let result = svc.next_ready(args.milestone.as_ref())?;
+            let filter = NextReadyFilter {
+                labels: args.labels,
+                unclaimed: args.unclaimed,
+                agent: args.agent,
+                repo_id: resolve_repo(conn, args.repo.as_deref())?,
+            };
+            let result = svc.next_ready_matching(args.milestone.as_ref(), &filter)?;
             match result {
                 Some(id) => {
                     let task = svc.get(&id)?;
-                    let with_ctx = get_task_with_context(conn, task)?;
+                    let with_ctx = get_task_with_context(conn, task)?
+                        .with_recent_comments(conn, args.comments)?;
                     Ok(TaskResult::MaybeOneWithContext(Some(with_ctx)))
                 }
                 None => Ok(TaskResult::MaybeOneWithContext(None)),
@@ -295,166 +927,249 @@ pub fn handle(conn: &Connection, cmd: TaskCommand) -> Result<TaskResult> {
 
         TaskCommand::Tree(args) => match args.id {
             Some(id) => {
-                let tree = build_tree_for_task(conn, &id)?;
+                let tree = build_tree_for_task(conn, &id, &args.labels)?;
                 Ok(TaskResult::Tree(tree))
             }
             None => {
-                let trees = build_all_trees(conn)?;
+                let trees = build_all_trees(conn, &args.labels)?;
                 Ok(TaskResult::Trees(trees))
             }
         },
 
-        TaskCommand::Search(args) => {
-            let tasks = search_tasks(conn, &args.query)?;
-            Ok(TaskResult::Many(tasks))
-        }
+        TaskCommand::Search(args) => Ok(TaskResult::Search(svc.search(
+            &args.query,
+            args.all,
+            args.limit,
+        )?)),
 
         TaskCommand::Progress(args) => {
-            let progress = calculate_progress(conn, args.id.as_ref())?;
+            let progress = calculate_progress(conn, args.id.as_ref(), &args.labels)?;
             Ok(TaskResult::Progress(progress))
         }
 
-        // Workflow commands require VCS - caller must use handle_workflow
-        TaskCommand::Start { .. } | TaskCommand::Complete(_) => {
-            Err(crate::error::OsError::NotARepository)
+        TaskCommand::Meta(cmd) => match cmd {
+            MetaCommand::Set { id, path, value } => Ok(TaskResult::One(svc.set_metadata(
+                &id,
+                &path,
+                &parse_meta_value(&value),
+            )?)),
+            MetaCommand::Get { id, path } => Ok(TaskResult::Metadata(
+                svc.get_metadata(&id, path.as_deref())?,
+            )),
+            MetaCommand::Unset { id, path } => Ok(TaskResult::One(svc.unset_metadata(&id, &path)?)),
+        },
+
+        TaskCommand::Label(cmd) => match cmd {
+            LabelCommand::Add { id, labels } => Ok(TaskResult::One(svc.add_labels(&id, &labels)?)),
+            LabelCommand::Rm { id, labels } => {
+                Ok(TaskResult::One(svc.remove_labels(&id, &labels)?))
+            }
+        },
+
+        TaskCommand::Comment(cmd) => {
+            match cmd {
+                CommentCommand::Add { id, body, author } => Ok(TaskResult::Comment(
+                    svc.add_comment(&id, author.as_deref(), &body)?,
+                )),
+                CommentCommand::List { id, latest } => {
+                    Ok(TaskResult::Comments(svc.comments(&id, latest)?))
+                }
+                CommentCommand::Delete { id } => {
+                    svc.delete_comment(&id)?;
+                    Ok(TaskResult::Deleted)
+                }
+            }
         }
+
+        // Adding a link needs VCS for validation (see handle_link)
+        TaskCommand::Link(cmd) => handle_link(conn, cmd, None),
+
+        TaskCommand::Claim(args) => Ok(TaskResult::Claim(svc.claim(
+            &args.id,
+            &args.agent,
+            args.ttl,
+        )?)),
+
+        TaskCommand::Heartbeat(args) => Ok(TaskResult::Claim(svc.heartbeat(
+            &args.id,
+            &args.agent,
+            args.ttl,
+        )?)),
+
+        TaskCommand::Release(args) => {
+            svc.release(&args.id, args.agent.as_deref(), args.force)?;
+            Ok(TaskResult::Released)
+        }
+
+        TaskCommand::Pause(args) => Ok(TaskResult::Session(
+            svc.pause(&args.id, args.agent.as_deref())?,
+        )),
+
+        TaskCommand::Resume(args) => Ok(TaskResult::Session(
+            svc.resume(&args.id, args.agent.as_deref())?,
+        )),
+
+        // Workflow commands require VCS - caller must use handle_workflow
+        TaskCommand::Start { .. } | TaskCommand::Complete(_) => Err(OsError::NotARepository),
     }
 }
 
-/// Handle workflow commands (start/complete - VCS required)
+/// Handle workflow commands (start/complete - VCS required). `vcs` serves tasks
+/// without a registered repo; the others open their own.
 pub fn handle_workflow(
     conn: &Connection,
     cmd: TaskCommand,
-    vcs: Box<dyn VcsBackend>,
+    vcs: Option<Box<dyn VcsBackend>>,
 ) -> Result<TaskResult> {
-    let workflow = TaskWorkflowService::new(conn, vcs);
-
     match cmd {
-        TaskCommand::Start { id } => Ok(TaskResult::One(workflow.start_follow_blockers(&id)?)),
+        TaskCommand::Start {
+            id,
+            agent,
+            if_revision,
+            worktree,
+        } => {
+            let workflow = TaskWorkflowService::with_optional_vcs(conn, vcs)
+                .with_agent(agent)
+                .with_expected_revision(if_revision)
+                .with_worktree(worktree);
+            Ok(TaskResult::One(workflow.start_follow_blockers(&id)?))
+        }
 
-        TaskCommand::Complete(args) => Ok(TaskResult::One(workflow.complete_with_learnings(
-            &args.id,
-            args.result.as_deref(),
-            &args.learnings,
-        )?)),
+        TaskCommand::Complete(args) => {
+            let workflow = TaskWorkflowService::with_optional_vcs(conn, vcs)
+                .with_agent(args.agent)
+                .with_expected_revision(args.if_revision)
+                .with_integration(args.integration);
+            Ok(TaskResult::One(workflow.complete_with_learnings(
+                &args.id,
+                args.result.as_deref(),
+                &args.learnings,
+            )?))
+        }
 
         // Non-workflow commands delegate to handle()
         _ => handle(conn, cmd),
     }
 }
 
-/// Handle delete command (VCS optional - best-effort bookmark cleanup)
-pub fn handle_delete(
+/// Handle trash commands. Purging deletes the recorded task branches when
+/// `vcs` is available; without it they are left for `os vcs cleanup`.
+pub fn handle_trash(
     conn: &Connection,
-    cmd: TaskCommand,
+    cmd: TrashCommand,
     vcs: Option<Box<dyn VcsBackend>>,
 ) -> Result<TaskResult> {
-    let TaskCommand::Delete { id } = cmd else {
-        return handle(conn, cmd);
-    };
-
-    // Prefetch bookmarks BEFORE cascade delete removes them
-    let bookmarks = task_repo::get_all_bookmarks(conn, &id)?;
+    match cmd {
+        TrashCommand::List => Ok(TaskResult::Trash(trash::list(conn)?)),
+        TrashCommand::Purge { older_than } => Ok(TaskResult::Purged(trash::purge(
+            conn,
+            older_than,
+            vcs.as_deref(),
+        )?)),
+    }
+}
 
-    // Delete task (cascades to children, learnings, blockers)
+/// Handle link commands. File and commit links are validated against `vcs`
+/// and fail without a repository; other kinds don't need one.
+pub fn handle_link(
+    conn: &Connection,
+    cmd: LinkCommand,
+    vcs: Option<Box<dyn VcsBackend>>,
+) -> Result<TaskResult> {
     let svc = TaskService::new(conn);
-    svc.delete(&id)?;
-
-    // Best-effort bookmark cleanup if VCS available
-    if let Some(vcs) = vcs {
-        for bookmark in bookmarks {
-            if let Err(e) = vcs.delete_bookmark(&bookmark) {
-                eprintln!("warn: failed to delete bookmark {}: {}", bookmark, e);
-            }
+    match cmd {
+        LinkCommand::Add(args) => {
+            // Paths and commits belong to the task's own repo when it has one
+            let vcs = match svc.get(&args.id)?.repo_id {
+                Some(repo_id) => Some(repos::open_backend(conn, &repo_id)?),
+                None => vcs,
+            };
+            let target = links::validate(args.target(), vcs.as_deref())?;
+            Ok(TaskResult::Link(svc.add_link(
+                &args.id,
+                &target,
+                args.note.as_deref(),
+            )?))
+        }
+        LinkCommand::Rm { id } => {
+            svc.remove_link(&id)?;
+            Ok(TaskResult::Deleted)
         }
+        LinkCommand::List { id } => Ok(TaskResult::Links(svc.links(&id)?)),
     }
-
-    Ok(TaskResult::Deleted)
 }
 
-fn build_tree_for_task(conn: &Connection, root_id: &TaskId) -> Result<TaskTree> {
+/// Tree rooted at `root_id`. With `labels`, only branches leading to a matching
+/// task are kept (the root itself always is).
+fn build_tree_for_task(conn: &Connection, root_id: &TaskId, labels: &[String]) -> Result<TaskTree> {
     let svc = TaskService::new(conn);
     let root_task = svc.get(root_id)?;
-    build_tree_recursive(conn, root_task)
+    let graph = TaskGraph::load(conn)?;
+    let mut tree = build_tree_recursive(&graph, root_task);
+    if !labels.is_empty() {
+        tree.children.retain_mut(|c| prune_to_labels(c, labels));
+    }
+    Ok(tree)
 }
 
-fn build_all_trees(conn: &Connection) -> Result<Vec<TaskTree>> {
-    let svc = TaskService::new(conn);
-
-    // Find all milestones (depth = 0)
-    let filter = ListTasksFilter {
-        parent_id: None,
-        ready: false,
-        completed: None,
-        depth: Some(0),
-        ..Default::default()
-    };
-    let mut milestones = svc.list(&filter)?;
-
-    // Sort by priority asc (p0 first), then created_at asc
-    milestones.sort_by(|a, b| {
-        a.priority
-            .cmp(&b.priority)
-            .then_with(|| a.created_at.cmp(&b.created_at))
-    });
-
-    // Build tree for each milestone
-    let mut trees = Vec::new();
-    for milestone in milestones {
-        trees.push(build_tree_recursive(conn, milestone)?);
+/// Trees for every non-archived milestone, in priority order. With `labels`,
+/// milestones without a matching task are dropped and the rest are pruned.
+fn build_all_trees(conn: &Connection, labels: &[String]) -> Result<Vec<TaskTree>> {
+    let graph = TaskGraph::load(conn)?;
+    let mut trees: Vec<TaskTree> = graph
+        .roots()
+        .filter(|m| !m.archived)
+        .map(|m| build_tree_recursive(&graph, m.clone()))
+        .collect();
+    if !labels.is_empty() {
+        trees.retain_mut(|t| prune_to_labels(t, labels));
     }
     Ok(trees)
 }
 
-fn build_tree_recursive(conn: &Connection, task: Task) -> Result<TaskTree> {
-    let svc = TaskService::new(conn);
-    let filter = ListTasksFilter {
-        parent_id: Some(task.id.clone()),
-        ready: false,
-        completed: None,
-        depth: None,
-        ..Default::default()
-    };
-
-    let children_tasks = svc.list(&filter)?;
-    let mut children = Vec::new();
-
-    for child in children_tasks {
-        children.push(build_tree_recursive(conn, child)?);
-    }
-
-    // Sort children by priority asc (p0 first), then created_at asc
-    children.sort_by(|a, b| {
-        a.task
-            .priority
-            .cmp(&b.task.priority)
-            .then_with(|| a.task.created_at.cmp(&b.task.created_at))
-    });
+/// Children in priority order (p0 first), then rank and created_at; archived children are hidden
+fn build_tree_recursive(graph: &TaskGraph, task: Task) -> TaskTree {
+    let children = graph
+        .children(&task.id)
+        .filter(|c| !c.archived)
+        .map(|c| build_tree_recursive(graph, c.clone()))
+        .collect();
+    TaskTree { task, children }
+}
 
-    Ok(TaskTree { task, children })
+/// Drop subtrees without a task carrying `labels`; true if anything is left
+fn prune_to_labels(tree: &mut TaskTree, labels: &[String]) -> bool {
+    tree.children.retain_mut(|c| prune_to_labels(c, labels));
+    !tree.children.is_empty() || has_labels(&tree.task, labels)
 }
 
-fn calculate_progress(conn: &Connection, root_id: Option<&TaskId>) -> Result<TaskProgressResult> {
-    let svc = TaskService::new(conn);
+fn calculate_progress(
+    conn: &Connection,
+    root_id: Option<&TaskId>,
+    labels: &[String],
+) -> Result<TaskProgressResult> {
+    let graph = TaskGraph::load(conn)?;
 
-    // Get all tasks (including archived), optionally filtered by descendant of root
-    let tasks = match root_id {
+    // All tasks (including archived), optionally limited to root and its descendants
+    let tasks: Vec<&Task> = match root_id {
         Some(id) => {
-            // Get all descendants of this task
-            get_descendants(conn, id)?
+            let root = graph
+                .get(id)
+                .ok_or_else(|| OsError::TaskNotFound(id.clone()))?;
+            std::iter::once(root).chain(graph.descendants(id)).collect()
         }
-        None => {
-            // Get all tasks (include archived in total)
-            let filter = ListTasksFilter {
-                parent_id: None,
-                ready: false,
-                completed: None,
-                depth: None,
+        None => graph.list(
+            &ListTasksFilter {
                 archived: None, // Include all (archived and non-archived)
-            };
-            svc.list(&filter)?
-        }
+                ..Default::default()
+            },
+            None,
+        ),
     };
+    let tasks: Vec<&Task> = tasks
+        .into_iter()
+        .filter(|t| has_labels(t, labels))
+        .collect();
 
     let total = tasks.len();
     let completed = tasks.iter().filter(|t| t.completed).count();
@@ -476,58 +1191,6 @@ fn calculate_progress(conn: &Connection, root_id: Option<&TaskId>) -> Result<Tas
     })
 }
 
-fn get_descendants(conn: &Connection, root_id: &TaskId) -> Result<Vec<Task>> {
-    let svc = TaskService::new(conn);
-    let root = svc.get(root_id)?;
-
-    let mut result = vec![root];
-    let mut queue = vec![root_id.clone()];
-
-    while let Some(parent_id) = queue.pop() {
-        let children = svc.list(&ListTasksFilter {
-            parent_id: Some(parent_id),
-            ready: false,
-            completed: None,
-            depth: None,
-            archived: None, // Include all (archived and non-archived)
-        })?;
-
-        for child in children {
-            queue.push(child.id.clone());
-            result.push(child);
-        }
-    }
-
-    Ok(result)
-}
-
-fn search_tasks(conn: &Connection, query: &str) -> Result<Vec<Task>> {
-    let svc = TaskService::new(conn);
-
-    // Simple substring search for now (FTS can be added later)
-    let all_tasks = svc.list(&ListTasksFilter {
-        parent_id: None,
-        ready: false,
-        completed: None,
-        depth: None,
-        ..Default::default()
-    })?;
-
-    let query_lower = query.to_lowercase();
-    let matching = all_tasks
-        .into_iter()
-        .filter(|t| {
-            t.description.to_lowercase().contains(&query_lower)
-                || t.context.to_lowercase().contains(&query_lower)
-                || t.result
-                    .as_ref()
-                    .is_some_and(|r| r.to_lowercase().contains(&query_lower))
-        })
-        .collect();
-
-    Ok(matching)
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -540,6 +1203,36 @@ mod tests {
         conn
     }
 
+    #[test]
+    fn test_parse_ttl() {
+        assert_eq!(parse_ttl("90s").unwrap(), chrono::Duration::seconds(90));
+        assert_eq!(parse_ttl("30m").unwrap(), chrono::Duration::minutes(30));
+        assert_eq!(parse_ttl("1h30m").unwrap(), chrono::Duration::minutes(90));
+        assert_eq!(parse_ttl("2d").unwrap(), chrono::Duration::days(2));
+        for bad in ["", "30", "m", "0m", "5w", "-5m"] {
+            assert!(parse_ttl(bad).is_err(), "{bad}");
+        }
+    }
+
+    #[test]
+    fn test_parse_due_and_defer_dates() {
+        assert_eq!(
+            parse_due("2026-03-01").unwrap().to_rfc3339(),
+            "2026-03-01T23:59:59+00:00"
+        );
+        assert_eq!(
+            parse_defer("2026-03-01").unwrap().to_rfc3339(),
+            "2026-03-01T00:00:00+00:00"
+        );
+        assert_eq!(
+            parse_due("2026-03-01T12:00:00+02:00").unwrap().to_rfc3339(),
+            "2026-03-01T10:00:00+00:00"
+        );
+        let in_two_days = parse_defer("2d").unwrap() - Utc::now();
+        assert!(in_two_days > chrono::Duration::hours(47));
+        assert!(parse_due("next week").is_err());
+    }
+
     #[test]
     fn test_next_ready_returns_highest_priority_ready_task() {
         let conn = setup_db();
@@ -553,6 +1246,10 @@ mod tests {
                 parent_id: None,
                 priority: None,
                 blocked_by: vec![],
+                labels: vec![],
+                due_at: None,
+                defer_until: None,
+                repo_id: None,
             })
             .unwrap();
 
@@ -564,6 +1261,10 @@ mod tests {
                 parent_id: Some(milestone.id.clone()),
                 priority: Some(1),
                 blocked_by: vec![],
+                labels: vec![],
+                due_at: None,
+                defer_until: None,
+                repo_id: None,
             })
             .unwrap();
 
@@ -574,6 +1275,10 @@ mod tests {
                 parent_id: Some(milestone.id.clone()),
                 priority: Some(0),
                 blocked_by: vec![],
+                labels: vec![],
+                due_at: None,
+                defer_until: None,
+                repo_id: None,
             })
             .unwrap();
 
@@ -582,6 +1287,11 @@ mod tests {
             &conn,
             TaskCommand::NextReady(NextReadyArgs {
                 milestone: Some(milestone.id.clone()),
+                labels: vec![],
+                unclaimed: false,
+                agent: None,
+                comments: None,
+                repo: None,
             }),
         )
         .unwrap();
@@ -606,6 +1316,10 @@ mod tests {
                 parent_id: None,
                 priority: None,
                 blocked_by: vec![],
+                labels: vec![],
+                due_at: None,
+                defer_until: None,
+                repo_id: None,
             })
             .unwrap();
 
@@ -616,6 +1330,10 @@ mod tests {
                 parent_id: Some(milestone.id.clone()),
                 priority: Some(0),
                 blocked_by: vec![],
+                labels: vec![],
+                due_at: None,
+                defer_until: None,
+                repo_id: None,
             })
             .unwrap();
 
@@ -626,6 +1344,10 @@ mod tests {
                 parent_id: Some(milestone.id.clone()),
                 priority: Some(0),
                 blocked_by: vec![blocker.id.clone()],
+                labels: vec![],
+                due_at: None,
+                defer_until: None,
+                repo_id: None,
             })
             .unwrap();
 
@@ -636,6 +1358,10 @@ mod tests {
                 parent_id: Some(milestone.id.clone()),
                 priority: Some(1),
                 blocked_by: vec![],
+                labels: vec![],
+                due_at: None,
+                defer_until: None,
+                repo_id: None,
             })
             .unwrap();
 
@@ -644,6 +1370,11 @@ mod tests {
             &conn,
             TaskCommand::NextReady(NextReadyArgs {
                 milestone: Some(milestone.id.clone()),
+                labels: vec![],
+                unclaimed: false,
+                agent: None,
+                comments: None,
+                repo: None,
             }),
         )
         .unwrap();
@@ -668,6 +1399,10 @@ mod tests {
                 parent_id: None,
                 priority: None,
                 blocked_by: vec![],
+                labels: vec![],
+                due_at: None,
+                defer_until: None,
+                repo_id: None,
             })
             .unwrap();
 
@@ -678,6 +1413,10 @@ mod tests {
                 parent_id: Some(milestone.id.clone()),
                 priority: None,
                 blocked_by: vec![],
+                labels: vec![],
+                due_at: None,
+                defer_until: None,
+                repo_id: None,
             })
             .unwrap();
 
@@ -688,6 +1427,10 @@ mod tests {
                 parent_id: Some(task1.id.clone()),
                 priority: None,
                 blocked_by: vec![],
+                labels: vec![],
+                due_at: None,
+                defer_until: None,
+                repo_id: None,
             })
             .unwrap();
 
@@ -696,6 +1439,7 @@ mod tests {
             &conn,
             TaskCommand::Tree(TreeArgs {
                 id: Some(milestone.id.clone()),
+                labels: vec![],
             }),
         )
         .unwrap();
@@ -722,6 +1466,10 @@ mod tests {
             parent_id: None,
             priority: None,
             blocked_by: vec![],
+            labels: vec![],
+            due_at: None,
+            defer_until: None,
+            repo_id: None,
         })
         .unwrap();
 
@@ -731,6 +1479,10 @@ mod tests {
             parent_id: None,
             priority: None,
             blocked_by: vec![],
+            labels: vec![],
+            due_at: None,
+            defer_until: None,
+            repo_id: None,
         })
         .unwrap();
 
@@ -740,6 +1492,10 @@ mod tests {
             parent_id: None,
             priority: None,
             blocked_by: vec![],
+            labels: vec![],
+            due_at: None,
+            defer_until: None,
+            repo_id: None,
         })
         .unwrap();
 
@@ -748,15 +1504,18 @@ mod tests {
             &conn,
             TaskCommand::Search(SearchArgs {
                 query: "feature".to_string(),
+                all: false,
+                limit: 50,
             }),
         )
         .unwrap();
 
-        if let TaskResult::Many(tasks) = result {
-            assert_eq!(tasks.len(), 1);
-            assert_eq!(tasks[0].description, "Implement feature");
+        if let TaskResult::Search(hits) = result {
+            assert_eq!(hits.len(), 1);
+            assert_eq!(hits[0].item.description, "Implement feature");
+            assert!(hits[0].snippet.contains("**feature**"));
         } else {
-            panic!("Expected Many result");
+            panic!("Expected Search result");
         }
     }
 
@@ -771,6 +1530,10 @@ mod tests {
             parent_id: None,
             priority: None,
             blocked_by: vec![],
+            labels: vec![],
+            due_at: None,
+            defer_until: None,
+            repo_id: None,
         })
         .unwrap();
 
@@ -780,6 +1543,10 @@ mod tests {
             parent_id: None,
             priority: None,
             blocked_by: vec![],
+            labels: vec![],
+            due_at: None,
+            defer_until: None,
+            repo_id: None,
         })
         .unwrap();
 
@@ -788,15 +1555,244 @@ mod tests {
             &conn,
             TaskCommand::Search(SearchArgs {
                 query: "backend".to_string(),
+                all: false,
+                limit: 50,
             }),
         )
         .unwrap();
 
-        if let TaskResult::Many(tasks) = result {
-            assert_eq!(tasks.len(), 1);
-            assert_eq!(tasks[0].description, "Task 1");
+        if let TaskResult::Search(hits) = result {
+            assert_eq!(hits.len(), 1);
+            assert_eq!(hits[0].item.description, "Task 1");
         } else {
-            panic!("Expected Many result");
+            panic!("Expected Search result");
+        }
+    }
+
+    #[test]
+    fn test_tree_and_progress_filter_by_label() {
+        let conn = setup_db();
+        let svc = TaskService::new(&conn);
+        let create = |description: &str, parent: Option<&TaskId>, labels: &[&str]| {
+            svc.create(&CreateTaskInput {
+                description: description.to_string(),
+                parent_id: parent.cloned(),
+                labels: labels.iter().map(|l| l.to_string()).collect(),
+                ..Default::default()
+            })
+            .unwrap()
+            .id
+        };
+        let m1 = create("M1", None, &[]);
+        let t1 = create("T1", Some(&m1), &[]);
+        let s1 = create("S1", Some(&t1), &["backend"]);
+        create("T2", Some(&m1), &["frontend"]);
+        let m2 = create("M2", None, &["frontend"]);
+        create("T3", Some(&m2), &[]);
+
+        let backend = vec!["backend".to_string()];
+        let TaskResult::Trees(trees) = handle(
+            &conn,
+            TaskCommand::Tree(TreeArgs {
+                id: None,
+                labels: backend.clone(),
+            }),
+        )
+        .unwrap() else {
+            panic!("Expected Trees result");
+        };
+        // M2 has no backend work; M1 keeps only the path down to S1
+        assert_eq!(trees.len(), 1);
+        assert_eq!(trees[0].task.id, m1);
+        assert_eq!(trees[0].children.len(), 1);
+        assert_eq!(trees[0].children[0].task.id, t1);
+        assert_eq!(trees[0].children[0].children[0].task.id, s1);
+
+        let TaskResult::Progress(progress) = handle(
+            &conn,
+            TaskCommand::Progress(ProgressArgs {
+                id: None,
+                labels: vec!["frontend".to_string()],
+            }),
+        )
+        .unwrap() else {
+            panic!("Expected Progress result");
+        };
+        assert_eq!(progress.total, 2);
+        assert_eq!(progress.ready, 2);
+    }
+
+    #[test]
+    fn test_comment_thread_and_get_with_latest_comments() {
+        let conn = setup_db();
+        let svc = TaskService::new(&conn);
+        let task = svc
+            .create(&CreateTaskInput {
+                description: "Flaky test".to_string(),
+                ..Default::default()
+            })
+            .unwrap();
+
+        for body in ["tried X", "X failed because Y", "trying Z"] {
+            handle(
+                &conn,
+                TaskCommand::Comment(CommentCommand::Add {
+                    id: task.id.clone(),
+                    body: body.to_string(),
+                    author: Some("agent-1".to_string()),
+                }),
+            )
+            .unwrap();
         }
+
+        let get = |comments| match handle(
+            &conn,
+            TaskCommand::Get {
+                id: task.id.clone(),
+                comments,
+            },
+        )
+        .unwrap()
+        {
+            TaskResult::OneWithContext(t) => t,
+            _ => panic!("Expected OneWithContext result"),
+        };
+        assert!(get(None).comments.is_none());
+        let latest = get(Some(2)).comments.unwrap();
+        let bodies: Vec<&str> = latest.iter().map(|c| c.body.as_str()).collect();
+        assert_eq!(bodies, vec!["X failed because Y", "trying Z"]);
+
+        handle(
+            &conn,
+            TaskCommand::Comment(CommentCommand::Delete {
+                id: latest[1].id.clone(),
+            }),
+        )
+        .unwrap();
+        let TaskResult::Comments(all) = handle(
+            &conn,
+            TaskCommand::Comment(CommentCommand::List {
+                id: task.id.clone(),
+                latest: None,
+            }),
+        )
+        .unwrap() else {
+            panic!("Expected Comments result");
+        };
+        assert_eq!(all.len(), 2);
+        assert_eq!(all[0].author.as_deref(), Some("agent-1"));
+    }
+
+    #[test]
+    fn test_links_validated_against_repo_and_shown_in_get() {
+        use crate::testutil::{GitTestRepo, TestRepo};
+        use crate::vcs::GixBackend;
+
+        let repo = GitTestRepo::new().unwrap();
+        repo.write_file("src/auth.rs", "fn login() {}\n").unwrap();
+        let head = repo.commit("initial commit").unwrap();
+        let vcs = || -> Option<Box<dyn VcsBackend>> {
+            Some(Box::new(GixBackend::open(repo.path()).unwrap()))
+        };
+
+        let conn = setup_db();
+        let svc = TaskService::new(&conn);
+        let task = svc
+            .create(&CreateTaskInput {
+                description: "Harden login".to_string(),
+                ..Default::default()
+            })
+            .unwrap();
+        let other = svc
+            .create(&CreateTaskInput {
+                description: "Unrelated".to_string(),
+                ..Default::default()
+            })
+            .unwrap();
+        let add = |target: (&str, &str), lines| {
+            let mut args = LinkAddArgs {
+                id: task.id.clone(),
+                file: None,
+                lines,
+                url: None,
+                commit: None,
+                issue: None,
+                note: None,
+            };
+            let value = Some(target.1.to_string());
+            match target.0 {
+                "file" => args.file = value,
+                "url" => args.url = value,
+                "commit" => args.commit = value,
+                _ => args.issue = value,
+            }
+            handle_link(&conn, LinkCommand::Add(args), vcs())
+        };
+
+        let TaskResult::Link(range) = add(("file", "./src/auth.rs"), Some((1, 1))).unwrap() else {
+            panic!("Expected Link result");
+        };
+        assert_eq!(range.target, "src/auth.rs");
+        let TaskResult::Link(commit) = add(("commit", "HEAD"), None).unwrap() else {
+            panic!("Expected Link result");
+        };
+        assert_eq!(commit.target, head);
+        add(("issue", "owner/repo#42"), None).unwrap();
+        assert!(matches!(
+            add(("file", "src/missing.rs"), None),
+            Err(OsError::LinkPathNotFound(_))
+        ));
+        assert!(matches!(
+            add(("commit", "no-such-rev"), None),
+            Err(OsError::CommitNotFound(_))
+        ));
+
+        let TaskResult::OneWithContext(with_ctx) = handle(
+            &conn,
+            TaskCommand::Get {
+                id: task.id.clone(),
+                comments: None,
+            },
+        )
+        .unwrap() else {
+            panic!("Expected OneWithContext result");
+        };
+        assert_eq!(with_ctx.links.len(), 3);
+        assert_eq!(with_ctx.links[0], range);
+
+        let linked = svc
+            .list(&ListTasksFilter {
+                linked: Some("src".to_string()),
+                ..Default::default()
+            })
+            .unwrap();
+        assert_eq!(linked.len(), 1);
+        assert_eq!(linked[0].id, task.id);
+        assert_ne!(linked[0].id, other.id);
+
+        handle(
+            &conn,
+            TaskCommand::Link(LinkCommand::Rm {
+                id: range.id.clone(),
+            }),
+        )
+        .unwrap();
+        let TaskResult::Links(links) = handle(
+            &conn,
+            TaskCommand::Link(LinkCommand::List {
+                id: task.id.clone(),
+            }),
+        )
+        .unwrap() else {
+            panic!("Expected Links result");
+        };
+        assert_eq!(links.len(), 2);
+        assert!(svc
+            .list(&ListTasksFilter {
+                linked: Some("src/auth.rs".to_string()),
+                ..Default::default()
+            })
+            .unwrap()
+            .is_empty());
     }
 }
@@ -0,0 +1,135 @@
+use std::collections::HashMap;
+use std::path::Path;
+
+use clap::{Args, Subcommand};
+use rusqlite::Connection;
+
+use crate::core::template::{
+    self, is_variable_name, AppliedTemplate, TemplateStore, TemplateSummary,
+};
+use crate::error::Result;
+use crate::id::TaskId;
+
+/// Parse TaskId from CLI string (requires prefix)
+fn parse_task_id(s: &str) -> std::result::Result<TaskId, String> {
+    s.parse().map_err(|e| format!("{e}"))
+}
+
+/// Parse `name=value` for a template placeholder
+fn parse_var(s: &str) -> std::result::Result<(String, String), String> {
+    let (name, value) = s
+        .split_once('=')
+        .ok_or_else(|| format!("expected NAME=VALUE, got '{s}'"))?;
+    let name = name.trim();
+    if !is_variable_name(name) {
+        return Err(format!(
+            "invalid variable name '{name}' (letters, digits, - and _)"
+        ));
+    }
+    Ok((name.to_string(), value.to_string()))
+}
+
+#[derive(Subcommand, Clone)]
+pub enum TemplateCommand {
+    /// Capture a task subtree as a reusable template
+    #[command(
+        about = "Capture a task subtree as a template",
+        long_about = r#"
+Capture a milestone (or any task) and its descendants into
+.overseer/templates/NAME.json.
+
+Descriptions, contexts, priorities, labels, sibling order and blockers
+between tasks of the subtree are kept. Lifecycle state, due dates, metadata
+and blockers on tasks outside the subtree are not. Cancelled and archived
+tasks are skipped.
+
+--var NAME=VALUE replaces every occurrence of VALUE with a {{NAME}}
+placeholder. Placeholders can also be added by editing the file.
+
+Examples:
+  os template save task_01JQAZ... endpoint
+  os template save task_01JQAZ... endpoint --var resource=users --force
+"#
+    )]
+    Save(SaveArgs),
+
+    /// Instantiate a template
+    #[command(
+        about = "Create tasks from a template",
+        long_about = r#"
+Create a fresh copy of a template's task tree, substituting {{NAME}}
+placeholders with --var values. Every placeholder needs a value.
+
+Without --parent the template root becomes a new milestone. All tasks and
+blockers are created in one transaction: if any step fails (depth limit,
+inactive parent) nothing is created.
+
+Examples:
+  os template apply endpoint --var resource=orders
+  os template apply endpoint --parent task_01JQAZ... --var resource=orders
+"#
+    )]
+    Apply(ApplyArgs),
+
+    /// List saved templates
+    List,
+}
+
+#[derive(Args, Clone)]
+pub struct SaveArgs {
+    /// Root of the subtree to capture
+    #[arg(value_parser = parse_task_id)]
+    pub id: TaskId,
+
+    /// Template name (letters, digits, - and _)
+    pub name: String,
+
+    /// Turn literal VALUE into a {{NAME}} placeholder (repeatable)
+    #[arg(long = "var", value_name = "NAME=VALUE", value_parser = parse_var, action = clap::ArgAction::Append)]
+    pub vars: Vec<(String, String)>,
+
+    /// Overwrite an existing template
+    #[arg(long)]
+    pub force: bool,
+}
+
+#[derive(Args, Clone)]
+pub struct ApplyArgs {
+    /// Template name
+    pub name: String,
+
+    /// Parent for the template root (default: create a new milestone)
+    #[arg(long, value_parser = parse_task_id)]
+    pub parent: Option<TaskId>,
+
+    /// Value for a {{NAME}} placeholder (repeatable)
+    #[arg(long = "var", value_name = "NAME=VALUE", value_parser = parse_var, action = clap::ArgAction::Append)]
+    pub vars: Vec<(String, String)>,
+}
+
+pub enum TemplateResult {
+    Saved(TemplateSummary),
+    Applied(AppliedTemplate),
+    Many(Vec<TemplateSummary>),
+}
+
+pub fn handle(conn: &Connection, db_path: &Path, cmd: TemplateCommand) -> Result<TemplateResult> {
+    let store = TemplateStore::for_db(db_path);
+    match cmd {
+        TemplateCommand::Save(args) => {
+            let template = template::capture(conn, &args.id, &args.name, &args.vars)?;
+            Ok(TemplateResult::Saved(store.save(&template, args.force)?))
+        }
+        TemplateCommand::Apply(args) => {
+            let template = store.load(&args.name)?;
+            let vars: HashMap<String, String> = args.vars.into_iter().collect();
+            Ok(TemplateResult::Applied(template::instantiate(
+                conn,
+                &template,
+                args.parent.as_ref(),
+                &vars,
+            )?))
+        }
+        TemplateCommand::List => Ok(TemplateResult::Many(store.list()?)),
+    }
+}
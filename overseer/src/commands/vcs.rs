@@ -2,7 +2,7 @@ use clap::{Args, Subcommand};
 use rusqlite::Connection;
 use serde::{Deserialize, Serialize};
 
-use crate::db::task_repo;
+use crate::db::{task_repo, trash_repo};
 use crate::error::Result;
 use crate::id::TaskId;
 use crate::vcs::{self, CommitResult, DiffEntry, LogEntry, VcsInfo, VcsStatus};
@@ -21,7 +21,7 @@ pub enum VcsCommand {
 List and optionally delete orphaned task branches/bookmarks.
 
 Orphaned branches are those matching 'task/*' pattern where:
-  - The task no longer exists in the database, OR
+  - The task no longer exists in the database (and is not in the trash), OR
   - The task is completed (and branch wasn't cleaned up)
 
 Examples:
@@ -40,7 +40,13 @@ pub struct LogArgs {
 
 #[derive(Args)]
 pub struct DiffArgs {
+    /// Revision to diff from (default: HEAD)
     pub base: Option<String>,
+    /// Revision to diff to (default: the working copy)
+    pub head: Option<String>,
+    /// Include the unified patch of each file
+    #[arg(long)]
+    pub patch: bool,
 }
 
 #[derive(Args)]
@@ -115,7 +121,7 @@ pub fn handle(cmd: VcsCommand) -> Result<VcsResult> {
 
         VcsCommand::Diff(args) => {
             let backend = vcs::get_backend(&cwd)?;
-            let diff = backend.diff(args.base.as_deref())?;
+            let diff = backend.diff(args.base.as_deref(), args.head.as_deref(), args.patch)?;
             Ok(VcsResult::Diff(diff))
         }
 
@@ -157,6 +163,8 @@ pub fn handle_cleanup(conn: &Connection, args: CleanupArgs) -> Result<VcsResult>
 
         // Check if task exists and its state
         match task_repo::get_task(conn, &task_id)? {
+            // Trashed tasks keep their branches until the trash is purged
+            None if trash_repo::containing_root(conn, &task_id)?.is_some() => {}
             None => {
                 // Task doesn't exist - orphaned
                 orphaned.push(OrphanedBranch {
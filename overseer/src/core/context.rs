@@ -2,7 +2,7 @@ use rusqlite::Connection;
 use serde::Serialize;
 
 use crate::db::learning_repo::Learning;
-use crate::db::{learning_repo, task_repo};
+use crate::db::{comment_repo, learning_repo, link_repo, task_repo, Comment, TaskLink};
 use crate::error::Result;
 use crate::id::TaskId;
 use crate::types::Task;
@@ -37,6 +37,22 @@ pub struct TaskWithContext {
     pub progressive_context: ProgressiveContext,
     #[serde(rename = "learnings")]
     pub inherited_learnings: InheritedLearnings,
+    /// Files, code ranges, URLs, commits and issues the task points at
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub links: Vec<TaskLink>,
+    /// Latest comments, oldest first; only when asked for (`--comments N`)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub comments: Option<Vec<Comment>>,
+}
+
+impl TaskWithContext {
+    /// Attach the task's latest `n` comments (`None` leaves them out).
+    pub fn with_recent_comments(mut self, conn: &Connection, n: Option<usize>) -> Result<Self> {
+        if let Some(n) = n {
+            self.comments = Some(comment_repo::list_comments(conn, &self.task.id, Some(n))?);
+        }
+        Ok(self)
+    }
 }
 
 pub fn get_ancestor_chain(conn: &Connection, task_id: &TaskId) -> Result<Vec<Task>> {
@@ -56,66 +72,47 @@ pub fn get_ancestor_chain(conn: &Connection, task_id: &TaskId) -> Result<Vec<Tas
     Ok(chain)
 }
 
+/// `chain` from [`get_ancestor_chain`] split into the direct parent (unless it
+/// is the root) and the root, excluding the task itself.
+fn intermediate_parent_and_root(chain: &[Task]) -> (Option<&Task>, Option<&Task>) {
+    match chain {
+        [] | [_] => (None, None),
+        [_, root] => (None, Some(root)),
+        [_, parent, .., root] => (Some(parent), Some(root)),
+    }
+}
+
 pub fn build_progressive_context(conn: &Connection, task: &Task) -> Result<ProgressiveContext> {
     let chain = get_ancestor_chain(conn, &task.id)?;
-
-    let own = task.context.clone();
-    let mut parent_ctx = None;
-    let mut milestone_ctx = None;
-
-    for (i, ancestor) in chain.iter().enumerate() {
-        if i == 0 {
-            continue;
-        }
-
-        let depth = task_repo::get_task_depth(conn, &ancestor.id)?;
-
-        if depth == 1 && parent_ctx.is_none() {
-            parent_ctx = Some(ancestor.context.clone());
-        } else if depth == 0 {
-            milestone_ctx = Some(ancestor.context.clone());
-        }
-    }
+    let (parent, root) = intermediate_parent_and_root(&chain);
 
     Ok(ProgressiveContext {
-        own,
-        parent: parent_ctx.filter(|s| !s.is_empty()),
-        milestone: milestone_ctx.filter(|s| !s.is_empty()),
+        own: task.context.clone(),
+        parent: parent.map(|p| p.context.clone()).filter(|s| !s.is_empty()),
+        milestone: root.map(|r| r.context.clone()).filter(|s| !s.is_empty()),
     })
 }
 
 pub fn build_inherited_learnings(conn: &Connection, task: &Task) -> Result<InheritedLearnings> {
     let chain = get_ancestor_chain(conn, &task.id)?;
-
-    let own = learning_repo::list_learnings(conn, &task.id)?;
-    let mut parent_learnings = Vec::new();
-    let mut milestone_learnings = Vec::new();
-
-    for (i, ancestor) in chain.iter().enumerate() {
-        if i == 0 {
-            continue;
-        }
-
-        let depth = task_repo::get_task_depth(conn, &ancestor.id)?;
-        let learnings = learning_repo::list_learnings(conn, &ancestor.id)?;
-
-        if depth == 1 {
-            parent_learnings.extend(learnings);
-        } else if depth == 0 {
-            milestone_learnings.extend(learnings);
-        }
-    }
+    let (parent, root) = intermediate_parent_and_root(&chain);
+    let learnings_of = |t: Option<&Task>| -> Result<Vec<Learning>> {
+        t.map_or(Ok(Vec::new()), |t| {
+            learning_repo::list_learnings(conn, &t.id)
+        })
+    };
 
     Ok(InheritedLearnings {
-        own,
-        parent: parent_learnings,
-        milestone: milestone_learnings,
+        own: learning_repo::list_learnings(conn, &task.id)?,
+        parent: learnings_of(parent)?,
+        milestone: learnings_of(root)?,
     })
 }
 
 pub fn get_task_with_context(conn: &Connection, mut task: Task) -> Result<TaskWithContext> {
     let progressive_context = build_progressive_context(conn, &task)?;
     let inherited_learnings = build_inherited_learnings(conn, &task)?;
+    let links = link_repo::list_links(conn, &task.id)?;
 
     // Clear task's own context_chain and learnings to avoid duplicate JSON keys
     // when TaskWithContext serializes (flatten + explicit fields would conflict)
@@ -126,6 +123,8 @@ pub fn get_task_with_context(conn: &Connection, mut task: Task) -> Result<TaskWi
         task,
         progressive_context,
         inherited_learnings,
+        links,
+        comments: None,
     })
 }
 
@@ -295,6 +294,35 @@ mod tests {
         assert_eq!(ctx.milestone, Some("milestone context".to_string()));
     }
 
+    #[test]
+    fn test_build_progressive_context_below_depth_two() {
+        let conn = rusqlite::Connection::open_in_memory().unwrap();
+        init_schema(&conn).unwrap();
+
+        let mut parent_id = None;
+        let mut task = None;
+        for name in ["root", "epic", "story", "leaf"] {
+            let created = create_task(
+                &conn,
+                &CreateTaskInput {
+                    description: name.to_string(),
+                    context: Some(format!("{name} context")),
+                    parent_id: parent_id.take(),
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+            parent_id = Some(created.id.clone());
+            task = Some(created);
+        }
+
+        // Direct parent plus root; intermediate ancestors are skipped
+        let ctx = build_progressive_context(&conn, &task.unwrap()).unwrap();
+        assert_eq!(ctx.own, "leaf context");
+        assert_eq!(ctx.parent, Some("story context".to_string()));
+        assert_eq!(ctx.milestone, Some("root context".to_string()));
+    }
+
     #[test]
     fn test_build_progressive_context_empty_contexts() {
         let conn = rusqlite::Connection::open_in_memory().unwrap();
@@ -0,0 +1,691 @@
+//! Release-mode integrity checks over the database and the VCS.
+//!
+//! Service methods keep the invariants on every write, but rows written by older
+//! versions, imports, or manual edits can still violate them. `check` reports each
+//! problem with a stable code; `repair` applies the fixes that cannot lose data.
+
+use std::collections::{HashMap, HashSet};
+
+use chrono::Utc;
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+
+use crate::core::repos;
+use crate::db::event_repo::{self, EventBody};
+use crate::db::{settings_repo, task_repo};
+use crate::error::Result;
+use crate::id::{RepoId, TaskId};
+use crate::types::Task;
+use crate::vcs::VcsBackend;
+
+/// Stable identifier for each kind of problem. Never renamed once released.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum IssueCode {
+    #[serde(rename = "sqlite.integrity")]
+    SqliteIntegrity,
+    #[serde(rename = "lifecycle.completedAndCancelled")]
+    CompletedAndCancelled,
+    #[serde(rename = "lifecycle.archivedNotFinished")]
+    ArchivedNotFinished,
+    #[serde(rename = "lifecycle.missingCompletedAt")]
+    MissingCompletedAt,
+    #[serde(rename = "lifecycle.missingCancelledAt")]
+    MissingCancelledAt,
+    #[serde(rename = "lifecycle.missingArchivedAt")]
+    MissingArchivedAt,
+    #[serde(rename = "graph.danglingParent")]
+    DanglingParent,
+    #[serde(rename = "graph.danglingBlocker")]
+    DanglingBlocker,
+    #[serde(rename = "graph.parentCycle")]
+    ParentCycle,
+    #[serde(rename = "graph.blockerCycle")]
+    BlockerCycle,
+    #[serde(rename = "graph.depthExceeded")]
+    DepthExceeded,
+    #[serde(rename = "graph.archivedWithActiveChildren")]
+    ArchivedWithActiveChildren,
+    #[serde(rename = "vcs.missingBranch")]
+    MissingBranch,
+}
+
+impl IssueCode {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::SqliteIntegrity => "sqlite.integrity",
+            Self::CompletedAndCancelled => "lifecycle.completedAndCancelled",
+            Self::ArchivedNotFinished => "lifecycle.archivedNotFinished",
+            Self::MissingCompletedAt => "lifecycle.missingCompletedAt",
+            Self::MissingCancelledAt => "lifecycle.missingCancelledAt",
+            Self::MissingArchivedAt => "lifecycle.missingArchivedAt",
+            Self::DanglingParent => "graph.danglingParent",
+            Self::DanglingBlocker => "graph.danglingBlocker",
+            Self::ParentCycle => "graph.parentCycle",
+            Self::BlockerCycle => "graph.blockerCycle",
+            Self::DepthExceeded => "graph.depthExceeded",
+            Self::ArchivedWithActiveChildren => "graph.archivedWithActiveChildren",
+            Self::MissingBranch => "vcs.missingBranch",
+        }
+    }
+}
+
+/// Safe repair for an issue. Only set for problems whose fix cannot lose data.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Repair {
+    /// Backfill a missing state timestamp from `updated_at`
+    BackfillTimestamp {
+        task_id: TaskId,
+        column: &'static str,
+    },
+    /// Drop a blocker row whose blocker task no longer exists
+    DeleteBlocker { task_id: TaskId, blocker_id: TaskId },
+    /// Forget a branch that no longer exists in the repository
+    ClearBookmark { task_id: TaskId },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Issue {
+    pub code: IssueCode,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub task_id: Option<TaskId>,
+    pub message: String,
+    /// Whether `--fix` can repair this issue
+    pub fixable: bool,
+    /// Set once `--fix` repaired it
+    pub fixed: bool,
+    #[serde(skip)]
+    repair: Option<Repair>,
+}
+
+impl Issue {
+    fn new(code: IssueCode, task_id: Option<&TaskId>, message: String) -> Self {
+        Self {
+            code,
+            task_id: task_id.cloned(),
+            message,
+            fixable: false,
+            fixed: false,
+            repair: None,
+        }
+    }
+
+    fn with_repair(mut self, repair: Repair) -> Self {
+        self.fixable = true;
+        self.repair = Some(repair);
+        self
+    }
+}
+
+/// Run every check. VCS checks are skipped when `vcs` is None (not in a repository).
+pub fn check(conn: &Connection, vcs: Option<&dyn VcsBackend>) -> Result<Vec<Issue>> {
+    let mut issues = check_sqlite(conn)?;
+
+    let tasks = task_repo::list_all_rows(conn)?;
+    let by_id: HashMap<&TaskId, &Task> = tasks.iter().map(|t| (&t.id, t)).collect();
+
+    for task in &tasks {
+        check_lifecycle(task, &mut issues);
+    }
+    let deepest = settings_repo::load(conn)?.deepest() as usize;
+    check_hierarchy(&tasks, &by_id, deepest, &mut issues);
+    check_blockers(conn, &by_id, &mut issues)?;
+    check_branches(conn, &tasks, vcs, &mut issues)?;
+    Ok(issues)
+}
+
+fn check_sqlite(conn: &Connection) -> Result<Vec<Issue>> {
+    let mut stmt = conn.prepare("PRAGMA integrity_check")?;
+    let messages = stmt
+        .query_map([], |row| row.get::<_, String>(0))?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+    Ok(messages
+        .into_iter()
+        .filter(|m| m != "ok")
+        .map(|m| Issue::new(IssueCode::SqliteIntegrity, None, m))
+        .collect())
+}
+
+/// Same rules as `Task::validate_lifecycle_invariants`, but reporting every violation.
+fn check_lifecycle(task: &Task, issues: &mut Vec<Issue>) {
+    let id = Some(&task.id);
+    if task.completed && task.cancelled {
+        issues.push(Issue::new(
+            IssueCode::CompletedAndCancelled,
+            id,
+            "Task is both completed and cancelled".into(),
+        ));
+    }
+    if task.archived && !task.is_finished_for_hierarchy() {
+        issues.push(Issue::new(
+            IssueCode::ArchivedNotFinished,
+            id,
+            "Archived task is neither completed nor cancelled".into(),
+        ));
+    }
+
+    let timestamps = [
+        (
+            task.completed && task.completed_at.is_none(),
+            IssueCode::MissingCompletedAt,
+            "completed_at",
+            "Completed task has no completed_at",
+        ),
+        (
+            task.cancelled && task.cancelled_at.is_none(),
+            IssueCode::MissingCancelledAt,
+            "cancelled_at",
+            "Cancelled task has no cancelled_at",
+        ),
+        (
+            task.archived && task.archived_at.is_none(),
+            IssueCode::MissingArchivedAt,
+            "archived_at",
+            "Archived task has no archived_at",
+        ),
+    ];
+    for (missing, code, column, message) in timestamps {
+        if missing {
+            issues.push(Issue::new(code, id, message.into()).with_repair(
+                Repair::BackfillTimestamp {
+                    task_id: task.id.clone(),
+                    column,
+                },
+            ));
+        }
+    }
+}
+
+/// Dangling parents, parent cycles, tasks deeper than `deepest`, and archived
+/// tasks with active children.
+fn check_hierarchy(
+    tasks: &[Task],
+    by_id: &HashMap<&TaskId, &Task>,
+    deepest: usize,
+    issues: &mut Vec<Issue>,
+) {
+    let mut in_cycle: HashSet<&TaskId> = HashSet::new();
+    let mut children: HashMap<&TaskId, Vec<&Task>> = HashMap::new();
+    for task in tasks {
+        if let Some(parent_id) = &task.parent_id {
+            children.entry(parent_id).or_default().push(task);
+        }
+    }
+
+    for task in tasks {
+        if let Some(parent_id) = &task.parent_id {
+            if !by_id.contains_key(parent_id) {
+                issues.push(Issue::new(
+                    IssueCode::DanglingParent,
+                    Some(&task.id),
+                    format!("Parent {} does not exist", parent_id),
+                ));
+            }
+        }
+
+        // Walk up the parent chain; a repeat means a cycle
+        let mut seen = vec![&task.id];
+        let mut current = task;
+        while let Some(parent) = current.parent_id.as_ref().and_then(|p| by_id.get(p)) {
+            if let Some(pos) = seen.iter().position(|id| **id == parent.id) {
+                let cycle = &seen[pos..];
+                // Every member reaches the same cycle; report it from the first one seen
+                if cycle.iter().all(|id| in_cycle.insert(id)) {
+                    issues.push(Issue::new(
+                        IssueCode::ParentCycle,
+                        Some(&parent.id),
+                        format!("Parent chain loops: {}", join_cycle(cycle)),
+                    ));
+                }
+                break;
+            }
+            seen.push(&parent.id);
+            current = parent;
+        }
+
+        let depth = seen.len() - 1;
+        if depth > deepest && !in_cycle.contains(&task.id) {
+            issues.push(Issue::new(
+                IssueCode::DepthExceeded,
+                Some(&task.id),
+                format!("Task is at depth {} (max {})", depth, deepest),
+            ));
+        }
+
+        if task.archived {
+            let active: Vec<&TaskId> = children
+                .get(&task.id)
+                .into_iter()
+                .flatten()
+                .filter(|c| c.is_active_for_work())
+                .map(|c| &c.id)
+                .collect();
+            if !active.is_empty() {
+                issues.push(Issue::new(
+                    IssueCode::ArchivedWithActiveChildren,
+                    Some(&task.id),
+                    format!(
+                        "Archived task has active children: {}",
+                        active
+                            .iter()
+                            .map(|id| id.to_string())
+                            .collect::<Vec<_>>()
+                            .join(", ")
+                    ),
+                ));
+            }
+        }
+    }
+}
+
+fn check_blockers(
+    conn: &Connection,
+    by_id: &HashMap<&TaskId, &Task>,
+    issues: &mut Vec<Issue>,
+) -> Result<()> {
+    let mut stmt = conn.prepare("SELECT task_id, blocker_id FROM task_blockers")?;
+    let edges = stmt
+        .query_map([], |row| {
+            Ok((row.get::<_, TaskId>(0)?, row.get::<_, TaskId>(1)?))
+        })?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+
+    let mut graph: HashMap<&TaskId, Vec<&TaskId>> = HashMap::new();
+    for (task_id, blocker_id) in &edges {
+        if !by_id.contains_key(blocker_id) || !by_id.contains_key(task_id) {
+            issues.push(
+                Issue::new(
+                    IssueCode::DanglingBlocker,
+                    Some(task_id),
+                    format!(
+                        "Blocker row {} -> {} references a missing task",
+                        task_id, blocker_id
+                    ),
+                )
+                .with_repair(Repair::DeleteBlocker {
+                    task_id: task_id.clone(),
+                    blocker_id: blocker_id.clone(),
+                }),
+            );
+            continue;
+        }
+        graph.entry(task_id).or_default().push(blocker_id);
+    }
+
+    // Iterative DFS with colors; a back edge closes a cycle
+    let mut roots: Vec<&TaskId> = graph.keys().copied().collect();
+    roots.sort();
+    let mut done: HashSet<&TaskId> = HashSet::new();
+    for root in roots {
+        if done.contains(root) {
+            continue;
+        }
+        let mut path: Vec<&TaskId> = vec![root];
+        let mut stack: Vec<(&TaskId, usize)> = vec![(root, 0)];
+        while let Some((node, next)) = stack.pop() {
+            let blockers = graph.get(node).map(Vec::as_slice).unwrap_or_default();
+            if let Some(&blocker) = blockers.get(next) {
+                stack.push((node, next + 1));
+                if let Some(pos) = path.iter().position(|id| *id == blocker) {
+                    issues.push(Issue::new(
+                        IssueCode::BlockerCycle,
+                        Some(blocker),
+                        format!("Blockers form a cycle: {}", join_cycle(&path[pos..])),
+                    ));
+                } else if !done.contains(blocker) {
+                    path.push(blocker);
+                    stack.push((blocker, 0));
+                }
+            } else {
+                done.insert(node);
+                path.pop();
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Each task's branch is looked up in its own repo; tasks without one use
+/// `vcs`. Repos that cannot be opened are skipped, since their branches
+/// cannot be told apart from missing ones.
+fn check_branches(
+    conn: &Connection,
+    tasks: &[Task],
+    vcs: Option<&dyn VcsBackend>,
+    issues: &mut Vec<Issue>,
+) -> Result<()> {
+    let mut by_repo: HashMap<Option<&RepoId>, Vec<&Task>> = HashMap::new();
+    for task in tasks.iter().filter(|t| t.bookmark.is_some()) {
+        by_repo.entry(task.repo_id.as_ref()).or_default().push(task);
+    }
+    let mut by_repo: Vec<_> = by_repo.into_iter().collect();
+    by_repo.sort_by_key(|(repo_id, _)| repo_id.map(ToString::to_string));
+
+    for (repo_id, tasks) in by_repo {
+        let opened;
+        let vcs = match repo_id {
+            None => match vcs {
+                Some(vcs) => vcs,
+                None => continue,
+            },
+            Some(repo_id) => match repos::open_backend(conn, repo_id) {
+                Ok(backend) => {
+                    opened = backend;
+                    &*opened
+                }
+                Err(_) => continue,
+            },
+        };
+        check_repo_branches(&tasks, vcs, issues)?;
+    }
+    Ok(())
+}
+
+fn check_repo_branches(
+    tasks: &[&Task],
+    vcs: &dyn VcsBackend,
+    issues: &mut Vec<Issue>,
+) -> Result<()> {
+    let branches: HashSet<String> = vcs.list_bookmarks(None)?.into_iter().collect();
+    for task in tasks {
+        let Some(bookmark) = &task.bookmark else {
+            continue;
+        };
+        if !branches.contains(bookmark) {
+            issues.push(
+                Issue::new(
+                    IssueCode::MissingBranch,
+                    Some(&task.id),
+                    format!("Branch '{}' no longer exists", bookmark),
+                )
+                .with_repair(Repair::ClearBookmark {
+                    task_id: task.id.clone(),
+                }),
+            );
+        }
+    }
+    Ok(())
+}
+
+fn join_cycle(cycle: &[&TaskId]) -> String {
+    cycle
+        .iter()
+        .chain(cycle.first())
+        .map(|id| id.to_string())
+        .collect::<Vec<_>>()
+        .join(" -> ")
+}
+
+/// Apply every available repair in one transaction, marking issues as fixed.
+/// Each touched task gets a `task.updated` event (or `blocker.removed`).
+pub fn repair(conn: &Connection, issues: &mut [Issue]) -> Result<usize> {
+    let tx = conn.unchecked_transaction()?;
+    let mut fixed = 0;
+    for issue in issues.iter_mut() {
+        let Some(repair) = &issue.repair else {
+            continue;
+        };
+        match repair {
+            Repair::BackfillTimestamp { task_id, column } => {
+                // Column names come from a fixed set above, never from input
+                conn.execute(
+                    &format!(
+                        "UPDATE tasks SET {column} = COALESCE({column}, updated_at, ?1) WHERE id = ?2"
+                    ),
+                    params![Utc::now().to_rfc3339(), task_id],
+                )?;
+                append_task_updated(conn, task_id)?;
+            }
+            Repair::DeleteBlocker {
+                task_id,
+                blocker_id,
+            } => {
+                conn.execute(
+                    "DELETE FROM task_blockers WHERE task_id = ?1 AND blocker_id = ?2",
+                    params![task_id, blocker_id],
+                )?;
+                event_repo::append(
+                    conn,
+                    &EventBody::BlockerRemoved {
+                        task_id: task_id.clone(),
+                        blocker_id: blocker_id.clone(),
+                    },
+                )?;
+            }
+            Repair::ClearBookmark { task_id } => {
+                task_repo::clear_bookmark(conn, task_id)?;
+                append_task_updated(conn, task_id)?;
+            }
+        }
+        issue.fixed = true;
+        fixed += 1;
+    }
+    tx.commit()?;
+    Ok(fixed)
+}
+
+fn append_task_updated(conn: &Connection, task_id: &TaskId) -> Result<()> {
+    if let Some(task) = task_repo::get_task(conn, task_id)? {
+        event_repo::append(conn, &EventBody::TaskUpdated { task })?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::TaskService;
+    use crate::db::schema;
+    use crate::testutil::{GitTestRepo, TestRepo};
+    use crate::types::CreateTaskInput;
+    use crate::vcs::GixBackend;
+
+    fn setup_db() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch("PRAGMA foreign_keys = ON;").unwrap();
+        schema::init_schema(&conn).unwrap();
+        conn
+    }
+
+    fn create(conn: &Connection, description: &str, parent_id: Option<&TaskId>) -> TaskId {
+        TaskService::new(conn)
+            .create(&CreateTaskInput {
+                description: description.to_string(),
+                parent_id: parent_id.cloned(),
+                ..Default::default()
+            })
+            .unwrap()
+            .id
+    }
+
+    fn codes(issues: &[Issue]) -> Vec<IssueCode> {
+        let mut codes: Vec<_> = issues.iter().map(|i| i.code).collect();
+        codes.sort_by_key(|c| c.as_str());
+        codes
+    }
+
+    #[test]
+    fn test_clean_database_has_no_issues() {
+        let conn = setup_db();
+        let milestone = create(&conn, "Milestone", None);
+        let task = create(&conn, "Task", Some(&milestone));
+        create(&conn, "Subtask", Some(&task));
+
+        assert!(check(&conn, None).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_lifecycle_issues_and_timestamp_backfill() {
+        let conn = setup_db();
+        let a = create(&conn, "A", None);
+        let b = create(&conn, "B", None);
+        conn.execute(
+            "UPDATE tasks SET completed = 1, cancelled = 1, cancelled_at = updated_at WHERE id = ?1",
+            params![a],
+        )
+        .unwrap();
+        conn.execute("UPDATE tasks SET archived = 1 WHERE id = ?1", params![b])
+            .unwrap();
+
+        let mut issues = check(&conn, None).unwrap();
+        assert_eq!(
+            codes(&issues),
+            vec![
+                IssueCode::ArchivedNotFinished,
+                IssueCode::CompletedAndCancelled,
+                IssueCode::MissingArchivedAt,
+                IssueCode::MissingCompletedAt,
+            ]
+        );
+
+        assert_eq!(repair(&conn, &mut issues).unwrap(), 2);
+        assert!(issues.iter().all(|i| i.fixed
+            == matches!(
+                i.code,
+                IssueCode::MissingArchivedAt | IssueCode::MissingCompletedAt
+            )));
+
+        // Only the unfixable state conflicts remain
+        assert_eq!(
+            codes(&check(&conn, None).unwrap()),
+            vec![
+                IssueCode::ArchivedNotFinished,
+                IssueCode::CompletedAndCancelled
+            ]
+        );
+    }
+
+    #[test]
+    fn test_graph_issues() {
+        let conn = setup_db();
+        let milestone = create(&conn, "Milestone", None);
+        let task = create(&conn, "Task", Some(&milestone));
+        let subtask = create(&conn, "Subtask", Some(&task));
+        let too_deep = create(&conn, "Too deep", None);
+        let x = create(&conn, "X", None);
+        let y = create(&conn, "Y", None);
+        let done = create(&conn, "Done", None);
+        let child = create(&conn, "Active child", Some(&done));
+
+        conn.execute_batch("PRAGMA foreign_keys = OFF;").unwrap();
+        conn.execute(
+            "UPDATE tasks SET parent_id = ?1 WHERE id = ?2",
+            params![subtask, too_deep],
+        )
+        .unwrap();
+        conn.execute(
+            "INSERT INTO task_blockers (task_id, blocker_id) VALUES (?1, ?2), (?2, ?1), (?1, ?3)",
+            params![x, y, TaskId::new()],
+        )
+        .unwrap();
+        conn.execute(
+            "UPDATE tasks SET completed = 1, completed_at = updated_at, archived = 1, archived_at = updated_at WHERE id = ?1",
+            params![done],
+        )
+        .unwrap();
+
+        let mut issues = check(&conn, None).unwrap();
+        assert_eq!(
+            codes(&issues),
+            vec![
+                IssueCode::ArchivedWithActiveChildren,
+                IssueCode::BlockerCycle,
+                IssueCode::DanglingBlocker,
+                IssueCode::DepthExceeded,
+            ]
+        );
+        let archived = issues
+            .iter()
+            .find(|i| i.code == IssueCode::ArchivedWithActiveChildren)
+            .unwrap();
+        assert_eq!(archived.task_id.as_ref(), Some(&done));
+        assert!(archived.message.contains(&child.to_string()));
+
+        assert_eq!(repair(&conn, &mut issues).unwrap(), 1);
+        assert!(!codes(&check(&conn, None).unwrap()).contains(&IssueCode::DanglingBlocker));
+    }
+
+    #[test]
+    fn test_parent_cycle_reported_once() {
+        let conn = setup_db();
+        let a = create(&conn, "A", None);
+        let b = create(&conn, "B", Some(&a));
+        conn.execute(
+            "UPDATE tasks SET parent_id = ?1 WHERE id = ?2",
+            params![b, a],
+        )
+        .unwrap();
+
+        let issues = check(&conn, None).unwrap();
+        assert_eq!(codes(&issues), vec![IssueCode::ParentCycle]);
+    }
+
+    #[test]
+    fn test_missing_branch_cleared_by_fix() {
+        let repo = GitTestRepo::new().unwrap();
+        repo.write_file("README.md", "hi").unwrap();
+        repo.add_all().unwrap();
+        repo.commit("init").unwrap();
+        let backend = GixBackend::open(repo.path()).unwrap();
+        backend.create_bookmark("task/kept", None).unwrap();
+
+        let conn = setup_db();
+        let kept = create(&conn, "Kept", None);
+        let gone = create(&conn, "Gone", None);
+        task_repo::set_bookmark(&conn, &kept, "task/kept").unwrap();
+        task_repo::set_bookmark(&conn, &gone, "task/gone").unwrap();
+
+        assert!(check(&conn, None).unwrap().is_empty());
+        let mut issues = check(&conn, Some(&backend)).unwrap();
+        assert_eq!(codes(&issues), vec![IssueCode::MissingBranch]);
+        assert_eq!(issues[0].task_id.as_ref(), Some(&gone));
+
+        repair(&conn, &mut issues).unwrap();
+        assert!(check(&conn, Some(&backend)).unwrap().is_empty());
+        let bookmark = |id| task_repo::get_task(&conn, id).unwrap().unwrap().bookmark;
+        assert_eq!(bookmark(&gone), None);
+        assert_eq!(bookmark(&kept).as_deref(), Some("task/kept"));
+    }
+
+    #[test]
+    fn test_branches_checked_in_each_tasks_repo() {
+        let (here, api) = (GitTestRepo::new().unwrap(), GitTestRepo::new().unwrap());
+        for repo in [&here, &api] {
+            repo.commit("init").unwrap();
+        }
+        let backend = GixBackend::open(here.path()).unwrap();
+        GixBackend::open(api.path())
+            .unwrap()
+            .create_bookmark("task/api", None)
+            .unwrap();
+
+        let conn = setup_db();
+        let registered = repos::register(&conn, api.path(), Some("api")).unwrap();
+        let create_in = |description: &str, repo_id: &RepoId| {
+            TaskService::new(&conn)
+                .create(&CreateTaskInput {
+                    description: description.to_string(),
+                    repo_id: Some(repo_id.clone()),
+                    ..Default::default()
+                })
+                .unwrap()
+                .id
+        };
+        let in_api = create_in("In api", &registered.id);
+        let gone = create_in("Gone from api", &registered.id);
+        task_repo::set_bookmark(&conn, &in_api, "task/api").unwrap();
+        task_repo::set_bookmark(&conn, &gone, "task/gone").unwrap();
+
+        // Only the branch missing from the task's own repo is reported
+        let issues = check(&conn, Some(&backend)).unwrap();
+        assert_eq!(codes(&issues), vec![IssueCode::MissingBranch]);
+        assert_eq!(issues[0].task_id.as_ref(), Some(&gone));
+        assert_eq!(check(&conn, None).unwrap().len(), 1);
+
+        // A repo that cannot be opened reports nothing
+        let path = api.path().to_path_buf();
+        drop(api);
+        assert!(!path.exists());
+        assert!(check(&conn, Some(&backend)).unwrap().is_empty());
+    }
+}
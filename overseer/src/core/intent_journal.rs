@@ -0,0 +1,240 @@
+//! On-disk intent journal for workflow operations.
+//!
+//! VCS side effects cannot take part in a SQLite transaction. While `start` or
+//! `complete` runs, every VCS step is appended to a small JSON file next to the
+//! database as soon as it succeeds. The file is removed while the write lock is
+//! still held: right before the DB transaction commits, or after the steps were
+//! undone when the operation returned an error. A leftover journal means the
+//! process died mid-operation: the next invocation resumes it (complete) or
+//! undoes the recorded steps (start).
+
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, Utc};
+use rusqlite::Connection;
+use serde::{Deserialize, Serialize};
+
+use crate::error::Result;
+use crate::id::TaskId;
+
+/// Workflow operation an intent belongs to, with the inputs needed to resume it.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "camelCase")]
+pub enum IntentOp {
+    Start,
+    Complete {
+        result: Option<String>,
+        learnings: Vec<String>,
+    },
+}
+
+/// A VCS step that has already been applied.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(
+    tag = "step",
+    rename_all = "camelCase",
+    rename_all_fields = "camelCase"
+)]
+pub enum VcsStep {
+    BookmarkCreated {
+        name: String,
+    },
+    CheckedOut {
+        target: String,
+        previous: Option<String>,
+    },
+    WorktreeAdded {
+        path: String,
+    },
+    /// `rev` is None when there was nothing to commit
+    Committed {
+        task_id: TaskId,
+        rev: Option<String>,
+    },
+    /// `rev` is None when the start branch already contained the task branch
+    Integrated {
+        task_id: TaskId,
+        rev: Option<String>,
+    },
+    /// `rev` is the tip the branch pointed at, so a failed complete can restore it
+    BookmarkDeleted {
+        task_id: TaskId,
+        name: String,
+        #[serde(default)]
+        rev: Option<String>,
+    },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Intent {
+    pub task_id: TaskId,
+    pub started_at: DateTime<Utc>,
+    #[serde(flatten)]
+    pub op: IntentOp,
+    pub steps: Vec<VcsStep>,
+}
+
+impl Intent {
+    pub fn new(task_id: TaskId, op: IntentOp) -> Self {
+        Self {
+            task_id,
+            started_at: Utc::now(),
+            op,
+            steps: Vec::new(),
+        }
+    }
+
+    /// Revision recorded for a task's commit, if the commit already happened.
+    pub fn committed(&self, task_id: &TaskId) -> Option<Option<&str>> {
+        self.steps.iter().find_map(|step| match step {
+            VcsStep::Committed { task_id: t, rev } if t == task_id => Some(rev.as_deref()),
+            _ => None,
+        })
+    }
+
+    /// New tip of the start branch, if the task branch was already integrated.
+    pub fn integrated(&self, task_id: &TaskId) -> Option<Option<&str>> {
+        self.steps.iter().find_map(|step| match step {
+            VcsStep::Integrated { task_id: t, rev } if t == task_id => Some(rev.as_deref()),
+            _ => None,
+        })
+    }
+
+    pub fn bookmark_deleted(&self, name: &str) -> bool {
+        self.steps
+            .iter()
+            .any(|step| matches!(step, VcsStep::BookmarkDeleted { name: n, .. } if n == name))
+    }
+}
+
+/// Location of the journal for one database. Disabled for in-memory databases.
+pub struct IntentJournal {
+    path: Option<PathBuf>,
+}
+
+impl IntentJournal {
+    /// Journal for the database file at `db_path` (`<db_path>.intent.json`).
+    pub fn for_db(db_path: &Path) -> Self {
+        let mut path = db_path.as_os_str().to_owned();
+        path.push(".intent.json");
+        Self {
+            path: Some(PathBuf::from(path)),
+        }
+    }
+
+    pub fn for_connection(conn: &Connection) -> Self {
+        match conn.path() {
+            Some(path) if !path.is_empty() => Self::for_db(Path::new(path)),
+            _ => Self { path: None },
+        }
+    }
+
+    pub fn exists(&self) -> bool {
+        self.path.as_ref().is_some_and(|p| p.exists())
+    }
+
+    pub fn load(&self) -> Result<Option<Intent>> {
+        let Some(ref path) = self.path else {
+            return Ok(None);
+        };
+        match fs::read_to_string(path) {
+            Ok(content) => Ok(Some(serde_json::from_str(&content)?)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Append a step and persist the intent before returning.
+    pub fn record(&self, intent: &mut Intent, step: VcsStep) -> Result<()> {
+        intent.steps.push(step);
+        self.save(intent)
+    }
+
+    /// Write via a temp file and rename, so a crash never leaves a torn journal.
+    fn save(&self, intent: &Intent) -> Result<()> {
+        let Some(ref path) = self.path else {
+            return Ok(());
+        };
+        let mut tmp = path.clone().into_os_string();
+        tmp.push(".tmp");
+        let tmp = PathBuf::from(tmp);
+
+        let mut file = fs::File::create(&tmp)?;
+        file.write_all(serde_json::to_string_pretty(intent)?.as_bytes())?;
+        file.sync_all()?;
+        fs::rename(&tmp, path)?;
+        Ok(())
+    }
+
+    pub fn clear(&self) -> Result<()> {
+        let Some(ref path) = self.path else {
+            return Ok(());
+        };
+        match fs::remove_file(path) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e.into()),
+            _ => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_record_load_clear_roundtrip() {
+        let dir = tempfile::tempdir().unwrap();
+        let journal = IntentJournal::for_db(&dir.path().join("tasks.db"));
+        assert!(!journal.exists());
+        assert!(journal.load().unwrap().is_none());
+
+        let task_id = TaskId::new();
+        let mut intent = Intent::new(
+            task_id.clone(),
+            IntentOp::Complete {
+                result: Some("done".to_string()),
+                learnings: vec![],
+            },
+        );
+        journal
+            .record(
+                &mut intent,
+                VcsStep::Committed {
+                    task_id: task_id.clone(),
+                    rev: Some("abc".to_string()),
+                },
+            )
+            .unwrap();
+        assert!(dir.path().join("tasks.db.intent.json").exists());
+
+        let loaded = journal.load().unwrap().unwrap();
+        assert_eq!(loaded.op, intent.op);
+        assert_eq!(loaded.committed(&task_id), Some(Some("abc")));
+        assert_eq!(loaded.committed(&TaskId::new()), None);
+
+        journal.clear().unwrap();
+        assert!(!journal.exists());
+        journal.clear().unwrap();
+    }
+
+    #[test]
+    fn test_in_memory_connection_disables_journal() {
+        let conn = Connection::open_in_memory().unwrap();
+        let journal = IntentJournal::for_connection(&conn);
+        let mut intent = Intent::new(TaskId::new(), IntentOp::Start);
+        journal
+            .record(
+                &mut intent,
+                VcsStep::BookmarkCreated {
+                    name: "task/x".to_string(),
+                },
+            )
+            .unwrap();
+        assert!(!journal.exists());
+        assert!(journal.load().unwrap().is_none());
+        assert_eq!(intent.steps.len(), 1);
+    }
+}
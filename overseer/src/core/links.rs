@@ -0,0 +1,182 @@
+//! Validation for task links (`os task link add`).
+//!
+//! File and commit targets are checked against the repository: paths must
+//! exist in the working copy or at HEAD and are stored relative to the root;
+//! commits are stored as the full id they resolve to. URLs and issue
+//! references are only checked for shape.
+
+use std::path::{Component, Path};
+
+use crate::db::link_repo::LinkKind;
+use crate::error::{OsError, Result};
+use crate::vcs::{VcsBackend, VcsError};
+
+/// What a new link points at, as given by the caller.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LinkTarget {
+    File {
+        path: String,
+        /// First and last line, inclusive
+        lines: Option<(u32, u32)>,
+    },
+    Url(String),
+    Commit(String),
+    Issue(String),
+}
+
+impl LinkTarget {
+    pub fn kind(&self) -> LinkKind {
+        match self {
+            Self::File { lines: None, .. } => LinkKind::File,
+            Self::File { lines: Some(_), .. } => LinkKind::FileRange,
+            Self::Url(_) => LinkKind::Url,
+            Self::Commit(_) => LinkKind::Commit,
+            Self::Issue(_) => LinkKind::Issue,
+        }
+    }
+
+    pub fn target(&self) -> &str {
+        match self {
+            Self::File { path, .. } => path,
+            Self::Url(s) | Self::Commit(s) | Self::Issue(s) => s,
+        }
+    }
+
+    pub fn lines(&self) -> Option<(u32, u32)> {
+        match self {
+            Self::File { lines, .. } => *lines,
+            _ => None,
+        }
+    }
+}
+
+/// Check `target` and bring it into stored form. File and commit links need
+/// `vcs`; without a repository they fail with `NotARepository`.
+pub fn validate(target: LinkTarget, vcs: Option<&dyn VcsBackend>) -> Result<LinkTarget> {
+    match target {
+        LinkTarget::File { path, lines } => {
+            let vcs = vcs.ok_or(OsError::NotARepository)?;
+            if let Some((start, end)) = lines {
+                if start == 0 || end < start {
+                    return Err(OsError::InvalidLink(format!(
+                        "invalid line range {start}-{end} (lines start at 1)"
+                    )));
+                }
+            }
+            let path = normalize_path(&path, Some(vcs.root()))?;
+            if !vcs.path_exists(&path)? {
+                return Err(OsError::LinkPathNotFound(path));
+            }
+            Ok(LinkTarget::File { path, lines })
+        }
+        LinkTarget::Commit(rev) => {
+            let vcs = vcs.ok_or(OsError::NotARepository)?;
+            match vcs.resolve_commit(rev.trim()) {
+                Ok(id) => Ok(LinkTarget::Commit(id)),
+                Err(VcsError::TargetNotFound(_)) => Err(OsError::CommitNotFound(rev)),
+                Err(e) => Err(e.into()),
+            }
+        }
+        LinkTarget::Url(url) => {
+            let url = url.trim();
+            let rest = url
+                .strip_prefix("https://")
+                .or_else(|| url.strip_prefix("http://"));
+            match rest {
+                Some(rest) if !rest.is_empty() && !url.contains(char::is_whitespace) => {
+                    Ok(LinkTarget::Url(url.to_string()))
+                }
+                _ => Err(OsError::InvalidLink(format!(
+                    "'{url}' is not an http(s) URL"
+                ))),
+            }
+        }
+        LinkTarget::Issue(reference) => {
+            let reference = reference.trim();
+            if reference.is_empty() || reference.contains(char::is_whitespace) {
+                return Err(OsError::InvalidLink(format!(
+                    "invalid issue reference '{reference}' (e.g. PROJ-123, owner/repo#42 or a URL)"
+                )));
+            }
+            Ok(LinkTarget::Issue(reference.to_string()))
+        }
+    }
+}
+
+/// Normalize a repository path: `/`-separated, relative to the root, without
+/// `.` components or a trailing slash. An absolute path is accepted when it
+/// lies under `root`.
+pub fn normalize_path(path: &str, root: Option<&str>) -> Result<String> {
+    let invalid = |reason: &str| OsError::InvalidLink(format!("path '{path}' {reason}"));
+    let mut relative = Path::new(path.trim());
+    if relative.is_absolute() {
+        relative = root
+            .and_then(|root| relative.strip_prefix(root).ok())
+            .ok_or_else(|| invalid("is outside the repository"))?;
+    }
+    let mut parts = Vec::new();
+    for component in relative.components() {
+        match component {
+            Component::Normal(part) => {
+                parts.push(part.to_str().ok_or_else(|| invalid("is not valid UTF-8"))?)
+            }
+            Component::CurDir => {}
+            Component::ParentDir => return Err(invalid("must not contain '..'")),
+            Component::RootDir | Component::Prefix(_) => {
+                return Err(invalid("must be relative to the repository root"))
+            }
+        }
+    }
+    if parts.is_empty() {
+        return Err(invalid("is empty"));
+    }
+    Ok(parts.join("/"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalize_path() {
+        assert_eq!(
+            normalize_path("./src//auth.rs", None).unwrap(),
+            "src/auth.rs"
+        );
+        assert_eq!(normalize_path("src/", None).unwrap(), "src");
+        assert_eq!(
+            normalize_path("/work/repo/src/auth.rs", Some("/work/repo")).unwrap(),
+            "src/auth.rs"
+        );
+        assert!(normalize_path("/elsewhere/auth.rs", Some("/work/repo")).is_err());
+        assert!(normalize_path("/work/repo/src", None).is_err());
+        assert!(normalize_path("src/../secret", None).is_err());
+        assert!(normalize_path(".", None).is_err());
+    }
+
+    #[test]
+    fn test_validate_shapes_without_repository() {
+        assert_eq!(
+            validate(LinkTarget::Url(" https://example.com/x ".into()), None).unwrap(),
+            LinkTarget::Url("https://example.com/x".into())
+        );
+        assert!(validate(LinkTarget::Url("ftp://example.com".into()), None).is_err());
+        assert!(validate(LinkTarget::Url("https://".into()), None).is_err());
+        assert!(validate(LinkTarget::Issue("owner/repo#42".into()), None).is_ok());
+        assert!(validate(LinkTarget::Issue("two words".into()), None).is_err());
+        assert!(matches!(
+            validate(LinkTarget::Commit("abc123".into()), None),
+            Err(OsError::NotARepository)
+        ));
+        assert!(matches!(
+            validate(
+                LinkTarget::File {
+                    path: "src/auth.rs".into(),
+                    lines: None
+                },
+                None
+            ),
+            Err(OsError::NotARepository)
+        ));
+    }
+}
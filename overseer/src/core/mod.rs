@@ -1,7 +1,16 @@
 pub mod context;
+pub mod integrity;
+pub mod intent_journal;
+pub mod links;
+pub mod repos;
+pub mod task_graph;
 pub mod task_service;
+pub mod template;
+pub mod time_report;
+pub mod trash;
 pub mod workflow_service;
 
 pub use context::{get_task_with_context, TaskWithContext};
+pub use task_graph::TaskGraph;
 pub use task_service::TaskService;
-pub use workflow_service::TaskWorkflowService;
+pub use workflow_service::{RecoveryAction, TaskWorkflowService};
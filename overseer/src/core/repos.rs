@@ -0,0 +1,161 @@
+//! Repositories registered with `os repo add`.
+//!
+//! A database is anchored in one repository by default, but any number of
+//! others can be registered so that one task tree spans them. Tasks name their
+//! repo (inherited from the parent at creation); start and complete run VCS
+//! operations in that repo, and tasks without one use the repository of the
+//! working directory. Blockers may cross repos freely.
+
+use std::path::Path;
+
+use chrono::Utc;
+use rusqlite::Connection;
+
+use crate::db::repo_repo::{self, Repo};
+use crate::error::{OsError, Result};
+use crate::id::RepoId;
+use crate::vcs::{self, VcsBackend, VcsType};
+
+/// Register the repository containing `path` (its root is stored). `name`
+/// defaults to the root directory's name.
+pub fn register(conn: &Connection, path: &Path, name: Option<&str>) -> Result<Repo> {
+    let path = path.canonicalize()?;
+    let (vcs_type, root) = vcs::detect_vcs_type(&path);
+    let root = match (vcs_type, root) {
+        (VcsType::Git, Some(root)) => root,
+        _ => return Err(OsError::NotARepository),
+    };
+    let root = root.to_string_lossy().to_string();
+    let name = match name {
+        Some(name) => name.trim().to_string(),
+        None => Path::new(&root)
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_else(|| root.clone()),
+    };
+    if name.is_empty() || name.starts_with(RepoId::PREFIX) {
+        return Err(OsError::InvalidRepoName(name));
+    }
+    if let Some(existing) = repo_repo::find_repo(conn, &name, &root)? {
+        return Err(OsError::RepoExists(if existing.path == root {
+            root
+        } else {
+            name
+        }));
+    }
+
+    let repo = Repo {
+        id: RepoId::new(),
+        path: root,
+        name,
+        vcs_type,
+        created_at: Utc::now(),
+    };
+    repo_repo::insert_repo(conn, &repo)?;
+    Ok(repo)
+}
+
+pub fn list(conn: &Connection) -> Result<Vec<Repo>> {
+    repo_repo::list_repos(conn)
+}
+
+/// Look a repo up by id (`repo_...`) or name.
+pub fn resolve(conn: &Connection, key: &str) -> Result<Repo> {
+    let found = match key.parse::<RepoId>() {
+        Ok(id) => repo_repo::get_repo(conn, &id)?,
+        Err(_) => repo_repo::find_repo(conn, key, "")?,
+    };
+    found.ok_or_else(|| OsError::RepoNotFound(key.to_string()))
+}
+
+/// Unregister a repo. Refused while it has active tasks; finished tasks stay
+/// and fall back to the working directory's repository.
+pub fn remove(conn: &Connection, key: &str) -> Result<Repo> {
+    let repo = resolve(conn, key)?;
+    let tasks = repo_repo::count_active_tasks(conn, &repo.id)?;
+    if tasks > 0 {
+        return Err(OsError::RepoInUse {
+            name: repo.name,
+            tasks,
+        });
+    }
+    repo_repo::delete_repo(conn, &repo.id)?;
+    Ok(repo)
+}
+
+/// Fail unless `id` is registered.
+pub fn ensure_exists(conn: &Connection, id: &RepoId) -> Result<()> {
+    match repo_repo::get_repo(conn, id)? {
+        Some(_) => Ok(()),
+        None => Err(OsError::RepoNotFound(id.to_string())),
+    }
+}
+
+/// Open the VCS backend of a registered repo.
+pub fn open_backend(conn: &Connection, id: &RepoId) -> Result<Box<dyn VcsBackend>> {
+    let repo =
+        repo_repo::get_repo(conn, id)?.ok_or_else(|| OsError::RepoNotFound(id.to_string()))?;
+    Ok(vcs::get_backend(Path::new(&repo.path))?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::schema::init_schema;
+    use crate::types::CreateTaskInput;
+    use tempfile::TempDir;
+
+    fn setup_db() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch("PRAGMA foreign_keys = ON;").unwrap();
+        init_schema(&conn).unwrap();
+        conn
+    }
+
+    #[test]
+    fn test_register_resolve_and_remove() {
+        let conn = setup_db();
+        let tmp = TempDir::new().unwrap();
+        let root = tmp.path().join("api");
+        std::fs::create_dir_all(root.join(".git")).unwrap();
+        std::fs::create_dir_all(root.join("src")).unwrap();
+
+        // Any path inside the repository registers its root
+        let repo = register(&conn, &root.join("src"), None).unwrap();
+        assert_eq!(repo.name, "api");
+        assert_eq!(repo.path, root.canonicalize().unwrap().to_string_lossy());
+        assert!(matches!(
+            register(&conn, &root, Some("other")),
+            Err(OsError::RepoExists(_))
+        ));
+        assert!(matches!(
+            register(&conn, tmp.path(), None),
+            Err(OsError::NotARepository)
+        ));
+
+        assert_eq!(resolve(&conn, "api").unwrap().id, repo.id);
+        assert_eq!(resolve(&conn, &repo.id.to_string()).unwrap().name, "api");
+        assert!(matches!(
+            resolve(&conn, "web"),
+            Err(OsError::RepoNotFound(_))
+        ));
+
+        let task = crate::core::TaskService::new(&conn)
+            .create(&CreateTaskInput {
+                description: "Endpoint".to_string(),
+                repo_id: Some(repo.id.clone()),
+                ..Default::default()
+            })
+            .unwrap();
+        assert!(matches!(
+            remove(&conn, "api"),
+            Err(OsError::RepoInUse { tasks: 1, .. })
+        ));
+
+        crate::core::TaskService::new(&conn)
+            .cancel(&task.id)
+            .unwrap();
+        remove(&conn, "api").unwrap();
+        assert!(list(&conn).unwrap().is_empty());
+    }
+}
@@ -0,0 +1,555 @@
+//! In-memory snapshot of the task hierarchy and blocker edges.
+//!
+//! Multi-task reads (list, next-ready, start resolution, tree, progress) used to
+//! query per node: blockers and dependents for every listed task, and one query
+//! per ancestor level. `TaskGraph::load` reads tasks, blocker edges, metadata,
+//! labels and live claims in five queries; everything else runs over the
+//! snapshot. Single-task paths (`TaskService::get` and writes) still go to the
+//! database directly.
+
+use std::cmp::Ordering;
+use std::collections::{HashMap, HashSet};
+
+use chrono::{DateTime, Utc};
+use rusqlite::Connection;
+
+use crate::db::{claim_repo, label_repo, metadata_repo, task_repo};
+use crate::error::{OsError, Result};
+use crate::id::TaskId;
+use crate::types::{ListTasksFilter, NextReadyFilter, Task};
+
+pub struct TaskGraph {
+    /// Tasks with `blocked_by`, `blocks`, `metadata`, `labels`, `claim`, `depth`
+    /// and `effectively_blocked` filled in
+    tasks: Vec<Task>,
+    index: HashMap<TaskId, usize>,
+    /// Parent of each task (None for milestones and dangling parents)
+    parents: Vec<Option<usize>>,
+    /// Children of each task in sibling order
+    children: Vec<Vec<usize>>,
+    /// Milestones in sibling order
+    roots: Vec<usize>,
+    /// Deferral and overdue checks are made against the load time
+    now: DateTime<Utc>,
+}
+
+/// Sibling order everywhere: priority ASC (p0 first), rank ASC, created_at ASC, id ASC
+fn sibling_order(a: &Task, b: &Task) -> Ordering {
+    a.priority
+        .cmp(&b.priority)
+        .then_with(|| a.rank.cmp(&b.rank))
+        .then_with(|| a.created_at.cmp(&b.created_at))
+        .then_with(|| a.id.cmp(&b.id))
+}
+
+/// Task carries every one of `labels` (normalized; task labels are kept sorted)
+pub fn has_labels(task: &Task, labels: &[String]) -> bool {
+    labels.iter().all(|l| task.labels.binary_search(l).is_ok())
+}
+
+impl TaskGraph {
+    pub fn load(conn: &Connection) -> Result<Self> {
+        let mut tasks = task_repo::list_all_rows(conn)?;
+        let edges = task_repo::list_blocker_edges(conn)?;
+        let mut metadata = metadata_repo::all_metadata(conn)?;
+        let mut labels = label_repo::all_labels(conn)?;
+        let mut claims = claim_repo::all_live_claims(conn, Utc::now())?;
+        for task in &mut tasks {
+            task.metadata = metadata.remove(&task.id);
+            task.labels = labels.remove(&task.id).unwrap_or_default();
+            task.claim = claims.remove(&task.id);
+        }
+        Ok(Self::build(tasks, edges))
+    }
+
+    /// Build from task rows and `(task_id, blocker_id)` edges. Existing relation
+    /// fields on `tasks` are replaced.
+    pub fn build(mut tasks: Vec<Task>, edges: Vec<(TaskId, TaskId)>) -> Self {
+        tasks.sort_by(sibling_order);
+        let index: HashMap<TaskId, usize> = tasks
+            .iter()
+            .enumerate()
+            .map(|(i, t)| (t.id.clone(), i))
+            .collect();
+
+        for task in &mut tasks {
+            task.blocked_by.clear();
+            task.blocks.clear();
+        }
+        for (task_id, blocker_id) in edges {
+            if let Some(&i) = index.get(&blocker_id) {
+                tasks[i].blocks.push(task_id.clone());
+            }
+            if let Some(&i) = index.get(&task_id) {
+                tasks[i].blocked_by.push(blocker_id);
+            }
+        }
+
+        // Tasks are sorted, so pushing in order keeps siblings ordered
+        let mut parents = vec![None; tasks.len()];
+        let mut children = vec![Vec::new(); tasks.len()];
+        let mut roots = Vec::new();
+        for (i, task) in tasks.iter().enumerate() {
+            match task.parent_id.as_ref().map(|p| index.get(p)) {
+                None => roots.push(i),
+                Some(Some(&parent)) => {
+                    parents[i] = Some(parent);
+                    children[parent].push(i);
+                }
+                // Dangling parent (only possible without foreign keys): unreachable from roots
+                Some(None) => {}
+            }
+        }
+
+        let mut graph = Self {
+            tasks,
+            index,
+            parents,
+            children,
+            roots,
+            now: Utc::now(),
+        };
+        for i in 0..graph.tasks.len() {
+            let (depth, blocked) = graph.walk_ancestors(i);
+            graph.tasks[i].depth = Some(depth);
+            graph.tasks[i].effectively_blocked = blocked;
+        }
+        graph
+    }
+
+    /// Depth and effective blockage of task `i`, walking up the parent chain.
+    /// A deferred task or ancestor blocks like an unsatisfied blocker.
+    /// Bounded by the task count so a corrupt parent cycle cannot loop forever.
+    fn walk_ancestors(&self, i: usize) -> (i32, bool) {
+        let mut blocked = self.is_held_back(i);
+        let mut depth = 0;
+        let mut current = i;
+        while self.tasks[current].parent_id.is_some() {
+            depth += 1;
+            // A dangling parent still counts as one level, as in `task_repo::get_task_depth`
+            let Some(parent) = self.parents[current] else {
+                break;
+            };
+            if depth as usize > self.tasks.len() {
+                break;
+            }
+            blocked = blocked || self.is_held_back(parent);
+            current = parent;
+        }
+        (depth, blocked)
+    }
+
+    /// Task `i` itself is deferred or has an unsatisfied blocker
+    fn is_held_back(&self, i: usize) -> bool {
+        self.tasks[i].is_deferred_at(self.now) || self.has_unsatisfied_blockers(i)
+    }
+
+    fn has_unsatisfied_blockers(&self, i: usize) -> bool {
+        self.tasks[i]
+            .blocked_by
+            .iter()
+            .any(|b| !self.satisfies_blocker(b))
+    }
+
+    #[allow(dead_code)] // Used in tests and benchmarks
+    pub fn len(&self) -> usize {
+        self.tasks.len()
+    }
+
+    #[allow(dead_code)] // Used in tests and benchmarks
+    pub fn is_empty(&self) -> bool {
+        self.tasks.is_empty()
+    }
+
+    /// Task with depth and effective blockage computed
+    pub fn get(&self, id: &TaskId) -> Option<&Task> {
+        self.index.get(id).map(|&i| &self.tasks[i])
+    }
+
+    fn get_or_err(&self, id: &TaskId) -> Result<&Task> {
+        self.get(id)
+            .ok_or_else(|| OsError::TaskNotFound(id.clone()))
+    }
+
+    /// Completed and not cancelled. Missing tasks never satisfy a blocker.
+    pub fn satisfies_blocker(&self, id: &TaskId) -> bool {
+        self.get(id).is_some_and(|t| t.completed && !t.cancelled)
+    }
+
+    /// Milestones in sibling order
+    pub fn roots(&self) -> impl Iterator<Item = &Task> {
+        self.roots.iter().map(|&i| &self.tasks[i])
+    }
+
+    /// Children in sibling order
+    pub fn children(&self, id: &TaskId) -> impl Iterator<Item = &Task> {
+        self.index
+            .get(id)
+            .map(|&i| self.children[i].as_slice())
+            .unwrap_or_default()
+            .iter()
+            .map(|&i| &self.tasks[i])
+    }
+
+    /// Parent chain, nearest first (excluding `id` itself). Bounded by the task
+    /// count so a corrupt parent cycle cannot loop forever.
+    pub fn ancestors(&self, id: &TaskId) -> impl Iterator<Item = &Task> {
+        let mut current = self.index.get(id).copied();
+        let mut steps = 0;
+        std::iter::from_fn(move || {
+            let parent = self.parents[current?]?;
+            steps += 1;
+            if steps > self.tasks.len() {
+                return None;
+            }
+            current = Some(parent);
+            Some(&self.tasks[parent])
+        })
+    }
+
+    /// All descendants, depth-first in sibling order (excluding `id` itself)
+    pub fn descendants(&self, id: &TaskId) -> Vec<&Task> {
+        let mut result = Vec::new();
+        let mut seen = HashSet::new();
+        let mut stack: Vec<&Task> = self.children(id).collect();
+        stack.reverse();
+        while let Some(task) = stack.pop() {
+            if !seen.insert(&task.id) {
+                continue;
+            }
+            result.push(task);
+            let mut children: Vec<&Task> = self.children(&task.id).collect();
+            children.reverse();
+            stack.extend(children);
+        }
+        result
+    }
+
+    /// Tasks matching `filter`, in sibling order. `id_matches` holds the ids
+    /// satisfying the filters answered in SQL: `filter.metadata` (see
+    /// `metadata_repo::matching_task_ids`) and `filter.linked`.
+    pub fn list(
+        &self,
+        filter: &ListTasksFilter,
+        id_matches: Option<&HashSet<TaskId>>,
+    ) -> Vec<&Task> {
+        self.tasks
+            .iter()
+            .filter(|t| filter.parent_id.is_none() || t.parent_id == filter.parent_id)
+            .filter(|t| filter.completed.is_none_or(|c| t.completed == c))
+            .filter(|t| filter.depth.is_none_or(|d| t.depth == Some(d)))
+            .filter(|t| filter.archived.is_none_or(|a| t.archived == a))
+            .filter(|t| id_matches.is_none_or(|ids| ids.contains(&t.id)))
+            .filter(|t| has_labels(t, &filter.labels))
+            .filter(|t| filter.repo_id.is_none() || t.repo_id == filter.repo_id)
+            .filter(|t| !filter.overdue || t.is_overdue_at(self.now))
+            .filter(|t| {
+                filter
+                    .due_before
+                    .is_none_or(|before| t.due_at.is_some_and(|due| due < before))
+            })
+            .filter(|t| !filter.ready || (t.is_active_for_work() && !t.effectively_blocked))
+            .collect()
+    }
+
+    // =========================================================================
+    // NEXT-READY & START-TARGET RESOLUTION
+    // =========================================================================
+
+    /// Find the next ready task (deepest incomplete unblocked leaf), searching
+    /// one milestone or all milestones in priority order.
+    pub fn next_ready(&self, milestone: Option<&TaskId>) -> Result<Option<TaskId>> {
+        self.next_ready_matching(milestone, &NextReadyFilter::default())
+    }
+
+    /// `next_ready`, skipping candidates that fail `filter`. The search continues
+    /// past a candidate without the labels (or in another repo) to the next one in
+    /// DFS order; a subtree under someone else's claim is skipped whole.
+    pub fn next_ready_matching(
+        &self,
+        milestone: Option<&TaskId>,
+        filter: &NextReadyFilter,
+    ) -> Result<Option<TaskId>> {
+        match milestone {
+            Some(id) => {
+                let root = self.get_or_err(id)?;
+                // Claims on the milestone's ancestors cover it too
+                if filter.unclaimed && self.claimed_by_other(root, filter) {
+                    return Ok(None);
+                }
+                Ok(self.find_next_ready_under(root, true, filter))
+            }
+            None => Ok(self
+                .roots()
+                .find_map(|root| self.find_next_ready_under(root, true, filter))),
+        }
+    }
+
+    /// A live claim on `task` or an ancestor is held by someone other than `filter.agent`
+    fn claimed_by_other(&self, task: &Task, filter: &NextReadyFilter) -> bool {
+        std::iter::once(task)
+            .chain(self.ancestors(&task.id))
+            .filter_map(|t| t.claim.as_ref())
+            .any(|c| !c.is_held_by(filter.agent.as_deref()))
+    }
+
+    /// DFS to find next ready task under a given root.
+    /// `ancestors_unblocked` tracks whether all ancestors are unblocked.
+    fn find_next_ready_under(
+        &self,
+        task: &Task,
+        ancestors_unblocked: bool,
+        filter: &NextReadyFilter,
+    ) -> Option<TaskId> {
+        // If task is not active (completed, cancelled, or archived), no ready work here
+        if !task.is_active_for_work() {
+            return None;
+        }
+        // Ancestors were checked on the way down, so only this task's own claim matters
+        if filter.unclaimed
+            && task
+                .claim
+                .as_ref()
+                .is_some_and(|c| !c.is_held_by(filter.agent.as_deref()))
+        {
+            return None;
+        }
+
+        let effectively_unblocked = ancestors_unblocked
+            && !task.is_deferred_at(self.now)
+            && task.blocked_by.iter().all(|b| self.satisfies_blocker(b));
+        let matches = has_labels(task, &filter.labels)
+            && (filter.repo_id.is_none() || task.repo_id == filter.repo_id);
+
+        let mut children = self.children(&task.id).peekable();
+        if children.peek().is_none() {
+            // Leaf node - return if effectively unblocked
+            return (effectively_unblocked && matches).then(|| task.id.clone());
+        }
+
+        let mut all_children_complete = true;
+        for child in children {
+            all_children_complete &= child.is_finished_for_hierarchy();
+            if let Some(ready_id) = self.find_next_ready_under(child, effectively_unblocked, filter)
+            {
+                return Some(ready_id);
+            }
+        }
+
+        // A non-leaf whose children are all done is itself ready once unblocked
+        (all_children_complete && effectively_unblocked && matches).then(|| task.id.clone())
+    }
+
+    /// Resolve which task to actually start given a requested root.
+    /// Follows blockers until finding a startable task.
+    ///
+    /// Errors if no startable task found or if blocker cycle detected.
+    pub fn resolve_start_target(&self, requested_root: &TaskId) -> Result<TaskId> {
+        let mut blocker_stack: Vec<TaskId> = Vec::new();
+        self.resolve_start_target_inner(requested_root, &mut blocker_stack)
+    }
+
+    fn resolve_start_target_inner(
+        &self,
+        root: &TaskId,
+        blocker_stack: &mut Vec<TaskId>,
+    ) -> Result<TaskId> {
+        let task = self.get_or_err(root)?;
+
+        let mut leaves = Vec::new();
+        self.collect_incomplete_leaves(task, vec![&task.id], &mut leaves);
+
+        for leaf_path in leaves {
+            // Deferred work is skipped, not followed: there is nothing to start first
+            if leaf_path
+                .iter()
+                .any(|id| self.get(id).is_some_and(|t| t.is_deferred_at(self.now)))
+            {
+                continue;
+            }
+            let Some(incomplete_blockers) = self.first_blockage_along_chain(&leaf_path) else {
+                // Leaf is startable - return it
+                if let Some(leaf_id) = leaf_path.last() {
+                    return Ok((*leaf_id).clone());
+                }
+                continue;
+            };
+
+            // Blocked - follow blockers
+            for blocker_id in incomplete_blockers {
+                if blocker_stack.contains(blocker_id) {
+                    let mut chain = blocker_stack.clone();
+                    chain.push(blocker_id.clone());
+                    return Err(OsError::BlockerCycleDetected {
+                        message: format!("Blocker cycle detected: {:?}", chain),
+                        chain,
+                    });
+                }
+
+                blocker_stack.push(blocker_id.clone());
+                match self.resolve_start_target_inner(blocker_id, blocker_stack) {
+                    Ok(found) => return Ok(found),
+                    Err(OsError::NoStartableTask { .. }) => {
+                        // Continue searching other blockers
+                    }
+                    Err(e) => return Err(e),
+                }
+                blocker_stack.pop();
+            }
+        }
+
+        Err(OsError::NoStartableTask {
+            message: format!("No startable task found under {}", root),
+            requested: root.clone(),
+        })
+    }
+
+    /// Collect incomplete leaf paths (root->...->leaf) in priority order.
+    /// A task whose children are all finished counts as a leaf.
+    fn collect_incomplete_leaves<'g>(
+        &'g self,
+        task: &'g Task,
+        path: Vec<&'g TaskId>,
+        leaves: &mut Vec<Vec<&'g TaskId>>,
+    ) {
+        if task.is_finished_for_hierarchy() {
+            return;
+        }
+
+        let unfinished: Vec<&Task> = self
+            .children(&task.id)
+            .filter(|c| !c.is_finished_for_hierarchy())
+            .collect();
+        if unfinished.is_empty() {
+            leaves.push(path);
+            return;
+        }
+
+        for child in unfinished {
+            let mut child_path = path.clone();
+            child_path.push(&child.id);
+            self.collect_incomplete_leaves(child, child_path, leaves);
+        }
+    }
+
+    /// Unsatisfied blockers of the first blocked task walking root->leaf, if any.
+    fn first_blockage_along_chain<'g>(&'g self, leaf_path: &[&TaskId]) -> Option<Vec<&'g TaskId>> {
+        leaf_path.iter().find_map(|id| {
+            let unsatisfied: Vec<&TaskId> = self
+                .get(id)?
+                .blocked_by
+                .iter()
+                .filter(|b| !self.satisfies_blocker(b))
+                .collect();
+            (!unsatisfied.is_empty()).then_some(unsatisfied)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::TaskService;
+    use crate::db::schema;
+    use crate::types::CreateTaskInput;
+
+    fn setup_db() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch("PRAGMA foreign_keys = ON;").unwrap();
+        schema::init_schema(&conn).unwrap();
+        conn
+    }
+
+    fn create(
+        svc: &TaskService,
+        description: &str,
+        parent: Option<&TaskId>,
+        priority: i32,
+        blocked_by: Vec<TaskId>,
+    ) -> TaskId {
+        svc.create(&CreateTaskInput {
+            description: description.to_string(),
+            parent_id: parent.cloned(),
+            priority: Some(priority),
+            blocked_by,
+            ..Default::default()
+        })
+        .unwrap()
+        .id
+    }
+
+    #[test]
+    fn test_snapshot_matches_per_task_queries() {
+        let conn = setup_db();
+        let svc = TaskService::new(&conn);
+        let m1 = create(&svc, "M1", None, 1, vec![]);
+        let m2 = create(&svc, "M2", None, 0, vec![]);
+        let t1 = create(&svc, "T1", Some(&m1), 1, vec![]);
+        let t2 = create(&svc, "T2", Some(&m1), 1, vec![m2.clone()]);
+        let s1 = create(&svc, "S1", Some(&t2), 2, vec![]);
+
+        let graph = TaskGraph::load(&conn).unwrap();
+        assert_eq!(graph.len(), 5);
+
+        // Milestones ordered by priority
+        let roots: Vec<_> = graph.roots().map(|t| t.id.clone()).collect();
+        assert_eq!(roots, vec![m2.clone(), m1.clone()]);
+
+        for id in [&m1, &m2, &t1, &t2, &s1] {
+            let expected = svc.get(id).unwrap();
+            let task = graph.get(id).unwrap();
+            assert_eq!(task.depth, expected.depth, "{id}");
+            assert_eq!(task.effectively_blocked, expected.effectively_blocked);
+            assert_eq!(task.blocked_by, expected.blocked_by);
+            assert_eq!(task.blocks, expected.blocks);
+        }
+        // S1 is blocked through its parent
+        assert!(graph.get(&s1).unwrap().effectively_blocked);
+
+        let descendants: Vec<_> = graph
+            .descendants(&m1)
+            .iter()
+            .map(|t| t.id.clone())
+            .collect();
+        assert_eq!(descendants, vec![t1, t2, s1]);
+    }
+
+    #[test]
+    fn test_next_ready_and_start_target_follow_blockers() {
+        let conn = setup_db();
+        let svc = TaskService::new(&conn);
+        let blocker = create(&svc, "Blocker", None, 2, vec![]);
+        let blocker_leaf = create(&svc, "Blocker leaf", Some(&blocker), 1, vec![]);
+        let milestone = create(&svc, "Milestone", None, 0, vec![blocker.clone()]);
+        create(&svc, "Blocked leaf", Some(&milestone), 1, vec![]);
+
+        let graph = TaskGraph::load(&conn).unwrap();
+        // Milestone is p0 but blocked, so the search moves on to the blocker's subtree
+        assert_eq!(graph.next_ready(None).unwrap(), Some(blocker_leaf.clone()));
+        assert_eq!(graph.next_ready(Some(&milestone)).unwrap(), None);
+        assert_eq!(
+            graph.resolve_start_target(&milestone).unwrap(),
+            blocker_leaf
+        );
+        assert!(matches!(
+            graph.next_ready(Some(&TaskId::new())),
+            Err(OsError::TaskNotFound(_))
+        ));
+    }
+
+    #[test]
+    fn test_start_target_reports_blocker_cycle() {
+        let conn = setup_db();
+        let svc = TaskService::new(&conn);
+        let a = create(&svc, "A", None, 1, vec![]);
+        let b = create(&svc, "B", None, 1, vec![a.clone()]);
+        // Insert the closing edge directly; the service refuses to create cycles
+        task_repo::add_blocker(&conn, &a, &b).unwrap();
+
+        let graph = TaskGraph::load(&conn).unwrap();
+        assert!(matches!(
+            graph.resolve_start_target(&a),
+            Err(OsError::BlockerCycleDetected { .. })
+        ));
+    }
+}
@@ -1,16 +1,29 @@
 use std::collections::HashSet;
 
+use chrono::{DateTime, Duration, Utc};
 use rusqlite::Connection;
 
-use crate::db::{self, learning_repo, task_repo};
+use crate::core::context::get_ancestor_chain;
+use crate::core::links::{self, LinkTarget};
+use crate::core::repos;
+use crate::core::task_graph::TaskGraph;
+use crate::core::trash;
+use crate::db::event_repo::{self, EventBody};
+use crate::db::search_repo::{self, SearchHit};
+use crate::db::Comment;
+use crate::db::{
+    self, claim_repo, comment_repo, label_repo, learning_repo, link_repo, metadata_repo, retry,
+    session_repo, settings_repo, task_repo, trash_repo::TrashEntry, TaskLink,
+};
 use crate::error::{OsError, Result};
-use crate::id::TaskId;
+use crate::id::{CommentId, LinkId, TaskId};
 use crate::types::{
-    CreateTaskInput, InheritedLearnings, LifecycleState, ListTasksFilter, Task, TaskContext,
-    UpdateTaskInput,
+    CreateTaskInput, InheritedLearnings, LifecycleState, ListTasksFilter, MovePosition,
+    NextReadyFilter, Task, TaskClaim, TaskContext, TaskSession, UpdateTaskInput,
 };
 
-const MAX_DEPTH: i32 = 2;
+/// Lease taken by `os task start --agent` when the agent holds no claim yet
+pub(crate) const DEFAULT_CLAIM_TTL_MINUTES: i64 = 30;
 
 pub struct TaskService<'a> {
     conn: &'a Connection,
@@ -29,9 +42,15 @@ impl<'a> TaskService<'a> {
             }
         }
 
+        let mut repo_id = input.repo_id.clone();
+        if let Some(ref id) = repo_id {
+            repos::ensure_exists(self.conn, id)?;
+        }
+
         if let Some(ref parent_id) = input.parent_id {
             let parent = task_repo::get_task(self.conn, parent_id)?
                 .ok_or_else(|| OsError::ParentNotFound(parent_id.clone()))?;
+            repo_id = repo_id.or(parent.repo_id.clone());
 
             // Cannot create child under inactive parent (cancelled, completed, or archived)
             // This prevents creating "stuck" tasks that can't be reached via next_ready()
@@ -42,9 +61,12 @@ impl<'a> TaskService<'a> {
                 });
             }
 
+            let settings = settings_repo::load(self.conn)?;
             let parent_depth = task_repo::get_task_depth(self.conn, parent_id)?;
-            if parent_depth >= MAX_DEPTH {
-                return Err(OsError::MaxDepthExceeded);
+            if parent_depth >= settings.deepest() {
+                return Err(OsError::MaxDepthExceeded {
+                    max: settings.max_depth,
+                });
             }
         }
 
@@ -66,10 +88,21 @@ impl<'a> TaskService<'a> {
             }
         }
 
-        let mut task = task_repo::create_task(self.conn, input)?;
-        task.depth = Some(self.get_depth(&task.id)?);
-        task.effectively_blocked = self.is_effectively_blocked(&task)?;
-        Ok(task)
+        let labels = label_repo::normalize_labels(&input.labels)?;
+        let input = &CreateTaskInput {
+            repo_id,
+            ..input.clone()
+        };
+
+        self.atomically(|| {
+            let mut task = task_repo::create_task(self.conn, input)?;
+            label_repo::add_labels(self.conn, &task.id, &labels)?;
+            task.labels = labels;
+            task.depth = Some(self.get_depth(&task.id)?);
+            task.effectively_blocked = self.is_effectively_blocked(&task)?;
+            event_repo::append(self.conn, &EventBody::TaskCreated { task: task.clone() })?;
+            Ok(task)
+        })
     }
 
     pub fn get(&self, id: &TaskId) -> Result<Task> {
@@ -82,18 +115,54 @@ impl<'a> TaskService<'a> {
         Ok(task)
     }
 
+    /// Tasks matching `filter`, evaluated over one `TaskGraph` snapshot.
     pub fn list(&self, filter: &ListTasksFilter) -> Result<Vec<Task>> {
-        let mut tasks = task_repo::list_tasks(self.conn, filter)?;
-        for task in &mut tasks {
-            task.depth = Some(self.get_depth(&task.id)?);
-            task.effectively_blocked = self.is_effectively_blocked(task)?;
+        let filter = &ListTasksFilter {
+            labels: label_repo::normalize_labels(&filter.labels)?,
+            ..filter.clone()
+        };
+        let graph = TaskGraph::load(self.conn)?;
+        let mut id_matches: Option<HashSet<TaskId>> = None;
+        let mut restrict = |ids: HashSet<TaskId>| {
+            id_matches = Some(match id_matches.take() {
+                Some(prev) => prev.intersection(&ids).cloned().collect(),
+                None => ids,
+            });
+        };
+        for meta in &filter.metadata {
+            restrict(metadata_repo::matching_task_ids(self.conn, meta)?);
         }
-        // Post-filter by effective readiness (ancestor-aware) when --ready requested
-        // DB layer does direct-blocker pre-filter; this catches ancestor-blocked tasks
-        if filter.ready {
-            tasks.retain(|t| t.is_active_for_work() && !t.effectively_blocked);
+        if let Some(path) = &filter.linked {
+            let path = links::normalize_path(path, None)?;
+            restrict(link_repo::tasks_linked_to_path(self.conn, &path)?);
         }
-        Ok(tasks)
+        Ok(graph
+            .list(filter, id_matches.as_ref())
+            .into_iter()
+            .cloned()
+            .collect())
+    }
+
+    /// Full-text search, best match first. Tasks are hydrated like `list` results.
+    pub fn search(
+        &self,
+        query: &str,
+        include_archived: bool,
+        limit: usize,
+    ) -> Result<Vec<SearchHit<Task>>> {
+        let hits = search_repo::search_tasks(self.conn, query, include_archived, limit)?;
+        let mut results = Vec::with_capacity(hits.len());
+        for hit in hits {
+            let mut task = self.get_task_or_err(&hit.item)?;
+            task.depth = Some(self.get_depth(&task.id)?);
+            task.effectively_blocked = self.is_effectively_blocked(&task)?;
+            results.push(SearchHit {
+                item: task,
+                score: hit.score,
+                snippet: hit.snippet,
+            });
+        }
+        Ok(results)
     }
 
     pub fn update(&self, id: &TaskId, input: &UpdateTaskInput) -> Result<Task> {
@@ -125,17 +194,13 @@ impl<'a> TaskService<'a> {
                 return Err(OsError::ParentCycle);
             }
 
-            // Then check depth limit for this task
-            let parent_depth = task_repo::get_task_depth(self.conn, new_parent_id)?;
-            if parent_depth >= MAX_DEPTH {
-                return Err(OsError::MaxDepthExceeded);
-            }
-
-            // Check subtree depth: descendants must not exceed MAX_DEPTH after reparent
-            let subtree_depth = self.max_subtree_depth(id)?;
-            let new_task_depth = parent_depth + 1;
-            if new_task_depth + subtree_depth > MAX_DEPTH {
-                return Err(OsError::MaxDepthExceeded);
+            // Then check depth limit for this task and, after the move, its deepest descendant
+            let settings = settings_repo::load(self.conn)?;
+            let new_task_depth = task_repo::get_task_depth(self.conn, new_parent_id)? + 1;
+            if new_task_depth + self.max_subtree_depth(id)? > settings.deepest() {
+                return Err(OsError::MaxDepthExceeded {
+                    max: settings.max_depth,
+                });
             }
 
             // Validate existing blockers against new ancestor chain
@@ -152,9 +217,76 @@ impl<'a> TaskService<'a> {
             }
         }
 
-        let mut task = task_repo::update_task(self.conn, id, input)?;
+        // A started task's branch lives in its current repo
+        if let Some(repo_id) = &input.repo_id {
+            if let Some(repo_id) = repo_id {
+                repos::ensure_exists(self.conn, repo_id)?;
+            }
+            let task = task_repo::get_task(self.conn, id)?
+                .ok_or_else(|| OsError::TaskNotFound(id.clone()))?;
+            if task.bookmark.is_some() && task.repo_id != *repo_id {
+                return Err(OsError::RepoChangeAfterStart(id.clone()));
+            }
+        }
+
+        let labels = label_repo::normalize_labels(&input.labels)?;
+        self.atomically(|| {
+            label_repo::add_labels(self.conn, id, &labels)?;
+            let mut task = task_repo::update_task(self.conn, id, input)?;
+            task.depth = Some(self.get_depth(id)?);
+            task.effectively_blocked = self.is_effectively_blocked(&task)?;
+            event_repo::append(self.conn, &EventBody::TaskUpdated { task: task.clone() })?;
+            Ok(task)
+        })
+    }
+
+    /// Reorder `id` among its siblings. Priority still orders siblings first, so
+    /// `Before`/`After` only accept a sibling of the same priority; `First`/`Last`
+    /// act within the task's priority. Siblings are renumbered densely.
+    pub fn move_task(&self, id: &TaskId, position: &MovePosition) -> Result<Task> {
+        // Guard: archived tasks cannot be modified
+        self.guard_mutable(id)?;
+        let task = self.get_task_or_err(id)?;
+
+        if let MovePosition::Before(other) | MovePosition::After(other) = position {
+            if other == id {
+                return Err(OsError::InvalidMove(format!(
+                    "cannot move {id} relative to itself"
+                )));
+            }
+            let sibling = self.get_task_or_err(other)?;
+            if sibling.parent_id != task.parent_id {
+                return Err(OsError::InvalidMove(format!(
+                    "{other} is not a sibling of {id}"
+                )));
+            }
+            if sibling.priority != task.priority {
+                return Err(OsError::InvalidMove(format!(
+                    "{other} is p{} but {id} is p{} - siblings are ordered by priority first",
+                    sibling.priority, task.priority
+                )));
+            }
+        }
+
+        let tx = self.conn.unchecked_transaction()?;
+        let mut order = task_repo::get_sibling_ids_by_rank(&tx, task.parent_id.as_ref())?;
+        order.retain(|s| s != id);
+        let index = match position {
+            MovePosition::First => 0,
+            MovePosition::Last => order.len(),
+            MovePosition::Before(other) | MovePosition::After(other) => {
+                let at = order.iter().position(|s| s == other).unwrap_or(order.len());
+                at + usize::from(matches!(position, MovePosition::After(_)))
+            }
+        };
+        order.insert(index, id.clone());
+        task_repo::set_sibling_ranks(&tx, &order)?;
+        task_repo::touch_task(&tx, id)?;
+        let mut task = self.get_task_or_err(id)?;
         task.depth = Some(self.get_depth(id)?);
         task.effectively_blocked = self.is_effectively_blocked(&task)?;
+        event_repo::append(&tx, &EventBody::TaskUpdated { task: task.clone() })?;
+        tx.commit()?;
         Ok(task)
     }
 
@@ -178,10 +310,13 @@ impl<'a> TaskService<'a> {
         if !task_repo::task_exists(self.conn, id)? {
             return Err(OsError::TaskNotFound(id.clone()));
         }
-        let mut task = task_repo::start_task(self.conn, id)?;
-        task.depth = Some(self.get_depth(id)?);
-        task.effectively_blocked = self.is_effectively_blocked(&task)?;
-        Ok(task)
+        self.atomically(|| {
+            let mut task = task_repo::start_task(self.conn, id)?;
+            task.depth = Some(self.get_depth(id)?);
+            task.effectively_blocked = self.is_effectively_blocked(&task)?;
+            event_repo::append(self.conn, &EventBody::TaskStarted { task: task.clone() })?;
+            Ok(task)
+        })
     }
 
     pub fn complete(&self, id: &TaskId, result: Option<&str>) -> Result<Task> {
@@ -220,25 +355,32 @@ impl<'a> TaskService<'a> {
             return Err(OsError::PendingChildren);
         }
 
-        // Add learnings to this task first (origin = self)
-        for content in learnings {
-            learning_repo::add_learning(self.conn, id, content, None)?;
-        }
+        self.atomically(|| {
+            // Add learnings to this task first (origin = self)
+            for content in learnings {
+                learning_repo::add_learning(self.conn, id, content, None)?;
+            }
 
-        let mut task = task_repo::complete_task(self.conn, id, result, commit_sha)?;
+            // A finished task needs no lease, and its clock stops
+            self.drop_claim(id)?;
+            self.end_session(id)?;
 
-        // NOTE: Dependency edges are preserved on completion.
-        // Readiness is computed from completion state (blocker.completed), not edge removal.
-        // This allows reopen() to naturally re-block dependents without edge reconstruction.
+            let mut task = task_repo::complete_task(self.conn, id, result, commit_sha)?;
+            task.depth = Some(self.get_depth(id)?);
+            event_repo::append(self.conn, &EventBody::TaskCompleted { task: task.clone() })?;
 
-        // Bubble all learnings (including newly added) to immediate parent
-        if let Some(ref parent_id) = task.parent_id {
-            learning_repo::bubble_learnings(self.conn, id, parent_id)?;
-        }
+            // NOTE: Dependency edges are preserved on completion.
+            // Readiness is computed from completion state (blocker.completed), not edge removal.
+            // This allows reopen() to naturally re-block dependents without edge reconstruction.
 
-        task.depth = Some(self.get_depth(id)?);
-        task.effectively_blocked = self.is_effectively_blocked(&task)?;
-        Ok(task)
+            // Bubble all learnings (including newly added) to immediate parent
+            if let Some(ref parent_id) = task.parent_id {
+                learning_repo::bubble_learnings(self.conn, id, parent_id)?;
+            }
+
+            task.effectively_blocked = self.is_effectively_blocked(&task)?;
+            Ok(task)
+        })
     }
 
     pub fn reopen(&self, id: &TaskId) -> Result<Task> {
@@ -261,17 +403,19 @@ impl<'a> TaskService<'a> {
             }
         }
 
-        let mut task = task_repo::reopen_task(self.conn, id)?;
-        task.depth = Some(self.get_depth(id)?);
-        task.effectively_blocked = self.is_effectively_blocked(&task)?;
-        Ok(task)
+        self.atomically(|| {
+            let mut task = task_repo::reopen_task(self.conn, id)?;
+            task.depth = Some(self.get_depth(id)?);
+            task.effectively_blocked = self.is_effectively_blocked(&task)?;
+            event_repo::append(self.conn, &EventBody::TaskReopened { task: task.clone() })?;
+            Ok(task)
+        })
     }
 
-    pub fn delete(&self, id: &TaskId) -> Result<()> {
-        if !task_repo::task_exists(self.conn, id)? {
-            return Err(OsError::TaskNotFound(id.clone()));
-        }
-        task_repo::delete_task(self.conn, id)
+    /// Move the task and its subtree to the trash (see `core::trash`).
+    /// `os task restore` brings it back; only a purge deletes it for good.
+    pub fn delete(&self, id: &TaskId) -> Result<TrashEntry> {
+        trash::move_to_trash(self.conn, id)
     }
 
     /// Cancel a task using lifecycle state validation.
@@ -307,10 +451,16 @@ impl<'a> TaskService<'a> {
             return Err(OsError::PendingChildren);
         }
 
-        let mut task = task_repo::cancel_task(self.conn, id)?;
-        task.depth = Some(self.get_depth(id)?);
-        task.effectively_blocked = self.is_effectively_blocked(&task)?;
-        Ok(task)
+        self.atomically(|| {
+            self.drop_claim(id)?;
+            self.end_session(id)?;
+
+            let mut task = task_repo::cancel_task(self.conn, id)?;
+            task.depth = Some(self.get_depth(id)?);
+            task.effectively_blocked = self.is_effectively_blocked(&task)?;
+            event_repo::append(self.conn, &EventBody::TaskCancelled { task: task.clone() })?;
+            Ok(task)
+        })
     }
 
     /// Archive a task using lifecycle state validation.
@@ -340,7 +490,7 @@ impl<'a> TaskService<'a> {
         let depth = self.get_depth(id)?;
 
         // For milestones: validate all descendants are finished, then cascade archive
-        if depth == 0 {
+        let descendants = if task.is_root() {
             let descendants = task_repo::get_all_descendants(self.conn, id)?;
 
             // Check all descendants are finished (completed, cancelled, or already archived)
@@ -354,19 +504,24 @@ impl<'a> TaskService<'a> {
                     }
                 }
             }
+            descendants
+        } else {
+            Vec::new()
+        };
 
+        self.atomically(|| {
             // Archive all non-archived descendants
-            for desc in &descendants {
-                if !desc.archived {
-                    task_repo::archive_task(self.conn, &desc.id)?;
-                }
+            for desc in descendants.iter().filter(|d| !d.archived) {
+                let archived = task_repo::archive_task(self.conn, &desc.id)?;
+                event_repo::append(self.conn, &EventBody::TaskArchived { task: archived })?;
             }
-        }
 
-        let mut task = task_repo::archive_task(self.conn, id)?;
-        task.depth = Some(depth);
-        task.effectively_blocked = self.is_effectively_blocked(&task)?;
-        Ok(task)
+            let mut task = task_repo::archive_task(self.conn, id)?;
+            task.depth = Some(depth);
+            task.effectively_blocked = self.is_effectively_blocked(&task)?;
+            event_repo::append(self.conn, &EventBody::TaskArchived { task: task.clone() })?;
+            Ok(task)
+        })
     }
 
     pub fn add_blocker(&self, task_id: &TaskId, blocker_id: &TaskId) -> Result<Task> {
@@ -377,6 +532,25 @@ impl<'a> TaskService<'a> {
             return Err(OsError::BlockerNotFound(blocker_id.clone()));
         }
 
+        self.validate_blocker(task_id, blocker_id)?;
+
+        self.atomically(|| {
+            task_repo::add_blocker(self.conn, task_id, blocker_id)?;
+            event_repo::append(
+                self.conn,
+                &EventBody::BlockerAdded {
+                    task_id: task_id.clone(),
+                    blocker_id: blocker_id.clone(),
+                },
+            )?;
+            Ok(())
+        })?;
+        self.get(task_id)
+    }
+
+    /// Reject edges the blocker graph doesn't allow: a task blocked by itself,
+    /// an ancestor or a descendant, or an edge closing a cycle.
+    pub(crate) fn validate_blocker(&self, task_id: &TaskId, blocker_id: &TaskId) -> Result<()> {
         // Reject self-block
         if task_id == blocker_id {
             return Err(OsError::InvalidBlockerRelation {
@@ -408,18 +582,415 @@ impl<'a> TaskService<'a> {
             return Err(OsError::BlockerCycle);
         }
 
-        task_repo::add_blocker(self.conn, task_id, blocker_id)?;
-        self.get(task_id)
+        Ok(())
     }
 
     pub fn remove_blocker(&self, task_id: &TaskId, blocker_id: &TaskId) -> Result<Task> {
         // Guard: archived tasks cannot be modified
         self.guard_mutable(task_id)?;
 
-        task_repo::remove_blocker(self.conn, task_id, blocker_id)?;
+        self.atomically(|| {
+            task_repo::remove_blocker(self.conn, task_id, blocker_id)?;
+            event_repo::append(
+                self.conn,
+                &EventBody::BlockerRemoved {
+                    task_id: task_id.clone(),
+                    blocker_id: blocker_id.clone(),
+                },
+            )?;
+            Ok(())
+        })?;
         self.get(task_id)
     }
 
+    /// Metadata object for a task, or the value at `path` when given.
+    pub fn get_metadata(
+        &self,
+        id: &TaskId,
+        path: Option<&str>,
+    ) -> Result<Option<serde_json::Value>> {
+        let task = self.get_task_or_err(id)?;
+        match path {
+            Some(path) => metadata_repo::get_metadata_path(self.conn, id, path),
+            None => Ok(task.metadata),
+        }
+    }
+
+    pub fn set_metadata(&self, id: &TaskId, path: &str, value: &serde_json::Value) -> Result<Task> {
+        // Guard: archived tasks cannot be modified
+        self.guard_mutable(id)?;
+
+        let tx = self.conn.unchecked_transaction()?;
+        metadata_repo::set_metadata_path(&tx, id, path, value)?;
+        task_repo::touch_task(&tx, id)?;
+        self.append_metadata_changed(&tx, id)?;
+        tx.commit()?;
+        self.get(id)
+    }
+
+    pub fn unset_metadata(&self, id: &TaskId, path: &str) -> Result<Task> {
+        // Guard: archived tasks cannot be modified
+        self.guard_mutable(id)?;
+
+        let tx = self.conn.unchecked_transaction()?;
+        metadata_repo::unset_metadata_path(&tx, id, path)?;
+        task_repo::touch_task(&tx, id)?;
+        self.append_metadata_changed(&tx, id)?;
+        tx.commit()?;
+        self.get(id)
+    }
+
+    /// Add labels (normalized to lowercase); labels the task already has are ignored.
+    pub fn add_labels(&self, id: &TaskId, labels: &[String]) -> Result<Task> {
+        self.change_labels(id, labels, label_repo::add_labels)
+    }
+
+    pub fn remove_labels(&self, id: &TaskId, labels: &[String]) -> Result<Task> {
+        self.change_labels(id, labels, label_repo::remove_labels)
+    }
+
+    fn change_labels(
+        &self,
+        id: &TaskId,
+        labels: &[String],
+        apply: fn(&Connection, &TaskId, &[String]) -> Result<()>,
+    ) -> Result<Task> {
+        // Guard: archived tasks cannot be modified
+        self.guard_mutable(id)?;
+        let labels = label_repo::normalize_labels(labels)?;
+
+        let tx = self.conn.unchecked_transaction()?;
+        apply(&tx, id, &labels)?;
+        task_repo::touch_task(&tx, id)?;
+        let mut task = self.get_task_or_err(id)?;
+        task.depth = Some(self.get_depth(id)?);
+        task.effectively_blocked = self.is_effectively_blocked(&task)?;
+        event_repo::append(&tx, &EventBody::TaskUpdated { task: task.clone() })?;
+        tx.commit()?;
+        Ok(task)
+    }
+
+    // =========================================================================
+    // REVISIONS
+    // =========================================================================
+
+    /// Fail with `RevisionConflict` unless `id` is still at revision `expected`.
+    /// No expectation (None) always passes.
+    pub fn check_revision(&self, id: &TaskId, expected: Option<i64>) -> Result<()> {
+        let Some(expected) = expected else {
+            return Ok(());
+        };
+        let actual = task_repo::get_revision(self.conn, id)?
+            .ok_or_else(|| OsError::TaskNotFound(id.clone()))?;
+        if actual != expected {
+            return Err(OsError::RevisionConflict {
+                task_id: id.clone(),
+                expected,
+                actual,
+            });
+        }
+        Ok(())
+    }
+
+    /// Run `op` only if `id` is still at revision `expected`, holding the write
+    /// lock from the check until `op` commits so no writer can slip in between.
+    /// Mutations inside `op` join this transaction; with no expectation it runs as is.
+    pub fn at_revision<T>(
+        &self,
+        id: &TaskId,
+        expected: Option<i64>,
+        op: impl FnOnce(&Self) -> Result<T>,
+    ) -> Result<T> {
+        if expected.is_none() {
+            return op(self);
+        }
+        let tx = retry::begin_immediate(self.conn)?;
+        self.check_revision(id, expected)?;
+        let value = op(self)?;
+        tx.commit()?;
+        Ok(value)
+    }
+
+    // =========================================================================
+    // CLAIMS
+    // =========================================================================
+
+    /// Claim `id` and its subtree for `agent` until `ttl` from now.
+    ///
+    /// Claiming a task the agent already holds renews it. Fails with
+    /// `TaskClaimed` while another agent holds a live claim on the task, an
+    /// ancestor or a descendant.
+    pub fn claim(&self, id: &TaskId, agent: &str, ttl: Duration) -> Result<TaskClaim> {
+        // IMMEDIATE so two agents cannot both see the task unclaimed
+        let tx = retry::begin_immediate(self.conn)?;
+        let claim = self.acquire_claim(id, agent, ttl)?;
+        tx.commit()?;
+        Ok(claim)
+    }
+
+    /// `claim` for callers that already hold the write lock.
+    pub(crate) fn acquire_claim(
+        &self,
+        id: &TaskId,
+        agent: &str,
+        ttl: Duration,
+    ) -> Result<TaskClaim> {
+        self.guard_mutable(id)?;
+        let now = Utc::now();
+        let lineage = claim_repo::lineage_claims(self.conn, id, now)?;
+        let below = claim_repo::descendant_claims(self.conn, id, now)?;
+        if let Some(other) = lineage.iter().chain(&below).find(|c| c.agent != agent) {
+            return Err(claimed_error(other));
+        }
+
+        let existing = lineage.into_iter().find(|c| &c.task_id == id);
+        let claim = TaskClaim {
+            task_id: id.clone(),
+            agent: agent.to_string(),
+            claimed_at: existing.as_ref().map_or(now, |c| c.claimed_at),
+            expires_at: now + ttl,
+        };
+        claim_repo::put_claim(self.conn, &claim)?;
+        let body = if existing.is_some() {
+            EventBody::ClaimRenewed {
+                claim: claim.clone(),
+            }
+        } else {
+            EventBody::ClaimAcquired {
+                claim: claim.clone(),
+            }
+        };
+        event_repo::append(self.conn, &body)?;
+        Ok(claim)
+    }
+
+    /// Extend `agent`'s live claim on `id` to `ttl` from now. An expired claim
+    /// cannot be renewed; claim the task again instead.
+    pub fn heartbeat(&self, id: &TaskId, agent: &str, ttl: Duration) -> Result<TaskClaim> {
+        self.get_task_or_err(id)?;
+        let tx = retry::begin_immediate(self.conn)?;
+        let now = Utc::now();
+        let Some(mut claim) =
+            claim_repo::get_live_claim(&tx, id, now)?.filter(|c| c.agent == agent)
+        else {
+            return Err(OsError::ClaimNotHeld {
+                task_id: id.clone(),
+                agent: agent.to_string(),
+            });
+        };
+        claim.expires_at = now + ttl;
+        claim_repo::put_claim(&tx, &claim)?;
+        event_repo::append(
+            &tx,
+            &EventBody::ClaimRenewed {
+                claim: claim.clone(),
+            },
+        )?;
+        tx.commit()?;
+        Ok(claim)
+    }
+
+    /// Drop the claim on `id`. Only its holder may release it unless `force`.
+    pub fn release(&self, id: &TaskId, agent: Option<&str>, force: bool) -> Result<()> {
+        self.get_task_or_err(id)?;
+        let tx = retry::begin_immediate(self.conn)?;
+        match claim_repo::get_claim(&tx, id)? {
+            Some(claim) if force || claim.is_held_by(agent) => self.drop_claim(id)?,
+            None if force => {}
+            _ => {
+                return Err(OsError::ClaimNotHeld {
+                    task_id: id.clone(),
+                    agent: agent.unwrap_or_default().to_string(),
+                })
+            }
+        }
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Fail with `TaskClaimed` if a live claim on `id` or an ancestor is held
+    /// by anyone but `agent`. Anonymous callers (None) are stopped by any claim.
+    pub fn check_claim(&self, id: &TaskId, agent: Option<&str>) -> Result<()> {
+        let claims = claim_repo::lineage_claims(self.conn, id, Utc::now())?;
+        match claims.iter().find(|c| !c.is_held_by(agent)) {
+            Some(claim) => Err(claimed_error(claim)),
+            None => Ok(()),
+        }
+    }
+
+    /// True if `agent` holds a live claim on `id` or an ancestor.
+    pub(crate) fn holds_claim(&self, id: &TaskId, agent: &str) -> Result<bool> {
+        let claims = claim_repo::lineage_claims(self.conn, id, Utc::now())?;
+        Ok(claims.iter().any(|c| c.agent == agent))
+    }
+
+    fn drop_claim(&self, id: &TaskId) -> Result<()> {
+        if let Some(claim) = claim_repo::get_claim(self.conn, id)? {
+            claim_repo::delete_claim(self.conn, id)?;
+            event_repo::append(
+                self.conn,
+                &EventBody::ClaimReleased {
+                    task_id: id.clone(),
+                    agent: claim.agent,
+                },
+            )?;
+        }
+        Ok(())
+    }
+
+    // =========================================================================
+    // COMMENTS
+    // =========================================================================
+
+    /// Append a comment to a task's thread. Archived tasks are read-only.
+    pub fn add_comment(&self, id: &TaskId, author: Option<&str>, body: &str) -> Result<Comment> {
+        self.guard_mutable(id)?;
+        comment_repo::add_comment(self.conn, id, author, body)
+    }
+
+    /// A task's comments, oldest first; with `latest`, only the last N.
+    pub fn comments(&self, id: &TaskId, latest: Option<usize>) -> Result<Vec<Comment>> {
+        self.get_task_or_err(id)?;
+        comment_repo::list_comments(self.conn, id, latest)
+    }
+
+    pub fn delete_comment(&self, id: &CommentId) -> Result<Comment> {
+        let comment = comment_repo::get_comment(self.conn, id)?
+            .ok_or_else(|| OsError::CommentNotFound(id.clone()))?;
+        self.guard_mutable(&comment.task_id)?;
+        comment_repo::delete_comment(self.conn, id)
+    }
+
+    // =========================================================================
+    // LINKS
+    // =========================================================================
+
+    /// Link a task to `target`, which must already have passed
+    /// [`links::validate`]. Archived tasks are read-only.
+    pub fn add_link(
+        &self,
+        id: &TaskId,
+        target: &LinkTarget,
+        note: Option<&str>,
+    ) -> Result<TaskLink> {
+        self.guard_mutable(id)?;
+        link_repo::add_link(
+            self.conn,
+            id,
+            target.kind(),
+            target.target(),
+            target.lines(),
+            note,
+        )
+    }
+
+    /// A task's links, oldest first.
+    pub fn links(&self, id: &TaskId) -> Result<Vec<TaskLink>> {
+        self.get_task_or_err(id)?;
+        link_repo::list_links(self.conn, id)
+    }
+
+    pub fn remove_link(&self, id: &LinkId) -> Result<TaskLink> {
+        let link =
+            link_repo::get_link(self.conn, id)?.ok_or_else(|| OsError::LinkNotFound(id.clone()))?;
+        self.guard_mutable(&link.task_id)?;
+        link_repo::remove_link(self.conn, id)
+    }
+
+    // =========================================================================
+    // SESSIONS
+    // =========================================================================
+
+    /// Stop the clock on a task without completing it. Fails with
+    /// `NoRunningSession` if no session is running.
+    pub fn pause(&self, id: &TaskId, agent: Option<&str>) -> Result<TaskSession> {
+        self.get_task_or_err(id)?;
+        let tx = retry::begin_immediate(self.conn)?;
+        self.check_claim(id, agent)?;
+        let session = self
+            .end_session(id)?
+            .ok_or_else(|| OsError::NoRunningSession(id.clone()))?;
+        tx.commit()?;
+        Ok(session)
+    }
+
+    /// Restart the clock on a started task. Unlike `os task start` this
+    /// touches no VCS state and skips the next-ready check.
+    pub fn resume(&self, id: &TaskId, agent: Option<&str>) -> Result<TaskSession> {
+        let tx = retry::begin_immediate(self.conn)?;
+        let task = self.get_task_or_err(id)?;
+        match task.lifecycle_state() {
+            LifecycleState::InProgress => {}
+            LifecycleState::Pending => return Err(OsError::TaskNotStarted(id.clone())),
+            LifecycleState::Completed => return Err(OsError::CannotStartCompleted),
+            LifecycleState::Cancelled => return Err(OsError::CannotStartCancelled),
+            LifecycleState::Archived => return Err(OsError::CannotModifyArchived),
+        }
+        self.check_claim(id, agent)?;
+        let session = self
+            .begin_session(id, agent)?
+            .ok_or_else(|| OsError::SessionRunning(id.clone()))?;
+        tx.commit()?;
+        Ok(session)
+    }
+
+    /// Open a session on `id` unless one is already running (None then).
+    /// For callers that already hold the write lock.
+    pub(crate) fn begin_session(
+        &self,
+        id: &TaskId,
+        agent: Option<&str>,
+    ) -> Result<Option<TaskSession>> {
+        if session_repo::running_session(self.conn, id)?.is_some() {
+            return Ok(None);
+        }
+        let session = session_repo::start_session(self.conn, id, agent, Utc::now())?;
+        event_repo::append(
+            self.conn,
+            &EventBody::SessionStarted {
+                session: session.clone(),
+            },
+        )?;
+        Ok(Some(session))
+    }
+
+    fn end_session(&self, id: &TaskId) -> Result<Option<TaskSession>> {
+        let session = session_repo::end_session(self.conn, id, Utc::now())?;
+        if let Some(ref session) = session {
+            event_repo::append(
+                self.conn,
+                &EventBody::SessionEnded {
+                    session: session.clone(),
+                },
+            )?;
+        }
+        Ok(session)
+    }
+
+    fn append_metadata_changed(&self, conn: &Connection, id: &TaskId) -> Result<()> {
+        event_repo::append(
+            conn,
+            &EventBody::TaskMetadataChanged {
+                task_id: id.clone(),
+                metadata: metadata_repo::get_metadata(conn, id)?,
+            },
+        )?;
+        Ok(())
+    }
+
+    /// Run `op` in a transaction, or in the caller's if one is already open, so
+    /// a row write and the event recording it commit or roll back together.
+    /// The write lock is taken up front, where losing a race can be retried.
+    fn atomically<T>(&self, op: impl FnOnce() -> Result<T>) -> Result<T> {
+        if !self.conn.is_autocommit() {
+            return op();
+        }
+        let tx = retry::begin_immediate(self.conn)?;
+        let value = op()?;
+        tx.commit()?;
+        Ok(value)
+    }
+
     fn get_depth(&self, id: &TaskId) -> Result<i32> {
         task_repo::get_task_depth(self.conn, id)
     }
@@ -453,103 +1024,38 @@ impl<'a> TaskService<'a> {
         Ok(task)
     }
 
+    /// Direct parent and root of `task`, when it has them. A depth-1 task's
+    /// parent is its root; `parent` is None for roots.
+    fn parent_and_root(&self, task: &Task) -> Result<(Option<Task>, Option<Task>)> {
+        let mut chain = get_ancestor_chain(self.conn, &task.id)?.into_iter().skip(1);
+        let parent = chain.next();
+        let root = chain.last().or_else(|| parent.clone());
+        Ok((parent, root))
+    }
+
     fn assemble_context_chain(&self, task: &Task) -> Result<TaskContext> {
-        let depth = task.depth.unwrap_or(0);
-        let own = task.context.clone();
-
-        match depth {
-            0 => {
-                // Milestone - only own context
-                Ok(TaskContext {
-                    own,
-                    parent: None,
-                    milestone: None,
-                })
-            }
-            1 => {
-                // Task with milestone parent
-                let parent = task
-                    .parent_id
-                    .as_ref()
-                    .and_then(|pid| task_repo::get_task(self.conn, pid).ok()?)
-                    .map(|p| p.context);
-
-                Ok(TaskContext {
-                    own,
-                    parent: parent.clone(),
-                    milestone: parent,
-                })
-            }
-            _ => {
-                // Subtask with task parent and milestone grandparent
-                let parent_task = task
-                    .parent_id
-                    .as_ref()
-                    .and_then(|pid| task_repo::get_task(self.conn, pid).ok()?);
-
-                let parent = parent_task.as_ref().map(|p| p.context.clone());
-
-                let milestone = parent_task
-                    .as_ref()
-                    .and_then(|p| p.parent_id.as_ref())
-                    .and_then(|mid| task_repo::get_task(self.conn, mid).ok()?)
-                    .map(|m| m.context);
-
-                Ok(TaskContext {
-                    own,
-                    parent,
-                    milestone,
-                })
-            }
-        }
+        // Roots have only their own context; a depth-1 task sees its root as
+        // both parent and milestone
+        let (parent, root) = self.parent_and_root(task)?;
+        Ok(TaskContext {
+            own: task.context.clone(),
+            parent: parent.map(|p| p.context),
+            milestone: root.map(|r| r.context),
+        })
     }
 
     fn assemble_inherited_learnings(&self, task: &Task) -> Result<InheritedLearnings> {
-        let depth = task.depth.unwrap_or(0);
-
-        match depth {
-            0 => {
-                // Milestone - no inherited learnings
-                Ok(InheritedLearnings {
-                    milestone: vec![],
-                    parent: vec![],
-                })
-            }
-            1 => {
-                // Task with milestone parent
-                let milestone = task
-                    .parent_id
-                    .as_ref()
-                    .map(|pid| learning_repo::list_learnings(self.conn, pid))
-                    .transpose()?
-                    .unwrap_or_default();
-
-                Ok(InheritedLearnings {
-                    milestone,
-                    parent: vec![],
-                })
-            }
-            _ => {
-                // Subtask with task parent and milestone grandparent
-                let parent_id = task.parent_id.as_ref();
-                let parent = parent_id
-                    .map(|pid| learning_repo::list_learnings(self.conn, pid))
-                    .transpose()?
-                    .unwrap_or_default();
-
-                let milestone_id = parent_id
-                    .and_then(|pid| task_repo::get_task(self.conn, pid).ok()?)
-                    .and_then(|p| p.parent_id);
-
-                let milestone = milestone_id
-                    .as_ref()
-                    .map(|mid| learning_repo::list_learnings(self.conn, mid))
-                    .transpose()?
-                    .unwrap_or_default();
-
-                Ok(InheritedLearnings { milestone, parent })
-            }
-        }
+        // Unlike context, a root's learnings are reported once, as milestone
+        let (parent, root) = self.parent_and_root(task)?;
+        let parent = match (&parent, &root) {
+            (Some(p), Some(r)) if p.id != r.id => learning_repo::list_learnings(self.conn, &p.id)?,
+            _ => vec![],
+        };
+        let milestone = match root {
+            Some(r) => learning_repo::list_learnings(self.conn, &r.id)?,
+            None => vec![],
+        };
+        Ok(InheritedLearnings { milestone, parent })
     }
 
     fn would_create_parent_cycle(&self, task_id: &TaskId, new_parent_id: &TaskId) -> Result<bool> {
@@ -593,74 +1099,22 @@ impl<'a> TaskService<'a> {
     /// - Returns None if no ready tasks found
     /// - Milestone with no children returns itself if ready
     pub fn next_ready(&self, milestone: Option<&TaskId>) -> Result<Option<TaskId>> {
-        match milestone {
-            Some(id) => {
-                let task = self.get(id)?;
-                self.find_next_ready_under(&task, true)
-            }
-            None => {
-                // Search all milestones (roots) in priority order
-                let roots = task_repo::list_roots(self.conn)?;
-                for root in roots {
-                    if let Some(ready_id) = self.find_next_ready_under(&root, true)? {
-                        return Ok(Some(ready_id));
-                    }
-                }
-                Ok(None)
-            }
-        }
+        TaskGraph::load(self.conn)?.next_ready(milestone)
     }
 
-    /// DFS to find next ready task under a given root.
-    /// `ancestors_unblocked` tracks whether all ancestors are unblocked.
-    fn find_next_ready_under(
+    /// Like `next_ready`, but only returns a task passing `filter` (labels,
+    /// claims). Ancestors that fail the label filter do not stop the search;
+    /// their blockers and claims still apply.
+    pub fn next_ready_matching(
         &self,
-        task: &Task,
-        ancestors_unblocked: bool,
+        milestone: Option<&TaskId>,
+        filter: &NextReadyFilter,
     ) -> Result<Option<TaskId>> {
-        // If task is not active (completed, cancelled, or archived), no ready work here
-        if !task.is_active_for_work() {
-            return Ok(None);
-        }
-
-        // Check if this task itself is blocked (cancelled tasks do NOT satisfy blockers)
-        let task_unblocked = task.blocked_by.iter().all(|blocker_id| {
-            task_repo::is_task_satisfies_blocker(self.conn, blocker_id).unwrap_or(false)
-        });
-        let effectively_unblocked = ancestors_unblocked && task_unblocked;
-
-        // Get children in priority order (reused for both DFS and all_complete check)
-        let children = task_repo::get_children_ordered(self.conn, &task.id)?;
-
-        if children.is_empty() {
-            // Leaf node - return if effectively unblocked
-            if effectively_unblocked {
-                return Ok(Some(task.id.clone()));
-            } else {
-                return Ok(None);
-            }
-        }
-
-        // Check if all children finished (completed or cancelled) before recursing
-        let all_children_complete = children.iter().all(|c| c.is_finished_for_hierarchy());
-
-        // Recurse into children (DFS)
-        for child in &children {
-            if let Some(ready_id) = self.find_next_ready_under(child, effectively_unblocked)? {
-                return Ok(Some(ready_id));
-            }
-        }
-
-        // No ready children found, but this task might be startable if:
-        // - All children are complete
-        // - This task is effectively unblocked
-        // This handles the case where we want to return a non-leaf that's ready
-        // (all children done, blockers done)
-        if all_children_complete && effectively_unblocked {
-            return Ok(Some(task.id.clone()));
-        }
-
-        Ok(None)
+        let filter = NextReadyFilter {
+            labels: label_repo::normalize_labels(&filter.labels)?,
+            ..filter.clone()
+        };
+        TaskGraph::load(self.conn)?.next_ready_matching(milestone, &filter)
     }
 
     /// Resolve which task to actually start given a requested root.
@@ -669,132 +1123,18 @@ impl<'a> TaskService<'a> {
     /// Returns the ID of the task that should be started.
     /// Errors if no startable task found or if blocker cycle detected.
     pub fn resolve_start_target(&self, requested_root: &TaskId) -> Result<TaskId> {
-        let mut blocker_stack: Vec<TaskId> = Vec::new();
-        self.resolve_start_target_inner(requested_root, &mut blocker_stack)
-    }
-
-    fn resolve_start_target_inner(
-        &self,
-        root: &TaskId,
-        blocker_stack: &mut Vec<TaskId>,
-    ) -> Result<TaskId> {
-        let task = self.get(root)?;
-
-        // Collect incomplete leaves under this root
-        let leaves = self.collect_incomplete_leaves(&task)?;
-
-        for leaf_path in leaves {
-            // Check for blockage along the chain from leaf to root
-            if let Some(blockage) = self.first_blockage_along_chain(&leaf_path)? {
-                // Blocked - follow blockers
-                for blocker_id in blockage.incomplete_blockers {
-                    // Check for cycle
-                    if blocker_stack.contains(&blocker_id) {
-                        let mut chain = blocker_stack.clone();
-                        chain.push(blocker_id.clone());
-                        return Err(OsError::BlockerCycleDetected {
-                            message: format!("Blocker cycle detected: {:?}", chain),
-                            chain,
-                        });
-                    }
-
-                    blocker_stack.push(blocker_id.clone());
-                    match self.resolve_start_target_inner(&blocker_id, blocker_stack) {
-                        Ok(found) => return Ok(found),
-                        Err(OsError::NoStartableTask { .. }) => {
-                            // Continue searching other blockers
-                        }
-                        Err(e) => return Err(e),
-                    }
-                    blocker_stack.pop();
-                }
-            } else {
-                // Leaf is startable - return it
-                if let Some(leaf_id) = leaf_path.last() {
-                    return Ok(leaf_id.clone());
-                }
-            }
-        }
-
-        Err(OsError::NoStartableTask {
-            message: format!("No startable task found under {}", root),
-            requested: root.clone(),
-        })
-    }
-
-    /// Collect all incomplete leaf paths under root (includes root if leaf).
-    /// Returns paths as root->...->leaf in priority order.
-    fn collect_incomplete_leaves(&self, root: &Task) -> Result<Vec<Vec<TaskId>>> {
-        let mut leaves = Vec::new();
-        self.collect_leaves_inner(root, vec![root.id.clone()], &mut leaves)?;
-        Ok(leaves)
-    }
-
-    fn collect_leaves_inner(
-        &self,
-        task: &Task,
-        path: Vec<TaskId>,
-        leaves: &mut Vec<Vec<TaskId>>,
-    ) -> Result<()> {
-        if task.is_finished_for_hierarchy() {
-            return Ok(());
-        }
-
-        let children = task_repo::get_children_ordered(self.conn, &task.id)?;
-
-        if children.is_empty() {
-            // Leaf node
-            leaves.push(path);
-            return Ok(());
-        }
-
-        // Check if all children are finished (completed or cancelled)
-        let all_complete = children.iter().all(|c| c.is_finished_for_hierarchy());
-        if all_complete {
-            // This node is effectively a leaf (all children done)
-            leaves.push(path);
-            return Ok(());
-        }
-
-        // Recurse into unfinished children
-        for child in children {
-            if !child.is_finished_for_hierarchy() {
-                let mut child_path = path.clone();
-                child_path.push(child.id.clone());
-                self.collect_leaves_inner(&child, child_path, leaves)?;
-            }
-        }
-
-        Ok(())
-    }
-
-    /// Find first blockage along leaf->root chain.
-    /// Returns None if leaf is startable (no blockers in chain).
-    fn first_blockage_along_chain(&self, leaf_path: &[TaskId]) -> Result<Option<Blockage>> {
-        // Walk from root to leaf, checking blockers at each level
-        for task_id in leaf_path.iter() {
-            let blockers = task_repo::get_blockers(self.conn, task_id)?;
-            // is_task_satisfies_blocker returns false for missing/errored/cancelled tasks (conservative)
-            // so unsatisfied blockers are those that do NOT satisfy blockers (completed only)
-            let unsatisfied_blockers: Vec<TaskId> = blockers
-                .into_iter()
-                .filter(|b| !task_repo::is_task_satisfies_blocker(self.conn, b).unwrap_or(false))
-                .collect();
-
-            if !unsatisfied_blockers.is_empty() {
-                return Ok(Some(Blockage {
-                    incomplete_blockers: unsatisfied_blockers,
-                }));
-            }
-        }
-
-        Ok(None)
+        TaskGraph::load(self.conn)?.resolve_start_target(requested_root)
     }
 
     /// Check if a task is effectively blocked (itself or any ancestor blocked).
     /// Uses satisfies_blocker() semantics: completed tasks satisfy blockers,
     /// but cancelled tasks do NOT (they keep dependents blocked).
     pub fn is_effectively_blocked(&self, task: &Task) -> Result<bool> {
+        // Deferral blocks the subtree like an unsatisfied blocker
+        if self.deferred_until(task)?.is_some() {
+            return Ok(true);
+        }
+
         // Check task's own blockers
         for blocker_id in &task.blocked_by {
             if !task_repo::is_task_satisfies_blocker(self.conn, blocker_id)? {
@@ -820,6 +1160,22 @@ impl<'a> TaskService<'a> {
         Ok(false)
     }
 
+    /// Latest future `defer_until` on the task or any ancestor, if any.
+    pub fn deferred_until(&self, task: &Task) -> Result<Option<DateTime<Utc>>> {
+        let now = Utc::now();
+        let mut until = task.defer_until.filter(|_| task.is_deferred_at(now));
+        let mut current_parent = task.parent_id.clone();
+        while let Some(ref parent_id) = current_parent {
+            let parent = task_repo::get_task(self.conn, parent_id)?
+                .ok_or_else(|| OsError::TaskNotFound(parent_id.clone()))?;
+            if parent.is_deferred_at(now) {
+                until = until.max(parent.defer_until);
+            }
+            current_parent = parent.parent_id;
+        }
+        Ok(until)
+    }
+
     fn would_create_blocker_cycle(
         &self,
         task_id: &TaskId,
@@ -845,15 +1201,19 @@ impl<'a> TaskService<'a> {
     }
 }
 
-/// Internal struct for tracking blockage information
-struct Blockage {
-    incomplete_blockers: Vec<TaskId>,
+fn claimed_error(claim: &TaskClaim) -> OsError {
+    OsError::TaskClaimed {
+        task_id: claim.task_id.clone(),
+        holder: claim.agent.clone(),
+        expires_at: claim.expires_at,
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::db::schema;
+    use crate::id::RepoId;
 
     fn setup_db() -> rusqlite::Connection {
         let conn = rusqlite::Connection::open_in_memory().unwrap();
@@ -874,6 +1234,10 @@ mod tests {
                 parent_id: None,
                 priority: None,
                 blocked_by: vec![],
+                labels: vec![],
+                due_at: None,
+                defer_until: None,
+                repo_id: None,
             })
             .unwrap();
 
@@ -897,6 +1261,10 @@ mod tests {
                 parent_id: None,
                 priority: None,
                 blocked_by: vec![],
+                labels: vec![],
+                due_at: None,
+                defer_until: None,
+                repo_id: None,
             })
             .unwrap();
 
@@ -907,6 +1275,10 @@ mod tests {
                 parent_id: Some(milestone.id.clone()),
                 priority: None,
                 blocked_by: vec![],
+                labels: vec![],
+                due_at: None,
+                defer_until: None,
+                repo_id: None,
             })
             .unwrap();
 
@@ -930,6 +1302,10 @@ mod tests {
                 parent_id: None,
                 priority: None,
                 blocked_by: vec![],
+                labels: vec![],
+                due_at: None,
+                defer_until: None,
+                repo_id: None,
             })
             .unwrap();
 
@@ -940,6 +1316,10 @@ mod tests {
                 parent_id: Some(milestone.id.clone()),
                 priority: None,
                 blocked_by: vec![],
+                labels: vec![],
+                due_at: None,
+                defer_until: None,
+                repo_id: None,
             })
             .unwrap();
 
@@ -950,6 +1330,10 @@ mod tests {
                 parent_id: Some(task.id.clone()),
                 priority: None,
                 blocked_by: vec![],
+                labels: vec![],
+                due_at: None,
+                defer_until: None,
+                repo_id: None,
             })
             .unwrap();
 
@@ -973,6 +1357,10 @@ mod tests {
                 parent_id: None,
                 priority: None,
                 blocked_by: vec![],
+                labels: vec![],
+                due_at: None,
+                defer_until: None,
+                repo_id: None,
             })
             .unwrap();
 
@@ -997,6 +1385,10 @@ mod tests {
                 parent_id: None,
                 priority: None,
                 blocked_by: vec![],
+                labels: vec![],
+                due_at: None,
+                defer_until: None,
+                repo_id: None,
             })
             .unwrap();
 
@@ -1010,6 +1402,10 @@ mod tests {
                 parent_id: Some(milestone.id.clone()),
                 priority: None,
                 blocked_by: vec![],
+                labels: vec![],
+                due_at: None,
+                defer_until: None,
+                repo_id: None,
             })
             .unwrap();
 
@@ -1036,6 +1432,10 @@ mod tests {
                 parent_id: None,
                 priority: None,
                 blocked_by: vec![],
+                labels: vec![],
+                due_at: None,
+                defer_until: None,
+                repo_id: None,
             })
             .unwrap();
 
@@ -1048,6 +1448,10 @@ mod tests {
                 parent_id: Some(milestone.id.clone()),
                 priority: None,
                 blocked_by: vec![],
+                labels: vec![],
+                due_at: None,
+                defer_until: None,
+                repo_id: None,
             })
             .unwrap();
 
@@ -1061,6 +1465,10 @@ mod tests {
                 parent_id: Some(task.id.clone()),
                 priority: None,
                 blocked_by: vec![],
+                labels: vec![],
+                due_at: None,
+                defer_until: None,
+                repo_id: None,
             })
             .unwrap();
 
@@ -1089,6 +1497,10 @@ mod tests {
                 parent_id: None,
                 priority: None,
                 blocked_by: vec![],
+                labels: vec![],
+                due_at: None,
+                defer_until: None,
+                repo_id: None,
             })
             .unwrap();
 
@@ -1114,6 +1526,10 @@ mod tests {
                 parent_id: None,
                 priority: None,
                 blocked_by: vec![],
+                labels: vec![],
+                due_at: None,
+                defer_until: None,
+                repo_id: None,
             })
             .unwrap();
 
@@ -1145,6 +1561,10 @@ mod tests {
                 parent_id: None,
                 priority: Some(0),
                 blocked_by: vec![],
+                labels: vec![],
+                due_at: None,
+                defer_until: None,
+                repo_id: None,
             })
             .unwrap();
 
@@ -1155,6 +1575,10 @@ mod tests {
                 parent_id: Some(milestone.id.clone()),
                 priority: Some(0),
                 blocked_by: vec![],
+                labels: vec![],
+                due_at: None,
+                defer_until: None,
+                repo_id: None,
             })
             .unwrap();
 
@@ -1165,6 +1589,10 @@ mod tests {
                 parent_id: Some(task.id.clone()),
                 priority: Some(0),
                 blocked_by: vec![],
+                labels: vec![],
+                due_at: None,
+                defer_until: None,
+                repo_id: None,
             })
             .unwrap();
 
@@ -1185,6 +1613,10 @@ mod tests {
                 parent_id: None,
                 priority: Some(1),
                 blocked_by: vec![],
+                labels: vec![],
+                due_at: None,
+                defer_until: None,
+                repo_id: None,
             })
             .unwrap();
 
@@ -1196,6 +1628,10 @@ mod tests {
                 parent_id: None,
                 priority: Some(0),
                 blocked_by: vec![blocker.id.clone()],
+                labels: vec![],
+                due_at: None,
+                defer_until: None,
+                repo_id: None,
             })
             .unwrap();
 
@@ -1206,6 +1642,10 @@ mod tests {
                 parent_id: Some(milestone.id.clone()),
                 priority: Some(0),
                 blocked_by: vec![],
+                labels: vec![],
+                due_at: None,
+                defer_until: None,
+                repo_id: None,
             })
             .unwrap();
 
@@ -1240,59 +1680,285 @@ mod tests {
                 parent_id: None,
                 priority: Some(0),
                 blocked_by: vec![],
+                labels: vec![],
+                due_at: None,
+                defer_until: None,
+                repo_id: None,
+            })
+            .unwrap();
+
+        // Should return the milestone itself
+        let result = service.next_ready(Some(&milestone.id)).unwrap();
+        assert_eq!(result, Some(milestone.id));
+    }
+
+    #[test]
+    fn test_next_ready_respects_priority_order() {
+        let conn = setup_db();
+        let service = TaskService::new(&conn);
+
+        let milestone = service
+            .create(&CreateTaskInput {
+                description: "Milestone".to_string(),
+                context: None,
+                parent_id: None,
+                priority: Some(0),
+                blocked_by: vec![],
+                labels: vec![],
+                due_at: None,
+                defer_until: None,
+                repo_id: None,
+            })
+            .unwrap();
+
+        // Low priority task (created first)
+        let low = service
+            .create(&CreateTaskInput {
+                description: "Low priority".to_string(),
+                context: None,
+                parent_id: Some(milestone.id.clone()),
+                priority: Some(1),
+                blocked_by: vec![],
+                labels: vec![],
+                due_at: None,
+                defer_until: None,
+                repo_id: None,
+            })
+            .unwrap();
+
+        // High priority task (created second)
+        let high = service
+            .create(&CreateTaskInput {
+                description: "High priority".to_string(),
+                context: None,
+                parent_id: Some(milestone.id.clone()),
+                priority: Some(0),
+                blocked_by: vec![],
+                labels: vec![],
+                due_at: None,
+                defer_until: None,
+                repo_id: None,
+            })
+            .unwrap();
+
+        // Should return high priority first
+        let result = service.next_ready(Some(&milestone.id)).unwrap();
+        assert_eq!(result, Some(high.id.clone()));
+
+        // Complete high, should return low
+        service.complete(&high.id, None).unwrap();
+        let result = service.next_ready(Some(&milestone.id)).unwrap();
+        assert_eq!(result, Some(low.id));
+    }
+
+    #[test]
+    fn test_move_orders_siblings_within_priority() {
+        let conn = setup_db();
+        let service = TaskService::new(&conn);
+        let milestone = service
+            .create(&CreateTaskInput {
+                description: "Milestone".to_string(),
+                ..Default::default()
+            })
+            .unwrap()
+            .id;
+        let child = |description: &str, priority| {
+            service
+                .create(&CreateTaskInput {
+                    description: description.to_string(),
+                    parent_id: Some(milestone.clone()),
+                    priority: Some(priority),
+                    ..Default::default()
+                })
+                .unwrap()
+                .id
+        };
+        let a = child("A", 1);
+        let b = child("B", 1);
+        let c = child("C", 1);
+        let urgent = child("Urgent", 0);
+        let order = || -> Vec<TaskId> {
+            service
+                .list(&ListTasksFilter {
+                    parent_id: Some(milestone.clone()),
+                    ..Default::default()
+                })
+                .unwrap()
+                .into_iter()
+                .map(|t| t.id)
+                .collect()
+        };
+        assert_eq!(
+            order(),
+            vec![urgent.clone(), a.clone(), b.clone(), c.clone()]
+        );
+
+        // Priority still wins: --first only reorders within p1
+        service.move_task(&c, &MovePosition::First).unwrap();
+        assert_eq!(
+            order(),
+            vec![urgent.clone(), c.clone(), a.clone(), b.clone()]
+        );
+        service
+            .move_task(&a, &MovePosition::After(b.clone()))
+            .unwrap();
+        service
+            .move_task(&b, &MovePosition::Before(c.clone()))
+            .unwrap();
+        assert_eq!(
+            order(),
+            vec![urgent.clone(), b.clone(), c.clone(), a.clone()]
+        );
+        assert_eq!(
+            service.next_ready(Some(&milestone)).unwrap(),
+            Some(urgent.clone())
+        );
+        service.complete(&urgent, None).unwrap();
+        assert_eq!(
+            service.next_ready(Some(&milestone)).unwrap(),
+            Some(b.clone())
+        );
+        assert_eq!(service.resolve_start_target(&milestone).unwrap(), b);
+
+        assert!(matches!(
+            service.move_task(&a, &MovePosition::Before(urgent)),
+            Err(OsError::InvalidMove(_))
+        ));
+        assert!(matches!(
+            service.move_task(&a, &MovePosition::After(milestone.clone())),
+            Err(OsError::InvalidMove(_))
+        ));
+        assert!(matches!(
+            service.move_task(&a, &MovePosition::Before(a.clone())),
+            Err(OsError::InvalidMove(_))
+        ));
+
+        // Reparented tasks go last under their new parent
+        let other = service
+            .create(&CreateTaskInput {
+                description: "Other".to_string(),
+                ..Default::default()
+            })
+            .unwrap()
+            .id;
+        let x = service
+            .create(&CreateTaskInput {
+                description: "X".to_string(),
+                parent_id: Some(other.clone()),
+                ..Default::default()
             })
             .unwrap();
-
-        // Should return the milestone itself
-        let result = service.next_ready(Some(&milestone.id)).unwrap();
-        assert_eq!(result, Some(milestone.id));
+        let moved = service
+            .update(
+                &b,
+                &UpdateTaskInput {
+                    parent_id: Some(other),
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+        assert!(moved.rank > x.rank);
     }
 
     #[test]
-    fn test_next_ready_respects_priority_order() {
+    fn test_deferred_tasks_wait_and_overdue_filters() {
         let conn = setup_db();
         let service = TaskService::new(&conn);
-
+        let now = Utc::now();
         let milestone = service
             .create(&CreateTaskInput {
                 description: "Milestone".to_string(),
-                context: None,
-                parent_id: None,
+                ..Default::default()
+            })
+            .unwrap()
+            .id;
+        let later = service
+            .create(&CreateTaskInput {
+                description: "Later".to_string(),
+                parent_id: Some(milestone.clone()),
                 priority: Some(0),
-                blocked_by: vec![],
+                defer_until: Some(now + Duration::days(2)),
+                ..Default::default()
             })
             .unwrap();
-
-        // Low priority task (created first)
-        let low = service
+        let sub = service
             .create(&CreateTaskInput {
-                description: "Low priority".to_string(),
-                context: None,
-                parent_id: Some(milestone.id.clone()),
-                priority: Some(1),
-                blocked_by: vec![],
+                description: "Sub".to_string(),
+                parent_id: Some(later.id.clone()),
+                ..Default::default()
             })
-            .unwrap();
-
-        // High priority task (created second)
-        let high = service
+            .unwrap()
+            .id;
+        let late = service
             .create(&CreateTaskInput {
-                description: "High priority".to_string(),
-                context: None,
-                parent_id: Some(milestone.id.clone()),
-                priority: Some(0),
-                blocked_by: vec![],
+                description: "Late".to_string(),
+                parent_id: Some(milestone.clone()),
+                due_at: Some(now - Duration::hours(1)),
+                ..Default::default()
+            })
+            .unwrap()
+            .id;
+        service
+            .create(&CreateTaskInput {
+                description: "Soon".to_string(),
+                parent_id: Some(milestone.clone()),
+                due_at: Some(now + Duration::days(3)),
+                ..Default::default()
             })
             .unwrap();
 
-        // Should return high priority first
-        let result = service.next_ready(Some(&milestone.id)).unwrap();
-        assert_eq!(result, Some(high.id.clone()));
+        // Deferral holds back the task and its subtree like a blocker would
+        assert!(service.get(&later.id).unwrap().effectively_blocked);
+        assert!(service.get(&sub).unwrap().effectively_blocked);
+        assert_eq!(
+            service.next_ready(Some(&milestone)).unwrap(),
+            Some(late.clone())
+        );
+        assert_eq!(service.resolve_start_target(&milestone).unwrap(), late);
 
-        // Complete high, should return low
-        service.complete(&high.id, None).unwrap();
-        let result = service.next_ready(Some(&milestone.id)).unwrap();
-        assert_eq!(result, Some(low.id));
+        let ids = |filter: ListTasksFilter| -> Vec<TaskId> {
+            service
+                .list(&filter)
+                .unwrap()
+                .into_iter()
+                .map(|t| t.id)
+                .collect()
+        };
+        assert_eq!(
+            ids(ListTasksFilter {
+                overdue: true,
+                ..Default::default()
+            }),
+            vec![late.clone()]
+        );
+        assert_eq!(
+            ids(ListTasksFilter {
+                due_before: Some(now + Duration::days(4)),
+                ..Default::default()
+            })
+            .len(),
+            2
+        );
+        service.complete(&late, None).unwrap();
+        assert!(ids(ListTasksFilter {
+            overdue: true,
+            ..Default::default()
+        })
+        .is_empty());
+
+        // Clearing the deferral makes the subtree ready again
+        let cleared = service
+            .update(
+                &later.id,
+                &UpdateTaskInput {
+                    defer_until: Some(None),
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+        assert!(cleared.defer_until.is_none());
+        assert!(!cleared.effectively_blocked);
+        assert_eq!(service.next_ready(Some(&milestone)).unwrap(), Some(sub));
     }
 
     #[test]
@@ -1308,6 +1974,10 @@ mod tests {
                 parent_id: None,
                 priority: Some(0),
                 blocked_by: vec![],
+                labels: vec![],
+                due_at: None,
+                defer_until: None,
+                repo_id: None,
             })
             .unwrap();
 
@@ -1318,6 +1988,10 @@ mod tests {
                 parent_id: None,
                 priority: Some(0),
                 blocked_by: vec![blocker_task.id.clone()],
+                labels: vec![],
+                due_at: None,
+                defer_until: None,
+                repo_id: None,
             })
             .unwrap();
 
@@ -1328,6 +2002,10 @@ mod tests {
                 parent_id: Some(blocked_milestone.id.clone()),
                 priority: Some(0),
                 blocked_by: vec![],
+                labels: vec![],
+                due_at: None,
+                defer_until: None,
+                repo_id: None,
             })
             .unwrap();
 
@@ -1349,6 +2027,10 @@ mod tests {
                 parent_id: None,
                 priority: Some(0),
                 blocked_by: vec![],
+                labels: vec![],
+                due_at: None,
+                defer_until: None,
+                repo_id: None,
             })
             .unwrap();
 
@@ -1359,6 +2041,10 @@ mod tests {
                 parent_id: None,
                 priority: Some(0),
                 blocked_by: vec![task_a.id.clone()],
+                labels: vec![],
+                due_at: None,
+                defer_until: None,
+                repo_id: None,
             })
             .unwrap();
 
@@ -1379,6 +2065,10 @@ mod tests {
                 parent_id: None,
                 priority: Some(0),
                 blocked_by: vec![],
+                labels: vec![],
+                due_at: None,
+                defer_until: None,
+                repo_id: None,
             })
             .unwrap();
 
@@ -1389,6 +2079,10 @@ mod tests {
                 parent_id: Some(milestone.id.clone()),
                 priority: Some(0),
                 blocked_by: vec![],
+                labels: vec![],
+                due_at: None,
+                defer_until: None,
+                repo_id: None,
             })
             .unwrap();
 
@@ -1412,6 +2106,10 @@ mod tests {
                 parent_id: None,
                 priority: Some(0),
                 blocked_by: vec![],
+                labels: vec![],
+                due_at: None,
+                defer_until: None,
+                repo_id: None,
             })
             .unwrap();
 
@@ -1422,6 +2120,10 @@ mod tests {
                 parent_id: Some(milestone.id.clone()),
                 priority: Some(0),
                 blocked_by: vec![],
+                labels: vec![],
+                due_at: None,
+                defer_until: None,
+                repo_id: None,
             })
             .unwrap();
 
@@ -1445,6 +2147,10 @@ mod tests {
                 parent_id: None,
                 priority: Some(0),
                 blocked_by: vec![],
+                labels: vec![],
+                due_at: None,
+                defer_until: None,
+                repo_id: None,
             })
             .unwrap();
 
@@ -1455,6 +2161,10 @@ mod tests {
             parent_id: Some(milestone.id.clone()),
             priority: Some(0),
             blocked_by: vec![milestone.id.clone()],
+            labels: vec![],
+            due_at: None,
+            defer_until: None,
+            repo_id: None,
         });
         assert!(matches!(
             result,
@@ -1475,6 +2185,10 @@ mod tests {
                 parent_id: None,
                 priority: Some(0),
                 blocked_by: vec![],
+                labels: vec![],
+                due_at: None,
+                defer_until: None,
+                repo_id: None,
             })
             .unwrap();
 
@@ -1485,6 +2199,10 @@ mod tests {
                 parent_id: None,
                 priority: Some(0),
                 blocked_by: vec![],
+                labels: vec![],
+                due_at: None,
+                defer_until: None,
+                repo_id: None,
             })
             .unwrap();
 
@@ -1496,6 +2214,10 @@ mod tests {
                 parent_id: Some(milestone_b.id.clone()),
                 priority: Some(0),
                 blocked_by: vec![milestone_a.id.clone()],
+                labels: vec![],
+                due_at: None,
+                defer_until: None,
+                repo_id: None,
             })
             .unwrap();
 
@@ -1526,6 +2248,10 @@ mod tests {
                 parent_id: None,
                 priority: Some(0),
                 blocked_by: vec![],
+                labels: vec![],
+                due_at: None,
+                defer_until: None,
+                repo_id: None,
             })
             .unwrap();
 
@@ -1536,6 +2262,10 @@ mod tests {
                 parent_id: Some(milestone.id.clone()),
                 priority: Some(0),
                 blocked_by: vec![],
+                labels: vec![],
+                due_at: None,
+                defer_until: None,
+                repo_id: None,
             })
             .unwrap();
 
@@ -1546,6 +2276,10 @@ mod tests {
                 parent_id: Some(task.id.clone()),
                 priority: Some(0),
                 blocked_by: vec![],
+                labels: vec![],
+                due_at: None,
+                defer_until: None,
+                repo_id: None,
             })
             .unwrap();
 
@@ -1557,6 +2291,10 @@ mod tests {
                 parent_id: None,
                 priority: Some(0),
                 blocked_by: vec![],
+                labels: vec![],
+                due_at: None,
+                defer_until: None,
+                repo_id: None,
             })
             .unwrap();
 
@@ -1567,11 +2305,15 @@ mod tests {
                 parent_id: Some(other_milestone.id.clone()),
                 priority: Some(0),
                 blocked_by: vec![],
+                labels: vec![],
+                due_at: None,
+                defer_until: None,
+                repo_id: None,
             })
             .unwrap();
 
         // Try to reparent "task" (which has a subtask) under "other_task" (depth 1)
-        // This would make subtask depth 3, exceeding MAX_DEPTH=2
+        // This would put the subtask at depth 3, past the default 3 levels
         let result = service.update(
             &task.id,
             &UpdateTaskInput {
@@ -1579,7 +2321,7 @@ mod tests {
                 ..Default::default()
             },
         );
-        assert!(matches!(result, Err(OsError::MaxDepthExceeded)));
+        assert!(matches!(result, Err(OsError::MaxDepthExceeded { .. })));
     }
 
     #[test]
@@ -1594,6 +2336,10 @@ mod tests {
                 parent_id: None,
                 priority: Some(0),
                 blocked_by: vec![],
+                labels: vec![],
+                due_at: None,
+                defer_until: None,
+                repo_id: None,
             })
             .unwrap();
 
@@ -1604,6 +2350,10 @@ mod tests {
                 parent_id: None,
                 priority: Some(0),
                 blocked_by: vec![blocker.id.clone()],
+                labels: vec![],
+                due_at: None,
+                defer_until: None,
+                repo_id: None,
             })
             .unwrap();
 
@@ -1614,6 +2364,10 @@ mod tests {
                 parent_id: None,
                 priority: Some(0),
                 blocked_by: vec![blocker.id.clone()],
+                labels: vec![],
+                due_at: None,
+                defer_until: None,
+                repo_id: None,
             })
             .unwrap();
 
@@ -1649,6 +2403,10 @@ mod tests {
                 parent_id: None,
                 priority: Some(0),
                 blocked_by: vec![],
+                labels: vec![],
+                due_at: None,
+                defer_until: None,
+                repo_id: None,
             })
             .unwrap();
 
@@ -1659,6 +2417,10 @@ mod tests {
                 parent_id: None,
                 priority: Some(0),
                 blocked_by: vec![blocker.id.clone()],
+                labels: vec![],
+                due_at: None,
+                defer_until: None,
+                repo_id: None,
             })
             .unwrap();
 
@@ -1690,6 +2452,10 @@ mod tests {
                 parent_id: None,
                 priority: None,
                 blocked_by: vec![],
+                labels: vec![],
+                due_at: None,
+                defer_until: None,
+                repo_id: None,
             })
             .unwrap();
 
@@ -1711,6 +2477,10 @@ mod tests {
                 parent_id: None,
                 priority: None,
                 blocked_by: vec![],
+                labels: vec![],
+                due_at: None,
+                defer_until: None,
+                repo_id: None,
             })
             .unwrap();
 
@@ -1734,6 +2504,10 @@ mod tests {
                 parent_id: None,
                 priority: None,
                 blocked_by: vec![],
+                labels: vec![],
+                due_at: None,
+                defer_until: None,
+                repo_id: None,
             })
             .unwrap();
 
@@ -1756,6 +2530,10 @@ mod tests {
                 parent_id: None,
                 priority: None,
                 blocked_by: vec![],
+                labels: vec![],
+                due_at: None,
+                defer_until: None,
+                repo_id: None,
             })
             .unwrap();
 
@@ -1778,6 +2556,10 @@ mod tests {
                 parent_id: None,
                 priority: None,
                 blocked_by: vec![],
+                labels: vec![],
+                due_at: None,
+                defer_until: None,
+                repo_id: None,
             })
             .unwrap();
 
@@ -1802,6 +2584,10 @@ mod tests {
                 parent_id: None,
                 priority: None,
                 blocked_by: vec![],
+                labels: vec![],
+                due_at: None,
+                defer_until: None,
+                repo_id: None,
             })
             .unwrap();
 
@@ -1825,6 +2611,10 @@ mod tests {
                 parent_id: None,
                 priority: None,
                 blocked_by: vec![],
+                labels: vec![],
+                due_at: None,
+                defer_until: None,
+                repo_id: None,
             })
             .unwrap();
 
@@ -1847,6 +2637,10 @@ mod tests {
                 parent_id: None,
                 priority: None,
                 blocked_by: vec![],
+                labels: vec![],
+                due_at: None,
+                defer_until: None,
+                repo_id: None,
             })
             .unwrap();
 
@@ -1867,6 +2661,10 @@ mod tests {
                 parent_id: None,
                 priority: None,
                 blocked_by: vec![],
+                labels: vec![],
+                due_at: None,
+                defer_until: None,
+                repo_id: None,
             })
             .unwrap();
 
@@ -1889,6 +2687,10 @@ mod tests {
                 parent_id: None,
                 priority: None,
                 blocked_by: vec![],
+                labels: vec![],
+                due_at: None,
+                defer_until: None,
+                repo_id: None,
             })
             .unwrap();
 
@@ -1914,6 +2716,10 @@ mod tests {
                 parent_id: None,
                 priority: Some(0),
                 blocked_by: vec![],
+                labels: vec![],
+                due_at: None,
+                defer_until: None,
+                repo_id: None,
             })
             .unwrap();
 
@@ -1924,6 +2730,10 @@ mod tests {
                 parent_id: None,
                 priority: Some(0),
                 blocked_by: vec![blocker.id.clone()],
+                labels: vec![],
+                due_at: None,
+                defer_until: None,
+                repo_id: None,
             })
             .unwrap();
 
@@ -1959,6 +2769,10 @@ mod tests {
                 parent_id: None,
                 priority: None,
                 blocked_by: vec![],
+                labels: vec![],
+                due_at: None,
+                defer_until: None,
+                repo_id: None,
             })
             .unwrap();
 
@@ -1977,6 +2791,10 @@ mod tests {
                 parent_id: None,
                 priority: None,
                 blocked_by: vec![],
+                labels: vec![],
+                due_at: None,
+                defer_until: None,
+                repo_id: None,
             })
             .unwrap();
 
@@ -2010,6 +2828,10 @@ mod tests {
                 parent_id: None,
                 priority: None,
                 blocked_by: vec![],
+                labels: vec![],
+                due_at: None,
+                defer_until: None,
+                repo_id: None,
             })
             .unwrap();
 
@@ -2020,6 +2842,10 @@ mod tests {
                 parent_id: Some(milestone.id.clone()),
                 priority: None,
                 blocked_by: vec![],
+                labels: vec![],
+                due_at: None,
+                defer_until: None,
+                repo_id: None,
             })
             .unwrap();
 
@@ -2045,6 +2871,10 @@ mod tests {
                 parent_id: None,
                 priority: None,
                 blocked_by: vec![],
+                labels: vec![],
+                due_at: None,
+                defer_until: None,
+                repo_id: None,
             })
             .unwrap();
 
@@ -2055,6 +2885,10 @@ mod tests {
                 parent_id: Some(milestone.id.clone()),
                 priority: None,
                 blocked_by: vec![],
+                labels: vec![],
+                due_at: None,
+                defer_until: None,
+                repo_id: None,
             })
             .unwrap();
 
@@ -2079,6 +2913,10 @@ mod tests {
                 parent_id: None,
                 priority: None,
                 blocked_by: vec![],
+                labels: vec![],
+                due_at: None,
+                defer_until: None,
+                repo_id: None,
             })
             .unwrap();
 
@@ -2089,6 +2927,10 @@ mod tests {
                 parent_id: Some(milestone.id.clone()),
                 priority: None,
                 blocked_by: vec![],
+                labels: vec![],
+                due_at: None,
+                defer_until: None,
+                repo_id: None,
             })
             .unwrap();
 
@@ -2117,6 +2959,10 @@ mod tests {
                 parent_id: None,
                 priority: None,
                 blocked_by: vec![],
+                labels: vec![],
+                due_at: None,
+                defer_until: None,
+                repo_id: None,
             })
             .unwrap();
 
@@ -2130,6 +2976,10 @@ mod tests {
             parent_id: Some(parent.id.clone()),
             priority: None,
             blocked_by: vec![],
+            labels: vec![],
+            due_at: None,
+            defer_until: None,
+            repo_id: None,
         });
 
         assert!(
@@ -2155,6 +3005,10 @@ mod tests {
                 parent_id: None,
                 priority: None,
                 blocked_by: vec![],
+                labels: vec![],
+                due_at: None,
+                defer_until: None,
+                repo_id: None,
             })
             .unwrap();
 
@@ -2168,6 +3022,10 @@ mod tests {
             parent_id: Some(parent.id.clone()),
             priority: None,
             blocked_by: vec![],
+            labels: vec![],
+            due_at: None,
+            defer_until: None,
+            repo_id: None,
         });
 
         assert!(
@@ -2193,6 +3051,10 @@ mod tests {
                 parent_id: None,
                 priority: None,
                 blocked_by: vec![],
+                labels: vec![],
+                due_at: None,
+                defer_until: None,
+                repo_id: None,
             })
             .unwrap();
 
@@ -2207,6 +3069,10 @@ mod tests {
             parent_id: Some(parent.id.clone()),
             priority: None,
             blocked_by: vec![],
+            labels: vec![],
+            due_at: None,
+            defer_until: None,
+            repo_id: None,
         });
 
         assert!(
@@ -2232,6 +3098,10 @@ mod tests {
                 parent_id: None,
                 priority: None,
                 blocked_by: vec![],
+                labels: vec![],
+                due_at: None,
+                defer_until: None,
+                repo_id: None,
             })
             .unwrap();
 
@@ -2242,6 +3112,10 @@ mod tests {
                 parent_id: None,
                 priority: None,
                 blocked_by: vec![],
+                labels: vec![],
+                due_at: None,
+                defer_until: None,
+                repo_id: None,
             })
             .unwrap();
 
@@ -2255,7 +3129,11 @@ mod tests {
                 description: None,
                 context: None,
                 parent_id: Some(target_parent.id.clone()),
+                labels: vec![],
                 priority: None,
+                due_at: None,
+                defer_until: None,
+                repo_id: None,
             },
         );
 
@@ -2282,6 +3160,10 @@ mod tests {
                 parent_id: None,
                 priority: None,
                 blocked_by: vec![],
+                labels: vec![],
+                due_at: None,
+                defer_until: None,
+                repo_id: None,
             })
             .unwrap();
 
@@ -2292,6 +3174,10 @@ mod tests {
                 parent_id: None,
                 priority: None,
                 blocked_by: vec![],
+                labels: vec![],
+                due_at: None,
+                defer_until: None,
+                repo_id: None,
             })
             .unwrap();
 
@@ -2306,7 +3192,11 @@ mod tests {
                 description: None,
                 context: None,
                 parent_id: Some(target_parent.id.clone()),
+                labels: vec![],
                 priority: None,
+                due_at: None,
+                defer_until: None,
+                repo_id: None,
             },
         );
 
@@ -2334,6 +3224,10 @@ mod tests {
                 parent_id: None,
                 priority: None,
                 blocked_by: vec![],
+                labels: vec![],
+                due_at: None,
+                defer_until: None,
+                repo_id: None,
             })
             .unwrap();
 
@@ -2344,6 +3238,10 @@ mod tests {
                 parent_id: Some(milestone.id.clone()),
                 priority: None,
                 blocked_by: vec![],
+                labels: vec![],
+                due_at: None,
+                defer_until: None,
+                repo_id: None,
             })
             .unwrap();
 
@@ -2354,6 +3252,10 @@ mod tests {
                 parent_id: Some(task.id.clone()),
                 priority: None,
                 blocked_by: vec![],
+                labels: vec![],
+                due_at: None,
+                defer_until: None,
+                repo_id: None,
             })
             .unwrap();
 
@@ -2389,6 +3291,10 @@ mod tests {
                 parent_id: None,
                 priority: None,
                 blocked_by: vec![],
+                labels: vec![],
+                due_at: None,
+                defer_until: None,
+                repo_id: None,
             })
             .unwrap();
 
@@ -2399,6 +3305,10 @@ mod tests {
                 parent_id: Some(milestone.id.clone()),
                 priority: None,
                 blocked_by: vec![],
+                labels: vec![],
+                due_at: None,
+                defer_until: None,
+                repo_id: None,
             })
             .unwrap();
 
@@ -2409,6 +3319,10 @@ mod tests {
                 parent_id: Some(task.id.clone()),
                 priority: None,
                 blocked_by: vec![],
+                labels: vec![],
+                due_at: None,
+                defer_until: None,
+                repo_id: None,
             })
             .unwrap();
 
@@ -2428,4 +3342,536 @@ mod tests {
             result
         );
     }
+
+    #[test]
+    fn test_metadata_set_get_unset() {
+        let conn = setup_db();
+        let service = TaskService::new(&conn);
+
+        let task = service
+            .create(&CreateTaskInput {
+                description: "Task".to_string(),
+                ..Default::default()
+            })
+            .unwrap();
+        assert_eq!(task.metadata, None);
+
+        let task = service
+            .set_metadata(
+                &task.id,
+                "pr.url",
+                &serde_json::json!("https://example.com/7"),
+            )
+            .unwrap();
+        assert_eq!(
+            task.metadata,
+            Some(serde_json::json!({"pr": {"url": "https://example.com/7"}}))
+        );
+        assert_eq!(
+            service.get_metadata(&task.id, Some("pr.url")).unwrap(),
+            Some(serde_json::json!("https://example.com/7"))
+        );
+
+        let task = service.unset_metadata(&task.id, "pr").unwrap();
+        assert_eq!(task.metadata, None);
+        assert_eq!(service.get_metadata(&task.id, None).unwrap(), None);
+    }
+
+    #[test]
+    fn test_metadata_rejected_on_archived_task() {
+        let conn = setup_db();
+        let service = TaskService::new(&conn);
+
+        let milestone = service
+            .create(&CreateTaskInput {
+                description: "Milestone".to_string(),
+                ..Default::default()
+            })
+            .unwrap();
+        service.complete(&milestone.id, None).unwrap();
+        service.archive(&milestone.id).unwrap();
+
+        let result = service.set_metadata(&milestone.id, "area", &serde_json::json!("auth"));
+        assert!(matches!(result, Err(OsError::CannotModifyArchived)));
+    }
+
+    #[test]
+    fn test_list_filters_by_metadata() {
+        let conn = setup_db();
+        let service = TaskService::new(&conn);
+
+        let auth = service
+            .create(&CreateTaskInput {
+                description: "Auth".to_string(),
+                ..Default::default()
+            })
+            .unwrap();
+        let billing = service
+            .create(&CreateTaskInput {
+                description: "Billing".to_string(),
+                ..Default::default()
+            })
+            .unwrap();
+        service
+            .set_metadata(&auth.id, "area", &serde_json::json!("auth"))
+            .unwrap();
+        service
+            .set_metadata(&auth.id, "size", &serde_json::json!(3))
+            .unwrap();
+        service
+            .set_metadata(&billing.id, "area", &serde_json::json!("billing"))
+            .unwrap();
+
+        let filter = |pairs: &[(&str, serde_json::Value)]| ListTasksFilter {
+            metadata: pairs
+                .iter()
+                .map(|(path, value)| crate::types::MetadataFilter {
+                    path: path.to_string(),
+                    value: value.clone(),
+                })
+                .collect(),
+            ..Default::default()
+        };
+
+        let tasks = service
+            .list(&filter(&[("area", serde_json::json!("auth"))]))
+            .unwrap();
+        assert_eq!(tasks.len(), 1);
+        assert_eq!(tasks[0].id, auth.id);
+
+        // Numbers compare as JSON, not as strings
+        let tasks = service
+            .list(&filter(&[("size", serde_json::json!(3))]))
+            .unwrap();
+        assert_eq!(tasks.len(), 1);
+        let tasks = service
+            .list(&filter(&[("size", serde_json::json!("3"))]))
+            .unwrap();
+        assert!(tasks.is_empty());
+
+        // Multiple filters are ANDed
+        let tasks = service
+            .list(&filter(&[
+                ("area", serde_json::json!("billing")),
+                ("size", serde_json::json!(3)),
+            ]))
+            .unwrap();
+        assert!(tasks.is_empty());
+    }
+
+    #[test]
+    fn test_labels_normalized_added_and_removed() {
+        let conn = setup_db();
+        let service = TaskService::new(&conn);
+
+        let task = service
+            .create(&CreateTaskInput {
+                description: "Task".to_string(),
+                labels: vec!["UI".to_string(), "backend".to_string(), "ui".to_string()],
+                ..Default::default()
+            })
+            .unwrap();
+        assert_eq!(task.labels, vec!["backend", "ui"]);
+
+        let task = service
+            .update(
+                &task.id,
+                &UpdateTaskInput {
+                    labels: vec!["infra".to_string()],
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+        assert_eq!(task.labels, vec!["backend", "infra", "ui"]);
+
+        let task = service
+            .remove_labels(&task.id, &["ui".to_string()])
+            .unwrap();
+        assert_eq!(task.labels, vec!["backend", "infra"]);
+        assert!(matches!(
+            service.add_labels(&task.id, &["not valid".to_string()]),
+            Err(OsError::InvalidLabel(_))
+        ));
+    }
+
+    #[test]
+    fn test_list_and_next_ready_filter_by_labels() {
+        let conn = setup_db();
+        let service = TaskService::new(&conn);
+        let create = |description: &str, parent: Option<&TaskId>, priority, labels: &[&str]| {
+            service
+                .create(&CreateTaskInput {
+                    description: description.to_string(),
+                    parent_id: parent.cloned(),
+                    priority: Some(priority),
+                    labels: labels.iter().map(|l| l.to_string()).collect(),
+                    ..Default::default()
+                })
+                .unwrap()
+                .id
+        };
+        let milestone = create("Milestone", None, 1, &[]);
+        let frontend = create("Frontend", Some(&milestone), 0, &["frontend"]);
+        let backend = create("Backend", Some(&milestone), 1, &["backend"]);
+        let both = create("Both", Some(&milestone), 2, &["backend", "frontend"]);
+
+        let labels = |labels: &[&str]| labels.iter().map(|l| l.to_string()).collect::<Vec<_>>();
+        let by_labels = |l: &[&str]| NextReadyFilter {
+            labels: labels(l),
+            ..Default::default()
+        };
+        let list = |l: &[&str]| -> Vec<TaskId> {
+            service
+                .list(&ListTasksFilter {
+                    labels: labels(l),
+                    ..Default::default()
+                })
+                .unwrap()
+                .into_iter()
+                .map(|t| t.id)
+                .collect()
+        };
+        assert_eq!(list(&["backend"]), vec![backend.clone(), both.clone()]);
+        assert_eq!(list(&["Backend", "frontend"]), vec![both.clone()]);
+
+        assert_eq!(service.next_ready(None).unwrap(), Some(frontend));
+        // Skips the higher-priority frontend leaf instead of stopping at it
+        assert_eq!(
+            service
+                .next_ready_matching(Some(&milestone), &by_labels(&["backend"]))
+                .unwrap(),
+            Some(backend.clone())
+        );
+        service.complete(&backend, None).unwrap();
+        assert_eq!(
+            service
+                .next_ready_matching(None, &by_labels(&["backend"]))
+                .unwrap(),
+            Some(both)
+        );
+        assert_eq!(
+            service
+                .next_ready_matching(None, &by_labels(&["docs"]))
+                .unwrap(),
+            None
+        );
+    }
+
+    #[test]
+    fn test_claims_conflict_across_subtree_and_expire() {
+        let conn = setup_db();
+        let service = TaskService::new(&conn);
+        let create = |parent: Option<&TaskId>| {
+            service
+                .create(&CreateTaskInput {
+                    description: "Task".to_string(),
+                    parent_id: parent.cloned(),
+                    ..Default::default()
+                })
+                .unwrap()
+                .id
+        };
+        let milestone = create(None);
+        let task = create(Some(&milestone));
+        let ttl = Duration::minutes(10);
+
+        let claim = service.claim(&task, "alice", ttl).unwrap();
+        assert_eq!(claim.agent, "alice");
+
+        // Ancestor and descendant claims conflict, naming the holder
+        assert!(matches!(
+            service.claim(&milestone, "bob", ttl),
+            Err(OsError::TaskClaimed { ref holder, .. }) if holder == "alice"
+        ));
+        assert!(matches!(
+            service.heartbeat(&task, "bob", ttl),
+            Err(OsError::ClaimNotHeld { .. })
+        ));
+        assert!(service.check_claim(&task, Some("alice")).is_ok());
+        assert!(service.check_claim(&task, None).is_err());
+
+        // Re-claiming renews and keeps claimed_at
+        let renewed = service.claim(&task, "alice", Duration::hours(1)).unwrap();
+        assert_eq!(renewed.claimed_at, claim.claimed_at);
+        assert!(renewed.expires_at > claim.expires_at);
+
+        // An expired claim is ignored and replaced
+        claim_repo::put_claim(
+            &conn,
+            &TaskClaim {
+                expires_at: Utc::now() - Duration::seconds(1),
+                ..renewed
+            },
+        )
+        .unwrap();
+        assert!(service.get(&task).unwrap().claim.is_none());
+        assert!(service.heartbeat(&task, "alice", ttl).is_err());
+        assert_eq!(service.claim(&milestone, "bob", ttl).unwrap().agent, "bob");
+    }
+
+    #[test]
+    fn test_release_and_next_ready_unclaimed() {
+        let conn = setup_db();
+        let service = TaskService::new(&conn);
+        let create = |description: &str, priority| {
+            service
+                .create(&CreateTaskInput {
+                    description: description.to_string(),
+                    priority: Some(priority),
+                    ..Default::default()
+                })
+                .unwrap()
+                .id
+        };
+        let first = create("First", 0);
+        let second = create("Second", 1);
+        let ttl = Duration::minutes(10);
+        service.claim(&first, "alice", ttl).unwrap();
+
+        let unclaimed = |agent: Option<&str>| {
+            service
+                .next_ready_matching(
+                    None,
+                    &NextReadyFilter {
+                        unclaimed: true,
+                        agent: agent.map(str::to_string),
+                        ..Default::default()
+                    },
+                )
+                .unwrap()
+        };
+        assert_eq!(service.next_ready(None).unwrap(), Some(first.clone()));
+        assert_eq!(unclaimed(Some("bob")), Some(second.clone()));
+        assert_eq!(unclaimed(Some("alice")), Some(first.clone()));
+        assert_eq!(unclaimed(None), Some(second));
+
+        assert!(matches!(
+            service.release(&first, Some("bob"), false),
+            Err(OsError::ClaimNotHeld { .. })
+        ));
+        service.release(&first, Some("alice"), false).unwrap();
+        assert_eq!(unclaimed(Some("bob")), Some(first.clone()));
+
+        service.claim(&first, "alice", ttl).unwrap();
+        service.release(&first, None, true).unwrap();
+        assert!(claim_repo::get_claim(&conn, &first).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_failed_event_rolls_back_the_mutation() {
+        let conn = setup_db();
+        let service = TaskService::new(&conn);
+        let task = service
+            .create(&CreateTaskInput {
+                description: "Before".to_string(),
+                ..Default::default()
+            })
+            .unwrap();
+        conn.execute_batch(
+            "CREATE TRIGGER reject_events BEFORE INSERT ON events
+             BEGIN SELECT RAISE(ABORT, 'event log unavailable'); END;",
+        )
+        .unwrap();
+
+        let update = UpdateTaskInput {
+            description: Some("After".to_string()),
+            ..Default::default()
+        };
+        assert!(service.update(&task.id, &update).is_err());
+        assert!(service.start(&task.id).is_err());
+        assert!(service
+            .create(&CreateTaskInput {
+                description: "Orphan".to_string(),
+                ..Default::default()
+            })
+            .is_err());
+
+        let after = service.get(&task.id).unwrap();
+        assert_eq!(after.description, "Before");
+        assert!(after.started_at.is_none());
+        assert_eq!(after.revision, task.revision);
+        assert_eq!(
+            task_repo::list_tasks(&conn, &Default::default())
+                .unwrap()
+                .len(),
+            1
+        );
+    }
+
+    #[test]
+    fn test_revision_bumps_and_guards_writes() {
+        let conn = setup_db();
+        let service = TaskService::new(&conn);
+        let task = service
+            .create(&CreateTaskInput {
+                description: "Task".to_string(),
+                ..Default::default()
+            })
+            .unwrap();
+        assert_eq!(task.revision, 0);
+
+        let update = |description: &str| UpdateTaskInput {
+            description: Some(description.to_string()),
+            ..Default::default()
+        };
+        let updated = service
+            .at_revision(&task.id, Some(0), |svc| svc.update(&task.id, &update("v1")))
+            .unwrap();
+        assert_eq!(updated.revision, 1);
+
+        // Writes outside the row (metadata touches it) bump the revision too
+        service
+            .set_metadata(&task.id, "k", &serde_json::json!(1))
+            .unwrap();
+        assert_eq!(service.get(&task.id).unwrap().revision, 2);
+
+        // A stale expectation is refused and leaves the task alone
+        match service.at_revision(&task.id, Some(1), |svc| svc.update(&task.id, &update("v2"))) {
+            Err(OsError::RevisionConflict {
+                expected, actual, ..
+            }) => assert_eq!((expected, actual), (1, 2)),
+            other => panic!("expected RevisionConflict, got {:?}", other.map(|t| t.id)),
+        }
+        assert_eq!(service.get(&task.id).unwrap().description, "v1");
+
+        let cancelled = service
+            .at_revision(&task.id, Some(2), |svc| svc.cancel(&task.id))
+            .unwrap();
+        assert_eq!(cancelled.revision, 3);
+        assert!(service.check_revision(&task.id, None).is_ok());
+    }
+
+    #[test]
+    fn test_configured_max_depth_and_deep_context() {
+        let conn = setup_db();
+        let service = TaskService::new(&conn);
+        let create = |parent: Option<&TaskId>, context: &str| {
+            service.create(&CreateTaskInput {
+                description: "Task".to_string(),
+                context: Some(context.to_string()),
+                parent_id: parent.cloned(),
+                ..Default::default()
+            })
+        };
+        let root = create(None, "root").unwrap().id;
+        let task = create(Some(&root), "task").unwrap().id;
+        let subtask = create(Some(&task), "subtask").unwrap().id;
+        assert!(matches!(
+            create(Some(&subtask), "deeper"),
+            Err(OsError::MaxDepthExceeded { max: 3 })
+        ));
+
+        settings_repo::set_max_depth(&conn, 4).unwrap();
+        let deep = create(Some(&subtask), "deeper").unwrap().id;
+        learning_repo::add_learning(&conn, &task, "Grandparent learning", None).unwrap();
+        learning_repo::add_learning(&conn, &subtask, "Parent learning", None).unwrap();
+
+        // Parent is the direct parent and milestone the root, at any depth
+        let fetched = service.get(&deep).unwrap();
+        assert_eq!(fetched.depth, Some(3));
+        let chain = fetched.context_chain.unwrap();
+        assert_eq!(chain.parent.as_deref(), Some("subtask"));
+        assert_eq!(chain.milestone.as_deref(), Some("root"));
+        let learnings = fetched.learnings.unwrap();
+        assert_eq!(learnings.parent.len(), 1);
+        assert_eq!(learnings.parent[0].content, "Parent learning");
+        assert!(learnings.milestone.is_empty());
+
+        // Reparenting checks the moved subtree against the limit too
+        let other = create(Some(&root), "other").unwrap().id;
+        let result = service.update(
+            &task,
+            &UpdateTaskInput {
+                parent_id: Some(other),
+                ..Default::default()
+            },
+        );
+        assert!(matches!(result, Err(OsError::MaxDepthExceeded { max: 4 })));
+    }
+
+    #[test]
+    fn test_repo_is_inherited_and_filters_list_and_next_ready() {
+        let conn = setup_db();
+        let service = TaskService::new(&conn);
+        let tmp = tempfile::TempDir::new().unwrap();
+        std::fs::create_dir_all(tmp.path().join("api/.git")).unwrap();
+        let api = repos::register(&conn, &tmp.path().join("api"), None).unwrap();
+
+        let milestone = service
+            .create(&CreateTaskInput {
+                description: "Milestone".to_string(),
+                ..Default::default()
+            })
+            .unwrap();
+        let web = service
+            .create(&CreateTaskInput {
+                description: "Web".to_string(),
+                parent_id: Some(milestone.id.clone()),
+                priority: Some(0),
+                ..Default::default()
+            })
+            .unwrap();
+        let endpoint = service
+            .create(&CreateTaskInput {
+                description: "Endpoint".to_string(),
+                parent_id: Some(milestone.id.clone()),
+                repo_id: Some(api.id.clone()),
+                ..Default::default()
+            })
+            .unwrap();
+        let handler = service
+            .create(&CreateTaskInput {
+                description: "Handler".to_string(),
+                parent_id: Some(endpoint.id.clone()),
+                ..Default::default()
+            })
+            .unwrap();
+        assert_eq!(handler.repo_id, Some(api.id.clone()));
+        assert!(web.repo_id.is_none());
+
+        let in_api = service
+            .list(&ListTasksFilter {
+                repo_id: Some(api.id.clone()),
+                ..Default::default()
+            })
+            .unwrap();
+        let mut in_api = in_api.into_iter().map(|t| t.id).collect::<Vec<_>>();
+        in_api.sort();
+        let mut expected = vec![endpoint.id.clone(), handler.id.clone()];
+        expected.sort();
+        assert_eq!(in_api, expected);
+        assert_eq!(service.next_ready(None).unwrap(), Some(web.id.clone()));
+        let api_filter = NextReadyFilter {
+            repo_id: Some(api.id.clone()),
+            ..Default::default()
+        };
+        assert_eq!(
+            service.next_ready_matching(None, &api_filter).unwrap(),
+            Some(handler.id.clone())
+        );
+
+        // Unknown repos are rejected, and a task with a branch keeps its repo
+        let unknown = UpdateTaskInput {
+            repo_id: Some(Some(RepoId::new())),
+            ..Default::default()
+        };
+        assert!(matches!(
+            service.update(&web.id, &unknown),
+            Err(OsError::RepoNotFound(_))
+        ));
+        task_repo::set_bookmark(&conn, &handler.id, "task/handler").unwrap();
+        let detach = UpdateTaskInput {
+            repo_id: Some(None),
+            ..Default::default()
+        };
+        assert!(matches!(
+            service.update(&handler.id, &detach),
+            Err(OsError::RepoChangeAfterStart(_))
+        ));
+        assert!(service
+            .update(&endpoint.id, &detach)
+            .unwrap()
+            .repo_id
+            .is_none());
+    }
 }
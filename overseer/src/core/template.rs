@@ -0,0 +1,529 @@
+//! Reusable task trees stored as JSON files under `.overseer/templates`.
+//!
+//! `capture` turns a live subtree into a [`Template`]: descriptions, contexts,
+//! priorities, labels, sibling order and blockers between tasks of the tree.
+//! Lifecycle state, due dates, metadata and blockers pointing outside the tree
+//! are left out. `instantiate` creates a fresh copy under a parent (or as a new
+//! milestone) in one transaction, substituting `{{name}}` placeholders.
+
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use rusqlite::Connection;
+use serde::{Deserialize, Serialize};
+
+use crate::core::{TaskGraph, TaskService};
+use crate::db::retry;
+use crate::error::{OsError, Result};
+use crate::id::TaskId;
+use crate::types::{CreateTaskInput, Task};
+
+/// Current template format version. Loading accepts any 1.x file.
+const TEMPLATE_VERSION: &str = "1.0.0";
+
+fn default_priority() -> i32 {
+    1
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Template {
+    pub version: String,
+    pub name: String,
+    /// Placeholders used anywhere in the tree, sorted
+    #[serde(default)]
+    pub variables: Vec<String>,
+    pub root: TemplateNode,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TemplateNode {
+    /// Unique within the template; `blockedBy` refers to nodes by key
+    pub key: String,
+    pub description: String,
+    #[serde(default)]
+    pub context: String,
+    #[serde(default = "default_priority")]
+    pub priority: i32,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub labels: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub blocked_by: Vec<String>,
+    /// In sibling order
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub children: Vec<TemplateNode>,
+}
+
+impl Template {
+    /// Nodes depth-first, parents before children
+    fn nodes(&self) -> Vec<&TemplateNode> {
+        let mut result = Vec::new();
+        let mut stack = vec![&self.root];
+        while let Some(node) = stack.pop() {
+            result.push(node);
+            stack.extend(node.children.iter().rev());
+        }
+        result
+    }
+
+    /// Placeholder names found in descriptions, contexts and labels
+    fn placeholders(&self) -> BTreeSet<String> {
+        let mut names = BTreeSet::new();
+        for node in self.nodes() {
+            let fields = [&node.description, &node.context]
+                .into_iter()
+                .chain(node.labels.iter());
+            for text in fields {
+                scan_placeholders(text, |name| {
+                    names.insert(name.to_string());
+                    None
+                });
+            }
+        }
+        names
+    }
+
+    /// Keys must be unique and blockers must name another node of the template
+    fn validate(&self) -> Result<()> {
+        let invalid = |message: String| OsError::InvalidTemplate {
+            name: self.name.clone(),
+            message,
+        };
+        if !self.version.starts_with("1.") {
+            return Err(invalid(format!(
+                "unsupported version {} (expected 1.x)",
+                self.version
+            )));
+        }
+        let mut keys = HashSet::new();
+        for node in self.nodes() {
+            if !keys.insert(node.key.as_str()) {
+                return Err(invalid(format!("duplicate key '{}'", node.key)));
+            }
+        }
+        for node in self.nodes() {
+            for blocker in &node.blocked_by {
+                if blocker == &node.key {
+                    return Err(invalid(format!("'{}' blocks itself", node.key)));
+                }
+                if !keys.contains(blocker.as_str()) {
+                    return Err(invalid(format!(
+                        "'{}' is blocked by unknown key '{}'",
+                        node.key, blocker
+                    )));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    pub fn task_count(&self) -> usize {
+        self.nodes().len()
+    }
+}
+
+/// Call `f` on every `{{name}}` in `text`; where it returns a value the
+/// placeholder is replaced. Braces around anything but a valid name are kept.
+fn scan_placeholders(text: &str, mut f: impl FnMut(&str) -> Option<String>) -> String {
+    let mut result = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(open) = rest.find("{{") {
+        result.push_str(&rest[..open]);
+        let after = &rest[open + 2..];
+        match after.find("}}") {
+            Some(close) if is_variable_name(after[..close].trim()) => {
+                let name = after[..close].trim();
+                match f(name) {
+                    Some(value) => result.push_str(&value),
+                    None => result.push_str(&rest[open..open + 2 + close + 2]),
+                }
+                rest = &after[close + 2..];
+            }
+            _ => {
+                result.push_str("{{");
+                rest = after;
+            }
+        }
+    }
+    result.push_str(rest);
+    result
+}
+
+pub fn is_variable_name(name: &str) -> bool {
+    !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+}
+
+/// Capture the subtree rooted at `root_id`. Cancelled and archived tasks are
+/// skipped with their subtrees. Each `(name, value)` in `vars` turns literal
+/// occurrences of `value` into `{{name}}`, longest value first.
+pub fn capture(
+    conn: &Connection,
+    root_id: &TaskId,
+    name: &str,
+    vars: &[(String, String)],
+) -> Result<Template> {
+    let graph = TaskGraph::load(conn)?;
+    let root = graph
+        .get(root_id)
+        .ok_or_else(|| OsError::TaskNotFound(root_id.clone()))?;
+
+    let mut vars: Vec<&(String, String)> = vars.iter().filter(|(_, v)| !v.is_empty()).collect();
+    vars.sort_by_key(|(_, value)| std::cmp::Reverse(value.len()));
+    let generalize = |text: &str| {
+        vars.iter().fold(text.to_string(), |text, (name, value)| {
+            text.replace(value.as_str(), &format!("{{{{{name}}}}}"))
+        })
+    };
+
+    // Outline keys ("1", "1.2", "1.2.1") assigned in sibling order
+    let mut keys: HashMap<&TaskId, String> = HashMap::new();
+    let mut order: Vec<&Task> = Vec::new();
+    let mut stack = vec![(root, "1".to_string())];
+    while let Some((task, key)) = stack.pop() {
+        let children: Vec<&Task> = graph
+            .children(&task.id)
+            .filter(|c| !c.cancelled && !c.archived)
+            .collect();
+        for (i, child) in children.iter().enumerate().rev() {
+            stack.push((child, format!("{key}.{}", i + 1)));
+        }
+        keys.insert(&task.id, key);
+        order.push(task);
+    }
+
+    let node_for = |task: &Task| TemplateNode {
+        key: keys[&task.id].clone(),
+        description: generalize(&task.description),
+        context: generalize(&task.context),
+        priority: task.priority,
+        labels: task.labels.iter().map(|l| generalize(l)).collect(),
+        blocked_by: task
+            .blocked_by
+            .iter()
+            .filter_map(|b| keys.get(b).cloned())
+            .collect(),
+        children: Vec::new(),
+    };
+
+    // Assemble bottom-up: `order` lists parents before children
+    let mut built: HashMap<&TaskId, TemplateNode> = HashMap::new();
+    for task in order.iter().rev() {
+        let mut node = node_for(task);
+        node.children = graph
+            .children(&task.id)
+            .filter_map(|c| built.remove(&c.id))
+            .collect();
+        built.insert(&task.id, node);
+    }
+
+    let mut template = Template {
+        version: TEMPLATE_VERSION.to_string(),
+        name: name.to_string(),
+        variables: Vec::new(),
+        root: built.remove(&root.id).expect("root is captured"),
+    };
+    template.variables = template.placeholders().into_iter().collect();
+    Ok(template)
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AppliedTemplate {
+    pub name: String,
+    pub root_id: TaskId,
+    /// Created tasks, parents before children
+    pub tasks: Vec<Task>,
+    pub blockers: usize,
+}
+
+/// Create the template's tree under `parent` (None: as a new milestone). Every
+/// placeholder needs a value in `vars`. All tasks and blockers are created in a
+/// single transaction, so a failure (depth limit, inactive parent) leaves
+/// nothing behind.
+pub fn instantiate(
+    conn: &Connection,
+    template: &Template,
+    parent: Option<&TaskId>,
+    vars: &HashMap<String, String>,
+) -> Result<AppliedTemplate> {
+    template.validate()?;
+    let missing: Vec<String> = template
+        .placeholders()
+        .into_iter()
+        .filter(|name| !vars.contains_key(name))
+        .collect();
+    if !missing.is_empty() {
+        return Err(OsError::TemplateVariablesMissing {
+            name: template.name.clone(),
+            missing,
+        });
+    }
+    let fill = |text: &str| scan_placeholders(text, |name| vars.get(name).cloned());
+
+    let tx = retry::begin_immediate(conn)?;
+    let service = TaskService::new(&tx);
+    let mut ids: HashMap<&str, TaskId> = HashMap::new();
+    let mut parents: HashMap<&str, Option<TaskId>> = HashMap::new();
+    parents.insert(&template.root.key, parent.cloned());
+
+    for node in template.nodes() {
+        let task = service.create(&CreateTaskInput {
+            description: fill(&node.description),
+            context: Some(fill(&node.context)),
+            parent_id: parents[node.key.as_str()].clone(),
+            priority: Some(node.priority),
+            labels: node.labels.iter().map(|l| fill(l)).collect(),
+            ..Default::default()
+        })?;
+        for child in &node.children {
+            parents.insert(&child.key, Some(task.id.clone()));
+        }
+        ids.insert(&node.key, task.id);
+    }
+
+    let mut blockers = 0;
+    for node in template.nodes() {
+        for blocker in &node.blocked_by {
+            service.add_blocker(&ids[node.key.as_str()], &ids[blocker.as_str()])?;
+            blockers += 1;
+        }
+    }
+
+    let tasks = template
+        .nodes()
+        .iter()
+        .map(|node| service.get(&ids[node.key.as_str()]))
+        .collect::<Result<Vec<_>>>()?;
+    tx.commit()?;
+
+    Ok(AppliedTemplate {
+        name: template.name.clone(),
+        root_id: ids[template.root.key.as_str()].clone(),
+        tasks,
+        blockers,
+    })
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TemplateSummary {
+    pub name: String,
+    pub path: PathBuf,
+    pub tasks: usize,
+    pub variables: Vec<String>,
+}
+
+impl TemplateSummary {
+    fn new(template: &Template, path: PathBuf) -> Self {
+        Self {
+            name: template.name.clone(),
+            path,
+            tasks: template.task_count(),
+            variables: template.placeholders().into_iter().collect(),
+        }
+    }
+}
+
+/// Directory of `<name>.json` template files.
+pub struct TemplateStore {
+    dir: PathBuf,
+}
+
+impl TemplateStore {
+    /// `templates/` next to the database, i.e. `.overseer/templates` by default
+    pub fn for_db(db_path: &Path) -> Self {
+        let base = db_path.parent().unwrap_or_else(|| Path::new("."));
+        Self {
+            dir: base.join("templates"),
+        }
+    }
+
+    fn path(&self, name: &str) -> Result<PathBuf> {
+        if !is_variable_name(name) {
+            return Err(OsError::InvalidTemplateName(name.to_string()));
+        }
+        Ok(self.dir.join(format!("{name}.json")))
+    }
+
+    pub fn save(&self, template: &Template, force: bool) -> Result<TemplateSummary> {
+        let path = self.path(&template.name)?;
+        if path.exists() && !force {
+            return Err(OsError::TemplateExists(template.name.clone()));
+        }
+        fs::create_dir_all(&self.dir)?;
+        fs::write(&path, serde_json::to_string_pretty(template)? + "\n")?;
+        Ok(TemplateSummary::new(template, path))
+    }
+
+    pub fn load(&self, name: &str) -> Result<Template> {
+        let path = self.path(name)?;
+        if !path.exists() {
+            return Err(OsError::TemplateNotFound(name.to_string()));
+        }
+        let mut template: Template =
+            serde_json::from_str(&fs::read_to_string(&path)?).map_err(|e| {
+                OsError::InvalidTemplate {
+                    name: name.to_string(),
+                    message: e.to_string(),
+                }
+            })?;
+        // The file name wins over a stale `name` field in a copied file
+        template.name = name.to_string();
+        template.validate()?;
+        Ok(template)
+    }
+
+    /// Every template in the directory, by name. Files that fail to load are skipped.
+    pub fn list(&self) -> Result<Vec<TemplateSummary>> {
+        if !self.dir.exists() {
+            return Ok(Vec::new());
+        }
+        let mut summaries = Vec::new();
+        for entry in fs::read_dir(&self.dir)? {
+            let path = entry?.path();
+            if path.extension().is_none_or(|ext| ext != "json") {
+                continue;
+            }
+            let Some(name) = path.file_stem().and_then(|s| s.to_str()) else {
+                continue;
+            };
+            if let Ok(template) = self.load(name) {
+                summaries.push(TemplateSummary::new(&template, path.clone()));
+            }
+        }
+        summaries.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(summaries)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::schema;
+
+    fn setup_db() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        schema::init_schema(&conn).unwrap();
+        conn
+    }
+
+    fn create(
+        service: &TaskService,
+        description: &str,
+        context: &str,
+        parent: Option<&TaskId>,
+    ) -> TaskId {
+        service
+            .create(&CreateTaskInput {
+                description: description.to_string(),
+                context: Some(context.to_string()),
+                parent_id: parent.cloned(),
+                ..Default::default()
+            })
+            .unwrap()
+            .id
+    }
+
+    #[test]
+    fn test_scan_placeholders_substitutes_valid_names_only() {
+        let vars = HashMap::from([("name".to_string(), "users".to_string())]);
+        let filled = scan_placeholders("GET /{{name}} {{ name }} {{x y}} {{", |n| {
+            vars.get(n).cloned()
+        });
+        assert_eq!(filled, "GET /users users {{x y}} {{");
+    }
+
+    #[test]
+    fn test_capture_and_instantiate_round_trip() {
+        let conn = setup_db();
+        let service = TaskService::new(&conn);
+        let milestone = create(&service, "Add users endpoint", "Resource: users", None);
+        let design = create(&service, "Design users API", "", Some(&milestone));
+        let implement = create(&service, "Implement", "", Some(&milestone));
+        let dropped = create(&service, "Dropped idea", "", Some(&milestone));
+        let outside = create(&service, "Unrelated", "", None);
+        service.add_blocker(&implement, &design).unwrap();
+        service.add_blocker(&implement, &outside).unwrap();
+        service.add_labels(&design, &["api".to_string()]).unwrap();
+        service.cancel(&dropped).unwrap();
+
+        let vars = [("resource".to_string(), "users".to_string())];
+        let template = capture(&conn, &milestone, "endpoint", &vars).unwrap();
+        assert_eq!(template.variables, vec!["resource"]);
+        assert_eq!(template.root.description, "Add {{resource}} endpoint");
+        assert_eq!(template.task_count(), 3);
+        let implement_node = &template.root.children[1];
+        assert_eq!(implement_node.key, "1.2");
+        // The blocker outside the subtree is dropped
+        assert_eq!(implement_node.blocked_by, vec!["1.1"]);
+
+        let missing = instantiate(&conn, &template, None, &HashMap::new());
+        assert!(matches!(
+            missing,
+            Err(OsError::TemplateVariablesMissing { .. })
+        ));
+
+        let vars = HashMap::from([("resource".to_string(), "orders".to_string())]);
+        let applied = instantiate(&conn, &template, Some(&outside), &vars).unwrap();
+        assert_eq!(applied.tasks.len(), 3);
+        assert_eq!(applied.blockers, 1);
+        let root = &applied.tasks[0];
+        assert_eq!(root.description, "Add orders endpoint");
+        assert_eq!(root.context, "Resource: orders");
+        assert_eq!(root.parent_id.as_ref(), Some(&outside));
+        assert_eq!(applied.tasks[1].labels, vec!["api"]);
+        assert_eq!(
+            applied.tasks[2].blocked_by,
+            vec![applied.tasks[1].id.clone()]
+        );
+    }
+
+    #[test]
+    fn test_instantiate_is_all_or_nothing() {
+        let conn = setup_db();
+        let service = TaskService::new(&conn);
+        let milestone = create(&service, "Milestone", "", None);
+        let task = create(&service, "Task", "", Some(&milestone));
+        create(&service, "Subtask", "", Some(&task));
+        let template = capture(&conn, &milestone, "three-levels", &[]).unwrap();
+        let before = service.list(&Default::default()).unwrap().len();
+
+        // Under a task the copied subtasks land past the default depth limit
+        let result = instantiate(&conn, &template, Some(&task), &HashMap::new());
+        assert!(matches!(result, Err(OsError::MaxDepthExceeded { .. })));
+        assert_eq!(service.list(&Default::default()).unwrap().len(), before);
+    }
+
+    #[test]
+    fn test_store_saves_loads_and_refuses_overwrite() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = TemplateStore::for_db(&dir.path().join("tasks.db"));
+        let conn = setup_db();
+        let milestone = create(&TaskService::new(&conn), "M {{x}}", "", None);
+        let template = capture(&conn, &milestone, "m", &[]).unwrap();
+
+        let summary = store.save(&template, false).unwrap();
+        assert_eq!(summary.path, dir.path().join("templates").join("m.json"));
+        assert_eq!(summary.variables, vec!["x"]);
+        assert!(matches!(
+            store.save(&template, false),
+            Err(OsError::TemplateExists(_))
+        ));
+        store.save(&template, true).unwrap();
+        assert_eq!(store.load("m").unwrap(), template);
+        assert_eq!(store.list().unwrap().len(), 1);
+        assert!(matches!(
+            store.load("../m"),
+            Err(OsError::InvalidTemplateName(_))
+        ));
+        assert!(matches!(
+            store.load("other"),
+            Err(OsError::TemplateNotFound(_))
+        ));
+    }
+}
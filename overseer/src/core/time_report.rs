@@ -0,0 +1,282 @@
+//! Time spent on tasks, aggregated from work sessions (`task_sessions`).
+//!
+//! Sessions are clipped to the report window; a running session counts up to
+//! the end of the window or now, whichever is earlier. Time is attributed to
+//! the task the session ran on and to that task's milestone. Days are UTC.
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use chrono::{DateTime, Duration, NaiveDate, Utc};
+use rusqlite::Connection;
+use serde::{Deserialize, Serialize};
+
+use crate::core::TaskGraph;
+use crate::db::session_repo;
+use crate::error::{OsError, Result};
+use crate::id::TaskId;
+use crate::types::TaskSession;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TimeReport {
+    /// Start of the window (None: since the first session)
+    pub since: Option<DateTime<Utc>>,
+    /// End of the window, never later than when the report was made
+    pub until: DateTime<Utc>,
+    pub total_seconds: i64,
+    /// Most time first
+    pub tasks: Vec<TaskTime>,
+    /// Most time first
+    pub milestones: Vec<MilestoneTime>,
+    /// Oldest first; days without time are left out
+    pub days: Vec<DayTime>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TaskTime {
+    pub task_id: TaskId,
+    pub description: String,
+    pub milestone_id: TaskId,
+    pub seconds: i64,
+    /// Sessions overlapping the window
+    pub sessions: usize,
+    /// A session is running now
+    pub running: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MilestoneTime {
+    pub milestone_id: TaskId,
+    pub description: String,
+    pub seconds: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DayTime {
+    pub date: NaiveDate,
+    pub seconds: i64,
+}
+
+/// Report on sessions under `root` (a milestone or any task; None: all tasks)
+/// between `since` and `until`.
+pub fn time_report(
+    conn: &Connection,
+    root: Option<&TaskId>,
+    since: Option<DateTime<Utc>>,
+    until: Option<DateTime<Utc>>,
+) -> Result<TimeReport> {
+    let graph = TaskGraph::load(conn)?;
+    let scope: Option<HashSet<&TaskId>> = match root {
+        Some(id) => {
+            let task = graph
+                .get(id)
+                .ok_or_else(|| OsError::TaskNotFound(id.clone()))?;
+            let mut ids: HashSet<&TaskId> =
+                graph.descendants(id).into_iter().map(|t| &t.id).collect();
+            ids.insert(&task.id);
+            Some(ids)
+        }
+        None => None,
+    };
+    let sessions: Vec<TaskSession> = session_repo::list_sessions(conn)?
+        .into_iter()
+        .filter(|s| scope.as_ref().is_none_or(|ids| ids.contains(&s.task_id)))
+        .collect();
+
+    let now = Utc::now();
+    let until = until.map_or(now, |u| u.min(now));
+    Ok(aggregate(&graph, &sessions, since, until))
+}
+
+fn aggregate(
+    graph: &TaskGraph,
+    sessions: &[TaskSession],
+    since: Option<DateTime<Utc>>,
+    until: DateTime<Utc>,
+) -> TimeReport {
+    let from = since.unwrap_or(DateTime::<Utc>::MIN_UTC);
+    let mut tasks: HashMap<&TaskId, TaskTime> = HashMap::new();
+    let mut milestones: HashMap<TaskId, MilestoneTime> = HashMap::new();
+    let mut days: BTreeMap<NaiveDate, i64> = BTreeMap::new();
+
+    for session in sessions {
+        // Sessions of tasks deleted since are cascaded away; skip any stragglers
+        let Some(task) = graph.get(&session.task_id) else {
+            continue;
+        };
+        let Some((start, end)) = session.clip(from, until) else {
+            continue;
+        };
+        let seconds = (end - start).num_seconds();
+        let milestone = graph.ancestors(&task.id).last().unwrap_or(task);
+
+        let entry = tasks.entry(&task.id).or_insert_with(|| TaskTime {
+            task_id: task.id.clone(),
+            description: task.description.clone(),
+            milestone_id: milestone.id.clone(),
+            seconds: 0,
+            sessions: 0,
+            running: false,
+        });
+        entry.seconds += seconds;
+        entry.sessions += 1;
+        entry.running |= session.is_running();
+
+        milestones
+            .entry(milestone.id.clone())
+            .or_insert_with(|| MilestoneTime {
+                milestone_id: milestone.id.clone(),
+                description: milestone.description.clone(),
+                seconds: 0,
+            })
+            .seconds += seconds;
+
+        for (date, seconds) in split_by_day(start, end) {
+            *days.entry(date).or_default() += seconds;
+        }
+    }
+
+    let mut tasks: Vec<TaskTime> = tasks.into_values().collect();
+    tasks.sort_by(|a, b| {
+        b.seconds
+            .cmp(&a.seconds)
+            .then_with(|| a.task_id.cmp(&b.task_id))
+    });
+    let mut milestones: Vec<MilestoneTime> = milestones.into_values().collect();
+    milestones.sort_by(|a, b| {
+        b.seconds
+            .cmp(&a.seconds)
+            .then_with(|| a.milestone_id.cmp(&b.milestone_id))
+    });
+
+    TimeReport {
+        since,
+        until,
+        total_seconds: tasks.iter().map(|t| t.seconds).sum(),
+        tasks,
+        milestones,
+        days: days
+            .into_iter()
+            .map(|(date, seconds)| DayTime { date, seconds })
+            .collect(),
+    }
+}
+
+/// Seconds of `[start, end)` falling on each UTC day.
+fn split_by_day(start: DateTime<Utc>, end: DateTime<Utc>) -> Vec<(NaiveDate, i64)> {
+    let mut result = Vec::new();
+    let mut cursor = start;
+    while cursor < end {
+        let date = cursor.date_naive();
+        let next_midnight = (date + Duration::days(1))
+            .and_hms_opt(0, 0, 0)
+            .map_or(end, |midnight| midnight.and_utc());
+        let stop = next_midnight.min(end);
+        result.push((date, (stop - cursor).num_seconds()));
+        cursor = stop;
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::TaskService;
+    use crate::db::schema;
+    use crate::types::CreateTaskInput;
+
+    fn at(s: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(s).unwrap().with_timezone(&Utc)
+    }
+
+    fn session(task_id: &TaskId, start: &str, end: Option<&str>) -> TaskSession {
+        TaskSession {
+            id: 0,
+            task_id: task_id.clone(),
+            agent: None,
+            started_at: at(start),
+            ended_at: end.map(at),
+        }
+    }
+
+    #[test]
+    fn test_split_by_day_crosses_midnight() {
+        assert_eq!(
+            split_by_day(at("2026-03-01T23:30:00Z"), at("2026-03-02T00:45:00Z")),
+            vec![
+                (NaiveDate::from_ymd_opt(2026, 3, 1).unwrap(), 1800),
+                (NaiveDate::from_ymd_opt(2026, 3, 2).unwrap(), 2700),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_aggregate_clips_to_window_and_rolls_up_to_milestones() {
+        let conn = rusqlite::Connection::open_in_memory().unwrap();
+        schema::init_schema(&conn).unwrap();
+        let service = TaskService::new(&conn);
+        let create = |description: &str, parent: Option<&TaskId>| {
+            service
+                .create(&CreateTaskInput {
+                    description: description.to_string(),
+                    parent_id: parent.cloned(),
+                    ..Default::default()
+                })
+                .unwrap()
+                .id
+        };
+        let milestone = create("Milestone", None);
+        let task = create("Task", Some(&milestone));
+        let subtask = create("Subtask", Some(&task));
+        let other = create("Other", None);
+        let graph = TaskGraph::load(&conn).unwrap();
+
+        let sessions = vec![
+            // Starts before the window: only the last 30 minutes count
+            session(
+                &subtask,
+                "2026-03-01T09:30:00Z",
+                Some("2026-03-01T10:30:00Z"),
+            ),
+            session(
+                &subtask,
+                "2026-03-02T08:00:00Z",
+                Some("2026-03-02T09:00:00Z"),
+            ),
+            session(&task, "2026-03-02T23:00:00Z", Some("2026-03-03T01:00:00Z")),
+            // Running: counts up to the end of the window
+            session(&other, "2026-03-03T11:00:00Z", None),
+            // Entirely before the window
+            session(&other, "2026-02-27T08:00:00Z", Some("2026-02-27T09:00:00Z")),
+        ];
+        let report = aggregate(
+            &graph,
+            &sessions,
+            Some(at("2026-03-01T10:00:00Z")),
+            at("2026-03-03T12:00:00Z"),
+        );
+
+        assert_eq!(report.total_seconds, (30 + 60 + 120 + 60) * 60);
+        let ids: Vec<(&TaskId, i64, usize)> = report
+            .tasks
+            .iter()
+            .map(|t| (&t.task_id, t.seconds, t.sessions))
+            .collect();
+        assert_eq!(
+            ids,
+            vec![(&task, 7200, 1), (&subtask, 5400, 2), (&other, 3600, 1)]
+        );
+        assert!(report.tasks[2].running);
+        assert_eq!(report.tasks[1].milestone_id, milestone);
+
+        assert_eq!(report.milestones[0].milestone_id, milestone);
+        assert_eq!(report.milestones[0].seconds, 12600);
+        assert_eq!(report.milestones[1].seconds, 3600);
+
+        let days: Vec<i64> = report.days.iter().map(|d| d.seconds).collect();
+        assert_eq!(days, vec![1800, 3600 + 3600, 3600 + 3600]);
+    }
+}
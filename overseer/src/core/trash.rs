@@ -0,0 +1,700 @@
+//! Trash bin for deleted task subtrees.
+//!
+//! `os task delete` snapshots the subtree (tasks, learnings, comments, links,
+//! work sessions, metadata, labels) together with every blocker edge touching
+//! it, then removes the live rows. Task branches are left alone. `restore`
+//! reinserts the snapshot under the original parent and rebuilds the edges
+//! whose other end still exists; `purge` drops old snapshots and their
+//! branches for good.
+
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+
+use chrono::{DateTime, Duration, Utc};
+use rusqlite::Connection;
+use serde::{Deserialize, Serialize};
+
+use crate::core::{repos, TaskService};
+use crate::db::event_repo::{self, EventBody};
+use crate::db::trash_repo::{self, TrashEntry};
+use crate::db::{
+    comment_repo, label_repo, learning_repo, link_repo, metadata_repo, repo_repo, retry,
+    session_repo, settings_repo, task_repo, Comment, Learning, TaskLink,
+};
+use crate::error::{OsError, Result};
+use crate::id::{RepoId, TaskId};
+use crate::types::{Task, TaskSession};
+use crate::vcs::VcsBackend;
+
+/// A task row as stored in a snapshot. Unlike [`Task`] it keeps the raw
+/// context, branch and start commit.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct TrashedTask {
+    id: TaskId,
+    parent_id: Option<TaskId>,
+    description: String,
+    context: String,
+    result: Option<String>,
+    priority: i32,
+    rank: i64,
+    due_at: Option<DateTime<Utc>>,
+    defer_until: Option<DateTime<Utc>>,
+    completed: bool,
+    completed_at: Option<DateTime<Utc>>,
+    cancelled: bool,
+    cancelled_at: Option<DateTime<Utc>>,
+    archived: bool,
+    archived_at: Option<DateTime<Utc>>,
+    created_at: DateTime<Utc>,
+    updated_at: DateTime<Utc>,
+    started_at: Option<DateTime<Utc>>,
+    commit_sha: Option<String>,
+    bookmark: Option<String>,
+    start_commit: Option<String>,
+    #[serde(default)]
+    repo_id: Option<RepoId>,
+    #[serde(default)]
+    start_branch: Option<String>,
+    #[serde(default)]
+    integration_commit: Option<String>,
+    #[serde(default)]
+    base_rev: Option<String>,
+    #[serde(default)]
+    head_rev: Option<String>,
+    #[serde(default)]
+    worktree: Option<String>,
+    revision: i64,
+    metadata: Option<serde_json::Value>,
+    labels: Vec<String>,
+}
+
+impl From<Task> for TrashedTask {
+    fn from(task: Task) -> Self {
+        Self {
+            id: task.id,
+            parent_id: task.parent_id,
+            description: task.description,
+            context: task.context,
+            result: task.result,
+            priority: task.priority,
+            rank: task.rank,
+            due_at: task.due_at,
+            defer_until: task.defer_until,
+            completed: task.completed,
+            completed_at: task.completed_at,
+            cancelled: task.cancelled,
+            cancelled_at: task.cancelled_at,
+            archived: task.archived,
+            archived_at: task.archived_at,
+            created_at: task.created_at,
+            updated_at: task.updated_at,
+            started_at: task.started_at,
+            commit_sha: task.commit_sha,
+            bookmark: task.bookmark,
+            start_commit: task.start_commit,
+            repo_id: task.repo_id,
+            start_branch: task.start_branch,
+            integration_commit: task.integration_commit,
+            base_rev: task.base_rev,
+            head_rev: task.head_rev,
+            worktree: task.worktree,
+            revision: task.revision,
+            metadata: task.metadata,
+            labels: task.labels,
+        }
+    }
+}
+
+impl TrashedTask {
+    fn to_task(&self, parent_id: Option<TaskId>) -> Task {
+        Task {
+            id: self.id.clone(),
+            parent_id,
+            description: self.description.clone(),
+            context: self.context.clone(),
+            context_chain: None,
+            learnings: None,
+            result: self.result.clone(),
+            priority: self.priority,
+            completed: self.completed,
+            completed_at: self.completed_at,
+            created_at: self.created_at,
+            updated_at: self.updated_at,
+            started_at: self.started_at,
+            commit_sha: self.commit_sha.clone(),
+            bookmark: self.bookmark.clone(),
+            start_commit: self.start_commit.clone(),
+            repo_id: self.repo_id.clone(),
+            start_branch: self.start_branch.clone(),
+            integration_commit: self.integration_commit.clone(),
+            base_rev: self.base_rev.clone(),
+            head_rev: self.head_rev.clone(),
+            worktree: self.worktree.clone(),
+            depth: None,
+            blocked_by: Vec::new(),
+            blocks: Vec::new(),
+            effectively_blocked: false,
+            cancelled: self.cancelled,
+            cancelled_at: self.cancelled_at,
+            archived: self.archived,
+            archived_at: self.archived_at,
+            revision: self.revision,
+            rank: self.rank,
+            due_at: self.due_at,
+            defer_until: self.defer_until,
+            metadata: self.metadata.clone(),
+            labels: self.labels.clone(),
+            claim: None,
+        }
+    }
+}
+
+/// `task_id` is blocked by `blocker_id`
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BlockerEdge {
+    pub task_id: TaskId,
+    pub blocker_id: TaskId,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Snapshot {
+    /// Parents before children; the first task is the root
+    tasks: Vec<TrashedTask>,
+    learnings: Vec<Learning>,
+    comments: Vec<Comment>,
+    links: Vec<TaskLink>,
+    sessions: Vec<TaskSession>,
+    /// Edges between tasks of the subtree and edges to or from outside it
+    blockers: Vec<BlockerEdge>,
+}
+
+/// Outcome of `os task restore`
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RestoredTrash {
+    /// The restored root
+    pub task: Task,
+    pub tasks: usize,
+    /// Blocker edges rebuilt
+    pub blockers: usize,
+    /// Edges dropped because the other task is gone or the edge is no longer
+    /// valid (it would close a cycle, or now points at an ancestor)
+    pub skipped_blockers: Vec<BlockerEdge>,
+}
+
+/// Outcome of `os task trash purge`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PurgedTrash {
+    pub purged: Vec<TrashEntry>,
+    /// Task branches deleted with the purged tasks
+    pub deleted_branches: Vec<String>,
+}
+
+/// Snapshot the subtree rooted at `id` into the trash and delete its live
+/// rows. Running work sessions are closed first; claims are dropped. Refused
+/// while a task in the subtree still has its worktree, since its branch could
+/// not be deleted on purge.
+pub fn move_to_trash(conn: &Connection, id: &TaskId) -> Result<TrashEntry> {
+    let tx = retry::begin_immediate(conn)?;
+    let root = task_repo::get_task(&tx, id)?.ok_or_else(|| OsError::TaskNotFound(id.clone()))?;
+    let mut tasks = vec![root];
+    tasks.extend(task_repo::get_all_descendants(&tx, id)?);
+    // A worktree deleted by hand no longer holds the branch
+    if let Some((task, path)) = tasks.iter().find_map(|t| {
+        let path = t.worktree.as_ref()?;
+        Path::new(path).exists().then_some((t, path))
+    }) {
+        return Err(OsError::WorktreeInUse {
+            task_id: task.id.clone(),
+            path: path.clone(),
+        });
+    }
+    let ids: HashSet<TaskId> = tasks.iter().map(|t| t.id.clone()).collect();
+
+    let now = Utc::now();
+    let mut snapshot = Snapshot {
+        tasks: Vec::new(),
+        learnings: Vec::new(),
+        comments: Vec::new(),
+        links: Vec::new(),
+        sessions: Vec::new(),
+        blockers: Vec::new(),
+    };
+    for task in &tasks {
+        if let Some(session) = session_repo::end_session(&tx, &task.id, now)? {
+            event_repo::append(&tx, &EventBody::SessionEnded { session })?;
+        }
+        snapshot
+            .learnings
+            .extend(learning_repo::list_learnings(&tx, &task.id)?);
+        snapshot
+            .comments
+            .extend(comment_repo::list_comments(&tx, &task.id, None)?);
+        snapshot.links.extend(link_repo::list_links(&tx, &task.id)?);
+        snapshot
+            .sessions
+            .extend(session_repo::list_task_sessions(&tx, &task.id)?);
+        for blocker_id in &task.blocked_by {
+            snapshot.blockers.push(BlockerEdge {
+                task_id: task.id.clone(),
+                blocker_id: blocker_id.clone(),
+            });
+        }
+        // Edges inside the subtree were recorded from their blocked side
+        for blocked_id in task.blocks.iter().filter(|b| !ids.contains(*b)) {
+            snapshot.blockers.push(BlockerEdge {
+                task_id: blocked_id.clone(),
+                blocker_id: task.id.clone(),
+            });
+        }
+    }
+
+    let members: Vec<(TaskId, Option<String>)> = tasks
+        .iter()
+        .map(|t| (t.id.clone(), t.bookmark.clone()))
+        .collect();
+    snapshot.tasks = tasks.into_iter().map(TrashedTask::from).collect();
+    let root = &snapshot.tasks[0];
+    let entry = TrashEntry {
+        id: root.id.clone(),
+        parent_id: root.parent_id.clone(),
+        description: root.description.clone(),
+        tasks: members.len(),
+        deleted_at: now,
+    };
+    trash_repo::insert_entry(&tx, &entry, &serde_json::to_string(&snapshot)?, &members)?;
+
+    // Cascades to descendants, learnings, comments, links, sessions and edges
+    task_repo::delete_task(&tx, id)?;
+    for (task_id, _) in members {
+        event_repo::append(&tx, &EventBody::TaskDeleted { task_id })?;
+    }
+    tx.commit()?;
+    Ok(entry)
+}
+
+/// Bring back the subtree trashed with root `id`, in one transaction.
+///
+/// The root returns under its original parent, which must be active and
+/// leave room for the subtree within the depth limit. A parent that is itself
+/// in the trash must be restored first; one that was purged makes the root a
+/// milestone. Blocker edges to tasks that no longer exist, or that are no
+/// longer valid, are skipped and reported.
+pub fn restore(conn: &Connection, id: &TaskId) -> Result<RestoredTrash> {
+    let tx = retry::begin_immediate(conn)?;
+    let Some((_, json)) = trash_repo::get_entry(&tx, id)? else {
+        return Err(match trash_repo::containing_root(&tx, id)? {
+            Some(root_id) => OsError::TrashedWithAncestor {
+                task_id: id.clone(),
+                root_id,
+            },
+            None => OsError::NotInTrash(id.clone()),
+        });
+    };
+    let snapshot: Snapshot = serde_json::from_str(&json)?;
+    let service = TaskService::new(&tx);
+
+    // Depth of each task below the root
+    let mut relative: HashMap<&TaskId, i32> = HashMap::new();
+    for task in &snapshot.tasks {
+        let depth = task
+            .parent_id
+            .as_ref()
+            .and_then(|p| relative.get(p))
+            .map_or(0, |d| d + 1);
+        relative.insert(&task.id, depth);
+    }
+    let height = relative.values().copied().max().unwrap_or(0);
+
+    let root = &snapshot.tasks[0];
+    let mut parent_id = root.parent_id.clone();
+    let mut base_depth = 0;
+    if let Some(pid) = parent_id.clone() {
+        if let Some(parent) = task_repo::get_task(&tx, &pid)? {
+            let root_active = !(root.completed || root.cancelled || root.archived);
+            if root_active && !parent.is_active_for_work() {
+                return Err(OsError::CannotAttachChildToInactiveParent {
+                    parent_id: pid,
+                    state: format!("{:?}", parent.lifecycle_state()),
+                });
+            }
+            base_depth = task_repo::get_task_depth(&tx, &pid)? + 1;
+        } else if trash_repo::containing_root(&tx, &pid)?.is_some() {
+            return Err(OsError::ParentInTrash(pid));
+        } else {
+            parent_id = None;
+        }
+    }
+    let settings = settings_repo::load(&tx)?;
+    if base_depth + height > settings.deepest() {
+        return Err(OsError::MaxDepthExceeded {
+            max: settings.max_depth,
+        });
+    }
+
+    for (i, trashed) in snapshot.tasks.iter().enumerate() {
+        let parent = if i == 0 {
+            parent_id.clone()
+        } else {
+            trashed.parent_id.clone()
+        };
+        let mut task = trashed.to_task(parent);
+        // A repo unregistered meanwhile leaves the task without one
+        if let Some(repo_id) = &task.repo_id {
+            if repo_repo::get_repo(&tx, repo_id)?.is_none() {
+                task.repo_id = None;
+            }
+        }
+        task_repo::insert_task_row(&tx, &task)?;
+        // Revisions read before the delete must not match the restored row
+        task_repo::set_revision(&tx, &task.id, trashed.revision + 1)?;
+        if let Some(metadata) = &trashed.metadata {
+            metadata_repo::replace_metadata(&tx, &task.id, metadata)?;
+        }
+        label_repo::add_labels(&tx, &task.id, &trashed.labels)?;
+    }
+    for learning in &snapshot.learnings {
+        learning_repo::insert_learning_row(&tx, learning)?;
+    }
+    for comment in &snapshot.comments {
+        comment_repo::insert_comment_row(&tx, comment)?;
+    }
+    for link in &snapshot.links {
+        link_repo::insert_link_row(&tx, link)?;
+    }
+    for session in &snapshot.sessions {
+        session_repo::insert_session_row(&tx, session)?;
+    }
+
+    let inside = |id: &TaskId| relative.contains_key(id);
+    let mut blockers = 0;
+    let mut skipped_blockers = Vec::new();
+    // Edges inside the subtree first: the hierarchy they were valid in is unchanged
+    let (internal, external): (Vec<_>, Vec<_>) = snapshot
+        .blockers
+        .iter()
+        .partition(|e| inside(&e.task_id) && inside(&e.blocker_id));
+    for edge in internal {
+        task_repo::add_blocker(&tx, &edge.task_id, &edge.blocker_id)?;
+        blockers += 1;
+    }
+    for edge in external {
+        let outside = if inside(&edge.task_id) {
+            &edge.blocker_id
+        } else {
+            &edge.task_id
+        };
+        let valid = task_repo::task_exists(&tx, outside)?
+            && match service.validate_blocker(&edge.task_id, &edge.blocker_id) {
+                Ok(()) => true,
+                Err(OsError::InvalidBlockerRelation { .. } | OsError::BlockerCycle) => false,
+                Err(e) => return Err(e),
+            };
+        if valid {
+            task_repo::add_blocker(&tx, &edge.task_id, &edge.blocker_id)?;
+            blockers += 1;
+        } else {
+            skipped_blockers.push(edge.clone());
+        }
+    }
+
+    trash_repo::delete_entry(&tx, id)?;
+    // The bare row: an assembled context chain would not read back from the log
+    let row = task_repo::get_task(&tx, id)?.ok_or_else(|| OsError::TaskNotFound(id.clone()))?;
+    event_repo::append(&tx, &EventBody::TaskRestored { task: row })?;
+    let task = service.get(id)?;
+    tx.commit()?;
+
+    Ok(RestoredTrash {
+        task,
+        tasks: snapshot.tasks.len(),
+        blockers,
+        skipped_blockers,
+    })
+}
+
+/// Trashed subtrees, most recently deleted first.
+pub fn list(conn: &Connection) -> Result<Vec<TrashEntry>> {
+    trash_repo::list_entries(conn)
+}
+
+/// Delete every snapshot trashed more than `older_than` ago. The task branches
+/// recorded in them are deleted too, each in its task's registered repo, or in
+/// `vcs` for tasks without one (best effort, as the snapshots are already gone).
+pub fn purge(
+    conn: &Connection,
+    older_than: Duration,
+    vcs: Option<&dyn VcsBackend>,
+) -> Result<PurgedTrash> {
+    let cutoff = Utc::now() - older_than;
+    let tx = retry::begin_immediate(conn)?;
+    let mut purged = Vec::new();
+    let mut branches = Vec::new();
+    for entry in trash_repo::list_entries(&tx)? {
+        if entry.deleted_at > cutoff {
+            continue;
+        }
+        if let Some((_, json)) = trash_repo::get_entry(&tx, &entry.id)? {
+            let snapshot: Snapshot = serde_json::from_str(&json)?;
+            for task in snapshot.tasks {
+                if let Some(bookmark) = task.bookmark {
+                    branches.push((entry.id.clone(), task.repo_id, bookmark));
+                }
+            }
+        }
+        trash_repo::delete_entry(&tx, &entry.id)?;
+        event_repo::append(
+            &tx,
+            &EventBody::TaskPurged {
+                task_id: entry.id.clone(),
+            },
+        )?;
+        purged.push(entry);
+    }
+    tx.commit()?;
+
+    // Each branch is deleted in its task's repo; tasks without one use `vcs`
+    let mut repo_vcs: HashMap<RepoId, Option<Box<dyn VcsBackend>>> = HashMap::new();
+    let mut deleted_branches = Vec::new();
+    for (task_id, repo_id, bookmark) in branches {
+        let vcs = match repo_id {
+            None => vcs,
+            Some(repo_id) => repo_vcs
+                .entry(repo_id)
+                .or_insert_with_key(|repo_id| match repos::open_backend(conn, repo_id) {
+                    Ok(backend) => Some(backend),
+                    Err(e) => {
+                        eprintln!("warn: failed to open repo {}: {}", repo_id, e);
+                        None
+                    }
+                })
+                .as_deref(),
+        };
+        if let Some(vcs) = vcs {
+            if let Err(e) = vcs.delete_bookmark(&bookmark) {
+                eprintln!("warn: failed to delete bookmark {}: {}", bookmark, e);
+            } else {
+                let event = EventBody::RefDeleted {
+                    task_id,
+                    ref_name: bookmark.clone(),
+                };
+                if let Err(e) = event_repo::append(conn, &event) {
+                    eprintln!("warn: failed to record deletion of {}: {}", bookmark, e);
+                }
+                deleted_branches.push(bookmark);
+            }
+        }
+    }
+
+    Ok(PurgedTrash {
+        purged,
+        deleted_branches,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::schema;
+    use crate::testutil::{GitTestRepo, TestRepo};
+    use crate::types::CreateTaskInput;
+    use crate::vcs::GixBackend;
+
+    fn setup_db() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        schema::init_schema(&conn).unwrap();
+        conn
+    }
+
+    fn create(service: &TaskService, description: &str, parent: Option<&TaskId>) -> TaskId {
+        service
+            .create(&CreateTaskInput {
+                description: description.to_string(),
+                context: Some(format!("{description} context")),
+                parent_id: parent.cloned(),
+                ..Default::default()
+            })
+            .unwrap()
+            .id
+    }
+
+    #[test]
+    fn test_delete_and_restore_round_trip() {
+        let conn = setup_db();
+        let service = TaskService::new(&conn);
+        let milestone = create(&service, "Milestone", None);
+        let design = create(&service, "Design", Some(&milestone));
+        let build = create(&service, "Build", Some(&milestone));
+        let outside = create(&service, "Outside", None);
+        let dependent = create(&service, "Dependent", None);
+        service.add_blocker(&build, &design).unwrap();
+        service.add_blocker(&build, &outside).unwrap();
+        service.add_blocker(&dependent, &milestone).unwrap();
+        service.add_labels(&design, &["api".to_string()]).unwrap();
+        service
+            .add_comment(&design, Some("agent"), "Sketched the schema")
+            .unwrap();
+        learning_repo::add_learning(&conn, &design, "Use UUIDs", None).unwrap();
+        let revision = service.get(&design).unwrap().revision;
+
+        let entry = service.delete(&milestone).unwrap();
+        assert_eq!(entry.tasks, 3);
+        let deleted: Vec<TaskId> = event_repo::list_events(&conn, &Default::default())
+            .unwrap()
+            .into_iter()
+            .filter_map(|e| match e.body {
+                EventBody::TaskDeleted { task_id } => Some(task_id),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(deleted.len(), 3, "one event per task in the subtree");
+        assert_eq!(deleted[0], milestone);
+        assert!(deleted.contains(&design) && deleted.contains(&build));
+        assert!(task_repo::get_task(&conn, &design).unwrap().is_none());
+        assert!(service.get(&dependent).unwrap().blocked_by.is_empty());
+        assert_eq!(list(&conn).unwrap(), vec![entry]);
+        assert!(matches!(
+            restore(&conn, &design),
+            Err(OsError::TrashedWithAncestor { .. })
+        ));
+
+        let restored = restore(&conn, &milestone).unwrap();
+        assert_eq!(restored.tasks, 3);
+        assert_eq!(restored.blockers, 3);
+        assert!(restored.skipped_blockers.is_empty());
+        let design_task = service.get(&design).unwrap();
+        assert_eq!(design_task.context, "Design context");
+        assert_eq!(design_task.labels, vec!["api"]);
+        assert_eq!(design_task.revision, revision + 1);
+        assert_eq!(service.comments(&design, None).unwrap().len(), 1);
+        assert_eq!(
+            learning_repo::list_learnings(&conn, &design).unwrap().len(),
+            1
+        );
+        assert_eq!(
+            service.get(&build).unwrap().blocked_by.len(),
+            2,
+            "internal and outgoing edges"
+        );
+        assert_eq!(service.get(&dependent).unwrap().blocked_by, vec![milestone]);
+        assert!(list(&conn).unwrap().is_empty());
+        assert!(matches!(
+            restore(&conn, &design),
+            Err(OsError::NotInTrash(_))
+        ));
+        let events = event_repo::list_events(&conn, &Default::default()).unwrap();
+        assert!(matches!(
+            events.last().unwrap().body,
+            EventBody::TaskRestored { .. }
+        ));
+    }
+
+    #[test]
+    fn test_restore_skips_gone_edges_and_waits_for_trashed_parent() {
+        let conn = setup_db();
+        let service = TaskService::new(&conn);
+        let milestone = create(&service, "Milestone", None);
+        let task = create(&service, "Task", Some(&milestone));
+        let blocker = create(&service, "Blocker", None);
+        service.add_blocker(&task, &blocker).unwrap();
+
+        service.delete(&task).unwrap();
+        service.delete(&milestone).unwrap();
+        service.delete(&blocker).unwrap();
+        assert!(matches!(
+            restore(&conn, &task),
+            Err(OsError::ParentInTrash(_))
+        ));
+
+        restore(&conn, &milestone).unwrap();
+        let restored = restore(&conn, &task).unwrap();
+        assert_eq!(restored.task.parent_id.as_ref(), Some(&milestone));
+        assert_eq!(
+            restored.skipped_blockers,
+            vec![BlockerEdge {
+                task_id: task.clone(),
+                blocker_id: blocker.clone(),
+            }]
+        );
+
+        // Purging keeps entries newer than the cutoff
+        assert!(purge(&conn, Duration::days(1), None)
+            .unwrap()
+            .purged
+            .is_empty());
+        let purged = purge(&conn, Duration::zero(), None).unwrap();
+        assert_eq!(purged.purged.len(), 1);
+        assert!(matches!(
+            restore(&conn, &blocker),
+            Err(OsError::NotInTrash(_))
+        ));
+    }
+
+    #[test]
+    fn test_delete_refused_while_a_worktree_exists() {
+        let conn = setup_db();
+        let service = TaskService::new(&conn);
+        let milestone = create(&service, "Milestone", None);
+        let task = create(&service, "Task", Some(&milestone));
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("task");
+        std::fs::create_dir(&path).unwrap();
+        task_repo::set_worktree(&conn, &task, path.to_str()).unwrap();
+
+        match move_to_trash(&conn, &milestone) {
+            Err(OsError::WorktreeInUse { task_id, .. }) => assert_eq!(task_id, task),
+            other => panic!("expected WorktreeInUse, got {other:?}"),
+        }
+        assert!(task_repo::get_task(&conn, &milestone).unwrap().is_some());
+
+        // Once the worktree is gone the subtree can be trashed
+        std::fs::remove_dir(&path).unwrap();
+        move_to_trash(&conn, &milestone).unwrap();
+    }
+
+    #[test]
+    fn test_purge_deletes_branches_in_each_tasks_repo() {
+        let (here, api) = (GitTestRepo::new().unwrap(), GitTestRepo::new().unwrap());
+        let (here_vcs, api_vcs) = (
+            GixBackend::open(here.path()).unwrap(),
+            GixBackend::open(api.path()).unwrap(),
+        );
+        for (repo, vcs) in [(&here, &here_vcs), (&api, &api_vcs)] {
+            repo.commit("init").unwrap();
+            vcs.create_bookmark("task/shared", None).unwrap();
+        }
+        here_vcs.create_bookmark("task/local", None).unwrap();
+
+        let conn = setup_db();
+        let registered = repos::register(&conn, api.path(), Some("api")).unwrap();
+        let service = TaskService::new(&conn);
+        let milestone = create(&service, "Milestone", None);
+        let in_api = service
+            .create(&CreateTaskInput {
+                description: "In api".to_string(),
+                parent_id: Some(milestone.clone()),
+                repo_id: Some(registered.id.clone()),
+                ..Default::default()
+            })
+            .unwrap()
+            .id;
+        task_repo::set_bookmark(&conn, &milestone, "task/local").unwrap();
+        task_repo::set_bookmark(&conn, &in_api, "task/shared").unwrap();
+        move_to_trash(&conn, &milestone).unwrap();
+
+        let purged = purge(&conn, Duration::zero(), Some(&here_vcs)).unwrap();
+        let mut deleted = purged.deleted_branches;
+        deleted.sort();
+        assert_eq!(deleted, ["task/local", "task/shared"]);
+        // The same-named branch of the working directory's repo is untouched
+        assert_eq!(
+            here_vcs.list_bookmarks(Some("task/")).unwrap(),
+            ["task/shared"]
+        );
+        assert!(api_vcs.list_bookmarks(Some("task/")).unwrap().is_empty());
+    }
+}
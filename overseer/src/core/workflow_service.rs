@@ -1,43 +1,234 @@
-use rusqlite::Connection;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::path::Path;
+use std::rc::Rc;
 
+use rusqlite::{Connection, ErrorCode, Transaction, TransactionBehavior};
+
+use crate::core::intent_journal::{Intent, IntentJournal, IntentOp, VcsStep};
+use crate::core::repos;
+use crate::core::task_service::DEFAULT_CLAIM_TTL_MINUTES;
 use crate::core::TaskService;
-use crate::db::task_repo;
+use crate::db::event_repo::{self, EventBody};
+use crate::db::{retry, settings_repo, task_repo};
 use crate::error::{NotReadyReason, OsError, Result};
-use crate::id::TaskId;
+use crate::id::{RepoId, TaskId};
 use crate::types::Task;
-use crate::vcs::backend::{VcsBackend, VcsError};
+use crate::vcs::backend::{IntegrationStrategy, VcsBackend, VcsError};
 
 /// Coordinates task state transitions with VCS operations.
 ///
-/// **Transaction semantics**: VCS-first, then DB.
-/// - VCS operations run first and can fail the entire operation
-/// - DB operations run after VCS succeeds
-/// - This prevents partial state on VCS failure
+/// **Transaction semantics**: one write transaction per operation.
+/// - `start`/`complete` hold an IMMEDIATE transaction for their whole run, so all
+///   DB mutations (including bubbling to ancestors) commit or roll back together
+/// - VCS steps run inside that window and are recorded in the intent journal
+/// - The journal is cleared after commit, or after undoing the steps when the
+///   operation fails; if the process dies first, `recover()` resumes an
+///   interrupted complete or undoes an interrupted start
 ///
 /// VCS is mandatory for workflow operations (start/complete).
 /// CRUD operations don't require VCS.
+///
+/// **Integration**: start records the branch that was checked out; complete
+/// commits on the task branch, integrates it into that branch (strategy from
+/// `os db config --integration` unless overridden) and only then deletes it.
+/// Conflicts abort the integration and fail with `IntegrationConflict`, leaving
+/// the task in progress on its branch. Tasks started on a detached HEAD keep
+/// their branch.
+///
+/// **Stacking**: branches follow the hierarchy. Starting a task first gives
+/// each ancestor in the same repo a branch if it lacks one (the topmost from
+/// the checked-out branch, the rest from their parent's), then branches the
+/// task from its parent's. Children integrate into their parent's branch, an
+/// auto-completed parent into its own parent's, and the milestone into the
+/// original base, so `base_rev..head_rev` shows one level's changes.
+///
+/// **Worktrees**: with `with_worktree(true)`, start checks a new task's branch
+/// out in a linked worktree instead of the shared working copy, so several
+/// tasks can be in progress at once. Complete commits in the worktree and
+/// integrates by moving the start branch, leaving the main checkout alone
+/// unless it has that branch checked out; the worktree is removed before its
+/// branch is deleted. Tasks in the shared working copy switch it back to their
+/// start branch once integrated.
+///
+/// **Repos**: VCS steps for a task run in its registered repo (see
+/// `core::repos`), opened on first use; tasks without one use the default
+/// backend, normally the working directory's repository. A milestone's branch
+/// cleanup visits every repo its subtree touched.
+///
+/// **Claims**: start/complete are refused while another agent holds a live
+/// claim on the task or an ancestor. Starting as an agent claims the task
+/// unless that agent already holds a covering claim.
+///
+/// **Sessions**: start opens a work session on the started task (unless one
+/// is running); completing or cancelling a task closes it.
+///
+/// **Revisions**: with an expected revision, start/complete fail with
+/// `RevisionConflict` if the requested task changed since the caller read it.
+/// The check runs inside the write transaction, before the idempotent paths.
 pub struct TaskWorkflowService<'a> {
     task_service: TaskService<'a>,
-    vcs: Box<dyn VcsBackend>,
+    /// Backend for tasks without a repo
+    vcs: Option<Rc<dyn VcsBackend>>,
+    repo_vcs: RefCell<HashMap<RepoId, Rc<dyn VcsBackend>>>,
     conn: &'a Connection,
+    journal: IntentJournal,
+    agent: Option<String>,
+    expected_revision: Option<i64>,
+    /// None: the database's `integration` setting
+    integration: Option<IntegrationStrategy>,
+    worktree: bool,
+}
+
+/// Branches of tasks started on a detached HEAD were never integrated, so
+/// cleanup leaves them alone; cancelled work is dropped either way.
+fn keeps_branch(task: &Task) -> bool {
+    !task.cancelled && task.start_branch.is_none()
+}
+
+/// What `recover()` did with a leftover intent.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RecoveryAction {
+    /// The DB transaction had committed; only the journal was left behind
+    AlreadyApplied,
+    /// An interrupted complete was finished using the recorded VCS steps
+    Resumed,
+    /// An interrupted start had its VCS steps undone
+    RolledBack,
+    /// The task no longer exists; nothing to resume
+    Discarded,
+}
+
+#[derive(Debug, Clone)]
+pub struct Recovered {
+    pub intent: Intent,
+    pub action: RecoveryAction,
 }
 
 impl<'a> TaskWorkflowService<'a> {
+    #[allow(dead_code)] // Used in tests; the CLI passes an optional default
     pub fn new(conn: &'a Connection, vcs: Box<dyn VcsBackend>) -> Self {
+        Self::with_optional_vcs(conn, Some(vcs))
+    }
+
+    /// Without a default backend, only tasks in a registered repo can be
+    /// started or completed; the others fail with `NotARepository`.
+    pub fn with_optional_vcs(conn: &'a Connection, vcs: Option<Box<dyn VcsBackend>>) -> Self {
         Self {
             task_service: TaskService::new(conn),
-            vcs,
+            vcs: vcs.map(Rc::from),
+            repo_vcs: RefCell::new(HashMap::new()),
             conn,
+            journal: IntentJournal::for_connection(conn),
+            agent: None,
+            expected_revision: None,
+            integration: None,
+            worktree: false,
         }
     }
 
+    /// Act as `agent` for claim checks (None: any live claim blocks)
+    pub fn with_agent(mut self, agent: Option<String>) -> Self {
+        self.agent = agent;
+        self
+    }
+
+    /// Require the requested task to be at this revision (None: no check)
+    pub fn with_expected_revision(mut self, revision: Option<i64>) -> Self {
+        self.expected_revision = revision;
+        self
+    }
+
+    /// Integrate completed branches with `strategy` (None: the database setting)
+    pub fn with_integration(mut self, strategy: Option<IntegrationStrategy>) -> Self {
+        self.integration = strategy;
+        self
+    }
+
+    /// Start new task branches in their own worktree
+    pub fn with_worktree(mut self, worktree: bool) -> Self {
+        self.worktree = worktree;
+        self
+    }
+
+    /// Use `vcs` for tasks in `repo_id` instead of opening the repo's path
+    #[allow(dead_code)] // Used in tests
+    pub fn with_repo_vcs(self, repo_id: RepoId, vcs: Box<dyn VcsBackend>) -> Self {
+        self.repo_vcs.borrow_mut().insert(repo_id, Rc::from(vcs));
+        self
+    }
+
+    /// Backend for VCS steps on `task`
+    fn vcs_for(&self, task: &Task) -> Result<Rc<dyn VcsBackend>> {
+        let Some(repo_id) = &task.repo_id else {
+            return self.vcs.clone().ok_or(OsError::NotARepository);
+        };
+        if let Some(vcs) = self.repo_vcs.borrow().get(repo_id) {
+            return Ok(vcs.clone());
+        }
+        let vcs: Rc<dyn VcsBackend> = Rc::from(repos::open_backend(self.conn, repo_id)?);
+        self.repo_vcs
+            .borrow_mut()
+            .insert(repo_id.clone(), vcs.clone());
+        Ok(vcs)
+    }
+
     /// Access the underlying TaskService (used primarily in tests)
     #[allow(dead_code)]
     pub fn task_service(&self) -> &TaskService<'a> {
         &self.task_service
     }
 
+    /// Take the write lock up front so no other writer (or recovery) interleaves.
+    /// Retried with backoff, since nothing has run yet when it fails.
+    fn begin(&self) -> Result<Transaction<'a>> {
+        retry::begin_immediate(self.conn)
+    }
+
+    #[allow(dead_code)] // Used in tests; the CLI always follows blockers
     pub fn start(&self, id: &TaskId) -> Result<Task> {
+        self.start_resolved(id, id)
+    }
+
+    /// Start `target`, on behalf of a request for `requested` (an ancestor or
+    /// blocker-resolved stand-in when following blockers).
+    fn start_resolved(&self, requested: &TaskId, target: &TaskId) -> Result<Task> {
+        let tx = self.begin()?;
+        let mut intent = Intent::new(target.clone(), IntentOp::Start);
+
+        let started = self
+            .task_service
+            .check_revision(requested, self.expected_revision)
+            .and_then(|()| self.start_inner(&mut intent, target));
+        match started {
+            Ok(task) => {
+                self.finish(tx, &intent, Self::undo_start)?;
+                Ok(task)
+            }
+            Err(e) => {
+                // Nothing will be committed; put VCS back where it was
+                self.undo_start(&intent);
+                self.journal.clear()?;
+                drop(tx);
+                Err(e)
+            }
+        }
+    }
+
+    /// Delete the journal, then commit. Both happen under the write lock: once
+    /// it is released, the journal on disk may already belong to another
+    /// process. A failed commit leaves no journal to recover from, so the
+    /// recorded steps are undone right away.
+    fn finish(&self, tx: Transaction<'_>, intent: &Intent, undo: fn(&Self, &Intent)) -> Result<()> {
+        self.journal.clear()?;
+        if let Err(e) = tx.commit() {
+            undo(self, intent);
+            return Err(e.into());
+        }
+        Ok(())
+    }
+
+    fn start_inner(&self, intent: &mut Intent, id: &TaskId) -> Result<Task> {
         let task = self.task_service.get(id)?;
 
         // Guard: cannot start non-active tasks (cancelled, completed, archived)
@@ -56,12 +247,25 @@ impl<'a> TaskWorkflowService<'a> {
             };
         }
 
+        // Another agent's claim wins even over the idempotent path below
+        self.task_service.check_claim(id, self.agent.as_deref())?;
+        let vcs = self.vcs_for(&task)?;
+
         // Idempotent: already started with VCS state
-        if task.started_at.is_some() && task.bookmark.is_some() {
-            // Just checkout the existing bookmark
-            if let Some(ref bookmark) = task.bookmark {
-                self.vcs.checkout(bookmark)?;
+        if let (Some(_), Some(bookmark)) = (task.started_at, &task.bookmark) {
+            match task.worktree {
+                // Recreate a worktree deleted by hand
+                Some(ref path) if !Path::new(path).exists() => {
+                    vcs.remove_worktree(path)?;
+                    vcs.add_worktree(bookmark, path)?;
+                }
+                Some(_) => {}
+                // Just checkout the existing bookmark
+                None => vcs.checkout(bookmark)?,
             }
+            self.claim_for_agent(id)?;
+            // Starting again after a pause resumes the clock
+            self.task_service.begin_session(id, self.agent.as_deref())?;
             return self.task_service.get(id);
         }
 
@@ -72,36 +276,281 @@ impl<'a> TaskWorkflowService<'a> {
             .bookmark
             .clone()
             .unwrap_or_else(|| format!("task/{}", id));
+        let previous = vcs.current_commit_id().ok();
+        let parent_branch = self.ensure_parent_branch(intent, &*vcs, &task)?;
+        // The branch to integrate into on completion (none on a detached HEAD)
+        let start_branch = match (task.start_branch.clone(), &parent_branch) {
+            (Some(branch), _) => Some(branch),
+            (None, Some(parent)) => Some(parent.clone()),
+            (None, None) => vcs.current_branch()?.filter(|b| *b != bookmark),
+        };
 
-        // 1. Ensure bookmark exists (idempotent)
-        match self.vcs.create_bookmark(&bookmark, None) {
-            Ok(()) | Err(VcsError::BookmarkExists(_)) => {}
+        // 1. Ensure bookmark exists (idempotent), stacked on the parent's branch
+        let created = match vcs.create_bookmark(&bookmark, parent_branch.as_deref()) {
+            Ok(()) => true,
+            Err(VcsError::BookmarkExists(_)) => false,
             Err(e) => return Err(e.into()),
+        };
+        if created {
+            self.journal.record(
+                intent,
+                VcsStep::BookmarkCreated {
+                    name: bookmark.clone(),
+                },
+            )?;
         }
 
-        // 2. Checkout (can fail on DirtyWorkingCopy)
-        self.vcs.checkout(&bookmark)?;
+        // 2. Checkout (can fail on DirtyWorkingCopy), or a worktree of its own
+        let worktree = if self.worktree {
+            let path = self.worktree_path(&*vcs, id)?;
+            vcs.add_worktree(&bookmark, &path)?;
+            self.journal
+                .record(intent, VcsStep::WorktreeAdded { path: path.clone() })?;
+            Some(path)
+        } else {
+            vcs.checkout(&bookmark)?;
+            self.journal.record(
+                intent,
+                VcsStep::CheckedOut {
+                    target: bookmark.clone(),
+                    previous,
+                },
+            )?;
+            None
+        };
 
         // 3. Record start commit
-        let sha = self.vcs.current_commit_id()?;
+        let sha = match worktree {
+            Some(ref path) => vcs.open_worktree(path)?.current_commit_id()?,
+            None => vcs.current_commit_id()?,
+        };
 
         // 4. DB updates (after VCS succeeds)
         task_repo::set_bookmark(self.conn, id, &bookmark)?;
         task_repo::set_start_commit(self.conn, id, &sha)?;
+        if let Some(ref path) = worktree {
+            task_repo::set_worktree(self.conn, id, Some(path))?;
+        }
+        if let Some(ref branch) = start_branch {
+            task_repo::set_start_branch(self.conn, id, branch)?;
+        }
+        if task.base_rev.is_none() {
+            task_repo::set_base_rev(self.conn, id, &vcs.resolve_commit(&bookmark)?)?;
+        }
+        if created {
+            event_repo::append(
+                self.conn,
+                &EventBody::RefCreated {
+                    task_id: id.clone(),
+                    ref_name: bookmark.clone(),
+                    target: sha,
+                },
+            )?;
+        }
 
         if task.started_at.is_none() {
             self.task_service.start(id)?;
         }
 
-        // 5. Bubble started_at to ancestors (but not VCS state)
+        // 5. Bubble started_at to ancestors (their branches exist already)
         self.bubble_start_to_ancestors(id)?;
 
+        self.claim_for_agent(id)?;
+        self.task_service.begin_session(id, self.agent.as_deref())?;
         self.task_service.get(id)
     }
 
+    /// Where `id`'s worktree goes: under the configured directory (relative to
+    /// the repository root), else under `<repo>.worktrees` next to the root.
+    fn worktree_path(&self, vcs: &dyn VcsBackend, id: &TaskId) -> Result<String> {
+        let root = Path::new(vcs.root());
+        let dir = match settings_repo::load(self.conn)?.worktree_dir {
+            Some(dir) => root.join(dir),
+            None => {
+                let name = root
+                    .file_name()
+                    .map(|n| n.to_string_lossy().to_string())
+                    .unwrap_or_default();
+                root.with_file_name(format!("{name}.worktrees"))
+            }
+        };
+        Ok(dir.join(id.as_str()).to_string_lossy().to_string())
+    }
+
+    /// Branch `task` stacks on: its parent's, created first (along with any
+    /// branchless ancestors above it in the same repo) if needed. None for
+    /// roots and for tasks whose parent is in another repo.
+    fn ensure_parent_branch(
+        &self,
+        intent: &mut Intent,
+        vcs: &dyn VcsBackend,
+        task: &Task,
+    ) -> Result<Option<String>> {
+        // Same-repo ancestors up to the nearest one with a branch, nearest first
+        let mut chain = Vec::new();
+        let mut next = task.parent_id.clone();
+        while let Some(parent_id) = next {
+            let parent = task_repo::get_task(self.conn, &parent_id)?
+                .ok_or_else(|| OsError::TaskNotFound(parent_id.clone()))?;
+            if parent.repo_id != task.repo_id {
+                break;
+            }
+            next = match parent.bookmark {
+                Some(_) => None,
+                None => parent.parent_id.clone(),
+            };
+            chain.push(parent);
+        }
+
+        // Top of the stack first, each from the branch above it
+        let mut base: Option<String> = None;
+        for ancestor in chain.iter().rev() {
+            if let Some(ref bookmark) = ancestor.bookmark {
+                base = Some(bookmark.clone());
+                continue;
+            }
+            let name = format!("task/{}", ancestor.id);
+            let start_branch = match base {
+                Some(ref branch) => Some(branch.clone()),
+                None => vcs.current_branch()?,
+            };
+            let base_rev = vcs.resolve_commit(base.as_deref().unwrap_or("HEAD"))?;
+            match vcs.create_bookmark(&name, base.as_deref()) {
+                Ok(()) => {
+                    self.journal
+                        .record(intent, VcsStep::BookmarkCreated { name: name.clone() })?;
+                    event_repo::append(
+                        self.conn,
+                        &EventBody::RefCreated {
+                            task_id: ancestor.id.clone(),
+                            ref_name: name.clone(),
+                            target: base_rev.clone(),
+                        },
+                    )?;
+                }
+                Err(VcsError::BookmarkExists(_)) => {}
+                Err(e) => return Err(e.into()),
+            }
+            task_repo::set_bookmark(self.conn, &ancestor.id, &name)?;
+            task_repo::set_base_rev(self.conn, &ancestor.id, &base_rev)?;
+            if let Some(ref branch) = start_branch {
+                task_repo::set_start_branch(self.conn, &ancestor.id, branch)?;
+            }
+            base = Some(name);
+        }
+        Ok(base)
+    }
+
+    /// Claim a started task for the acting agent unless it already holds a
+    /// claim covering it.
+    fn claim_for_agent(&self, id: &TaskId) -> Result<()> {
+        let Some(agent) = self.agent.as_deref() else {
+            return Ok(());
+        };
+        if !self.task_service.holds_claim(id, agent)? {
+            self.task_service.acquire_claim(
+                id,
+                agent,
+                chrono::Duration::minutes(DEFAULT_CLAIM_TTL_MINUTES),
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Best-effort reversal of the VCS steps of a start, newest first.
+    fn undo_start(&self, intent: &Intent) {
+        if intent.steps.is_empty() {
+            return;
+        }
+        let vcs = match task_repo::get_task(self.conn, &intent.task_id)
+            .and_then(|task| task.ok_or_else(|| OsError::TaskNotFound(intent.task_id.clone())))
+            .and_then(|task| self.vcs_for(&task))
+        {
+            Ok(vcs) => vcs,
+            Err(e) => {
+                eprintln!("warn: cannot undo start of {}: {}", intent.task_id, e);
+                return;
+            }
+        };
+        for step in intent.steps.iter().rev() {
+            match step {
+                VcsStep::CheckedOut {
+                    previous: Some(previous),
+                    ..
+                } => {
+                    if let Err(e) = vcs.checkout(previous) {
+                        eprintln!("warn: failed to checkout {}: {}", previous, e);
+                    }
+                }
+                VcsStep::WorktreeAdded { path } => {
+                    if let Err(e) = vcs.remove_worktree(path) {
+                        eprintln!("warn: failed to remove worktree {}: {}", path, e);
+                    }
+                }
+                VcsStep::BookmarkCreated { name } => {
+                    if let Err(e) = vcs.delete_bookmark(name) {
+                        eprintln!("warn: failed to delete bookmark {}: {}", name, e);
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// Best-effort reversal of a failed complete: deleted branches are recreated
+    /// at their old tip (with the worktree the task still records), so the task
+    /// can be completed again. Commits and integrations stay; a retry finds
+    /// nothing new to commit and the work already in the start branch.
+    fn undo_complete(&self, intent: &Intent) {
+        for step in intent.steps.iter().rev() {
+            let VcsStep::BookmarkDeleted {
+                task_id,
+                name,
+                rev: Some(rev),
+            } = step
+            else {
+                continue;
+            };
+            let restored = task_repo::get_task(self.conn, task_id)
+                .and_then(|task| task.ok_or_else(|| OsError::TaskNotFound(task_id.clone())))
+                .and_then(|task| {
+                    let vcs = self.vcs_for(&task)?;
+                    vcs.create_bookmark(name, Some(rev))?;
+                    if let Some(ref path) = task.worktree {
+                        if vcs.open_worktree(path).is_err() {
+                            vcs.add_worktree(name, path)?;
+                        }
+                    }
+                    Ok(())
+                });
+            if let Err(e) = restored {
+                eprintln!("warn: failed to restore bookmark {}: {}", name, e);
+            }
+        }
+    }
+
     /// Validate that a task can be started.
     /// Returns error if task is not the next ready task in its subtree.
     fn validate_start_target(&self, id: &TaskId, task: &Task) -> Result<()> {
+        // Deferred work is not startable yet, whatever its blockers
+        if let Some(until) = self.task_service.deferred_until(task)? {
+            let next_ready = self.task_service.next_ready(None)?;
+            return Err(OsError::NotNextReady {
+                message: format!(
+                    "Cannot start '{}' - deferred until {}. {}",
+                    task.description,
+                    until.format("%Y-%m-%d %H:%M:%S UTC"),
+                    next_ready
+                        .as_ref()
+                        .map(|nr| format!("Start '{}' instead.", nr))
+                        .unwrap_or_else(|| "No ready tasks available.".to_string())
+                ),
+                requested: id.clone(),
+                next_ready,
+                reason: NotReadyReason::Deferred { until },
+            });
+        }
+
         // Check if blocked first (more specific error)
         if self.task_service.is_effectively_blocked(task)? {
             let blockers: Vec<TaskId> = task
@@ -168,7 +617,7 @@ impl<'a> TaskWorkflowService<'a> {
     }
 
     /// Bubble started_at to all ancestors that don't have it set.
-    /// Only sets started_at, not VCS bookmark/start_commit.
+    /// Their branches come from `ensure_parent_branch`; start_commit stays unset.
     fn bubble_start_to_ancestors(&self, id: &TaskId) -> Result<()> {
         let mut current_id = id.clone();
 
@@ -200,8 +649,13 @@ impl<'a> TaskWorkflowService<'a> {
     /// follows blockers until finding a startable task.
     /// Cascades down to deepest incomplete leaf.
     pub fn start_follow_blockers(&self, root: &TaskId) -> Result<Task> {
+        // Nothing under a deferred task is startable; report the deferral itself
+        let requested = self.task_service.get(root)?;
+        if self.task_service.deferred_until(&requested)?.is_some() {
+            return self.start_resolved(root, root);
+        }
         let target = self.task_service.resolve_start_target(root)?;
-        self.start(&target)
+        self.start_resolved(root, &target)
     }
 
     /// Convenience method for completing without learnings.
@@ -213,12 +667,46 @@ impl<'a> TaskWorkflowService<'a> {
     /// Complete a task with optional learnings.
     /// Learnings are added to the task and bubbled to immediate parent.
     ///
-    /// VCS-first ordering: commit changes before updating DB state.
+    /// VCS-first ordering: commit changes before updating DB state. All DB
+    /// updates, including auto-completed ancestors, land in one transaction.
     pub fn complete_with_learnings(
         &self,
         id: &TaskId,
         result: Option<&str>,
         learnings: &[String],
+    ) -> Result<Task> {
+        let mut intent = Intent::new(
+            id.clone(),
+            IntentOp::Complete {
+                result: result.map(str::to_string),
+                learnings: learnings.to_vec(),
+            },
+        );
+
+        let tx = self.begin()?;
+        self.task_service
+            .check_revision(id, self.expected_revision)?;
+        // On error the transaction rolls back. Only a crash may leave the journal
+        // behind: a retry must start over, not replay steps that no longer apply
+        let task = match self.complete_inner(&mut intent, id, result, learnings) {
+            Ok(task) => task,
+            Err(e) => {
+                self.undo_complete(&intent);
+                self.journal.clear()?;
+                drop(tx);
+                return Err(e);
+            }
+        };
+        self.finish(tx, &intent, Self::undo_complete)?;
+        Ok(task)
+    }
+
+    fn complete_inner(
+        &self,
+        intent: &mut Intent,
+        id: &TaskId,
+        result: Option<&str>,
+        learnings: &[String],
     ) -> Result<Task> {
         let task = self.task_service.get(id)?;
 
@@ -235,20 +723,26 @@ impl<'a> TaskWorkflowService<'a> {
             return Ok(task);
         }
 
-        // Auto-detect milestone (depth 0)
-        if task.depth == Some(0) {
-            return self.complete_milestone_with_learnings(id, result, learnings);
+        self.task_service.check_claim(id, self.agent.as_deref())?;
+
+        // Auto-detect milestone (root of its hierarchy)
+        if task.is_root() {
+            return self.complete_milestone(intent, id, result, learnings);
         }
+        // Checked again by the DB update, but integration must not run first
+        if task_repo::has_pending_children(self.conn, id)? {
+            return Err(OsError::PendingChildren);
+        }
+        let vcs = self.vcs_for(&task)?;
 
         // 1. VCS first - commit (NothingToCommit is OK)
         let msg = format!("Complete: {}\n\n{}", task.description, result.unwrap_or(""));
-        let commit_sha = match self.vcs.commit(&msg) {
-            Ok(commit) => Some(commit.id),
-            Err(VcsError::NothingToCommit) => None,
-            Err(e) => return Err(e.into()),
-        };
+        let commit_sha = self.commit(&*vcs, intent, &task, &msg)?;
+
+        // 2. Bring the task branch into the branch it was started from
+        let integrated = self.integrate(&*vcs, intent, &task, &msg)?;
 
-        // 2. DB updates (after VCS succeeds)
+        // 3. DB updates (after VCS succeeds)
         let completed_task = self.task_service.complete_with_learnings_and_commit_sha(
             id,
             result,
@@ -256,45 +750,247 @@ impl<'a> TaskWorkflowService<'a> {
             commit_sha.as_deref(),
         )?;
 
-        // 3. Best-effort cleanup: checkout safe target then delete bookmark/branch
-        // Unified stacking semantics for git backend
-        // Checkout first solves git's "cannot delete checked-out branch" error
-        if let Some(ref bookmark) = task.bookmark {
-            // Find checkout target: prefer start_commit, fallback to current HEAD
-            let checkout_target = task
-                .start_commit
-                .clone()
-                .or_else(|| self.vcs.current_commit_id().ok());
-
-            if let Some(ref target) = checkout_target {
-                if let Err(e) = self.vcs.checkout(target) {
-                    eprintln!(
-                        "warn: failed to checkout {}: {} - skipping branch cleanup",
-                        target, e
-                    );
-                } else if let Err(e) = self.vcs.delete_bookmark(bookmark) {
-                    eprintln!("warn: failed to delete bookmark {}: {}", bookmark, e);
-                } else {
-                    // Clear bookmark field in DB after successful VCS deletion
-                    let _ = task_repo::clear_bookmark(self.conn, id);
-                }
-            } else {
+        // 4. Best-effort cleanup
+        self.drop_integrated_branch(&*vcs, intent, &task, integrated)?;
+
+        // Bubble up: auto-complete parents if all children done and unblocked
+        self.bubble_up_completion(intent, id)?;
+
+        Ok(completed_task)
+    }
+
+    /// Delete the branch of a completed task once it was integrated: the start
+    /// branch is checked out and contains the work. Unintegrated branches stay.
+    fn drop_integrated_branch(
+        &self,
+        vcs: &dyn VcsBackend,
+        intent: &mut Intent,
+        task: &Task,
+        integrated: bool,
+    ) -> Result<()> {
+        let Some(ref bookmark) = task.bookmark else {
+            return Ok(());
+        };
+        if !integrated {
+            eprintln!(
+                "warn: no start branch recorded for {} - keeping {} checked out",
+                task.id, bookmark
+            );
+            return Ok(());
+        }
+        // Integration only moved the start branch: step the shared working copy
+        // off the task branch so it can be deleted
+        let on_branch = vcs.current_branch().ok().flatten().as_ref() == Some(bookmark);
+        if let (None, true, Some(onto)) = (&task.worktree, on_branch, &task.start_branch) {
+            if let Err(e) = vcs.checkout(onto) {
                 eprintln!(
-                    "warn: no checkout target available - skipping branch cleanup for {}",
-                    bookmark
+                    "warn: failed to checkout {}: {} - keeping {}",
+                    onto, e, bookmark
                 );
+                return Ok(());
             }
         }
+        self.remove_task_worktree(vcs, task);
+        if let Err(e) = self.delete_bookmark(vcs, intent, &task.id, bookmark)? {
+            eprintln!("warn: failed to delete bookmark {}: {}", bookmark, e);
+        } else {
+            // Clear bookmark field in DB after successful VCS deletion
+            self.forget_bookmark(&task.id, bookmark);
+        }
+        Ok(())
+    }
 
-        // Bubble up: auto-complete parents if all children done and unblocked
-        self.bubble_up_completion(id)?;
+    /// Commit for a task (in its worktree, if it has one), or reuse the
+    /// revision recorded by an interrupted run.
+    fn commit(
+        &self,
+        vcs: &dyn VcsBackend,
+        intent: &mut Intent,
+        task: &Task,
+        msg: &str,
+    ) -> Result<Option<String>> {
+        let id = &task.id;
+        let commit_sha = match intent.committed(id) {
+            Some(rev) => rev.map(str::to_string),
+            None => {
+                let result = match task.worktree {
+                    Some(ref path) => vcs.open_worktree(path)?.commit(msg),
+                    None => vcs.commit(msg),
+                };
+                let rev = match result {
+                    Ok(commit) => Some(commit.id),
+                    Err(VcsError::NothingToCommit) => None,
+                    Err(e) => return Err(e.into()),
+                };
+                self.journal.record(
+                    intent,
+                    VcsStep::Committed {
+                        task_id: id.clone(),
+                        rev: rev.clone(),
+                    },
+                )?;
+                rev
+            }
+        };
 
-        Ok(completed_task)
+        if let Some(ref rev) = commit_sha {
+            event_repo::append(
+                self.conn,
+                &EventBody::Committed {
+                    task_id: id.clone(),
+                    rev: rev.clone(),
+                },
+            )?;
+        }
+        Ok(commit_sha)
+    }
+
+    /// Integrate `task`'s branch into its start branch, or reuse the result
+    /// recorded by an interrupted run. Records the branch tip (`head_rev`) and
+    /// the new start branch tip on the task.
+    /// Returns false when the task has no branch or no start branch.
+    fn integrate(
+        &self,
+        vcs: &dyn VcsBackend,
+        intent: &mut Intent,
+        task: &Task,
+        commit_msg: &str,
+    ) -> Result<bool> {
+        let Some(branch) = &task.bookmark else {
+            return Ok(false);
+        };
+        // Gone only if an interrupted run got as far as deleting the branch
+        if let Ok(head) = vcs.resolve_commit(branch) {
+            task_repo::set_head_rev(self.conn, &task.id, &head)?;
+        }
+        let Some(onto) = &task.start_branch else {
+            return Ok(false);
+        };
+        let strategy = match self.integration {
+            Some(strategy) => strategy,
+            None => settings_repo::load(self.conn)?.integration,
+        };
+
+        let rev = match intent.integrated(&task.id) {
+            Some(rev) => rev.map(str::to_string),
+            None => {
+                // A squash is the only commit carrying the work; keep its message
+                let msg = match strategy {
+                    IntegrationStrategy::Squash => commit_msg.to_string(),
+                    _ => format!("Merge {}: {}", branch, task.description),
+                };
+                let rev = match vcs.integrate(branch, onto, strategy, &msg) {
+                    Ok(rev) => rev,
+                    Err(VcsError::Conflict(files)) => {
+                        return Err(OsError::IntegrationConflict {
+                            task_id: task.id.clone(),
+                            branch: branch.clone(),
+                            onto: onto.clone(),
+                            files,
+                        })
+                    }
+                    Err(e) => return Err(e.into()),
+                };
+                self.journal.record(
+                    intent,
+                    VcsStep::Integrated {
+                        task_id: task.id.clone(),
+                        rev: rev.clone(),
+                    },
+                )?;
+                rev
+            }
+        };
+
+        if let Some(ref rev) = rev {
+            task_repo::set_integration_commit(self.conn, &task.id, rev)?;
+            event_repo::append(
+                self.conn,
+                &EventBody::Integrated {
+                    task_id: task.id.clone(),
+                    branch: branch.clone(),
+                    onto: onto.clone(),
+                    strategy,
+                    rev: rev.clone(),
+                },
+            )?;
+        }
+        Ok(true)
+    }
+
+    /// A resumed complete that failed for any reason but local storage (the
+    /// VCS refused or aborted an integration, the task changed) would fail the
+    /// same way on every invocation: undo it like a failed complete and drop
+    /// the journal. Database and I/O errors keep it so the next run retries.
+    fn forget_aborted(&self, intent: &Intent, error: &OsError) -> Result<()> {
+        if !matches!(
+            error,
+            OsError::Database(_) | OsError::DatabaseBusy | OsError::Io(_)
+        ) {
+            self.undo_complete(intent);
+            self.journal.clear()?;
+        }
+        Ok(())
+    }
+
+    /// Delete a bookmark unless an interrupted run already did.
+    /// Outer error: journal write failed. Inner error: VCS refused (best-effort).
+    fn delete_bookmark(
+        &self,
+        vcs: &dyn VcsBackend,
+        intent: &mut Intent,
+        id: &TaskId,
+        bookmark: &str,
+    ) -> Result<std::result::Result<(), VcsError>> {
+        if intent.bookmark_deleted(bookmark) {
+            return Ok(Ok(()));
+        }
+        let rev = vcs.resolve_commit(bookmark).ok();
+        if let Err(e) = vcs.delete_bookmark(bookmark) {
+            return Ok(Err(e));
+        }
+        self.journal.record(
+            intent,
+            VcsStep::BookmarkDeleted {
+                task_id: id.clone(),
+                name: bookmark.to_string(),
+                rev,
+            },
+        )?;
+        Ok(Ok(()))
+    }
+
+    /// Remove `task`'s worktree, if any, so its branch can be deleted.
+    /// Best-effort: failures are warned about and the worktree stays recorded.
+    fn remove_task_worktree(&self, vcs: &dyn VcsBackend, task: &Task) {
+        let Some(ref path) = task.worktree else {
+            return;
+        };
+        match vcs.remove_worktree(path) {
+            Ok(()) => {
+                if let Err(e) = task_repo::set_worktree(self.conn, &task.id, None) {
+                    eprintln!("warn: failed to forget worktree {}: {}", path, e);
+                }
+            }
+            Err(e) => eprintln!("warn: failed to remove worktree {}: {}", path, e),
+        }
+    }
+
+    /// Best-effort DB bookkeeping after a bookmark was deleted in VCS.
+    fn forget_bookmark(&self, id: &TaskId, bookmark: &str) {
+        let _ = task_repo::clear_bookmark(self.conn, id);
+        let _ = event_repo::append(
+            self.conn,
+            &EventBody::RefDeleted {
+                task_id: id.clone(),
+                ref_name: bookmark.to_string(),
+            },
+        );
     }
 
     /// Auto-complete parent tasks if all siblings are done and parent is unblocked.
     /// Bubbles up recursively until hitting a blocked parent or pending children.
-    fn bubble_up_completion(&self, completed_id: &TaskId) -> Result<()> {
+    fn bubble_up_completion(&self, intent: &mut Intent, completed_id: &TaskId) -> Result<()> {
         let mut current_id = completed_id.clone();
 
         loop {
@@ -316,9 +1012,16 @@ impl<'a> TaskWorkflowService<'a> {
                 break;
             }
 
-            // Auto-complete parent (use service method to handle depth-0 special case)
-            if parent.depth == Some(0) {
-                self.complete_milestone(&parent_id, None)?;
+            // Auto-complete parent (roots get the milestone commit and cleanup,
+            // other parents fold their branch into the one above)
+            if parent.is_root() {
+                self.complete_milestone(intent, &parent_id, None, &[])?;
+            } else if parent.bookmark.is_some() {
+                let vcs = self.vcs_for(&parent)?;
+                let msg = format!("Complete: {}", parent.description);
+                let integrated = self.integrate(&*vcs, intent, &parent, &msg)?;
+                self.task_service.complete(&parent_id, None)?;
+                self.drop_integrated_branch(&*vcs, intent, &parent, integrated)?;
             } else {
                 self.task_service.complete(&parent_id, None)?;
             }
@@ -329,15 +1032,12 @@ impl<'a> TaskWorkflowService<'a> {
         Ok(())
     }
 
-    pub fn complete_milestone(&self, id: &TaskId, result: Option<&str>) -> Result<Task> {
-        self.complete_milestone_with_learnings(id, result, &[])
-    }
-
     /// Complete a milestone with optional learnings.
     ///
     /// VCS-first ordering: commit changes before updating DB state.
-    pub fn complete_milestone_with_learnings(
+    fn complete_milestone(
         &self,
+        intent: &mut Intent,
         id: &TaskId,
         result: Option<&str>,
         learnings: &[String],
@@ -357,15 +1057,13 @@ impl<'a> TaskWorkflowService<'a> {
             return Ok(task);
         }
 
+        let vcs = self.vcs_for(&task)?;
+
         // Not a milestone - delegate to regular complete (avoid infinite recursion)
-        if task.depth != Some(0) {
+        if !task.is_root() {
             // 1. VCS first - commit (NothingToCommit is OK)
             let msg = format!("Complete: {}\n\n{}", task.description, result.unwrap_or(""));
-            let commit_sha = match self.vcs.commit(&msg) {
-                Ok(commit) => Some(commit.id),
-                Err(VcsError::NothingToCommit) => None,
-                Err(e) => return Err(e.into()),
-            };
+            let commit_sha = self.commit(&*vcs, intent, &task, &msg)?;
 
             // 2. DB updates (after VCS succeeds)
             let completed_task = self.task_service.complete_with_learnings_and_commit_sha(
@@ -378,17 +1076,19 @@ impl<'a> TaskWorkflowService<'a> {
             return Ok(completed_task);
         }
 
+        if task_repo::has_pending_children(self.conn, id)? {
+            return Err(OsError::PendingChildren);
+        }
+
         // Milestone: VCS first - commit (NothingToCommit is OK)
         let msg = format!(
             "Milestone: {}\n\n{}",
             task.description,
             result.unwrap_or("")
         );
-        let commit_sha = match self.vcs.commit(&msg) {
-            Ok(commit) => Some(commit.id),
-            Err(VcsError::NothingToCommit) => None,
-            Err(e) => return Err(e.into()),
-        };
+        let commit_sha = self.commit(&*vcs, intent, &task, &msg)?;
+        // A milestone started as a leaf has a branch of its own
+        self.integrate(&*vcs, intent, &task, &msg)?;
 
         // DB updates (after VCS succeeds)
         let completed_task = self.task_service.complete_with_learnings_and_commit_sha(
@@ -400,55 +1100,173 @@ impl<'a> TaskWorkflowService<'a> {
 
         // Best-effort cleanup: delete ALL descendant bookmarks
         // Unified stacking semantics for git backend
-        // For milestone, we need to checkout a safe commit first, then clean all descendants
+        // Grouped by repo, the milestone's own first; each repo gets a safe
+        // checkout before its branches are deleted
         let descendants = task_repo::get_all_descendants(self.conn, id)?;
+        let mut groups: Vec<(Option<&RepoId>, Vec<&Task>)> = vec![(task.repo_id.as_ref(), vec![])];
+        for descendant in &descendants {
+            let repo_id = descendant.repo_id.as_ref();
+            match groups.iter_mut().find(|(r, _)| *r == repo_id) {
+                Some((_, members)) => members.push(descendant),
+                None => groups.push((repo_id, vec![descendant])),
+            }
+        }
 
-        // Find checkout target: prefer milestone's start_commit, then descendant's, then HEAD
-        let checkout_target = task
-            .start_commit
-            .clone()
+        for (i, (_, members)) in groups.iter().enumerate() {
+            if i == 0 {
+                self.cleanup_milestone_branches(intent, &*vcs, Some(&task), members)?;
+            } else if members.iter().any(|d| d.bookmark.is_some()) {
+                match self.vcs_for(members[0]) {
+                    Ok(repo_vcs) => {
+                        self.cleanup_milestone_branches(intent, &*repo_vcs, None, members)?
+                    }
+                    Err(e) => eprintln!("warn: {} - skipping branch cleanup", e),
+                }
+            }
+        }
+
+        Ok(completed_task)
+    }
+
+    /// Branch cleanup for a completed milestone in one repo: checkout a safe
+    /// commit, then delete the branches of `descendants` in that repo.
+    /// `milestone` is given for the milestone's own repo.
+    fn cleanup_milestone_branches(
+        &self,
+        intent: &mut Intent,
+        vcs: &dyn VcsBackend,
+        milestone: Option<&Task>,
+        descendants: &[&Task],
+    ) -> Result<()> {
+        // Branches about to be deleted; git refuses to delete the checked-out one
+        let doomed: Vec<&str> = descendants
+            .iter()
+            .copied()
+            .chain(milestone)
+            .filter(|t| !keeps_branch(t))
+            .filter_map(|t| t.bookmark.as_deref())
+            .collect();
+        let on_doomed = vcs
+            .current_branch()
+            .ok()
+            .flatten()
+            .is_some_and(|b| doomed.contains(&b.as_str()));
+
+        // Find checkout target: a surviving start branch, else the milestone's
+        // start_commit, then a descendant's, then HEAD
+        let checkout_target = milestone
+            .into_iter()
+            .chain(descendants.iter().copied())
+            .filter_map(|t| t.start_branch.clone())
+            .find(|b| !doomed.contains(&b.as_str()))
+            .or_else(|| milestone.and_then(|m| m.start_commit.clone()))
             .or_else(|| descendants.iter().find_map(|d| d.start_commit.clone()))
-            .or_else(|| self.vcs.current_commit_id().ok());
+            .or_else(|| vcs.current_commit_id().ok());
 
-        if let Some(ref target) = checkout_target {
-            if let Err(e) = self.vcs.checkout(target) {
+        if !on_doomed {
+            // Already somewhere safe; stay there
+        } else if let Some(ref target) = checkout_target {
+            if let Err(e) = vcs.checkout(target) {
                 eprintln!(
                     "warn: failed to checkout {}: {} - skipping branch cleanup",
                     target, e
                 );
-                return Ok(completed_task);
+                return Ok(());
             }
         } else {
             // No checkout target available - skip branch cleanup entirely
             // This matches single-task behavior for consistency
             eprintln!("warn: no checkout target available - skipping milestone branch cleanup");
-            return Ok(completed_task);
+            return Ok(());
         }
 
-        for descendant in descendants.iter() {
+        for descendant in descendants.iter().filter(|d| !keeps_branch(d)) {
             if let Some(ref bookmark) = descendant.bookmark {
-                if let Err(e) = self.vcs.delete_bookmark(bookmark) {
+                self.remove_task_worktree(vcs, descendant);
+                if let Err(e) = self.delete_bookmark(vcs, intent, &descendant.id, bookmark)? {
                     eprintln!("warn: failed to delete bookmark {}: {}", bookmark, e);
                 } else {
                     // Clear bookmark field in DB after successful VCS deletion
-                    let _ = task_repo::clear_bookmark(self.conn, &descendant.id);
+                    self.forget_bookmark(&descendant.id, bookmark);
                 }
             }
         }
 
         // Also clean up milestone's own bookmark (if started as leaf before children added)
-        if let Some(ref bookmark) = task.bookmark {
-            if let Err(e) = self.vcs.delete_bookmark(bookmark) {
+        if let Some((milestone, bookmark)) = milestone
+            .filter(|m| !keeps_branch(m))
+            .and_then(|m| m.bookmark.as_ref().map(|b| (m, b)))
+        {
+            self.remove_task_worktree(vcs, milestone);
+            if let Err(e) = self.delete_bookmark(vcs, intent, &milestone.id, bookmark)? {
                 eprintln!(
                     "warn: failed to delete milestone bookmark {}: {}",
                     bookmark, e
                 );
             } else {
-                let _ = task_repo::clear_bookmark(self.conn, id);
+                self.forget_bookmark(&milestone.id, bookmark);
             }
         }
 
-        Ok(completed_task)
+        Ok(())
+    }
+
+    /// Resume or roll back a start/complete left behind by a crashed process.
+    ///
+    /// Returns None when there is no journal, or when another process holds the
+    /// write lock (its operation is still running).
+    pub fn recover(&self) -> Result<Option<Recovered>> {
+        if !self.journal.exists() {
+            return Ok(None);
+        }
+        // No retry loop: a writer still holding the lock after the busy timeout
+        // is a live operation, not a crash
+        let tx = match Transaction::new_unchecked(self.conn, TransactionBehavior::Immediate) {
+            Ok(tx) => tx,
+            Err(e) if e.sqlite_error_code() == Some(ErrorCode::DatabaseBusy) => {
+                return Ok(None);
+            }
+            Err(e) => return Err(e.into()),
+        };
+        // Re-read under the lock: the owner may have finished in the meantime
+        let Some(mut intent) = self.journal.load()? else {
+            return Ok(None);
+        };
+
+        let task = task_repo::get_task(self.conn, &intent.task_id)?;
+        let action = match (intent.op.clone(), task) {
+            (IntentOp::Start, Some(task))
+                if task.started_at.is_some() && task.bookmark.is_some() =>
+            {
+                RecoveryAction::AlreadyApplied
+            }
+            (IntentOp::Start, _) => {
+                self.undo_start(&intent);
+                RecoveryAction::RolledBack
+            }
+            (IntentOp::Complete { .. }, None) => RecoveryAction::Discarded,
+            (IntentOp::Complete { .. }, Some(task)) if task.completed => {
+                RecoveryAction::AlreadyApplied
+            }
+            (IntentOp::Complete { result, learnings }, Some(_)) => {
+                let id = intent.task_id.clone();
+                if let Err(e) = self.complete_inner(&mut intent, &id, result.as_deref(), &learnings)
+                {
+                    self.forget_aborted(&intent, &e)?;
+                    drop(tx);
+                    return Err(e);
+                }
+                RecoveryAction::Resumed
+            }
+        };
+
+        // Rolled back or already applied: a failed commit has nothing to undo
+        let undo: fn(&Self, &Intent) = match action {
+            RecoveryAction::Resumed => Self::undo_complete,
+            _ => |_: &Self, _: &Intent| {},
+        };
+        self.finish(tx, &intent, undo)?;
+        Ok(Some(Recovered { intent, action }))
     }
 }
 
@@ -485,7 +1303,12 @@ mod tests {
         fn log(&self, _limit: usize) -> VcsResult<Vec<LogEntry>> {
             Ok(vec![])
         }
-        fn diff(&self, _base: Option<&str>) -> VcsResult<Vec<DiffEntry>> {
+        fn diff(
+            &self,
+            _base: Option<&str>,
+            _head: Option<&str>,
+            _patch: bool,
+        ) -> VcsResult<Vec<DiffEntry>> {
             Ok(vec![])
         }
         fn commit(&self, message: &str) -> VcsResult<CommitResult> {
@@ -509,6 +1332,33 @@ mod tests {
         fn checkout(&self, _target: &str) -> VcsResult<()> {
             Ok(())
         }
+        fn path_exists(&self, _path: &str) -> VcsResult<bool> {
+            Ok(true)
+        }
+        fn resolve_commit(&self, rev: &str) -> VcsResult<String> {
+            Ok(rev.to_string())
+        }
+        fn current_branch(&self) -> VcsResult<Option<String>> {
+            Ok(Some("main".to_string()))
+        }
+        fn integrate(
+            &self,
+            _branch: &str,
+            _onto: &str,
+            _strategy: IntegrationStrategy,
+            _message: &str,
+        ) -> VcsResult<Option<String>> {
+            Ok(Some("mock-merge-id".to_string()))
+        }
+        fn add_worktree(&self, _branch: &str, _path: &str) -> VcsResult<()> {
+            Ok(())
+        }
+        fn remove_worktree(&self, _path: &str) -> VcsResult<()> {
+            Ok(())
+        }
+        fn open_worktree(&self, _path: &str) -> VcsResult<Box<dyn VcsBackend>> {
+            Ok(Box::new(MockVcsBackend))
+        }
     }
 
     fn mock_vcs() -> Box<dyn VcsBackend> {
@@ -528,6 +1378,10 @@ mod tests {
                 parent_id: None,
                 priority: None,
                 blocked_by: vec![],
+                labels: vec![],
+                due_at: None,
+                defer_until: None,
+                repo_id: None,
             })
             .unwrap();
 
@@ -550,6 +1404,10 @@ mod tests {
                 parent_id: None,
                 priority: None,
                 blocked_by: vec![],
+                labels: vec![],
+                due_at: None,
+                defer_until: None,
+                repo_id: None,
             })
             .unwrap();
 
@@ -573,6 +1431,10 @@ mod tests {
                 parent_id: None,
                 priority: Some(0),
                 blocked_by: vec![],
+                labels: vec![],
+                due_at: None,
+                defer_until: None,
+                repo_id: None,
             })
             .unwrap();
 
@@ -583,6 +1445,10 @@ mod tests {
                 parent_id: Some(milestone.id.clone()),
                 priority: Some(0),
                 blocked_by: vec![],
+                labels: vec![],
+                due_at: None,
+                defer_until: None,
+                repo_id: None,
             })
             .unwrap();
 
@@ -593,6 +1459,10 @@ mod tests {
                 parent_id: Some(task.id.clone()),
                 priority: Some(0),
                 blocked_by: vec![],
+                labels: vec![],
+                due_at: None,
+                defer_until: None,
+                repo_id: None,
             })
             .unwrap();
 
@@ -616,6 +1486,10 @@ mod tests {
                 parent_id: None,
                 priority: Some(0),
                 blocked_by: vec![],
+                labels: vec![],
+                due_at: None,
+                defer_until: None,
+                repo_id: None,
             })
             .unwrap();
 
@@ -626,6 +1500,10 @@ mod tests {
                 parent_id: None,
                 priority: Some(0),
                 blocked_by: vec![blocker_task.id.clone()],
+                labels: vec![],
+                due_at: None,
+                defer_until: None,
+                repo_id: None,
             })
             .unwrap();
 
@@ -636,6 +1514,10 @@ mod tests {
                 parent_id: Some(blocked_milestone.id.clone()),
                 priority: Some(0),
                 blocked_by: vec![],
+                labels: vec![],
+                due_at: None,
+                defer_until: None,
+                repo_id: None,
             })
             .unwrap();
 
@@ -661,6 +1543,10 @@ mod tests {
                 parent_id: None,
                 priority: Some(0),
                 blocked_by: vec![],
+                labels: vec![],
+                due_at: None,
+                defer_until: None,
+                repo_id: None,
             })
             .unwrap();
 
@@ -671,6 +1557,10 @@ mod tests {
                 parent_id: Some(milestone.id.clone()),
                 priority: Some(0),
                 blocked_by: vec![],
+                labels: vec![],
+                due_at: None,
+                defer_until: None,
+                repo_id: None,
             })
             .unwrap();
 
@@ -681,6 +1571,10 @@ mod tests {
                 parent_id: Some(task.id.clone()),
                 priority: Some(0),
                 blocked_by: vec![],
+                labels: vec![],
+                due_at: None,
+                defer_until: None,
+                repo_id: None,
             })
             .unwrap();
 
@@ -691,6 +1585,10 @@ mod tests {
                 parent_id: Some(task.id.clone()),
                 priority: Some(0),
                 blocked_by: vec![],
+                labels: vec![],
+                due_at: None,
+                defer_until: None,
+                repo_id: None,
             })
             .unwrap();
 
@@ -719,6 +1617,10 @@ mod tests {
                 parent_id: None,
                 priority: Some(0),
                 blocked_by: vec![],
+                labels: vec![],
+                due_at: None,
+                defer_until: None,
+                repo_id: None,
             })
             .unwrap();
 
@@ -729,6 +1631,10 @@ mod tests {
                 parent_id: Some(milestone.id.clone()),
                 priority: Some(0),
                 blocked_by: vec![],
+                labels: vec![],
+                due_at: None,
+                defer_until: None,
+                repo_id: None,
             })
             .unwrap();
 
@@ -753,6 +1659,10 @@ mod tests {
                 parent_id: None,
                 priority: Some(0),
                 blocked_by: vec![],
+                labels: vec![],
+                due_at: None,
+                defer_until: None,
+                repo_id: None,
             })
             .unwrap();
 
@@ -763,6 +1673,10 @@ mod tests {
                 parent_id: None,
                 priority: Some(0),
                 blocked_by: vec![blocker.id.clone()],
+                labels: vec![],
+                due_at: None,
+                defer_until: None,
+                repo_id: None,
             })
             .unwrap();
 
@@ -773,6 +1687,10 @@ mod tests {
                 parent_id: Some(milestone.id.clone()),
                 priority: Some(0),
                 blocked_by: vec![],
+                labels: vec![],
+                due_at: None,
+                defer_until: None,
+                repo_id: None,
             })
             .unwrap();
 
@@ -797,6 +1715,10 @@ mod tests {
                 parent_id: None,
                 priority: Some(0),
                 blocked_by: vec![],
+                labels: vec![],
+                due_at: None,
+                defer_until: None,
+                repo_id: None,
             })
             .unwrap();
 
@@ -807,6 +1729,10 @@ mod tests {
                 parent_id: Some(milestone.id.clone()),
                 priority: Some(0),
                 blocked_by: vec![],
+                labels: vec![],
+                due_at: None,
+                defer_until: None,
+                repo_id: None,
             })
             .unwrap();
 
@@ -817,6 +1743,10 @@ mod tests {
                 parent_id: Some(milestone.id.clone()),
                 priority: Some(0),
                 blocked_by: vec![],
+                labels: vec![],
+                due_at: None,
+                defer_until: None,
+                repo_id: None,
             })
             .unwrap();
 
@@ -841,6 +1771,10 @@ mod tests {
                 parent_id: None,
                 priority: Some(0),
                 blocked_by: vec![],
+                labels: vec![],
+                due_at: None,
+                defer_until: None,
+                repo_id: None,
             })
             .unwrap();
 
@@ -851,6 +1785,10 @@ mod tests {
                 parent_id: Some(milestone.id.clone()),
                 priority: Some(0),
                 blocked_by: vec![],
+                labels: vec![],
+                due_at: None,
+                defer_until: None,
+                repo_id: None,
             })
             .unwrap();
 
@@ -861,6 +1799,10 @@ mod tests {
                 parent_id: Some(task.id.clone()),
                 priority: Some(0),
                 blocked_by: vec![],
+                labels: vec![],
+                due_at: None,
+                defer_until: None,
+                repo_id: None,
             })
             .unwrap();
 
@@ -872,6 +1814,10 @@ mod tests {
                 parent_id: Some(task.id.clone()),
                 priority: Some(0),
                 blocked_by: vec![],
+                labels: vec![],
+                due_at: None,
+                defer_until: None,
+                repo_id: None,
             })
             .unwrap();
 
@@ -927,6 +1873,10 @@ mod tests {
                 parent_id: None,
                 priority: Some(0),
                 blocked_by: vec![],
+                labels: vec![],
+                due_at: None,
+                defer_until: None,
+                repo_id: None,
             })
             .unwrap();
 
@@ -937,6 +1887,10 @@ mod tests {
                 parent_id: Some(milestone.id.clone()),
                 priority: Some(0),
                 blocked_by: vec![],
+                labels: vec![],
+                due_at: None,
+                defer_until: None,
+                repo_id: None,
             })
             .unwrap();
 
@@ -947,6 +1901,10 @@ mod tests {
                 parent_id: Some(task.id.clone()),
                 priority: Some(0),
                 blocked_by: vec![],
+                labels: vec![],
+                due_at: None,
+                defer_until: None,
+                repo_id: None,
             })
             .unwrap();
 
@@ -985,6 +1943,10 @@ mod tests {
                 parent_id: None,
                 priority: Some(0),
                 blocked_by: vec![],
+                labels: vec![],
+                due_at: None,
+                defer_until: None,
+                repo_id: None,
             })
             .unwrap();
 
@@ -995,6 +1957,10 @@ mod tests {
                 parent_id: Some(milestone.id.clone()),
                 priority: Some(0),
                 blocked_by: vec![],
+                labels: vec![],
+                due_at: None,
+                defer_until: None,
+                repo_id: None,
             })
             .unwrap();
 
@@ -1005,6 +1971,10 @@ mod tests {
                 parent_id: Some(task_a.id.clone()),
                 priority: Some(0),
                 blocked_by: vec![],
+                labels: vec![],
+                due_at: None,
+                defer_until: None,
+                repo_id: None,
             })
             .unwrap();
 
@@ -1015,6 +1985,10 @@ mod tests {
                 parent_id: Some(task_a.id.clone()),
                 priority: Some(0),
                 blocked_by: vec![],
+                labels: vec![],
+                due_at: None,
+                defer_until: None,
+                repo_id: None,
             })
             .unwrap();
 
@@ -1050,6 +2024,10 @@ mod tests {
                 parent_id: None,
                 priority: Some(0),
                 blocked_by: vec![],
+                labels: vec![],
+                due_at: None,
+                defer_until: None,
+                repo_id: None,
             })
             .unwrap();
 
@@ -1060,6 +2038,10 @@ mod tests {
                 parent_id: Some(milestone.id.clone()),
                 priority: Some(0),
                 blocked_by: vec![],
+                labels: vec![],
+                due_at: None,
+                defer_until: None,
+                repo_id: None,
             })
             .unwrap();
 
@@ -1093,6 +2075,10 @@ mod tests {
                 parent_id: None,
                 priority: Some(0),
                 blocked_by: vec![],
+                labels: vec![],
+                due_at: None,
+                defer_until: None,
+                repo_id: None,
             })
             .unwrap();
 
@@ -1103,6 +2089,10 @@ mod tests {
                 parent_id: Some(milestone.id.clone()),
                 priority: Some(0),
                 blocked_by: vec![],
+                labels: vec![],
+                due_at: None,
+                defer_until: None,
+                repo_id: None,
             })
             .unwrap();
 
@@ -1113,6 +2103,10 @@ mod tests {
                 parent_id: Some(task.id.clone()),
                 priority: Some(0),
                 blocked_by: vec![],
+                labels: vec![],
+                due_at: None,
+                defer_until: None,
+                repo_id: None,
             })
             .unwrap();
 
@@ -1167,6 +2161,10 @@ mod tests {
                 parent_id: None,
                 priority: Some(0),
                 blocked_by: vec![],
+                labels: vec![],
+                due_at: None,
+                defer_until: None,
+                repo_id: None,
             })
             .unwrap();
 
@@ -1177,6 +2175,10 @@ mod tests {
                 parent_id: None,
                 priority: Some(0),
                 blocked_by: vec![blocker.id.clone()],
+                labels: vec![],
+                due_at: None,
+                defer_until: None,
+                repo_id: None,
             })
             .unwrap();
 
@@ -1196,29 +2198,66 @@ mod tests {
     }
 
     #[test]
-    fn test_start_bubbles_started_at_to_ancestors() {
+    fn test_start_rejects_deferred_task() {
         let conn = setup_db();
         let service = TaskWorkflowService::new(&conn, mock_vcs());
         let svc = service.task_service();
 
-        // Create: milestone -> task -> subtask
-        let milestone = svc
+        let until = chrono::Utc::now() + chrono::Duration::days(1);
+        let deferred = svc
             .create(&CreateTaskInput {
-                description: "Milestone".to_string(),
-                context: None,
-                parent_id: None,
-                priority: Some(0),
-                blocked_by: vec![],
+                description: "Deferred".to_string(),
+                defer_until: Some(until),
+                ..Default::default()
             })
             .unwrap();
 
-        let task = svc
-            .create(&CreateTaskInput {
-                description: "Task".to_string(),
+        let err = service.start(&deferred.id).unwrap_err();
+        match err {
+            OsError::NotNextReady {
+                message, reason, ..
+            } => {
+                assert!(message.contains("deferred until"), "{message}");
+                assert!(
+                    matches!(reason, NotReadyReason::Deferred { until: u } if Some(u) == deferred.defer_until)
+                );
+            }
+            _ => panic!("Expected NotNextReady error with Deferred reason, got {err:?}"),
+        }
+    }
+
+    #[test]
+    fn test_start_bubbles_started_at_to_ancestors() {
+        let conn = setup_db();
+        let service = TaskWorkflowService::new(&conn, mock_vcs());
+        let svc = service.task_service();
+
+        // Create: milestone -> task -> subtask
+        let milestone = svc
+            .create(&CreateTaskInput {
+                description: "Milestone".to_string(),
+                context: None,
+                parent_id: None,
+                priority: Some(0),
+                blocked_by: vec![],
+                labels: vec![],
+                due_at: None,
+                defer_until: None,
+                repo_id: None,
+            })
+            .unwrap();
+
+        let task = svc
+            .create(&CreateTaskInput {
+                description: "Task".to_string(),
                 context: None,
                 parent_id: Some(milestone.id.clone()),
                 priority: Some(0),
                 blocked_by: vec![],
+                labels: vec![],
+                due_at: None,
+                defer_until: None,
+                repo_id: None,
             })
             .unwrap();
 
@@ -1229,6 +2268,10 @@ mod tests {
                 parent_id: Some(task.id.clone()),
                 priority: Some(0),
                 blocked_by: vec![],
+                labels: vec![],
+                due_at: None,
+                defer_until: None,
+                repo_id: None,
             })
             .unwrap();
 
@@ -1240,16 +2283,22 @@ mod tests {
         // Start the subtask
         let started = service.start(&subtask.id).unwrap();
         assert!(started.started_at.is_some());
-        assert!(started.bookmark.is_some()); // VCS state only on leaf
+        assert!(started.start_commit.is_some());
 
-        // Ancestors should now have started_at but NOT VCS state
+        // Ancestors now have started_at and a stacked branch, but no start commit
         let task_after = svc.get(&task.id).unwrap();
         assert!(task_after.started_at.is_some());
-        assert!(task_after.bookmark.is_none()); // No VCS bookmark on parent
-
+        assert!(task_after.start_commit.is_none());
         let milestone_after = svc.get(&milestone.id).unwrap();
         assert!(milestone_after.started_at.is_some());
-        assert!(milestone_after.bookmark.is_none()); // No VCS bookmark on grandparent
+
+        assert_eq!(milestone_after.start_branch.as_deref(), Some("main"));
+        assert_eq!(task_after.start_branch, milestone_after.bookmark);
+        assert_eq!(started.start_branch, task_after.bookmark);
+        assert_eq!(
+            task_after.bookmark.as_deref(),
+            Some(format!("task/{}", task.id).as_str())
+        );
     }
 
     #[test]
@@ -1265,6 +2314,10 @@ mod tests {
                 parent_id: None,
                 priority: Some(0),
                 blocked_by: vec![],
+                labels: vec![],
+                due_at: None,
+                defer_until: None,
+                repo_id: None,
             })
             .unwrap();
 
@@ -1278,6 +2331,202 @@ mod tests {
         assert_eq!(second_start.started_at, first_started_at);
     }
 
+    #[test]
+    fn test_sessions_follow_start_pause_resume_complete() {
+        use crate::db::session_repo;
+
+        let conn = setup_db();
+        let service = TaskWorkflowService::new(&conn, mock_vcs()).with_agent(Some("alice".into()));
+        let svc = service.task_service();
+        let milestone = svc
+            .create(&CreateTaskInput {
+                description: "Milestone".to_string(),
+                ..Default::default()
+            })
+            .unwrap();
+        let task = svc
+            .create(&CreateTaskInput {
+                description: "Task".to_string(),
+                parent_id: Some(milestone.id.clone()),
+                ..Default::default()
+            })
+            .unwrap();
+        let running = || session_repo::running_session(&conn, &task.id).unwrap();
+
+        service.start(&task.id).unwrap();
+        assert_eq!(running().unwrap().agent.as_deref(), Some("alice"));
+        // Ancestors only get started_at; the clock runs on the started task
+        assert!(session_repo::running_session(&conn, &milestone.id)
+            .unwrap()
+            .is_none());
+        assert!(matches!(
+            svc.resume(&task.id, Some("alice")),
+            Err(OsError::SessionRunning(_))
+        ));
+
+        let paused = svc.pause(&task.id, Some("alice")).unwrap();
+        assert!(paused.ended_at.is_some());
+        assert!(running().is_none());
+        assert!(matches!(
+            svc.pause(&task.id, Some("alice")),
+            Err(OsError::NoRunningSession(_))
+        ));
+
+        // Resuming or starting again opens a new session
+        svc.resume(&task.id, Some("alice")).unwrap();
+        svc.pause(&task.id, Some("alice")).unwrap();
+        service.start(&task.id).unwrap();
+        assert!(running().is_some());
+
+        service.complete(&task.id, None).unwrap();
+        assert!(running().is_none());
+        let sessions = session_repo::list_sessions(&conn).unwrap();
+        assert_eq!(sessions.len(), 3);
+        assert!(sessions.iter().all(|s| s.task_id == task.id));
+
+        assert!(matches!(
+            svc.resume(&task.id, Some("alice")),
+            Err(OsError::CannotStartCompleted)
+        ));
+        assert!(matches!(
+            svc.resume(&milestone.id, None),
+            Err(OsError::CannotStartCompleted)
+        ));
+        let fresh = svc
+            .create(&CreateTaskInput {
+                description: "Fresh".to_string(),
+                ..Default::default()
+            })
+            .unwrap();
+        assert!(matches!(
+            svc.resume(&fresh.id, None),
+            Err(OsError::TaskNotStarted(_))
+        ));
+    }
+
+    #[test]
+    fn test_claims_gate_start_and_complete() {
+        let conn = setup_db();
+        let alice = TaskWorkflowService::new(&conn, mock_vcs()).with_agent(Some("alice".into()));
+        let bob = TaskWorkflowService::new(&conn, mock_vcs()).with_agent(Some("bob".into()));
+        let anonymous = TaskWorkflowService::new(&conn, mock_vcs());
+        let svc = alice.task_service();
+
+        let milestone = svc
+            .create(&CreateTaskInput {
+                description: "Milestone".to_string(),
+                ..Default::default()
+            })
+            .unwrap();
+        let task = svc
+            .create(&CreateTaskInput {
+                description: "Task".to_string(),
+                parent_id: Some(milestone.id.clone()),
+                ..Default::default()
+            })
+            .unwrap();
+
+        // Starting as an agent claims the task
+        let started = alice.start(&task.id).unwrap();
+        assert_eq!(started.claim.as_ref().unwrap().agent, "alice");
+
+        // Bob cannot take over even the idempotent restart, nor complete it
+        for result in [bob.start(&task.id), bob.complete(&task.id, None)] {
+            match result {
+                Err(OsError::TaskClaimed {
+                    task_id, holder, ..
+                }) => {
+                    assert_eq!(task_id, task.id);
+                    assert_eq!(holder, "alice");
+                }
+                other => panic!("expected TaskClaimed, got {:?}", other.map(|t| t.id)),
+            }
+        }
+        assert!(matches!(
+            anonymous.complete(&task.id, None),
+            Err(OsError::TaskClaimed { .. })
+        ));
+
+        // Completing releases the claim
+        let completed = alice.complete(&task.id, None).unwrap();
+        assert!(completed.claim.is_none());
+        assert!(crate::db::claim_repo::get_claim(&conn, &task.id)
+            .unwrap()
+            .is_none());
+    }
+
+    #[test]
+    fn test_expected_revision_gates_start_and_complete() {
+        let conn = setup_db();
+        let svc = TaskService::new(&conn);
+        let milestone = svc
+            .create(&CreateTaskInput {
+                description: "Milestone".to_string(),
+                ..Default::default()
+            })
+            .unwrap();
+        let task = svc
+            .create(&CreateTaskInput {
+                description: "Task".to_string(),
+                parent_id: Some(milestone.id.clone()),
+                ..Default::default()
+            })
+            .unwrap();
+        let at = |revision| {
+            TaskWorkflowService::new(&conn, mock_vcs()).with_expected_revision(Some(revision))
+        };
+
+        // Following blockers checks the requested milestone, not the leaf
+        assert!(matches!(
+            at(1).start_follow_blockers(&milestone.id),
+            Err(OsError::RevisionConflict { ref task_id, .. }) if *task_id == milestone.id
+        ));
+        assert!(svc.get(&task.id).unwrap().started_at.is_none());
+
+        let started = at(0).start_follow_blockers(&milestone.id).unwrap();
+        assert_eq!(started.id, task.id);
+        let revision = started.revision;
+
+        assert!(matches!(
+            at(revision - 1).complete(&task.id, None),
+            Err(OsError::RevisionConflict { .. })
+        ));
+        let completed = at(revision).complete(&task.id, None).unwrap();
+        assert!(completed.completed);
+        // Even the idempotent already-completed path honours the expectation
+        assert!(matches!(
+            at(revision).complete(&task.id, None),
+            Err(OsError::RevisionConflict { .. })
+        ));
+    }
+
+    #[test]
+    fn test_start_under_own_milestone_claim_does_not_claim_again() {
+        let conn = setup_db();
+        let alice = TaskWorkflowService::new(&conn, mock_vcs()).with_agent(Some("alice".into()));
+        let svc = alice.task_service();
+
+        let milestone = svc
+            .create(&CreateTaskInput {
+                description: "Milestone".to_string(),
+                ..Default::default()
+            })
+            .unwrap();
+        let task = svc
+            .create(&CreateTaskInput {
+                description: "Task".to_string(),
+                parent_id: Some(milestone.id.clone()),
+                ..Default::default()
+            })
+            .unwrap();
+        svc.claim(&milestone.id, "alice", chrono::Duration::minutes(5))
+            .unwrap();
+
+        let started = alice.start_follow_blockers(&milestone.id).unwrap();
+        assert_eq!(started.id, task.id);
+        assert!(started.claim.is_none());
+    }
+
     #[test]
     fn test_start_allows_leaf_without_children() {
         let conn = setup_db();
@@ -1292,6 +2541,10 @@ mod tests {
                 parent_id: None,
                 priority: Some(0),
                 blocked_by: vec![],
+                labels: vec![],
+                due_at: None,
+                defer_until: None,
+                repo_id: None,
             })
             .unwrap();
 
@@ -1314,6 +2567,10 @@ mod tests {
                 parent_id: None,
                 priority: None,
                 blocked_by: vec![],
+                labels: vec![],
+                due_at: None,
+                defer_until: None,
+                repo_id: None,
             })
             .unwrap();
 
@@ -1342,6 +2599,10 @@ mod tests {
                 parent_id: None,
                 priority: None,
                 blocked_by: vec![],
+                labels: vec![],
+                due_at: None,
+                defer_until: None,
+                repo_id: None,
             })
             .unwrap();
 
@@ -1357,4 +2618,1094 @@ mod tests {
             result
         );
     }
+
+    #[test]
+    fn test_start_and_complete_append_events() {
+        use crate::db::event_repo::{self, EventBody, EventQuery};
+
+        let conn = setup_db();
+        let service = TaskWorkflowService::new(&conn, mock_vcs());
+        let svc = service.task_service();
+
+        let parent = svc
+            .create(&CreateTaskInput {
+                description: "Parent".to_string(),
+                ..Default::default()
+            })
+            .unwrap();
+        let child = svc
+            .create(&CreateTaskInput {
+                description: "Child".to_string(),
+                parent_id: Some(parent.id.clone()),
+                ..Default::default()
+            })
+            .unwrap();
+        let _sibling = svc
+            .create(&CreateTaskInput {
+                description: "Sibling".to_string(),
+                parent_id: Some(parent.id.clone()),
+                ..Default::default()
+            })
+            .unwrap();
+
+        service.start(&child.id).unwrap();
+        service
+            .complete_with_learnings(&child.id, Some("done"), &["Learned".to_string()])
+            .unwrap();
+
+        let events = event_repo::list_events(
+            &conn,
+            &EventQuery {
+                task_id: Some(child.id.clone()),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        let types: Vec<String> = events
+            .iter()
+            .map(|e| {
+                serde_json::to_value(&e.body).unwrap()["type"]
+                    .as_str()
+                    .unwrap()
+                    .to_string()
+            })
+            .collect();
+        assert_eq!(
+            types,
+            vec![
+                "task.created",
+                "vcs.refCreated",
+                "task.started",
+                "session.started",
+                "vcs.committed",
+                "vcs.integrated",
+                "learning.added",
+                "session.ended",
+                "task.completed",
+                "learning.bubbled",
+                "vcs.refDeleted",
+            ]
+        );
+
+        match &events[9].body {
+            EventBody::LearningBubbled {
+                to, learning_ids, ..
+            } => {
+                assert_eq!(to, &parent.id);
+                assert_eq!(learning_ids.len(), 1);
+            }
+            other => panic!("unexpected event {other:?}"),
+        }
+        // Every event from one process shares a correlation id
+        assert!(events
+            .iter()
+            .all(|e| e.correlation_id == events[0].correlation_id));
+    }
+
+    /// VCS mock that logs every call and can be told to fail commits, checkouts
+    /// or integrations.
+    #[derive(Default)]
+    struct RecordingVcs {
+        calls: std::sync::Arc<std::sync::Mutex<Vec<String>>>,
+        fail_commit_prefix: Option<&'static str>,
+        fail_checkout_prefix: Option<&'static str>,
+        conflict_on: Option<String>,
+        /// Set on backends returned by `open_worktree`; prefixes their calls
+        worktree: Option<String>,
+    }
+
+    impl RecordingVcs {
+        fn log(&self, call: String) {
+            let call = match self.worktree {
+                Some(ref path) => format!("[{path}] {call}"),
+                None => call,
+            };
+            self.calls.lock().unwrap().push(call);
+        }
+    }
+
+    impl VcsBackend for RecordingVcs {
+        fn vcs_type(&self) -> VcsType {
+            VcsType::Git
+        }
+        fn root(&self) -> &str {
+            "/mock"
+        }
+        fn status(&self) -> VcsResult<VcsStatus> {
+            Ok(VcsStatus {
+                files: vec![],
+                working_copy_id: None,
+            })
+        }
+        fn log(&self, _limit: usize) -> VcsResult<Vec<LogEntry>> {
+            Ok(vec![])
+        }
+        fn diff(
+            &self,
+            _base: Option<&str>,
+            _head: Option<&str>,
+            _patch: bool,
+        ) -> VcsResult<Vec<DiffEntry>> {
+            Ok(vec![])
+        }
+        fn commit(&self, message: &str) -> VcsResult<CommitResult> {
+            self.log(format!("commit {}", message.lines().next().unwrap_or("")));
+            if self
+                .fail_commit_prefix
+                .is_some_and(|p| message.starts_with(p))
+            {
+                return Err(VcsError::Git("hook rejected commit".to_string()));
+            }
+            Ok(CommitResult {
+                id: format!("rev-{}", self.calls.lock().unwrap().len()),
+                message: message.to_string(),
+            })
+        }
+        fn current_commit_id(&self) -> VcsResult<String> {
+            Ok("head".to_string())
+        }
+        fn create_bookmark(&self, name: &str, target: Option<&str>) -> VcsResult<()> {
+            match target {
+                Some(target) => self.log(format!("create_bookmark {name} from {target}")),
+                None => self.log(format!("create_bookmark {name}")),
+            }
+            Ok(())
+        }
+        fn delete_bookmark(&self, name: &str) -> VcsResult<()> {
+            self.log(format!("delete_bookmark {name}"));
+            Ok(())
+        }
+        fn list_bookmarks(&self, _prefix: Option<&str>) -> VcsResult<Vec<String>> {
+            Ok(vec![])
+        }
+        fn checkout(&self, target: &str) -> VcsResult<()> {
+            self.log(format!("checkout {target}"));
+            if self
+                .fail_checkout_prefix
+                .is_some_and(|p| target.starts_with(p))
+            {
+                return Err(VcsError::DirtyWorkingCopy);
+            }
+            Ok(())
+        }
+        fn path_exists(&self, _path: &str) -> VcsResult<bool> {
+            Ok(true)
+        }
+        fn resolve_commit(&self, rev: &str) -> VcsResult<String> {
+            Ok(rev.to_string())
+        }
+        fn current_branch(&self) -> VcsResult<Option<String>> {
+            Ok(Some("main".to_string()))
+        }
+        fn integrate(
+            &self,
+            branch: &str,
+            onto: &str,
+            strategy: IntegrationStrategy,
+            _message: &str,
+        ) -> VcsResult<Option<String>> {
+            self.log(format!("integrate {branch} {onto} {}", strategy.as_str()));
+            if self
+                .conflict_on
+                .as_ref()
+                .is_some_and(|p| branch.ends_with(p.as_str()))
+            {
+                return Err(VcsError::Conflict(vec!["src/lib.rs".to_string()]));
+            }
+            Ok(Some(format!("merge-{}", self.calls.lock().unwrap().len())))
+        }
+        fn add_worktree(&self, branch: &str, path: &str) -> VcsResult<()> {
+            self.log(format!("add_worktree {branch} {path}"));
+            Ok(())
+        }
+        fn remove_worktree(&self, path: &str) -> VcsResult<()> {
+            self.log(format!("remove_worktree {path}"));
+            Ok(())
+        }
+        fn open_worktree(&self, path: &str) -> VcsResult<Box<dyn VcsBackend>> {
+            Ok(Box::new(RecordingVcs {
+                calls: self.calls.clone(),
+                worktree: Some(path.to_string()),
+                ..Default::default()
+            }))
+        }
+    }
+
+    fn setup_file_db(dir: &tempfile::TempDir) -> Connection {
+        crate::db::open_db(&dir.path().join("tasks.db")).unwrap()
+    }
+
+    /// Milestone with one started child, on a file DB so the journal is live.
+    fn started_child(service: &TaskWorkflowService) -> (Task, Task) {
+        let svc = service.task_service();
+        let milestone = svc
+            .create(&CreateTaskInput {
+                description: "Milestone".to_string(),
+                ..Default::default()
+            })
+            .unwrap();
+        let task = svc
+            .create(&CreateTaskInput {
+                description: "Task".to_string(),
+                parent_id: Some(milestone.id.clone()),
+                ..Default::default()
+            })
+            .unwrap();
+        let task = service.start(&task.id).unwrap();
+        (milestone, task)
+    }
+
+    #[test]
+    fn test_failed_complete_restores_branch_and_clears_journal() {
+        let dir = tempfile::tempdir().unwrap();
+        let conn = setup_file_db(&dir);
+        let journal = IntentJournal::for_connection(&conn);
+
+        let failing = RecordingVcs {
+            fail_commit_prefix: Some("Milestone:"),
+            ..Default::default()
+        };
+        let calls = failing.calls.clone();
+        let service = TaskWorkflowService::new(&conn, Box::new(failing));
+        let (milestone, task) = started_child(&service);
+        let bookmark = task.bookmark.clone().unwrap();
+
+        // Milestone auto-completion fails after the task was committed and its branch deleted
+        let err = service
+            .complete_with_learnings(&task.id, Some("done"), &["Learned".to_string()])
+            .unwrap_err();
+        assert!(matches!(err, OsError::Vcs(_)), "got {err:?}");
+
+        // Nothing from the operation reached the DB, the branch is back and
+        // no journal is left for the next invocation to replay
+        let after = service.task_service().get(&task.id).unwrap();
+        assert!(!after.completed);
+        assert_eq!(after.bookmark.as_deref(), Some(bookmark.as_str()));
+        assert!(crate::db::learning_repo::list_learnings(&conn, &task.id)
+            .unwrap()
+            .is_empty());
+        assert!(!journal.exists());
+        assert!(service.recover().unwrap().is_none());
+        assert_eq!(
+            calls.lock().unwrap().last().unwrap(),
+            &format!("create_bookmark {bookmark} from {bookmark}")
+        );
+
+        // A plain retry completes the task and its milestone
+        let service = TaskWorkflowService::new(&conn, Box::new(RecordingVcs::default()));
+        let done = service.complete(&task.id, Some("done")).unwrap();
+        assert!(done.completed);
+        assert!(service.task_service().get(&milestone.id).unwrap().completed);
+    }
+
+    #[test]
+    fn test_recover_resumes_interrupted_complete() {
+        let dir = tempfile::tempdir().unwrap();
+        let conn = setup_file_db(&dir);
+        let journal = IntentJournal::for_connection(&conn);
+        let service = TaskWorkflowService::new(&conn, Box::new(RecordingVcs::default()));
+        let (milestone, task) = started_child(&service);
+        let bookmark = task.bookmark.clone().unwrap();
+
+        // Crash after the task's commit and branch deletion, before COMMIT
+        let mut intent = Intent::new(
+            task.id.clone(),
+            IntentOp::Complete {
+                result: Some("done".to_string()),
+                learnings: vec![],
+            },
+        );
+        journal
+            .record(
+                &mut intent,
+                VcsStep::Committed {
+                    task_id: task.id.clone(),
+                    rev: Some("rev-1".to_string()),
+                },
+            )
+            .unwrap();
+        journal
+            .record(
+                &mut intent,
+                VcsStep::BookmarkDeleted {
+                    task_id: task.id.clone(),
+                    name: bookmark.clone(),
+                    rev: Some("rev-1".to_string()),
+                },
+            )
+            .unwrap();
+
+        let vcs = RecordingVcs::default();
+        let calls = vcs.calls.clone();
+        let service = TaskWorkflowService::new(&conn, Box::new(vcs));
+        let recovered = service.recover().unwrap().unwrap();
+        assert_eq!(recovered.action, RecoveryAction::Resumed);
+        assert!(!journal.exists());
+
+        let task = service.task_service().get(&task.id).unwrap();
+        assert!(task.completed);
+        assert_eq!(task.commit_sha.as_deref(), Some("rev-1"));
+        assert!(task.bookmark.is_none());
+        assert_eq!(task.result.as_deref(), Some("done"));
+        assert!(service.task_service().get(&milestone.id).unwrap().completed);
+
+        // The task's commit and branch deletion were not repeated
+        let calls = calls.lock().unwrap();
+        assert!(!calls.iter().any(|c| c.starts_with("commit Complete:")));
+        assert!(!calls.contains(&format!("delete_bookmark {bookmark}")));
+        assert!(calls.iter().any(|c| c == "commit Milestone: Milestone"));
+    }
+
+    #[test]
+    fn test_recover_drops_journal_when_resume_is_refused() {
+        let dir = tempfile::tempdir().unwrap();
+        let conn = setup_file_db(&dir);
+        let journal = IntentJournal::for_connection(&conn);
+        let service = TaskWorkflowService::new(&conn, Box::new(RecordingVcs::default()));
+        let (milestone, task) = started_child(&service);
+
+        let mut intent = Intent::new(
+            task.id.clone(),
+            IntentOp::Complete {
+                result: None,
+                learnings: vec![],
+            },
+        );
+        journal
+            .record(
+                &mut intent,
+                VcsStep::Committed {
+                    task_id: task.id.clone(),
+                    rev: None,
+                },
+            )
+            .unwrap();
+
+        // The milestone commit is refused: retrying next time would fail the same way
+        let failing = RecordingVcs {
+            fail_commit_prefix: Some("Milestone:"),
+            ..Default::default()
+        };
+        let service = TaskWorkflowService::new(&conn, Box::new(failing));
+        assert!(matches!(service.recover(), Err(OsError::Vcs(_))));
+        assert!(!journal.exists());
+        assert!(service.recover().unwrap().is_none());
+
+        let after = service.task_service().get(&task.id).unwrap();
+        assert!(!after.completed);
+        assert_eq!(after.bookmark, task.bookmark);
+        assert!(!service.task_service().get(&milestone.id).unwrap().completed);
+    }
+
+    #[test]
+    fn test_failed_start_undoes_created_bookmark() {
+        let conn = setup_db();
+        let vcs = RecordingVcs {
+            fail_checkout_prefix: Some("task/"),
+            ..Default::default()
+        };
+        let calls = vcs.calls.clone();
+        let service = TaskWorkflowService::new(&conn, Box::new(vcs));
+        let task = service
+            .task_service()
+            .create(&CreateTaskInput {
+                description: "Task".to_string(),
+                ..Default::default()
+            })
+            .unwrap();
+
+        let result = service.start(&task.id);
+        assert!(matches!(result, Err(OsError::DirtyWorkingCopy)));
+
+        let bookmark = format!("task/{}", task.id);
+        assert_eq!(
+            *calls.lock().unwrap(),
+            vec![
+                format!("create_bookmark {bookmark}"),
+                format!("checkout {bookmark}"),
+                format!("delete_bookmark {bookmark}"),
+            ]
+        );
+        let task = service.task_service().get(&task.id).unwrap();
+        assert!(task.started_at.is_none());
+        assert!(task.bookmark.is_none());
+    }
+
+    #[test]
+    fn test_conflicting_integration_keeps_task_in_progress() {
+        let dir = tempfile::tempdir().unwrap();
+        let conn = setup_file_db(&dir);
+        let journal = IntentJournal::for_connection(&conn);
+        let vcs = RecordingVcs {
+            conflict_on: Some(String::new()),
+            ..Default::default()
+        };
+        let service = TaskWorkflowService::new(&conn, Box::new(vcs));
+        let task = service
+            .task_service()
+            .create(&CreateTaskInput {
+                description: "Task".to_string(),
+                ..Default::default()
+            })
+            .unwrap();
+        let started = service.start(&task.id).unwrap();
+        assert_eq!(started.start_branch.as_deref(), Some("main"));
+
+        let err = service.complete(&task.id, None).unwrap_err();
+        match err {
+            OsError::IntegrationConflict { onto, files, .. } => {
+                assert_eq!(onto, "main");
+                assert_eq!(files, vec!["src/lib.rs"]);
+            }
+            other => panic!("expected conflict, got {other:?}"),
+        }
+        assert!(!journal.exists());
+        let after = service.task_service().get(&task.id).unwrap();
+        assert!(!after.completed);
+        assert_eq!(after.bookmark, started.bookmark);
+
+        // Once resolved, completing again integrates with the requested strategy
+        let vcs = RecordingVcs::default();
+        let calls = vcs.calls.clone();
+        let service = TaskWorkflowService::new(&conn, Box::new(vcs))
+            .with_integration(Some(IntegrationStrategy::Squash));
+        service.complete(&task.id, None).unwrap();
+        let done = service.task_service().get(&task.id).unwrap();
+        assert!(done.completed);
+        assert!(done.bookmark.is_none());
+        assert!(done.integration_commit.is_some());
+        let bookmark = started.bookmark.unwrap();
+        assert_eq!(
+            calls.lock().unwrap()[1..],
+            [
+                format!("integrate {bookmark} main squash"),
+                format!("delete_bookmark {bookmark}"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_recover_rolls_back_interrupted_start() {
+        let dir = tempfile::tempdir().unwrap();
+        let conn = setup_file_db(&dir);
+        let task = crate::db::task_repo::create_task(
+            &conn,
+            &CreateTaskInput {
+                description: "Task".to_string(),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+
+        // Simulate a process that died after switching branches
+        let journal = IntentJournal::for_connection(&conn);
+        let mut intent = Intent::new(task.id.clone(), IntentOp::Start);
+        let bookmark = format!("task/{}", task.id);
+        journal
+            .record(
+                &mut intent,
+                VcsStep::BookmarkCreated {
+                    name: bookmark.clone(),
+                },
+            )
+            .unwrap();
+        journal
+            .record(
+                &mut intent,
+                VcsStep::CheckedOut {
+                    target: bookmark.clone(),
+                    previous: Some("abc123".to_string()),
+                },
+            )
+            .unwrap();
+
+        let vcs = RecordingVcs::default();
+        let calls = vcs.calls.clone();
+        let service = TaskWorkflowService::new(&conn, Box::new(vcs));
+        let recovered = service.recover().unwrap().unwrap();
+
+        assert_eq!(recovered.action, RecoveryAction::RolledBack);
+        assert_eq!(
+            *calls.lock().unwrap(),
+            vec![
+                "checkout abc123".to_string(),
+                format!("delete_bookmark {bookmark}"),
+            ]
+        );
+        assert!(!journal.exists());
+        assert!(service.recover().unwrap().is_none());
+    }
+
+    #[test]
+    fn test_recover_only_clears_journal_when_already_applied() {
+        let dir = tempfile::tempdir().unwrap();
+        let conn = setup_file_db(&dir);
+        let vcs = RecordingVcs::default();
+        let calls = vcs.calls.clone();
+        let service = TaskWorkflowService::new(&conn, Box::new(vcs));
+        let task = service
+            .task_service()
+            .create(&CreateTaskInput {
+                description: "Task".to_string(),
+                ..Default::default()
+            })
+            .unwrap();
+        service.complete(&task.id, None).unwrap();
+        calls.lock().unwrap().clear();
+
+        // Crash between COMMIT and journal removal
+        let journal = IntentJournal::for_connection(&conn);
+        let mut intent = Intent::new(
+            task.id.clone(),
+            IntentOp::Complete {
+                result: None,
+                learnings: vec![],
+            },
+        );
+        journal
+            .record(
+                &mut intent,
+                VcsStep::Committed {
+                    task_id: task.id.clone(),
+                    rev: None,
+                },
+            )
+            .unwrap();
+
+        let recovered = service.recover().unwrap().unwrap();
+        assert_eq!(recovered.action, RecoveryAction::AlreadyApplied);
+        assert!(calls.lock().unwrap().is_empty());
+        assert!(!journal.exists());
+    }
+
+    #[test]
+    fn test_deep_hierarchy_bubbles_to_root_milestone() {
+        let conn = setup_db();
+        crate::db::settings_repo::set_max_depth(&conn, 4).unwrap();
+        let vcs = RecordingVcs::default();
+        let calls = vcs.calls.clone();
+        let service = TaskWorkflowService::new(&conn, Box::new(vcs));
+        let svc = service.task_service();
+
+        let mut parent = None;
+        let mut ids = Vec::new();
+        for description in ["Root", "Epic", "Story", "Leaf"] {
+            let task = svc
+                .create(&CreateTaskInput {
+                    description: description.to_string(),
+                    parent_id: parent.clone(),
+                    ..Default::default()
+                })
+                .unwrap();
+            parent = Some(task.id.clone());
+            ids.push(task.id);
+        }
+
+        let started = service.start_follow_blockers(&ids[0]).unwrap();
+        assert_eq!(started.id, ids[3]);
+        service.complete(&ids[3], None).unwrap();
+
+        for id in &ids {
+            assert!(svc.get(id).unwrap().completed);
+        }
+        // Only the root gets the milestone commit, whatever the depth
+        let commits: Vec<String> = calls
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|c| c.starts_with("commit "))
+            .cloned()
+            .collect();
+        assert_eq!(
+            commits,
+            vec!["commit Complete: Leaf", "commit Milestone: Root"]
+        );
+    }
+
+    #[test]
+    fn test_tasks_run_vcs_steps_in_their_own_repo() {
+        let conn = setup_db();
+        let api = crate::db::repo_repo::Repo {
+            id: RepoId::new(),
+            path: "/code/api".to_string(),
+            name: "api".to_string(),
+            vcs_type: VcsType::Git,
+            created_at: chrono::Utc::now(),
+        };
+        crate::db::repo_repo::insert_repo(&conn, &api).unwrap();
+        let default_vcs = RecordingVcs::default();
+        let default_calls = default_vcs.calls.clone();
+        let api_vcs = RecordingVcs::default();
+        let api_calls = api_vcs.calls.clone();
+        let service = TaskWorkflowService::new(&conn, Box::new(default_vcs))
+            .with_repo_vcs(api.id.clone(), Box::new(api_vcs));
+        let svc = service.task_service();
+
+        let milestone = svc
+            .create(&CreateTaskInput {
+                description: "Milestone".to_string(),
+                ..Default::default()
+            })
+            .unwrap();
+        let web = svc
+            .create(&CreateTaskInput {
+                description: "Web".to_string(),
+                parent_id: Some(milestone.id.clone()),
+                ..Default::default()
+            })
+            .unwrap();
+        // Blockers may cross repos
+        let endpoint = svc
+            .create(&CreateTaskInput {
+                description: "Endpoint".to_string(),
+                parent_id: Some(milestone.id.clone()),
+                repo_id: Some(api.id.clone()),
+                blocked_by: vec![web.id.clone()],
+                ..Default::default()
+            })
+            .unwrap();
+
+        // Starting the blocked endpoint follows its blocker into the default repo
+        assert_eq!(
+            service.start_follow_blockers(&endpoint.id).unwrap().id,
+            web.id
+        );
+        service.complete(&web.id, None).unwrap();
+        service.start(&endpoint.id).unwrap();
+        service.complete(&endpoint.id, None).unwrap();
+        assert!(svc.get(&milestone.id).unwrap().completed);
+
+        let default_calls = default_calls.lock().unwrap();
+        let api_calls = api_calls.lock().unwrap();
+        assert!(default_calls.contains(&format!(
+            "create_bookmark task/{} from task/{}",
+            web.id, milestone.id
+        )));
+        assert!(default_calls.contains(&"commit Milestone: Milestone".to_string()));
+        assert!(!default_calls
+            .iter()
+            .any(|c| c.contains(endpoint.id.as_str())));
+        assert_eq!(
+            *api_calls,
+            vec![
+                format!("create_bookmark task/{}", endpoint.id),
+                format!("checkout task/{}", endpoint.id),
+                "commit Complete: Endpoint".to_string(),
+                format!("integrate task/{} main merge", endpoint.id),
+                format!("delete_bookmark task/{}", endpoint.id),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_children_stack_on_parent_branches() {
+        let conn = setup_db();
+        let vcs = RecordingVcs::default();
+        let calls = vcs.calls.clone();
+        let service = TaskWorkflowService::new(&conn, Box::new(vcs));
+        let svc = service.task_service();
+        let create = |description: &str, parent_id: Option<&TaskId>| {
+            svc.create(&CreateTaskInput {
+                description: description.to_string(),
+                parent_id: parent_id.cloned(),
+                ..Default::default()
+            })
+            .unwrap()
+        };
+        let milestone = create("Milestone", None);
+        let task = create("Task", Some(&milestone.id));
+        let first = create("First", Some(&task.id));
+        let second = create("Second", Some(&task.id));
+        let [m, t, s1, s2] = [&milestone, &task, &first, &second].map(|t| format!("task/{}", t.id));
+
+        service.start(&first.id).unwrap();
+        service.complete(&first.id, None).unwrap();
+        let task_branch = svc.get(&task.id).unwrap();
+        assert!(task_branch.base_rev.is_some());
+        assert!(task_branch.head_rev.is_none());
+        assert!(svc.get(&first.id).unwrap().head_rev.is_some());
+
+        service.start(&second.id).unwrap();
+        service.complete(&second.id, None).unwrap();
+        assert!(svc.get(&milestone.id).unwrap().completed);
+        assert!(svc.get(&task.id).unwrap().head_rev.is_some());
+
+        let calls = calls.lock().unwrap();
+        let calls: Vec<&str> = calls
+            .iter()
+            .map(String::as_str)
+            .filter(|c| !c.starts_with("commit "))
+            .collect();
+        assert_eq!(
+            calls,
+            [
+                format!("create_bookmark {m}"),
+                format!("create_bookmark {t} from {m}"),
+                format!("create_bookmark {s1} from {t}"),
+                format!("checkout {s1}"),
+                format!("integrate {s1} {t} merge"),
+                format!("delete_bookmark {s1}"),
+                // The parent's branch already exists
+                format!("create_bookmark {s2} from {t}"),
+                format!("checkout {s2}"),
+                format!("integrate {s2} {t} merge"),
+                format!("delete_bookmark {s2}"),
+                // Completion folds each level into the one above
+                format!("integrate {t} {m} merge"),
+                format!("delete_bookmark {t}"),
+                format!("integrate {m} main merge"),
+                format!("delete_bookmark {m}"),
+            ]
+        );
+    }
+
+    /// Create tasks from (description, parent index) pairs; returns them with
+    /// their branch names.
+    fn create_tree(svc: &TaskService, spec: &[(&str, Option<usize>)]) -> Vec<(Task, String)> {
+        let mut tasks: Vec<(Task, String)> = Vec::new();
+        for (description, parent) in spec {
+            let task = svc
+                .create(&CreateTaskInput {
+                    description: description.to_string(),
+                    parent_id: parent.map(|i| tasks[i].0.id.clone()),
+                    ..Default::default()
+                })
+                .unwrap();
+            let branch = format!("task/{}", task.id);
+            tasks.push((task, branch));
+        }
+        tasks
+    }
+
+    fn integrations(calls: &[String]) -> Vec<&str> {
+        calls
+            .iter()
+            .map(String::as_str)
+            .filter(|c| c.starts_with("integrate "))
+            .collect()
+    }
+
+    #[test]
+    fn test_grandchild_stacks_on_child_branch() {
+        let conn = setup_db();
+        let vcs = RecordingVcs::default();
+        let calls = vcs.calls.clone();
+        let service = TaskWorkflowService::new(&conn, Box::new(vcs));
+        let svc = service.task_service();
+        let tree = create_tree(
+            svc,
+            &[
+                ("Milestone", None),
+                ("Child", Some(0)),
+                ("First", Some(1)),
+                ("Second", Some(1)),
+            ],
+        );
+        let [(milestone, m), (child, c), (first, g1), (second, g2)] = &tree[..] else {
+            unreachable!()
+        };
+
+        let started = service.start(&first.id).unwrap();
+        assert_eq!(started.start_branch.as_deref(), Some(c.as_str()));
+        let child_task = svc.get(&child.id).unwrap();
+        assert_eq!(child_task.bookmark.as_deref(), Some(c.as_str()));
+        assert_eq!(child_task.start_branch.as_deref(), Some(m.as_str()));
+        assert_eq!(child_task.base_rev.as_deref(), Some(m.as_str()));
+        let milestone_task = svc.get(&milestone.id).unwrap();
+        assert_eq!(milestone_task.start_branch.as_deref(), Some("main"));
+        assert_eq!(milestone_task.base_rev.as_deref(), Some("HEAD"));
+        service.complete(&first.id, None).unwrap();
+
+        // The second grandchild reuses the child's branch, which is not recreated
+        calls.lock().unwrap().clear();
+        let started = service.start(&second.id).unwrap();
+        assert_eq!(started.start_branch.as_deref(), Some(c.as_str()));
+        assert_eq!(
+            *calls.lock().unwrap(),
+            [
+                format!("create_bookmark {g2} from {c}"),
+                format!("checkout {g2}"),
+            ]
+        );
+        assert!(!svc.get(&child.id).unwrap().completed);
+        assert!(!calls
+            .lock()
+            .unwrap()
+            .iter()
+            .any(|call| call.contains(g1.as_str())));
+    }
+
+    #[test]
+    fn test_levels_integrate_bottom_up() {
+        let conn = setup_db();
+        let vcs = RecordingVcs::default();
+        let calls = vcs.calls.clone();
+        let service = TaskWorkflowService::new(&conn, Box::new(vcs));
+        let svc = service.task_service();
+        let tree = create_tree(
+            svc,
+            &[
+                ("Milestone", None),
+                ("Api", Some(0)),
+                ("Schema", Some(1)),
+                ("Handlers", Some(1)),
+                ("Docs", Some(0)),
+            ],
+        );
+        let [(milestone, m), (api, a), (schema, s), (handlers, h), (docs, d)] = &tree[..] else {
+            unreachable!()
+        };
+
+        for task in [schema, handlers] {
+            service.start(&task.id).unwrap();
+            service.complete(&task.id, None).unwrap();
+        }
+        // Api folded into the milestone branch as soon as its children were done
+        let api_task = svc.get(&api.id).unwrap();
+        assert!(api_task.completed);
+        assert!(api_task.head_rev.is_some());
+        assert!(!svc.get(&milestone.id).unwrap().completed);
+
+        service.start(&docs.id).unwrap();
+        service.complete(&docs.id, None).unwrap();
+        assert!(svc.get(&milestone.id).unwrap().completed);
+
+        let calls = calls.lock().unwrap();
+        assert_eq!(
+            integrations(&calls),
+            [
+                format!("integrate {s} {a} merge"),
+                format!("integrate {h} {a} merge"),
+                format!("integrate {a} {m} merge"),
+                format!("integrate {d} {m} merge"),
+                format!("integrate {m} main merge"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_sibling_conflict_leaves_level_unintegrated() {
+        let dir = tempfile::tempdir().unwrap();
+        let conn = setup_file_db(&dir);
+        let journal = IntentJournal::for_connection(&conn);
+        let service = TaskWorkflowService::new(&conn, Box::new(RecordingVcs::default()));
+        let svc = service.task_service();
+        let tree = create_tree(
+            svc,
+            &[
+                ("Milestone", None),
+                ("Parent", Some(0)),
+                ("First", Some(1)),
+                ("Second", Some(1)),
+            ],
+        );
+        let [(milestone, m), (parent, p), (first, _), (second, s2)] = &tree[..] else {
+            unreachable!()
+        };
+        service.start(&first.id).unwrap();
+        service.complete(&first.id, None).unwrap();
+        service.start(&second.id).unwrap();
+
+        let conflicting = RecordingVcs {
+            conflict_on: Some(second.id.to_string()),
+            ..Default::default()
+        };
+        let calls = conflicting.calls.clone();
+        let service = TaskWorkflowService::new(&conn, Box::new(conflicting));
+        let svc = service.task_service();
+        match service.complete(&second.id, None).unwrap_err() {
+            OsError::IntegrationConflict { branch, onto, .. } => {
+                assert_eq!(&branch, s2);
+                assert_eq!(&onto, p);
+            }
+            other => panic!("expected conflict, got {other:?}"),
+        }
+        assert!(!journal.exists());
+
+        // The conflict stops the level: the sibling, its parent and the
+        // milestone keep their branches and nothing moves up
+        let after = svc.get(&second.id).unwrap();
+        assert!(!after.completed);
+        assert_eq!(after.bookmark.as_deref(), Some(s2.as_str()));
+        assert!(svc.get(&first.id).unwrap().completed);
+        for (task, branch) in [(parent, p), (milestone, m)] {
+            let task = svc.get(&task.id).unwrap();
+            assert!(!task.completed);
+            assert_eq!(task.bookmark.as_deref(), Some(branch.as_str()));
+            assert!(task.head_rev.is_none());
+        }
+        assert_eq!(integrations(&calls.lock().unwrap()).len(), 1);
+
+        // Once resolved, the level integrates and completion continues upward
+        let vcs = RecordingVcs::default();
+        let calls = vcs.calls.clone();
+        let service = TaskWorkflowService::new(&conn, Box::new(vcs));
+        service.complete(&second.id, None).unwrap();
+        assert!(service.task_service().get(&milestone.id).unwrap().completed);
+        assert_eq!(
+            integrations(&calls.lock().unwrap()),
+            [
+                format!("integrate {s2} {p} merge"),
+                format!("integrate {p} {m} merge"),
+                format!("integrate {m} main merge"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_completing_parent_integrates_its_children_work() {
+        use crate::testutil::{GitTestRepo, TestRepo};
+        use crate::vcs::GixBackend;
+
+        let repo = GitTestRepo::new().unwrap();
+        repo.write_file("README.md", "# Project\n").unwrap();
+        repo.commit("Initial commit").unwrap();
+        let vcs = || Box::new(GixBackend::open(repo.path()).unwrap());
+        let main = vcs().current_branch().unwrap().unwrap();
+
+        let conn = setup_db();
+        let service = TaskWorkflowService::new(&conn, vcs());
+        let svc = service.task_service();
+        let tree = create_tree(
+            svc,
+            &[
+                ("Milestone", None),
+                ("Parent", Some(0)),
+                ("Login", Some(1)),
+                ("Logout", Some(1)),
+                ("Audit", Some(1)),
+            ],
+        );
+        let [(milestone, _), (parent, p), (login, _), (logout, _), (audit, _)] = &tree[..] else {
+            unreachable!()
+        };
+
+        for (task, file) in [(login, "src/login.rs"), (logout, "src/logout.rs")] {
+            service.start(&task.id).unwrap();
+            repo.write_file(file, "pub fn run() {}\n").unwrap();
+            service.complete(&task.id, None).unwrap();
+        }
+        let waiting = svc.get(&parent.id).unwrap();
+        assert!(!waiting.completed);
+        assert_eq!(vcs().current_branch().unwrap().as_deref(), Some(p.as_str()));
+        assert!(repo.file_exists("src/login.rs") && repo.file_exists("src/logout.rs"));
+
+        // Cancelling the last child does not bubble, so the parent is completed
+        // by hand; its own change lands on its branch next to its children's
+        svc.cancel(&audit.id).unwrap();
+        repo.write_file("src/session.rs", "pub struct Session;\n")
+            .unwrap();
+        let done = service.complete(&parent.id, Some("auth done")).unwrap();
+        assert!(done.commit_sha.is_some());
+        assert!(svc.get(&milestone.id).unwrap().completed);
+
+        let done = svc.get(&parent.id).unwrap();
+        let changed: Vec<String> = vcs()
+            .diff(done.base_rev.as_deref(), done.head_rev.as_deref(), false)
+            .unwrap()
+            .into_iter()
+            .map(|entry| entry.path)
+            .collect();
+        assert_eq!(changed, ["src/login.rs", "src/logout.rs", "src/session.rs"]);
+        assert_eq!(vcs().current_branch().unwrap(), Some(main));
+        for file in ["src/login.rs", "src/logout.rs", "src/session.rs"] {
+            assert!(repo.file_exists(file), "{file} missing on main");
+        }
+        assert!(vcs().list_bookmarks(Some("task/")).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_worktree_start_commits_there_and_is_removed_on_complete() {
+        let conn = setup_db();
+        let vcs = RecordingVcs::default();
+        let calls = vcs.calls.clone();
+        let service = TaskWorkflowService::new(&conn, Box::new(vcs)).with_worktree(true);
+        let svc = service.task_service();
+        let milestone = svc
+            .create(&CreateTaskInput {
+                description: "Milestone".to_string(),
+                ..Default::default()
+            })
+            .unwrap();
+        let task = svc
+            .create(&CreateTaskInput {
+                description: "Task".to_string(),
+                parent_id: Some(milestone.id.clone()),
+                ..Default::default()
+            })
+            .unwrap();
+        let [m, t] = [&milestone, &task].map(|t| format!("task/{}", t.id));
+        let path = format!("/mock.worktrees/{}", task.id);
+
+        let started = service.start(&task.id).unwrap();
+        assert_eq!(started.worktree.as_deref(), Some(path.as_str()));
+        // Ancestor branches get no worktree
+        assert!(svc.get(&milestone.id).unwrap().worktree.is_none());
+
+        service.complete(&task.id, Some("done")).unwrap();
+        let completed = svc.get(&task.id).unwrap();
+        assert!(completed.worktree.is_none());
+        assert!(completed.bookmark.is_none());
+
+        assert_eq!(
+            *calls.lock().unwrap(),
+            [
+                // The shared working copy is never switched
+                format!("create_bookmark {m}"),
+                format!("create_bookmark {t} from {m}"),
+                format!("add_worktree {t} {path}"),
+                format!("[{path}] commit Complete: Task"),
+                format!("integrate {t} {m} merge"),
+                format!("remove_worktree {path}"),
+                format!("delete_bookmark {t}"),
+                "commit Milestone: Milestone".to_string(),
+                format!("integrate {m} main merge"),
+                format!("delete_bookmark {m}"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_worktree_dir_setting_places_worktrees() {
+        let conn = setup_db();
+        settings_repo::set_worktree_dir(&conn, Some("../trees")).unwrap();
+        let service =
+            TaskWorkflowService::new(&conn, Box::new(RecordingVcs::default())).with_worktree(true);
+        let task = service
+            .task_service()
+            .create(&CreateTaskInput {
+                description: "Task".to_string(),
+                ..Default::default()
+            })
+            .unwrap();
+
+        let started = service.start(&task.id).unwrap();
+        assert_eq!(
+            started.worktree,
+            Some(format!("/mock/../trees/{}", task.id))
+        );
+    }
+
+    #[test]
+    fn test_tasks_without_repo_need_default_vcs() {
+        let conn = setup_db();
+        let service = TaskWorkflowService::with_optional_vcs(&conn, None);
+        let task = service
+            .task_service()
+            .create(&CreateTaskInput {
+                description: "Task".to_string(),
+                ..Default::default()
+            })
+            .unwrap();
+        assert!(matches!(
+            service.start(&task.id),
+            Err(OsError::NotARepository)
+        ));
+        assert!(service
+            .task_service()
+            .get(&task.id)
+            .unwrap()
+            .started_at
+            .is_none());
+    }
 }
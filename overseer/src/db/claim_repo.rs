@@ -0,0 +1,213 @@
+//! Agent claims (leases) stored in `task_claims`.
+//!
+//! At most one row per task. Rows are never swept on a timer: a claim whose
+//! `expires_at` has passed is simply not live, and the next claim replaces it.
+//! Callers decide liveness with [`TaskClaim::is_live_at`].
+
+use std::collections::HashMap;
+
+use chrono::{DateTime, Utc};
+use rusqlite::{params, Connection, OptionalExtension, Row};
+
+use crate::error::Result;
+use crate::id::TaskId;
+use crate::types::TaskClaim;
+
+fn parse_timestamp(s: &str) -> DateTime<Utc> {
+    DateTime::parse_from_rfc3339(s)
+        .map(|dt| dt.with_timezone(&Utc))
+        // Unparsable rows read as already expired rather than failing reads
+        .unwrap_or(DateTime::<Utc>::MIN_UTC)
+}
+
+fn row_to_claim(row: &Row) -> rusqlite::Result<TaskClaim> {
+    Ok(TaskClaim {
+        task_id: row.get("task_id")?,
+        agent: row.get("agent")?,
+        claimed_at: parse_timestamp(&row.get::<_, String>("claimed_at")?),
+        expires_at: parse_timestamp(&row.get::<_, String>("expires_at")?),
+    })
+}
+
+/// The claim row for `task_id`, live or not.
+pub fn get_claim(conn: &Connection, task_id: &TaskId) -> Result<Option<TaskClaim>> {
+    Ok(conn
+        .query_row(
+            "SELECT task_id, agent, claimed_at, expires_at FROM task_claims WHERE task_id = ?1",
+            params![task_id],
+            row_to_claim,
+        )
+        .optional()?)
+}
+
+pub fn get_live_claim(
+    conn: &Connection,
+    task_id: &TaskId,
+    now: DateTime<Utc>,
+) -> Result<Option<TaskClaim>> {
+    Ok(get_claim(conn, task_id)?.filter(|c| c.is_live_at(now)))
+}
+
+/// Every live claim, keyed by task.
+pub fn all_live_claims(
+    conn: &Connection,
+    now: DateTime<Utc>,
+) -> Result<HashMap<TaskId, TaskClaim>> {
+    let mut stmt =
+        conn.prepare("SELECT task_id, agent, claimed_at, expires_at FROM task_claims")?;
+    let claims = stmt
+        .query_map([], row_to_claim)?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+    Ok(claims
+        .into_iter()
+        .filter(|c| c.is_live_at(now))
+        .map(|c| (c.task_id.clone(), c))
+        .collect())
+}
+
+/// Live claims on `task_id` and its ancestors, nearest first.
+pub fn lineage_claims(
+    conn: &Connection,
+    task_id: &TaskId,
+    now: DateTime<Utc>,
+) -> Result<Vec<TaskClaim>> {
+    // `level` bounds the walk so a corrupt parent cycle cannot recurse forever
+    let mut stmt = conn.prepare(
+        r#"
+        WITH RECURSIVE lineage(id, level) AS (
+            SELECT ?1, 0
+            UNION ALL
+            SELECT t.parent_id, l.level + 1 FROM tasks t JOIN lineage l ON t.id = l.id
+            WHERE t.parent_id IS NOT NULL AND l.level < 64
+        )
+        SELECT c.task_id, c.agent, c.claimed_at, c.expires_at
+        FROM task_claims c JOIN lineage l ON c.task_id = l.id
+        ORDER BY l.level
+        "#,
+    )?;
+    let claims = stmt
+        .query_map(params![task_id], row_to_claim)?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+    Ok(claims.into_iter().filter(|c| c.is_live_at(now)).collect())
+}
+
+/// Live claims on strict descendants of `task_id`.
+pub fn descendant_claims(
+    conn: &Connection,
+    task_id: &TaskId,
+    now: DateTime<Utc>,
+) -> Result<Vec<TaskClaim>> {
+    let mut stmt = conn.prepare(
+        r#"
+        WITH RECURSIVE subtree(id) AS (
+            SELECT id FROM tasks WHERE parent_id = ?1
+            UNION
+            SELECT t.id FROM tasks t JOIN subtree s ON t.parent_id = s.id
+        )
+        SELECT c.task_id, c.agent, c.claimed_at, c.expires_at
+        FROM task_claims c JOIN subtree s ON c.task_id = s.id
+        "#,
+    )?;
+    let claims = stmt
+        .query_map(params![task_id], row_to_claim)?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+    Ok(claims.into_iter().filter(|c| c.is_live_at(now)).collect())
+}
+
+/// Insert or replace the claim for `claim.task_id`.
+pub fn put_claim(conn: &Connection, claim: &TaskClaim) -> Result<()> {
+    conn.execute(
+        r#"
+        INSERT OR REPLACE INTO task_claims (task_id, agent, claimed_at, expires_at)
+        VALUES (?1, ?2, ?3, ?4)
+        "#,
+        params![
+            &claim.task_id,
+            &claim.agent,
+            claim.claimed_at.to_rfc3339(),
+            claim.expires_at.to_rfc3339(),
+        ],
+    )?;
+    Ok(())
+}
+
+/// Delete the claim on `task_id`; true if there was one.
+pub fn delete_claim(conn: &Connection, task_id: &TaskId) -> Result<bool> {
+    let deleted = conn.execute(
+        "DELETE FROM task_claims WHERE task_id = ?1",
+        params![task_id],
+    )?;
+    Ok(deleted > 0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::schema::init_schema;
+    use crate::db::task_repo::create_task;
+    use crate::types::CreateTaskInput;
+    use chrono::Duration;
+
+    fn task(conn: &Connection, parent: Option<&TaskId>) -> TaskId {
+        create_task(
+            conn,
+            &CreateTaskInput {
+                description: "test task".to_string(),
+                parent_id: parent.cloned(),
+                ..Default::default()
+            },
+        )
+        .unwrap()
+        .id
+    }
+
+    fn claim(task_id: &TaskId, agent: &str, expires_at: DateTime<Utc>) -> TaskClaim {
+        TaskClaim {
+            task_id: task_id.clone(),
+            agent: agent.to_string(),
+            claimed_at: Utc::now(),
+            expires_at,
+        }
+    }
+
+    #[test]
+    fn test_lineage_and_descendant_claims_skip_expired() {
+        let conn = Connection::open_in_memory().unwrap();
+        init_schema(&conn).unwrap();
+        let milestone = task(&conn, None);
+        let child = task(&conn, Some(&milestone));
+        let grandchild = task(&conn, Some(&child));
+        let now = Utc::now();
+
+        put_claim(&conn, &claim(&milestone, "a", now + Duration::minutes(5))).unwrap();
+        put_claim(&conn, &claim(&child, "b", now - Duration::minutes(5))).unwrap();
+        put_claim(&conn, &claim(&grandchild, "c", now + Duration::minutes(5))).unwrap();
+
+        let lineage = lineage_claims(&conn, &grandchild, now).unwrap();
+        let agents: Vec<&str> = lineage.iter().map(|c| c.agent.as_str()).collect();
+        assert_eq!(agents, vec!["c", "a"]);
+
+        let below = descendant_claims(&conn, &milestone, now).unwrap();
+        assert_eq!(below.len(), 1);
+        assert_eq!(below[0].task_id, grandchild);
+
+        assert!(get_live_claim(&conn, &child, now).unwrap().is_none());
+        assert!(get_claim(&conn, &child).unwrap().is_some());
+        assert_eq!(all_live_claims(&conn, now).unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_put_replaces_and_delete() {
+        let conn = Connection::open_in_memory().unwrap();
+        init_schema(&conn).unwrap();
+        let id = task(&conn, None);
+        let later = Utc::now() + Duration::minutes(30);
+
+        put_claim(&conn, &claim(&id, "a", later)).unwrap();
+        put_claim(&conn, &claim(&id, "b", later)).unwrap();
+        assert_eq!(get_claim(&conn, &id).unwrap().unwrap().agent, "b");
+
+        assert!(delete_claim(&conn, &id).unwrap());
+        assert!(!delete_claim(&conn, &id).unwrap());
+    }
+}
@@ -0,0 +1,170 @@
+//! Comment threads stored in `task_comments`.
+//!
+//! Comments are timestamped notes from humans or agents ("tried X, failed
+//! because Y") kept apart from a task's `context` and `result`.
+
+use chrono::{DateTime, Utc};
+use rusqlite::{params, Connection, OptionalExtension, Row};
+
+use crate::db::event_repo::{self, EventBody};
+use crate::error::{OsError, Result};
+use crate::id::{CommentId, TaskId};
+
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Comment {
+    pub id: CommentId,
+    pub task_id: TaskId,
+    pub author: Option<String>,
+    pub body: String,
+    pub created_at: DateTime<Utc>,
+}
+
+fn row_to_comment(row: &Row) -> rusqlite::Result<Comment> {
+    Ok(Comment {
+        id: row.get("id")?,
+        task_id: row.get("task_id")?,
+        author: row.get("author")?,
+        body: row.get("body")?,
+        created_at: row
+            .get::<_, String>("created_at")
+            .ok()
+            .and_then(|s| DateTime::parse_from_rfc3339(&s).ok())
+            .map(|dt| dt.with_timezone(&Utc))
+            .unwrap_or(DateTime::<Utc>::MIN_UTC),
+    })
+}
+
+pub fn add_comment(
+    conn: &Connection,
+    task_id: &TaskId,
+    author: Option<&str>,
+    body: &str,
+) -> Result<Comment> {
+    let comment = Comment {
+        id: CommentId::new(),
+        task_id: task_id.clone(),
+        author: author.map(str::to_string),
+        body: body.to_string(),
+        created_at: Utc::now(),
+    };
+    insert_comment_row(conn, &comment)?;
+    event_repo::append(
+        conn,
+        &EventBody::CommentAdded {
+            comment: comment.clone(),
+        },
+    )?;
+    Ok(comment)
+}
+
+/// Insert a comment verbatim, preserving its id and timestamp.
+/// Returns false if a comment with the same id already exists.
+pub fn insert_comment_row(conn: &Connection, comment: &Comment) -> Result<bool> {
+    let inserted = conn.execute(
+        r#"
+        INSERT OR IGNORE INTO task_comments (id, task_id, author, body, created_at)
+        VALUES (?1, ?2, ?3, ?4, ?5)
+        "#,
+        params![
+            &comment.id,
+            &comment.task_id,
+            comment.author,
+            comment.body,
+            comment.created_at.to_rfc3339(),
+        ],
+    )?;
+    Ok(inserted > 0)
+}
+
+pub fn get_comment(conn: &Connection, id: &CommentId) -> Result<Option<Comment>> {
+    let comment = conn
+        .query_row(
+            "SELECT * FROM task_comments WHERE id = ?1",
+            params![id],
+            row_to_comment,
+        )
+        .optional()?;
+    Ok(comment)
+}
+
+/// Comments on `task_id`, oldest first. With `latest`, only the last N.
+pub fn list_comments(
+    conn: &Connection,
+    task_id: &TaskId,
+    latest: Option<usize>,
+) -> Result<Vec<Comment>> {
+    // rowid breaks ties between comments added within the same instant
+    let mut stmt = conn.prepare(
+        "SELECT * FROM task_comments WHERE task_id = ?1 ORDER BY created_at DESC, rowid DESC LIMIT ?2",
+    )?;
+    let limit = latest.map_or(-1, |n| i64::try_from(n).unwrap_or(i64::MAX));
+    let mut comments = stmt
+        .query_map(params![task_id, limit], row_to_comment)?
+        .collect::<rusqlite::Result<Vec<Comment>>>()?;
+    comments.reverse();
+    Ok(comments)
+}
+
+/// Every comment, oldest first (for `os data export`)
+pub fn list_all_comments(conn: &Connection) -> Result<Vec<Comment>> {
+    let mut stmt = conn.prepare("SELECT * FROM task_comments ORDER BY created_at, rowid")?;
+    let comments = stmt
+        .query_map([], row_to_comment)?
+        .collect::<rusqlite::Result<Vec<Comment>>>()?;
+    Ok(comments)
+}
+
+pub fn delete_comment(conn: &Connection, id: &CommentId) -> Result<Comment> {
+    let comment = get_comment(conn, id)?.ok_or_else(|| OsError::CommentNotFound(id.clone()))?;
+    conn.execute("DELETE FROM task_comments WHERE id = ?1", params![id])?;
+    event_repo::append(
+        conn,
+        &EventBody::CommentDeleted {
+            comment_id: comment.id.clone(),
+            task_id: comment.task_id.clone(),
+        },
+    )?;
+    Ok(comment)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::schema::init_schema;
+    use crate::db::task_repo::create_task;
+    use crate::types::CreateTaskInput;
+
+    #[test]
+    fn test_comments_listed_oldest_first_with_latest_limit() {
+        let conn = Connection::open_in_memory().unwrap();
+        init_schema(&conn).unwrap();
+        let task_id = create_task(
+            &conn,
+            &CreateTaskInput {
+                description: "test task".to_string(),
+                ..Default::default()
+            },
+        )
+        .unwrap()
+        .id;
+
+        let first = add_comment(&conn, &task_id, Some("alice"), "tried X").unwrap();
+        add_comment(&conn, &task_id, None, "X failed because Y").unwrap();
+        let third = add_comment(&conn, &task_id, Some("agent-1"), "doing Z").unwrap();
+
+        let all = list_comments(&conn, &task_id, None).unwrap();
+        assert_eq!(all.len(), 3);
+        assert_eq!(all[0], first);
+        let latest = list_comments(&conn, &task_id, Some(2)).unwrap();
+        assert_eq!(latest[0].body, "X failed because Y");
+        assert_eq!(latest[1], third);
+
+        assert_eq!(delete_comment(&conn, &first.id).unwrap(), first);
+        assert!(matches!(
+            delete_comment(&conn, &first.id),
+            Err(OsError::CommentNotFound(_))
+        ));
+        assert_eq!(list_all_comments(&conn).unwrap().len(), 2);
+    }
+}
@@ -0,0 +1,415 @@
+//! Append-only event log stored in `events`.
+//!
+//! Every task, learning and VCS mutation appends one typed event. `seq` is
+//! monotonic, so consumers follow the log incrementally with `after_seq`.
+
+use std::sync::OnceLock;
+
+use chrono::{DateTime, Utc};
+use rusqlite::{params, Connection, Row};
+use serde::{Deserialize, Serialize};
+
+use crate::db::comment_repo::Comment;
+use crate::db::learning_repo::Learning;
+use crate::db::link_repo::TaskLink;
+use crate::error::Result;
+use crate::id::{CommentId, EventId, LearningId, LinkId, TaskId};
+use crate::types::{Task, TaskClaim, TaskSession};
+use crate::vcs::IntegrationStrategy;
+
+/// Who caused an event. Set by hosts via `OVERSEER_EVENT_SOURCE`; defaults to `cli`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum EventSource {
+    Cli,
+    Mcp,
+    Ui,
+}
+
+impl EventSource {
+    pub fn current() -> Self {
+        match std::env::var("OVERSEER_EVENT_SOURCE").as_deref() {
+            Ok("mcp") => Self::Mcp,
+            Ok("ui") => Self::Ui,
+            _ => Self::Cli,
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Cli => "cli",
+            Self::Mcp => "mcp",
+            Self::Ui => "ui",
+        }
+    }
+
+    fn parse(s: &str) -> Self {
+        match s {
+            "mcp" => Self::Mcp,
+            "ui" => Self::Ui,
+            _ => Self::Cli,
+        }
+    }
+}
+
+/// Typed event payload. Serializes as `{"type": "task.created", "data": {...}}`;
+/// `type` is stored in `event_type` and `data` in `payload`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", content = "data", rename_all_fields = "camelCase")]
+pub enum EventBody {
+    // Task lifecycle (payload is the task snapshot after the change)
+    #[serde(rename = "task.created")]
+    TaskCreated { task: Task },
+    #[serde(rename = "task.updated")]
+    TaskUpdated { task: Task },
+    #[serde(rename = "task.started")]
+    TaskStarted { task: Task },
+    #[serde(rename = "task.completed")]
+    TaskCompleted { task: Task },
+    #[serde(rename = "task.reopened")]
+    TaskReopened { task: Task },
+    #[serde(rename = "task.cancelled")]
+    TaskCancelled { task: Task },
+    #[serde(rename = "task.archived")]
+    TaskArchived { task: Task },
+    /// Moved to the trash; one per task in the subtree, root first
+    #[serde(rename = "task.deleted")]
+    TaskDeleted { task_id: TaskId },
+    #[serde(rename = "task.restored")]
+    TaskRestored { task: Task },
+    /// Removed from the trash for good
+    #[serde(rename = "task.purged")]
+    TaskPurged { task_id: TaskId },
+    #[serde(rename = "task.metadataChanged")]
+    TaskMetadataChanged {
+        task_id: TaskId,
+        metadata: Option<serde_json::Value>,
+    },
+
+    // Blockers
+    #[serde(rename = "blocker.added")]
+    BlockerAdded { task_id: TaskId, blocker_id: TaskId },
+    #[serde(rename = "blocker.removed")]
+    BlockerRemoved { task_id: TaskId, blocker_id: TaskId },
+
+    // Learnings
+    #[serde(rename = "learning.added")]
+    LearningAdded { learning: Learning },
+    #[serde(rename = "learning.deleted")]
+    LearningDeleted {
+        learning_id: LearningId,
+        task_id: TaskId,
+    },
+    #[serde(rename = "comment.added")]
+    CommentAdded { comment: Comment },
+    #[serde(rename = "comment.deleted")]
+    CommentDeleted {
+        comment_id: CommentId,
+        task_id: TaskId,
+    },
+    #[serde(rename = "link.added")]
+    LinkAdded { link: TaskLink },
+    #[serde(rename = "link.removed")]
+    LinkRemoved { link_id: LinkId, task_id: TaskId },
+    #[serde(rename = "learning.bubbled")]
+    LearningBubbled {
+        from: TaskId,
+        to: TaskId,
+        learning_ids: Vec<LearningId>,
+    },
+
+    // Claims
+    #[serde(rename = "claim.acquired")]
+    ClaimAcquired { claim: TaskClaim },
+    #[serde(rename = "claim.renewed")]
+    ClaimRenewed { claim: TaskClaim },
+    #[serde(rename = "claim.released")]
+    ClaimReleased { task_id: TaskId, agent: String },
+
+    // Work sessions
+    #[serde(rename = "session.started")]
+    SessionStarted { session: TaskSession },
+    #[serde(rename = "session.ended")]
+    SessionEnded { session: TaskSession },
+
+    // VCS
+    #[serde(rename = "vcs.refCreated")]
+    RefCreated {
+        task_id: TaskId,
+        ref_name: String,
+        target: String,
+    },
+    #[serde(rename = "vcs.refDeleted")]
+    RefDeleted { task_id: TaskId, ref_name: String },
+    #[serde(rename = "vcs.committed")]
+    Committed { task_id: TaskId, rev: String },
+    /// A completed task's branch was brought into the branch it started from
+    #[serde(rename = "vcs.integrated")]
+    Integrated {
+        task_id: TaskId,
+        branch: String,
+        onto: String,
+        strategy: IntegrationStrategy,
+        rev: String,
+    },
+}
+
+impl EventBody {
+    /// Task the event is indexed under (for `--task` filtering)
+    pub fn task_id(&self) -> Option<&TaskId> {
+        match self {
+            Self::TaskCreated { task }
+            | Self::TaskUpdated { task }
+            | Self::TaskStarted { task }
+            | Self::TaskCompleted { task }
+            | Self::TaskReopened { task }
+            | Self::TaskCancelled { task }
+            | Self::TaskArchived { task }
+            | Self::TaskRestored { task } => Some(&task.id),
+            Self::TaskDeleted { task_id }
+            | Self::TaskPurged { task_id }
+            | Self::TaskMetadataChanged { task_id, .. }
+            | Self::BlockerAdded { task_id, .. }
+            | Self::BlockerRemoved { task_id, .. }
+            | Self::LearningDeleted { task_id, .. }
+            | Self::CommentDeleted { task_id, .. }
+            | Self::LinkRemoved { task_id, .. }
+            | Self::ClaimReleased { task_id, .. }
+            | Self::RefCreated { task_id, .. }
+            | Self::RefDeleted { task_id, .. }
+            | Self::Committed { task_id, .. }
+            | Self::Integrated { task_id, .. } => Some(task_id),
+            Self::LearningAdded { learning } => Some(&learning.task_id),
+            Self::CommentAdded { comment } => Some(&comment.task_id),
+            Self::LinkAdded { link } => Some(&link.task_id),
+            Self::ClaimAcquired { claim } | Self::ClaimRenewed { claim } => Some(&claim.task_id),
+            Self::SessionStarted { session } | Self::SessionEnded { session } => {
+                Some(&session.task_id)
+            }
+            Self::LearningBubbled { from, .. } => Some(from),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Event {
+    pub seq: i64,
+    pub id: EventId,
+    pub at: DateTime<Utc>,
+    /// Shared by all events appended by one `os` invocation
+    pub correlation_id: Option<String>,
+    pub source: EventSource,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub task_id: Option<TaskId>,
+    #[serde(flatten)]
+    pub body: EventBody,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct EventQuery {
+    /// Only events with `seq` strictly greater than this
+    pub after_seq: Option<i64>,
+    pub task_id: Option<TaskId>,
+    pub limit: Option<usize>,
+}
+
+/// One id per process, so every event from a single command can be grouped.
+fn correlation_id() -> &'static str {
+    static ID: OnceLock<String> = OnceLock::new();
+    ID.get_or_init(|| ulid::Ulid::new().to_string())
+}
+
+/// Append an event and return its `seq`.
+pub fn append(conn: &Connection, body: &EventBody) -> Result<i64> {
+    let serde_json::Value::Object(mut tagged) = serde_json::to_value(body)? else {
+        unreachable!("adjacently tagged enum always serializes to an object");
+    };
+    let event_type = tagged
+        .remove("type")
+        .and_then(|t| t.as_str().map(str::to_string))
+        .unwrap_or_default();
+    let payload = tagged.remove("data").unwrap_or(serde_json::Value::Null);
+
+    conn.execute(
+        r#"
+        INSERT INTO events (id, at, correlation_id, source, event_type, task_id, payload)
+        VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)
+        "#,
+        params![
+            EventId::new(),
+            Utc::now().to_rfc3339(),
+            correlation_id(),
+            EventSource::current().as_str(),
+            event_type,
+            body.task_id(),
+            payload.to_string(),
+        ],
+    )?;
+    Ok(conn.last_insert_rowid())
+}
+
+fn row_to_event(row: &Row) -> rusqlite::Result<Event> {
+    let event_type: String = row.get("event_type")?;
+    let payload: String = row.get("payload")?;
+    let body = serde_json::from_str::<serde_json::Value>(&payload)
+        .and_then(|data| {
+            serde_json::from_value(serde_json::json!({ "type": event_type, "data": data }))
+        })
+        .map_err(|e| {
+            rusqlite::Error::FromSqlConversionFailure(0, rusqlite::types::Type::Text, Box::new(e))
+        })?;
+
+    Ok(Event {
+        seq: row.get("seq")?,
+        id: row.get("id")?,
+        at: row
+            .get::<_, String>("at")
+            .ok()
+            .and_then(|s| DateTime::parse_from_rfc3339(&s).ok())
+            .map(|dt| dt.with_timezone(&Utc))
+            .unwrap_or_else(Utc::now),
+        correlation_id: row.get("correlation_id")?,
+        source: EventSource::parse(&row.get::<_, String>("source")?),
+        task_id: row.get("task_id")?,
+        body,
+    })
+}
+
+/// Events in ascending `seq` order.
+pub fn list_events(conn: &Connection, query: &EventQuery) -> Result<Vec<Event>> {
+    let mut stmt = conn.prepare(
+        r#"
+        SELECT * FROM events
+        WHERE seq > ?1 AND (?2 IS NULL OR task_id = ?2)
+        ORDER BY seq ASC
+        LIMIT ?3
+        "#,
+    )?;
+    let limit = query.limit.map(|l| l as i64).unwrap_or(-1);
+    let events = stmt
+        .query_map(
+            params![query.after_seq.unwrap_or(0), query.task_id, limit],
+            row_to_event,
+        )?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+    Ok(events)
+}
+
+/// Highest `seq` written so far (0 for an empty log).
+pub fn max_seq(conn: &Connection) -> Result<i64> {
+    Ok(
+        conn.query_row("SELECT COALESCE(MAX(seq), 0) FROM events", [], |row| {
+            row.get(0)
+        })?,
+    )
+}
+
+/// The last `count` events (optionally for one task), in ascending `seq` order.
+pub fn latest_events(
+    conn: &Connection,
+    task_id: Option<&TaskId>,
+    count: usize,
+) -> Result<Vec<Event>> {
+    let mut stmt = conn.prepare(
+        r#"
+        SELECT * FROM events
+        WHERE (?1 IS NULL OR task_id = ?1)
+        ORDER BY seq DESC
+        LIMIT ?2
+        "#,
+    )?;
+    let mut events = stmt
+        .query_map(params![task_id, count as i64], row_to_event)?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+    events.reverse();
+    Ok(events)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::schema::init_schema;
+    use crate::db::task_repo;
+    use crate::types::CreateTaskInput;
+
+    fn setup() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        init_schema(&conn).unwrap();
+        conn
+    }
+
+    #[test]
+    fn test_append_and_read_back_typed_event() {
+        let conn = setup();
+        let task = task_repo::create_task(
+            &conn,
+            &CreateTaskInput {
+                description: "Task".to_string(),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+
+        let seq = append(&conn, &EventBody::TaskCreated { task: task.clone() }).unwrap();
+        let events = list_events(&conn, &EventQuery::default()).unwrap();
+
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].seq, seq);
+        assert_eq!(events[0].task_id, Some(task.id.clone()));
+        assert_eq!(events[0].source, EventSource::Cli);
+        match &events[0].body {
+            EventBody::TaskCreated { task: t } => assert_eq!(t.description, "Task"),
+            other => panic!("unexpected event {other:?}"),
+        }
+
+        let json = serde_json::to_value(&events[0]).unwrap();
+        assert_eq!(json["type"], "task.created");
+        assert_eq!(json["data"]["task"]["id"], task.id.to_string());
+    }
+
+    #[test]
+    fn test_after_seq_task_filter_and_latest() {
+        let conn = setup();
+        let a = TaskId::new();
+        let b = TaskId::new();
+        let first = append(&conn, &EventBody::TaskDeleted { task_id: a.clone() }).unwrap();
+        append(&conn, &EventBody::TaskDeleted { task_id: b.clone() }).unwrap();
+        let third = append(
+            &conn,
+            &EventBody::Committed {
+                task_id: a.clone(),
+                rev: "abc".to_string(),
+            },
+        )
+        .unwrap();
+        assert!(first < third);
+
+        let after = list_events(
+            &conn,
+            &EventQuery {
+                after_seq: Some(first),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        assert_eq!(after.len(), 2);
+
+        let for_a = list_events(
+            &conn,
+            &EventQuery {
+                task_id: Some(a.clone()),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        assert_eq!(
+            for_a.iter().map(|e| e.seq).collect::<Vec<_>>(),
+            vec![first, third]
+        );
+
+        let latest = latest_events(&conn, None, 2).unwrap();
+        assert_eq!(latest.len(), 2);
+        assert_eq!(latest[1].seq, third);
+    }
+}
@@ -0,0 +1,131 @@
+//! Free-form task labels stored in `task_labels`.
+//!
+//! Labels are normalized to lowercase on write so `Backend` and `backend` are
+//! the same label. A task's labels are always returned sorted.
+
+use std::collections::HashMap;
+
+use rusqlite::{params, Connection};
+
+use crate::error::{OsError, Result};
+use crate::id::TaskId;
+
+const MAX_LABEL_LEN: usize = 64;
+
+/// Trim and lowercase `label`, rejecting anything outside `[a-z0-9._:/-]`
+/// or not starting with a letter or digit.
+pub fn normalize_label(label: &str) -> Result<String> {
+    let normalized = label.trim().to_lowercase();
+    let valid = normalized.len() <= MAX_LABEL_LEN
+        && normalized
+            .chars()
+            .next()
+            .is_some_and(|c| c.is_ascii_alphanumeric())
+        && normalized
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || "._:/-".contains(c));
+    if !valid {
+        return Err(OsError::InvalidLabel(label.to_string()));
+    }
+    Ok(normalized)
+}
+
+/// Normalize, sort and dedupe a list of labels.
+pub fn normalize_labels<S: AsRef<str>>(labels: &[S]) -> Result<Vec<String>> {
+    let mut normalized = labels
+        .iter()
+        .map(|l| normalize_label(l.as_ref()))
+        .collect::<Result<Vec<_>>>()?;
+    normalized.sort();
+    normalized.dedup();
+    Ok(normalized)
+}
+
+pub fn get_labels(conn: &Connection, task_id: &TaskId) -> Result<Vec<String>> {
+    let mut stmt =
+        conn.prepare("SELECT label FROM task_labels WHERE task_id = ?1 ORDER BY label")?;
+    let labels = stmt
+        .query_map(params![task_id], |row| row.get(0))?
+        .collect::<rusqlite::Result<Vec<String>>>()?;
+    Ok(labels)
+}
+
+/// Sorted labels for every task that has any.
+pub fn all_labels(conn: &Connection) -> Result<HashMap<TaskId, Vec<String>>> {
+    let mut stmt = conn.prepare("SELECT task_id, label FROM task_labels ORDER BY label")?;
+    let rows = stmt.query_map([], |row| {
+        Ok((row.get::<_, TaskId>(0)?, row.get::<_, String>(1)?))
+    })?;
+    let mut labels: HashMap<TaskId, Vec<String>> = HashMap::new();
+    for row in rows {
+        let (task_id, label) = row?;
+        labels.entry(task_id).or_default().push(label);
+    }
+    Ok(labels)
+}
+
+/// Add already-normalized labels; existing ones are left alone.
+pub fn add_labels(conn: &Connection, task_id: &TaskId, labels: &[String]) -> Result<()> {
+    let mut stmt =
+        conn.prepare("INSERT OR IGNORE INTO task_labels (task_id, label) VALUES (?1, ?2)")?;
+    for label in labels {
+        stmt.execute(params![task_id, label])?;
+    }
+    Ok(())
+}
+
+pub fn remove_labels(conn: &Connection, task_id: &TaskId, labels: &[String]) -> Result<()> {
+    let mut stmt = conn.prepare("DELETE FROM task_labels WHERE task_id = ?1 AND label = ?2")?;
+    for label in labels {
+        stmt.execute(params![task_id, label])?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::schema::init_schema;
+    use crate::db::task_repo::create_task;
+    use crate::types::CreateTaskInput;
+
+    fn setup() -> (Connection, TaskId) {
+        let conn = Connection::open_in_memory().unwrap();
+        init_schema(&conn).unwrap();
+        let task = create_task(
+            &conn,
+            &CreateTaskInput {
+                description: "test task".to_string(),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        (conn, task.id)
+    }
+
+    #[test]
+    fn test_normalize_label() {
+        assert_eq!(normalize_label(" Backend ").unwrap(), "backend");
+        assert_eq!(normalize_label("area:api/v2").unwrap(), "area:api/v2");
+        assert!(normalize_label("").is_err());
+        assert!(normalize_label("-leading").is_err());
+        assert!(normalize_label("two words").is_err());
+        assert!(normalize_label(&"x".repeat(65)).is_err());
+        assert_eq!(
+            normalize_labels(&["ui", "Backend", "ui"]).unwrap(),
+            vec!["backend", "ui"]
+        );
+    }
+
+    #[test]
+    fn test_add_is_idempotent_and_remove() {
+        let (conn, id) = setup();
+        add_labels(&conn, &id, &["ui".into(), "backend".into()]).unwrap();
+        add_labels(&conn, &id, &["backend".into()]).unwrap();
+        assert_eq!(get_labels(&conn, &id).unwrap(), vec!["backend", "ui"]);
+        assert_eq!(all_labels(&conn).unwrap()[&id], vec!["backend", "ui"]);
+
+        remove_labels(&conn, &id, &["ui".into(), "missing".into()]).unwrap();
+        assert_eq!(get_labels(&conn, &id).unwrap(), vec!["backend"]);
+    }
+}
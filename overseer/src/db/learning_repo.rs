@@ -1,6 +1,7 @@
 use chrono::{DateTime, Utc};
 use rusqlite::{params, Connection, OptionalExtension, Row};
 
+use crate::db::event_repo::{self, EventBody};
 use crate::error::Result;
 use crate::id::{LearningId, TaskId};
 
@@ -18,7 +19,7 @@ pub struct Learning {
     pub created_at: DateTime<Utc>,
 }
 
-fn row_to_learning(row: &Row) -> rusqlite::Result<Learning> {
+pub(crate) fn row_to_learning(row: &Row) -> rusqlite::Result<Learning> {
     Ok(Learning {
         id: row.get("id")?,
         task_id: row.get("task_id")?,
@@ -52,7 +53,34 @@ pub fn add_learning(
         params![&id, task_id, content, origin, now_str],
     )?;
 
-    get_learning(conn, &id)?.ok_or_else(|| crate::error::OsError::LearningNotFound(id))
+    let learning =
+        get_learning(conn, &id)?.ok_or_else(|| crate::error::OsError::LearningNotFound(id))?;
+    event_repo::append(
+        conn,
+        &EventBody::LearningAdded {
+            learning: learning.clone(),
+        },
+    )?;
+    Ok(learning)
+}
+
+/// Insert a learning verbatim, preserving its id and timestamp.
+/// Returns false if an identical learning (same task, source and content) already exists.
+pub fn insert_learning_row(conn: &Connection, learning: &Learning) -> Result<bool> {
+    let inserted = conn.execute(
+        r#"
+        INSERT OR IGNORE INTO learnings (id, task_id, content, source_task_id, created_at)
+        VALUES (?1, ?2, ?3, ?4, ?5)
+        "#,
+        params![
+            &learning.id,
+            &learning.task_id,
+            learning.content,
+            learning.source_task_id.as_ref(),
+            learning.created_at.to_rfc3339(),
+        ],
+    )?;
+    Ok(inserted > 0)
 }
 
 pub fn get_learning(conn: &Connection, id: &LearningId) -> Result<Option<Learning>> {
@@ -76,7 +104,17 @@ pub fn list_learnings(conn: &Connection, task_id: &TaskId) -> Result<Vec<Learnin
 }
 
 pub fn delete_learning(conn: &Connection, id: &LearningId) -> Result<()> {
+    let Some(learning) = get_learning(conn, id)? else {
+        return Ok(());
+    };
     conn.execute("DELETE FROM learnings WHERE id = ?1", params![id])?;
+    event_repo::append(
+        conn,
+        &EventBody::LearningDeleted {
+            learning_id: learning.id,
+            task_id: learning.task_id,
+        },
+    )?;
     Ok(())
 }
 
@@ -85,7 +123,7 @@ pub fn delete_learning(conn: &Connection, id: &LearningId) -> Result<()> {
 /// Preserves original source_task_id through multiple bubbles (A1 -> A -> M keeps source = A1).
 pub fn bubble_learnings(conn: &Connection, from_id: &TaskId, to_id: &TaskId) -> Result<u64> {
     let learnings = list_learnings(conn, from_id)?;
-    let mut bubbled = 0u64;
+    let mut bubbled_ids = Vec::new();
 
     for learning in learnings {
         let id = LearningId::new();
@@ -101,9 +139,22 @@ pub fn bubble_learnings(conn: &Connection, from_id: &TaskId, to_id: &TaskId) ->
             "#,
             params![&id, to_id, &learning.content, origin_id, now_str],
         )?;
-        bubbled += result as u64;
+        if result > 0 {
+            bubbled_ids.push(id);
+        }
     }
 
+    let bubbled = bubbled_ids.len() as u64;
+    if !bubbled_ids.is_empty() {
+        event_repo::append(
+            conn,
+            &EventBody::LearningBubbled {
+                from: from_id.clone(),
+                to: to_id.clone(),
+                learning_ids: bubbled_ids,
+            },
+        )?;
+    }
     Ok(bubbled)
 }
 
@@ -0,0 +1,284 @@
+//! Typed links stored in `task_links`.
+//!
+//! A link points a task at a file, a line range in a file, a URL, a commit or
+//! an external issue. Targets arrive validated and normalized (see
+//! `core::links`): paths are relative to the repository root and commits are
+//! full ids.
+
+use std::collections::HashSet;
+
+use chrono::{DateTime, Utc};
+use rusqlite::types::{FromSql, FromSqlError, FromSqlResult, ToSql, ToSqlOutput, ValueRef};
+use rusqlite::{params, Connection, OptionalExtension, Row};
+use serde::{Deserialize, Serialize};
+
+use crate::db::event_repo::{self, EventBody};
+use crate::error::{OsError, Result};
+use crate::id::{LinkId, TaskId};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum LinkKind {
+    File,
+    FileRange,
+    Url,
+    Commit,
+    Issue,
+}
+
+impl LinkKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::File => "file",
+            Self::FileRange => "fileRange",
+            Self::Url => "url",
+            Self::Commit => "commit",
+            Self::Issue => "issue",
+        }
+    }
+}
+
+impl ToSql for LinkKind {
+    fn to_sql(&self) -> rusqlite::Result<ToSqlOutput<'_>> {
+        Ok(ToSqlOutput::from(self.as_str()))
+    }
+}
+
+impl FromSql for LinkKind {
+    fn column_result(value: ValueRef<'_>) -> FromSqlResult<Self> {
+        match value.as_str()? {
+            "file" => Ok(Self::File),
+            "fileRange" => Ok(Self::FileRange),
+            "url" => Ok(Self::Url),
+            "commit" => Ok(Self::Commit),
+            "issue" => Ok(Self::Issue),
+            other => Err(FromSqlError::Other(
+                format!("unknown link kind '{other}'").into(),
+            )),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TaskLink {
+    pub id: LinkId,
+    pub task_id: TaskId,
+    pub kind: LinkKind,
+    /// Path, URL, full commit id or issue reference
+    pub target: String,
+    /// First and last line (inclusive) of a `fileRange` link
+    pub line_start: Option<u32>,
+    pub line_end: Option<u32>,
+    pub note: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+fn row_to_link(row: &Row) -> rusqlite::Result<TaskLink> {
+    Ok(TaskLink {
+        id: row.get("id")?,
+        task_id: row.get("task_id")?,
+        kind: row.get("kind")?,
+        target: row.get("target")?,
+        line_start: row.get("line_start")?,
+        line_end: row.get("line_end")?,
+        note: row.get("note")?,
+        created_at: row
+            .get::<_, String>("created_at")
+            .ok()
+            .and_then(|s| DateTime::parse_from_rfc3339(&s).ok())
+            .map(|dt| dt.with_timezone(&Utc))
+            .unwrap_or(DateTime::<Utc>::MIN_UTC),
+    })
+}
+
+/// Link `task_id` to `target`. Linking the same target (and line range) twice
+/// returns the existing link unchanged.
+pub fn add_link(
+    conn: &Connection,
+    task_id: &TaskId,
+    kind: LinkKind,
+    target: &str,
+    lines: Option<(u32, u32)>,
+    note: Option<&str>,
+) -> Result<TaskLink> {
+    let (line_start, line_end) = lines.unzip();
+    let existing = conn
+        .query_row(
+            r#"
+            SELECT * FROM task_links
+            WHERE task_id = ?1 AND kind = ?2 AND target = ?3
+              AND line_start IS ?4 AND line_end IS ?5
+            "#,
+            params![task_id, kind, target, line_start, line_end],
+            row_to_link,
+        )
+        .optional()?;
+    if let Some(link) = existing {
+        return Ok(link);
+    }
+
+    let link = TaskLink {
+        id: LinkId::new(),
+        task_id: task_id.clone(),
+        kind,
+        target: target.to_string(),
+        line_start,
+        line_end,
+        note: note.map(str::to_string),
+        created_at: Utc::now(),
+    };
+    insert_link_row(conn, &link)?;
+    event_repo::append(conn, &EventBody::LinkAdded { link: link.clone() })?;
+    Ok(link)
+}
+
+/// Insert a link verbatim, preserving its id and timestamp.
+/// Returns false if a link with the same id already exists.
+pub fn insert_link_row(conn: &Connection, link: &TaskLink) -> Result<bool> {
+    let inserted = conn.execute(
+        r#"
+        INSERT OR IGNORE INTO task_links
+            (id, task_id, kind, target, line_start, line_end, note, created_at)
+        VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)
+        "#,
+        params![
+            &link.id,
+            &link.task_id,
+            link.kind,
+            link.target,
+            link.line_start,
+            link.line_end,
+            link.note,
+            link.created_at.to_rfc3339(),
+        ],
+    )?;
+    Ok(inserted > 0)
+}
+
+pub fn get_link(conn: &Connection, id: &LinkId) -> Result<Option<TaskLink>> {
+    let link = conn
+        .query_row(
+            "SELECT * FROM task_links WHERE id = ?1",
+            params![id],
+            row_to_link,
+        )
+        .optional()?;
+    Ok(link)
+}
+
+/// Links of `task_id`, oldest first.
+pub fn list_links(conn: &Connection, task_id: &TaskId) -> Result<Vec<TaskLink>> {
+    let mut stmt =
+        conn.prepare("SELECT * FROM task_links WHERE task_id = ?1 ORDER BY created_at, rowid")?;
+    let links = stmt
+        .query_map(params![task_id], row_to_link)?
+        .collect::<rusqlite::Result<Vec<TaskLink>>>()?;
+    Ok(links)
+}
+
+/// Every link, oldest first (for `os data export`)
+pub fn list_all_links(conn: &Connection) -> Result<Vec<TaskLink>> {
+    let mut stmt = conn.prepare("SELECT * FROM task_links ORDER BY created_at, rowid")?;
+    let links = stmt
+        .query_map([], row_to_link)?
+        .collect::<rusqlite::Result<Vec<TaskLink>>>()?;
+    Ok(links)
+}
+
+/// Tasks with a file or file-range link to `path`, or to anything under it
+/// when `path` is a directory.
+pub fn tasks_linked_to_path(conn: &Connection, path: &str) -> Result<HashSet<TaskId>> {
+    let mut stmt = conn.prepare(
+        r#"
+        SELECT DISTINCT task_id FROM task_links
+        WHERE kind IN ('file', 'fileRange')
+          AND (target = ?1 OR substr(target, 1, length(?1) + 1) = ?1 || '/')
+        "#,
+    )?;
+    let ids = stmt
+        .query_map(params![path], |row| row.get(0))?
+        .collect::<rusqlite::Result<HashSet<TaskId>>>()?;
+    Ok(ids)
+}
+
+pub fn remove_link(conn: &Connection, id: &LinkId) -> Result<TaskLink> {
+    let link = get_link(conn, id)?.ok_or_else(|| OsError::LinkNotFound(id.clone()))?;
+    conn.execute("DELETE FROM task_links WHERE id = ?1", params![id])?;
+    event_repo::append(
+        conn,
+        &EventBody::LinkRemoved {
+            link_id: link.id.clone(),
+            task_id: link.task_id.clone(),
+        },
+    )?;
+    Ok(link)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::schema::init_schema;
+    use crate::db::task_repo::create_task;
+    use crate::types::CreateTaskInput;
+
+    #[test]
+    fn test_links_dedupe_and_match_paths_by_prefix() {
+        let conn = Connection::open_in_memory().unwrap();
+        init_schema(&conn).unwrap();
+        let create = |description: &str| {
+            create_task(
+                &conn,
+                &CreateTaskInput {
+                    description: description.to_string(),
+                    ..Default::default()
+                },
+            )
+            .unwrap()
+            .id
+        };
+        let auth = create("auth");
+        let api = create("api");
+
+        let file = add_link(&conn, &auth, LinkKind::File, "src/auth.rs", None, None).unwrap();
+        let again = add_link(&conn, &auth, LinkKind::File, "src/auth.rs", None, None).unwrap();
+        assert_eq!(again, file);
+        let range = add_link(
+            &conn,
+            &api,
+            LinkKind::FileRange,
+            "src/auth.rs",
+            Some((10, 40)),
+            Some("login handler"),
+        )
+        .unwrap();
+        add_link(&conn, &api, LinkKind::File, "src/authz.rs", None, None).unwrap();
+        add_link(
+            &conn,
+            &api,
+            LinkKind::Url,
+            "https://example.com",
+            None,
+            None,
+        )
+        .unwrap();
+        assert_eq!(list_links(&conn, &api).unwrap()[0], range);
+
+        let linked = tasks_linked_to_path(&conn, "src/auth.rs").unwrap();
+        assert_eq!(linked, HashSet::from([auth.clone(), api.clone()]));
+        // Directory prefixes match whole components only
+        assert_eq!(tasks_linked_to_path(&conn, "src").unwrap().len(), 2);
+        assert!(tasks_linked_to_path(&conn, "src/auth").unwrap().is_empty());
+
+        assert_eq!(remove_link(&conn, &file.id).unwrap(), file);
+        assert!(matches!(
+            remove_link(&conn, &file.id),
+            Err(OsError::LinkNotFound(_))
+        ));
+        assert_eq!(
+            tasks_linked_to_path(&conn, "src/auth.rs").unwrap(),
+            HashSet::from([api])
+        );
+        assert_eq!(list_all_links(&conn).unwrap().len(), 3);
+    }
+}
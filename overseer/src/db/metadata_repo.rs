@@ -0,0 +1,187 @@
+//! Arbitrary per-task JSON metadata stored in `task_metadata`.
+//!
+//! Metadata is a single JSON object per task. Paths are dotted keys
+//! (`area`, `pr.url`) and may be written with or without a leading `$.`.
+
+use std::collections::{HashMap, HashSet};
+
+use rusqlite::{params, Connection, OptionalExtension};
+use serde_json::Value;
+
+use crate::error::{OsError, Result};
+use crate::id::TaskId;
+use crate::types::MetadataFilter;
+
+/// Convert a dotted key path into a quoted SQLite JSON path (`pr.url` -> `$."pr"."url"`).
+pub fn to_json_path(path: &str) -> Result<String> {
+    let trimmed = path.strip_prefix("$.").unwrap_or(path);
+    if trimmed.is_empty() {
+        return Err(OsError::InvalidMetadataPath(path.to_string()));
+    }
+
+    let mut json_path = String::from("$");
+    for segment in trimmed.split('.') {
+        if segment.is_empty() || segment.contains('"') {
+            return Err(OsError::InvalidMetadataPath(path.to_string()));
+        }
+        json_path.push_str(&format!(".\"{}\"", segment));
+    }
+    Ok(json_path)
+}
+
+pub fn get_metadata(conn: &Connection, task_id: &TaskId) -> Result<Option<Value>> {
+    let data: Option<String> = conn
+        .query_row(
+            "SELECT data FROM task_metadata WHERE task_id = ?1",
+            params![task_id],
+            |row| row.get(0),
+        )
+        .optional()?;
+    Ok(data.map(|d| serde_json::from_str(&d)).transpose()?)
+}
+
+/// Metadata objects for every task that has one.
+pub fn all_metadata(conn: &Connection) -> Result<HashMap<TaskId, Value>> {
+    let mut stmt = conn.prepare("SELECT task_id, data FROM task_metadata")?;
+    let rows = stmt
+        .query_map([], |row| {
+            Ok((row.get::<_, TaskId>(0)?, row.get::<_, String>(1)?))
+        })?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+    rows.into_iter()
+        .map(|(id, data)| Ok((id, serde_json::from_str(&data)?)))
+        .collect()
+}
+
+/// Tasks whose value at `filter.path` equals `filter.value` (JSON equality, as in `list_tasks`).
+pub fn matching_task_ids(conn: &Connection, filter: &MetadataFilter) -> Result<HashSet<TaskId>> {
+    let mut stmt =
+        conn.prepare("SELECT task_id FROM task_metadata WHERE (data -> ?1) = json(?2)")?;
+    let ids = stmt
+        .query_map(
+            params![
+                to_json_path(&filter.path)?,
+                serde_json::to_string(&filter.value)?
+            ],
+            |row| row.get(0),
+        )?
+        .collect::<rusqlite::Result<HashSet<TaskId>>>()?;
+    Ok(ids)
+}
+
+/// Get the value at `path`, or None if the task has no metadata or the path is unset.
+pub fn get_metadata_path(conn: &Connection, task_id: &TaskId, path: &str) -> Result<Option<Value>> {
+    let json_path = to_json_path(path)?;
+    let data: Option<Option<String>> = conn
+        .query_row(
+            "SELECT data -> ?2 FROM task_metadata WHERE task_id = ?1",
+            params![task_id, json_path],
+            |row| row.get(0),
+        )
+        .optional()?;
+    Ok(data
+        .flatten()
+        .map(|d| serde_json::from_str(&d))
+        .transpose()?)
+}
+
+/// Set `path` to `value`, creating the metadata object and intermediate objects as needed.
+pub fn set_metadata_path(
+    conn: &Connection,
+    task_id: &TaskId,
+    path: &str,
+    value: &Value,
+) -> Result<()> {
+    let json_path = to_json_path(path)?;
+    let value_json = serde_json::to_string(value)?;
+    conn.execute(
+        r#"
+        INSERT INTO task_metadata (task_id, data) VALUES (?1, json_set('{}', ?2, json(?3)))
+        ON CONFLICT(task_id) DO UPDATE SET data = json_set(data, ?2, json(?3))
+        "#,
+        params![task_id, json_path, value_json],
+    )?;
+    Ok(())
+}
+
+/// Remove `path`. Drops the row entirely once the object is empty.
+pub fn unset_metadata_path(conn: &Connection, task_id: &TaskId, path: &str) -> Result<()> {
+    let json_path = to_json_path(path)?;
+    conn.execute(
+        "UPDATE task_metadata SET data = json_remove(data, ?2) WHERE task_id = ?1",
+        params![task_id, json_path],
+    )?;
+    conn.execute(
+        "DELETE FROM task_metadata WHERE task_id = ?1 AND data = '{}'",
+        params![task_id],
+    )?;
+    Ok(())
+}
+
+/// Replace the whole metadata object (used by import).
+pub fn replace_metadata(conn: &Connection, task_id: &TaskId, data: &Value) -> Result<()> {
+    conn.execute(
+        r#"
+        INSERT INTO task_metadata (task_id, data) VALUES (?1, json(?2))
+        ON CONFLICT(task_id) DO UPDATE SET data = json(?2)
+        "#,
+        params![task_id, serde_json::to_string(data)?],
+    )?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::schema::init_schema;
+    use crate::db::task_repo::create_task;
+    use crate::types::CreateTaskInput;
+    use serde_json::json;
+
+    fn setup() -> (Connection, TaskId) {
+        let conn = Connection::open_in_memory().unwrap();
+        init_schema(&conn).unwrap();
+        let task = create_task(
+            &conn,
+            &CreateTaskInput {
+                description: "test task".to_string(),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        (conn, task.id)
+    }
+
+    #[test]
+    fn test_to_json_path() {
+        assert_eq!(to_json_path("area").unwrap(), "$.\"area\"");
+        assert_eq!(to_json_path("$.pr.url").unwrap(), "$.\"pr\".\"url\"");
+        assert!(to_json_path("").is_err());
+        assert!(to_json_path("a..b").is_err());
+    }
+
+    #[test]
+    fn test_set_and_get_nested_path() {
+        let (conn, id) = setup();
+        set_metadata_path(&conn, &id, "pr.url", &json!("https://example.com/1")).unwrap();
+        set_metadata_path(&conn, &id, "size", &json!(3)).unwrap();
+
+        assert_eq!(
+            get_metadata(&conn, &id).unwrap(),
+            Some(json!({"pr": {"url": "https://example.com/1"}, "size": 3}))
+        );
+        assert_eq!(
+            get_metadata_path(&conn, &id, "pr.url").unwrap(),
+            Some(json!("https://example.com/1"))
+        );
+        assert_eq!(get_metadata_path(&conn, &id, "missing").unwrap(), None);
+    }
+
+    #[test]
+    fn test_unset_removes_row_when_empty() {
+        let (conn, id) = setup();
+        set_metadata_path(&conn, &id, "files", &json!(["a.rs", "b.rs"])).unwrap();
+        unset_metadata_path(&conn, &id, "files").unwrap();
+        assert_eq!(get_metadata(&conn, &id).unwrap(), None);
+    }
+}
@@ -1,7 +1,21 @@
+pub mod claim_repo;
+pub mod comment_repo;
+pub mod event_repo;
+pub mod label_repo;
 pub mod learning_repo;
+pub mod link_repo;
+pub mod metadata_repo;
+pub mod repo_repo;
+pub mod retry;
 pub mod schema;
+pub mod search_repo;
+pub mod session_repo;
+pub mod settings_repo;
 pub mod task_repo;
+pub mod trash_repo;
 
+pub use comment_repo::Comment;
 pub use learning_repo::Learning;
+pub use link_repo::TaskLink;
 pub use schema::open_db;
 pub use task_repo::get_blockers;
@@ -0,0 +1,153 @@
+//! Registered repositories stored in `repos`.
+//!
+//! One database can track tasks for several repositories. Each task may name
+//! the repository it belongs to (`tasks.repo_id`); workflow operations open
+//! that repository instead of the one containing the working directory.
+
+use chrono::{DateTime, Utc};
+use rusqlite::{params, Connection, OptionalExtension, Row};
+use serde::{Deserialize, Serialize};
+
+use crate::error::Result;
+use crate::id::RepoId;
+use crate::vcs::VcsType;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Repo {
+    pub id: RepoId,
+    /// Absolute path of the repository root
+    pub path: String,
+    /// Short name accepted wherever a repo id is (`--repo api`)
+    pub name: String,
+    pub vcs_type: VcsType,
+    pub created_at: DateTime<Utc>,
+}
+
+fn row_to_repo(row: &Row) -> rusqlite::Result<Repo> {
+    Ok(Repo {
+        id: row.get("id")?,
+        path: row.get("path")?,
+        name: row.get("name")?,
+        vcs_type: match row.get::<_, String>("vcs_type")?.as_str() {
+            "git" => VcsType::Git,
+            _ => VcsType::None,
+        },
+        created_at: row
+            .get::<_, String>("created_at")
+            .ok()
+            .and_then(|s| DateTime::parse_from_rfc3339(&s).ok())
+            .map(|dt| dt.with_timezone(&Utc))
+            .unwrap_or(DateTime::<Utc>::MIN_UTC),
+    })
+}
+
+pub fn insert_repo(conn: &Connection, repo: &Repo) -> Result<()> {
+    let vcs_type = match repo.vcs_type {
+        VcsType::Git => "git",
+        VcsType::None => "none",
+    };
+    conn.execute(
+        r#"
+        INSERT INTO repos (id, path, name, vcs_type, created_at)
+        VALUES (?1, ?2, ?3, ?4, ?5)
+        "#,
+        params![
+            &repo.id,
+            repo.path,
+            repo.name,
+            vcs_type,
+            repo.created_at.to_rfc3339(),
+        ],
+    )?;
+    Ok(())
+}
+
+pub fn get_repo(conn: &Connection, id: &RepoId) -> Result<Option<Repo>> {
+    let repo = conn
+        .query_row(
+            "SELECT * FROM repos WHERE id = ?1",
+            params![id],
+            row_to_repo,
+        )
+        .optional()?;
+    Ok(repo)
+}
+
+/// Repo registered under `name` or at `path` (exact match on either).
+pub fn find_repo(conn: &Connection, name: &str, path: &str) -> Result<Option<Repo>> {
+    let repo = conn
+        .query_row(
+            "SELECT * FROM repos WHERE name = ?1 OR path = ?2",
+            params![name, path],
+            row_to_repo,
+        )
+        .optional()?;
+    Ok(repo)
+}
+
+/// Every registered repo, by name.
+pub fn list_repos(conn: &Connection) -> Result<Vec<Repo>> {
+    let mut stmt = conn.prepare("SELECT * FROM repos ORDER BY name")?;
+    let repos = stmt
+        .query_map([], row_to_repo)?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+    Ok(repos)
+}
+
+/// Number of tasks in `id` that are neither completed nor cancelled.
+pub fn count_active_tasks(conn: &Connection, id: &RepoId) -> Result<usize> {
+    let count: i64 = conn.query_row(
+        "SELECT COUNT(*) FROM tasks WHERE repo_id = ?1 AND completed = 0 AND cancelled = 0",
+        params![id],
+        |row| row.get(0),
+    )?;
+    Ok(count as usize)
+}
+
+/// Unregister a repo; its remaining tasks lose their `repo_id`.
+pub fn delete_repo(conn: &Connection, id: &RepoId) -> Result<()> {
+    conn.execute("DELETE FROM repos WHERE id = ?1", params![id])?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::schema::init_schema;
+
+    #[test]
+    fn test_repos_round_trip_and_detach_tasks_on_delete() {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch("PRAGMA foreign_keys = ON;").unwrap();
+        init_schema(&conn).unwrap();
+        let repo = Repo {
+            id: RepoId::new(),
+            path: "/code/api".to_string(),
+            name: "api".to_string(),
+            vcs_type: VcsType::Git,
+            created_at: Utc::now(),
+        };
+        insert_repo(&conn, &repo).unwrap();
+
+        assert_eq!(get_repo(&conn, &repo.id).unwrap().unwrap().name, "api");
+        assert!(find_repo(&conn, "api", "").unwrap().is_some());
+        assert!(find_repo(&conn, "", "/code/api").unwrap().is_some());
+        assert!(find_repo(&conn, "web", "/code/web").unwrap().is_none());
+
+        conn.execute(
+            "INSERT INTO tasks (id, description, created_at, updated_at, repo_id)
+             VALUES ('task_01JQAZ0000000000000000000A', 'T', '', '', ?1)",
+            params![&repo.id],
+        )
+        .unwrap();
+        assert_eq!(count_active_tasks(&conn, &repo.id).unwrap(), 1);
+
+        delete_repo(&conn, &repo.id).unwrap();
+        assert!(list_repos(&conn).unwrap().is_empty());
+        let repo_id: Option<String> = conn
+            .query_row("SELECT repo_id FROM tasks", [], |row| row.get(0))
+            .unwrap();
+        assert!(repo_id.is_none());
+    }
+}
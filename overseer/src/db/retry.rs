@@ -0,0 +1,133 @@
+//! Retrying writes that lose a lock race.
+//!
+//! Connections wait up to [`BUSY_TIMEOUT`](super::schema::BUSY_TIMEOUT) for a
+//! lock on their own, but SQLite returns SQLITE_BUSY immediately when waiting
+//! could deadlock (two deferred transactions both upgrading to a write). Those
+//! operations are safe to rerun from the top once the other writer finishes.
+
+use std::thread;
+use std::time::Duration;
+
+use rusqlite::{Connection, ErrorCode, Transaction, TransactionBehavior};
+
+use crate::error::{OsError, Result};
+
+/// Attempts after the first before giving up with `DatabaseBusy`
+const MAX_RETRIES: u32 = 5;
+/// Delay before the first retry; doubles on every further attempt
+const INITIAL_BACKOFF: Duration = Duration::from_millis(25);
+
+/// True for SQLITE_BUSY / SQLITE_LOCKED.
+pub fn is_busy(e: &OsError) -> bool {
+    matches!(
+        e,
+        OsError::Database(err)
+            if matches!(
+                err.sqlite_error_code(),
+                Some(ErrorCode::DatabaseBusy | ErrorCode::DatabaseLocked)
+            )
+    )
+}
+
+/// Run `op`, rerunning it with exponential backoff while it fails because the
+/// database is locked. `op` must be safe to repeat: either one transaction or
+/// idempotent statements. Persistent contention ends in `DatabaseBusy`.
+pub fn with_retry<T>(mut op: impl FnMut() -> Result<T>) -> Result<T> {
+    let mut backoff = INITIAL_BACKOFF;
+    for _ in 0..MAX_RETRIES {
+        match op() {
+            Err(e) if is_busy(&e) => {
+                thread::sleep(backoff);
+                backoff *= 2;
+            }
+            other => return other,
+        }
+    }
+    op().map_err(|e| {
+        if is_busy(&e) {
+            OsError::DatabaseBusy
+        } else {
+            e
+        }
+    })
+}
+
+/// Open an IMMEDIATE transaction, retrying while another writer holds the
+/// lock. Always safe to repeat, since nothing has run in the transaction yet.
+pub fn begin_immediate(conn: &Connection) -> Result<Transaction<'_>> {
+    with_retry(|| {
+        Ok(Transaction::new_unchecked(
+            conn,
+            TransactionBehavior::Immediate,
+        )?)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rusqlite::ffi;
+
+    fn busy() -> OsError {
+        OsError::Database(rusqlite::Error::SqliteFailure(
+            ffi::Error::new(ffi::SQLITE_BUSY),
+            None,
+        ))
+    }
+
+    #[test]
+    fn test_retries_until_lock_is_free() {
+        let mut calls = 0;
+        let value = with_retry(|| {
+            calls += 1;
+            if calls < 3 {
+                Err(busy())
+            } else {
+                Ok(calls)
+            }
+        })
+        .unwrap();
+        assert_eq!(value, 3);
+    }
+
+    #[test]
+    fn test_gives_up_with_database_busy() {
+        let mut calls = 0;
+        let result: Result<()> = with_retry(|| {
+            calls += 1;
+            Err(busy())
+        });
+        assert!(matches!(result, Err(OsError::DatabaseBusy)));
+        assert_eq!(calls, MAX_RETRIES + 1);
+    }
+
+    #[test]
+    fn test_other_errors_are_not_retried() {
+        let mut calls = 0;
+        let result: Result<()> = with_retry(|| {
+            calls += 1;
+            Err(OsError::ParentCycle)
+        });
+        assert!(matches!(result, Err(OsError::ParentCycle)));
+        assert_eq!(calls, 1);
+    }
+
+    #[test]
+    fn test_begin_immediate_waits_out_another_writer() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("test.db");
+        let holder = Connection::open(&path).unwrap();
+        let conn = Connection::open(&path).unwrap();
+        // Fail fast instead of waiting, so the lock is only outlasted by retrying
+        conn.busy_timeout(Duration::ZERO).unwrap();
+
+        holder.execute_batch("BEGIN IMMEDIATE").unwrap();
+        let release = thread::spawn(move || {
+            thread::sleep(INITIAL_BACKOFF);
+            holder.execute_batch("COMMIT").unwrap();
+        });
+        let tx = begin_immediate(&conn).unwrap();
+        tx.commit().unwrap();
+        release.join().unwrap();
+    }
+}
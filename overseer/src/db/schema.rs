@@ -1,115 +1,130 @@
-use rusqlite::Connection;
+//! Schema versioning.
+//!
+//! `PRAGMA user_version` holds the schema version. New databases get the
+//! [`BASELINE`] DDL; everything after that is an entry in [`MIGRATIONS`], applied
+//! in order, one transaction per entry. Existing databases are backed up before
+//! they are upgraded, and databases from a newer binary are refused.
 
-use crate::error::Result;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
 
-const SCHEMA_VERSION: i32 = 5;
+use rusqlite::{Connection, OpenFlags};
+use serde::{Deserialize, Serialize};
 
-pub fn init_schema(conn: &Connection) -> Result<()> {
-    let current_version: i32 = conn.pragma_query_value(None, "user_version", |row| row.get(0))?;
+use crate::error::{OsError, Result};
 
-    if current_version == 0 {
-        conn.execute_batch(
-            r#"
-            CREATE TABLE IF NOT EXISTS tasks (
-                id TEXT PRIMARY KEY CHECK (id LIKE 'task_%'),
-                parent_id TEXT REFERENCES tasks(id) ON DELETE CASCADE CHECK (parent_id LIKE 'task_%'),
-                description TEXT NOT NULL,
-                context TEXT NOT NULL DEFAULT '',
-                result TEXT,
-                priority INTEGER NOT NULL DEFAULT 1,
-                completed INTEGER NOT NULL DEFAULT 0,
-                completed_at TEXT,
-                created_at TEXT NOT NULL,
-                updated_at TEXT NOT NULL,
-                commit_sha TEXT,
-                started_at TEXT,
-                bookmark TEXT,
-                start_commit TEXT,
-                cancelled INTEGER NOT NULL DEFAULT 0,
-                cancelled_at TEXT,
-                archived INTEGER NOT NULL DEFAULT 0,
-                archived_at TEXT
-            );
-
-            CREATE TABLE IF NOT EXISTS learnings (
-                id TEXT PRIMARY KEY CHECK (id LIKE 'lrn_%'),
-                task_id TEXT NOT NULL REFERENCES tasks(id) ON DELETE CASCADE CHECK (task_id LIKE 'task_%'),
-                content TEXT NOT NULL,
-                source_task_id TEXT CHECK (source_task_id LIKE 'task_%'),
-                created_at TEXT NOT NULL
-            );
+/// How a migration changes the database.
+pub enum MigrationStep {
+    /// Batch of SQL statements (no BEGIN/COMMIT; the runner owns the transaction)
+    Sql(&'static str),
+    /// Code for changes that need inspection or conditional logic
+    Rust(fn(&Connection) -> Result<()>),
+}
 
-            CREATE TABLE IF NOT EXISTS task_blockers (
-                task_id TEXT NOT NULL REFERENCES tasks(id) ON DELETE CASCADE CHECK (task_id LIKE 'task_%'),
-                blocker_id TEXT NOT NULL REFERENCES tasks(id) ON DELETE CASCADE CHECK (blocker_id LIKE 'task_%'),
-                PRIMARY KEY (task_id, blocker_id)
-            );
+pub struct Migration {
+    /// `user_version` after this migration has run
+    pub version: i32,
+    pub id: &'static str,
+    pub description: &'static str,
+    pub step: MigrationStep,
+}
 
-            CREATE TABLE IF NOT EXISTS task_metadata (
-                task_id TEXT PRIMARY KEY REFERENCES tasks(id) ON DELETE CASCADE,
-                data TEXT NOT NULL
-            );
+/// Full schema for new databases, equivalent to running migrations 2-5 on a v1 database.
+pub const BASELINE: Migration = Migration {
+    version: 5,
+    id: "0005_baseline",
+    description: "Create tasks, learnings, blockers and metadata tables",
+    step: MigrationStep::Sql(
+        r#"
+        CREATE TABLE IF NOT EXISTS tasks (
+            id TEXT PRIMARY KEY CHECK (id LIKE 'task_%'),
+            parent_id TEXT REFERENCES tasks(id) ON DELETE CASCADE CHECK (parent_id LIKE 'task_%'),
+            description TEXT NOT NULL,
+            context TEXT NOT NULL DEFAULT '',
+            result TEXT,
+            priority INTEGER NOT NULL DEFAULT 1,
+            completed INTEGER NOT NULL DEFAULT 0,
+            completed_at TEXT,
+            created_at TEXT NOT NULL,
+            updated_at TEXT NOT NULL,
+            commit_sha TEXT,
+            started_at TEXT,
+            bookmark TEXT,
+            start_commit TEXT,
+            cancelled INTEGER NOT NULL DEFAULT 0,
+            cancelled_at TEXT,
+            archived INTEGER NOT NULL DEFAULT 0,
+            archived_at TEXT
+        );
 
-            CREATE INDEX IF NOT EXISTS idx_tasks_parent ON tasks(parent_id);
-            CREATE INDEX IF NOT EXISTS idx_tasks_completed ON tasks(completed);
-            CREATE INDEX IF NOT EXISTS idx_tasks_cancelled ON tasks(cancelled);
-            CREATE INDEX IF NOT EXISTS idx_tasks_archived ON tasks(archived);
-            CREATE INDEX IF NOT EXISTS idx_learnings_task ON learnings(task_id);
-            CREATE INDEX IF NOT EXISTS idx_blockers_blocker ON task_blockers(blocker_id);
-            CREATE UNIQUE INDEX IF NOT EXISTS idx_learnings_unique 
-                ON learnings(task_id, source_task_id, content);
-            CREATE INDEX IF NOT EXISTS idx_learnings_task_created 
-                ON learnings(task_id, created_at);
+        CREATE TABLE IF NOT EXISTS learnings (
+            id TEXT PRIMARY KEY CHECK (id LIKE 'lrn_%'),
+            task_id TEXT NOT NULL REFERENCES tasks(id) ON DELETE CASCADE CHECK (task_id LIKE 'task_%'),
+            content TEXT NOT NULL,
+            source_task_id TEXT CHECK (source_task_id LIKE 'task_%'),
+            created_at TEXT NOT NULL
+        );
 
-            PRAGMA journal_mode = WAL;
-            "#,
-        )?;
+        CREATE TABLE IF NOT EXISTS task_blockers (
+            task_id TEXT NOT NULL REFERENCES tasks(id) ON DELETE CASCADE CHECK (task_id LIKE 'task_%'),
+            blocker_id TEXT NOT NULL REFERENCES tasks(id) ON DELETE CASCADE CHECK (blocker_id LIKE 'task_%'),
+            PRIMARY KEY (task_id, blocker_id)
+        );
 
-        // Fresh database gets the latest schema version
-        conn.pragma_update(None, "user_version", SCHEMA_VERSION)?;
-    }
+        CREATE TABLE IF NOT EXISTS task_metadata (
+            task_id TEXT PRIMARY KEY REFERENCES tasks(id) ON DELETE CASCADE,
+            data TEXT NOT NULL
+        );
 
-    // Track version for sequential migrations
-    let mut version = current_version;
+        CREATE INDEX IF NOT EXISTS idx_tasks_parent ON tasks(parent_id);
+        CREATE INDEX IF NOT EXISTS idx_tasks_completed ON tasks(completed);
+        CREATE INDEX IF NOT EXISTS idx_tasks_cancelled ON tasks(cancelled);
+        CREATE INDEX IF NOT EXISTS idx_tasks_archived ON tasks(archived);
+        CREATE INDEX IF NOT EXISTS idx_learnings_task ON learnings(task_id);
+        CREATE INDEX IF NOT EXISTS idx_blockers_blocker ON task_blockers(blocker_id);
+        CREATE UNIQUE INDEX IF NOT EXISTS idx_learnings_unique
+            ON learnings(task_id, source_task_id, content);
+        CREATE INDEX IF NOT EXISTS idx_learnings_task_created
+            ON learnings(task_id, created_at);
+        "#,
+    ),
+};
 
-    // Migration for existing databases at version 1
-    if version == 1 {
-        conn.execute_batch(
+/// Ordered upgrade steps. Append only: never edit or reorder a released entry.
+pub const MIGRATIONS: &[Migration] = &[
+    Migration {
+        version: 2,
+        id: "0002_task_vcs_columns",
+        description: "Add bookmark and start_commit to tasks",
+        step: MigrationStep::Sql(
             r#"
-            BEGIN;
             ALTER TABLE tasks ADD COLUMN bookmark TEXT;
             ALTER TABLE tasks ADD COLUMN start_commit TEXT;
-            COMMIT;
             "#,
-        )?;
-        conn.pragma_update(None, "user_version", 2)?;
-        version = 2;
-    }
-
-    // Migration for version 2 -> 3: Add unique index for learning bubbling idempotency
+        ),
+    },
     // Also backfill source_task_id where NULL (set to task_id as origin)
-    if version == 2 {
-        conn.execute_batch(
+    Migration {
+        version: 3,
+        id: "0003_learning_bubbling_index",
+        description: "Unique index for idempotent learning bubbling",
+        step: MigrationStep::Sql(
             r#"
-            BEGIN;
             UPDATE learnings SET source_task_id = task_id WHERE source_task_id IS NULL;
-            CREATE UNIQUE INDEX IF NOT EXISTS idx_learnings_unique 
+            CREATE UNIQUE INDEX IF NOT EXISTS idx_learnings_unique
                 ON learnings(task_id, source_task_id, content);
-            CREATE INDEX IF NOT EXISTS idx_learnings_task_created 
+            CREATE INDEX IF NOT EXISTS idx_learnings_task_created
                 ON learnings(task_id, created_at);
-            COMMIT;
             "#,
-        )?;
-        conn.pragma_update(None, "user_version", 3)?;
-        version = 3;
-    }
-
-    // Migration for version 3 -> 4: Simplify priorities from 1-5 to 0-2
+        ),
+    },
     // p0 = highest (was 1), p1 = default/medium (was 2-3), p2 = lowest (was 4-5)
-    if version == 3 {
-        conn.execute_batch(
+    Migration {
+        version: 4,
+        id: "0004_three_priorities",
+        description: "Simplify priorities from 1-5 to 0-2",
+        step: MigrationStep::Sql(
             r#"
-            BEGIN;
             UPDATE tasks
             SET priority =
               CASE
@@ -117,45 +132,890 @@ pub fn init_schema(conn: &Connection) -> Result<()> {
                 WHEN priority <= 3 THEN 1
                 ELSE 2
               END;
-            COMMIT;
             "#,
-        )?;
-        conn.pragma_update(None, "user_version", 4)?;
-        version = 4;
-    }
-
-    // Migration for version 4 -> 5: Add cancelled and archived columns
+        ),
+    },
     // cancelled: marks task as cancelled (incomplete tasks only)
     // archived: hides completed/cancelled tasks from default views
-    if version == 4 {
-        conn.execute_batch(
+    Migration {
+        version: 5,
+        id: "0005_cancel_archive",
+        description: "Add cancelled and archived columns",
+        step: MigrationStep::Sql(
             r#"
-            BEGIN;
             ALTER TABLE tasks ADD COLUMN cancelled INTEGER NOT NULL DEFAULT 0;
             ALTER TABLE tasks ADD COLUMN cancelled_at TEXT;
             ALTER TABLE tasks ADD COLUMN archived INTEGER NOT NULL DEFAULT 0;
             ALTER TABLE tasks ADD COLUMN archived_at TEXT;
             CREATE INDEX IF NOT EXISTS idx_tasks_cancelled ON tasks(cancelled);
             CREATE INDEX IF NOT EXISTS idx_tasks_archived ON tasks(archived);
-            COMMIT;
+            "#,
+        ),
+    },
+    // Standalone (not external-content) tables kept in sync by triggers; 0023 rekeys
+    // them on rowid, 0024 on key tables that survive VACUUM.
+    Migration {
+        version: 6,
+        id: "0006_full_text_search",
+        description: "FTS5 index over tasks and learnings",
+        step: MigrationStep::Sql(
+            r#"
+            CREATE VIRTUAL TABLE IF NOT EXISTS tasks_fts USING fts5(
+                task_id UNINDEXED,
+                description,
+                context,
+                result,
+                tokenize = 'unicode61 remove_diacritics 2'
+            );
+            CREATE VIRTUAL TABLE IF NOT EXISTS learnings_fts USING fts5(
+                learning_id UNINDEXED,
+                content,
+                tokenize = 'unicode61 remove_diacritics 2'
+            );
+
+            CREATE TRIGGER IF NOT EXISTS tasks_fts_insert AFTER INSERT ON tasks BEGIN
+                INSERT INTO tasks_fts (task_id, description, context, result)
+                VALUES (new.id, new.description, new.context, new.result);
+            END;
+            CREATE TRIGGER IF NOT EXISTS tasks_fts_update
+            AFTER UPDATE OF description, context, result ON tasks BEGIN
+                DELETE FROM tasks_fts WHERE task_id = old.id;
+                INSERT INTO tasks_fts (task_id, description, context, result)
+                VALUES (new.id, new.description, new.context, new.result);
+            END;
+            CREATE TRIGGER IF NOT EXISTS tasks_fts_delete AFTER DELETE ON tasks BEGIN
+                DELETE FROM tasks_fts WHERE task_id = old.id;
+            END;
+
+            CREATE TRIGGER IF NOT EXISTS learnings_fts_insert AFTER INSERT ON learnings BEGIN
+                INSERT INTO learnings_fts (learning_id, content) VALUES (new.id, new.content);
+            END;
+            CREATE TRIGGER IF NOT EXISTS learnings_fts_update AFTER UPDATE OF content ON learnings BEGIN
+                DELETE FROM learnings_fts WHERE learning_id = old.id;
+                INSERT INTO learnings_fts (learning_id, content) VALUES (new.id, new.content);
+            END;
+            CREATE TRIGGER IF NOT EXISTS learnings_fts_delete AFTER DELETE ON learnings BEGIN
+                DELETE FROM learnings_fts WHERE learning_id = old.id;
+            END;
+
+            INSERT INTO tasks_fts (task_id, description, context, result)
+                SELECT id, description, context, result FROM tasks;
+            INSERT INTO learnings_fts (learning_id, content)
+                SELECT id, content FROM learnings;
+            "#,
+        ),
+    },
+    // seq is AUTOINCREMENT so it never goes backwards, even after deletes.
+    // task_id has no foreign key: events outlive the tasks they describe.
+    Migration {
+        version: 7,
+        id: "0007_event_log",
+        description: "Append-only event log",
+        step: MigrationStep::Sql(
+            r#"
+            CREATE TABLE IF NOT EXISTS events (
+                seq INTEGER PRIMARY KEY AUTOINCREMENT,
+                id TEXT NOT NULL UNIQUE CHECK (id LIKE 'evt_%'),
+                at TEXT NOT NULL,
+                correlation_id TEXT,
+                source TEXT NOT NULL,
+                event_type TEXT NOT NULL,
+                task_id TEXT,
+                payload TEXT NOT NULL
+            );
+            CREATE INDEX IF NOT EXISTS idx_events_task ON events(task_id, seq);
+            CREATE INDEX IF NOT EXISTS idx_events_type ON events(event_type, seq);
+            CREATE INDEX IF NOT EXISTS idx_events_corr ON events(correlation_id);
+            "#,
+        ),
+    },
+    // task_metadata only ever existed in the baseline DDL, so databases created
+    // before v5 and upgraded never got it.
+    Migration {
+        version: 8,
+        id: "0008_task_metadata_for_upgraded_dbs",
+        description: "Create task_metadata if an upgraded database lacks it",
+        step: MigrationStep::Rust(ensure_task_metadata),
+    },
+    Migration {
+        version: 9,
+        id: "0009_task_labels",
+        description: "Task labels",
+        step: MigrationStep::Sql(
+            r#"
+            CREATE TABLE IF NOT EXISTS task_labels (
+                task_id TEXT NOT NULL REFERENCES tasks(id) ON DELETE CASCADE CHECK (task_id LIKE 'task_%'),
+                label TEXT NOT NULL,
+                PRIMARY KEY (task_id, label)
+            );
+            CREATE INDEX IF NOT EXISTS idx_task_labels_label ON task_labels(label);
+            "#,
+        ),
+    },
+    Migration {
+        version: 10,
+        id: "0010_task_claims",
+        description: "Task claims (agent leases)",
+        step: MigrationStep::Sql(
+            r#"
+            CREATE TABLE IF NOT EXISTS task_claims (
+                task_id TEXT PRIMARY KEY REFERENCES tasks(id) ON DELETE CASCADE CHECK (task_id LIKE 'task_%'),
+                agent TEXT NOT NULL,
+                claimed_at TEXT NOT NULL,
+                expires_at TEXT NOT NULL
+            );
+            "#,
+        ),
+    },
+    // A trigger rather than `revision = revision + 1` in each UPDATE, so repairs
+    // and any future writer bump it too. The WHEN clause stops it re-firing on
+    // its own update.
+    Migration {
+        version: 11,
+        id: "0011_task_revision",
+        description: "Task revision counter for optimistic concurrency",
+        step: MigrationStep::Sql(
+            r#"
+            ALTER TABLE tasks ADD COLUMN revision INTEGER NOT NULL DEFAULT 0;
+            CREATE TRIGGER IF NOT EXISTS tasks_bump_revision
+            AFTER UPDATE ON tasks FOR EACH ROW WHEN new.revision = old.revision BEGIN
+                UPDATE tasks SET revision = old.revision + 1 WHERE id = new.id;
+            END;
+            "#,
+        ),
+    },
+    Migration {
+        version: 12,
+        id: "0012_settings",
+        description: "Per-database settings (hierarchy depth)",
+        step: MigrationStep::Sql(
+            r#"
+            CREATE TABLE IF NOT EXISTS settings (
+                key TEXT PRIMARY KEY,
+                value TEXT NOT NULL
+            );
+            "#,
+        ),
+    },
+    Migration {
+        version: 13,
+        id: "0013_task_rank",
+        description: "Manual sibling order (os task move)",
+        // Existing rows share rank 0, so they keep their created_at order
+        step: MigrationStep::Sql(
+            r#"
+            ALTER TABLE tasks ADD COLUMN rank INTEGER NOT NULL DEFAULT 0;
+            "#,
+        ),
+    },
+    Migration {
+        version: 14,
+        id: "0014_task_schedule",
+        description: "Task due dates and deferral",
+        step: MigrationStep::Sql(
+            r#"
+            ALTER TABLE tasks ADD COLUMN due_at TEXT;
+            ALTER TABLE tasks ADD COLUMN defer_until TEXT;
+            "#,
+        ),
+    },
+    // The partial unique index keeps at most one running session per task
+    Migration {
+        version: 15,
+        id: "0015_task_sessions",
+        description: "Work sessions for time tracking",
+        step: MigrationStep::Sql(
+            r#"
+            CREATE TABLE IF NOT EXISTS task_sessions (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                task_id TEXT NOT NULL REFERENCES tasks(id) ON DELETE CASCADE CHECK (task_id LIKE 'task_%'),
+                agent TEXT,
+                started_at TEXT NOT NULL,
+                ended_at TEXT
+            );
+            CREATE INDEX IF NOT EXISTS idx_task_sessions_task ON task_sessions(task_id);
+            CREATE UNIQUE INDEX IF NOT EXISTS idx_task_sessions_running
+                ON task_sessions(task_id) WHERE ended_at IS NULL;
+            "#,
+        ),
+    },
+    Migration {
+        version: 16,
+        id: "0016_task_comments",
+        description: "Timestamped comment threads on tasks",
+        step: MigrationStep::Sql(
+            r#"
+            CREATE TABLE IF NOT EXISTS task_comments (
+                id TEXT PRIMARY KEY CHECK (id LIKE 'cmt_%'),
+                task_id TEXT NOT NULL REFERENCES tasks(id) ON DELETE CASCADE CHECK (task_id LIKE 'task_%'),
+                author TEXT,
+                body TEXT NOT NULL,
+                created_at TEXT NOT NULL
+            );
+            CREATE INDEX IF NOT EXISTS idx_task_comments_task ON task_comments(task_id, created_at);
+            "#,
+        ),
+    },
+    Migration {
+        version: 17,
+        id: "0017_task_links",
+        description: "Typed links from tasks to files, code ranges, URLs, commits and issues",
+        step: MigrationStep::Sql(
+            r#"
+            CREATE TABLE IF NOT EXISTS task_links (
+                id TEXT PRIMARY KEY CHECK (id LIKE 'lnk_%'),
+                task_id TEXT NOT NULL REFERENCES tasks(id) ON DELETE CASCADE CHECK (task_id LIKE 'task_%'),
+                kind TEXT NOT NULL CHECK (kind IN ('file', 'fileRange', 'url', 'commit', 'issue')),
+                target TEXT NOT NULL,
+                line_start INTEGER,
+                line_end INTEGER,
+                note TEXT,
+                created_at TEXT NOT NULL,
+                CHECK ((kind = 'fileRange') = (line_start IS NOT NULL AND line_end IS NOT NULL)),
+                CHECK (line_start IS NULL OR (line_start >= 1 AND line_end >= line_start))
+            );
+            CREATE INDEX IF NOT EXISTS idx_task_links_task ON task_links(task_id);
+            CREATE INDEX IF NOT EXISTS idx_task_links_target ON task_links(target);
+            "#,
+        ),
+    },
+    // A trashed subtree is kept as one JSON snapshot keyed by its root;
+    // trash_tasks maps every task in it back to the root (and its branch)
+    Migration {
+        version: 18,
+        id: "0018_trash",
+        description: "Trash bin for deleted task subtrees",
+        step: MigrationStep::Sql(
+            r#"
+            CREATE TABLE IF NOT EXISTS trash (
+                id TEXT PRIMARY KEY CHECK (id LIKE 'task_%'),
+                parent_id TEXT CHECK (parent_id LIKE 'task_%'),
+                description TEXT NOT NULL,
+                deleted_at TEXT NOT NULL,
+                snapshot TEXT NOT NULL
+            );
+            CREATE INDEX IF NOT EXISTS idx_trash_deleted_at ON trash(deleted_at);
+            CREATE TABLE IF NOT EXISTS trash_tasks (
+                task_id TEXT PRIMARY KEY CHECK (task_id LIKE 'task_%'),
+                trash_id TEXT NOT NULL REFERENCES trash(id) ON DELETE CASCADE,
+                bookmark TEXT
+            );
+            CREATE INDEX IF NOT EXISTS idx_trash_tasks_trash ON trash_tasks(trash_id);
+            "#,
+        ),
+    },
+    // Tasks without a repo keep using the repository of the working directory
+    Migration {
+        version: 19,
+        id: "0019_repos",
+        description: "Registered repositories and the repo each task belongs to",
+        step: MigrationStep::Sql(
+            r#"
+            CREATE TABLE IF NOT EXISTS repos (
+                id TEXT PRIMARY KEY CHECK (id LIKE 'repo_%'),
+                path TEXT NOT NULL UNIQUE,
+                name TEXT NOT NULL UNIQUE,
+                vcs_type TEXT NOT NULL,
+                created_at TEXT NOT NULL
+            );
+            ALTER TABLE tasks ADD COLUMN repo_id TEXT REFERENCES repos(id) ON DELETE SET NULL;
+            CREATE INDEX IF NOT EXISTS idx_tasks_repo ON tasks(repo_id);
+            "#,
+        ),
+    },
+    Migration {
+        version: 20,
+        id: "0020_task_integration",
+        description: "Branch a task started from and the commit its branch was integrated as",
+        step: MigrationStep::Sql(
+            r#"
+            ALTER TABLE tasks ADD COLUMN start_branch TEXT;
+            ALTER TABLE tasks ADD COLUMN integration_commit TEXT;
+            "#,
+        ),
+    },
+    Migration {
+        version: 21,
+        id: "0021_task_revs",
+        description: "Revision a task branch was stacked on and its tip when completed",
+        step: MigrationStep::Sql(
+            r#"
+            ALTER TABLE tasks ADD COLUMN base_rev TEXT;
+            ALTER TABLE tasks ADD COLUMN head_rev TEXT;
+            "#,
+        ),
+    },
+    Migration {
+        version: 22,
+        id: "0022_task_worktree",
+        description: "Linked worktree a task's branch is checked out in",
+        step: MigrationStep::Sql(
+            r#"
+            ALTER TABLE tasks ADD COLUMN worktree TEXT;
+            "#,
+        ),
+    },
+    // Rebuilds the 0006 indexes keyed on the indexed row's rowid: the triggers'
+    // deletes become rowid lookups instead of scans of an UNINDEXED id column.
+    Migration {
+        version: 23,
+        id: "0023_fts_rowid",
+        description: "Key the full-text indexes on rowid",
+        step: MigrationStep::Sql(
+            r#"
+            DROP TRIGGER IF EXISTS tasks_fts_insert;
+            DROP TRIGGER IF EXISTS tasks_fts_update;
+            DROP TRIGGER IF EXISTS tasks_fts_delete;
+            DROP TRIGGER IF EXISTS learnings_fts_insert;
+            DROP TRIGGER IF EXISTS learnings_fts_update;
+            DROP TRIGGER IF EXISTS learnings_fts_delete;
+            DROP TABLE IF EXISTS tasks_fts;
+            DROP TABLE IF EXISTS learnings_fts;
+
+            CREATE VIRTUAL TABLE tasks_fts USING fts5(
+                description,
+                context,
+                result,
+                tokenize = 'unicode61 remove_diacritics 2'
+            );
+            CREATE VIRTUAL TABLE learnings_fts USING fts5(
+                content,
+                tokenize = 'unicode61 remove_diacritics 2'
+            );
+
+            CREATE TRIGGER tasks_fts_insert AFTER INSERT ON tasks BEGIN
+                INSERT INTO tasks_fts (rowid, description, context, result)
+                VALUES (new.rowid, new.description, new.context, new.result);
+            END;
+            CREATE TRIGGER tasks_fts_update
+            AFTER UPDATE OF description, context, result ON tasks BEGIN
+                DELETE FROM tasks_fts WHERE rowid = old.rowid;
+                INSERT INTO tasks_fts (rowid, description, context, result)
+                VALUES (new.rowid, new.description, new.context, new.result);
+            END;
+            CREATE TRIGGER tasks_fts_delete AFTER DELETE ON tasks BEGIN
+                DELETE FROM tasks_fts WHERE rowid = old.rowid;
+            END;
+
+            CREATE TRIGGER learnings_fts_insert AFTER INSERT ON learnings BEGIN
+                INSERT INTO learnings_fts (rowid, content) VALUES (new.rowid, new.content);
+            END;
+            CREATE TRIGGER learnings_fts_update AFTER UPDATE OF content ON learnings BEGIN
+                DELETE FROM learnings_fts WHERE rowid = old.rowid;
+                INSERT INTO learnings_fts (rowid, content) VALUES (new.rowid, new.content);
+            END;
+            CREATE TRIGGER learnings_fts_delete AFTER DELETE ON learnings BEGIN
+                DELETE FROM learnings_fts WHERE rowid = old.rowid;
+            END;
+
+            INSERT INTO tasks_fts (rowid, description, context, result)
+                SELECT rowid, description, context, result FROM tasks;
+            INSERT INTO learnings_fts (rowid, content)
+                SELECT rowid, content FROM learnings;
+            "#,
+        ),
+    },
+    // tasks and learnings have TEXT primary keys, so VACUUM may renumber their
+    // rowids and detach the 0023 index rows. Each index row now gets its rowid
+    // from a key table whose INTEGER PRIMARY KEY is stable, looked up by id.
+    Migration {
+        version: 24,
+        id: "0024_fts_keys",
+        description: "Key the full-text indexes on task and learning ids",
+        step: MigrationStep::Sql(
+            r#"
+            DROP TRIGGER IF EXISTS tasks_fts_insert;
+            DROP TRIGGER IF EXISTS tasks_fts_update;
+            DROP TRIGGER IF EXISTS tasks_fts_delete;
+            DROP TRIGGER IF EXISTS learnings_fts_insert;
+            DROP TRIGGER IF EXISTS learnings_fts_update;
+            DROP TRIGGER IF EXISTS learnings_fts_delete;
+            DELETE FROM tasks_fts;
+            DELETE FROM learnings_fts;
+
+            CREATE TABLE tasks_fts_keys (
+                fts_rowid INTEGER PRIMARY KEY,
+                task_id TEXT NOT NULL UNIQUE
+            );
+            CREATE TABLE learnings_fts_keys (
+                fts_rowid INTEGER PRIMARY KEY,
+                learning_id TEXT NOT NULL UNIQUE
+            );
+
+            CREATE TRIGGER tasks_fts_insert AFTER INSERT ON tasks BEGIN
+                INSERT INTO tasks_fts_keys (task_id) VALUES (new.id);
+                INSERT INTO tasks_fts (rowid, description, context, result)
+                SELECT fts_rowid, new.description, new.context, new.result
+                FROM tasks_fts_keys WHERE task_id = new.id;
+            END;
+            CREATE TRIGGER tasks_fts_update
+            AFTER UPDATE OF description, context, result ON tasks BEGIN
+                DELETE FROM tasks_fts
+                WHERE rowid = (SELECT fts_rowid FROM tasks_fts_keys WHERE task_id = old.id);
+                INSERT INTO tasks_fts (rowid, description, context, result)
+                SELECT fts_rowid, new.description, new.context, new.result
+                FROM tasks_fts_keys WHERE task_id = old.id;
+            END;
+            CREATE TRIGGER tasks_fts_delete AFTER DELETE ON tasks BEGIN
+                DELETE FROM tasks_fts
+                WHERE rowid = (SELECT fts_rowid FROM tasks_fts_keys WHERE task_id = old.id);
+                DELETE FROM tasks_fts_keys WHERE task_id = old.id;
+            END;
+
+            CREATE TRIGGER learnings_fts_insert AFTER INSERT ON learnings BEGIN
+                INSERT INTO learnings_fts_keys (learning_id) VALUES (new.id);
+                INSERT INTO learnings_fts (rowid, content)
+                SELECT fts_rowid, new.content FROM learnings_fts_keys WHERE learning_id = new.id;
+            END;
+            CREATE TRIGGER learnings_fts_update AFTER UPDATE OF content ON learnings BEGIN
+                DELETE FROM learnings_fts
+                WHERE rowid = (SELECT fts_rowid FROM learnings_fts_keys WHERE learning_id = old.id);
+                INSERT INTO learnings_fts (rowid, content)
+                SELECT fts_rowid, new.content FROM learnings_fts_keys WHERE learning_id = old.id;
+            END;
+            CREATE TRIGGER learnings_fts_delete AFTER DELETE ON learnings BEGIN
+                DELETE FROM learnings_fts
+                WHERE rowid = (SELECT fts_rowid FROM learnings_fts_keys WHERE learning_id = old.id);
+                DELETE FROM learnings_fts_keys WHERE learning_id = old.id;
+            END;
+
+            INSERT INTO tasks_fts_keys (task_id) SELECT id FROM tasks;
+            INSERT INTO tasks_fts (rowid, description, context, result)
+                SELECT k.fts_rowid, t.description, t.context, t.result
+                FROM tasks t JOIN tasks_fts_keys k ON k.task_id = t.id;
+            INSERT INTO learnings_fts_keys (learning_id) SELECT id FROM learnings;
+            INSERT INTO learnings_fts (rowid, content)
+                SELECT k.fts_rowid, l.content
+                FROM learnings l JOIN learnings_fts_keys k ON k.learning_id = l.id;
+            "#,
+        ),
+    },
+];
+
+pub const SCHEMA_VERSION: i32 = MIGRATIONS[MIGRATIONS.len() - 1].version;
+
+fn ensure_task_metadata(conn: &Connection) -> Result<()> {
+    let exists: bool = conn.query_row(
+        "SELECT EXISTS (SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = 'task_metadata')",
+        [],
+        |row| row.get(0),
+    )?;
+    if !exists {
+        conn.execute_batch(
+            r#"
+            CREATE TABLE task_metadata (
+                task_id TEXT PRIMARY KEY REFERENCES tasks(id) ON DELETE CASCADE,
+                data TEXT NOT NULL
+            );
             "#,
         )?;
-        conn.pragma_update(None, "user_version", 5)?;
-        version = 5;
     }
+    Ok(())
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum MigrationKind {
+    Sql,
+    Rust,
+}
+
+/// Summary of a migration for status/dry-run output.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MigrationInfo {
+    pub version: i32,
+    pub id: String,
+    pub description: String,
+    pub kind: MigrationKind,
+}
+
+impl From<&Migration> for MigrationInfo {
+    fn from(m: &Migration) -> Self {
+        Self {
+            version: m.version,
+            id: m.id.to_string(),
+            description: m.description.to_string(),
+            kind: match m.step {
+                MigrationStep::Sql(_) => MigrationKind::Sql,
+                MigrationStep::Rust(_) => MigrationKind::Rust,
+            },
+        }
+    }
+}
+
+/// Result of bringing a database up to [`SCHEMA_VERSION`].
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MigrationReport {
+    pub from_version: i32,
+    pub to_version: i32,
+    pub applied: Vec<MigrationInfo>,
+    /// Copy of the database taken before upgrading (existing file databases only)
+    pub backup: Option<PathBuf>,
+}
+
+pub fn current_version(conn: &Connection) -> Result<i32> {
+    Ok(conn.pragma_query_value(None, "user_version", |row| row.get(0))?)
+}
 
-    // Suppress unused variable warning - version is used for sequential migration chaining
-    let _ = version;
+/// Migrations that would run on a database at `version` (0 = new database).
+pub fn pending_migrations(version: i32) -> Vec<&'static Migration> {
+    let baseline = (version == 0).then_some(&BASELINE);
+    let from = if version == 0 {
+        BASELINE.version
+    } else {
+        version
+    };
+    baseline
+        .into_iter()
+        .chain(MIGRATIONS.iter().filter(|m| m.version > from))
+        .collect()
+}
+
+fn ensure_supported(version: i32) -> Result<()> {
+    if version > SCHEMA_VERSION {
+        return Err(OsError::SchemaTooNew {
+            found: version,
+            supported: SCHEMA_VERSION,
+        });
+    }
+    Ok(())
+}
 
+fn apply(conn: &Connection, migration: &Migration) -> Result<()> {
+    match migration.step {
+        MigrationStep::Sql(sql) => conn.execute_batch(sql)?,
+        MigrationStep::Rust(step) => step(conn)?,
+    }
+    conn.pragma_update(None, "user_version", migration.version)?;
     Ok(())
 }
 
-pub fn open_db(path: &std::path::Path) -> Result<Connection> {
+/// Apply all pending migrations, each in its own transaction.
+pub fn migrate(conn: &Connection) -> Result<MigrationReport> {
+    let from_version = current_version(conn)?;
+    ensure_supported(from_version)?;
+
+    let pending = pending_migrations(from_version);
+    let backup = if from_version > 0 && !pending.is_empty() {
+        backup_before_upgrade(conn, from_version)?
+    } else {
+        None
+    };
+
+    for migration in &pending {
+        let tx = conn.unchecked_transaction()?;
+        apply(&tx, migration)?;
+        tx.commit()?;
+    }
+    if from_version == 0 {
+        // Cannot change journal mode inside a transaction
+        conn.execute_batch("PRAGMA journal_mode = WAL;")?;
+    }
+
+    Ok(MigrationReport {
+        from_version,
+        to_version: current_version(conn)?,
+        applied: pending.into_iter().map(MigrationInfo::from).collect(),
+        backup,
+    })
+}
+
+/// Run all pending migrations in one transaction and roll it back.
+/// Returns the migrations that would be applied; errors if any of them fails.
+pub fn dry_run(conn: &Connection) -> Result<Vec<MigrationInfo>> {
+    let from_version = current_version(conn)?;
+    ensure_supported(from_version)?;
+
+    let pending = pending_migrations(from_version);
+    let tx = conn.unchecked_transaction()?;
+    for migration in &pending {
+        apply(&tx, migration)?;
+    }
+    tx.rollback()?;
+
+    Ok(pending.into_iter().map(MigrationInfo::from).collect())
+}
+
+/// Copy the database next to itself as `<db>.v<version>-<timestamp>.bak`.
+fn backup_before_upgrade(conn: &Connection, version: i32) -> Result<Option<PathBuf>> {
+    let Some(path) = conn.path().filter(|p| !p.is_empty()) else {
+        return Ok(None);
+    };
+    let backup = PathBuf::from(format!(
+        "{}.v{}-{}.bak",
+        path,
+        version,
+        chrono::Utc::now().format("%Y%m%dT%H%M%S%.3f")
+    ));
+    conn.execute("VACUUM INTO ?1", [backup.to_string_lossy()])?;
+    Ok(Some(backup))
+}
+
+#[allow(dead_code)] // Used in tests; the CLI goes through open_and_migrate
+pub fn init_schema(conn: &Connection) -> Result<()> {
+    migrate(conn).map(|_| ())
+}
+
+/// How long SQLite itself waits on a locked database before returning
+/// SQLITE_BUSY; see `db::retry` for what happens after that.
+pub const BUSY_TIMEOUT: Duration = Duration::from_secs(5);
+
+pub fn open_db(path: &Path) -> Result<Connection> {
+    open_and_migrate(path).map(|(conn, _)| conn)
+}
+
+/// Like [`open_db`], also returning what the migrator did.
+pub fn open_and_migrate(path: &Path) -> Result<(Connection, MigrationReport)> {
     if let Some(parent) = path.parent() {
         std::fs::create_dir_all(parent)?;
     }
     let conn = Connection::open(path)?;
+    conn.busy_timeout(BUSY_TIMEOUT)?;
     conn.execute_batch("PRAGMA foreign_keys = ON;")?;
-    init_schema(&conn)?;
+    let report = migrate(&conn)?;
+    Ok((conn, report))
+}
+
+/// Open an existing database without migrating it. Fails if the file does not exist.
+pub fn open_db_unmigrated(path: &Path) -> Result<Connection> {
+    let conn = Connection::open_with_flags(
+        path,
+        OpenFlags::SQLITE_OPEN_READ_WRITE | OpenFlags::SQLITE_OPEN_NO_MUTEX,
+    )?;
+    conn.busy_timeout(BUSY_TIMEOUT)?;
     Ok(conn)
 }
+
+/// Schema as shipped at version 1 (before any migration existed).
+#[cfg(test)]
+const V1_SCHEMA: &str = r#"
+    CREATE TABLE tasks (
+        id TEXT PRIMARY KEY CHECK (id LIKE 'task_%'),
+        parent_id TEXT REFERENCES tasks(id) ON DELETE CASCADE CHECK (parent_id LIKE 'task_%'),
+        description TEXT NOT NULL,
+        context TEXT NOT NULL DEFAULT '',
+        result TEXT,
+        priority INTEGER NOT NULL DEFAULT 1,
+        completed INTEGER NOT NULL DEFAULT 0,
+        completed_at TEXT,
+        created_at TEXT NOT NULL,
+        updated_at TEXT NOT NULL,
+        commit_sha TEXT,
+        started_at TEXT
+    );
+    CREATE TABLE learnings (
+        id TEXT PRIMARY KEY CHECK (id LIKE 'lrn_%'),
+        task_id TEXT NOT NULL REFERENCES tasks(id) ON DELETE CASCADE CHECK (task_id LIKE 'task_%'),
+        content TEXT NOT NULL,
+        source_task_id TEXT CHECK (source_task_id LIKE 'task_%'),
+        created_at TEXT NOT NULL
+    );
+    CREATE TABLE task_blockers (
+        task_id TEXT NOT NULL REFERENCES tasks(id) ON DELETE CASCADE CHECK (task_id LIKE 'task_%'),
+        blocker_id TEXT NOT NULL REFERENCES tasks(id) ON DELETE CASCADE CHECK (blocker_id LIKE 'task_%'),
+        PRIMARY KEY (task_id, blocker_id)
+    );
+    CREATE INDEX idx_tasks_parent ON tasks(parent_id);
+    CREATE INDEX idx_tasks_completed ON tasks(completed);
+    CREATE INDEX idx_learnings_task ON learnings(task_id);
+    CREATE INDEX idx_blockers_blocker ON task_blockers(blocker_id);
+
+    INSERT INTO tasks (id, description, priority, created_at, updated_at)
+        VALUES ('task_01JQAZ0000000000000000000A', 'Legacy task', 5, '2024-01-01T00:00:00Z', '2024-01-01T00:00:00Z');
+    INSERT INTO learnings (id, task_id, content, created_at)
+        VALUES ('lrn_01JQAZ0000000000000000000A', 'task_01JQAZ0000000000000000000A', 'Legacy learning', '2024-01-01T00:00:00Z');
+    PRAGMA user_version = 1;
+"#;
+
+/// Test fixture: bring an empty database to the state it had at `version`,
+/// the v1 schema (with a legacy task and learning) plus the migrations up to it.
+#[cfg(test)]
+pub(crate) fn create_at_version(conn: &Connection, version: i32) {
+    conn.execute_batch(V1_SCHEMA).unwrap();
+    for migration in MIGRATIONS.iter().filter(|m| m.version <= version) {
+        apply(conn, migration).unwrap();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Database as it looked at `version`: the v1 schema plus migrations up to it.
+    fn database_at(version: i32) -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        create_at_version(&conn, version);
+        conn
+    }
+
+    /// Tables with their columns, plus index and trigger names, in a comparable form.
+    fn fingerprint(conn: &Connection) -> Vec<String> {
+        let mut stmt = conn
+            .prepare("SELECT type, name FROM sqlite_master WHERE name NOT LIKE 'sqlite_%'")
+            .unwrap();
+        let objects: Vec<(String, String)> = stmt
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))
+            .unwrap()
+            .collect::<rusqlite::Result<_>>()
+            .unwrap();
+
+        let mut out = Vec::new();
+        for (kind, name) in objects {
+            if kind == "table" {
+                let mut cols = conn
+                    .prepare(&format!("SELECT name, type, \"notnull\", dflt_value, pk FROM pragma_table_info('{name}')"))
+                    .unwrap();
+                let mut columns: Vec<String> = cols
+                    .query_map([], |row| {
+                        Ok(format!(
+                            "{}:{}:{}:{:?}:{}",
+                            row.get::<_, String>(0)?,
+                            row.get::<_, String>(1)?,
+                            row.get::<_, i32>(2)?,
+                            row.get::<_, Option<String>>(3)?,
+                            row.get::<_, i32>(4)?
+                        ))
+                    })
+                    .unwrap()
+                    .collect::<rusqlite::Result<_>>()
+                    .unwrap();
+                columns.sort();
+                out.push(format!("table {name} [{}]", columns.join(", ")));
+            } else {
+                out.push(format!("{kind} {name}"));
+            }
+        }
+        out.sort();
+        out
+    }
+
+    #[test]
+    fn test_registry_is_ordered_and_unique() {
+        let mut ids = std::collections::HashSet::new();
+        let mut expected = 2;
+        for migration in MIGRATIONS {
+            assert_eq!(migration.version, expected, "{}", migration.id);
+            assert!(ids.insert(migration.id), "duplicate id {}", migration.id);
+            expected += 1;
+        }
+        assert_eq!(SCHEMA_VERSION, expected - 1);
+        assert!(MIGRATIONS.iter().any(|m| m.version == BASELINE.version));
+    }
+
+    #[test]
+    fn test_fresh_database_is_at_latest_version() {
+        let conn = Connection::open_in_memory().unwrap();
+        let report = migrate(&conn).unwrap();
+        assert_eq!(report.from_version, 0);
+        assert_eq!(report.to_version, SCHEMA_VERSION);
+        assert_eq!(report.applied[0].id, BASELINE.id);
+        assert!(report.backup.is_none());
+
+        // Opening again is a no-op
+        assert!(migrate(&conn).unwrap().applied.is_empty());
+    }
+
+    #[test]
+    fn test_migrates_every_historical_version() {
+        let fresh = Connection::open_in_memory().unwrap();
+        migrate(&fresh).unwrap();
+        let expected = fingerprint(&fresh);
+
+        for version in 1..=SCHEMA_VERSION {
+            let conn = database_at(version);
+            assert_eq!(current_version(&conn).unwrap(), version);
+
+            let report = migrate(&conn).unwrap();
+            assert_eq!(report.from_version, version);
+            assert_eq!(report.to_version, SCHEMA_VERSION);
+            assert_eq!(
+                fingerprint(&conn),
+                expected,
+                "schema after migrating from v{version} differs from a fresh database"
+            );
+
+            // Data survives, priority 5 was remapped to p2, and search was backfilled
+            let (priority, description): (i32, String) = conn
+                .query_row("SELECT priority, description FROM tasks", [], |row| {
+                    Ok((row.get(0)?, row.get(1)?))
+                })
+                .unwrap();
+            assert_eq!(description, "Legacy task");
+            assert_eq!(priority, 2);
+            let source: String = conn
+                .query_row("SELECT source_task_id FROM learnings", [], |row| row.get(0))
+                .unwrap();
+            assert_eq!(source, "task_01JQAZ0000000000000000000A");
+            let indexed: i64 = conn
+                .query_row(
+                    "SELECT COUNT(*) FROM tasks_fts WHERE tasks_fts MATCH 'legacy'",
+                    [],
+                    |row| row.get(0),
+                )
+                .unwrap();
+            assert_eq!(indexed, 1);
+        }
+    }
+
+    #[test]
+    fn test_refuses_newer_schema() {
+        let conn = Connection::open_in_memory().unwrap();
+        migrate(&conn).unwrap();
+        conn.pragma_update(None, "user_version", SCHEMA_VERSION + 1)
+            .unwrap();
+
+        let err = migrate(&conn).unwrap_err();
+        assert!(
+            matches!(err, OsError::SchemaTooNew { found, supported }
+                if found == SCHEMA_VERSION + 1 && supported == SCHEMA_VERSION),
+            "got {err:?}"
+        );
+        assert!(err.to_string().contains("upgrade os"));
+        assert!(dry_run(&conn).is_err());
+    }
+
+    #[test]
+    fn test_backup_taken_before_upgrade() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("tasks.db");
+        {
+            let conn = Connection::open(&path).unwrap();
+            create_at_version(&conn, 1);
+        }
+
+        let (_conn, report) = open_and_migrate(&path).unwrap();
+        let backup = report.backup.expect("upgrade should back up");
+        assert!(backup
+            .file_name()
+            .unwrap()
+            .to_string_lossy()
+            .starts_with("tasks.db.v1-"));
+
+        let copy = Connection::open(&backup).unwrap();
+        assert_eq!(current_version(&copy).unwrap(), 1);
+        let count: i64 = copy
+            .query_row("SELECT COUNT(*) FROM tasks", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(count, 1);
+
+        // Up-to-date databases are not backed up again
+        let (_conn, report) = open_and_migrate(&path).unwrap();
+        assert!(report.backup.is_none());
+    }
+
+    #[test]
+    fn test_dry_run_rolls_back() {
+        let conn = database_at(3);
+        let before = fingerprint(&conn);
+
+        let pending = dry_run(&conn).unwrap();
+        assert_eq!(pending.first().unwrap().version, 4);
+        assert_eq!(pending.last().unwrap().version, SCHEMA_VERSION);
+        assert!(pending.iter().any(|m| m.kind == MigrationKind::Rust));
+
+        assert_eq!(current_version(&conn).unwrap(), 3);
+        assert_eq!(fingerprint(&conn), before);
+    }
+}
@@ -0,0 +1,306 @@
+//! Full-text search over tasks and learnings (FTS5 tables from schema v6).
+//!
+//! User queries are treated as plain words, not FTS5 syntax: every word becomes a
+//! quoted prefix term and all terms must match. Results are ordered by bm25.
+
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+
+use crate::db::learning_repo::{row_to_learning, Learning};
+use crate::error::Result;
+use crate::id::TaskId;
+
+/// Markers wrapped around matched terms in snippets
+pub const HIGHLIGHT_START: &str = "**";
+pub const HIGHLIGHT_END: &str = "**";
+
+/// Tokens of context kept on each side of a match in snippets
+const SNIPPET_TOKENS: i32 = 16;
+
+/// A ranked match. `score` is the negated bm25 rank, so higher is better.
+/// Serializes as the matched item's fields plus `score` and `snippet`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SearchHit<T> {
+    #[serde(flatten)]
+    pub item: T,
+    pub score: f64,
+    pub snippet: String,
+}
+
+/// Build an FTS5 MATCH expression from free text, or None if it has no searchable words.
+pub fn to_match_query(query: &str) -> Option<String> {
+    let terms: Vec<String> = query
+        .split_whitespace()
+        .filter(|word| word.chars().any(char::is_alphanumeric))
+        .map(|word| format!("\"{}\"*", word.replace('"', "\"\"")))
+        .collect();
+
+    if terms.is_empty() {
+        None
+    } else {
+        Some(terms.join(" "))
+    }
+}
+
+/// Search task description, context and result. Description matches weigh the most.
+pub fn search_tasks(
+    conn: &Connection,
+    query: &str,
+    include_archived: bool,
+    limit: usize,
+) -> Result<Vec<SearchHit<TaskId>>> {
+    let Some(match_query) = to_match_query(query) else {
+        return Ok(Vec::new());
+    };
+
+    let mut stmt = conn.prepare(
+        r#"
+        SELECT t.id,
+               bm25(tasks_fts, 10.0, 4.0, 2.0) AS rank,
+               snippet(tasks_fts, -1, ?2, ?3, '…', ?4) AS snippet
+        FROM tasks_fts
+        JOIN tasks_fts_keys k ON k.fts_rowid = tasks_fts.rowid
+        JOIN tasks t ON t.id = k.task_id
+        WHERE tasks_fts MATCH ?1 AND (?5 OR t.archived = 0)
+        ORDER BY rank, t.priority, t.created_at
+        LIMIT ?6
+        "#,
+    )?;
+
+    let hits = stmt
+        .query_map(
+            params![
+                match_query,
+                HIGHLIGHT_START,
+                HIGHLIGHT_END,
+                SNIPPET_TOKENS,
+                include_archived,
+                limit as i64
+            ],
+            |row| {
+                Ok(SearchHit {
+                    item: row.get(0)?,
+                    score: -row.get::<_, f64>(1)?,
+                    snippet: row.get(2)?,
+                })
+            },
+        )?
+        .collect::<std::result::Result<Vec<_>, _>>()?;
+    Ok(hits)
+}
+
+/// Search learning content. Learnings on archived tasks are skipped unless requested.
+pub fn search_learnings(
+    conn: &Connection,
+    query: &str,
+    include_archived: bool,
+    limit: usize,
+) -> Result<Vec<SearchHit<Learning>>> {
+    let Some(match_query) = to_match_query(query) else {
+        return Ok(Vec::new());
+    };
+
+    let mut stmt = conn.prepare(
+        r#"
+        SELECT l.*,
+               bm25(learnings_fts) AS rank,
+               snippet(learnings_fts, 0, ?2, ?3, '…', ?4) AS snippet
+        FROM learnings_fts
+        JOIN learnings_fts_keys k ON k.fts_rowid = learnings_fts.rowid
+        JOIN learnings l ON l.id = k.learning_id
+        JOIN tasks t ON t.id = l.task_id
+        WHERE learnings_fts MATCH ?1 AND (?5 OR t.archived = 0)
+        ORDER BY rank, l.created_at
+        LIMIT ?6
+        "#,
+    )?;
+
+    let hits = stmt
+        .query_map(
+            params![
+                match_query,
+                HIGHLIGHT_START,
+                HIGHLIGHT_END,
+                SNIPPET_TOKENS,
+                include_archived,
+                limit as i64
+            ],
+            |row| {
+                Ok(SearchHit {
+                    item: row_to_learning(row)?,
+                    score: -row.get::<_, f64>("rank")?,
+                    snippet: row.get("snippet")?,
+                })
+            },
+        )?
+        .collect::<std::result::Result<Vec<_>, _>>()?;
+    Ok(hits)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::schema::init_schema;
+    use crate::db::{learning_repo, task_repo};
+    use crate::types::{CreateTaskInput, UpdateTaskInput};
+
+    fn setup() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch("PRAGMA foreign_keys = ON;").unwrap();
+        init_schema(&conn).unwrap();
+        conn
+    }
+
+    fn create(conn: &Connection, description: &str, context: &str) -> TaskId {
+        task_repo::create_task(
+            conn,
+            &CreateTaskInput {
+                description: description.to_string(),
+                context: Some(context.to_string()),
+                ..Default::default()
+            },
+        )
+        .unwrap()
+        .id
+    }
+
+    #[test]
+    fn test_to_match_query() {
+        assert_eq!(
+            to_match_query("auth token").unwrap(),
+            "\"auth\"* \"token\"*"
+        );
+        assert_eq!(
+            to_match_query("say \"hi\"").unwrap(),
+            "\"say\"* \"\"\"hi\"\"\"*"
+        );
+        assert_eq!(to_match_query("  - ! "), None);
+    }
+
+    #[test]
+    fn test_description_match_ranks_above_context_match() {
+        let conn = setup();
+        let in_context = create(&conn, "Refactor module", "touches the login flow");
+        let in_description = create(&conn, "Fix login redirect", "");
+        create(&conn, "Unrelated", "nothing here");
+
+        let hits = search_tasks(&conn, "login", false, 10).unwrap();
+        let ids: Vec<_> = hits.iter().map(|h| h.item.clone()).collect();
+        assert_eq!(ids, vec![in_description, in_context]);
+        assert!(hits[0].snippet.contains("**login**"));
+        assert!(hits[0].score >= hits[1].score);
+    }
+
+    #[test]
+    fn test_prefix_match_and_index_follows_updates() {
+        let conn = setup();
+        let id = create(&conn, "Authentication overhaul", "");
+        assert_eq!(search_tasks(&conn, "auth", false, 10).unwrap().len(), 1);
+
+        task_repo::update_task(
+            &conn,
+            &id,
+            &UpdateTaskInput {
+                description: Some("Billing overhaul".to_string()),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        assert!(search_tasks(&conn, "auth", false, 10).unwrap().is_empty());
+        assert_eq!(search_tasks(&conn, "billing", false, 10).unwrap().len(), 1);
+
+        task_repo::delete_task(&conn, &id).unwrap();
+        assert!(search_tasks(&conn, "billing", false, 10)
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn test_archived_tasks_excluded_by_default() {
+        let conn = setup();
+        let id = create(&conn, "Old migration work", "");
+        task_repo::complete_task(&conn, &id, None, None).unwrap();
+        task_repo::archive_task(&conn, &id).unwrap();
+
+        assert!(search_tasks(&conn, "migration", false, 10)
+            .unwrap()
+            .is_empty());
+        assert_eq!(search_tasks(&conn, "migration", true, 10).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_search_learnings() {
+        let conn = setup();
+        let id = create(&conn, "Task", "");
+        learning_repo::add_learning(&conn, &id, "SQLite needs WAL for concurrency", None).unwrap();
+        learning_repo::add_learning(&conn, &id, "Prefer small commits", None).unwrap();
+
+        let hits = search_learnings(&conn, "wal", false, 10).unwrap();
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].item.content, "SQLite needs WAL for concurrency");
+        assert!(hits[0].snippet.contains("**WAL**"));
+    }
+
+    #[test]
+    fn test_migration_backfills_existing_rows() {
+        let conn = setup();
+        let id = create(&conn, "Pre-existing task", "");
+        learning_repo::add_learning(&conn, &id, "Pre-existing learning", None).unwrap();
+
+        // Drop the index as it stood before 0024, then let that migration rebuild it
+        conn.execute_batch(
+            r#"
+            DROP TABLE tasks_fts_keys;
+            DROP TABLE learnings_fts_keys;
+            PRAGMA user_version = 23;
+            "#,
+        )
+        .unwrap();
+        init_schema(&conn).unwrap();
+
+        assert_eq!(search_tasks(&conn, "existing", false, 10).unwrap().len(), 1);
+        assert_eq!(
+            search_learnings(&conn, "existing", false, 10)
+                .unwrap()
+                .len(),
+            1
+        );
+    }
+
+    #[test]
+    fn test_search_survives_vacuum() {
+        let dir = tempfile::tempdir().unwrap();
+        let conn = Connection::open(dir.path().join("test.db")).unwrap();
+        conn.execute_batch("PRAGMA foreign_keys = ON;").unwrap();
+        init_schema(&conn).unwrap();
+
+        // Deleting early rows leaves rowid gaps for VACUUM to close
+        let gone: Vec<_> = (0..5)
+            .map(|i| create(&conn, &format!("Filler {}", i), ""))
+            .collect();
+        let id = create(&conn, "Vacuumed task", "");
+        learning_repo::add_learning(&conn, &id, "Vacuumed learning", None).unwrap();
+        for filler in &gone {
+            task_repo::delete_task(&conn, filler).unwrap();
+        }
+        conn.execute_batch("VACUUM;").unwrap();
+
+        let hits = search_tasks(&conn, "vacuumed", false, 10).unwrap();
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].item, id);
+        let hits = search_learnings(&conn, "vacuumed", false, 10).unwrap();
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].item.content, "Vacuumed learning");
+
+        // Trigger deletes still find the index row
+        task_repo::delete_task(&conn, &id).unwrap();
+        let left: i64 = conn
+            .query_row(
+                "SELECT (SELECT COUNT(*) FROM tasks_fts) + (SELECT COUNT(*) FROM learnings_fts)",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(left, 0);
+    }
+}
@@ -0,0 +1,166 @@
+//! Work sessions stored in `task_sessions`.
+//!
+//! A session opens when work on a task starts or resumes and closes when it
+//! pauses, completes or is cancelled. A running session has no `ended_at`;
+//! the schema allows one per task.
+
+use chrono::{DateTime, Utc};
+use rusqlite::{params, Connection, OptionalExtension, Row};
+
+use crate::error::Result;
+use crate::id::TaskId;
+use crate::types::TaskSession;
+
+fn parse_timestamp(s: &str) -> DateTime<Utc> {
+    DateTime::parse_from_rfc3339(s)
+        .map(|dt| dt.with_timezone(&Utc))
+        // Unparsable rows read as empty sessions rather than failing reads
+        .unwrap_or(DateTime::<Utc>::MIN_UTC)
+}
+
+fn row_to_session(row: &Row) -> rusqlite::Result<TaskSession> {
+    let started_at = parse_timestamp(&row.get::<_, String>("started_at")?);
+    Ok(TaskSession {
+        id: row.get("id")?,
+        task_id: row.get("task_id")?,
+        agent: row.get("agent")?,
+        started_at,
+        ended_at: row
+            .get::<_, Option<String>>("ended_at")?
+            .map(|s| parse_timestamp(&s).max(started_at)),
+    })
+}
+
+const SESSION_COLUMNS: &str = "id, task_id, agent, started_at, ended_at";
+
+/// The running session on `task_id`, if any.
+pub fn running_session(conn: &Connection, task_id: &TaskId) -> Result<Option<TaskSession>> {
+    Ok(conn
+        .query_row(
+            &format!(
+                "SELECT {SESSION_COLUMNS} FROM task_sessions WHERE task_id = ?1 AND ended_at IS NULL"
+            ),
+            params![task_id],
+            row_to_session,
+        )
+        .optional()?)
+}
+
+/// Open a session on `task_id` at `now`. Fails on the unique index if one is
+/// already running; check [`running_session`] first.
+pub fn start_session(
+    conn: &Connection,
+    task_id: &TaskId,
+    agent: Option<&str>,
+    now: DateTime<Utc>,
+) -> Result<TaskSession> {
+    conn.execute(
+        "INSERT INTO task_sessions (task_id, agent, started_at) VALUES (?1, ?2, ?3)",
+        params![task_id, agent, now.to_rfc3339()],
+    )?;
+    Ok(TaskSession {
+        id: conn.last_insert_rowid(),
+        task_id: task_id.clone(),
+        agent: agent.map(str::to_string),
+        started_at: now,
+        ended_at: None,
+    })
+}
+
+/// Close the running session on `task_id` at `now`; None if none was running.
+pub fn end_session(
+    conn: &Connection,
+    task_id: &TaskId,
+    now: DateTime<Utc>,
+) -> Result<Option<TaskSession>> {
+    let Some(mut session) = running_session(conn, task_id)? else {
+        return Ok(None);
+    };
+    // A clock that went backwards still yields an empty, not negative, session
+    let ended_at = now.max(session.started_at);
+    conn.execute(
+        "UPDATE task_sessions SET ended_at = ?1 WHERE id = ?2",
+        params![ended_at.to_rfc3339(), session.id],
+    )?;
+    session.ended_at = Some(ended_at);
+    Ok(Some(session))
+}
+
+/// Sessions on `task_id`, oldest first.
+pub fn list_task_sessions(conn: &Connection, task_id: &TaskId) -> Result<Vec<TaskSession>> {
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {SESSION_COLUMNS} FROM task_sessions WHERE task_id = ?1 ORDER BY started_at, id"
+    ))?;
+    let sessions = stmt
+        .query_map(params![task_id], row_to_session)?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+    Ok(sessions)
+}
+
+/// Insert a session verbatim, preserving its id.
+pub fn insert_session_row(conn: &Connection, session: &TaskSession) -> Result<()> {
+    conn.execute(
+        "INSERT INTO task_sessions (id, task_id, agent, started_at, ended_at) VALUES (?1, ?2, ?3, ?4, ?5)",
+        params![
+            session.id,
+            &session.task_id,
+            session.agent,
+            session.started_at.to_rfc3339(),
+            session.ended_at.map(|dt| dt.to_rfc3339()),
+        ],
+    )?;
+    Ok(())
+}
+
+/// Every session, oldest first.
+pub fn list_sessions(conn: &Connection) -> Result<Vec<TaskSession>> {
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {SESSION_COLUMNS} FROM task_sessions ORDER BY started_at, id"
+    ))?;
+    let sessions = stmt
+        .query_map([], row_to_session)?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+    Ok(sessions)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::schema::init_schema;
+    use crate::db::task_repo::create_task;
+    use crate::types::CreateTaskInput;
+    use chrono::Duration;
+
+    #[test]
+    fn test_one_running_session_per_task() {
+        let conn = Connection::open_in_memory().unwrap();
+        init_schema(&conn).unwrap();
+        let task_id = create_task(
+            &conn,
+            &CreateTaskInput {
+                description: "test task".to_string(),
+                ..Default::default()
+            },
+        )
+        .unwrap()
+        .id;
+        let now = Utc::now();
+
+        let first = start_session(&conn, &task_id, Some("a"), now).unwrap();
+        assert!(start_session(&conn, &task_id, None, now).is_err());
+        assert_eq!(running_session(&conn, &task_id).unwrap(), Some(first));
+
+        let ended = end_session(&conn, &task_id, now + Duration::minutes(5))
+            .unwrap()
+            .unwrap();
+        assert_eq!(ended.ended_at, Some(now + Duration::minutes(5)));
+        assert!(end_session(&conn, &task_id, now).unwrap().is_none());
+
+        start_session(&conn, &task_id, None, now + Duration::hours(1)).unwrap();
+        let sessions = list_sessions(&conn).unwrap();
+        assert_eq!(sessions.len(), 2);
+        assert_eq!(sessions[0].agent.as_deref(), Some("a"));
+        assert!(!sessions[0].is_running());
+        assert!(sessions[1].is_running());
+    }
+}
@@ -0,0 +1,152 @@
+//! Per-database settings stored as key/value rows in `settings`.
+//!
+//! Missing keys read as their defaults, so a fresh database has no rows.
+
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+
+use crate::error::{OsError, Result};
+use crate::vcs::IntegrationStrategy;
+
+/// Levels in the task hierarchy when unset: milestone → task → subtask
+pub const DEFAULT_MAX_DEPTH: i32 = 3;
+/// Upper bound for `max_depth`; ancestor walks assume chains stay short
+pub const MAX_DEPTH_LIMIT: i32 = 16;
+
+const MAX_DEPTH_KEY: &str = "max_depth";
+const INTEGRATION_KEY: &str = "integration";
+const WORKTREE_DIR_KEY: &str = "worktree_dir";
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Settings {
+    /// Number of hierarchy levels; roots are level 1, so tasks may sit at
+    /// depth `0..max_depth`
+    pub max_depth: i32,
+    /// How completing a task brings its branch into the branch it started from
+    #[serde(default)]
+    pub integration: IntegrationStrategy,
+    /// Where `start --worktree` creates worktrees, relative to the repository
+    /// root; None: a `<repo>.worktrees` directory next to it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub worktree_dir: Option<String>,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            max_depth: DEFAULT_MAX_DEPTH,
+            integration: IntegrationStrategy::default(),
+            worktree_dir: None,
+        }
+    }
+}
+
+impl Settings {
+    /// Deepest `Task::depth` allowed (roots are depth 0).
+    pub fn deepest(&self) -> i32 {
+        self.max_depth - 1
+    }
+}
+
+fn get(conn: &Connection, key: &str) -> Result<Option<String>> {
+    Ok(conn
+        .query_row(
+            "SELECT value FROM settings WHERE key = ?1",
+            params![key],
+            |row| row.get(0),
+        )
+        .optional()?)
+}
+
+fn put(conn: &Connection, key: &str, value: &str) -> Result<()> {
+    conn.execute(
+        "INSERT INTO settings (key, value) VALUES (?1, ?2)
+         ON CONFLICT(key) DO UPDATE SET value = excluded.value",
+        params![key, value],
+    )?;
+    Ok(())
+}
+
+/// All settings, defaults filled in. Unparsable values read as the default.
+pub fn load(conn: &Connection) -> Result<Settings> {
+    let defaults = Settings::default();
+    Ok(Settings {
+        max_depth: get(conn, MAX_DEPTH_KEY)?
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(defaults.max_depth),
+        integration: get(conn, INTEGRATION_KEY)?
+            .and_then(|v| IntegrationStrategy::parse(&v))
+            .unwrap_or(defaults.integration),
+        worktree_dir: get(conn, WORKTREE_DIR_KEY)?,
+    })
+}
+
+/// Reject `max_depth` values outside `1..=MAX_DEPTH_LIMIT`.
+pub fn validate_max_depth(max_depth: i32) -> Result<()> {
+    if !(1..=MAX_DEPTH_LIMIT).contains(&max_depth) {
+        return Err(OsError::InvalidMaxDepth(max_depth));
+    }
+    Ok(())
+}
+
+/// Store `max_depth` after range-checking it. Callers check existing tasks fit.
+pub fn set_max_depth(conn: &Connection, max_depth: i32) -> Result<()> {
+    validate_max_depth(max_depth)?;
+    put(conn, MAX_DEPTH_KEY, &max_depth.to_string())
+}
+
+pub fn set_integration(conn: &Connection, strategy: IntegrationStrategy) -> Result<()> {
+    put(conn, INTEGRATION_KEY, strategy.as_str())
+}
+
+/// Store the worktree directory; None restores the default.
+pub fn set_worktree_dir(conn: &Connection, dir: Option<&str>) -> Result<()> {
+    match dir {
+        Some(dir) => put(conn, WORKTREE_DIR_KEY, dir),
+        None => {
+            conn.execute(
+                "DELETE FROM settings WHERE key = ?1",
+                params![WORKTREE_DIR_KEY],
+            )?;
+            Ok(())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::schema::init_schema;
+
+    #[test]
+    fn test_defaults_and_round_trip() {
+        let conn = Connection::open_in_memory().unwrap();
+        init_schema(&conn).unwrap();
+        assert_eq!(load(&conn).unwrap(), Settings::default());
+        assert_eq!(load(&conn).unwrap().deepest(), 2);
+
+        set_max_depth(&conn, 5).unwrap();
+        set_max_depth(&conn, 4).unwrap();
+        assert_eq!(load(&conn).unwrap().max_depth, 4);
+
+        assert!(matches!(
+            set_max_depth(&conn, 0),
+            Err(OsError::InvalidMaxDepth(0))
+        ));
+        assert!(set_max_depth(&conn, MAX_DEPTH_LIMIT + 1).is_err());
+
+        set_integration(&conn, IntegrationStrategy::Rebase).unwrap();
+        let settings = load(&conn).unwrap();
+        assert_eq!(settings.integration, IntegrationStrategy::Rebase);
+        assert_eq!(settings.max_depth, 4);
+
+        set_worktree_dir(&conn, Some("../trees")).unwrap();
+        assert_eq!(
+            load(&conn).unwrap().worktree_dir.as_deref(),
+            Some("../trees")
+        );
+        set_worktree_dir(&conn, None).unwrap();
+        assert_eq!(load(&conn).unwrap().worktree_dir, None);
+    }
+}
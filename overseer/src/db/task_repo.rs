@@ -1,6 +1,7 @@
 use chrono::{DateTime, Utc};
 use rusqlite::{params, Connection, OptionalExtension, Row};
 
+use crate::db::{claim_repo, label_repo, metadata_repo};
 use crate::error::{OsError, Result};
 use crate::id::TaskId;
 use crate::types::{CreateTaskInput, ListTasksFilter, Task, UpdateTaskInput};
@@ -9,65 +10,87 @@ fn now() -> DateTime<Utc> {
     Utc::now()
 }
 
+/// Columns read by `row_to_task`, in positional order. Selecting them explicitly
+/// (rather than `*`) lets rows be read by index; name lookups dominate on large scans.
+const TASK_COLUMNS: &str = "id, parent_id, description, context, result, priority, completed, \
+     completed_at, created_at, updated_at, started_at, commit_sha, bookmark, start_commit, \
+     cancelled, cancelled_at, archived, archived_at, revision, rank, due_at, defer_until, repo_id, \
+     start_branch, integration_commit, base_rev, head_rev, worktree";
+
+/// RFC 3339 timestamp at `idx`; NULL, non-text or unparsable values read as None.
+fn timestamp_at(row: &Row, idx: usize) -> rusqlite::Result<Option<DateTime<Utc>>> {
+    Ok(row
+        .get_ref(idx)?
+        .as_str_or_null()
+        .ok()
+        .flatten()
+        .and_then(|s| DateTime::parse_from_rfc3339(s).ok())
+        .map(|dt| dt.with_timezone(&Utc)))
+}
+
+/// Map a row selected with `TASK_COLUMNS`.
 fn row_to_task(row: &Row) -> rusqlite::Result<Task> {
     Ok(Task {
-        id: row.get("id")?,
-        parent_id: row.get("parent_id")?,
-        description: row.get("description")?,
-        context: row.get("context")?,
+        id: row.get(0)?,
+        parent_id: row.get(1)?,
+        description: row.get(2)?,
+        context: row.get(3)?,
         context_chain: None,
         learnings: None,
-        result: row.get("result")?,
-        priority: row.get("priority")?,
-        completed: row.get::<_, i32>("completed")? != 0,
-        completed_at: row
-            .get::<_, Option<String>>("completed_at")?
-            .and_then(|s| DateTime::parse_from_rfc3339(&s).ok())
-            .map(|dt| dt.with_timezone(&Utc)),
-        created_at: row
-            .get::<_, String>("created_at")
-            .ok()
-            .and_then(|s| DateTime::parse_from_rfc3339(&s).ok())
-            .map(|dt| dt.with_timezone(&Utc))
-            .unwrap_or_else(now),
-        updated_at: row
-            .get::<_, String>("updated_at")
-            .ok()
-            .and_then(|s| DateTime::parse_from_rfc3339(&s).ok())
-            .map(|dt| dt.with_timezone(&Utc))
-            .unwrap_or_else(now),
-        started_at: row
-            .get::<_, Option<String>>("started_at")?
-            .and_then(|s| DateTime::parse_from_rfc3339(&s).ok())
-            .map(|dt| dt.with_timezone(&Utc)),
-        commit_sha: row.get("commit_sha")?,
-        bookmark: row.get("bookmark")?,
-        start_commit: row.get("start_commit")?,
+        result: row.get(4)?,
+        priority: row.get(5)?,
+        completed: row.get::<_, i32>(6)? != 0,
+        completed_at: timestamp_at(row, 7)?,
+        created_at: timestamp_at(row, 8)?.unwrap_or_else(now),
+        updated_at: timestamp_at(row, 9)?.unwrap_or_else(now),
+        started_at: timestamp_at(row, 10)?,
+        commit_sha: row.get(11)?,
+        bookmark: row.get(12)?,
+        start_commit: row.get(13)?,
         depth: None,
         blocked_by: Vec::new(),
         blocks: Vec::new(),
         effectively_blocked: false, // Computed by TaskService
-        cancelled: row.get::<_, i32>("cancelled")? != 0,
-        cancelled_at: row
-            .get::<_, Option<String>>("cancelled_at")?
-            .and_then(|s| DateTime::parse_from_rfc3339(&s).ok())
-            .map(|dt| dt.with_timezone(&Utc)),
-        archived: row.get::<_, i32>("archived")? != 0,
-        archived_at: row
-            .get::<_, Option<String>>("archived_at")?
-            .and_then(|s| DateTime::parse_from_rfc3339(&s).ok())
-            .map(|dt| dt.with_timezone(&Utc)),
+        cancelled: row.get::<_, i32>(14)? != 0,
+        cancelled_at: timestamp_at(row, 15)?,
+        archived: row.get::<_, i32>(16)? != 0,
+        archived_at: timestamp_at(row, 17)?,
+        revision: row.get(18)?,
+        rank: row.get(19)?,
+        due_at: timestamp_at(row, 20)?,
+        defer_until: timestamp_at(row, 21)?,
+        repo_id: row.get(22)?,
+        start_branch: row.get(23)?,
+        integration_commit: row.get(24)?,
+        base_rev: row.get(25)?,
+        head_rev: row.get(26)?,
+        worktree: row.get(27)?,
+        metadata: None,
+        labels: Vec::new(),
+        claim: None,
     })
 }
 
+/// Load relation-backed fields (blocker edges, metadata, labels, live claim) onto a task row.
+fn hydrate(conn: &Connection, task: &mut Task) -> Result<()> {
+    task.blocked_by = get_blockers(conn, &task.id)?;
+    task.blocks = get_blocking(conn, &task.id)?;
+    task.metadata = metadata_repo::get_metadata(conn, &task.id)?;
+    task.labels = label_repo::get_labels(conn, &task.id)?;
+    task.claim = claim_repo::get_live_claim(conn, &task.id, now())?;
+    Ok(())
+}
+
 pub fn create_task(conn: &Connection, input: &CreateTaskInput) -> Result<Task> {
     let id = TaskId::new();
     let now_str = now().to_rfc3339();
 
     conn.execute(
         r#"
-        INSERT INTO tasks (id, parent_id, description, context, priority, created_at, updated_at)
-        VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)
+        INSERT INTO tasks (id, parent_id, description, context, priority, created_at, updated_at,
+                           due_at, defer_until, repo_id, rank)
+        VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10,
+                (SELECT COALESCE(MAX(rank) + 1, 0) FROM tasks WHERE parent_id IS ?2))
         "#,
         params![
             &id,
@@ -77,6 +100,9 @@ pub fn create_task(conn: &Connection, input: &CreateTaskInput) -> Result<Task> {
             input.priority.unwrap_or(1),
             now_str,
             now_str,
+            input.due_at.map(|dt| dt.to_rfc3339()),
+            input.defer_until.map(|dt| dt.to_rfc3339()),
+            input.repo_id.as_ref(),
         ],
     )?;
 
@@ -90,24 +116,87 @@ pub fn create_task(conn: &Connection, input: &CreateTaskInput) -> Result<Task> {
     get_task(conn, &id)?.ok_or_else(|| OsError::TaskNotFound(id))
 }
 
+/// Insert a task row verbatim, preserving its id, timestamps and lifecycle columns.
+/// Used by import; callers must insert parents before children.
+pub fn insert_task_row(conn: &Connection, task: &Task) -> Result<()> {
+    conn.execute(
+        r#"
+        INSERT INTO tasks (id, parent_id, description, context, result, priority,
+                           completed, completed_at, created_at, updated_at, commit_sha,
+                           started_at, bookmark, start_commit, cancelled, cancelled_at,
+                           archived, archived_at, rank, due_at, defer_until, repo_id,
+                           start_branch, integration_commit, base_rev, head_rev, worktree)
+        VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18,
+                ?19, ?20, ?21, ?22, ?23, ?24, ?25, ?26, ?27)
+        "#,
+        params![
+            &task.id,
+            task.parent_id.as_ref(),
+            task.description,
+            task.context,
+            task.result,
+            task.priority,
+            task.completed as i32,
+            task.completed_at.map(|dt| dt.to_rfc3339()),
+            task.created_at.to_rfc3339(),
+            task.updated_at.to_rfc3339(),
+            task.commit_sha,
+            task.started_at.map(|dt| dt.to_rfc3339()),
+            task.bookmark,
+            task.start_commit,
+            task.cancelled as i32,
+            task.cancelled_at.map(|dt| dt.to_rfc3339()),
+            task.archived as i32,
+            task.archived_at.map(|dt| dt.to_rfc3339()),
+            task.rank,
+            task.due_at.map(|dt| dt.to_rfc3339()),
+            task.defer_until.map(|dt| dt.to_rfc3339()),
+            task.repo_id.as_ref(),
+            task.start_branch,
+            task.integration_commit,
+            task.base_rev,
+            task.head_rev,
+            task.worktree,
+        ],
+    )?;
+    Ok(())
+}
+
 pub fn get_task(conn: &Connection, id: &TaskId) -> Result<Option<Task>> {
     let task: Option<Task> = conn
         .query_row(
-            "SELECT * FROM tasks WHERE id = ?1",
+            &format!("SELECT {TASK_COLUMNS} FROM tasks WHERE id = ?1"),
             params![id],
             row_to_task,
         )
         .optional()?;
 
     if let Some(mut task) = task {
-        task.blocked_by = get_blockers(conn, id)?;
-        task.blocks = get_blocking(conn, id)?;
+        hydrate(conn, &mut task)?;
         Ok(Some(task))
     } else {
         Ok(None)
     }
 }
 
+/// Every task row without relation fields, in one query (see `core::task_graph`).
+pub fn list_all_rows(conn: &Connection) -> Result<Vec<Task>> {
+    let mut stmt = conn.prepare(&format!("SELECT {TASK_COLUMNS} FROM tasks"))?;
+    let tasks = stmt
+        .query_map([], row_to_task)?
+        .collect::<rusqlite::Result<Vec<Task>>>()?;
+    Ok(tasks)
+}
+
+/// Every blocker edge as `(task_id, blocker_id)`.
+pub fn list_blocker_edges(conn: &Connection) -> Result<Vec<(TaskId, TaskId)>> {
+    let mut stmt = conn.prepare("SELECT task_id, blocker_id FROM task_blockers")?;
+    let edges = stmt
+        .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+    Ok(edges)
+}
+
 pub fn get_blockers(conn: &Connection, task_id: &TaskId) -> Result<Vec<TaskId>> {
     let mut stmt = conn.prepare("SELECT blocker_id FROM task_blockers WHERE task_id = ?1")?;
     let ids = stmt
@@ -134,20 +223,24 @@ pub fn list_tasks(conn: &Connection, filter: &ListTasksFilter) -> Result<Vec<Tas
             WITH RECURSIVE task_depths AS (
                 SELECT id, parent_id, description, context, result, priority, completed,
                        completed_at, created_at, updated_at, started_at, commit_sha, bookmark, start_commit,
-                       cancelled, cancelled_at, archived, archived_at,
-                       0 as depth
+                       cancelled, cancelled_at, archived, archived_at, revision, rank,
+                       due_at, defer_until, repo_id, start_branch, integration_commit, base_rev, head_rev,
+                       worktree, 0 as depth
                 FROM tasks WHERE parent_id IS NULL
                 UNION ALL
                 SELECT t.id, t.parent_id, t.description, t.context, t.result, t.priority, t.completed,
                        t.completed_at, t.created_at, t.updated_at, t.started_at, t.commit_sha, t.bookmark, t.start_commit,
-                       t.cancelled, t.cancelled_at, t.archived, t.archived_at,
+                       t.cancelled, t.cancelled_at, t.archived, t.archived_at, t.revision, t.rank,
+                       t.due_at, t.defer_until, t.repo_id, t.start_branch, t.integration_commit,
+                       t.base_rev, t.head_rev, t.worktree,
                        td.depth + 1
                 FROM tasks t
                 INNER JOIN task_depths td ON t.parent_id = td.id
             )
             SELECT id, parent_id, description, context, result, priority, completed,
                    completed_at, created_at, updated_at, started_at, commit_sha, bookmark, start_commit,
-                   cancelled, cancelled_at, archived, archived_at
+                   cancelled, cancelled_at, archived, archived_at, revision, rank, due_at, defer_until,
+                   repo_id, start_branch, integration_commit, base_rev, head_rev, worktree
             FROM task_depths WHERE 1=1
             "#,
         );
@@ -174,11 +267,13 @@ pub fn list_tasks(conn: &Connection, filter: &ListTasksFilter) -> Result<Vec<Tas
         }
         // None = include all (no filter clause)
 
-        sql.push_str(" ORDER BY priority ASC, created_at ASC");
+        push_metadata_filters(&mut sql, &mut params_vec, filter)?;
+
+        sql.push_str(" ORDER BY priority ASC, rank ASC, created_at ASC");
         sql
     } else {
         // Original simple query when not filtering by depth
-        let mut sql = String::from("SELECT * FROM tasks WHERE 1=1");
+        let mut sql = format!("SELECT {TASK_COLUMNS} FROM tasks WHERE 1=1");
 
         if let Some(ref parent_id) = filter.parent_id {
             sql.push_str(" AND parent_id = ?");
@@ -197,7 +292,9 @@ pub fn list_tasks(conn: &Connection, filter: &ListTasksFilter) -> Result<Vec<Tas
         }
         // None = include all (no filter clause)
 
-        sql.push_str(" ORDER BY priority ASC, created_at ASC");
+        push_metadata_filters(&mut sql, &mut params_vec, filter)?;
+
+        sql.push_str(" ORDER BY priority ASC, rank ASC, created_at ASC");
         sql
     };
 
@@ -208,8 +305,7 @@ pub fn list_tasks(conn: &Connection, filter: &ListTasksFilter) -> Result<Vec<Tas
         .collect::<rusqlite::Result<Vec<Task>>>()?;
 
     for task in &mut tasks {
-        task.blocked_by = get_blockers(conn, &task.id)?;
-        task.blocks = get_blocking(conn, &task.id)?;
+        hydrate(conn, task)?;
     }
 
     if filter.ready {
@@ -222,6 +318,20 @@ pub fn list_tasks(conn: &Connection, filter: &ListTasksFilter) -> Result<Vec<Tas
     Ok(tasks)
 }
 
+/// Append `AND` clauses matching each metadata filter by JSON equality.
+fn push_metadata_filters(
+    sql: &mut String,
+    params_vec: &mut Vec<Box<dyn rusqlite::ToSql>>,
+    filter: &ListTasksFilter,
+) -> Result<()> {
+    for meta in &filter.metadata {
+        sql.push_str(" AND id IN (SELECT task_id FROM task_metadata WHERE (data -> ?) = json(?))");
+        params_vec.push(Box::new(metadata_repo::to_json_path(&meta.path)?));
+        params_vec.push(Box::new(serde_json::to_string(&meta.value)?));
+    }
+    Ok(())
+}
+
 /// Check if task is completed. Returns false if task not found or DB error.
 /// This conservative default treats missing/errored tasks as "not completed" (blocking).
 fn is_completed(conn: &Connection, id: &TaskId) -> bool {
@@ -262,11 +372,35 @@ pub fn update_task(conn: &Connection, id: &TaskId, input: &UpdateTaskInput) -> R
     }
 
     if let Some(ref parent_id) = input.parent_id {
+        // A task moved under a new parent goes last among its new siblings
+        updates.push(format!(
+            "rank = CASE WHEN parent_id IS ?{0} THEN rank \
+             ELSE (SELECT COALESCE(MAX(rank) + 1, 0) FROM tasks WHERE parent_id IS ?{0}) END",
+            param_idx
+        ));
         updates.push(format!("parent_id = ?{}", param_idx));
         params_vec.push(Box::new(parent_id.clone()));
         param_idx += 1;
     }
 
+    if let Some(due_at) = input.due_at {
+        updates.push(format!("due_at = ?{}", param_idx));
+        params_vec.push(Box::new(due_at.map(|dt| dt.to_rfc3339())));
+        param_idx += 1;
+    }
+
+    if let Some(defer_until) = input.defer_until {
+        updates.push(format!("defer_until = ?{}", param_idx));
+        params_vec.push(Box::new(defer_until.map(|dt| dt.to_rfc3339())));
+        param_idx += 1;
+    }
+
+    if let Some(ref repo_id) = input.repo_id {
+        updates.push(format!("repo_id = ?{}", param_idx));
+        params_vec.push(Box::new(repo_id.clone()));
+        param_idx += 1;
+    }
+
     params_vec.push(Box::new(id.clone()));
 
     let sql = format!(
@@ -331,17 +465,99 @@ pub fn archive_task(conn: &Connection, id: &TaskId) -> Result<Task> {
     get_task(conn, id)?.ok_or_else(|| OsError::TaskNotFound(id.clone()))
 }
 
+/// Depth of the deepest task reachable from a root (None without tasks).
+/// Rows caught in a parent cycle have no root and are not counted.
+pub fn deepest_depth(conn: &Connection) -> Result<Option<i32>> {
+    Ok(conn.query_row(
+        r#"
+        WITH RECURSIVE levels(id, depth) AS (
+            SELECT id, 0 FROM tasks WHERE parent_id IS NULL
+            UNION ALL
+            SELECT t.id, l.depth + 1 FROM tasks t JOIN levels l ON t.parent_id = l.id
+        )
+        SELECT MAX(depth) FROM levels
+        "#,
+        [],
+        |row| row.get(0),
+    )?)
+}
+
+/// Current revision of a task, None if it does not exist.
+pub fn get_revision(conn: &Connection, id: &TaskId) -> Result<Option<i64>> {
+    Ok(conn
+        .query_row(
+            "SELECT revision FROM tasks WHERE id = ?1",
+            params![id],
+            |row| row.get(0),
+        )
+        .optional()?)
+}
+
+/// Overwrite a task's revision (restoring a row from the trash). Doesn't touch
+/// `updated_at`; the bump trigger skips writes that change the revision.
+pub fn set_revision(conn: &Connection, id: &TaskId, revision: i64) -> Result<()> {
+    conn.execute(
+        "UPDATE tasks SET revision = ?1 WHERE id = ?2",
+        params![revision, id],
+    )?;
+    Ok(())
+}
+
+/// Rank that puts a new child of `parent_id` (a root when None) after its siblings.
+pub fn next_rank(conn: &Connection, parent_id: Option<&TaskId>) -> Result<i64> {
+    Ok(conn.query_row(
+        "SELECT COALESCE(MAX(rank) + 1, 0) FROM tasks WHERE parent_id IS ?1",
+        params![parent_id],
+        |row| row.get(0),
+    )?)
+}
+
+/// Children of `parent_id` (roots when None) in rank order, whatever their priority.
+/// Ties, such as rows that predate ranks, fall back to creation order.
+pub fn get_sibling_ids_by_rank(
+    conn: &Connection,
+    parent_id: Option<&TaskId>,
+) -> Result<Vec<TaskId>> {
+    let mut stmt = conn.prepare(
+        "SELECT id FROM tasks WHERE parent_id IS ?1 ORDER BY rank ASC, created_at ASC, id ASC",
+    )?;
+    let ids = stmt
+        .query_map(params![parent_id], |row| row.get(0))?
+        .collect::<rusqlite::Result<Vec<TaskId>>>()?;
+    Ok(ids)
+}
+
+/// Renumber siblings 0, 1, 2... in the given order. Rows already at their rank
+/// are left alone so their revision does not change.
+pub fn set_sibling_ranks(conn: &Connection, ordered: &[TaskId]) -> Result<()> {
+    let mut stmt = conn.prepare("UPDATE tasks SET rank = ?1 WHERE id = ?2 AND rank != ?1")?;
+    for (rank, id) in ordered.iter().enumerate() {
+        stmt.execute(params![rank as i64, id])?;
+    }
+    Ok(())
+}
+
+/// Bump `updated_at` for writes that live outside the tasks table (e.g. metadata).
+pub fn touch_task(conn: &Connection, id: &TaskId) -> Result<()> {
+    conn.execute(
+        "UPDATE tasks SET updated_at = ?1 WHERE id = ?2",
+        params![now().to_rfc3339(), id],
+    )?;
+    Ok(())
+}
+
 pub fn delete_task(conn: &Connection, id: &TaskId) -> Result<()> {
     conn.execute("DELETE FROM tasks WHERE id = ?1", params![id])?;
     Ok(())
 }
 
-pub fn add_blocker(conn: &Connection, task_id: &TaskId, blocker_id: &TaskId) -> Result<()> {
-    conn.execute(
+/// Returns false if the edge already existed.
+pub fn add_blocker(conn: &Connection, task_id: &TaskId, blocker_id: &TaskId) -> Result<bool> {
+    let inserted = conn.execute(
         "INSERT OR IGNORE INTO task_blockers (task_id, blocker_id) VALUES (?1, ?2)",
         params![task_id, blocker_id],
     )?;
-    Ok(())
+    Ok(inserted > 0)
 }
 
 pub fn remove_blocker(conn: &Connection, task_id: &TaskId, blocker_id: &TaskId) -> Result<()> {
@@ -413,57 +629,77 @@ pub fn set_start_commit(conn: &Connection, id: &TaskId, start_commit: &str) -> R
     Ok(())
 }
 
-/// Clear bookmark field after VCS bookmark deletion
-pub fn clear_bookmark(conn: &Connection, id: &TaskId) -> Result<()> {
+/// Branch that was checked out when the task started; its branch is
+/// integrated back into it on completion
+pub fn set_start_branch(conn: &Connection, id: &TaskId, start_branch: &str) -> Result<()> {
     let now_str = now().to_rfc3339();
     conn.execute(
-        "UPDATE tasks SET bookmark = NULL, updated_at = ?1 WHERE id = ?2",
-        params![now_str, id],
+        "UPDATE tasks SET start_branch = ?1, updated_at = ?2 WHERE id = ?3",
+        params![start_branch, now_str, id],
     )?;
     Ok(())
 }
 
-/// Get bookmark for a task (lightweight query for delete cleanup)
-pub fn get_bookmark(conn: &Connection, id: &TaskId) -> Result<Option<String>> {
-    conn.query_row(
-        "SELECT bookmark FROM tasks WHERE id = ?1",
-        params![id],
-        |row| row.get(0),
-    )
-    .optional()
-    .map(|opt| opt.flatten())
-    .map_err(OsError::from)
+/// Tip of the start branch after the task's branch was integrated into it
+pub fn set_integration_commit(conn: &Connection, id: &TaskId, commit: &str) -> Result<()> {
+    let now_str = now().to_rfc3339();
+    conn.execute(
+        "UPDATE tasks SET integration_commit = ?1, updated_at = ?2 WHERE id = ?3",
+        params![commit, now_str, id],
+    )?;
+    Ok(())
 }
 
-/// Get bookmarks for task and all descendants (for delete cleanup)
-pub fn get_all_bookmarks(conn: &Connection, id: &TaskId) -> Result<Vec<String>> {
-    let mut bookmarks = Vec::new();
+/// Revision the task branch was created from
+pub fn set_base_rev(conn: &Connection, id: &TaskId, rev: &str) -> Result<()> {
+    let now_str = now().to_rfc3339();
+    conn.execute(
+        "UPDATE tasks SET base_rev = ?1, updated_at = ?2 WHERE id = ?3",
+        params![rev, now_str, id],
+    )?;
+    Ok(())
+}
 
-    // Get the task's own bookmark
-    if let Some(bookmark) = get_bookmark(conn, id)? {
-        bookmarks.push(bookmark);
-    }
+/// Tip of the task branch at completion
+pub fn set_head_rev(conn: &Connection, id: &TaskId, rev: &str) -> Result<()> {
+    let now_str = now().to_rfc3339();
+    conn.execute(
+        "UPDATE tasks SET head_rev = ?1, updated_at = ?2 WHERE id = ?3",
+        params![rev, now_str, id],
+    )?;
+    Ok(())
+}
 
-    // Get all descendant bookmarks
-    let descendants = get_all_descendants(conn, id)?;
-    for desc in descendants {
-        if let Some(bookmark) = desc.bookmark {
-            bookmarks.push(bookmark);
-        }
-    }
+/// Worktree the task's branch was checked out in; None after removing it
+pub fn set_worktree(conn: &Connection, id: &TaskId, path: Option<&str>) -> Result<()> {
+    let now_str = now().to_rfc3339();
+    conn.execute(
+        "UPDATE tasks SET worktree = ?1, updated_at = ?2 WHERE id = ?3",
+        params![path, now_str, id],
+    )?;
+    Ok(())
+}
 
-    Ok(bookmarks)
+/// Clear bookmark field after VCS bookmark deletion
+pub fn clear_bookmark(conn: &Connection, id: &TaskId) -> Result<()> {
+    let now_str = now().to_rfc3339();
+    conn.execute(
+        "UPDATE tasks SET bookmark = NULL, updated_at = ?1 WHERE id = ?2",
+        params![now_str, id],
+    )?;
+    Ok(())
 }
 
 pub fn get_children(conn: &Connection, parent_id: &TaskId) -> Result<Vec<Task>> {
-    let mut stmt = conn.prepare("SELECT * FROM tasks WHERE parent_id = ?1")?;
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {TASK_COLUMNS} FROM tasks WHERE parent_id = ?1"
+    ))?;
     let mut tasks: Vec<Task> = stmt
         .query_map(params![parent_id], row_to_task)?
         .collect::<rusqlite::Result<Vec<Task>>>()?;
 
     for task in &mut tasks {
-        task.blocked_by = get_blockers(conn, &task.id)?;
-        task.blocks = get_blocking(conn, &task.id)?;
+        hydrate(conn, task)?;
     }
 
     Ok(tasks)
@@ -486,40 +722,6 @@ pub fn get_all_descendants(conn: &Connection, root_id: &TaskId) -> Result<Vec<Ta
     Ok(all_descendants)
 }
 
-/// List root tasks (milestones) ordered by priority ASC (p0 first), created_at ASC, id ASC
-pub fn list_roots(conn: &Connection) -> Result<Vec<Task>> {
-    let mut stmt = conn.prepare(
-        "SELECT * FROM tasks WHERE parent_id IS NULL ORDER BY priority ASC, created_at ASC, id ASC",
-    )?;
-    let mut tasks: Vec<Task> = stmt
-        .query_map([], row_to_task)?
-        .collect::<rusqlite::Result<Vec<Task>>>()?;
-
-    for task in &mut tasks {
-        task.blocked_by = get_blockers(conn, &task.id)?;
-        task.blocks = get_blocking(conn, &task.id)?;
-    }
-
-    Ok(tasks)
-}
-
-/// Get children ordered by priority ASC (p0 first), created_at ASC, id ASC
-pub fn get_children_ordered(conn: &Connection, parent_id: &TaskId) -> Result<Vec<Task>> {
-    let mut stmt = conn.prepare(
-        "SELECT * FROM tasks WHERE parent_id = ?1 ORDER BY priority ASC, created_at ASC, id ASC",
-    )?;
-    let mut tasks: Vec<Task> = stmt
-        .query_map(params![parent_id], row_to_task)?
-        .collect::<rusqlite::Result<Vec<Task>>>()?;
-
-    for task in &mut tasks {
-        task.blocked_by = get_blockers(conn, &task.id)?;
-        task.blocks = get_blocking(conn, &task.id)?;
-    }
-
-    Ok(tasks)
-}
-
 /// Check if task is completed.
 /// Returns false if task not found or DB error (conservative default).
 /// Note: This function never errors - the Result wrapper is for API consistency
@@ -0,0 +1,156 @@
+//! Trashed subtrees stored in `trash` and `trash_tasks`.
+//!
+//! Each `trash` row holds a JSON snapshot of one deleted subtree, keyed by the
+//! id of its root; `core::trash` builds and restores the snapshots. Every task
+//! of the subtree has a `trash_tasks` row pointing back at the root, with its
+//! branch, so ids and branches of trashed tasks can be recognized.
+
+use chrono::{DateTime, Utc};
+use rusqlite::{params, Connection, OptionalExtension, Row};
+use serde::{Deserialize, Serialize};
+
+use crate::error::Result;
+use crate::id::TaskId;
+
+/// A trashed subtree, as listed by `os task trash list`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TrashEntry {
+    /// Root of the subtree; `os task restore` takes this id
+    pub id: TaskId,
+    pub parent_id: Option<TaskId>,
+    pub description: String,
+    /// Number of tasks in the subtree, root included
+    pub tasks: usize,
+    pub deleted_at: DateTime<Utc>,
+}
+
+const ENTRY_COLUMNS: &str = "t.id, t.parent_id, t.description, t.deleted_at, \
+     (SELECT COUNT(*) FROM trash_tasks m WHERE m.trash_id = t.id) AS tasks";
+
+fn row_to_entry(row: &Row) -> rusqlite::Result<TrashEntry> {
+    Ok(TrashEntry {
+        id: row.get("id")?,
+        parent_id: row.get("parent_id")?,
+        description: row.get("description")?,
+        tasks: row.get::<_, i64>("tasks")? as usize,
+        deleted_at: row
+            .get::<_, String>("deleted_at")
+            .ok()
+            .and_then(|s| DateTime::parse_from_rfc3339(&s).ok())
+            .map(|dt| dt.with_timezone(&Utc))
+            .unwrap_or(DateTime::<Utc>::MIN_UTC),
+    })
+}
+
+/// Store a snapshot. `members` lists every task of the subtree with its
+/// branch, root included.
+pub fn insert_entry(
+    conn: &Connection,
+    entry: &TrashEntry,
+    snapshot: &str,
+    members: &[(TaskId, Option<String>)],
+) -> Result<()> {
+    conn.execute(
+        r#"
+        INSERT INTO trash (id, parent_id, description, deleted_at, snapshot)
+        VALUES (?1, ?2, ?3, ?4, ?5)
+        "#,
+        params![
+            &entry.id,
+            entry.parent_id.as_ref(),
+            entry.description,
+            entry.deleted_at.to_rfc3339(),
+            snapshot,
+        ],
+    )?;
+    let mut stmt =
+        conn.prepare("INSERT INTO trash_tasks (task_id, trash_id, bookmark) VALUES (?1, ?2, ?3)")?;
+    for (task_id, bookmark) in members {
+        stmt.execute(params![task_id, &entry.id, bookmark])?;
+    }
+    Ok(())
+}
+
+/// The entry rooted at `id` with its snapshot JSON.
+pub fn get_entry(conn: &Connection, id: &TaskId) -> Result<Option<(TrashEntry, String)>> {
+    let entry = conn
+        .query_row(
+            &format!("SELECT {ENTRY_COLUMNS}, t.snapshot FROM trash t WHERE t.id = ?1"),
+            params![id],
+            |row| Ok((row_to_entry(row)?, row.get("snapshot")?)),
+        )
+        .optional()?;
+    Ok(entry)
+}
+
+/// Root of the trashed subtree containing `task_id`, if it is in the trash.
+pub fn containing_root(conn: &Connection, task_id: &TaskId) -> Result<Option<TaskId>> {
+    let root = conn
+        .query_row(
+            "SELECT trash_id FROM trash_tasks WHERE task_id = ?1",
+            params![task_id],
+            |row| row.get(0),
+        )
+        .optional()?;
+    Ok(root)
+}
+
+/// Every entry, most recently deleted first.
+pub fn list_entries(conn: &Connection) -> Result<Vec<TrashEntry>> {
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {ENTRY_COLUMNS} FROM trash t ORDER BY t.deleted_at DESC, t.rowid DESC"
+    ))?;
+    let entries = stmt
+        .query_map([], row_to_entry)?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+    Ok(entries)
+}
+
+/// Drop the entry rooted at `id` (its `trash_tasks` rows cascade).
+pub fn delete_entry(conn: &Connection, id: &TaskId) -> Result<()> {
+    conn.execute("DELETE FROM trash WHERE id = ?1", params![id])?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::schema::init_schema;
+
+    #[test]
+    fn test_entries_track_members_and_cascade() {
+        let conn = Connection::open_in_memory().unwrap();
+        init_schema(&conn).unwrap();
+        let root = TaskId::new();
+        let child = TaskId::new();
+        let entry = TrashEntry {
+            id: root.clone(),
+            parent_id: None,
+            description: "Milestone".to_string(),
+            tasks: 2,
+            deleted_at: Utc::now(),
+        };
+        insert_entry(
+            &conn,
+            &entry,
+            "{}",
+            &[
+                (root.clone(), None),
+                (child.clone(), Some(format!("task/{child}"))),
+            ],
+        )
+        .unwrap();
+
+        let (stored, snapshot) = get_entry(&conn, &root).unwrap().unwrap();
+        assert_eq!(stored.tasks, 2);
+        assert_eq!(stored.deleted_at.timestamp(), entry.deleted_at.timestamp());
+        assert_eq!(snapshot, "{}");
+        assert_eq!(containing_root(&conn, &child).unwrap(), Some(root.clone()));
+        assert_eq!(list_entries(&conn).unwrap().len(), 1);
+
+        delete_entry(&conn, &root).unwrap();
+        assert!(get_entry(&conn, &root).unwrap().is_none());
+        assert!(containing_root(&conn, &child).unwrap().is_none());
+    }
+}
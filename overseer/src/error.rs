@@ -1,6 +1,7 @@
+use chrono::{DateTime, Utc};
 use thiserror::Error;
 
-use crate::id::{LearningId, TaskId};
+use crate::id::{CommentId, LearningId, LinkId, TaskId};
 use crate::vcs::VcsError;
 
 /// Reason why a task cannot be started
@@ -13,6 +14,9 @@ pub enum NotReadyReason {
     Blocked { blockers: Vec<TaskId> },
     /// No ready tasks in subtree (all complete or blocked)
     NoReadyTasksInSubtree,
+    /// Task or an ancestor is deferred until this time
+    #[allow(dead_code)]
+    Deferred { until: DateTime<Utc> },
 }
 
 #[derive(Error, Debug)]
@@ -20,6 +24,10 @@ pub enum OsError {
     #[error("Database error: {0}")]
     Database(#[from] rusqlite::Error),
 
+    /// Lock contention outlasted the busy timeout and every retry
+    #[error("Database is locked by another writer - try again")]
+    DatabaseBusy,
+
     #[error("Task not found: {0}")]
     TaskNotFound(TaskId),
 
@@ -32,8 +40,34 @@ pub enum OsError {
     #[error("Learning not found: {0}")]
     LearningNotFound(LearningId),
 
-    #[error("Maximum depth exceeded: subtasks cannot have children")]
-    MaxDepthExceeded,
+    #[error("Comment not found: {0}")]
+    CommentNotFound(CommentId),
+
+    #[error("Link not found: {0}")]
+    LinkNotFound(LinkId),
+
+    #[error("Invalid link: {0}")]
+    InvalidLink(String),
+
+    #[error("Path not found in repository: {0}")]
+    LinkPathNotFound(String),
+
+    #[error("Commit not found: {0}")]
+    CommitNotFound(String),
+
+    #[error(
+        "Maximum depth exceeded: tasks nest at most {max} levels deep (see `os db config --max-depth`)"
+    )]
+    MaxDepthExceeded { max: i32 },
+
+    #[error(
+        "Invalid max depth: {0} (must be 1-{limit})",
+        limit = crate::db::settings_repo::MAX_DEPTH_LIMIT
+    )]
+    InvalidMaxDepth(i32),
+
+    #[error("Cannot lower max depth to {requested}: existing tasks nest {existing} levels deep")]
+    MaxDepthBelowExisting { requested: i32, existing: i32 },
 
     #[error("Cycle detected in parent chain")]
     ParentCycle,
@@ -109,6 +143,116 @@ pub enum OsError {
     #[error("Invalid priority: {0} (must be 0-2)")]
     InvalidPriority(i32),
 
+    #[error("Invalid metadata path: {0} (expected dotted keys like 'area' or 'pr.url')")]
+    InvalidMetadataPath(String),
+
+    #[error("Invalid label: '{0}' (lowercase letters, digits and - _ . : /, up to 64 chars)")]
+    InvalidLabel(String),
+
+    /// A live claim held by another agent covers the task
+    #[error(
+        "Task {task_id} is claimed by '{holder}' until {}",
+        .expires_at.format("%Y-%m-%d %H:%M:%S UTC")
+    )]
+    TaskClaimed {
+        task_id: TaskId,
+        holder: String,
+        expires_at: DateTime<Utc>,
+    },
+
+    #[error("No live claim on task {task_id} held by '{agent}'")]
+    ClaimNotHeld { task_id: TaskId, agent: String },
+
+    /// `--if-revision` / `expectedRevision` did not match the stored revision
+    #[error(
+        "Task {task_id} changed since revision {expected} (now at {actual}) - reload and retry"
+    )]
+    RevisionConflict {
+        task_id: TaskId,
+        expected: i64,
+        actual: i64,
+    },
+
+    #[error("Task {0} has not been started - use `os task start`")]
+    TaskNotStarted(TaskId),
+
+    #[error("Task {0} has no running session")]
+    NoRunningSession(TaskId),
+
+    #[error("Task {0} already has a running session")]
+    SessionRunning(TaskId),
+
+    #[error("Invalid move: {0}")]
+    InvalidMove(String),
+
+    #[error("Invalid import: {0}")]
+    InvalidImport(String),
+
+    #[error("Template not found: {0} (see `os template list`)")]
+    TemplateNotFound(String),
+
+    #[error("Template '{0}' already exists - pass --force to overwrite")]
+    TemplateExists(String),
+
+    #[error("Invalid template name: '{0}' (letters, digits, - and _)")]
+    InvalidTemplateName(String),
+
+    #[error("Invalid template '{name}': {message}")]
+    InvalidTemplate { name: String, message: String },
+
+    #[error(
+        "Template '{name}' needs values for: {} (pass --var NAME=VALUE)",
+        .missing.join(", ")
+    )]
+    TemplateVariablesMissing { name: String, missing: Vec<String> },
+
+    #[error("Task not in trash: {0} (see `os task trash list`)")]
+    NotInTrash(TaskId),
+
+    #[error("Task {task_id} was trashed with {root_id} - restore {root_id} instead")]
+    TrashedWithAncestor { task_id: TaskId, root_id: TaskId },
+
+    #[error("Parent task {0} is in the trash - restore it first")]
+    ParentInTrash(TaskId),
+
+    #[error(
+        "Task {task_id} is checked out in the worktree {path} - complete it or remove the worktree first"
+    )]
+    WorktreeInUse { task_id: TaskId, path: String },
+
+    #[error("Repository not registered: {0} (see `os repo list`)")]
+    RepoNotFound(String),
+
+    #[error("Invalid repo name: '{0}'")]
+    InvalidRepoName(String),
+
+    #[error("Repository already registered: {0}")]
+    RepoExists(String),
+
+    #[error(
+        "Repository {name} still has {tasks} active task(s) - complete, cancel or move them first"
+    )]
+    RepoInUse { name: String, tasks: usize },
+
+    #[error("Cannot change the repo of task {0}: it already has a branch")]
+    RepoChangeAfterStart(TaskId),
+
+    #[error(
+        "Cannot integrate {branch} into {onto}: conflicts in {} - resolve them on {branch}, then complete {task_id} again",
+        files.join(", ")
+    )]
+    IntegrationConflict {
+        task_id: TaskId,
+        branch: String,
+        onto: String,
+        files: Vec<String>,
+    },
+
+    #[error(
+        "Database schema version {found} is newer than this os supports ({supported}) - upgrade os"
+    )]
+    SchemaTooNew { found: i32, supported: i32 },
+
     #[error("IO error: {0}")]
     Io(#[from] std::io::Error),
 
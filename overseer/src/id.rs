@@ -2,6 +2,10 @@
 //!
 //! - `TaskId`: `task_01ARZ3NDEKTSV4RRFFQ69G5FAV`
 //! - `LearningId`: `lrn_01ARZ3NDEKTSV4RRFFQ69G5FAV`
+//! - `CommentId`: `cmt_01ARZ3NDEKTSV4RRFFQ69G5FAV`
+//! - `LinkId`: `lnk_01ARZ3NDEKTSV4RRFFQ69G5FAV`
+//! - `RepoId`: `repo_01ARZ3NDEKTSV4RRFFQ69G5FAV`
+//! - `EventId`: `evt_01ARZ3NDEKTSV4RRFFQ69G5FAV`
 
 use std::fmt;
 use std::str::FromStr;
@@ -171,6 +175,220 @@ impl FromSql for LearningId {
     }
 }
 
+// ============ CommentId ============
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct CommentId(String);
+
+impl CommentId {
+    pub const PREFIX: &'static str = "cmt_";
+
+    pub fn new() -> Self {
+        Self(format!("{}{}", Self::PREFIX, ulid::Ulid::new()))
+    }
+
+    pub(crate) fn from_raw_ulid(ulid: String) -> Self {
+        Self(format!("{}{}", Self::PREFIX, ulid))
+    }
+}
+
+impl Default for CommentId {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl fmt::Display for CommentId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl FromStr for CommentId {
+    type Err = IdParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let ulid = s
+            .strip_prefix(Self::PREFIX)
+            .ok_or_else(|| IdParseError::MissingPrefix {
+                expected: Self::PREFIX,
+                actual: s.to_string(),
+            })?;
+        validate_ulid(ulid)?;
+        Ok(Self::from_raw_ulid(ulid.to_string()))
+    }
+}
+
+impl ToSql for CommentId {
+    fn to_sql(&self) -> rusqlite::Result<ToSqlOutput<'_>> {
+        Ok(ToSqlOutput::from(self.0.clone()))
+    }
+}
+
+impl FromSql for CommentId {
+    fn column_result(value: ValueRef<'_>) -> FromSqlResult<Self> {
+        let s = value.as_str()?.to_string();
+        Ok(Self(s))
+    }
+}
+
+// ============ LinkId ============
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct LinkId(String);
+
+impl LinkId {
+    pub const PREFIX: &'static str = "lnk_";
+
+    pub fn new() -> Self {
+        Self(format!("{}{}", Self::PREFIX, ulid::Ulid::new()))
+    }
+
+    pub(crate) fn from_raw_ulid(ulid: String) -> Self {
+        Self(format!("{}{}", Self::PREFIX, ulid))
+    }
+}
+
+impl Default for LinkId {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl fmt::Display for LinkId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl FromStr for LinkId {
+    type Err = IdParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let ulid = s
+            .strip_prefix(Self::PREFIX)
+            .ok_or_else(|| IdParseError::MissingPrefix {
+                expected: Self::PREFIX,
+                actual: s.to_string(),
+            })?;
+        validate_ulid(ulid)?;
+        Ok(Self::from_raw_ulid(ulid.to_string()))
+    }
+}
+
+impl ToSql for LinkId {
+    fn to_sql(&self) -> rusqlite::Result<ToSqlOutput<'_>> {
+        Ok(ToSqlOutput::from(self.0.clone()))
+    }
+}
+
+impl FromSql for LinkId {
+    fn column_result(value: ValueRef<'_>) -> FromSqlResult<Self> {
+        let s = value.as_str()?.to_string();
+        Ok(Self(s))
+    }
+}
+
+// ============ RepoId ============
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct RepoId(String);
+
+impl RepoId {
+    pub const PREFIX: &'static str = "repo_";
+
+    pub fn new() -> Self {
+        Self(format!("{}{}", Self::PREFIX, ulid::Ulid::new()))
+    }
+
+    pub(crate) fn from_raw_ulid(ulid: String) -> Self {
+        Self(format!("{}{}", Self::PREFIX, ulid))
+    }
+}
+
+impl Default for RepoId {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl fmt::Display for RepoId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl FromStr for RepoId {
+    type Err = IdParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let ulid = s
+            .strip_prefix(Self::PREFIX)
+            .ok_or_else(|| IdParseError::MissingPrefix {
+                expected: Self::PREFIX,
+                actual: s.to_string(),
+            })?;
+        validate_ulid(ulid)?;
+        Ok(Self::from_raw_ulid(ulid.to_string()))
+    }
+}
+
+impl ToSql for RepoId {
+    fn to_sql(&self) -> rusqlite::Result<ToSqlOutput<'_>> {
+        Ok(ToSqlOutput::from(self.0.clone()))
+    }
+}
+
+impl FromSql for RepoId {
+    fn column_result(value: ValueRef<'_>) -> FromSqlResult<Self> {
+        let s = value.as_str()?.to_string();
+        Ok(Self(s))
+    }
+}
+
+// ============ EventId ============
+
+/// Event ids are only ever generated and read back, never parsed from user input.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct EventId(String);
+
+impl EventId {
+    pub const PREFIX: &'static str = "evt_";
+
+    pub fn new() -> Self {
+        Self(format!("{}{}", Self::PREFIX, ulid::Ulid::new()))
+    }
+}
+
+impl Default for EventId {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl fmt::Display for EventId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl ToSql for EventId {
+    fn to_sql(&self) -> rusqlite::Result<ToSqlOutput<'_>> {
+        Ok(ToSqlOutput::from(self.0.clone()))
+    }
+}
+
+impl FromSql for EventId {
+    fn column_result(value: ValueRef<'_>) -> FromSqlResult<Self> {
+        let s = value.as_str()?.to_string();
+        Ok(Self(s))
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -17,9 +17,13 @@ mod vcs;
 #[cfg(test)]
 mod testutil;
 
+use crate::core::intent_journal::{IntentJournal, IntentOp};
+use crate::core::{RecoveryAction, TaskWorkflowService};
 use commands::{
-    data, learning, task, vcs as vcs_cmd, DataCommand, DataResult, LearningCommand, LearningResult,
-    TaskCommand, TaskResult, VcsCommand,
+    data, db as db_cmd, events, learning, repo, report, task, template, vcs as vcs_cmd,
+    DataCommand, DataResult, DbCommand, DbResult, EventsCommand, EventsResult, LearningCommand,
+    LearningResult, RepoCommand, RepoResult, ReportCommand, ReportResult, TaskCommand, TaskResult,
+    TemplateCommand, TemplateResult, VcsCommand,
 };
 use output::Printer;
 
@@ -77,6 +81,26 @@ enum Command {
     #[command(subcommand)]
     Data(DataCommand),
 
+    /// Database schema and integrity (status, migrate, check)
+    #[command(subcommand)]
+    Db(DbCommand),
+
+    /// Event log (list, tail)
+    #[command(subcommand)]
+    Events(EventsCommand),
+
+    /// Reports (time spent)
+    #[command(subcommand)]
+    Report(ReportCommand),
+
+    /// Task templates (save, apply, list)
+    #[command(subcommand)]
+    Template(TemplateCommand),
+
+    /// Repositories sharing this database (add, list, remove)
+    #[command(subcommand)]
+    Repo(RepoCommand),
+
     /// Generate shell completions
     #[command(
         about = "Generate shell completions",
@@ -319,9 +343,12 @@ fn default_db_path() -> PathBuf {
 
     let cwd = std::env::current_dir().unwrap_or_else(|_| PathBuf::from("."));
 
-    // Use VCS root if available (same detection as VCS module)
+    // Use VCS root if available (same detection as VCS module); tasks started
+    // with --worktree share the main checkout's database
     let (_, vcs_root) = vcs::detect_vcs_type(&cwd);
-    let base = vcs_root.unwrap_or(cwd);
+    let base = vcs_root
+        .map(|root| vcs::main_worktree_root(&root))
+        .unwrap_or(cwd);
 
     base.join(".overseer").join("tasks.db")
 }
@@ -347,6 +374,19 @@ fn main() {
 
     let db_path = cli.db.unwrap_or_else(default_db_path);
 
+    // Finish or undo a start/complete interrupted by a crash before any command runs
+    recover_interrupted_workflow(&db_path);
+
+    // PRECONDITION: tail streams JSON lines (possibly forever), bypass normal run()
+    if let Command::Events(EventsCommand::Tail(args)) = &cli.command {
+        let result = db::open_db(&db_path)
+            .and_then(|conn| events::tail(&conn, args, &mut io::stdout().lock()));
+        if let Err(e) = result {
+            exit_with_error(&e, cli.json, cli.no_color);
+        }
+        return;
+    }
+
     let result = run(&cli.command, &db_path);
 
     match result {
@@ -358,19 +398,54 @@ fn main() {
                 printer.print(&cli.command, &output);
             }
         }
-        Err(e) => {
-            if cli.json {
-                let err = serde_json::json!({ "error": e.to_string() });
-                eprintln!("{}", err);
-            } else {
-                let printer = Printer::new_for_stderr(cli.no_color);
-                printer.print_error(&format!("Error: {}", e));
-            }
-            std::process::exit(1);
+        Err(e) => exit_with_error(&e, cli.json, cli.no_color),
+    }
+}
+
+/// Resume an interrupted complete or roll back an interrupted start (see `core::intent_journal`).
+/// Failures only warn. Database and I/O errors keep the journal for the next invocation;
+/// any other failure drops it (see `TaskWorkflowService::recover`).
+fn recover_interrupted_workflow(db_path: &Path) {
+    if !IntentJournal::for_db(db_path).exists() {
+        return;
+    }
+
+    let result = db::open_db(db_path).and_then(|conn| {
+        // The journal was written against the main checkout, even from a task worktree
+        let vcs = vcs::get_main_backend(&std::env::current_dir().unwrap_or_default()).ok();
+        TaskWorkflowService::with_optional_vcs(&conn, vcs).recover()
+    });
+
+    match result {
+        Ok(Some(recovered)) => {
+            let op = match recovered.intent.op {
+                IntentOp::Start => "start",
+                IntentOp::Complete { .. } => "complete",
+            };
+            let action = match recovered.action {
+                RecoveryAction::AlreadyApplied => "cleared finished",
+                RecoveryAction::Resumed => "resumed interrupted",
+                RecoveryAction::RolledBack => "rolled back interrupted",
+                RecoveryAction::Discarded => "discarded interrupted",
+            };
+            eprintln!("note: {} {} of {}", action, op, recovered.intent.task_id);
         }
+        Ok(None) => {}
+        Err(e) => eprintln!("warn: failed to recover interrupted task operation: {}", e),
     }
 }
 
+fn exit_with_error(e: &error::OsError, json: bool, no_color: bool) -> ! {
+    if json {
+        let err = serde_json::json!({ "error": e.to_string() });
+        eprintln!("{}", err);
+    } else {
+        let printer = Printer::new_for_stderr(no_color);
+        printer.print_error(&format!("Error: {}", e));
+    }
+    std::process::exit(1);
+}
+
 fn run(command: &Command, db_path: &PathBuf) -> error::Result<String> {
     match command {
         Command::Init => {
@@ -382,18 +457,38 @@ fn run(command: &Command, db_path: &PathBuf) -> error::Result<String> {
             let cloned_cmd = clone_task_cmd(cmd);
 
             // Only workflow commands (start/complete) require VCS
-            // Delete is best-effort VCS cleanup (works without VCS)
+            // Trash purge is best-effort branch cleanup (works without VCS)
             let result = match &cloned_cmd {
+                // Tasks in a registered repo open it; the rest need the cwd's
                 TaskCommand::Start { .. } | TaskCommand::Complete(_) => {
-                    let vcs = vcs::get_backend(&std::env::current_dir().unwrap_or_default())?;
-                    task::handle_workflow(&conn, cloned_cmd, vcs)?
+                    // Branches are managed from the main repository, even from a task worktree
+                    let cwd = std::env::current_dir().unwrap_or_default();
+                    let main_root = vcs::detect_vcs_type(&cwd)
+                        .1
+                        .map(|root| vcs::main_worktree_root(&root));
+                    let vcs = vcs::get_main_backend(&cwd).ok();
+                    let result = task::handle_workflow(&conn, cloned_cmd, vcs);
+                    // Completing from inside a task worktree removes the directory we are in
+                    if std::env::current_dir().is_err() {
+                        if let Some(root) = main_root {
+                            let _ = std::env::set_current_dir(root);
+                        }
+                    }
+                    result?
+                }
+                TaskCommand::Trash(trash_cmd @ task::TrashCommand::Purge { .. }) => {
+                    // VCS optional for purge - best effort cleanup
+                    let vcs =
+                        vcs::get_main_backend(&std::env::current_dir().unwrap_or_default()).ok();
+                    task::handle_trash(&conn, trash_cmd.clone(), vcs)?
                 }
-                TaskCommand::Delete { .. } => {
-                    // VCS optional for delete - best effort cleanup
+                // File and commit links are validated against the repository
+                TaskCommand::Link(link_cmd @ task::LinkCommand::Add(_)) => {
                     let vcs = vcs::get_backend(&std::env::current_dir().unwrap_or_default()).ok();
-                    task::handle_delete(&conn, cloned_cmd, vcs)?
+                    task::handle_link(&conn, link_cmd.clone(), vcs)?
                 }
-                _ => task::handle(&conn, cloned_cmd)?,
+                // Store-only commands rerun from scratch if they lose a lock race
+                _ => db::retry::with_retry(|| task::handle(&conn, clone_task_cmd(cmd)))?,
             };
 
             match result {
@@ -405,14 +500,27 @@ fn run(command: &Command, db_path: &PathBuf) -> error::Result<String> {
                 TaskResult::Tree(tree) => Ok(serde_json::to_string_pretty(&tree)?),
                 TaskResult::Trees(trees) => Ok(serde_json::to_string_pretty(&trees)?),
                 TaskResult::Progress(progress) => Ok(serde_json::to_string_pretty(&progress)?),
+                TaskResult::Metadata(value) => Ok(serde_json::to_string_pretty(&value)?),
+                TaskResult::Search(hits) => Ok(serde_json::to_string_pretty(&hits)?),
+                TaskResult::Claim(claim) => Ok(serde_json::to_string_pretty(&claim)?),
+                TaskResult::Released => Ok(serde_json::json!({ "released": true }).to_string()),
+                TaskResult::Session(session) => Ok(serde_json::to_string_pretty(&session)?),
+                TaskResult::Comment(comment) => Ok(serde_json::to_string_pretty(&comment)?),
+                TaskResult::Comments(comments) => Ok(serde_json::to_string_pretty(&comments)?),
+                TaskResult::Link(link) => Ok(serde_json::to_string_pretty(&link)?),
+                TaskResult::Links(links) => Ok(serde_json::to_string_pretty(&links)?),
+                TaskResult::Restored(restored) => Ok(serde_json::to_string_pretty(&restored)?),
+                TaskResult::Trash(entries) => Ok(serde_json::to_string_pretty(&entries)?),
+                TaskResult::Purged(purged) => Ok(serde_json::to_string_pretty(&purged)?),
             }
         }
         Command::Learning(cmd) => {
             let conn = db::open_db(db_path)?;
-            match learning::handle(&conn, clone_learning_cmd(cmd))? {
+            match db::retry::with_retry(|| learning::handle(&conn, clone_learning_cmd(cmd)))? {
                 LearningResult::One(l) => Ok(serde_json::to_string_pretty(&l)?),
                 LearningResult::Many(ls) => Ok(serde_json::to_string_pretty(&ls)?),
                 LearningResult::Deleted => Ok(serde_json::json!({ "deleted": true }).to_string()),
+                LearningResult::Search(hits) => Ok(serde_json::to_string_pretty(&hits)?),
             }
         }
         Command::Vcs(cmd) => {
@@ -436,7 +544,7 @@ fn run(command: &Command, db_path: &PathBuf) -> error::Result<String> {
         }
         Command::Data(cmd) => {
             let conn = db::open_db(db_path)?;
-            match data::handle(&conn, clone_data_cmd(cmd))? {
+            match db::retry::with_retry(|| data::handle(&conn, clone_data_cmd(cmd)))? {
                 DataResult::Exported {
                     path,
                     tasks,
@@ -447,6 +555,41 @@ fn run(command: &Command, db_path: &PathBuf) -> error::Result<String> {
                     "tasks": tasks,
                     "learnings": learnings
                 }))?),
+                DataResult::Imported(summary) => Ok(serde_json::to_string_pretty(&summary)?),
+            }
+        }
+        Command::Db(cmd) => match db_cmd::handle(db_path, cmd.clone())? {
+            DbResult::Status(status) => Ok(serde_json::to_string_pretty(&status)?),
+            DbResult::Migrate(result) => Ok(serde_json::to_string_pretty(&result)?),
+            DbResult::Check(result) => Ok(serde_json::to_string_pretty(&result)?),
+            DbResult::Config(settings) => Ok(serde_json::to_string_pretty(&settings)?),
+        },
+        Command::Events(cmd) => {
+            let conn = db::open_db(db_path)?;
+            match events::handle(&conn, cmd.clone())? {
+                EventsResult::Many(events) => Ok(serde_json::to_string_pretty(&events)?),
+            }
+        }
+        Command::Report(cmd) => {
+            let conn = db::open_db(db_path)?;
+            match report::handle(&conn, cmd.clone())? {
+                ReportResult::Time(report) => Ok(serde_json::to_string_pretty(&report)?),
+            }
+        }
+        Command::Template(cmd) => {
+            let conn = db::open_db(db_path)?;
+            let result = db::retry::with_retry(|| template::handle(&conn, db_path, cmd.clone()))?;
+            match result {
+                TemplateResult::Saved(summary) => Ok(serde_json::to_string_pretty(&summary)?),
+                TemplateResult::Applied(applied) => Ok(serde_json::to_string_pretty(&applied)?),
+                TemplateResult::Many(summaries) => Ok(serde_json::to_string_pretty(&summaries)?),
+            }
+        }
+        Command::Repo(cmd) => {
+            let conn = db::open_db(db_path)?;
+            match db::retry::with_retry(|| repo::handle(&conn, cmd.clone()))? {
+                RepoResult::One(repo) => Ok(serde_json::to_string_pretty(&repo)?),
+                RepoResult::Many(repos) => Ok(serde_json::to_string_pretty(&repos)?),
             }
         }
         // PRECONDITION: Completions handled in main() before run() is called
@@ -465,18 +608,32 @@ fn clone_task_cmd(cmd: &TaskCommand) -> TaskCommand {
             parent: args.parent.clone(),
             priority: args.priority,
             blocked_by: args.blocked_by.clone(),
+            labels: args.labels.clone(),
+            due: args.due,
+            defer_until: args.defer_until,
+            repo: args.repo.clone(),
         }),
-        TaskCommand::Get { id } => TaskCommand::Get { id: id.clone() },
+        TaskCommand::Get { id, comments } => TaskCommand::Get {
+            id: id.clone(),
+            comments: *comments,
+        },
         TaskCommand::List(args) => TaskCommand::List(task::ListArgs {
             parent: args.parent.clone(),
             ready: args.ready,
             completed: args.completed,
+            depth: args.depth,
             milestones: args.milestones,
             tasks: args.tasks,
             subtasks: args.subtasks,
             archived: args.archived,
             all: args.all,
             flat: args.flat,
+            meta: args.meta.clone(),
+            labels: args.labels.clone(),
+            overdue: args.overdue,
+            due_before: args.due_before,
+            linked: args.linked.clone(),
+            repo: args.repo.clone(),
         }),
         TaskCommand::Update(args) => TaskCommand::Update(task::UpdateArgs {
             id: args.id.clone(),
@@ -484,17 +641,46 @@ fn clone_task_cmd(cmd: &TaskCommand) -> TaskCommand {
             context: args.context.clone(),
             priority: args.priority,
             parent: args.parent.clone(),
+            labels: args.labels.clone(),
+            due: args.due,
+            clear_due: args.clear_due,
+            defer_until: args.defer_until,
+            clear_defer: args.clear_defer,
+            repo: args.repo.clone(),
+            clear_repo: args.clear_repo,
+            if_revision: args.if_revision,
         }),
-        TaskCommand::Start { id } => TaskCommand::Start { id: id.clone() },
+        TaskCommand::Start {
+            id,
+            agent,
+            if_revision,
+            worktree,
+        } => TaskCommand::Start {
+            id: id.clone(),
+            agent: agent.clone(),
+            if_revision: *if_revision,
+            worktree: *worktree,
+        },
         TaskCommand::Complete(args) => TaskCommand::Complete(task::CompleteArgs {
             id: args.id.clone(),
             result: args.result.clone(),
             learnings: args.learnings.clone(),
+            agent: args.agent.clone(),
+            if_revision: args.if_revision,
+            integration: args.integration,
         }),
         TaskCommand::Reopen { id } => TaskCommand::Reopen { id: id.clone() },
-        TaskCommand::Cancel { id } => TaskCommand::Cancel { id: id.clone() },
-        TaskCommand::Archive { id } => TaskCommand::Archive { id: id.clone() },
+        TaskCommand::Cancel { id, if_revision } => TaskCommand::Cancel {
+            id: id.clone(),
+            if_revision: *if_revision,
+        },
+        TaskCommand::Archive { id, if_revision } => TaskCommand::Archive {
+            id: id.clone(),
+            if_revision: *if_revision,
+        },
         TaskCommand::Delete { id } => TaskCommand::Delete { id: id.clone() },
+        TaskCommand::Restore { id } => TaskCommand::Restore { id: id.clone() },
+        TaskCommand::Trash(cmd) => TaskCommand::Trash(cmd.clone()),
         TaskCommand::Block(args) => TaskCommand::Block(task::BlockArgs {
             id: args.id.clone(),
             by: args.by.clone(),
@@ -503,18 +689,84 @@ fn clone_task_cmd(cmd: &TaskCommand) -> TaskCommand {
             id: args.id.clone(),
             by: args.by.clone(),
         }),
+        TaskCommand::Move(args) => TaskCommand::Move(task::MoveArgs {
+            id: args.id.clone(),
+            before: args.before.clone(),
+            after: args.after.clone(),
+            first: args.first,
+            last: args.last,
+        }),
         TaskCommand::NextReady(args) => TaskCommand::NextReady(task::NextReadyArgs {
             milestone: args.milestone.clone(),
+            labels: args.labels.clone(),
+            unclaimed: args.unclaimed,
+            agent: args.agent.clone(),
+            repo: args.repo.clone(),
+            comments: args.comments,
         }),
         TaskCommand::Tree(args) => TaskCommand::Tree(task::TreeArgs {
             id: args.id.clone(),
+            labels: args.labels.clone(),
         }),
         TaskCommand::Search(args) => TaskCommand::Search(task::SearchArgs {
             query: args.query.clone(),
+            all: args.all,
+            limit: args.limit,
         }),
         TaskCommand::Progress(args) => TaskCommand::Progress(task::ProgressArgs {
             id: args.id.clone(),
+            labels: args.labels.clone(),
+        }),
+        TaskCommand::Meta(cmd) => TaskCommand::Meta(match cmd {
+            task::MetaCommand::Set { id, path, value } => task::MetaCommand::Set {
+                id: id.clone(),
+                path: path.clone(),
+                value: value.clone(),
+            },
+            task::MetaCommand::Get { id, path } => task::MetaCommand::Get {
+                id: id.clone(),
+                path: path.clone(),
+            },
+            task::MetaCommand::Unset { id, path } => task::MetaCommand::Unset {
+                id: id.clone(),
+                path: path.clone(),
+            },
+        }),
+        TaskCommand::Label(cmd) => TaskCommand::Label(match cmd {
+            task::LabelCommand::Add { id, labels } => task::LabelCommand::Add {
+                id: id.clone(),
+                labels: labels.clone(),
+            },
+            task::LabelCommand::Rm { id, labels } => task::LabelCommand::Rm {
+                id: id.clone(),
+                labels: labels.clone(),
+            },
+        }),
+        TaskCommand::Comment(cmd) => TaskCommand::Comment(cmd.clone()),
+        TaskCommand::Link(cmd) => TaskCommand::Link(cmd.clone()),
+        TaskCommand::Claim(args) => TaskCommand::Claim(clone_claim_args(args)),
+        TaskCommand::Heartbeat(args) => TaskCommand::Heartbeat(clone_claim_args(args)),
+        TaskCommand::Release(args) => TaskCommand::Release(task::ReleaseArgs {
+            id: args.id.clone(),
+            agent: args.agent.clone(),
+            force: args.force,
+        }),
+        TaskCommand::Pause(args) => TaskCommand::Pause(task::SessionArgs {
+            id: args.id.clone(),
+            agent: args.agent.clone(),
         }),
+        TaskCommand::Resume(args) => TaskCommand::Resume(task::SessionArgs {
+            id: args.id.clone(),
+            agent: args.agent.clone(),
+        }),
+    }
+}
+
+fn clone_claim_args(args: &task::ClaimArgs) -> task::ClaimArgs {
+    task::ClaimArgs {
+        id: args.id.clone(),
+        agent: args.agent.clone(),
+        ttl: args.ttl,
     }
 }
 
@@ -529,6 +781,11 @@ fn clone_learning_cmd(cmd: &LearningCommand) -> LearningCommand {
             task_id: task_id.clone(),
         },
         LearningCommand::Delete { id } => LearningCommand::Delete { id: id.clone() },
+        LearningCommand::Search(args) => LearningCommand::Search(learning::SearchArgs {
+            query: args.query.clone(),
+            all: args.all,
+            limit: args.limit,
+        }),
     }
 }
 
@@ -539,6 +796,8 @@ fn clone_vcs_cmd(cmd: &VcsCommand) -> VcsCommand {
         VcsCommand::Log(args) => VcsCommand::Log(vcs_cmd::LogArgs { limit: args.limit }),
         VcsCommand::Diff(args) => VcsCommand::Diff(vcs_cmd::DiffArgs {
             base: args.base.clone(),
+            head: args.head.clone(),
+            patch: args.patch,
         }),
         VcsCommand::Commit(args) => VcsCommand::Commit(vcs_cmd::CommitArgs {
             message: args.message.clone(),
@@ -559,5 +818,9 @@ fn clone_data_cmd(cmd: &DataCommand) -> DataCommand {
         DataCommand::Export { output } => DataCommand::Export {
             output: output.clone(),
         },
+        DataCommand::Import { input, mode } => DataCommand::Import {
+            input: input.clone(),
+            mode: *mode,
+        },
     }
 }
@@ -3,8 +3,19 @@ use std::io::IsTerminal;
 use owo_colors::{OwoColorize, Style};
 use serde::Deserialize;
 
-use crate::commands::{learning::LearningCommand, task::TaskCommand, vcs::VcsCommand, DataCommand};
+use crate::commands::{
+    db::DbCommand,
+    events::EventsCommand,
+    learning::LearningCommand,
+    repo::RepoCommand,
+    report::ReportCommand,
+    task::{CommentCommand, LinkCommand, MetaCommand, TaskCommand, TrashCommand},
+    template::TemplateCommand,
+    vcs::VcsCommand,
+    DataCommand,
+};
 use crate::db;
+use crate::db::search_repo::SearchHit;
 use crate::id::TaskId;
 use crate::types;
 use crate::vcs::{
@@ -55,6 +66,8 @@ struct TreeTask {
     completed: bool,
     depth: Option<i32>,
     priority: i32,
+    #[serde(default)]
+    rank: i64,
     created_at: chrono::DateTime<chrono::Utc>,
     #[serde(default)]
     effectively_blocked: bool,
@@ -62,6 +75,10 @@ struct TreeTask {
     cancelled: bool,
     #[serde(default)]
     archived: bool,
+    #[serde(default)]
+    labels: Vec<String>,
+    #[serde(default)]
+    due_at: Option<chrono::DateTime<chrono::Utc>>,
 }
 
 /// Tree structure for display
@@ -71,6 +88,53 @@ struct TreeNode {
     children: Vec<TreeNode>,
 }
 
+/// `os template apply` result, with tasks read as [`TreeTask`]
+#[derive(Deserialize)]
+struct AppliedTemplateView {
+    name: String,
+    tasks: Vec<TreeTask>,
+    blockers: usize,
+}
+
+/// `os task restore` result, with the root read as [`TreeTask`]
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RestoredTrashView {
+    task: TreeTask,
+    tasks: usize,
+    blockers: usize,
+    #[serde(default)]
+    skipped_blockers: Vec<crate::core::trash::BlockerEdge>,
+}
+
+/// Elapsed time as `2h 05m`, `45m` or `30s`
+fn fmt_duration(seconds: i64) -> String {
+    let (hours, minutes) = (seconds / 3600, seconds % 3600 / 60);
+    if hours > 0 {
+        format!("{}h {:02}m", hours, minutes)
+    } else if minutes > 0 {
+        format!("{}m", minutes)
+    } else {
+        format!("{}s", seconds.max(0))
+    }
+}
+
+/// Widest diffstat bar, as `git diff --stat` on a narrow terminal
+const DIFFSTAT_WIDTH: usize = 40;
+
+/// `+` and `-` counts of a diffstat bar, scaled so the largest change fits
+fn diffstat_bar(additions: usize, deletions: usize, largest: usize) -> (usize, usize) {
+    if largest <= DIFFSTAT_WIDTH {
+        return (additions, deletions);
+    }
+    // Any change at all gets at least one mark
+    let scale = |n: usize| match n {
+        0 => 0,
+        n => (n * DIFFSTAT_WIDTH / largest).max(1),
+    };
+    (scale(additions), scale(deletions))
+}
+
 /// Color policy: --no-color > NO_COLOR env > TERM=dumb > !isatty > default (color)
 fn should_use_color_for(no_color_flag: bool, is_tty: bool) -> bool {
     if no_color_flag {
@@ -105,6 +169,7 @@ struct Colors {
     priority_med: Style,
     milestone: Style,
     tree_line: Style,
+    label: Style,
     error: Style,
 }
 
@@ -122,6 +187,7 @@ impl Colors {
                 priority_med: Style::new().yellow(),
                 milestone: Style::new().bold(),
                 tree_line: Style::new().dimmed(),
+                label: Style::new().blue(),
                 error: Style::new().red().bold(),
             }
         } else {
@@ -137,6 +203,7 @@ impl Colors {
                 priority_med: Style::new(),
                 milestone: Style::new(),
                 tree_line: Style::new(),
+                label: Style::new(),
                 error: Style::new(),
             }
         }
@@ -174,6 +241,29 @@ impl Printer {
         format!("{}", id.to_string().style(self.colors.task_id))
     }
 
+    /// Labels as a line suffix (` #backend #ui`), empty when there are none
+    fn fmt_labels(&self, labels: &[String]) -> String {
+        labels
+            .iter()
+            .map(|l| format!(" {}", format!("#{l}").style(self.colors.label)))
+            .collect()
+    }
+
+    /// ` (overdue)` suffix for open tasks past their due date
+    fn fmt_overdue(
+        &self,
+        status: TaskStatus,
+        due_at: Option<chrono::DateTime<chrono::Utc>>,
+    ) -> String {
+        let open = matches!(status, TaskStatus::Blocked | TaskStatus::Ready);
+        match due_at {
+            Some(due) if open && due < chrono::Utc::now() => {
+                format!(" {}", "(overdue)".style(self.colors.blocked))
+            }
+            _ => String::new(),
+        }
+    }
+
     /// Get status symbol and style for a task status
     fn status_symbol_style(&self, status: TaskStatus) -> (&'static str, Style) {
         match status {
@@ -188,7 +278,20 @@ impl Printer {
     pub fn print(&self, command: &Command, output: &str) {
         match command {
             Command::Init => println!("Initialized overseer database"),
-            Command::Task(TaskCommand::Delete { .. }) => println!("Task deleted"),
+            Command::Task(TaskCommand::Delete { id }) => println!(
+                "Moved {} to the trash (undo with `os task restore {}`)",
+                self.fmt_id(id),
+                id
+            ),
+            Command::Task(TaskCommand::Restore { .. }) => {
+                self.print_restored(output);
+            }
+            Command::Task(TaskCommand::Trash(TrashCommand::List)) => {
+                self.print_trash(output);
+            }
+            Command::Task(TaskCommand::Trash(TrashCommand::Purge { .. })) => {
+                self.print_purged(output);
+            }
             Command::Task(TaskCommand::NextReady(_)) => {
                 self.print_next_ready(output);
             }
@@ -199,7 +302,7 @@ impl Printer {
                 self.print_task_progress(output);
             }
             Command::Task(TaskCommand::Search(_)) => {
-                self.print_task_list_flat(output);
+                self.print_task_search(output);
             }
             Command::Task(TaskCommand::List(args)) => {
                 if args.flat {
@@ -211,6 +314,38 @@ impl Printer {
             Command::Task(TaskCommand::Get { .. }) => {
                 println!("{}", output);
             }
+            Command::Task(TaskCommand::Meta(MetaCommand::Get { .. })) => {
+                println!("{}", output);
+            }
+            Command::Task(TaskCommand::Claim(_) | TaskCommand::Heartbeat(_)) => {
+                self.print_claim(output);
+            }
+            Command::Task(TaskCommand::Release(_)) => println!("Claim released"),
+            Command::Task(TaskCommand::Comment(CommentCommand::Add { .. })) => {
+                match serde_json::from_str::<db::Comment>(output) {
+                    Ok(comment) => self.print_comment(&comment, ""),
+                    Err(_) => println!("{}", output),
+                }
+            }
+            Command::Task(TaskCommand::Comment(CommentCommand::List { .. })) => {
+                self.print_comments(output);
+            }
+            Command::Task(TaskCommand::Comment(CommentCommand::Delete { .. })) => {
+                println!("Comment deleted")
+            }
+            Command::Task(TaskCommand::Link(LinkCommand::Add(_))) => {
+                match serde_json::from_str::<db::TaskLink>(output) {
+                    Ok(link) => self.print_link(&link, ""),
+                    Err(_) => println!("{}", output),
+                }
+            }
+            Command::Task(TaskCommand::Link(LinkCommand::List { .. })) => {
+                self.print_links(output);
+            }
+            Command::Task(TaskCommand::Link(LinkCommand::Rm { .. })) => println!("Link removed"),
+            Command::Task(TaskCommand::Pause(_) | TaskCommand::Resume(_)) => {
+                self.print_session(output);
+            }
             Command::Task(_) => {
                 self.print_task(output);
             }
@@ -218,6 +353,9 @@ impl Printer {
             Command::Learning(LearningCommand::List { .. }) => {
                 self.print_learning_list(output);
             }
+            Command::Learning(LearningCommand::Search(_)) => {
+                self.print_learning_search(output);
+            }
             Command::Learning(_) => {
                 self.print_learning(output);
             }
@@ -242,6 +380,41 @@ impl Printer {
             Command::Data(DataCommand::Export { .. }) => {
                 self.print_data_export(output);
             }
+            Command::Data(DataCommand::Import { .. }) => {
+                self.print_data_import(output);
+            }
+            Command::Db(DbCommand::Status) => {
+                self.print_db_status(output);
+            }
+            Command::Db(DbCommand::Migrate(_)) => {
+                self.print_db_migrate(output);
+            }
+            Command::Db(DbCommand::Check(_)) => {
+                self.print_db_check(output);
+            }
+            Command::Db(DbCommand::Config(_)) => {
+                self.print_db_config(output);
+            }
+            Command::Events(EventsCommand::List(_)) => {
+                self.print_events(output);
+            }
+            Command::Report(ReportCommand::Time(_)) => {
+                self.print_time_report(output);
+            }
+            Command::Template(TemplateCommand::Save(_)) => {
+                self.print_template_saved(output);
+            }
+            Command::Template(TemplateCommand::Apply(_)) => {
+                self.print_template_applied(output);
+            }
+            Command::Template(TemplateCommand::List) => {
+                self.print_templates(output);
+            }
+            Command::Repo(RepoCommand::Add { .. }) => self.print_repo("Registered", output),
+            Command::Repo(RepoCommand::Remove { .. }) => self.print_repo("Unregistered", output),
+            Command::Repo(RepoCommand::List) => self.print_repos(output),
+            // PRECONDITION: tail handled in main() before print() is called
+            Command::Events(EventsCommand::Tail(_)) => unreachable!("tail handled before print()"),
             // PRECONDITION: Completions handled in main() before print() is called
             Command::Completions { .. } => unreachable!("completions handled before print()"),
             // PRECONDITION: UI and MCP handled in main() before print() is called
@@ -275,6 +448,36 @@ impl Printer {
                 if let Some(depth) = task.get("depth").and_then(|v| v.as_i64()) {
                     println!("  Depth: {}", depth);
                 }
+                if let Some(labels) = task.get("labels").and_then(|v| v.as_array()) {
+                    let labels: Vec<&str> = labels.iter().filter_map(|l| l.as_str()).collect();
+                    println!("  Labels: {}", labels.join(", "));
+                }
+                if let Some(agent) = task
+                    .get("claim")
+                    .and_then(|c| c.get("agent"))
+                    .and_then(|v| v.as_str())
+                {
+                    println!("  Claimed by: {}", agent);
+                }
+                if let Some(comments) = task
+                    .get("comments")
+                    .and_then(|c| serde_json::from_value::<Vec<db::Comment>>(c.clone()).ok())
+                    .filter(|c| !c.is_empty())
+                {
+                    println!("  Comments:");
+                    for comment in &comments {
+                        self.print_comment(comment, "    ");
+                    }
+                }
+                if let Some(links) = task
+                    .get("links")
+                    .and_then(|l| serde_json::from_value::<Vec<db::TaskLink>>(l.clone()).ok())
+                {
+                    println!("  Links:");
+                    for link in &links {
+                        self.print_link(link, "    ");
+                    }
+                }
             } else {
                 println!("{}", output);
             }
@@ -386,12 +589,14 @@ impl Printer {
         };
 
         println!(
-            "{}{} [{}] {} - {}",
+            "{}{} [{}] {} - {}{}{}",
             tree_prefix,
             tree_connector,
             status_sym.style(status_style),
             self.fmt_id(&tree.task.id),
-            desc
+            desc,
+            self.fmt_labels(&tree.task.labels),
+            self.fmt_overdue(status, tree.task.due_at)
         );
 
         let new_prefix = format!("{}{}  ", prefix, if is_last { " " } else { "│" });
@@ -427,10 +632,12 @@ impl Printer {
                     }
                     let (status_sym, status_style) = self.status_symbol_style(status);
                     println!(
-                        "[{}] {} - {}",
+                        "[{}] {} - {}{}{}",
                         status_sym.style(status_style),
                         self.fmt_id(&t.id),
-                        t.description
+                        t.description,
+                        self.fmt_labels(&t.labels),
+                        self.fmt_overdue(status, t.due_at)
                     );
                 }
 
@@ -517,12 +724,13 @@ impl Printer {
                 .iter()
                 .filter_map(|cid| build_node(cid, task_map, children_map))
                 .collect();
-            // Sort: incomplete first, then priority ASC (p0 first), created_at ASC, id ASC (deterministic)
+            // Sort: incomplete first, then priority ASC (p0 first), rank ASC, created_at ASC, id ASC (deterministic)
             children.sort_by(|a, b| {
                 a.task
                     .completed
                     .cmp(&b.task.completed)
                     .then_with(|| a.task.priority.cmp(&b.task.priority))
+                    .then_with(|| a.task.rank.cmp(&b.task.rank))
                     .then_with(|| a.task.created_at.cmp(&b.task.created_at))
                     .then_with(|| a.task.id.cmp(&b.task.id))
             });
@@ -536,13 +744,14 @@ impl Printer {
             .filter_map(|id| build_node(id, &mut task_map, &children_map))
             .collect();
 
-        // Sort roots: incomplete first, depth ASC (milestones first), priority ASC (p0 first), created_at ASC, id ASC
+        // Sort roots: incomplete first, depth ASC (milestones first), priority ASC (p0 first), rank ASC, created_at ASC, id ASC
         roots.sort_by(|a, b| {
             a.task
                 .completed
                 .cmp(&b.task.completed)
                 .then_with(|| a.task.depth.cmp(&b.task.depth))
                 .then_with(|| a.task.priority.cmp(&b.task.priority))
+                .then_with(|| a.task.rank.cmp(&b.task.rank))
                 .then_with(|| a.task.created_at.cmp(&b.task.created_at))
                 .then_with(|| a.task.id.cmp(&b.task.id))
         });
@@ -582,20 +791,24 @@ impl Printer {
         if is_root {
             // Root nodes: no connector prefix
             println!(
-                "[{}] {} - {}",
+                "[{}] {} - {}{}{}",
                 status_sym.style(status_style),
                 self.fmt_id(&node.task.id),
-                desc
+                desc,
+                self.fmt_labels(&node.task.labels),
+                self.fmt_overdue(status, node.task.due_at)
             );
         } else {
             // Child nodes: use tree connectors (caller sets correct prefix)
             let tree_prefix = format!("{}", prefix.style(self.colors.tree_line));
             println!(
-                "{}[{}] {} - {}",
+                "{}[{}] {} - {}{}{}",
                 tree_prefix,
                 status_sym.style(status_style),
                 self.fmt_id(&node.task.id),
-                desc
+                desc,
+                self.fmt_labels(&node.task.labels),
+                self.fmt_overdue(status, node.task.due_at)
             );
         }
 
@@ -638,11 +851,13 @@ impl Printer {
 
         let styled_prefix = format!("{}", line_prefix.style(self.colors.tree_line));
         println!(
-            "{}[{}] {} - {}",
+            "{}[{}] {} - {}{}{}",
             styled_prefix,
             status_sym.style(status_style),
             self.fmt_id(&node.task.id),
-            desc
+            desc,
+            self.fmt_labels(&node.task.labels),
+            self.fmt_overdue(status, node.task.due_at)
         );
 
         let child_count = node.children.len();
@@ -694,6 +909,7 @@ impl Printer {
             if let Some(depth) = task.depth {
                 println!("  Depth: {}", depth);
             }
+            println!("  Revision: {}", task.revision);
             if !task.blocked_by.is_empty() {
                 let blocked_ids: Vec<String> =
                     task.blocked_by.iter().map(|id| self.fmt_id(id)).collect();
@@ -703,6 +919,92 @@ impl Printer {
                 let block_ids: Vec<String> = task.blocks.iter().map(|id| self.fmt_id(id)).collect();
                 println!("  Blocks: {}", block_ids.join(", "));
             }
+            if let Some(ref metadata) = task.metadata {
+                println!("  Metadata: {}", metadata);
+            }
+            if !task.labels.is_empty() {
+                println!("  Labels: {}", task.labels.join(", "));
+            }
+            if let Some(due) = task.due_at {
+                println!(
+                    "  Due: {}{}",
+                    due.format("%Y-%m-%d %H:%M:%S"),
+                    self.fmt_overdue(status, task.due_at)
+                );
+            }
+            if let Some(until) = task.defer_until {
+                println!("  Deferred until: {}", until.format("%Y-%m-%d %H:%M:%S"));
+            }
+            if let Some(ref claim) = task.claim {
+                println!(
+                    "  Claimed by: {} (until {})",
+                    claim.agent,
+                    claim.expires_at.format("%Y-%m-%d %H:%M:%S")
+                );
+            }
+            if let Some(ref worktree) = task.worktree {
+                println!("  Worktree: {}", worktree);
+            }
+        } else {
+            println!("{}", output);
+        }
+    }
+
+    fn print_claim(&self, output: &str) {
+        if let Ok(claim) = serde_json::from_str::<types::TaskClaim>(output) {
+            println!(
+                "Claimed {} for {} until {}",
+                self.fmt_id(&claim.task_id),
+                claim.agent,
+                claim.expires_at.format("%Y-%m-%d %H:%M:%S")
+            );
+        } else {
+            println!("{}", output);
+        }
+    }
+
+    fn print_task_search(&self, output: &str) {
+        if let Ok(hits) = serde_json::from_str::<Vec<SearchHit<types::Task>>>(output) {
+            if hits.is_empty() {
+                println!("No tasks found");
+            }
+            for hit in hits {
+                let t = &hit.item;
+                let status = TaskStatus::classify(
+                    t.completed,
+                    t.effectively_blocked,
+                    t.cancelled,
+                    t.archived,
+                );
+                let (status_sym, status_style) = self.status_symbol_style(status);
+                println!(
+                    "[{}] {} - {}{}{}",
+                    status_sym.style(status_style),
+                    self.fmt_id(&t.id),
+                    t.description,
+                    self.fmt_labels(&t.labels),
+                    self.fmt_overdue(status, t.due_at)
+                );
+                println!("    {}", hit.snippet);
+            }
+        } else {
+            println!("{}", output);
+        }
+    }
+
+    fn print_learning_search(&self, output: &str) {
+        if let Ok(hits) = serde_json::from_str::<Vec<SearchHit<db::Learning>>>(output) {
+            if hits.is_empty() {
+                println!("No learnings found");
+            }
+            for hit in hits {
+                println!(
+                    "• {} ({}) - {}",
+                    self.fmt_id(&hit.item.id),
+                    self.fmt_id(&hit.item.task_id),
+                    hit.snippet
+                );
+            }
         } else {
             println!("{}", output);
         }
@@ -782,22 +1084,84 @@ impl Printer {
     }
 
     fn print_vcs_diff(&self, output: &str) {
-        if let Ok(entries) = serde_json::from_str::<Vec<DiffEntry>>(output) {
-            if entries.is_empty() {
-                println!("No changes");
+        let Ok(entries) = serde_json::from_str::<Vec<DiffEntry>>(output) else {
+            println!("{}", output);
+            return;
+        };
+        if entries.is_empty() {
+            println!("No changes");
+            return;
+        }
+
+        let names: Vec<String> = entries
+            .iter()
+            .map(|entry| match &entry.old_path {
+                Some(old) => format!("{old} => {}", entry.path),
+                None => entry.path.clone(),
+            })
+            .collect();
+        let name_width = names.iter().map(|n| n.chars().count()).max().unwrap_or(0);
+        let largest = entries
+            .iter()
+            .map(|e| e.additions + e.deletions)
+            .max()
+            .unwrap_or(0);
+        let count_width = largest.to_string().len();
+
+        for (entry, name) in entries.iter().zip(&names) {
+            let (symbol, style) = match entry.change_type {
+                ChangeType::Added => ("+", self.colors.completed),
+                ChangeType::Deleted => ("-", self.colors.blocked),
+                ChangeType::Modified => ("~", self.colors.pending),
+                ChangeType::Renamed => ("→", self.colors.pending),
+            };
+            let stat = if entry.binary {
+                "Bin".to_string()
             } else {
-                for entry in entries {
-                    let (symbol, style) = match entry.change_type {
-                        ChangeType::Added => ("+", self.colors.completed),
-                        ChangeType::Deleted => ("-", self.colors.blocked),
-                        ChangeType::Modified => ("~", self.colors.pending),
-                        ChangeType::Renamed => ("→", self.colors.pending),
-                    };
-                    println!("{} {}", symbol.style(style), entry.path);
+                let (plus, minus) = diffstat_bar(entry.additions, entry.deletions, largest);
+                let count = format!("{:>count_width$}", entry.additions + entry.deletions);
+                if plus + minus == 0 {
+                    count
+                } else {
+                    format!(
+                        "{count} {}{}",
+                        "+".repeat(plus).style(self.colors.completed),
+                        "-".repeat(minus).style(self.colors.blocked)
+                    )
+                }
+            };
+            println!("{} {:<name_width$} | {}", symbol.style(style), name, stat);
+        }
+
+        let additions: usize = entries.iter().map(|e| e.additions).sum();
+        let deletions: usize = entries.iter().map(|e| e.deletions).sum();
+        let plural = |n: usize| if n == 1 { "" } else { "s" };
+        println!(
+            "{} file{} changed, {} insertion{}(+), {} deletion{}(-)",
+            entries.len(),
+            plural(entries.len()),
+            additions,
+            plural(additions),
+            deletions,
+            plural(deletions)
+        );
+
+        for patch in entries.iter().filter_map(|e| e.patch.as_deref()) {
+            if patch.is_empty() {
+                continue;
+            }
+            println!();
+            for line in patch.lines() {
+                if line.starts_with("@@") {
+                    println!("{}", line.style(self.colors.tree_line));
+                } else if line.starts_with('+') && !line.starts_with("+++") {
+                    println!("{}", line.style(self.colors.completed));
+                } else if line.starts_with('-') && !line.starts_with("---") {
+                    println!("{}", line.style(self.colors.blocked));
+                } else {
+                    println!("{}", line);
                 }
             }
-        } else {
-            println!("{}", output);
         }
     }
 
@@ -871,6 +1235,502 @@ impl Printer {
             println!("{}", output);
         }
     }
+
+    fn print_db_status(&self, output: &str) {
+        use crate::commands::db::{DbStatus, SchemaState};
+
+        if let Ok(status) = serde_json::from_str::<DbStatus>(output) {
+            println!("Database: {}", status.path.display());
+            let state = match status.state {
+                SchemaState::Missing => "not created yet".to_string(),
+                SchemaState::UpToDate => "up to date".to_string(),
+                SchemaState::Outdated => format!("{} migration(s) pending", status.pending.len()),
+                SchemaState::TooNew => "newer than this os supports - upgrade os".to_string(),
+            };
+            println!(
+                "  Schema: v{} (latest v{}) - {}",
+                status.current_version, status.latest_version, state
+            );
+            for m in &status.pending {
+                println!("  • v{} {} - {}", m.version, m.id, m.description);
+            }
+        } else {
+            println!("{}", output);
+        }
+    }
+
+    fn print_db_migrate(&self, output: &str) {
+        use crate::commands::db::MigrateResult;
+
+        if let Ok(result) = serde_json::from_str::<MigrateResult>(output) {
+            if result.migrations.is_empty() {
+                println!("Schema up to date (v{})", result.to_version);
+                return;
+            }
+            let verb = if result.dry_run {
+                "Would migrate"
+            } else {
+                "Migrated"
+            };
+            println!("{} v{} → v{}", verb, result.from_version, result.to_version);
+            for m in &result.migrations {
+                println!("  • v{} {} - {}", m.version, m.id, m.description);
+            }
+            if let Some(ref backup) = result.backup {
+                println!("Backup: {}", backup.display());
+            }
+        } else {
+            println!("{}", output);
+        }
+    }
+
+    fn print_db_config(&self, output: &str) {
+        use crate::db::settings_repo::Settings;
+
+        if let Ok(settings) = serde_json::from_str::<Settings>(output) {
+            println!("Max depth: {} levels", settings.max_depth);
+            println!("Integration: {}", settings.integration.as_str());
+            if let Some(ref dir) = settings.worktree_dir {
+                println!("Worktree dir: {}", dir);
+            }
+        } else {
+            println!("{}", output);
+        }
+    }
+
+    fn print_db_check(&self, output: &str) {
+        use crate::commands::db::CheckResult;
+
+        if let Ok(result) = serde_json::from_str::<CheckResult>(output) {
+            if result.issues.is_empty() {
+                println!("{}", "No problems found".style(self.colors.completed));
+            }
+            for issue in &result.issues {
+                let code = issue.code.as_str();
+                let code = if issue.fixed {
+                    code.style(self.colors.completed)
+                } else {
+                    code.style(self.colors.blocked)
+                };
+                let task = issue
+                    .task_id
+                    .as_ref()
+                    .map(|id| format!(" {}", self.fmt_id(id)))
+                    .unwrap_or_default();
+                let status = if issue.fixed { " (fixed)" } else { "" };
+                println!("  {}{} {}{}", code, task, issue.message, status);
+            }
+
+            let unfixed = result.issues.iter().filter(|i| !i.fixed).count();
+            let fixable = result
+                .issues
+                .iter()
+                .filter(|i| i.fixable && !i.fixed)
+                .count();
+            if result.fixed > 0 {
+                println!();
+                println!(
+                    "{} problem(s) fixed",
+                    result.fixed.style(self.colors.completed)
+                );
+            }
+            if unfixed > 0 {
+                println!();
+                println!(
+                    "{} problem(s) remaining",
+                    unfixed.style(self.colors.blocked)
+                );
+                if fixable > 0 {
+                    println!(
+                        "Run with {} to repair {} of them",
+                        "--fix".style(self.colors.pending),
+                        fixable
+                    );
+                }
+            }
+            if !result.vcs_checked {
+                println!("note: not in a repository, branch checks skipped");
+            }
+        } else {
+            println!("{}", output);
+        }
+    }
+
+    fn print_events(&self, output: &str) {
+        use crate::db::event_repo::Event;
+
+        if let Ok(events) = serde_json::from_str::<Vec<Event>>(output) {
+            if events.is_empty() {
+                println!("No events found");
+            }
+            for event in events {
+                // Adjacently tagged body: the type string lives under "type"
+                let body = serde_json::to_value(&event.body).unwrap_or_default();
+                let event_type = body["type"].as_str().unwrap_or("?");
+                let task = event
+                    .task_id
+                    .as_ref()
+                    .map(|id| format!(" {}", self.fmt_id(id)))
+                    .unwrap_or_default();
+                println!(
+                    "#{} {} {}{} ({})",
+                    event.seq,
+                    event.at.format("%Y-%m-%d %H:%M:%S"),
+                    event_type,
+                    task,
+                    event.source.as_str()
+                );
+            }
+        } else {
+            println!("{}", output);
+        }
+    }
+
+    fn print_session(&self, output: &str) {
+        if let Ok(session) = serde_json::from_str::<types::TaskSession>(output) {
+            match session.ended_at {
+                Some(ended_at) => println!(
+                    "Paused {} after {}",
+                    self.fmt_id(&session.task_id),
+                    fmt_duration((ended_at - session.started_at).num_seconds())
+                ),
+                None => println!(
+                    "Resumed {} at {}",
+                    self.fmt_id(&session.task_id),
+                    session.started_at.format("%Y-%m-%d %H:%M:%S")
+                ),
+            }
+        } else {
+            println!("{}", output);
+        }
+    }
+
+    fn print_time_report(&self, output: &str) {
+        use crate::core::time_report::TimeReport;
+
+        let Ok(report) = serde_json::from_str::<TimeReport>(output) else {
+            println!("{}", output);
+            return;
+        };
+        let window = match report.since {
+            Some(since) => format!(
+                "{} to {} UTC",
+                since.format("%Y-%m-%d %H:%M"),
+                report.until.format("%Y-%m-%d %H:%M")
+            ),
+            None => format!("until {} UTC", report.until.format("%Y-%m-%d %H:%M")),
+        };
+        if report.tasks.is_empty() {
+            println!("No time recorded ({})", window);
+            return;
+        }
+        println!(
+            "Total: {} ({})",
+            fmt_duration(report.total_seconds).style(self.colors.milestone),
+            window
+        );
+
+        println!("\nBy milestone:");
+        for m in &report.milestones {
+            println!(
+                "  {:>8}  {} - {}",
+                fmt_duration(m.seconds),
+                self.fmt_id(&m.milestone_id),
+                m.description.style(self.colors.milestone)
+            );
+        }
+
+        println!("\nBy task:");
+        for t in &report.tasks {
+            let sessions = if t.sessions == 1 {
+                "session"
+            } else {
+                "sessions"
+            };
+            let running = if t.running {
+                format!(", {}", "running".style(self.colors.pending))
+            } else {
+                String::new()
+            };
+            println!(
+                "  {:>8}  {} - {} ({} {}{})",
+                fmt_duration(t.seconds),
+                self.fmt_id(&t.task_id),
+                t.description,
+                t.sessions,
+                sessions,
+                running
+            );
+        }
+
+        println!("\nBy day:");
+        for d in &report.days {
+            println!("  {}  {:>8}", d.date, fmt_duration(d.seconds));
+        }
+    }
+
+    fn print_comments(&self, output: &str) {
+        let Ok(comments) = serde_json::from_str::<Vec<db::Comment>>(output) else {
+            println!("{}", output);
+            return;
+        };
+        if comments.is_empty() {
+            println!("No comments");
+            return;
+        }
+        for comment in &comments {
+            self.print_comment(comment, "");
+        }
+    }
+
+    /// Header line (`2026-03-01 14:05 alice cmt_...`) then the indented body
+    fn print_comment(&self, comment: &db::Comment, indent: &str) {
+        println!(
+            "{}{} {} {}",
+            indent,
+            comment.created_at.format("%Y-%m-%d %H:%M"),
+            comment
+                .author
+                .as_deref()
+                .unwrap_or("anonymous")
+                .style(self.colors.label),
+            self.fmt_id(&comment.id)
+        );
+        for line in comment.body.lines() {
+            println!("{}  {}", indent, line);
+        }
+    }
+
+    fn print_links(&self, output: &str) {
+        let Ok(links) = serde_json::from_str::<Vec<db::TaskLink>>(output) else {
+            println!("{}", output);
+            return;
+        };
+        if links.is_empty() {
+            println!("No links");
+            return;
+        }
+        for link in &links {
+            self.print_link(link, "");
+        }
+    }
+
+    /// `lnk_...  fileRange  src/auth.rs:40-85  - note`
+    fn print_link(&self, link: &db::TaskLink, indent: &str) {
+        use crate::db::link_repo::LinkKind;
+
+        let target = match (link.kind, link.line_start, link.line_end) {
+            (LinkKind::FileRange, Some(start), Some(end)) if start == end => {
+                format!("{}:{}", link.target, start)
+            }
+            (LinkKind::FileRange, Some(start), Some(end)) => {
+                format!("{}:{}-{}", link.target, start, end)
+            }
+            (LinkKind::Commit, ..) => link.target.chars().take(12).collect(),
+            _ => link.target.clone(),
+        };
+        let note = link
+            .note
+            .as_deref()
+            .map(|n| format!("  - {n}"))
+            .unwrap_or_default();
+        println!(
+            "{}{}  {}  {}{}",
+            indent,
+            self.fmt_id(&link.id),
+            format!("{:<9}", link.kind.as_str()).style(self.colors.label),
+            target,
+            note
+        );
+    }
+
+    fn print_restored(&self, output: &str) {
+        let Ok(restored) = serde_json::from_str::<RestoredTrashView>(output) else {
+            println!("{}", output);
+            return;
+        };
+        println!(
+            "Restored {} - {} ({} tasks, {} blockers)",
+            self.fmt_id(&restored.task.id),
+            restored.task.description,
+            restored.tasks.style(self.colors.completed),
+            restored.blockers
+        );
+        for edge in &restored.skipped_blockers {
+            println!(
+                "  {} {} blocked by {} (task gone or edge no longer valid)",
+                "skipped".style(self.colors.pending),
+                self.fmt_id(&edge.task_id),
+                self.fmt_id(&edge.blocker_id)
+            );
+        }
+    }
+
+    /// `task_...  2026-03-01 14:05  3 tasks  description`
+    fn print_trash(&self, output: &str) {
+        use crate::db::trash_repo::TrashEntry;
+
+        let Ok(entries) = serde_json::from_str::<Vec<TrashEntry>>(output) else {
+            println!("{}", output);
+            return;
+        };
+        if entries.is_empty() {
+            println!("Trash is empty");
+            return;
+        }
+        for entry in &entries {
+            println!(
+                "{}  {}  {:>3} {}  {}",
+                self.fmt_id(&entry.id),
+                entry.deleted_at.format("%Y-%m-%d %H:%M"),
+                entry.tasks,
+                if entry.tasks == 1 { "task " } else { "tasks" },
+                entry.description
+            );
+        }
+    }
+
+    fn print_purged(&self, output: &str) {
+        use crate::core::trash::PurgedTrash;
+
+        let Ok(purged) = serde_json::from_str::<PurgedTrash>(output) else {
+            println!("{}", output);
+            return;
+        };
+        let tasks: usize = purged.purged.iter().map(|e| e.tasks).sum();
+        println!(
+            "Purged {} subtrees ({} tasks)",
+            purged.purged.len().style(self.colors.completed),
+            tasks
+        );
+        if !purged.deleted_branches.is_empty() {
+            println!("{} branches deleted", purged.deleted_branches.len());
+        }
+    }
+
+    fn print_template_saved(&self, output: &str) {
+        use crate::core::template::TemplateSummary;
+
+        let Ok(summary) = serde_json::from_str::<TemplateSummary>(output) else {
+            println!("{}", output);
+            return;
+        };
+        println!(
+            "Saved template {} ({} tasks) to {}",
+            summary.name.style(self.colors.milestone),
+            summary.tasks,
+            summary.path.display()
+        );
+        if !summary.variables.is_empty() {
+            println!("Variables: {}", summary.variables.join(", "));
+        }
+    }
+
+    fn print_template_applied(&self, output: &str) {
+        let Ok(applied) = serde_json::from_str::<AppliedTemplateView>(output) else {
+            println!("{}", output);
+            return;
+        };
+        println!(
+            "Created {} tasks and {} blockers from template {}",
+            applied.tasks.len().style(self.colors.completed),
+            applied.blockers,
+            applied.name
+        );
+        let base = applied.tasks.first().and_then(|t| t.depth).unwrap_or(0);
+        for task in &applied.tasks {
+            let indent = "  ".repeat((task.depth.unwrap_or(base) - base) as usize + 1);
+            println!(
+                "{}{} - {}{}",
+                indent,
+                self.fmt_id(&task.id),
+                task.description,
+                self.fmt_labels(&task.labels)
+            );
+        }
+    }
+
+    fn print_templates(&self, output: &str) {
+        use crate::core::template::TemplateSummary;
+
+        let Ok(summaries) = serde_json::from_str::<Vec<TemplateSummary>>(output) else {
+            println!("{}", output);
+            return;
+        };
+        if summaries.is_empty() {
+            println!("No templates (save one with `os template save`)");
+            return;
+        }
+        for summary in &summaries {
+            let variables = if summary.variables.is_empty() {
+                String::new()
+            } else {
+                format!(" [{}]", summary.variables.join(", "))
+            };
+            println!(
+                "{}  {} tasks{}",
+                summary.name.style(self.colors.milestone),
+                summary.tasks,
+                variables
+            );
+        }
+    }
+
+    fn print_repo(&self, verb: &str, output: &str) {
+        let Ok(repo) = serde_json::from_str::<db::repo_repo::Repo>(output) else {
+            println!("{}", output);
+            return;
+        };
+        println!(
+            "{} repo {} ({}) at {}",
+            verb,
+            repo.name.style(self.colors.milestone),
+            self.fmt_id(&repo.id),
+            repo.path
+        );
+    }
+
+    fn print_repos(&self, output: &str) {
+        let Ok(repos) = serde_json::from_str::<Vec<db::repo_repo::Repo>>(output) else {
+            println!("{}", output);
+            return;
+        };
+        if repos.is_empty() {
+            println!("No repos registered (add one with `os repo add`)");
+            return;
+        }
+        for repo in &repos {
+            println!(
+                "{}  {}  {}",
+                repo.name.style(self.colors.milestone),
+                self.fmt_id(&repo.id),
+                repo.path
+            );
+        }
+    }
+
+    fn print_data_import(&self, output: &str) {
+        use crate::commands::data::ImportSummary;
+
+        if let Ok(summary) = serde_json::from_str::<ImportSummary>(output) {
+            println!(
+                "Imported {} tasks, {} learnings and {} blockers from {}",
+                summary.tasks.style(self.colors.completed),
+                summary.learnings,
+                summary.blockers,
+                summary.path
+            );
+            if summary.skipped_tasks > 0 || summary.skipped_learnings > 0 {
+                println!(
+                    "Skipped {} existing tasks and {} existing learnings",
+                    summary.skipped_tasks.style(self.colors.pending),
+                    summary.skipped_learnings
+                );
+            }
+        } else {
+            println!("{}", output);
+        }
+    }
 }
 
 impl Default for Printer {
@@ -1,9 +1,10 @@
 //! Test utilities for creating real git repositories.
 
 use std::fs;
-use std::io;
+use std::io::{self, Write};
 use std::path::{Path, PathBuf};
 
+use gix::objs::tree::EntryKind;
 use tempfile::TempDir;
 
 /// Common operations for test repositories.
@@ -37,7 +38,8 @@ pub trait TestRepo {
 }
 
 /// A test git repository backed by a temporary directory.
-/// Uses std::process::Command to run git commands.
+/// Created and committed to with plain gix objects, so tests do not need a git
+/// binary and fixtures do not go through the backend under test.
 pub struct GitTestRepo {
     #[allow(dead_code)]
     tempdir: TempDir,
@@ -50,83 +52,80 @@ impl GitTestRepo {
         let tempdir = TempDir::new()?;
         let root = tempdir.path().to_path_buf();
 
-        let output = std::process::Command::new("git")
-            .args(["init"])
-            .current_dir(&root)
-            .output()?;
+        gix::init(&root).map_err(io::Error::other)?;
 
-        if !output.status.success() {
-            return Err(io::Error::other(format!(
-                "git init failed: {}",
-                String::from_utf8_lossy(&output.stderr)
-            )));
-        }
-
-        std::process::Command::new("git")
-            .args(["config", "user.name", "Test User"])
-            .current_dir(&root)
-            .output()?;
-
-        std::process::Command::new("git")
-            .args(["config", "user.email", "test@example.com"])
-            .current_dir(&root)
-            .output()?;
+        let mut config = fs::OpenOptions::new()
+            .append(true)
+            .open(root.join(".git/config"))?;
+        config.write_all(b"[user]\n\tname = Test User\n\temail = test@example.com\n")?;
 
         Ok(Self { tempdir, root })
     }
 
     /// Stages all changes.
     pub fn add_all(&self) -> io::Result<()> {
-        let output = std::process::Command::new("git")
-            .args(["add", "-A"])
-            .current_dir(&self.root)
-            .output()?;
-
-        if !output.status.success() {
-            return Err(io::Error::other(format!(
-                "git add failed: {}",
-                String::from_utf8_lossy(&output.stderr)
-            )));
+        self.stage_all().map(|_| ())
+    }
+
+    /// Writes every file under the root as one tree and makes it the index.
+    fn stage_all(&self) -> io::Result<gix::ObjectId> {
+        let repo = gix::open(&self.root).map_err(io::Error::other)?;
+        let mut editor = repo
+            .edit_tree(gix::ObjectId::empty_tree(repo.object_hash()))
+            .map_err(io::Error::other)?;
+        let mut dirs = vec![self.root.clone()];
+        while let Some(dir) = dirs.pop() {
+            for entry in fs::read_dir(&dir)? {
+                let path = entry?.path();
+                let metadata = fs::symlink_metadata(&path)?;
+                if metadata.is_dir() {
+                    if path != self.root.join(".git") {
+                        dirs.push(path);
+                    }
+                    continue;
+                }
+                let kind = if gix::fs::is_executable(&metadata) {
+                    EntryKind::BlobExecutable
+                } else {
+                    EntryKind::Blob
+                };
+                let id = repo
+                    .write_blob(fs::read(&path)?)
+                    .map_err(io::Error::other)?;
+                let relative = path.strip_prefix(&self.root).map_err(io::Error::other)?;
+                let relative = relative
+                    .to_str()
+                    .ok_or_else(|| io::Error::other("not UTF-8"))?;
+                editor
+                    .upsert(relative, kind, id)
+                    .map_err(io::Error::other)?;
+            }
         }
-        Ok(())
+        let tree = editor.write().map_err(io::Error::other)?.detach();
+
+        let mut index = repo.index_from_tree(&tree).map_err(io::Error::other)?;
+        index.write(Default::default()).map_err(io::Error::other)?;
+        Ok(tree)
     }
 
-    /// Commits staged changes with a message.
+    /// Commits all changes with a message, even if there are none.
     pub fn commit(&self, message: &str) -> io::Result<String> {
-        self.add_all()?;
-
-        let output = std::process::Command::new("git")
-            .args(["commit", "-m", message, "--allow-empty"])
-            .current_dir(&self.root)
-            .output()?;
-
-        if !output.status.success() {
-            return Err(io::Error::other(format!(
-                "git commit failed: {}",
-                String::from_utf8_lossy(&output.stderr)
-            )));
-        }
+        let tree = self.stage_all()?;
 
-        let output = std::process::Command::new("git")
-            .args(["rev-parse", "HEAD"])
-            .current_dir(&self.root)
-            .output()?;
+        let repo = gix::open(&self.root).map_err(io::Error::other)?;
+        let parent = repo.head_id().ok().map(|id| id.detach());
+        let id = repo
+            .commit("HEAD", message, tree, parent)
+            .map_err(io::Error::other)?;
 
-        Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+        Ok(id.to_string())
     }
 
     /// Gets the current HEAD commit hash.
     pub fn head(&self) -> io::Result<String> {
-        let output = std::process::Command::new("git")
-            .args(["rev-parse", "HEAD"])
-            .current_dir(&self.root)
-            .output()?;
-
-        if !output.status.success() {
-            return Err(io::Error::other("git rev-parse HEAD failed"));
-        }
-
-        Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+        let repo = gix::open(&self.root).map_err(io::Error::other)?;
+        let id = repo.head_id().map_err(io::Error::other)?;
+        Ok(id.to_string())
     }
 }
 
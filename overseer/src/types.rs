@@ -5,7 +5,7 @@ use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 
 use crate::db::learning_repo::Learning;
-use crate::id::TaskId;
+use crate::id::{RepoId, TaskId};
 
 /// Task lifecycle state - computed from field values
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
@@ -64,6 +64,29 @@ pub struct Task {
     pub bookmark: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub start_commit: Option<String>,
+    /// Branch checked out when the task started; the task branch is
+    /// integrated into it on completion
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub start_branch: Option<String>,
+    /// Tip of `start_branch` once the task branch was integrated
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub integration_commit: Option<String>,
+    /// Revision the task branch was created from: its parent's branch tip,
+    /// or the base branch for the top of a stack
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub base_rev: Option<String>,
+    /// Tip of the task branch when it was completed; `base_rev..head_rev`
+    /// is this level's diff
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub head_rev: Option<String>,
+    /// Linked worktree the task's branch is checked out in (`start --worktree`);
+    /// removed once the branch is integrated
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub worktree: Option<String>,
+    /// Registered repository the task's branch lives in (`os repo add`);
+    /// None means the repository of the working directory
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub repo_id: Option<RepoId>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub depth: Option<i32>,
     #[serde(default, skip_serializing_if = "Vec::is_empty")]
@@ -79,6 +102,80 @@ pub struct Task {
     #[serde(default)]
     pub archived: bool,
     pub archived_at: Option<DateTime<Utc>>,
+    /// Bumped on every write to the task row; pass back as `--if-revision`
+    #[serde(default)]
+    pub revision: i64,
+    /// Manual position among siblings, lower first; orders siblings of equal
+    /// priority (`os task move`)
+    #[serde(default)]
+    pub rank: i64,
+    /// Overdue once this passes while the task is still active
+    #[serde(default)]
+    pub due_at: Option<DateTime<Utc>>,
+    /// Hidden from next-ready and effectively blocked (with its subtree) until then
+    #[serde(default)]
+    pub defer_until: Option<DateTime<Utc>>,
+    /// Arbitrary JSON object attached via `os task meta`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub metadata: Option<serde_json::Value>,
+    /// Normalized (lowercase), sorted labels
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub labels: Vec<String>,
+    /// Live claim on this task itself (claims on ancestors are not copied down)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub claim: Option<TaskClaim>,
+}
+
+/// Lease on a task and its subtree, held by one agent until `expires_at`.
+/// Expired claims are ignored everywhere and replaced by the next claim.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TaskClaim {
+    pub task_id: TaskId,
+    pub agent: String,
+    pub claimed_at: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>,
+}
+
+/// One stretch of work on a task. `ended_at` is None while it is running;
+/// a task has at most one running session.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TaskSession {
+    pub id: i64,
+    pub task_id: TaskId,
+    pub agent: Option<String>,
+    pub started_at: DateTime<Utc>,
+    pub ended_at: Option<DateTime<Utc>>,
+}
+
+impl TaskSession {
+    pub fn is_running(&self) -> bool {
+        self.ended_at.is_none()
+    }
+
+    /// The part of the session inside `[from, to)`, with a running session
+    /// counted up to `to`. None if they don't overlap.
+    pub fn clip(
+        &self,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+    ) -> Option<(DateTime<Utc>, DateTime<Utc>)> {
+        let start = self.started_at.max(from);
+        let end = self.ended_at.unwrap_or(to).min(to);
+        (start < end).then_some((start, end))
+    }
+}
+
+impl TaskClaim {
+    pub fn is_live_at(&self, now: DateTime<Utc>) -> bool {
+        self.expires_at > now
+    }
+
+    /// Held by `agent`; anonymous callers (None) hold nothing
+    pub fn is_held_by(&self, agent: Option<&str>) -> bool {
+        agent == Some(self.agent.as_str())
+    }
 }
 
 impl Task {
@@ -98,6 +195,11 @@ impl Task {
         }
     }
 
+    /// Top of a hierarchy (a milestone), whatever the configured depth
+    pub fn is_root(&self) -> bool {
+        self.parent_id.is_none()
+    }
+
     /// Task is active for work (not finished or archived)
     pub fn is_active_for_work(&self) -> bool {
         matches!(
@@ -106,13 +208,22 @@ impl Task {
         )
     }
 
+    /// Deferred past `now`: treated like a blocked task until the date arrives
+    pub fn is_deferred_at(&self, now: DateTime<Utc>) -> bool {
+        self.defer_until.is_some_and(|d| d > now)
+    }
+
+    /// Still active for work with its due date behind `now`
+    pub fn is_overdue_at(&self, now: DateTime<Utc>) -> bool {
+        self.is_active_for_work() && self.due_at.is_some_and(|d| d < now)
+    }
+
     /// Task is finished for hierarchy (completed OR cancelled, regardless of archived)
     pub fn is_finished_for_hierarchy(&self) -> bool {
         self.completed || self.cancelled
     }
 
-    /// Validate lifecycle invariants (call at DB hydrate in debug/tests)
-    #[cfg(debug_assertions)]
+    /// Validate lifecycle invariants (call at DB hydrate in debug/tests, and on import)
     pub fn validate_lifecycle_invariants(&self) -> Result<(), String> {
         // Invalid: completed AND cancelled
         if self.completed && self.cancelled {
@@ -143,6 +254,11 @@ pub struct CreateTaskInput {
     pub parent_id: Option<TaskId>,
     pub priority: Option<i32>,
     pub blocked_by: Vec<TaskId>,
+    pub labels: Vec<String>,
+    pub due_at: Option<DateTime<Utc>>,
+    pub defer_until: Option<DateTime<Utc>>,
+    /// None inherits the parent's repo
+    pub repo_id: Option<RepoId>,
 }
 
 #[derive(Debug, Clone, Default)]
@@ -151,6 +267,21 @@ pub struct UpdateTaskInput {
     pub context: Option<String>,
     pub priority: Option<i32>,
     pub parent_id: Option<TaskId>,
+    /// Labels to add (existing labels are kept)
+    pub labels: Vec<String>,
+    /// `Some(None)` clears the due date
+    pub due_at: Option<Option<DateTime<Utc>>>,
+    /// `Some(None)` clears the deferral
+    pub defer_until: Option<Option<DateTime<Utc>>>,
+    /// `Some(None)` detaches the task from its repo
+    pub repo_id: Option<Option<RepoId>>,
+}
+
+/// Equality match on a metadata path, e.g. `area = "auth"`
+#[derive(Debug, Clone)]
+pub struct MetadataFilter {
+    pub path: String,
+    pub value: serde_json::Value,
 }
 
 #[derive(Debug, Clone)]
@@ -165,6 +296,42 @@ pub struct ListTasksFilter {
     /// - Some(true): only archived
     /// - Some(false): hide archived (default)
     pub archived: Option<bool>,
+    /// All metadata matches must hold (AND)
+    pub metadata: Vec<MetadataFilter>,
+    /// Task must carry every label (AND)
+    pub labels: Vec<String>,
+    /// Only active tasks whose due date has passed
+    pub overdue: bool,
+    /// Only tasks due before this instant
+    pub due_before: Option<DateTime<Utc>>,
+    /// Only tasks with a file or file-range link to this path (or under it),
+    /// relative to the repository root
+    pub linked: Option<String>,
+    /// Only tasks belonging to this repo
+    pub repo_id: Option<RepoId>,
+}
+
+/// Which candidates `next-ready` may return
+#[derive(Debug, Clone, Default)]
+pub struct NextReadyFilter {
+    /// Candidate must carry every label (AND)
+    pub labels: Vec<String>,
+    /// Skip tasks under a live claim held by anyone other than `agent`
+    pub unclaimed: bool,
+    pub agent: Option<String>,
+    /// Candidate must belong to this repo
+    pub repo_id: Option<RepoId>,
+}
+
+/// Where `os task move` puts a task among its siblings
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MovePosition {
+    First,
+    Last,
+    /// Directly before this sibling (must share the task's priority)
+    Before(TaskId),
+    /// Directly after this sibling (must share the task's priority)
+    After(TaskId),
 }
 
 impl Default for ListTasksFilter {
@@ -175,6 +342,12 @@ impl Default for ListTasksFilter {
             completed: None,
             depth: None,
             archived: Some(false), // Default: hide archived
+            metadata: Vec::new(),
+            labels: Vec::new(),
+            overdue: false,
+            due_before: None,
+            linked: None,
+            repo_id: None,
         }
     }
 }
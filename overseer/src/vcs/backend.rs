@@ -28,6 +28,13 @@ pub enum VcsError {
     #[error("Working copy has uncommitted changes")]
     DirtyWorkingCopy,
 
+    /// An integration stopped on conflicts and was aborted
+    #[error("Conflicts in: {}", .0.join(", "))]
+    Conflict(Vec<String>),
+
+    #[error("Cannot fast-forward {0}: it has commits the branch lacks")]
+    NotFastForward(String),
+
     #[error("Git error: {0}")]
     Git(String),
 
@@ -100,6 +107,56 @@ pub enum ChangeType {
 pub struct DiffEntry {
     pub path: String,
     pub change_type: ChangeType,
+    /// Path before a rename
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub old_path: Option<String>,
+    /// Lines added; 0 for binary files
+    #[serde(default)]
+    pub additions: usize,
+    /// Lines removed; 0 for binary files
+    #[serde(default)]
+    pub deletions: usize,
+    #[serde(default)]
+    pub binary: bool,
+    /// Unified diff of the file, when asked for
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub patch: Option<String>,
+}
+
+/// How a finished task branch is brought into the branch it was started from.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "kebab-case")]
+pub enum IntegrationStrategy {
+    /// Merge commit, even when a fast-forward is possible (`merge --no-ff`)
+    #[default]
+    Merge,
+    /// All of the branch's changes as one new commit
+    Squash,
+    /// Move the base branch up to the task branch; fails if the base moved on
+    FastForward,
+    /// Replay the branch's commits on the base branch, then fast-forward
+    Rebase,
+}
+
+impl IntegrationStrategy {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Merge => "merge",
+            Self::Squash => "squash",
+            Self::FastForward => "fast-forward",
+            Self::Rebase => "rebase",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "merge" => Some(Self::Merge),
+            "squash" => Some(Self::Squash),
+            "fast-forward" => Some(Self::FastForward),
+            "rebase" => Some(Self::Rebase),
+            _ => None,
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -119,7 +176,14 @@ pub trait VcsBackend: Send + Sync {
     fn root(&self) -> &str;
     fn status(&self) -> VcsResult<VcsStatus>;
     fn log(&self, limit: usize) -> VcsResult<Vec<LogEntry>>;
-    fn diff(&self, base: Option<&str>) -> VcsResult<Vec<DiffEntry>>;
+    /// Changes from `base` (default HEAD) to `head` (default the working
+    /// copy, untracked files included), with unified patches if `patch`
+    fn diff(
+        &self,
+        base: Option<&str>,
+        head: Option<&str>,
+        patch: bool,
+    ) -> VcsResult<Vec<DiffEntry>>;
     fn commit(&self, message: &str) -> VcsResult<CommitResult>;
     fn current_commit_id(&self) -> VcsResult<String>;
 
@@ -130,6 +194,40 @@ pub trait VcsBackend: Send + Sync {
 
     // Navigation
     fn checkout(&self, target: &str) -> VcsResult<()>;
+    /// Branch checked out in the working copy; None when HEAD is detached
+    fn current_branch(&self) -> VcsResult<Option<String>>;
+
+    // Integration
+    /// Bring `branch` into `onto` with `strategy` (`message` names the merge or
+    /// squash commit) by moving the `onto` ref. Returns the new tip of `onto`,
+    /// or None if it already contained `branch`. No checkout is switched: a
+    /// working copy that has `onto` checked out (this one or a linked
+    /// worktree) must be clean and is updated to the new tip; others are left
+    /// alone. On conflicts nothing changes and `Conflict` lists the
+    /// conflicting files.
+    fn integrate(
+        &self,
+        branch: &str,
+        onto: &str,
+        strategy: IntegrationStrategy,
+        message: &str,
+    ) -> VcsResult<Option<String>>;
+
+    // Worktrees
+    /// Check `branch` out in a new linked worktree at `path` (absolute)
+    fn add_worktree(&self, branch: &str, path: &str) -> VcsResult<()>;
+    /// Remove the linked worktree at `path`, which must be clean. A worktree
+    /// that is already gone is not an error. The process may be left in a
+    /// deleted working directory if it was inside `path`.
+    fn remove_worktree(&self, path: &str) -> VcsResult<()>;
+    /// Backend working in the linked worktree at `path`
+    fn open_worktree(&self, path: &str) -> VcsResult<Box<dyn VcsBackend>>;
+
+    // Lookups
+    /// Whether `path` (relative to the root) is in the working copy or tracked at HEAD
+    fn path_exists(&self, path: &str) -> VcsResult<bool>;
+    /// Full id of the commit `rev` names; `TargetNotFound` if it names none
+    fn resolve_commit(&self, rev: &str) -> VcsResult<String>;
 
     // Working copy safety
     fn is_clean(&self) -> VcsResult<bool> {
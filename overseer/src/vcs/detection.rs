@@ -1,3 +1,4 @@
+use std::fs;
 use std::path::{Path, PathBuf};
 
 use crate::vcs::backend::VcsType;
@@ -16,6 +17,23 @@ pub fn detect_vcs_type(start: &Path) -> (VcsType, Option<PathBuf>) {
     }
 }
 
+/// Root of the main working copy for the repository at `root`. A linked
+/// worktree's `.git` is a file pointing into the main repository's
+/// `.git/worktrees/<name>`, whose `commondir` leads back to `.git`.
+pub fn main_worktree_root(root: &Path) -> PathBuf {
+    let linked = || -> Option<PathBuf> {
+        let dot_git = fs::read_to_string(root.join(".git")).ok()?;
+        let gitdir = root.join(dot_git.strip_prefix("gitdir:")?.trim());
+        let commondir = fs::read_to_string(gitdir.join("commondir")).ok()?;
+        let common = gitdir.join(commondir.trim()).canonicalize().ok()?;
+        if common.file_name()? != ".git" {
+            return None;
+        }
+        common.parent().map(Path::to_path_buf)
+    };
+    linked().unwrap_or_else(|| root.to_path_buf())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -52,4 +70,23 @@ mod tests {
         assert_eq!(vcs_type, VcsType::Git);
         assert_eq!(root.unwrap(), tmp.path());
     }
+
+    #[test]
+    fn test_main_worktree_root() {
+        let tmp = TempDir::new().unwrap();
+        let main = tmp.path().canonicalize().unwrap().join("repo");
+        let gitdir = main.join(".git").join("worktrees").join("task");
+        fs::create_dir_all(&gitdir).unwrap();
+        fs::write(gitdir.join("commondir"), "../..\n").unwrap();
+        let linked = tmp.path().join("repo.worktrees").join("task");
+        fs::create_dir_all(&linked).unwrap();
+        fs::write(
+            linked.join(".git"),
+            format!("gitdir: {}\n", gitdir.display()),
+        )
+        .unwrap();
+
+        assert_eq!(main_worktree_root(&linked), main);
+        assert_eq!(main_worktree_root(&main), main);
+    }
 }
@@ -0,0 +1,354 @@
+//! File diffs between two trees.
+//!
+//! Changes come from gix's tree diff with rename tracking, as `git diff`
+//! finds them; changed text files get line counts and, on request, a unified
+//! patch.
+
+use std::ops::Range;
+
+use gix::bstr::{BString, ByteSlice};
+use gix::diff::blob::{
+    diff, intern::InternedInput, sources::byte_lines_with_terminator, Algorithm,
+};
+use gix::diff::Rewrites;
+use gix::object::tree::diff::ChangeDetached;
+use gix::objs::tree::{EntryKind, EntryMode};
+use gix::ObjectId;
+
+use crate::vcs::backend::{ChangeType, DiffEntry, VcsResult};
+use crate::vcs::git::failed;
+
+/// Unchanged lines shown around each patch hunk, as `git diff` does
+const CONTEXT: usize = 3;
+
+/// Diff the tree `old` against the tree `new`, sorted by path.
+///
+/// Both trees must be readable from `repo`. Deleted and added files with the
+/// same or similar contents are paired up as renames, with git's defaults.
+/// `patch` fills in `DiffEntry::patch` with unified diff text.
+pub fn diff_trees(
+    repo: &gix::Repository,
+    old: ObjectId,
+    new: ObjectId,
+    patch: bool,
+) -> VcsResult<Vec<DiffEntry>> {
+    let old = repo.find_tree(old).map_err(failed("find tree"))?;
+    let new = repo.find_tree(new).map_err(failed("find tree"))?;
+    let options = gix::diff::Options::default().with_rewrites(Some(Rewrites::default()));
+    let changes = repo
+        .diff_tree_to_tree(&old, &new, options)
+        .map_err(failed("diff"))?;
+
+    // Contents of a file; a submodule shows the commit it points at, as git does
+    let read = |mode: EntryMode, id: ObjectId| -> VcsResult<Vec<u8>> {
+        if mode.kind() == EntryKind::Commit {
+            return Ok(format!("Subproject commit {id}\n").into_bytes());
+        }
+        Ok(repo.find_blob(id).map_err(failed("read blob"))?.take_data())
+    };
+
+    let mut entries = Vec::new();
+    for change in changes {
+        let entry = match change {
+            ChangeDetached::Addition {
+                location,
+                entry_mode,
+                id,
+                ..
+            } if !entry_mode.is_tree() => {
+                let after = read(entry_mode, id)?;
+                entry(ChangeType::Added, None, &location, &[], &after, patch)
+            }
+            ChangeDetached::Deletion {
+                location,
+                entry_mode,
+                id,
+                ..
+            } if !entry_mode.is_tree() => {
+                let before = read(entry_mode, id)?;
+                entry(ChangeType::Deleted, None, &location, &before, &[], patch)
+            }
+            ChangeDetached::Modification {
+                location,
+                previous_entry_mode,
+                previous_id,
+                entry_mode,
+                id,
+            } if !previous_entry_mode.is_tree() && !entry_mode.is_tree() => {
+                let before = read(previous_entry_mode, previous_id)?;
+                let after = read(entry_mode, id)?;
+                entry(
+                    ChangeType::Modified,
+                    None,
+                    &location,
+                    &before,
+                    &after,
+                    patch,
+                )
+            }
+            ChangeDetached::Rewrite {
+                source_location,
+                source_entry_mode,
+                source_id,
+                entry_mode,
+                id,
+                location,
+                ..
+            } if !entry_mode.is_tree() => {
+                let before = read(source_entry_mode, source_id)?;
+                let after = read(entry_mode, id)?;
+                entry(
+                    ChangeType::Renamed,
+                    Some(&source_location),
+                    &location,
+                    &before,
+                    &after,
+                    patch,
+                )
+            }
+            // Directories only group the files listed on their own
+            _ => continue,
+        };
+        entries.push(entry);
+    }
+
+    entries.sort_by(|a, b| a.path.cmp(&b.path));
+    Ok(entries)
+}
+
+fn entry(
+    change_type: ChangeType,
+    old_path: Option<&BString>,
+    path: &BString,
+    before: &[u8],
+    after: &[u8],
+    patch: bool,
+) -> DiffEntry {
+    let binary = is_binary(before) || is_binary(after);
+    let hunks = if binary {
+        Vec::new()
+    } else {
+        line_hunks(before, after)
+    };
+    let patch = patch.then(|| {
+        let mut text = String::new();
+        if let Some(old_path) = old_path {
+            text.push_str(&format!("rename from {old_path}\nrename to {path}\n"));
+        }
+        let from = match change_type {
+            ChangeType::Added => "/dev/null".to_string(),
+            _ => format!("a/{}", old_path.unwrap_or(path)),
+        };
+        let to = match change_type {
+            ChangeType::Deleted => "/dev/null".to_string(),
+            _ => format!("b/{path}"),
+        };
+        if binary && before != after {
+            text.push_str(&format!("Binary files {from} and {to} differ\n"));
+        } else if !hunks.is_empty() {
+            text.push_str(&format!("--- {from}\n+++ {to}\n"));
+            text.push_str(&unified(before, after, &hunks));
+        }
+        text
+    });
+
+    DiffEntry {
+        path: path.to_string(),
+        change_type,
+        old_path: old_path.map(ToString::to_string),
+        additions: hunks.iter().map(|(_, after)| after.len()).sum(),
+        deletions: hunks.iter().map(|(before, _)| before.len()).sum(),
+        binary,
+        patch,
+    }
+}
+
+/// Whether `data` looks binary: a NUL byte early on, as git checks.
+fn is_binary(data: &[u8]) -> bool {
+    data[..data.len().min(8000)].contains(&0)
+}
+
+fn lines(data: &[u8]) -> Vec<&[u8]> {
+    data.split_inclusive(|&b| b == b'\n').collect()
+}
+
+/// Changed line ranges, `before` lines replaced by `after` lines, in order.
+fn line_hunks(before: &[u8], after: &[u8]) -> Vec<(Range<usize>, Range<usize>)> {
+    let input = InternedInput::new(
+        byte_lines_with_terminator(before),
+        byte_lines_with_terminator(after),
+    );
+    let mut hunks = Vec::new();
+    diff(
+        Algorithm::Histogram,
+        &input,
+        |before: Range<u32>, after: Range<u32>| {
+            hunks.push((
+                before.start as usize..before.end as usize,
+                after.start as usize..after.end as usize,
+            ))
+        },
+    );
+    hunks
+}
+
+/// Unified diff hunks with `CONTEXT` lines around each change.
+fn unified(before: &[u8], after: &[u8], hunks: &[(Range<usize>, Range<usize>)]) -> String {
+    let (before_lines, after_lines) = (lines(before), lines(after));
+    let mut out = String::new();
+
+    let mut i = 0;
+    while i < hunks.len() {
+        // Hunks close enough to share context are printed together
+        let mut j = i + 1;
+        while j < hunks.len() && hunks[j].0.start - hunks[j - 1].0.end <= 2 * CONTEXT {
+            j += 1;
+        }
+        let group = &hunks[i..j];
+        let (first, last) = (&group[0], &group[group.len() - 1]);
+        let start = first.0.start.saturating_sub(CONTEXT);
+        let end = (last.0.end + CONTEXT).min(before_lines.len());
+        let after_start = first.1.start - (first.0.start - start);
+        let after_end = last.1.end + (end - last.0.end);
+        out.push_str(&format!(
+            "@@ -{} +{} @@\n",
+            hunk_range(start, end - start),
+            hunk_range(after_start, after_end - after_start)
+        ));
+
+        let mut pos = start;
+        for (b, a) in group {
+            for text in &before_lines[pos..b.start] {
+                push_line(&mut out, ' ', text);
+            }
+            for text in &before_lines[b.clone()] {
+                push_line(&mut out, '-', text);
+            }
+            for text in &after_lines[a.clone()] {
+                push_line(&mut out, '+', text);
+            }
+            pos = b.end;
+        }
+        for text in &before_lines[pos..end] {
+            push_line(&mut out, ' ', text);
+        }
+        i = j;
+    }
+    out
+}
+
+/// `start,len` of a hunk header, 1-based; an empty range names the line before it.
+fn hunk_range(start: usize, len: usize) -> String {
+    match len {
+        0 => format!("{start},0"),
+        1 => format!("{}", start + 1),
+        _ => format!("{},{len}", start + 1),
+    }
+}
+
+fn push_line(out: &mut String, prefix: char, text: &[u8]) {
+    out.push(prefix);
+    out.push_str(&text.to_str_lossy());
+    if !text.ends_with(b"\n") {
+        out.push_str("\n\\ No newline at end of file\n");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn diff(old: &[(&str, &str)], new: &[(&str, &str)], patch: bool) -> Vec<DiffEntry> {
+        let dir = tempfile::TempDir::new().unwrap();
+        let repo = gix::init(dir.path()).unwrap().with_object_memory();
+        let tree = |entries: &[(&str, &str)]| {
+            let mut editor = repo
+                .edit_tree(ObjectId::empty_tree(repo.object_hash()))
+                .unwrap();
+            for &(path, data) in entries {
+                let id = repo.write_blob(data).unwrap();
+                editor.upsert(path, EntryKind::Blob, id).unwrap();
+            }
+            editor.write().unwrap().detach()
+        };
+        diff_trees(&repo, tree(old), tree(new), patch).unwrap()
+    }
+
+    #[test]
+    fn test_diff_trees() {
+        let body = "one\ntwo\nthree\nfour\nfive\n";
+        let entries = diff(
+            &[
+                ("kept", "same\n"),
+                ("edited", "a\nb\nc\n"),
+                ("gone", "x\n"),
+                ("old", body),
+            ],
+            &[
+                ("kept", "same\n"),
+                ("edited", "a\nB\nc\nd\n"),
+                ("new", "fresh\n"),
+                ("moved", "one\ntwo\nthree\nfour\nFIVE\n"),
+                ("bin", "\0data"),
+            ],
+            false,
+        );
+        let summary: Vec<_> = entries
+            .iter()
+            .map(|e| {
+                (
+                    e.path.as_str(),
+                    e.change_type,
+                    e.old_path.as_deref(),
+                    e.additions,
+                    e.deletions,
+                    e.binary,
+                )
+            })
+            .collect();
+        assert_eq!(
+            summary,
+            [
+                ("bin", ChangeType::Added, None, 0, 0, true),
+                ("edited", ChangeType::Modified, None, 2, 1, false),
+                ("gone", ChangeType::Deleted, None, 0, 1, false),
+                ("moved", ChangeType::Renamed, Some("old"), 1, 1, false),
+                ("new", ChangeType::Added, None, 1, 0, false),
+            ]
+        );
+        assert!(entries.iter().all(|e| e.patch.is_none()));
+
+        // Identical contents pair up even when they are not text
+        let entries = diff(&[("a", "\0bin")], &[("b", "\0bin")], false);
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].change_type, ChangeType::Renamed);
+        assert_eq!(entries[0].old_path.as_deref(), Some("a"));
+
+        // Moving between directories lists the file, not the directories
+        let entries = diff(&[("src/a", body)], &[("lib/auth/a", body)], false);
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].path, "lib/auth/a");
+        assert_eq!(entries[0].old_path.as_deref(), Some("src/a"));
+    }
+
+    #[test]
+    fn test_patch_text() {
+        let before: String = (1..=12).map(|n| format!("{n}\n")).collect();
+        let after = before.replacen("2\n", "two\n", 1).replace("12\n", "twelve");
+        let entries = diff(&[("f", &before)], &[("f", &after)], true);
+        assert_eq!(
+            entries[0].patch.as_deref(),
+            Some(
+                "--- a/f\n+++ b/f\n\
+                 @@ -1,5 +1,5 @@\n 1\n-2\n+two\n 3\n 4\n 5\n\
+                 @@ -9,4 +9,4 @@\n 9\n 10\n 11\n-12\n+twelve\n\\ No newline at end of file\n"
+            )
+        );
+
+        let entries = diff(&[], &[("new", "hi\n")], true);
+        assert_eq!(
+            entries[0].patch.as_deref(),
+            Some("--- /dev/null\n+++ b/new\n@@ -0,0 +1 @@\n+hi\n")
+        );
+    }
+}
@@ -1,18 +1,35 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
 use std::path::{Path, PathBuf};
-use std::process::Command;
+use std::sync::atomic::AtomicBool;
 
 use chrono::{TimeZone, Utc};
-use gix::bstr::ByteSlice;
+use gix::bstr::{BString, ByteSlice};
+use gix::index::entry::{Flags, Mode, Stat};
+use gix::merge::tree::TreatAsUnresolved;
+use gix::objs::tree::EntryKind;
+use gix::refs::transaction::{Change, LogChange, PreviousValue, RefEdit, RefLog};
+use gix::refs::Target;
+use gix::ObjectId;
 
 use crate::vcs::backend::{
-    ChangeType, CommitResult, DiffEntry, FileStatus, FileStatusKind, LogEntry, VcsBackend,
+    CommitResult, DiffEntry, FileStatus, FileStatusKind, IntegrationStrategy, LogEntry, VcsBackend,
     VcsError, VcsResult, VcsStatus, VcsType,
 };
+use crate::vcs::diff;
+
+/// A flattened tree: every non-tree entry by its slash-separated path.
+type Files = BTreeMap<BString, (EntryKind, ObjectId)>;
 
 pub struct GixBackend {
     root: PathBuf,
 }
 
+/// Wrap a gix error as `OperationFailed`, prefixed with what was being done.
+pub(crate) fn failed<E: std::fmt::Display>(what: &str) -> impl FnOnce(E) -> VcsError + '_ {
+    move |e| VcsError::OperationFailed(format!("{what}: {e}"))
+}
+
 impl GixBackend {
     pub fn open(path: &Path) -> VcsResult<Self> {
         // Verify it's a valid git repo
@@ -27,6 +44,376 @@ impl GixBackend {
     fn open_repo(&self) -> VcsResult<gix::Repository> {
         gix::discover(&self.root).map_err(|e| VcsError::OperationFailed(format!("open repo: {e}")))
     }
+
+    /// Stage every change in the working copy, like `git add -A`, and write
+    /// the index. Returns the id of the staged tree.
+    fn stage_all(&self) -> VcsResult<ObjectId> {
+        let repo = self.open_repo()?;
+
+        let changed = Self::changed_paths(&repo)?;
+
+        let (mut pipeline, index) = repo.filter_pipeline(None).map_err(failed("filters"))?;
+        let mut index = index.into_owned();
+        let mut added = Vec::new();
+        let mut removed = BTreeSet::new();
+        for path in changed {
+            let object = pipeline
+                .worktree_file_to_object(path.as_bstr(), &index)
+                .map_err(failed("stage"))?;
+            let Some((id, kind, _)) = object.filter(|&(_, kind, _)| kind != EntryKind::Tree) else {
+                removed.insert(path);
+                continue;
+            };
+            let stat = gix::index::fs::Metadata::from_path_no_follow(
+                &self.root.join(gix::path::from_bstr(path.as_bstr())),
+            )
+            .ok()
+            .and_then(|metadata| Stat::from_fs(&metadata).ok())
+            .unwrap_or_default();
+            match index.entry_index_by_path(path.as_bstr()) {
+                Ok(i) => {
+                    let entry = &mut index.entries_mut()[i];
+                    entry.id = id;
+                    entry.mode = Mode::from(kind);
+                    entry.stat = stat;
+                }
+                Err(_) => added.push((path, id, kind, stat)),
+            }
+        }
+        index.remove_entries(|_, path, _| removed.contains(path));
+        for (path, id, kind, stat) in added {
+            index.dangerously_push_entry(
+                stat,
+                id,
+                Flags::empty(),
+                Mode::from(kind),
+                path.as_bstr(),
+            );
+        }
+        index.sort_entries();
+        // The cached trees no longer match the entries
+        index.remove_tree();
+        index
+            .write(Default::default())
+            .map_err(failed("write index"))?;
+
+        Self::write_tree(&repo, &Self::index_files(&index))
+    }
+
+    /// Paths whose working copy file differs from the index, untracked
+    /// files included.
+    fn changed_paths(repo: &gix::Repository) -> VcsResult<BTreeSet<BString>> {
+        let mut changed = BTreeSet::new();
+        let status = repo
+            .status(gix::progress::Discard)
+            .map_err(failed("status"))?
+            .untracked_files(gix::status::UntrackedFiles::Files)
+            .into_iter(Vec::new())
+            .map_err(failed("status iter"))?;
+        for item in status {
+            use gix::status::index_worktree::Item;
+            match item.map_err(failed("status item"))? {
+                gix::status::Item::IndexWorktree(Item::Modification { rela_path, .. }) => {
+                    changed.insert(rela_path);
+                }
+                gix::status::Item::IndexWorktree(Item::DirectoryContents { entry, .. }) => {
+                    if entry.status == gix::dir::entry::Status::Untracked {
+                        changed.insert(entry.rela_path);
+                    }
+                }
+                gix::status::Item::IndexWorktree(Item::Rewrite {
+                    source,
+                    dirwalk_entry,
+                    ..
+                }) => {
+                    changed.insert(source.rela_path().to_owned());
+                    changed.insert(dirwalk_entry.rela_path);
+                }
+                // Already staged
+                gix::status::Item::TreeIndex(_) => {}
+            }
+        }
+        Ok(changed)
+    }
+
+    /// The commit `rev` points to.
+    fn commit_id(repo: &gix::Repository, rev: &str) -> VcsResult<ObjectId> {
+        let not_found = || VcsError::TargetNotFound(rev.to_string());
+        let id = repo.rev_parse_single(rev).map_err(|_| not_found())?;
+        let commit = id
+            .object()
+            .map_err(|e| VcsError::Git(e.to_string()))?
+            .peel_to_commit()
+            .map_err(|_| not_found())?;
+        Ok(commit.id)
+    }
+
+    /// The tip of local branch `name`.
+    fn branch_id(repo: &gix::Repository, name: &str) -> VcsResult<ObjectId> {
+        let not_found = || VcsError::BookmarkNotFound(name.to_string());
+        repo.try_find_reference(format!("refs/heads/{name}").as_str())
+            .map_err(failed("find branch"))?
+            .ok_or_else(not_found)?
+            .peel_to_id()
+            .map(|id| id.detach())
+            .map_err(|_| not_found())
+    }
+
+    fn tree_of(repo: &gix::Repository, commit: ObjectId) -> VcsResult<ObjectId> {
+        let commit = repo.find_commit(commit).map_err(failed("find commit"))?;
+        Ok(commit.tree_id().map_err(failed("read commit"))?.detach())
+    }
+
+    fn index_files(index: &gix::index::State) -> Files {
+        index
+            .entries()
+            .iter()
+            .filter_map(|entry| {
+                let kind = entry.mode.to_tree_entry_mode()?.kind();
+                Some((entry.path(index).to_owned(), (kind, entry.id)))
+            })
+            .collect()
+    }
+
+    /// The tree `stage_all` would stage from the working copy. New blobs go
+    /// wherever `repo` writes objects; give it object memory to keep them out
+    /// of the object database.
+    fn worktree_tree(&self, repo: &gix::Repository) -> VcsResult<ObjectId> {
+        let index = repo.index_or_empty().map_err(failed("read index"))?;
+        let mut files = Self::index_files(&index);
+        for path in Self::changed_paths(repo)? {
+            let file = self.root.join(gix::path::from_bstr(path.as_bstr()));
+            let metadata = fs::symlink_metadata(&file).ok();
+            let (kind, data) = match metadata {
+                Some(m) if m.is_symlink() => {
+                    let target = fs::read_link(&file)?;
+                    (EntryKind::Link, gix::path::into_bstr(target).to_vec())
+                }
+                Some(m) if m.is_file() => {
+                    let kind = if gix::fs::is_executable(&m) {
+                        EntryKind::BlobExecutable
+                    } else {
+                        EntryKind::Blob
+                    };
+                    (kind, fs::read(&file)?)
+                }
+                // Deleted
+                _ => {
+                    files.remove(&path);
+                    continue;
+                }
+            };
+            let id = repo.write_blob(data).map_err(failed("write blob"))?;
+            files.insert(path, (kind, id.detach()));
+        }
+        Self::write_tree(repo, &files)
+    }
+
+    fn write_tree(repo: &gix::Repository, files: &Files) -> VcsResult<ObjectId> {
+        let mut editor = repo
+            .edit_tree(ObjectId::empty_tree(repo.object_hash()))
+            .map_err(failed("edit tree"))?;
+        for (path, &(kind, id)) in files {
+            editor.upsert(path, kind, id).map_err(failed("edit tree"))?;
+        }
+        Ok(editor.write().map_err(failed("write tree"))?.detach())
+    }
+
+    /// Where a working copy of this repository has `branch` (a full ref
+    /// name) checked out.
+    fn checked_out_at(repo: &gix::Repository, branch: &str) -> VcsResult<Option<PathBuf>> {
+        let main = repo.main_repo().map_err(failed("open main worktree"))?;
+        let mut heads = vec![(
+            main.workdir().map(Path::to_path_buf),
+            main.head_name().map_err(failed("get head"))?,
+        )];
+        for proxy in repo.worktrees()? {
+            let base = proxy.base().ok();
+            let worktree = proxy
+                .into_repo_with_possibly_inaccessible_worktree()
+                .map_err(failed("open worktree"))?;
+            heads.push((base, worktree.head_name().map_err(failed("get head"))?));
+        }
+        Ok(heads
+            .into_iter()
+            .find(|(_, head)| head.as_ref().is_some_and(|h| h.as_bstr() == branch))
+            .map(|(path, _)| path.unwrap_or_default()))
+    }
+
+    /// Point HEAD at `target`, a branch or a commit.
+    fn set_head(repo: &gix::Repository, target: Target, message: String) -> VcsResult<()> {
+        repo.edit_reference(RefEdit {
+            change: Change::Update {
+                log: LogChange {
+                    mode: RefLog::AndReference,
+                    force_create_reflog: false,
+                    message: message.into(),
+                },
+                expected: PreviousValue::Any,
+                new: target,
+            },
+            name: "HEAD".try_into().expect("valid ref name"),
+            deref: false,
+        })
+        .map_err(failed("update HEAD"))?;
+        Ok(())
+    }
+
+    /// Make the working copy and index match `tree`. Only files that differ
+    /// from the index are written; the working copy must be clean.
+    fn switch_tree(&self, tree: ObjectId) -> VcsResult<()> {
+        // A fresh handle, so the index is read from disk
+        let repo = &self.open_repo()?;
+        let current = repo.index_or_empty().map_err(failed("read index"))?;
+        let mut index = repo.index_from_tree(&tree).map_err(failed("read tree"))?;
+
+        let mut unchanged = Vec::new();
+        let (entries, paths) = index.entries_mut_and_pathbacking();
+        for (i, entry) in entries.iter_mut().enumerate() {
+            let Some(old) = current.entry_by_path(entry.path_in(paths)) else {
+                continue;
+            };
+            if old.id == entry.id && old.mode == entry.mode {
+                entry.stat = old.stat;
+                entry.flags.insert(Flags::SKIP_WORKTREE);
+                unchanged.push(i);
+            }
+        }
+
+        // Files the new tree no longer has
+        for entry in current.entries() {
+            let path = entry.path(&current);
+            if index.entry_by_path(path).is_some() {
+                continue;
+            }
+            let file = self.root.join(gix::path::from_bstr(path));
+            match fs::remove_file(&file) {
+                Err(e) if e.kind() != std::io::ErrorKind::NotFound => return Err(e.into()),
+                _ => {}
+            }
+            let mut dir = file.parent();
+            while let Some(parent) = dir.filter(|d| *d != self.root) {
+                if fs::remove_dir(parent).is_err() {
+                    break;
+                }
+                dir = parent.parent();
+            }
+        }
+
+        Self::checkout_index(repo, &mut index, &self.root, false)?;
+        for i in unchanged {
+            index.entries_mut()[i].flags.remove(Flags::SKIP_WORKTREE);
+        }
+        index
+            .write(Default::default())
+            .map_err(failed("write index"))?;
+        Ok(())
+    }
+
+    /// Write the files of `index` into `dir`, skipping SKIP_WORKTREE entries.
+    fn checkout_index(
+        repo: &gix::Repository,
+        index: &mut gix::index::File,
+        dir: &Path,
+        fresh: bool,
+    ) -> VcsResult<()> {
+        let mut options = repo
+            .checkout_options(gix::worktree::stack::state::attributes::Source::IdMapping)
+            .map_err(failed("checkout options"))?;
+        options.destination_is_initially_empty = fresh;
+        options.overwrite_existing = !fresh;
+        gix::worktree::state::checkout(
+            index,
+            dir,
+            repo.objects
+                .clone()
+                .into_arc()
+                .map_err(failed("open objects"))?,
+            &gix::progress::Discard,
+            &gix::progress::Discard,
+            &AtomicBool::new(false),
+            options,
+        )
+        .map_err(failed("checkout"))?;
+        Ok(())
+    }
+
+    /// Three-way merge of the trees of `ours` and `theirs` against `base`,
+    /// following renames on either side. Conflicts are reported instead of
+    /// being written with markers.
+    fn merge_commits(
+        repo: &gix::Repository,
+        base: Option<ObjectId>,
+        ours: ObjectId,
+        theirs: ObjectId,
+    ) -> VcsResult<ObjectId> {
+        let base = match base {
+            Some(base) => Self::tree_of(repo, base)?,
+            None => ObjectId::empty_tree(repo.object_hash()),
+        };
+        let options = repo.tree_merge_options().map_err(failed("merge options"))?;
+        let mut outcome = repo
+            .merge_trees(
+                base,
+                Self::tree_of(repo, ours)?,
+                Self::tree_of(repo, theirs)?,
+                Default::default(),
+                options,
+            )
+            .map_err(failed("merge"))?;
+
+        let conflicts: BTreeSet<String> = outcome
+            .conflicts
+            .iter()
+            .filter(|conflict| conflict.is_unresolved(TreatAsUnresolved::git()))
+            .flat_map(|conflict| {
+                let (ours, theirs) = conflict.changes_in_resolution();
+                [ours.location().to_string(), theirs.location().to_string()]
+            })
+            .collect();
+        if !conflicts.is_empty() {
+            return Err(VcsError::Conflict(conflicts.into_iter().collect()));
+        }
+        Ok(outcome.tree.write().map_err(failed("write tree"))?.detach())
+    }
+
+    /// Replay the commits of `branch` missing from `onto` on top of it, like
+    /// `git rebase`: merge commits are flattened and emptied commits dropped.
+    fn replay(repo: &gix::Repository, onto: ObjectId, branch: ObjectId) -> VcsResult<ObjectId> {
+        let mut commits = repo
+            .rev_walk([branch])
+            .with_hidden([onto])
+            .all()
+            .map_err(failed("rev walk"))?
+            .map(|info| info.map(|info| info.id))
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(failed("walk commit"))?;
+        commits.reverse();
+
+        let committer = repo
+            .committer()
+            .ok_or_else(|| VcsError::OperationFailed("no committer configured".to_string()))?
+            .map_err(failed("committer"))?;
+        let mut tip = onto;
+        for id in commits {
+            let commit = repo.find_commit(id).map_err(failed("find commit"))?;
+            let parents: Vec<ObjectId> = commit.parent_ids().map(|id| id.detach()).collect();
+            if parents.len() > 1 {
+                continue;
+            }
+            let tree = Self::merge_commits(repo, parents.first().copied(), tip, id)?;
+            if tree == Self::tree_of(repo, tip)? {
+                continue;
+            }
+            let message = commit.message_raw().map_err(failed("read commit"))?;
+            let author = commit.author().map_err(failed("read commit"))?;
+            tip = repo
+                .new_commit_as(committer, author, message.to_str_lossy(), tree, [tip])
+                .map_err(failed("commit"))?
+                .id;
+        }
+        Ok(tip)
+    }
 }
 
 impl VcsBackend for GixBackend {
@@ -166,141 +553,49 @@ impl VcsBackend for GixBackend {
         Ok(entries)
     }
 
-    fn diff(&self, _base: Option<&str>) -> VcsResult<Vec<DiffEntry>> {
-        let repo = self.open_repo()?;
-        let mut entries = Vec::new();
-
-        // Use status API to get working directory changes
-        let status_platform = repo
-            .status(gix::progress::Discard)
-            .map_err(|e| VcsError::OperationFailed(format!("status: {e}")))?;
-
-        let status_iter = status_platform
-            .into_iter(Vec::new())
-            .map_err(|e| VcsError::OperationFailed(format!("status iter: {e}")))?;
-
-        for item in status_iter {
-            let item = item.map_err(|e| VcsError::OperationFailed(format!("status item: {e}")))?;
-
-            match item {
-                gix::status::Item::IndexWorktree(worktree_item) => {
-                    use gix::status::index_worktree::Item;
-
-                    match worktree_item {
-                        Item::Modification { rela_path, .. } => {
-                            entries.push(DiffEntry {
-                                path: rela_path.to_string(),
-                                change_type: ChangeType::Modified,
-                            });
-                        }
-                        Item::DirectoryContents { entry, .. } => {
-                            entries.push(DiffEntry {
-                                path: entry.rela_path.to_string(),
-                                change_type: ChangeType::Added,
-                            });
-                        }
-                        Item::Rewrite {
-                            dirwalk_entry,
-                            source,
-                            ..
-                        } => {
-                            entries.push(DiffEntry {
-                                path: format!(
-                                    "{} -> {}",
-                                    source.rela_path(),
-                                    dirwalk_entry.rela_path
-                                ),
-                                change_type: ChangeType::Renamed,
-                            });
-                        }
-                    }
-                }
-                gix::status::Item::TreeIndex(change) => {
-                    // Staged changes (HEAD tree vs index) - include in diff
-                    let path = change.location().to_string();
-                    entries.push(DiffEntry {
-                        path,
-                        change_type: ChangeType::Modified,
-                    });
-                }
-            }
-        }
-
-        Ok(entries)
+    fn diff(
+        &self,
+        base: Option<&str>,
+        head: Option<&str>,
+        patch: bool,
+    ) -> VcsResult<Vec<DiffEntry>> {
+        // The working copy is diffed as a tree that only lives in memory
+        let repo = self.open_repo()?.with_object_memory();
+        let old = match base {
+            Some(rev) => Self::tree_of(&repo, Self::commit_id(&repo, rev)?)?,
+            // An unborn HEAD has no files yet
+            None => match repo.head_id() {
+                Ok(id) => Self::tree_of(&repo, id.detach())?,
+                Err(_) => ObjectId::empty_tree(repo.object_hash()),
+            },
+        };
+        let new = match head {
+            Some(rev) => Self::tree_of(&repo, Self::commit_id(&repo, rev)?)?,
+            None => self.worktree_tree(&repo)?,
+        };
+        diff::diff_trees(&repo, old, new, patch)
     }
 
     fn commit(&self, message: &str) -> VcsResult<CommitResult> {
-        // Use git CLI for commit since gix's staging/commit API is still unstable.
-        // This is the git fallback backend, so having git CLI available is reasonable.
-
-        // Check if there's anything to commit first (using porcelain for locale-independence)
-        let status_output = Command::new("git")
-            .args(["status", "--porcelain"])
-            .current_dir(&self.root)
-            .output()
-            .map_err(|e| VcsError::OperationFailed(format!("failed to run git status: {e}")))?;
-
-        if !status_output.status.success() {
-            let stderr = String::from_utf8_lossy(&status_output.stderr);
-            return Err(VcsError::OperationFailed(format!(
-                "git status failed: {stderr}"
-            )));
-        }
+        let tree = self.stage_all()?;
 
-        let status_str = String::from_utf8_lossy(&status_output.stdout);
-        if status_str.trim().is_empty() {
+        let repo = self.open_repo()?;
+        // An unborn HEAD has no parent
+        let parent = repo.head_id().ok().map(|id| id.detach());
+        let parent_tree = match parent {
+            Some(id) => Self::tree_of(&repo, id)?,
+            None => ObjectId::empty_tree(repo.object_hash()),
+        };
+        if tree == parent_tree {
             return Err(VcsError::NothingToCommit);
         }
 
-        // Stage all changes (git add -A)
-        let add_output = Command::new("git")
-            .args(["add", "-A"])
-            .current_dir(&self.root)
-            .output()
-            .map_err(|e| VcsError::OperationFailed(format!("failed to run git add: {e}")))?;
-
-        if !add_output.status.success() {
-            let stderr = String::from_utf8_lossy(&add_output.stderr);
-            return Err(VcsError::OperationFailed(format!(
-                "git add -A failed: {stderr}"
-            )));
-        }
-
-        // Create commit (with --no-gpg-sign to avoid GPG agent issues in automation)
-        let commit_output = Command::new("git")
-            .args(["commit", "--no-gpg-sign", "-m", message])
-            .current_dir(&self.root)
-            .output()
-            .map_err(|e| VcsError::OperationFailed(format!("failed to run git commit: {e}")))?;
-
-        if !commit_output.status.success() {
-            let stderr = String::from_utf8_lossy(&commit_output.stderr);
-            return Err(VcsError::OperationFailed(format!(
-                "git commit failed: {stderr}"
-            )));
-        }
-
-        // Get the commit ID
-        let rev_output = Command::new("git")
-            .args(["rev-parse", "HEAD"])
-            .current_dir(&self.root)
-            .output()
-            .map_err(|e| VcsError::OperationFailed(format!("failed to run git rev-parse: {e}")))?;
-
-        if !rev_output.status.success() {
-            let stderr = String::from_utf8_lossy(&rev_output.stderr);
-            return Err(VcsError::OperationFailed(format!(
-                "git rev-parse HEAD failed: {stderr}"
-            )));
-        }
-
-        let full_id = String::from_utf8_lossy(&rev_output.stdout)
-            .trim()
-            .to_string();
-        let id = full_id[..12.min(full_id.len())].to_string();
+        let id = repo
+            .commit("HEAD", message, tree, parent)
+            .map_err(failed("commit"))?;
 
         Ok(CommitResult {
-            id,
+            id: id.to_string()[..12].to_string(),
             message: message.to_string(),
         })
     }
@@ -316,91 +611,47 @@ impl VcsBackend for GixBackend {
     }
 
     fn create_bookmark(&self, name: &str, target: Option<&str>) -> VcsResult<()> {
-        // Check if branch already exists using gix
         let repo = self.open_repo()?;
-        let references = repo
-            .references()
-            .map_err(|e| VcsError::Git(e.to_string()))?;
-
-        let branch_ref = format!("refs/heads/{}", name);
-        for reference in references.all().map_err(|e| VcsError::Git(e.to_string()))? {
-            let reference = reference.map_err(|e| VcsError::Git(e.to_string()))?;
-            if reference.name().as_bstr().to_str_lossy() == branch_ref {
-                return Err(VcsError::BookmarkExists(name.to_string()));
-            }
+        let branch_ref = format!("refs/heads/{name}");
+        if repo
+            .try_find_reference(branch_ref.as_str())
+            .map_err(|e| VcsError::Git(e.to_string()))?
+            .is_some()
+        {
+            return Err(VcsError::BookmarkExists(name.to_string()));
         }
 
-        // Use git CLI to create branch
-        let mut args = vec!["branch".to_string(), name.to_string()];
-        if let Some(t) = target {
-            args.push(t.to_string());
-        }
-
-        let output = Command::new("git")
-            .args(&args)
-            .current_dir(&self.root)
-            .output()
-            .map_err(|e| VcsError::Git(format!("failed to run git branch: {e}")))?;
-
-        if !output.status.success() {
-            let stderr = String::from_utf8_lossy(&output.stderr);
-            if stderr.contains("not a valid object name") || stderr.contains("unknown revision") {
-                return Err(VcsError::TargetNotFound(
-                    target.unwrap_or("HEAD").to_string(),
-                ));
-            }
-            return Err(VcsError::Git(format!("git branch failed: {stderr}")));
-        }
+        let id = Self::commit_id(&repo, target.unwrap_or("HEAD"))?;
+        repo.reference(
+            branch_ref.as_str(),
+            id,
+            PreviousValue::MustNotExist,
+            format!("branch: Created from {}", target.unwrap_or("HEAD")),
+        )
+        .map_err(|e| VcsError::Git(format!("create branch {name}: {e}")))?;
 
         Ok(())
     }
 
     fn delete_bookmark(&self, name: &str) -> VcsResult<()> {
-        // Check if branch exists using gix
         let repo = self.open_repo()?;
-        let references = repo
-            .references()
-            .map_err(|e| VcsError::Git(e.to_string()))?;
-
-        let branch_ref = format!("refs/heads/{}", name);
-        let mut found = false;
-        for reference in references.all().map_err(|e| VcsError::Git(e.to_string()))? {
-            let reference = reference.map_err(|e| VcsError::Git(e.to_string()))?;
-            if reference.name().as_bstr().to_str_lossy() == branch_ref {
-                found = true;
-                break;
-            }
-        }
-
-        if !found {
-            return Err(VcsError::BookmarkNotFound(name.to_string()));
+        let branch_ref = format!("refs/heads/{name}");
+        let reference = repo
+            .try_find_reference(branch_ref.as_str())
+            .map_err(|e| VcsError::Git(e.to_string()))?
+            .ok_or_else(|| VcsError::BookmarkNotFound(name.to_string()))?;
+
+        if let Some(path) = Self::checked_out_at(&repo, &branch_ref)? {
+            return Err(VcsError::Git(format!(
+                "cannot delete branch '{name}' checked out at '{}'",
+                path.display()
+            )));
         }
 
-        // Use git CLI to delete branch
-        let output = Command::new("git")
-            .args(["branch", "-d", name])
-            .current_dir(&self.root)
-            .output()
-            .map_err(|e| VcsError::Git(format!("failed to run git branch -d: {e}")))?;
-
-        if !output.status.success() {
-            let stderr = String::from_utf8_lossy(&output.stderr);
-            // Try force delete if branch is not fully merged
-            if stderr.contains("not fully merged") {
-                let output = Command::new("git")
-                    .args(["branch", "-D", name])
-                    .current_dir(&self.root)
-                    .output()
-                    .map_err(|e| VcsError::Git(format!("failed to run git branch -D: {e}")))?;
-
-                if !output.status.success() {
-                    let stderr = String::from_utf8_lossy(&output.stderr);
-                    return Err(VcsError::Git(format!("git branch -D failed: {stderr}")));
-                }
-            } else {
-                return Err(VcsError::Git(format!("git branch -d failed: {stderr}")));
-            }
-        }
+        // Unmerged branches are deleted too, like `git branch -D`
+        reference
+            .delete()
+            .map_err(|e| VcsError::Git(format!("delete branch {name}: {e}")))?;
 
         Ok(())
     }
@@ -435,32 +686,249 @@ impl VcsBackend for GixBackend {
             return Err(VcsError::DirtyWorkingCopy);
         }
 
-        let output = Command::new("git")
-            .args(["checkout", target])
-            .current_dir(&self.root)
-            .output()
-            .map_err(|e| VcsError::Git(format!("failed to run git checkout: {e}")))?;
-
-        if !output.status.success() {
-            let stderr = String::from_utf8_lossy(&output.stderr);
-            if stderr.contains("did not match any")
-                || stderr.contains("pathspec")
-                || stderr.contains("not a valid object name")
-            {
-                return Err(VcsError::TargetNotFound(target.to_string()));
+        let repo = self.open_repo()?;
+        let branch_ref = format!("refs/heads/{target}");
+        let (id, head) = match Self::branch_id(&repo, target) {
+            Ok(id) => {
+                let current = repo.head_name().map_err(failed("get head"))?;
+                if current.is_none_or(|name| name.as_bstr() != branch_ref.as_str()) {
+                    if let Some(path) = Self::checked_out_at(&repo, &branch_ref)? {
+                        return Err(VcsError::Git(format!(
+                            "'{target}' is already checked out at '{}'",
+                            path.display()
+                        )));
+                    }
+                }
+                let name = branch_ref
+                    .as_str()
+                    .try_into()
+                    .map_err(failed("branch name"))?;
+                (id, Target::Symbolic(name))
+            }
+            // Anything else detaches HEAD at the commit it names
+            Err(VcsError::BookmarkNotFound(_)) => {
+                let id = Self::commit_id(&repo, target)?;
+                (id, Target::Object(id))
+            }
+            Err(e) => return Err(e),
+        };
+
+        self.switch_tree(Self::tree_of(&repo, id)?)?;
+        Self::set_head(&repo, head, format!("checkout: moving to {target}"))
+    }
+
+    fn current_branch(&self) -> VcsResult<Option<String>> {
+        let repo = self.open_repo()?;
+        let head = repo
+            .head_name()
+            .map_err(|e| VcsError::OperationFailed(format!("get head: {e}")))?;
+        Ok(head.map(|name| name.shorten().to_str_lossy().to_string()))
+    }
+
+    fn integrate(
+        &self,
+        branch: &str,
+        onto: &str,
+        strategy: IntegrationStrategy,
+        message: &str,
+    ) -> VcsResult<Option<String>> {
+        let repo = self.open_repo()?;
+        let branch_id = Self::commit_id(&repo, branch)
+            .map_err(|_| VcsError::BookmarkNotFound(branch.to_string()))?;
+        let onto_id = Self::branch_id(&repo, onto)?;
+        let onto_ref = format!("refs/heads/{onto}");
+
+        // A working copy with `onto` checked out moves along with it, so it
+        // must be clean; any other checkout is left alone
+        let checkout = match Self::checked_out_at(&repo, &onto_ref)? {
+            Some(path) => {
+                let backend = GixBackend::open(&path)?;
+                if !backend.is_clean()? {
+                    return Err(VcsError::DirtyWorkingCopy);
+                }
+                Some(backend)
+            }
+            None => None,
+        };
+
+        let base = repo
+            .merge_base(onto_id, branch_id)
+            .ok()
+            .map(|id| id.detach());
+        // Nothing the base branch does not already have
+        if base == Some(branch_id) {
+            return Ok(None);
+        }
+
+        // Every strategy builds the new tip without touching a working copy,
+        // so a conflict leaves nothing to clean up
+        let tip = match strategy {
+            IntegrationStrategy::Merge => {
+                let tree = Self::merge_commits(&repo, base, onto_id, branch_id)?;
+                repo.new_commit(message, tree, [onto_id, branch_id])
+                    .map_err(failed("commit"))?
+                    .id
+            }
+            IntegrationStrategy::Squash => {
+                let tree = Self::merge_commits(&repo, base, onto_id, branch_id)?;
+                // Changes that cancel out leave nothing to commit
+                if tree == Self::tree_of(&repo, onto_id)? {
+                    return Ok(None);
+                }
+                repo.new_commit(message, tree, [onto_id])
+                    .map_err(failed("commit"))?
+                    .id
+            }
+            IntegrationStrategy::FastForward => {
+                if base != Some(onto_id) {
+                    return Err(VcsError::NotFastForward(onto.to_string()));
+                }
+                branch_id
+            }
+            // The branch itself stays put; it may be checked out elsewhere
+            IntegrationStrategy::Rebase => Self::replay(&repo, onto_id, branch_id)?,
+        };
+
+        repo.reference(
+            onto_ref.as_str(),
+            tip,
+            PreviousValue::MustExistAndMatch(Target::Object(onto_id)),
+            format!("integrate: {branch} into {onto}"),
+        )
+        .map_err(|e| VcsError::Git(format!("update {onto}: {e}")))?;
+        // A checkout that cannot follow puts the ref back, so it still
+        // matches the files on disk
+        if let Some(ref checkout) = checkout {
+            let switched = Self::tree_of(&repo, tip).and_then(|tree| checkout.switch_tree(tree));
+            if let Err(e) = switched {
+                repo.reference(
+                    onto_ref.as_str(),
+                    onto_id,
+                    PreviousValue::MustExistAndMatch(Target::Object(tip)),
+                    format!("integrate: roll back {onto}"),
+                )
+                .map_err(|e| VcsError::Git(format!("roll back {onto}: {e}")))?;
+                return Err(e);
             }
-            return Err(VcsError::Git(format!("git checkout failed: {stderr}")));
         }
 
+        Ok(Some(tip.to_hex_with_len(12).to_string()))
+    }
+
+    fn add_worktree(&self, branch: &str, path: &str) -> VcsResult<()> {
+        let repo = self.open_repo()?;
+        let id = Self::branch_id(&repo, branch)?;
+        let branch_ref = format!("refs/heads/{branch}");
+        if let Some(at) = Self::checked_out_at(&repo, &branch_ref)? {
+            return Err(VcsError::Git(format!(
+                "'{branch}' is already checked out at '{}'",
+                at.display()
+            )));
+        }
+        let dir = Path::new(path);
+        if dir
+            .read_dir()
+            .is_ok_and(|mut entries| entries.next().is_some())
+        {
+            return Err(VcsError::Git(format!("'{path}' already exists")));
+        }
+        fs::create_dir_all(dir)
+            .map_err(|e| VcsError::Git(format!("create {}: {e}", dir.display())))?;
+        let dir = dir.canonicalize()?;
+
+        // The administrative directory git keeps for each linked worktree
+        let admin_root = repo.common_dir().join("worktrees");
+        let base_name = dir
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_else(|| branch.replace('/', "-"));
+        let mut name = base_name.clone();
+        let mut n = 1;
+        while admin_root.join(&name).exists() {
+            name = format!("{base_name}{n}");
+            n += 1;
+        }
+        let admin = admin_root.join(name);
+        fs::create_dir_all(&admin)?;
+        fs::write(
+            admin.join("gitdir"),
+            format!("{}\n", dir.join(".git").display()),
+        )?;
+        fs::write(admin.join("commondir"), "../..\n")?;
+        fs::write(admin.join("HEAD"), format!("ref: {branch_ref}\n"))?;
+        fs::write(dir.join(".git"), format!("gitdir: {}\n", admin.display()))?;
+
+        let worktree = gix::open(&dir).map_err(failed("open worktree"))?;
+        let tree = Self::tree_of(&worktree, id)?;
+        let mut index = worktree
+            .index_from_tree(&tree)
+            .map_err(failed("read tree"))?;
+        Self::checkout_index(&worktree, &mut index, &dir, true)?;
+        index
+            .write(Default::default())
+            .map_err(failed("write index"))?;
         Ok(())
     }
+
+    fn remove_worktree(&self, path: &str) -> VcsResult<()> {
+        let repo = self.open_repo()?;
+        let Ok(dir) = Path::new(path).canonicalize() else {
+            // Deleted by hand; drop git's record of it
+            for proxy in repo.worktrees()? {
+                if !proxy.is_locked() && !proxy.base().is_ok_and(|base| base.exists()) {
+                    fs::remove_dir_all(proxy.git_dir())?;
+                }
+            }
+            return Ok(());
+        };
+        let proxy = repo
+            .worktrees()?
+            .into_iter()
+            .find(|proxy| {
+                proxy
+                    .base()
+                    .and_then(|base| base.canonicalize())
+                    .is_ok_and(|base| base == dir)
+            })
+            .ok_or_else(|| VcsError::Git(format!("'{path}' is not a working tree")))?;
+        if !GixBackend::open(&dir)?.is_clean()? {
+            return Err(VcsError::DirtyWorkingCopy);
+        }
+        fs::remove_dir_all(&dir)?;
+        fs::remove_dir_all(proxy.git_dir())?;
+        Ok(())
+    }
+
+    fn open_worktree(&self, path: &str) -> VcsResult<Box<dyn VcsBackend>> {
+        Ok(Box::new(GixBackend::open(Path::new(path))?))
+    }
+
+    fn path_exists(&self, path: &str) -> VcsResult<bool> {
+        if self.root.join(path).exists() {
+            return Ok(true);
+        }
+        // Deleted from the working copy but still tracked; an unborn HEAD has no tree
+        let repo = self.open_repo()?;
+        let Ok(tree) = repo.head_tree() else {
+            return Ok(false);
+        };
+        let entry = tree
+            .lookup_entry_by_path(path)
+            .map_err(|e| VcsError::Git(e.to_string()))?;
+        Ok(entry.is_some())
+    }
+
+    fn resolve_commit(&self, rev: &str) -> VcsResult<String> {
+        let repo = self.open_repo()?;
+        Ok(Self::commit_id(&repo, rev)?.to_string())
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::testutil::{GitTestRepo, TestRepo};
-    use crate::vcs::backend::VcsType;
+    use crate::vcs::backend::{ChangeType, VcsType};
 
     #[test]
     fn test_open_git_repo() {
@@ -562,7 +1030,7 @@ mod tests {
         repo.commit("initial commit").unwrap();
 
         let backend = GixBackend::open(repo.path()).unwrap();
-        let diff = backend.diff(None).unwrap();
+        let diff = backend.diff(None, None, false).unwrap();
         assert!(diff.is_empty());
     }
 
@@ -575,7 +1043,7 @@ mod tests {
         repo.write_file("test.txt", "modified").unwrap();
 
         let backend = GixBackend::open(repo.path()).unwrap();
-        let diff = backend.diff(None).unwrap();
+        let diff = backend.diff(None, None, false).unwrap();
         assert!(!diff.is_empty());
         assert_eq!(diff[0].change_type, ChangeType::Modified);
     }
@@ -587,11 +1055,44 @@ mod tests {
         repo.write_file("new_file.txt", "new content").unwrap();
 
         let backend = GixBackend::open(repo.path()).unwrap();
-        let diff = backend.diff(None).unwrap();
+        let diff = backend.diff(None, None, false).unwrap();
         assert!(!diff.is_empty());
         assert_eq!(diff[0].change_type, ChangeType::Added);
     }
 
+    #[test]
+    fn test_diff_between_revisions() {
+        let repo = GitTestRepo::new().unwrap();
+        repo.write_file("a.txt", "one\ntwo\nthree\n").unwrap();
+        repo.write_file("old.txt", "moved\n").unwrap();
+        let first = repo.commit("initial commit").unwrap();
+        repo.write_file("a.txt", "one\n2\nthree\nfour\n").unwrap();
+        repo.delete_file("old.txt").unwrap();
+        repo.write_file("new.txt", "moved\n").unwrap();
+        let second = repo.commit("second").unwrap();
+
+        let backend = GixBackend::open(repo.path()).unwrap();
+        let diff = backend.diff(Some(&first), Some(&second), true).unwrap();
+        assert_eq!(diff.len(), 2);
+        assert_eq!(diff[0].path, "a.txt");
+        assert_eq!((diff[0].additions, diff[0].deletions), (2, 1));
+        assert!(diff[0].patch.as_ref().unwrap().contains("-two\n+2\n"));
+        assert_eq!(diff[1].change_type, ChangeType::Renamed);
+        assert_eq!(diff[1].old_path.as_deref(), Some("old.txt"));
+
+        // Against the working copy, from HEAD by default or from any base
+        repo.write_file("a.txt", "one\n").unwrap();
+        let diff = backend.diff(None, None, false).unwrap();
+        assert_eq!((diff[0].additions, diff[0].deletions), (0, 3));
+        let diff = backend.diff(Some(&first), None, false).unwrap();
+        assert_eq!((diff[0].additions, diff[0].deletions), (0, 2));
+
+        assert!(matches!(
+            backend.diff(Some("nope"), None, false),
+            Err(VcsError::TargetNotFound(_))
+        ));
+    }
+
     #[test]
     fn test_commit_workflow() {
         let repo = GitTestRepo::new().unwrap();
@@ -688,11 +1189,7 @@ mod tests {
 
         // Modify and stage the file
         repo.write_file("test.txt", "modified").unwrap();
-        std::process::Command::new("git")
-            .args(["add", "test.txt"])
-            .current_dir(repo.path())
-            .output()
-            .unwrap();
+        repo.add_all().unwrap();
 
         let backend = GixBackend::open(repo.path()).unwrap();
         let status = backend.status().unwrap();
@@ -712,11 +1209,7 @@ mod tests {
 
         // Modify and stage the file
         repo.write_file("test.txt", "modified").unwrap();
-        std::process::Command::new("git")
-            .args(["add", "test.txt"])
-            .current_dir(repo.path())
-            .output()
-            .unwrap();
+        repo.add_all().unwrap();
 
         let backend = GixBackend::open(repo.path()).unwrap();
 
@@ -726,4 +1219,290 @@ mod tests {
             "is_clean should return false with staged changes"
         );
     }
+
+    #[test]
+    fn test_path_exists_and_resolve_commit() {
+        let repo = GitTestRepo::new().unwrap();
+        repo.write_file("src/auth.rs", "fn login() {}").unwrap();
+        let first = repo.commit("initial commit").unwrap();
+        repo.commit("second commit").unwrap();
+
+        let backend = GixBackend::open(repo.path()).unwrap();
+        assert!(backend.path_exists("src/auth.rs").unwrap());
+        assert!(backend.path_exists("src").unwrap());
+        assert!(!backend.path_exists("src/missing.rs").unwrap());
+
+        // Tracked files removed from the working copy still count
+        std::fs::remove_file(repo.path().join("src/auth.rs")).unwrap();
+        assert!(backend.path_exists("src/auth.rs").unwrap());
+
+        assert_eq!(backend.resolve_commit(&first[..7]).unwrap(), first);
+        assert_eq!(backend.resolve_commit("HEAD~1").unwrap(), first);
+        assert!(matches!(
+            backend.resolve_commit("no-such-rev"),
+            Err(VcsError::TargetNotFound(_))
+        ));
+    }
+
+    /// Branch `name` off the default branch with one commit writing `file`.
+    fn task_branch(repo: &GitTestRepo, backend: &GixBackend, name: &str, file: &str) {
+        let base = backend.current_branch().unwrap().unwrap();
+        backend.create_bookmark(name, None).unwrap();
+        backend.checkout(name).unwrap();
+        repo.write_file(file, name).unwrap();
+        backend.commit(&format!("work on {name}")).unwrap();
+        backend.checkout(&base).unwrap();
+    }
+
+    #[test]
+    fn test_integrate_strategies_and_conflicts() {
+        let repo = GitTestRepo::new().unwrap();
+        repo.write_file("shared.txt", "base").unwrap();
+        repo.commit("initial commit").unwrap();
+        let backend = GixBackend::open(repo.path()).unwrap();
+        let main = backend.current_branch().unwrap().unwrap();
+
+        for (branch, strategy) in [
+            ("task/merge", IntegrationStrategy::Merge),
+            ("task/squash", IntegrationStrategy::Squash),
+            ("task/rebase", IntegrationStrategy::Rebase),
+        ] {
+            task_branch(&repo, &backend, branch, &format!("{branch}.txt"));
+            // Diverge so none of them can fast-forward
+            repo.write_file("main.txt", branch).unwrap();
+            backend.commit(&format!("main before {branch}")).unwrap();
+            backend.checkout(branch).unwrap();
+
+            let rev = backend
+                .integrate(branch, &main, strategy, "Integrate")
+                .unwrap()
+                .unwrap();
+            // Only the ref moved; the task branch stays checked out
+            assert_eq!(backend.current_branch().unwrap().as_deref(), Some(branch));
+            assert!(backend.is_clean().unwrap());
+            backend.checkout(&main).unwrap();
+            assert_eq!(rev, backend.current_commit_id().unwrap());
+            assert!(repo.file_exists(&format!("{branch}.txt")));
+        }
+        let log = backend.log(20).unwrap();
+        assert_eq!(log[0].description, "work on task/rebase");
+        assert_eq!(log[2].description, "Integrate");
+        assert!(!log.iter().any(|e| e.description == "work on task/squash"));
+
+        // Already contained: nothing to do
+        assert_eq!(
+            backend
+                .integrate("task/merge", &main, IntegrationStrategy::Merge, "Again")
+                .unwrap(),
+            None
+        );
+
+        task_branch(&repo, &backend, "task/ff", "ff.txt");
+        repo.write_file("other.txt", "diverged").unwrap();
+        backend.commit("diverge").unwrap();
+        backend.checkout("task/ff").unwrap();
+        assert!(matches!(
+            backend.integrate("task/ff", &main, IntegrationStrategy::FastForward, "FF"),
+            Err(VcsError::NotFastForward(_))
+        ));
+        assert_eq!(
+            backend.current_branch().unwrap().as_deref(),
+            Some("task/ff")
+        );
+        backend.checkout(&main).unwrap();
+
+        // A conflict changes nothing
+        task_branch(&repo, &backend, "task/conflict", "shared.txt");
+        repo.write_file("shared.txt", "changed on main").unwrap();
+        backend.commit("change shared").unwrap();
+        let head = backend.current_commit_id().unwrap();
+        backend.checkout("task/conflict").unwrap();
+        match backend.integrate("task/conflict", &main, IntegrationStrategy::Merge, "M") {
+            Err(VcsError::Conflict(files)) => assert_eq!(files, vec!["shared.txt"]),
+            other => panic!("expected conflict, got {other:?}"),
+        }
+        assert_eq!(
+            backend.current_branch().unwrap().as_deref(),
+            Some("task/conflict")
+        );
+        assert!(backend.is_clean().unwrap());
+        backend.checkout(&main).unwrap();
+        assert_eq!(backend.current_commit_id().unwrap(), head);
+    }
+
+    #[test]
+    fn test_integrate_follows_renames() {
+        let repo = GitTestRepo::new().unwrap();
+        repo.write_file("auth.rs", "fn login() {}\n\nfn logout() {}\n")
+            .unwrap();
+        repo.write_file("notes.txt", "draft\n").unwrap();
+        repo.commit("initial commit").unwrap();
+        let backend = GixBackend::open(repo.path()).unwrap();
+        let main = backend.current_branch().unwrap().unwrap();
+
+        backend.create_bookmark("task/edit", None).unwrap();
+        backend.checkout("task/edit").unwrap();
+        repo.write_file("auth.rs", "fn login() {}\n\nfn logout() { end() }\n")
+            .unwrap();
+        backend.commit("edit logout").unwrap();
+        backend.checkout(&main).unwrap();
+        std::fs::create_dir(repo.path().join("src")).unwrap();
+        std::fs::rename(repo.path().join("auth.rs"), repo.path().join("src/auth.rs")).unwrap();
+        backend.commit("move auth").unwrap();
+
+        // The edit lands in the moved file
+        backend
+            .integrate("task/edit", &main, IntegrationStrategy::Merge, "Merge")
+            .unwrap()
+            .unwrap();
+        assert!(!repo.file_exists("auth.rs"));
+        assert_eq!(
+            repo.read_file("src/auth.rs").unwrap(),
+            "fn login() {}\n\nfn logout() { end() }\n"
+        );
+
+        // Edited on one side, deleted on the other
+        backend.create_bookmark("task/notes", None).unwrap();
+        backend.checkout("task/notes").unwrap();
+        repo.write_file("notes.txt", "final\n").unwrap();
+        backend.commit("finish notes").unwrap();
+        backend.checkout(&main).unwrap();
+        repo.delete_file("notes.txt").unwrap();
+        backend.commit("drop notes").unwrap();
+        match backend.integrate("task/notes", &main, IntegrationStrategy::Merge, "M") {
+            Err(VcsError::Conflict(files)) => assert_eq!(files, vec!["notes.txt"]),
+            other => panic!("expected conflict, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_integrate_leaves_other_checkouts_alone() {
+        let repo = GitTestRepo::new().unwrap();
+        repo.commit("initial commit").unwrap();
+        let backend = GixBackend::open(repo.path()).unwrap();
+        let main = backend.current_branch().unwrap().unwrap();
+        task_branch(&repo, &backend, "task/done", "done.txt");
+        task_branch(&repo, &backend, "task/busy", "busy.txt");
+
+        // Mid-way through another task: its uncommitted work is no obstacle
+        backend.checkout("task/busy").unwrap();
+        repo.write_file("scratch.txt", "in progress").unwrap();
+        backend
+            .integrate("task/done", &main, IntegrationStrategy::Merge, "Merge")
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            backend.current_branch().unwrap().as_deref(),
+            Some("task/busy")
+        );
+        assert!(repo.file_exists("scratch.txt"));
+        assert!(!repo.file_exists("done.txt"));
+
+        // With `onto` checked out, uncommitted work blocks the update
+        std::fs::remove_file(repo.path().join("scratch.txt")).unwrap();
+        backend.checkout(&main).unwrap();
+        assert!(repo.file_exists("done.txt"));
+        let head = backend.current_commit_id().unwrap();
+        repo.write_file("scratch.txt", "in progress").unwrap();
+        assert!(matches!(
+            backend.integrate("task/busy", &main, IntegrationStrategy::Merge, "Merge"),
+            Err(VcsError::DirtyWorkingCopy)
+        ));
+        assert_eq!(backend.current_commit_id().unwrap(), head);
+    }
+
+    #[test]
+    fn test_add_worktree_is_a_linked_worktree() {
+        let repo = GitTestRepo::new().unwrap();
+        repo.write_file("src/lib.rs", "// lib").unwrap();
+        repo.commit("initial commit").unwrap();
+        let backend = GixBackend::open(repo.path()).unwrap();
+        backend.create_bookmark("task/tree", None).unwrap();
+
+        let trees = tempfile::TempDir::new().unwrap();
+        let path = trees.path().join("task_tree");
+        backend
+            .add_worktree("task/tree", path.to_str().unwrap())
+            .unwrap();
+        let path = path.canonicalize().unwrap();
+
+        // Discovered from inside as a linked worktree of the main repository
+        let main = gix::open(repo.path()).unwrap();
+        let linked = gix::discover(path.join("src")).unwrap();
+        assert!(matches!(
+            linked.kind(),
+            gix::repository::Kind::LinkedWorkTree
+        ));
+        assert_eq!(linked.workdir(), Some(path.as_path()));
+        assert_eq!(
+            linked.common_dir().canonicalize().unwrap(),
+            main.git_dir().canonicalize().unwrap()
+        );
+        assert_eq!(
+            linked.head_name().unwrap().unwrap().as_bstr(),
+            "refs/heads/task/tree"
+        );
+        assert!(backend
+            .open_worktree(path.to_str().unwrap())
+            .unwrap()
+            .is_clean()
+            .unwrap());
+
+        // Listed by the main repository, pointing back at the checkout
+        let mut worktrees = main.worktrees().unwrap();
+        assert_eq!(worktrees.len(), 1);
+        assert_eq!(worktrees[0].base().unwrap(), path);
+        assert!(!worktrees[0].is_locked());
+        let opened = worktrees.remove(0).into_repo().unwrap();
+        assert_eq!(opened.git_dir(), linked.git_dir());
+    }
+
+    #[test]
+    fn test_worktree_commit_integrate_and_remove() {
+        let repo = GitTestRepo::new().unwrap();
+        repo.commit("initial commit").unwrap();
+        let backend = GixBackend::open(repo.path()).unwrap();
+        let main = backend.current_branch().unwrap().unwrap();
+        backend.create_bookmark("task/tree", None).unwrap();
+
+        let trees = tempfile::TempDir::new().unwrap();
+        let path = trees.path().join("nested/task_tree");
+        let path = path.to_str().unwrap();
+        backend.add_worktree("task/tree", path).unwrap();
+        let worktree = backend.open_worktree(path).unwrap();
+        assert_eq!(
+            worktree.current_branch().unwrap().as_deref(),
+            Some("task/tree")
+        );
+
+        // Committed in the worktree; the main checkout stays where it was
+        std::fs::write(Path::new(path).join("work.txt"), "done").unwrap();
+        worktree.commit("work in tree").unwrap();
+        assert_eq!(backend.current_branch().unwrap().as_deref(), Some(&*main));
+        repo.write_file("main.txt", "moved on").unwrap();
+        backend.commit("main moved").unwrap();
+
+        // Rebasing works while the branch is checked out in the worktree
+        backend
+            .integrate("task/tree", &main, IntegrationStrategy::Rebase, "Rebase")
+            .unwrap()
+            .unwrap();
+        assert!(repo.file_exists("work.txt"));
+        assert_eq!(backend.log(1).unwrap()[0].description, "work in tree");
+
+        // Uncommitted work is never thrown away
+        std::fs::write(Path::new(path).join("notes.txt"), "wip").unwrap();
+        assert!(matches!(
+            backend.remove_worktree(path),
+            Err(VcsError::DirtyWorkingCopy)
+        ));
+        assert!(Path::new(path).join("notes.txt").exists());
+        std::fs::remove_file(Path::new(path).join("notes.txt")).unwrap();
+
+        backend.remove_worktree(path).unwrap();
+        assert!(!Path::new(path).exists());
+        backend.delete_bookmark("task/tree").unwrap();
+        // Already gone
+        backend.remove_worktree(path).unwrap();
+    }
 }
@@ -1,13 +1,15 @@
 pub mod backend;
 pub mod detection;
+pub mod diff;
 pub mod git;
 
 use std::path::Path;
 
 pub use backend::{
-    CommitResult, DiffEntry, LogEntry, VcsBackend, VcsError, VcsInfo, VcsResult, VcsStatus, VcsType,
+    CommitResult, DiffEntry, IntegrationStrategy, LogEntry, VcsBackend, VcsError, VcsInfo,
+    VcsResult, VcsStatus, VcsType,
 };
-pub use detection::detect_vcs_type;
+pub use detection::{detect_vcs_type, main_worktree_root};
 pub use git::GixBackend;
 
 pub fn get_backend(path: &Path) -> VcsResult<Box<dyn VcsBackend>> {
@@ -22,6 +24,14 @@ pub fn get_backend(path: &Path) -> VcsResult<Box<dyn VcsBackend>> {
     }
 }
 
+/// Like `get_backend`, but for the main working copy when `path` is inside a
+/// linked worktree.
+pub fn get_main_backend(path: &Path) -> VcsResult<Box<dyn VcsBackend>> {
+    let (_, root) = detect_vcs_type(path);
+    let root = root.ok_or(VcsError::NotARepository)?;
+    get_backend(&main_worktree_root(&root))
+}
+
 pub fn detect(path: &Path) -> VcsInfo {
     let (vcs_type, root) = detect_vcs_type(path);
     VcsInfo {
@@ -1,7 +1,8 @@
+use gix::objs::tree::EntryKind;
 use overseer::vcs::backend::{ChangeType, FileStatusKind};
 use overseer::vcs::{GixBackend, VcsBackend, VcsType};
+use std::io::Write;
 use std::path::{Path, PathBuf};
-use std::process::Command;
 use tempfile::TempDir;
 
 /// Test helper for creating git repositories
@@ -16,22 +17,12 @@ impl GitTestRepo {
         let tempdir = TempDir::new()?;
         let root = tempdir.path().to_path_buf();
 
-        // Initialize git repo
-        Command::new("git")
-            .args(["init"])
-            .current_dir(&root)
-            .output()?;
-
-        // Configure git user for commits
-        Command::new("git")
-            .args(["config", "user.name", "Test User"])
-            .current_dir(&root)
-            .output()?;
-
-        Command::new("git")
-            .args(["config", "user.email", "test@example.com"])
-            .current_dir(&root)
-            .output()?;
+        // Initialize git repo and configure the user for commits
+        gix::init(&root).map_err(std::io::Error::other)?;
+        let mut config = std::fs::OpenOptions::new()
+            .append(true)
+            .open(root.join(".git/config"))?;
+        config.write_all(b"[user]\n\tname = Test User\n\temail = test@example.com\n")?;
 
         Ok(Self { tempdir, root })
     }
@@ -52,27 +43,43 @@ impl GitTestRepo {
         std::fs::remove_file(self.root.join(relative_path))
     }
 
+    /// Commit every file under the root, written with plain gix objects so the
+    /// fixture does not depend on the backend under test
     fn commit(&self, message: &str) -> std::io::Result<String> {
-        // Stage all changes
-        Command::new("git")
-            .args(["add", "-A"])
-            .current_dir(&self.root)
-            .output()?;
-
-        // Commit
-        Command::new("git")
-            .args(["commit", "-m", message])
-            .current_dir(&self.root)
-            .output()?;
-
-        // Get commit hash
-        let output = Command::new("git")
-            .args(["rev-parse", "HEAD"])
-            .current_dir(&self.root)
-            .output()?;
-
-        let hash = String::from_utf8_lossy(&output.stdout).trim().to_string();
-        Ok(hash[..12.min(hash.len())].to_string())
+        let repo = gix::open(&self.root).map_err(std::io::Error::other)?;
+        let mut editor = repo
+            .edit_tree(gix::ObjectId::empty_tree(repo.object_hash()))
+            .map_err(std::io::Error::other)?;
+        let mut dirs = vec![self.root.clone()];
+        while let Some(dir) = dirs.pop() {
+            for entry in std::fs::read_dir(&dir)? {
+                let path = entry?.path();
+                if path.is_dir() {
+                    if path != self.root.join(".git") {
+                        dirs.push(path);
+                    }
+                    continue;
+                }
+                let id = repo
+                    .write_blob(std::fs::read(&path)?)
+                    .map_err(std::io::Error::other)?;
+                let relative = path.strip_prefix(&self.root).unwrap().to_str().unwrap();
+                editor
+                    .upsert(relative, EntryKind::Blob, id)
+                    .map_err(std::io::Error::other)?;
+            }
+        }
+        let tree = editor.write().map_err(std::io::Error::other)?.detach();
+        let mut index = repo.index_from_tree(&tree).map_err(std::io::Error::other)?;
+        index
+            .write(Default::default())
+            .map_err(std::io::Error::other)?;
+
+        let parent = repo.head_id().ok().map(|id| id.detach());
+        let id = repo
+            .commit("HEAD", message, tree, parent)
+            .map_err(std::io::Error::other)?;
+        Ok(id.to_string())
     }
 }
 
@@ -268,7 +275,7 @@ fn test_diff_empty_working_copy() {
     repo.commit("initial").unwrap();
 
     let backend = GixBackend::open(repo.path()).unwrap();
-    let diff = backend.diff(None).unwrap();
+    let diff = backend.diff(None, None, false).unwrap();
     assert!(diff.is_empty());
 }
 
@@ -281,7 +288,7 @@ fn test_diff_with_added_file() {
     repo.write_file("new.txt", "new content").unwrap();
 
     let backend = GixBackend::open(repo.path()).unwrap();
-    let diff = backend.diff(None).unwrap();
+    let diff = backend.diff(None, None, false).unwrap();
 
     assert!(!diff.is_empty());
     let new_entry = diff.iter().find(|e| e.path == "new.txt").unwrap();
@@ -297,7 +304,7 @@ fn test_diff_with_modified_file() {
     repo.write_file("test.txt", "modified").unwrap();
 
     let backend = GixBackend::open(repo.path()).unwrap();
-    let diff = backend.diff(None).unwrap();
+    let diff = backend.diff(None, None, false).unwrap();
 
     assert!(!diff.is_empty());
     assert_eq!(diff[0].change_type, ChangeType::Modified);
@@ -314,7 +321,7 @@ fn test_diff_with_multiple_changes() {
     repo.write_file("new.txt", "new").unwrap();
 
     let backend = GixBackend::open(repo.path()).unwrap();
-    let diff = backend.diff(None).unwrap();
+    let diff = backend.diff(None, None, false).unwrap();
 
     assert!(diff.len() >= 2);
     assert!(diff.iter().any(|e| e.path == "existing.txt"));
@@ -33,6 +33,10 @@ fn test_add_learning() {
             parent_id: None,
             priority: None,
             blocked_by: vec![],
+            labels: vec![],
+            due_at: None,
+            defer_until: None,
+            repo_id: None,
         })
         .unwrap();
 
@@ -57,6 +61,10 @@ fn test_add_learning_with_source() {
             parent_id: None,
             priority: None,
             blocked_by: vec![],
+            labels: vec![],
+            due_at: None,
+            defer_until: None,
+            repo_id: None,
         })
         .unwrap();
 
@@ -67,6 +75,10 @@ fn test_add_learning_with_source() {
             parent_id: None,
             priority: None,
             blocked_by: vec![],
+            labels: vec![],
+            due_at: None,
+            defer_until: None,
+            repo_id: None,
         })
         .unwrap();
 
@@ -93,6 +105,10 @@ fn test_list_learnings() {
             parent_id: None,
             priority: None,
             blocked_by: vec![],
+            labels: vec![],
+            due_at: None,
+            defer_until: None,
+            repo_id: None,
         })
         .unwrap();
 
@@ -119,6 +135,10 @@ fn test_list_learnings_empty() {
             parent_id: None,
             priority: None,
             blocked_by: vec![],
+            labels: vec![],
+            due_at: None,
+            defer_until: None,
+            repo_id: None,
         })
         .unwrap();
 
@@ -138,6 +158,10 @@ fn test_delete_learning() {
             parent_id: None,
             priority: None,
             blocked_by: vec![],
+            labels: vec![],
+            due_at: None,
+            defer_until: None,
+            repo_id: None,
         })
         .unwrap();
 
@@ -163,6 +187,10 @@ fn test_cascade_delete_learnings_with_task() {
             parent_id: None,
             priority: None,
             blocked_by: vec![],
+            labels: vec![],
+            due_at: None,
+            defer_until: None,
+            repo_id: None,
         })
         .unwrap();
 
@@ -192,6 +220,10 @@ fn test_cascade_delete_learnings_with_parent_task() {
             parent_id: None,
             priority: None,
             blocked_by: vec![],
+            labels: vec![],
+            due_at: None,
+            defer_until: None,
+            repo_id: None,
         })
         .unwrap();
 
@@ -202,6 +234,10 @@ fn test_cascade_delete_learnings_with_parent_task() {
             parent_id: Some(parent.id.clone()),
             priority: None,
             blocked_by: vec![],
+            labels: vec![],
+            due_at: None,
+            defer_until: None,
+            repo_id: None,
         })
         .unwrap();
 
@@ -239,6 +275,10 @@ fn test_learnings_isolated_by_task() {
             parent_id: None,
             priority: None,
             blocked_by: vec![],
+            labels: vec![],
+            due_at: None,
+            defer_until: None,
+            repo_id: None,
         })
         .unwrap();
 
@@ -249,6 +289,10 @@ fn test_learnings_isolated_by_task() {
             parent_id: None,
             priority: None,
             blocked_by: vec![],
+            labels: vec![],
+            due_at: None,
+            defer_until: None,
+            repo_id: None,
         })
         .unwrap();
 
@@ -38,6 +38,10 @@ fn test_create_milestone() {
             parent_id: None,
             priority: Some(1),
             blocked_by: vec![],
+            labels: vec![],
+            due_at: None,
+            defer_until: None,
+            repo_id: None,
         })
         .unwrap();
 
@@ -61,6 +65,10 @@ fn test_create_task_with_parent() {
             parent_id: None,
             priority: None,
             blocked_by: vec![],
+            labels: vec![],
+            due_at: None,
+            defer_until: None,
+            repo_id: None,
         })
         .unwrap();
 
@@ -71,6 +79,10 @@ fn test_create_task_with_parent() {
             parent_id: Some(milestone.id.clone()),
             priority: None,
             blocked_by: vec![],
+            labels: vec![],
+            due_at: None,
+            defer_until: None,
+            repo_id: None,
         })
         .unwrap();
 
@@ -91,6 +103,10 @@ fn test_create_with_nonexistent_parent() {
         parent_id: Some(nonexistent_id),
         priority: None,
         blocked_by: vec![],
+        labels: vec![],
+        due_at: None,
+        defer_until: None,
+        repo_id: None,
     });
 
     assert!(matches!(result, Err(OsError::ParentNotFound(_))));
@@ -109,6 +125,10 @@ fn test_create_with_nonexistent_blocker() {
         parent_id: None,
         priority: None,
         blocked_by: vec![nonexistent_id],
+        labels: vec![],
+        due_at: None,
+        defer_until: None,
+        repo_id: None,
     });
 
     assert!(matches!(result, Err(OsError::BlockerNotFound(_))));
@@ -126,6 +146,10 @@ fn test_get_task_with_full_context() {
             parent_id: None,
             priority: None,
             blocked_by: vec![],
+            labels: vec![],
+            due_at: None,
+            defer_until: None,
+            repo_id: None,
         })
         .unwrap();
 
@@ -158,6 +182,10 @@ fn test_list_all_tasks() {
             parent_id: None,
             priority: None,
             blocked_by: vec![],
+            labels: vec![],
+            due_at: None,
+            defer_until: None,
+            repo_id: None,
         })
         .unwrap();
 
@@ -168,6 +196,10 @@ fn test_list_all_tasks() {
             parent_id: None,
             priority: None,
             blocked_by: vec![],
+            labels: vec![],
+            due_at: None,
+            defer_until: None,
+            repo_id: None,
         })
         .unwrap();
 
@@ -196,6 +228,10 @@ fn test_update_description() {
             parent_id: None,
             priority: None,
             blocked_by: vec![],
+            labels: vec![],
+            due_at: None,
+            defer_until: None,
+            repo_id: None,
         })
         .unwrap();
 
@@ -206,7 +242,11 @@ fn test_update_description() {
                 description: Some("Updated".to_string()),
                 context: None,
                 parent_id: None,
+                labels: vec![],
                 priority: None,
+                due_at: None,
+                defer_until: None,
+                repo_id: None,
             },
         )
         .unwrap();
@@ -226,7 +266,11 @@ fn test_update_nonexistent_task() {
             description: Some("Updated".to_string()),
             context: None,
             parent_id: None,
+            labels: vec![],
             priority: None,
+            due_at: None,
+            defer_until: None,
+            repo_id: None,
         },
     );
 
@@ -245,6 +289,10 @@ fn test_delete_task() {
             parent_id: None,
             priority: None,
             blocked_by: vec![],
+            labels: vec![],
+            due_at: None,
+            defer_until: None,
+            repo_id: None,
         })
         .unwrap();
 
@@ -279,6 +327,10 @@ fn test_max_depth_enforcement() {
             parent_id: None,
             priority: None,
             blocked_by: vec![],
+            labels: vec![],
+            due_at: None,
+            defer_until: None,
+            repo_id: None,
         })
         .unwrap();
 
@@ -290,6 +342,10 @@ fn test_max_depth_enforcement() {
             parent_id: Some(milestone.id.clone()),
             priority: None,
             blocked_by: vec![],
+            labels: vec![],
+            due_at: None,
+            defer_until: None,
+            repo_id: None,
         })
         .unwrap();
 
@@ -301,6 +357,10 @@ fn test_max_depth_enforcement() {
             parent_id: Some(task.id.clone()),
             priority: None,
             blocked_by: vec![],
+            labels: vec![],
+            due_at: None,
+            defer_until: None,
+            repo_id: None,
         })
         .unwrap();
 
@@ -313,9 +373,13 @@ fn test_max_depth_enforcement() {
         parent_id: Some(subtask.id),
         priority: None,
         blocked_by: vec![],
+        labels: vec![],
+        due_at: None,
+        defer_until: None,
+        repo_id: None,
     });
 
-    assert!(matches!(result, Err(OsError::MaxDepthExceeded)));
+    assert!(matches!(result, Err(OsError::MaxDepthExceeded { .. })));
 }
 
 #[test]
@@ -331,6 +395,10 @@ fn test_update_parent_violates_max_depth() {
             parent_id: None,
             priority: None,
             blocked_by: vec![],
+            labels: vec![],
+            due_at: None,
+            defer_until: None,
+            repo_id: None,
         })
         .unwrap();
 
@@ -341,6 +409,10 @@ fn test_update_parent_violates_max_depth() {
             parent_id: Some(milestone.id.clone()),
             priority: None,
             blocked_by: vec![],
+            labels: vec![],
+            due_at: None,
+            defer_until: None,
+            repo_id: None,
         })
         .unwrap();
 
@@ -351,6 +423,10 @@ fn test_update_parent_violates_max_depth() {
             parent_id: Some(task.id.clone()),
             priority: None,
             blocked_by: vec![],
+            labels: vec![],
+            due_at: None,
+            defer_until: None,
+            repo_id: None,
         })
         .unwrap();
 
@@ -362,6 +438,10 @@ fn test_update_parent_violates_max_depth() {
             parent_id: None,
             priority: None,
             blocked_by: vec![],
+            labels: vec![],
+            due_at: None,
+            defer_until: None,
+            repo_id: None,
         })
         .unwrap();
 
@@ -372,11 +452,15 @@ fn test_update_parent_violates_max_depth() {
             description: None,
             context: None,
             parent_id: Some(subtask.id),
+            labels: vec![],
             priority: None,
+            due_at: None,
+            defer_until: None,
+            repo_id: None,
         },
     );
 
-    assert!(matches!(result, Err(OsError::MaxDepthExceeded)));
+    assert!(matches!(result, Err(OsError::MaxDepthExceeded { .. })));
 }
 
 // ==================== Cycle Detection ====================
@@ -393,6 +477,10 @@ fn test_parent_cycle_direct() {
             parent_id: None,
             priority: None,
             blocked_by: vec![],
+            labels: vec![],
+            due_at: None,
+            defer_until: None,
+            repo_id: None,
         })
         .unwrap();
 
@@ -403,7 +491,11 @@ fn test_parent_cycle_direct() {
             description: None,
             context: None,
             parent_id: Some(task.id.clone()),
+            labels: vec![],
             priority: None,
+            due_at: None,
+            defer_until: None,
+            repo_id: None,
         },
     );
 
@@ -423,6 +515,10 @@ fn test_parent_cycle_indirect() {
             parent_id: None,
             priority: None,
             blocked_by: vec![],
+            labels: vec![],
+            due_at: None,
+            defer_until: None,
+            repo_id: None,
         })
         .unwrap();
 
@@ -433,6 +529,10 @@ fn test_parent_cycle_indirect() {
             parent_id: Some(task_a.id.clone()),
             priority: None,
             blocked_by: vec![],
+            labels: vec![],
+            due_at: None,
+            defer_until: None,
+            repo_id: None,
         })
         .unwrap();
 
@@ -443,6 +543,10 @@ fn test_parent_cycle_indirect() {
             parent_id: Some(task_b.id.clone()),
             priority: None,
             blocked_by: vec![],
+            labels: vec![],
+            due_at: None,
+            defer_until: None,
+            repo_id: None,
         })
         .unwrap();
 
@@ -453,7 +557,11 @@ fn test_parent_cycle_indirect() {
             description: None,
             context: None,
             parent_id: Some(task_c.id),
+            labels: vec![],
             priority: None,
+            due_at: None,
+            defer_until: None,
+            repo_id: None,
         },
     );
 
@@ -472,6 +580,10 @@ fn test_blocker_cycle_direct() {
             parent_id: None,
             priority: None,
             blocked_by: vec![],
+            labels: vec![],
+            due_at: None,
+            defer_until: None,
+            repo_id: None,
         })
         .unwrap();
 
@@ -496,6 +608,10 @@ fn test_blocker_cycle_indirect() {
             parent_id: None,
             priority: None,
             blocked_by: vec![],
+            labels: vec![],
+            due_at: None,
+            defer_until: None,
+            repo_id: None,
         })
         .unwrap();
 
@@ -506,6 +622,10 @@ fn test_blocker_cycle_indirect() {
             parent_id: None,
             priority: None,
             blocked_by: vec![task_a.id.clone()],
+            labels: vec![],
+            due_at: None,
+            defer_until: None,
+            repo_id: None,
         })
         .unwrap();
 
@@ -516,6 +636,10 @@ fn test_blocker_cycle_indirect() {
             parent_id: None,
             priority: None,
             blocked_by: vec![task_b.id.clone()],
+            labels: vec![],
+            due_at: None,
+            defer_until: None,
+            repo_id: None,
         })
         .unwrap();
 
@@ -537,6 +661,10 @@ fn test_blocker_cycle_complex() {
             parent_id: None,
             priority: None,
             blocked_by: vec![],
+            labels: vec![],
+            due_at: None,
+            defer_until: None,
+            repo_id: None,
         })
         .unwrap();
 
@@ -547,6 +675,10 @@ fn test_blocker_cycle_complex() {
             parent_id: None,
             priority: None,
             blocked_by: vec![task_a.id.clone()],
+            labels: vec![],
+            due_at: None,
+            defer_until: None,
+            repo_id: None,
         })
         .unwrap();
 
@@ -557,6 +689,10 @@ fn test_blocker_cycle_complex() {
             parent_id: None,
             priority: None,
             blocked_by: vec![task_a.id.clone()],
+            labels: vec![],
+            due_at: None,
+            defer_until: None,
+            repo_id: None,
         })
         .unwrap();
 
@@ -567,6 +703,10 @@ fn test_blocker_cycle_complex() {
             parent_id: None,
             priority: None,
             blocked_by: vec![task_b.id.clone(), task_c.id.clone()],
+            labels: vec![],
+            due_at: None,
+            defer_until: None,
+            repo_id: None,
         })
         .unwrap();
 
@@ -589,6 +729,10 @@ fn test_add_blocker() {
             parent_id: None,
             priority: None,
             blocked_by: vec![],
+            labels: vec![],
+            due_at: None,
+            defer_until: None,
+            repo_id: None,
         })
         .unwrap();
 
@@ -599,6 +743,10 @@ fn test_add_blocker() {
             parent_id: None,
             priority: None,
             blocked_by: vec![],
+            labels: vec![],
+            due_at: None,
+            defer_until: None,
+            repo_id: None,
         })
         .unwrap();
 
@@ -618,6 +766,10 @@ fn test_remove_blocker() {
             parent_id: None,
             priority: None,
             blocked_by: vec![],
+            labels: vec![],
+            due_at: None,
+            defer_until: None,
+            repo_id: None,
         })
         .unwrap();
 
@@ -628,6 +780,10 @@ fn test_remove_blocker() {
             parent_id: None,
             priority: None,
             blocked_by: vec![blocker.id.clone()],
+            labels: vec![],
+            due_at: None,
+            defer_until: None,
+            repo_id: None,
         })
         .unwrap();
 
@@ -651,6 +807,10 @@ fn test_start_task() {
             parent_id: None,
             priority: None,
             blocked_by: vec![],
+            labels: vec![],
+            due_at: None,
+            defer_until: None,
+            repo_id: None,
         })
         .unwrap();
 
@@ -672,6 +832,10 @@ fn test_complete_task() {
             parent_id: None,
             priority: None,
             blocked_by: vec![],
+            labels: vec![],
+            due_at: None,
+            defer_until: None,
+            repo_id: None,
         })
         .unwrap();
 
@@ -696,6 +860,10 @@ fn test_complete_task_with_pending_children() {
             parent_id: None,
             priority: None,
             blocked_by: vec![],
+            labels: vec![],
+            due_at: None,
+            defer_until: None,
+            repo_id: None,
         })
         .unwrap();
 
@@ -706,6 +874,10 @@ fn test_complete_task_with_pending_children() {
             parent_id: Some(parent.id.clone()),
             priority: None,
             blocked_by: vec![],
+            labels: vec![],
+            due_at: None,
+            defer_until: None,
+            repo_id: None,
         })
         .unwrap();
 
@@ -725,6 +897,10 @@ fn test_reopen_task() {
             parent_id: None,
             priority: None,
             blocked_by: vec![],
+            labels: vec![],
+            due_at: None,
+            defer_until: None,
+            repo_id: None,
         })
         .unwrap();
 
@@ -750,6 +926,10 @@ fn test_cascade_delete_children() {
             parent_id: None,
             priority: None,
             blocked_by: vec![],
+            labels: vec![],
+            due_at: None,
+            defer_until: None,
+            repo_id: None,
         })
         .unwrap();
 
@@ -760,6 +940,10 @@ fn test_cascade_delete_children() {
             parent_id: Some(parent.id.clone()),
             priority: None,
             blocked_by: vec![],
+            labels: vec![],
+            due_at: None,
+            defer_until: None,
+            repo_id: None,
         })
         .unwrap();
 
@@ -782,6 +966,10 @@ fn test_cascade_delete_blockers() {
             parent_id: None,
             priority: None,
             blocked_by: vec![],
+            labels: vec![],
+            due_at: None,
+            defer_until: None,
+            repo_id: None,
         })
         .unwrap();
 
@@ -792,6 +980,10 @@ fn test_cascade_delete_blockers() {
             parent_id: None,
             priority: None,
             blocked_by: vec![blocker.id.clone()],
+            labels: vec![],
+            due_at: None,
+            defer_until: None,
+            repo_id: None,
         })
         .unwrap();
 
@@ -815,6 +1007,10 @@ fn test_cascade_delete_deep_hierarchy() {
             parent_id: None,
             priority: None,
             blocked_by: vec![],
+            labels: vec![],
+            due_at: None,
+            defer_until: None,
+            repo_id: None,
         })
         .unwrap();
 
@@ -825,6 +1021,10 @@ fn test_cascade_delete_deep_hierarchy() {
             parent_id: Some(milestone.id.clone()),
             priority: None,
             blocked_by: vec![],
+            labels: vec![],
+            due_at: None,
+            defer_until: None,
+            repo_id: None,
         })
         .unwrap();
 
@@ -835,6 +1035,10 @@ fn test_cascade_delete_deep_hierarchy() {
             parent_id: Some(task.id.clone()),
             priority: None,
             blocked_by: vec![],
+            labels: vec![],
+            due_at: None,
+            defer_until: None,
+            repo_id: None,
         })
         .unwrap();
 
@@ -17,6 +17,7 @@ export async function callCli(args: string[]): Promise<unknown> {
   return new Promise((resolve, reject) => {
     const proc = spawn(CLI_PATH, [...args, "--json"], {
       cwd: CLI_CWD,
+      env: { ...process.env, OVERSEER_EVENT_SOURCE: "ui" },
       stdio: ["ignore", "pipe", "pipe"],
     });
 
@@ -62,6 +62,7 @@ export function getStatusLabel(task: Task): string {
 
 /**
  * Human-readable depth label (MILESTONE, TASK, SUBTASK).
+ * Anything deeper than depth 2 (databases with a larger max depth) is a SUBTASK.
  */
 export function getDepthLabel(depth: Depth): string {
   switch (depth) {
@@ -69,7 +70,7 @@ export function getDepthLabel(depth: Depth): string {
       return "MILESTONE";
     case 1:
       return "TASK";
-    case 2:
+    default:
       return "SUBTASK";
   }
 }
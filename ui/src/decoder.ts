@@ -49,7 +49,7 @@ function isNumber(v: unknown): v is number {
 
 // Helper to check valid priority (0=p0 highest, 1=p1 default, 2=p2 lowest)
 function isPriority(v: unknown): v is Priority {
-  return v === 0 || v === 1 || v === 2;
+  return typeof v === "number" && Number.isInteger(v) && v >= 0;
 }
 
 // Helper to check valid depth
@@ -139,11 +139,21 @@ export function decodeTask(v: unknown): Result<Task, DecodeError> {
     blocks,
     bookmark,
     startCommit,
+    startBranch,
+    integrationCommit,
+    baseRev,
+    headRev,
+    worktree,
     effectivelyBlocked,
     cancelled,
     cancelledAt,
     archived,
     archivedAt,
+    revision,
+    rank,
+    dueAt,
+    deferUntil,
+    metadata,
   } = v;
 
   // Required fields
@@ -198,6 +208,18 @@ export function decodeTask(v: unknown): Result<Task, DecodeError> {
   if (archivedAt !== null && !isString(archivedAt)) {
     return Result.err(new DecodeError({ message: "Task archivedAt must be string or null" }));
   }
+  if (!isNumber(revision) || !Number.isInteger(revision) || revision < 0) {
+    return Result.err(new DecodeError({ message: `Invalid task revision: ${revision}` }));
+  }
+  if (!isNumber(rank) || !Number.isInteger(rank)) {
+    return Result.err(new DecodeError({ message: `Invalid task rank: ${rank}` }));
+  }
+  if (dueAt !== null && !isString(dueAt)) {
+    return Result.err(new DecodeError({ message: "Task dueAt must be string or null" }));
+  }
+  if (deferUntil !== null && !isString(deferUntil)) {
+    return Result.err(new DecodeError({ message: "Task deferUntil must be string or null" }));
+  }
 
   // Optional array fields
   let decodedBlockedBy: TaskId[] | undefined;
@@ -228,6 +250,10 @@ export function decodeTask(v: unknown): Result<Task, DecodeError> {
     }
   }
 
+  if (metadata !== undefined && !isObject(metadata)) {
+    return Result.err(new DecodeError({ message: "Task metadata must be object" }));
+  }
+
   // Optional string fields
   if (bookmark !== undefined && !isString(bookmark)) {
     return Result.err(new DecodeError({ message: "Task bookmark must be string" }));
@@ -235,6 +261,21 @@ export function decodeTask(v: unknown): Result<Task, DecodeError> {
   if (startCommit !== undefined && !isString(startCommit)) {
     return Result.err(new DecodeError({ message: "Task startCommit must be string" }));
   }
+  if (startBranch !== undefined && !isString(startBranch)) {
+    return Result.err(new DecodeError({ message: "Task startBranch must be string" }));
+  }
+  if (integrationCommit !== undefined && !isString(integrationCommit)) {
+    return Result.err(new DecodeError({ message: "Task integrationCommit must be string" }));
+  }
+  if (baseRev !== undefined && !isString(baseRev)) {
+    return Result.err(new DecodeError({ message: "Task baseRev must be string" }));
+  }
+  if (headRev !== undefined && !isString(headRev)) {
+    return Result.err(new DecodeError({ message: "Task headRev must be string" }));
+  }
+  if (worktree !== undefined && !isString(worktree)) {
+    return Result.err(new DecodeError({ message: "Task worktree must be string" }));
+  }
 
   const task: Task = {
     id: id as TaskId,
@@ -254,12 +295,22 @@ export function decodeTask(v: unknown): Result<Task, DecodeError> {
     cancelledAt: cancelledAt as string | null,
     archived,
     archivedAt: archivedAt as string | null,
+    revision,
+    rank,
+    dueAt: dueAt as string | null,
+    deferUntil: deferUntil as string | null,
   };
 
   if (decodedBlockedBy) task.blockedBy = decodedBlockedBy;
   if (decodedBlocks) task.blocks = decodedBlocks;
+  if (metadata !== undefined) task.metadata = metadata as Record<string, unknown>;
   if (bookmark !== undefined) task.bookmark = bookmark as string;
   if (startCommit !== undefined) task.startCommit = startCommit as string;
+  if (startBranch !== undefined) task.startBranch = startBranch as string;
+  if (integrationCommit !== undefined) task.integrationCommit = integrationCommit as string;
+  if (baseRev !== undefined) task.baseRev = baseRev as string;
+  if (headRev !== undefined) task.headRev = headRev as string;
+  if (worktree !== undefined) task.worktree = worktree as string;
 
   return Result.ok(task);
 }
@@ -60,9 +60,10 @@ export interface InheritedLearnings {
 export type Priority = 0 | 1 | 2;
 
 /**
- * Task depth (0=milestone, 1=task, 2=subtask)
+ * Task depth: 0 for roots (milestones), +1 per level.
+ * The deepest allowed depth is set per database (`os db config --max-depth`).
  */
-export type Depth = 0 | 1 | 2;
+export type Depth = number;
 
 /**
  * Task returned from list/create/update/start/complete/reopen
@@ -85,6 +86,16 @@ export interface Task {
   blocks?: TaskId[];
   bookmark?: string;
   startCommit?: string;
+  /** Branch checked out when the task started; complete integrates into it */
+  startBranch?: string;
+  /** Start branch tip after complete integrated the task branch */
+  integrationCommit?: string;
+  /** Parent branch tip the task branch was created from (stacked) */
+  baseRev?: string;
+  /** Task branch tip at completion; baseRev..headRev is this level's diff */
+  headRev?: string;
+  /** Linked worktree the task branch is checked out in (start --worktree) */
+  worktree?: string;
   /** Computed: true if task or any ancestor has incomplete blockers */
   effectivelyBlocked: boolean;
   /** Task was cancelled (abandoned without completion) */
@@ -95,6 +106,30 @@ export interface Task {
   archived: boolean;
   /** Timestamp when task was archived */
   archivedAt: string | null;
+  /** Bumped on every write to the task; pass back as expectedRevision */
+  revision: number;
+  /** Manual sibling order within a priority, lower first (tasks.move) */
+  rank: number;
+  /** Due date; open tasks past it are overdue */
+  dueAt: string | null;
+  /** Hidden from next-ready (treated as blocked) until this time */
+  deferUntil: string | null;
+  /** Arbitrary JSON object attached via os task meta (omitted if none) */
+  metadata?: Record<string, unknown>;
+  /** Lowercase labels, sorted (omitted if none) */
+  labels?: string[];
+  /** Live claim on this task itself (omitted if none) */
+  claim?: TaskClaim;
+}
+
+/**
+ * Lease on a task and its subtree, held by one agent until expiresAt
+ */
+export interface TaskClaim {
+  taskId: TaskId;
+  agent: string;
+  claimedAt: string;
+  expiresAt: string;
 }
 
 /**
@@ -103,6 +138,35 @@ export interface Task {
 export interface TaskWithContext extends Task {
   context: TaskContext;
   learnings: InheritedLearnings;
+  /** Files, code ranges, URLs, commits and issues the task points at (omitted when none) */
+  links?: TaskLink[];
+  /** Recent comments, oldest first (only with --comments) */
+  comments?: Comment[];
+}
+
+export type LinkKind = "file" | "fileRange" | "url" | "commit" | "issue";
+
+/** Typed link from a task to a file, code range, URL, commit or issue */
+export interface TaskLink {
+  id: string;
+  taskId: TaskId;
+  kind: LinkKind;
+  /** Path relative to the repository root, URL, full commit id or issue reference */
+  target: string;
+  /** Inclusive line range, only for fileRange links */
+  lineStart: number | null;
+  lineEnd: number | null;
+  note: string | null;
+  createdAt: string;
+}
+
+/** Comment on a task's discussion thread */
+export interface Comment {
+  id: string;
+  taskId: TaskId;
+  author: string | null;
+  body: string;
+  createdAt: string;
 }
 
 export interface Learning {